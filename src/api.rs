@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "gui")]
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use crate::config::ApiSettings;
+#[cfg(feature = "gui")]
+use crate::config::ApiToken;
+
+// Bumped on every apply(); a listener thread exits once its generation is stale
+static API_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// A client that recently talked to the API
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    pub address: std::net::IpAddr,
+    pub token_name: String,
+    pub last_seen: u64, // Unix timestamp (seconds)
+    pub requests: u64,
+}
+
+// Recently seen API clients, keyed by "address/token name", for Tools → API Access...
+#[cfg(feature = "gui")]
+pub static API_CLIENTS: LazyLock<Mutex<HashMap<String, ApiClient>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Parsed HTTP request (only what the API needs)
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    headers: HashMap<String, String>,
+}
+
+/// Current Unix timestamp in seconds
+#[cfg(feature = "gui")]
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Generate a new random access token (32 hex chars)
+#[cfg(feature = "gui")]
+pub fn generate_token() -> String {
+    crate::secret::random_hex(16)
+}
+
+/// Create a new named token entry
+#[cfg(feature = "gui")]
+pub fn new_token(name: &str) -> ApiToken {
+    ApiToken {
+        name: name.to_string(),
+        token: generate_token(),
+        created: unix_now(),
+    }
+}
+
+/// Mask a token for display ("abcd…wxyz")
+#[cfg(any(feature = "gui", test))]
+pub fn mask_token(token: &str) -> String {
+    let chars: Vec<char> = token.chars().collect();
+    if chars.len() <= 8 {
+        return "…".to_string();
+    }
+    let head: String = chars.iter().take(4).collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+/// Get clients seen within the last 10 minutes, most recent first
+#[cfg(feature = "gui")]
+pub fn get_connected_clients() -> Vec<ApiClient> {
+    let now = unix_now();
+    let mut clients: Vec<ApiClient> = if let Ok(clients) = API_CLIENTS.lock() {
+        clients.values()
+            .filter(|c| now.saturating_sub(c.last_seen) < 600)
+            .cloned()
+            .collect()
+    } else {
+        Vec::new()
+    };
+    clients.sort_by_key(|c| std::cmp::Reverse(c.last_seen));
+    clients
+}
+
+/// Start, restart or stop the API listener to match the given settings
+pub fn apply(settings: &ApiSettings) {
+    let generation = API_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    if !settings.enabled {
+        println!("Control API disabled");
+        return;
+    }
+
+    let bind_ip = if settings.loopback_only { "127.0.0.1" } else { "0.0.0.0" };
+    // The previous listener lets go of the port within one accept poll
+    let mut bound = TcpListener::bind((bind_ip, settings.port));
+    for _ in 0..5 {
+        if bound.is_ok() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        bound = TcpListener::bind((bind_ip, settings.port));
+    }
+    let listener = match bound {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to bind control API on {}:{}: {}", bind_ip, settings.port, e);
+            return;
+        }
+    };
+    // Non-blocking accept so the thread can notice when it's been superseded
+    if let Err(e) = listener.set_nonblocking(true) {
+        eprintln!("Failed to configure control API listener: {}", e);
+        return;
+    }
+
+    println!("Control API listening on {}:{}", bind_ip, settings.port);
+
+    let settings = settings.clone();
+    std::thread::spawn(move || {
+        while API_GENERATION.load(Ordering::SeqCst) == generation {
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    let settings = settings.clone();
+                    std::thread::spawn(move || {
+                        handle_connection(stream, peer, &settings, generation);
+                    });
+                }
+                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                Err(e) => {
+                    eprintln!("Control API accept failed: {}", e);
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+            }
+        }
+        println!("Control API listener stopped");
+    });
+}
+
+fn handle_connection(mut stream: TcpStream, peer: SocketAddr, settings: &ApiSettings, generation: u64) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+
+    let request = match parse_request(&mut stream) {
+        Ok(request) => request,
+        Err(_) => {
+            write_response(&mut stream, 400, "{\"error\":\"bad request\"}", None);
+            return;
+        }
+    };
+
+    if settings.loopback_only && !peer.ip().is_loopback() {
+        write_response(&mut stream, 403, "{\"error\":\"remote access disabled\"}", None);
+        return;
+    }
+
+    // Browser requests carry an Origin header; only allow configured ones
+    let origin = request.headers.get("origin").cloned();
+    if let Some(origin) = &origin {
+        let allowed = settings.allowed_origins.iter().any(|o| o == "*" || o == origin);
+        if !allowed {
+            write_response(&mut stream, 403, "{\"error\":\"origin not allowed\"}", None);
+            return;
+        }
+    }
+
+    // CORS preflight doesn't carry credentials
+    if request.method == "OPTIONS" {
+        write_response(&mut stream, 204, "", origin.as_deref());
+        return;
+    }
+
+    let token_name = match authenticate(&request, settings) {
+        Some(name) => name,
+        None => {
+            write_response(&mut stream, 401, "{\"error\":\"invalid or missing token\"}", origin.as_deref());
+            return;
+        }
+    };
+    #[cfg(feature = "gui")]
+    record_client(peer.ip(), &token_name);
+    #[cfg(not(feature = "gui"))]
+    let _ = token_name;
+
+    // Long-lived event stream; everything else is request/response
+    if request.method == "GET" && request.path == "/api/events" {
+        stream_events(stream, origin.as_deref(), generation);
+        return;
+    }
+
+    let (status, body) = route(&request);
+    write_response(&mut stream, status, &body, origin.as_deref());
+}
+
+// Returns the token name if the request carries a valid token
+fn authenticate(request: &Request, settings: &ApiSettings) -> Option<String> {
+    let presented = request.headers.get("authorization")
+        .and_then(|h| h.strip_prefix("Bearer ").map(|t| t.trim().to_string()))
+        .or_else(|| request.query.get("token").cloned())?;
+
+    settings.tokens.iter()
+        .find(|t| !t.token.is_empty() && crate::secret::matches(&t.token, &presented))
+        .map(|t| t.name.clone())
+}
+
+#[cfg(feature = "gui")]
+fn record_client(address: std::net::IpAddr, token_name: &str) {
+    if let Ok(mut clients) = API_CLIENTS.lock() {
+        let key = format!("{}/{}", address, token_name);
+        let client = clients.entry(key).or_insert(ApiClient {
+            address,
+            token_name: token_name.to_string(),
+            last_seen: 0,
+            requests: 0,
+        });
+        client.last_seen = unix_now();
+        client.requests += 1;
+    }
+}
+
+// Dispatch an authenticated request, returning (status, JSON body)
+fn route(request: &Request) -> (u16, String) {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/api/status") | ("GET", "/api/servers") => (200, servers_json(&request.query).to_string()),
+        ("POST", "/api/servers/start") | ("POST", "/api/servers/stop") => {
+            let Some(key) = request.query.get("key") else {
+                return (400, "{\"error\":\"missing key parameter\"}".to_string());
+            };
+            let result = if request.path.ends_with("start") {
+                crate::start_single_server(key)
+            } else {
+                crate::stop_single_server(key)
+            };
+            match result {
+                Ok(_) => (200, serde_json::json!({ "ok": true, "key": key }).to_string()),
+                Err(e) => (500, serde_json::json!({ "ok": false, "error": e }).to_string()),
+            }
+        }
+        ("POST", "/api/servers/test") => {
+            // Without a key every running server is tested
+            let Some(key) = request.query.get("key").filter(|key| !key.is_empty()) else {
+                let results: Vec<_> = crate::probe::test_running_servers().into_iter()
+                    .map(|(key, result)| match result {
+                        Ok(latency) => serde_json::json!({ "key": key, "ok": true, "latency_ms": latency.as_millis() as u64 }),
+                        Err(e) => serde_json::json!({ "key": key, "ok": false, "error": e }),
+                    })
+                    .collect();
+                return (200, serde_json::json!({ "ok": true, "results": results }).to_string());
+            };
+            if crate::xray_manager::get_server_info(key).is_none() {
+                return (400, serde_json::json!({ "ok": false, "error": "server is not running" }).to_string());
+            }
+            match crate::health::check_server(key) {
+                Ok(latency) => (200, serde_json::json!({ "ok": true, "key": key, "latency_ms": latency.as_millis() as u64 }).to_string()),
+                Err(e) => (200, serde_json::json!({ "ok": false, "key": key, "error": e }).to_string()),
+            }
+        }
+        ("POST", "/api/restart") => {
+            let summary = crate::restart_xray_servers();
+            let failed: Vec<_> = summary.failed.iter()
+                .map(|(name, error)| serde_json::json!({ "name": name, "error": error }))
+                .collect();
+            (200, serde_json::json!({ "ok": failed.is_empty(), "started": summary.started, "kept": summary.kept, "failed": failed }).to_string())
+        }
+        _ => (404, "{\"error\":\"not found\"}".to_string()),
+    }
+}
+
+// Server-sent events: one "event:"/"data:" block per internal event until the client
+// disconnects or the listener is reconfigured. Comments keep idle proxies from timing out.
+fn stream_events(mut stream: TcpStream, origin: Option<&str>, generation: u64) {
+    use std::sync::mpsc::RecvTimeoutError;
+
+    let receiver = crate::events::subscribe();
+
+    let mut headers = String::from(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n"
+    );
+    if let Some(origin) = origin {
+        headers.push_str(&format!("Access-Control-Allow-Origin: {}\r\n", origin));
+    }
+    headers.push_str("\r\n: connected\n\n");
+    if stream.write_all(headers.as_bytes()).is_err() {
+        return;
+    }
+
+    let mut idle = Duration::ZERO;
+    while API_GENERATION.load(Ordering::SeqCst) == generation {
+        let chunk = match receiver.recv_timeout(Duration::from_secs(1)) {
+            Ok(event) => {
+                idle = Duration::ZERO;
+                let data = serde_json::to_string(&event).unwrap_or_default();
+                format!("event: {}\ndata: {}\n\n", event.name(), data)
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                idle += Duration::from_secs(1);
+                if idle < Duration::from_secs(15) {
+                    continue;
+                }
+                idle = Duration::ZERO;
+                ": keepalive\n\n".to_string()
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if stream.write_all(chunk.as_bytes()).is_err() || stream.flush().is_err() {
+            break;
+        }
+    }
+}
+
+// Current server list with running state, optionally filtered by
+// ?tag=IPLC and ?max_rate=1 (servers without a rate count as 1)
+fn servers_json(query: &HashMap<String, String>) -> serde_json::Value {
+    let running = crate::xray_manager::get_running_servers();
+    let tag = query.get("tag");
+    let max_rate = query.get("max_rate").and_then(|r| r.parse::<f32>().ok());
+    let mut servers = Vec::new();
+
+    if let Ok(global_servers) = crate::vpn::VPN_SERVERS.lock() {
+        if let Some(list) = global_servers.as_ref() {
+            for server in list {
+                if tag.is_some_and(|tag| !server.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))) {
+                    continue;
+                }
+                if max_rate.is_some_and(|max| server.rate.unwrap_or(1.0) > max) {
+                    continue;
+                }
+                let key = server.get_server_key();
+                let info = crate::xray_manager::get_server_info(&key).filter(|_| server.auto_port);
+                let health = crate::health::get_summary(&key).map(|h| serde_json::json!({
+                    "latency_ms": h.avg_latency_ms,
+                    "error_rate": h.error_rate,
+                    "samples": h.samples,
+                }));
+                servers.push(serde_json::json!({
+                    "key": key,
+                    "name": server.name,
+                    "alias": server.alias,
+                    "note": server.note,
+                    "protocol": server.protocol,
+                    "address": server.address,
+                    "port": server.port,
+                    // Auto-port servers report the ports they got while running, 0 otherwise
+                    "local_port": info.as_ref().map_or(server.local_port, |info| info.local_port),
+                    "proxy_type": server.proxy_type,
+                    "http_port": info.as_ref().map_or(server.http_port, |info| info.http_port),
+                    "auto_port": server.auto_port,
+                    "listen": server.listen,
+                    "udp": server.udp,
+                    "transport": server.transport,
+                    "country": server.country,
+                    "rate": server.rate,
+                    "tags": server.tags,
+                    "enabled": server.enabled,
+                    "running": running.contains(&key),
+                    "health": health,
+                    "exit_ip": crate::exitip::observed(&key).map(|observed| observed.ip),
+                }));
+            }
+        }
+    }
+
+    serde_json::json!({ "running": running, "servers": servers })
+}
+
+fn parse_request(stream: &mut TcpStream) -> Result<Request, String> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).map_err(|e| e.to_string())?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().ok_or("Missing method")?.to_string();
+    let target = parts.next().ok_or("Missing path")?.to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        let read = reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        let line = line.trim_end();
+        if read == 0 || line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    // Drain the body so the client doesn't see a reset; the API takes no body
+    if let Some(len) = headers.get("content-length").and_then(|l| l.parse::<usize>().ok()) {
+        let mut body = vec![0u8; len.min(64 * 1024)];
+        let _ = reader.read_exact(&mut body);
+    }
+
+    let (path, query_string) = target.split_once('?').unwrap_or((target.as_str(), ""));
+    let query = parse_query(query_string);
+
+    Ok(Request {
+        method,
+        path: path.to_string(),
+        query,
+        headers,
+    })
+}
+
+/// Parse a URL query string into a map (percent-decoded, '+' as space)
+pub fn parse_query(query: &str) -> HashMap<String, String> {
+    query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (form_decode(k), form_decode(v))
+        })
+        .collect()
+}
+
+// Query strings encode spaces as '+'; a literal plus arrives as %2B
+fn form_decode(component: &str) -> String {
+    crate::config::percent_decode(&component.replace('+', " "))
+}
+
+/// Write a complete HTTP response and close the connection
+pub fn write_response(stream: &mut TcpStream, status: u16, body: &str, origin: Option<&str>) {
+    let reason = match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        _ => "Internal Server Error",
+    };
+
+    let mut response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status, reason, body.len()
+    );
+    if let Some(origin) = origin {
+        response.push_str(&format!(
+            "Access-Control-Allow-Origin: {}\r\nAccess-Control-Allow-Headers: Authorization\r\nAccess-Control-Allow-Methods: GET, POST, OPTIONS\r\n",
+            origin
+        ));
+    }
+    response.push_str("\r\n");
+    response.push_str(body);
+
+    let _ = stream.write_all(response.as_bytes());
+    let _ = stream.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_token_keeps_four_characters_each_side() {
+        assert_eq!(mask_token("0123456789abcdef"), "0123…cdef");
+        assert_eq!(mask_token("short"), "…");
+    }
+
+    #[test]
+    fn mask_token_handles_non_ascii_tokens() {
+        assert_eq!(mask_token("ключ-для-доступа"), "ключ…тупа");
+    }
+}
@@ -0,0 +1,235 @@
+use serde_json::{json, Value};
+use v2parser::parser;
+
+use crate::config::Config;
+use crate::vpn::protocol::Core;
+use crate::xray_manager::StartSummary;
+
+// Balanced groups: several servers behind one local port. The group runs as one
+// xray process with an outbound per member, an observatory probing each member
+// through the test URL, and a balancer that spreads connections over the
+// members the observatory last saw working.
+
+// Running groups are registered in XRAY_PROCESSES under this prefix and their name
+const GROUP_PREFIX: &str = "group://";
+
+// Member outbounds are tagged member-0, member-1, ...; the observatory and balancer select them by prefix
+const MEMBER_TAG_PREFIX: &str = "member-";
+const BALANCER_TAG: &str = "balanced";
+
+/// Balancer strategies xray offers, as saved in the config
+#[cfg(feature = "gui")]
+pub const STRATEGIES: &[&str] = &["random", "roundRobin", "leastPing", "leastLoad"];
+
+/// Key a running group is registered under
+pub fn group_key(name: &str) -> String {
+    format!("{}{}", GROUP_PREFIX, name)
+}
+
+/// True for keys of running groups (as opposed to single servers)
+pub fn is_group_key(key: &str) -> bool {
+    key.starts_with(GROUP_PREFIX)
+}
+
+/// xray config with one outbound per member URI behind a balancer.
+/// Routing rules are added by the caller; `route_through_balancer` then points them at the balancer.
+pub fn build_config(
+    member_uris: &[String],
+    socks_port: Option<u16>,
+    http_port: Option<u16>,
+    strategy: &str,
+    probe_url: &str,
+) -> Result<String, String> {
+    let Some(first_uri) = member_uris.first() else {
+        return Err("The group has no servers in the current server list".to_string());
+    };
+    let mut config: Value = serde_json::from_str(&member_config(first_uri, socks_port, http_port)?)
+        .map_err(|e| format!("Failed to read generated xray config: {}", e))?;
+
+    let mut outbounds = Vec::new();
+    for (index, uri) in member_uris.iter().enumerate() {
+        if crate::vpn::ssr::is_ssr_uri(uri) {
+            return Err("ShadowsocksR servers xray can't dial can't join a balanced group".to_string());
+        }
+        let member: Value = serde_json::from_str(&member_config(uri, socks_port, http_port)?)
+            .map_err(|e| format!("Failed to read generated xray config: {}", e))?;
+        let mut outbound = member["outbounds"].as_array()
+            .and_then(|outbounds| outbounds.first())
+            .cloned()
+            .ok_or_else(|| "Generated xray config has no outbounds".to_string())?;
+        outbound["tag"] = json!(format!("{}{}", MEMBER_TAG_PREFIX, index));
+        outbounds.push(outbound);
+    }
+    // Keep whatever else the generator added (direct, block) after the members
+    if let Some(extra) = config["outbounds"].as_array() {
+        outbounds.extend(extra.iter().skip(1).cloned());
+    }
+    config["outbounds"] = Value::Array(outbounds);
+
+    config["observatory"] = json!({
+        "subjectSelector": [MEMBER_TAG_PREFIX],
+        "probeUrl": probe_url,
+        "probeInterval": "30s",
+        "enableConcurrency": true,
+    });
+    config["routing"]["balancers"] = json!([{
+        "tag": BALANCER_TAG,
+        "selector": [MEMBER_TAG_PREFIX],
+        "strategy": { "type": strategy },
+        // Used while the observatory has no results yet or every member failed
+        "fallbackTag": format!("{}0", MEMBER_TAG_PREFIX),
+    }]);
+    Ok(config.to_string())
+}
+
+// xray config of one member; servers of a protocol run by another core can't share an xray process
+fn member_config(uri: &str, socks_port: Option<u16>, http_port: Option<u16>) -> Result<String, String> {
+    match crate::vpn::protocol::find(uri) {
+        Some(handler) if handler.core() != Core::Xray => Err(format!(
+            "{} servers don't run in xray and can't join a balanced group", handler.protocol())),
+        Some(handler) => handler.core_config(uri, socks_port, http_port),
+        None => Ok(parser::create_json_config(uri, socks_port, http_port)),
+    }
+}
+
+/// Send proxied traffic to the balancer: routing rules aimed at the first member
+/// (`routing::apply` treats it as "the proxy") and everything left unmatched
+pub fn route_through_balancer(config_json: &str) -> Result<String, String> {
+    let mut config: Value = serde_json::from_str(config_json)
+        .map_err(|e| format!("Failed to read generated xray config: {}", e))?;
+    let first_member = format!("{}0", MEMBER_TAG_PREFIX);
+
+    let mut rules = config["routing"]["rules"].as_array().cloned().unwrap_or_default();
+    for rule in rules.iter_mut() {
+        if rule["outboundTag"].as_str() == Some(first_member.as_str()) {
+            if let Some(rule) = rule.as_object_mut() {
+                rule.remove("outboundTag");
+                rule.insert("balancerTag".to_string(), json!(BALANCER_TAG));
+            }
+        }
+    }
+    // Catch-all last; xray rules need a condition, and every connection has a network
+    rules.push(json!({ "type": "field", "network": "tcp,udp", "balancerTag": BALANCER_TAG }));
+    config["routing"]["rules"] = Value::Array(rules);
+    Ok(config.to_string())
+}
+
+/// Bring running groups in line with the config: start enabled groups that aren't running,
+/// restart those whose config changed (members, their links, routing, ...), stop disabled
+/// and deleted ones.
+/// Member links come from the current server list, so call it after VPN_SERVERS is updated.
+pub fn sync_groups(config: &Config) -> StartSummary {
+    let mut summary = StartSummary::default();
+    let running = crate::xray_manager::get_running_uris();
+    let servers = crate::vpn::VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.clone())
+        .unwrap_or_default();
+
+    for (key, _) in running.iter().filter(|(key, _)| is_group_key(key)) {
+        let configured = config.balanced_groups.iter().any(|g| g.enabled && group_key(&g.name) == *key);
+        if !configured {
+            println!("Balanced group {} removed or disabled, stopping", key);
+            let _ = crate::TOKIO_RUNTIME.block_on(crate::xray_manager::stop_server(key));
+        }
+    }
+
+    if config.xray_binary_path.is_empty() {
+        return summary;
+    }
+    for group in config.balanced_groups.iter().filter(|g| g.enabled) {
+        let key = group_key(&group.name);
+        let member_uris: Vec<String> = group.members.iter()
+            .filter_map(|member| servers.iter().find(|s| s.get_server_key() == *member))
+            .map(|server| server.uri.clone())
+            .filter(|uri| !uri.is_empty())
+            .collect();
+        // New group without servers yet, or its servers left the subscriptions
+        if member_uris.is_empty() {
+            if running.contains_key(&key) {
+                let _ = crate::TOKIO_RUNTIME.block_on(crate::xray_manager::stop_server(&key));
+            }
+            continue;
+        }
+        match running.get(&key) {
+            Some(_) if crate::xray_manager::group_is_current(group, &member_uris, &config.xray_binary_path) => continue,
+            Some(_) => {
+                println!("Balanced group {} changed, restarting", group.name);
+                let _ = crate::TOKIO_RUNTIME.block_on(crate::xray_manager::stop_server(&key));
+            }
+            None => {}
+        }
+        match crate::TOKIO_RUNTIME.block_on(crate::xray_manager::start_group(group, &member_uris, &config.xray_binary_path)) {
+            Ok(()) => {
+                println!("Started balanced group: {} ({} servers)", group.name, member_uris.len());
+                summary.started.push(group.name.clone());
+            }
+            Err(e) => {
+                eprintln!("Failed to start balanced group {}: {}", group.name, e);
+                summary.failed.push((group.name.clone(), e));
+            }
+        }
+    }
+    summary
+}
+
+/// Add a group or replace the one named `previous_name`, then apply
+#[cfg(feature = "gui")]
+pub fn save_group(previous_name: &str, group: crate::config::BalancedGroup) -> Result<(), String> {
+    if group.name.trim().is_empty() {
+        return Err("Enter a group name".to_string());
+    }
+    let mut config = Config::load()?;
+    if group.name != previous_name && config.balanced_groups.iter().any(|g| g.name == group.name) {
+        return Err(format!("A group named {} already exists", group.name));
+    }
+    let port_taken = config.server_settings.values().any(|s| s.local_port == group.local_port)
+        || (config.primary.enabled && config.primary.port == group.local_port)
+        || config.balanced_groups.iter().any(|g| g.name != previous_name && g.local_port == group.local_port);
+    if port_taken {
+        return Err(format!("Port {} is already used by a server, group or the primary port", group.local_port));
+    }
+
+    match config.balanced_groups.iter_mut().find(|g| g.name == previous_name && !previous_name.is_empty()) {
+        Some(existing) => *existing = group,
+        None => config.balanced_groups.push(group),
+    }
+    config.save()?;
+    // Port or strategy may have changed, which the member check in sync_groups doesn't see
+    if !previous_name.is_empty() {
+        let _ = crate::TOKIO_RUNTIME.block_on(crate::xray_manager::stop_server(&group_key(previous_name)));
+    }
+    apply(&config)
+}
+
+/// Set the member servers of a group, then apply
+#[cfg(feature = "gui")]
+pub fn set_members(name: &str, members: Vec<String>) -> Result<(), String> {
+    let mut config = Config::load()?;
+    let group = config.balanced_groups.iter_mut()
+        .find(|g| g.name == name)
+        .ok_or_else(|| format!("Group {} not found", name))?;
+    group.members = members;
+    config.save()?;
+    apply(&config)
+}
+
+/// Remove a group (stopping it if running)
+#[cfg(feature = "gui")]
+pub fn delete_group(name: &str) -> Result<(), String> {
+    let mut config = Config::load()?;
+    config.balanced_groups.retain(|g| g.name != name);
+    config.save()?;
+    apply(&config)
+}
+
+// Sync running groups with the saved config and report failures
+#[cfg(feature = "gui")]
+fn apply(config: &Config) -> Result<(), String> {
+    let summary = sync_groups(config);
+    crate::request_menu_update();
+    if summary.failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Saved, but the group didn't start:\n{}", summary.failure_text()))
+    }
+}
@@ -0,0 +1,437 @@
+// vpnctl: command-line companion for VPN Manager.
+// Talks to the running tray app through its local control API, so the
+// tray stays the single owner of xray processes.
+
+#[allow(dead_code)]
+#[path = "../config.rs"]
+mod config;
+#[allow(dead_code)]
+#[path = "../vpn/pattern.rs"]
+mod pattern;
+
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: vpnctl [--config <path>] [--port <port>] [--token <token>] <command> [server]
+       vpnctl [--config <path>] --get <setting> | --set <setting>=<value> ...
+
+Commands:
+  list             List servers with their state
+  status           Show running servers
+  start <server>   Start a server (key or name)
+  stop <server>    Stop a server (key or name)
+  test [server]    Fetch the test URL through a running server (all running servers if omitted)
+  restart          Restart all enabled servers
+
+Settings (read and written in the config file, no running app needed):
+  --get api.port                          Print a setting as JSON
+  --set subscriptions[0].url=https://...  Change a setting; the value is JSON or else a string
+  --set 'server_settings[\"VLESS://1.2.3.4:443\"].local_port=10810'
+Several --set run in order; the previous config is kept as <name>.json.bak. A running
+app reads most settings on use; restart it for ports and startup options.
+
+The token defaults to $VPNCTL_TOKEN, then the first token in the config.
+Use --config to talk to a tray app started with the same --config profile.";
+
+struct Client {
+    base_url: String,
+    token: String,
+    http: reqwest::blocking::Client,
+}
+
+impl Client {
+    fn get(&self, path: &str) -> Result<serde_json::Value, String> {
+        let response = self.http.get(format!("{}{}", self.base_url, path))
+            .bearer_auth(&self.token)
+            .send()
+            .map_err(|e| format!("Cannot reach VPN Manager (is it running with the API enabled?): {}", e))?;
+        Self::parse(response)
+    }
+
+    fn post(&self, path: &str, key: &str) -> Result<serde_json::Value, String> {
+        let response = self.http.post(format!("{}{}", self.base_url, path))
+            .bearer_auth(&self.token)
+            .query(&[("key", key)])
+            .send()
+            .map_err(|e| format!("Cannot reach VPN Manager (is it running with the API enabled?): {}", e))?;
+        Self::parse(response)
+    }
+
+    fn parse(response: reqwest::blocking::Response) -> Result<serde_json::Value, String> {
+        let status = response.status();
+        let text = response.text()
+            .map_err(|e| format!("Invalid response: {}", e))?;
+        let body: serde_json::Value = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid response: {}", e))?;
+        if status.as_u16() == 401 {
+            return Err("Access denied: invalid or missing API token".to_string());
+        }
+        if !status.is_success() || body["ok"] == false {
+            let error = body["error"].as_str().unwrap_or("request failed");
+            return Err(error.to_string());
+        }
+        Ok(body)
+    }
+
+    // Resolve a server by key, or by name (case-insensitive)
+    fn resolve(&self, server: &str) -> Result<String, String> {
+        let list = self.get("/api/servers")?;
+        let servers = list["servers"].as_array().cloned().unwrap_or_default();
+        if servers.iter().any(|s| s["key"] == server) {
+            return Ok(server.to_string());
+        }
+        let matches: Vec<&serde_json::Value> = servers.iter()
+            .filter(|s| s["name"].as_str().map(|n| n.eq_ignore_ascii_case(server)).unwrap_or(false))
+            .collect();
+        match matches.as_slice() {
+            [single] => Ok(single["key"].as_str().unwrap_or_default().to_string()),
+            [] => Err(format!("No server named {}", server)),
+            _ => Err(format!("Several servers are named {}; use the key instead", server)),
+        }
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("vpnctl: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut port: Option<u16> = None;
+    let mut token: Option<String> = std::env::var("VPNCTL_TOKEN").ok();
+    let mut settings: Vec<(String, String)> = Vec::new(); // (--get or --set, argument)
+
+    // Global options before the command
+    while let Some(flag) = args.first().cloned() {
+        match flag.as_str() {
+            "--port" => {
+                args.remove(0);
+                let value = if args.is_empty() { String::new() } else { args.remove(0) };
+                port = Some(value.parse().map_err(|_| "--port needs a number".to_string())?);
+            }
+            "--config" => {
+                args.remove(0);
+                if args.is_empty() {
+                    return Err("--config needs a path".to_string());
+                }
+                config::Config::set_config_path(std::path::PathBuf::from(args.remove(0)));
+            }
+            "--token" => {
+                args.remove(0);
+                if args.is_empty() {
+                    return Err("--token needs a value".to_string());
+                }
+                token = Some(args.remove(0));
+            }
+            "--get" | "--set" => {
+                args.remove(0);
+                if args.is_empty() {
+                    return Err(format!("{} needs a setting", flag));
+                }
+                settings.push((flag, args.remove(0)));
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => break,
+        }
+    }
+
+    if !settings.is_empty() {
+        for (flag, argument) in settings {
+            if flag == "--get" {
+                let value = get_value(&argument)?;
+                // Strings bare, so scripts can use them directly
+                match value.as_str() {
+                    Some(text) => println!("{}", text),
+                    None => println!("{}", serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?),
+                }
+            } else {
+                let (path, value) = argument.split_once('=')
+                    .ok_or_else(|| format!("--set needs <setting>=<value>, got {}", argument))?;
+                set_value(path, value)?;
+                println!("Set {}", path);
+            }
+        }
+        return Ok(());
+    }
+
+    let Some(command) = args.first().cloned() else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+
+    let config = config::Config::load().unwrap_or_default();
+    let port = port.unwrap_or(config.api.port);
+    let token = token
+        .or_else(|| config.api.tokens.first().map(|t| t.token.clone()))
+        .ok_or("No API token; create one in Settings → Tools → API Access")?;
+
+    let client = Client {
+        base_url: format!("http://127.0.0.1:{}", port),
+        token,
+        http: reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| e.to_string())?,
+    };
+
+    let server_arg = || args.get(1).cloned().ok_or(format!("{} needs a server key or name", command));
+
+    match command.as_str() {
+        "list" => {
+            let list = client.get("/api/servers")?;
+            for server in list["servers"].as_array().cloned().unwrap_or_default() {
+                let mark = if server["running"] == true { "*" } else if server["enabled"] == true { "-" } else { " " };
+                println!(
+                    "{} {:<30} {:>5}:{:<5} {}",
+                    mark,
+                    server["name"].as_str().unwrap_or(""),
+                    server["proxy_type"].as_str().unwrap_or(""),
+                    server["local_port"],
+                    server["key"].as_str().unwrap_or(""),
+                );
+            }
+        }
+        "status" => {
+            let list = client.get("/api/status")?;
+            let running: Vec<serde_json::Value> = list["servers"].as_array().cloned().unwrap_or_default()
+                .into_iter()
+                .filter(|s| s["running"] == true)
+                .collect();
+            if running.is_empty() {
+                println!("No servers running");
+            }
+            for server in running {
+                println!(
+                    "running  {} ({} on 127.0.0.1:{})",
+                    server["name"].as_str().unwrap_or(""),
+                    server["proxy_type"].as_str().unwrap_or(""),
+                    server["local_port"],
+                );
+            }
+        }
+        "start" | "stop" => {
+            let key = client.resolve(&server_arg()?)?;
+            client.post(&format!("/api/servers/{}", command), &key)?;
+            println!("{} {}", if command == "start" { "Started" } else { "Stopped" }, key);
+        }
+        "test" if args.len() < 2 => {
+            let report = client.post("/api/servers/test", "")?;
+            let results = report["results"].as_array().cloned().unwrap_or_default();
+            if results.is_empty() {
+                println!("No servers running");
+            }
+            for result in results {
+                let key = result["key"].as_str().unwrap_or("");
+                if result["ok"] == true {
+                    println!("{}: OK ({} ms)", key, result["latency_ms"]);
+                } else {
+                    println!("{}: FAILED ({})", key, result["error"].as_str().unwrap_or("unknown error"));
+                }
+            }
+        }
+        "test" => {
+            let key = client.resolve(&server_arg()?)?;
+            let result = client.post("/api/servers/test", &key)?;
+            println!("{}: OK ({} ms)", key, result["latency_ms"]);
+        }
+        "restart" => {
+            client.post("/api/restart", "")?;
+            println!("Restarted enabled servers");
+        }
+        other => return Err(format!("Unknown command {}\n\n{}", other, USAGE)),
+    }
+
+    Ok(())
+}
+
+// One step of a --get/--set path: an object key or an array index
+#[derive(Debug, PartialEq)]
+enum PathStep {
+    Key(String),
+    Index(usize),
+}
+
+// Parse "subscriptions[0].url" or `server_settings["VLESS://1.2.3.4:443"].local_port`
+fn parse_value_path(path: &str) -> Result<Vec<PathStep>, String> {
+    let mut steps = Vec::new();
+    let mut rest = path.trim();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let close = after.find(']').ok_or_else(|| format!("Missing ] in {}", path))?;
+            let inside = after[..close].trim();
+            if let Some(key) = inside.strip_prefix('"').and_then(|k| k.strip_suffix('"')) {
+                steps.push(PathStep::Key(key.to_string()));
+            } else {
+                let index = inside.parse().map_err(|_| format!("{} is not an array index in {}", inside, path))?;
+                steps.push(PathStep::Index(index));
+            }
+            rest = &after[close + 1..];
+        } else {
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(format!("Empty name in {}", path));
+            }
+            steps.push(PathStep::Key(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+        rest = rest.strip_prefix('.').unwrap_or(rest);
+    }
+    if steps.is_empty() {
+        return Err("Give a setting path, e.g. api.port or subscriptions[0].url".to_string());
+    }
+    Ok(steps)
+}
+
+/// Read one setting of the (migrated) config as JSON, e.g. `api.port`
+fn get_value(path: &str) -> Result<serde_json::Value, String> {
+    let config = serde_json::to_value(config::Config::load()?)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    let mut value = &config;
+    for step in parse_value_path(path)? {
+        value = match step {
+            PathStep::Key(key) => value.get(&key),
+            PathStep::Index(index) => value.get(index),
+        }.ok_or_else(|| format!("{} is not a setting", path))?;
+    }
+    Ok(value.clone())
+}
+
+/// Change one setting and save, e.g. `subscriptions[0].url` to `https://...`. The value is
+/// JSON (`8080`, `true`, `{"name":"A","url":"..."}`), or taken as a string when it isn't.
+/// Missing object keys are created and index = length appends to an array. The result has
+/// to load as a config and pass `validate`; the previous file is kept as <name>.json.bak.
+fn set_value(path: &str, raw: &str) -> Result<(), String> {
+    let steps = parse_value_path(path)?;
+    let new_value: serde_json::Value = serde_json::from_str(raw)
+        .unwrap_or_else(|_| serde_json::Value::String(raw.to_string()));
+    let current = config::Config::load()?;
+    let mut config = serde_json::to_value(&current)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+
+    let mut target = &mut config;
+    for step in steps {
+        target = match step {
+            PathStep::Key(key) => target.as_object_mut()
+                .ok_or_else(|| format!("{} goes into something that isn't an object", path))?
+                .entry(key)
+                .or_insert(serde_json::Value::Null),
+            PathStep::Index(index) => {
+                let array = target.as_array_mut()
+                    .ok_or_else(|| format!("{} indexes something that isn't a list", path))?;
+                if index == array.len() {
+                    array.push(serde_json::Value::Null);
+                }
+                let len = array.len();
+                array.get_mut(index)
+                    .ok_or_else(|| format!("Index {} is past the end of a list of {}", index, len))?
+            }
+        };
+    }
+    *target = new_value;
+
+    let updated: config::Config = serde_json::from_value(config)
+        .map_err(|e| format!("Invalid value for {}: {}", path, e))?;
+    // Only refuse problems this change brings in, not ones the config already had
+    if let Err(e) = validate(&updated) {
+        if validate(&current).err().as_ref() != Some(&e) {
+            return Err(e);
+        }
+    }
+
+    let config_path = config::Config::get_config_path()?;
+    if config_path.exists() {
+        std::fs::copy(&config_path, config_path.with_extension("json.bak"))
+            .map_err(|e| format!("Failed to back up the config: {}", e))?;
+    }
+    updated.save()
+}
+
+// Checks the settings window makes on save, for configs edited from the command line
+fn validate(config: &config::Config) -> Result<(), String> {
+    for subscription in &config.subscriptions {
+        let url = &subscription.url;
+        let is_web = url.starts_with("http://") || url.starts_with("https://");
+        if !is_web && config::local_subscription_path(url).is_none() {
+            return Err(format!("Subscription {} needs an http:// or https:// URL, a file:// URL or a file path", subscription.name));
+        }
+        for (field, pattern) in [("include", &subscription.include), ("exclude", &subscription.exclude)] {
+            if !pattern.trim().is_empty() {
+                pattern::compile(pattern.trim())
+                    .map_err(|e| format!("Subscription {} {} pattern: {}", subscription.name, field, e))?;
+            }
+        }
+    }
+    if !config.test.url.starts_with("http://") && !config.test.url.starts_with("https://") {
+        return Err("test.url must be an http:// or https:// URL".to_string());
+    }
+    if !config.test.speed_url.starts_with("http://") && !config.test.speed_url.starts_with("https://") {
+        return Err("test.speed_url must be an http:// or https:// URL".to_string());
+    }
+    if !(1..=120).contains(&config.test.speed_secs) {
+        return Err("test.speed_secs must be from 1 to 120".to_string());
+    }
+    for (key, settings) in &config.server_settings {
+        if settings.auto_port {
+            if settings.local_port != 0 || settings.http_port != 0 {
+                return Err(format!("server_settings[\"{}\"] picks its ports with auto_port; set local_port and http_port to 0", key));
+            }
+        } else if settings.local_port == 0 {
+            return Err(format!("server_settings[\"{}\"].local_port can't be 0 without auto_port", key));
+        }
+        if !["SOCKS", "HTTP", "Both"].contains(&settings.proxy_type.as_str()) {
+            return Err(format!("server_settings[\"{}\"].proxy_type must be SOCKS, HTTP or Both", key));
+        }
+        if settings.proxy_type == "Both" && !settings.auto_port && (settings.http_port == 0 || settings.http_port == settings.local_port) {
+            return Err(format!("server_settings[\"{}\"].http_port must be set, and differ from local_port, for Both", key));
+        }
+        if settings.upstream == *key {
+            return Err(format!("server_settings[\"{}\"].upstream can't be the server itself", key));
+        }
+        if !settings.listen.is_empty() && settings.listen.parse::<std::net::IpAddr>().is_err() {
+            return Err(format!("server_settings[\"{}\"].listen must be an IP address", key));
+        }
+    }
+    if config.inbound.listen.parse::<std::net::IpAddr>().is_err() {
+        return Err("inbound.listen must be an IP address".to_string());
+    }
+    if !config.inbound.username.is_empty() && config.inbound.password.is_empty() {
+        return Err("inbound.password is empty while inbound.username is set".to_string());
+    }
+    if config.inbound.auth_local && config.inbound.username.is_empty() {
+        return Err("inbound.auth_local needs inbound.username and inbound.password".to_string());
+    }
+    let muxes = std::iter::once(("mux".to_string(), &config.mux)).chain(config.server_settings.iter()
+        .filter_map(|(key, settings)| settings.mux.as_ref().map(|mux| (format!("server_settings[\"{}\"].mux", key), mux))));
+    for (name, mux) in muxes {
+        if !(-1..=1024).contains(&mux.concurrency) || !(-1..=1024).contains(&mux.xudp_concurrency) {
+            return Err(format!("{} concurrency values must be from -1 to 1024", name));
+        }
+    }
+    let mut ports: Vec<(u16, &str)> = config.server_settings.iter()
+        .filter(|(_, settings)| !settings.auto_port)
+        .map(|(key, settings)| (settings.local_port, key.as_str()))
+        .chain(config.server_settings.iter()
+            .filter(|(_, settings)| settings.proxy_type == "Both" && !settings.auto_port)
+            .map(|(key, settings)| (settings.http_port, key.as_str())))
+        .collect();
+    if config.api.enabled {
+        ports.push((config.api.port, "api.port"));
+    }
+    if config.primary.enabled {
+        ports.push((config.primary.port, "primary.port"));
+    }
+    ports.sort();
+    if let Some(pair) = ports.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        return Err(format!("Port {} is used by both {} and {}", pair[0].0, pair[0].1, pair[1].1));
+    }
+    if ports.iter().any(|(port, _)| *port == 0) {
+        return Err("Ports can't be 0".to_string());
+    }
+    Ok(())
+}
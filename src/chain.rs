@@ -0,0 +1,82 @@
+use serde_json::json;
+
+use crate::config::Config;
+use crate::vpn::protocol::Core;
+
+// Proxy chaining (server context menu → Upstream Server...): a server with an
+// `upstream` reaches its own address through another server, the bridge, for
+// exits only reachable from the bridge's network. The bridge's outbound is added
+// to the generated xray config tagged "upstream" and the proxy outbound dials
+// through it (`sockopt.dialerProxy`). One hop only: the bridge's own upstream is
+// not followed. Fragmenting (dpi.rs) moves to the bridge, the first hop on the wire.
+
+// Tag of the bridge's outbound
+const UPSTREAM_TAG: &str = "upstream";
+
+// Tag dpi.rs gives its fragmenting outbound
+const FRAGMENT_TAG: &str = "fragment";
+
+/// Why `upstream` can't carry the server `server_key`, if it can't
+pub fn check_upstream(server_key: &str, upstream: &str) -> Result<(), String> {
+    if upstream == server_key {
+        return Err("A server can't be its own upstream".to_string());
+    }
+    if crate::balancer::is_group_key(upstream) {
+        return Err("A balanced group can't be an upstream".to_string());
+    }
+    let uri = upstream_uri(upstream)?;
+    if crate::vpn::protocol::find(&uri).is_some_and(|handler| handler.core() != Core::Xray) || crate::vpn::ssr::is_ssr_uri(&uri) {
+        return Err(format!("{} doesn't run on xray and can't be an upstream", crate::vpn::get_server_name(upstream)));
+    }
+    Ok(())
+}
+
+// Share link of the bridge, from the current server list
+fn upstream_uri(upstream: &str) -> Result<String, String> {
+    crate::vpn::VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.as_ref()?.iter().find(|s| s.get_server_key() == upstream).map(|s| s.uri.clone()))
+        .filter(|uri| !uri.is_empty())
+        .ok_or_else(|| format!("Upstream server {} is not in the server list", upstream))
+}
+
+/// Key of the server's upstream, None when it connects directly
+pub fn upstream_of<'a>(config: &'a Config, server_key: &str) -> Option<&'a str> {
+    config.server_settings.get(server_key).map(|s| s.upstream.as_str()).filter(|upstream| !upstream.is_empty())
+}
+
+/// Route the generated xray config's proxy outbound through the server's upstream, if it has one
+pub fn apply(config_json: &str, config: &Config, server_key: &str) -> Result<String, String> {
+    let Some(upstream) = upstream_of(config, server_key) else {
+        return Ok(config_json.to_string());
+    };
+    check_upstream(server_key, upstream)?;
+    let uri = upstream_uri(upstream)?;
+    // Only the outbound is used; the inbounds the config comes with are dropped
+    let bridge_json = match crate::vpn::protocol::find(&uri) {
+        Some(handler) => handler.core_config(&uri, None, None)?,
+        None => v2parser::parser::create_json_config(&uri, None, None),
+    };
+    let bridge: serde_json::Value = serde_json::from_str(&bridge_json)
+        .map_err(|e| format!("Failed to read the upstream's xray config: {}", e))?;
+    let Some(mut bridge_outbound) = bridge["outbounds"].as_array().and_then(|outbounds| outbounds.first()).cloned() else {
+        return Err("Upstream's xray config has no outbounds".to_string());
+    };
+
+    let mut config: serde_json::Value = serde_json::from_str(config_json)
+        .map_err(|e| format!("Failed to read generated xray config: {}", e))?;
+    let Some(outbounds) = config["outbounds"].as_array_mut() else {
+        return Err("Generated xray config has no outbounds".to_string());
+    };
+    let Some(outbound) = outbounds.first_mut() else {
+        return Err("Generated xray config has no outbounds".to_string());
+    };
+    let sockopt = &mut outbound["streamSettings"]["sockopt"];
+    // Fragments belong on the first hop
+    if sockopt["dialerProxy"].as_str() == Some(FRAGMENT_TAG) {
+        bridge_outbound["streamSettings"]["sockopt"]["dialerProxy"] = json!(FRAGMENT_TAG);
+    }
+    sockopt["dialerProxy"] = json!(UPSTREAM_TAG);
+    bridge_outbound["tag"] = json!(UPSTREAM_TAG);
+    outbounds.push(bridge_outbound);
+    serde_json::to_string_pretty(&config).map_err(|e| format!("Failed to write xray config: {}", e))
+}
@@ -5,40 +5,945 @@ use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerSettings {
-    pub local_port: u16,
-    pub proxy_type: String, // "SOCKS" or "HTTP"
+    pub local_port: u16, // With proxy_type "Both", the SOCKS port
+    pub proxy_type: String, // "SOCKS", "HTTP" or "Both"
+    #[serde(default)]
+    pub http_port: u16, // HTTP port when proxy_type is "Both"; unused otherwise
+    #[serde(default)]
+    pub auto_port: bool, // Pick free ports at each start; local_port and http_port stay 0
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+    #[serde(default)]
+    pub limits: ServerLimits,
+    #[serde(default)]
+    pub system_proxy: bool, // Point the system proxy at this server whenever it starts
+    #[serde(default)]
+    pub alias: String, // Name shown instead of the provider's; empty = the provider's name
+    #[serde(default)]
+    pub note: String, // Free text shown next to the server
+    #[serde(default)]
+    pub pinned: bool, // Favorite: listed first in settings and under Favorites in the tray
+    #[serde(default)]
+    pub hidden: bool, // Left out of the settings list (unless shown) and the tray; settings are kept
+    #[serde(default)]
+    pub listen: String, // Address the local inbounds bind; empty = inbound.listen
+    #[serde(default = "default_true")]
+    pub udp: bool, // Relay UDP on the SOCKS inbound (games, QUIC, calls)
+    #[serde(default)]
+    pub mux: Option<MuxSettings>, // Own multiplexing; None = Config::mux
+    #[serde(default)]
+    pub fingerprint: String, // uTLS fingerprint ("chrome", "firefox", "random", ...); empty = the link's
+    #[serde(default)]
+    pub fragment: Option<FragmentSettings>, // Split the TLS ClientHello; None = sent whole
+    #[serde(default)]
+    pub upstream: String, // Key of the server this one dials through (proxy chain); empty = direct
+}
+
+/// Per-server xray policy and limits on the local inbound
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerLimits {
+    #[serde(default)]
+    pub max_connections: u32, // Simultaneous connections to the local port(s), "Both" counting both; 0 = unlimited
+    #[serde(default)]
+    pub bandwidth_kbps: u32, // Cap per direction in kilobits per second; 0 = unlimited
+    #[serde(default)]
+    pub buffer_size_kb: Option<u32>, // xray policy bufferSize; None = xray default
+    #[serde(default)]
+    pub conn_idle_secs: Option<u32>, // xray policy connIdle; None = xray default
+    #[serde(default)]
+    pub handshake_secs: Option<u32>, // xray policy handshake; None = xray default
 }
 
 fn default_enabled() -> bool {
     true
 }
 
+/// Current format of server keys ("PROTOCOL://address:port"). Bump it when the format
+/// changes, so saved server_settings are re-keyed against the next server list.
+pub const SERVER_KEYS_VERSION: u32 = 1;
+
+/// Default port for the local control API
+pub const DEFAULT_API_PORT: u16 = 9876;
+
+fn default_api_port() -> u16 {
+    DEFAULT_API_PORT
+}
+
+pub fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub name: String,
+    pub token: String,
+    #[serde(default)]
+    pub created: u64, // Unix timestamp (seconds)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_api_port")]
+    pub port: u16,
+    #[serde(default = "default_true")]
+    pub loopback_only: bool, // Bind to 127.0.0.1 and reject remote peers
+    #[serde(default)]
+    pub allowed_origins: Vec<String>, // Browser origins allowed to call the API ("*" for any)
+    #[serde(default)]
+    pub tokens: Vec<ApiToken>,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        ApiSettings {
+            enabled: false,
+            port: DEFAULT_API_PORT,
+            loopback_only: true,
+            allowed_origins: Vec::new(),
+            tokens: Vec::new(),
+        }
+    }
+}
+
+/// Commands run when a server starts or stops (.ps1, .bat/.cmd or .exe paths)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HookSettings {
+    #[serde(default)]
+    pub on_start: String,
+    #[serde(default)]
+    pub on_stop: String,
+}
+
+/// Traffic matching any of the domains or IPs goes to `outbound`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutingRule {
+    pub outbound: String, // "proxy", "direct" or "block"
+    #[serde(default)]
+    pub domains: Vec<String>, // xray domain matchers: "example.com", "domain:", "full:", "geosite:", "*.example.com"
+    #[serde(default)]
+    pub ips: Vec<String>, // IPs, CIDRs or "geoip:" lists
+}
+
+/// A named set of routing and DNS rules, e.g. "RU bypass"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutingPreset {
+    pub name: String,
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
+    #[serde(default)]
+    pub dns_servers: Vec<String>, // Empty = xray default (system resolver)
+}
+
+/// A remotely hosted domain/IP list (dnsmasq, Clash rule-provider or plain lines)
+/// whose entries are all sent to one outbound
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+    pub name: String,
+    pub url: String,
+    pub outbound: String, // "proxy", "direct" or "block"
+}
+
+/// A named subscription link
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub include: String, // Name pattern servers must match to be kept; empty = all
+    #[serde(default)]
+    pub exclude: String, // Name pattern of servers to leave out, e.g. "expire|traffic"
+}
+
+/// Connectivity test used for health checks and the Test actions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestSettings {
+    #[serde(default = "default_test_url")]
+    pub url: String, // http:// or https:// URL fetched through the server
+    #[serde(default = "default_test_timeout")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_test_interval")]
+    pub interval_secs: u64, // Background health check interval
+    #[serde(default = "default_speed_url")]
+    pub speed_url: String, // File downloaded by the speed test
+    #[serde(default = "default_speed_secs")]
+    pub speed_secs: u64, // Longest a speed test downloads for
+}
+
+fn default_test_url() -> String {
+    "http://www.gstatic.com/generate_204".to_string()
+}
+
+fn default_test_timeout() -> u64 {
+    10
+}
+
+fn default_test_interval() -> u64 {
+    60
+}
+
+fn default_speed_url() -> String {
+    "https://speed.cloudflare.com/__down?bytes=50000000".to_string()
+}
+
+fn default_speed_secs() -> u64 {
+    10
+}
+
+impl Default for TestSettings {
+    fn default() -> Self {
+        TestSettings {
+            url: default_test_url(),
+            timeout_secs: default_test_timeout(),
+            interval_secs: default_test_interval(),
+            speed_url: default_speed_url(),
+            speed_secs: default_speed_secs(),
+        }
+    }
+}
+
+/// Default port for the primary (virtual) proxy
+pub const DEFAULT_PRIMARY_PORT: u16 = 10808;
+
+fn default_primary_port() -> u16 {
+    DEFAULT_PRIMARY_PORT
+}
+
+fn default_proxy_type() -> String {
+    "SOCKS".to_string()
+}
+
+/// Primary port: one stable local port relayed to a chosen running server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrimarySettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_primary_port")]
+    pub port: u16,
+    #[serde(default = "default_proxy_type")]
+    pub proxy_type: String, // Only servers with this proxy type can be targets
+    #[serde(default)]
+    pub server: String, // Preferred server key; empty = healthiest
+    #[serde(default)]
+    pub country: String, // Only use servers in this country (ISO code); empty = any
+    #[serde(default)]
+    pub max_rate: f32, // Only use servers billed at most this multiplier; 0 = any
+    #[serde(default = "default_primary_policy")]
+    pub policy: String, // "manual", "fastest" or "random"; how the server is picked on refresh
+}
+
+impl Default for PrimarySettings {
+    fn default() -> Self {
+        PrimarySettings {
+            enabled: false,
+            port: DEFAULT_PRIMARY_PORT,
+            proxy_type: default_proxy_type(),
+            server: String::new(),
+            country: String::new(),
+            max_rate: 0.0,
+            policy: default_primary_policy(),
+        }
+    }
+}
+
+fn default_primary_policy() -> String {
+    "manual".to_string()
+}
+
+fn default_rotation_interval() -> u64 {
+    30
+}
+
+fn default_rotation_mode() -> String {
+    "round_robin".to_string()
+}
+
+fn default_sticky_minutes() -> u64 {
+    10
+}
+
+/// Periodic switching of the primary port to another healthy server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotationSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_rotation_interval")]
+    pub interval_minutes: u64,
+    #[serde(default = "default_rotation_mode")]
+    pub mode: String, // "round_robin" or "random"
+    #[serde(default)]
+    pub sticky_sessions: bool, // Keep each client program on its server for sticky_minutes after its last connection (Windows)
+    #[serde(default = "default_sticky_minutes")]
+    pub sticky_minutes: u64,
+}
+
+impl Default for RotationSettings {
+    fn default() -> Self {
+        RotationSettings {
+            enabled: false,
+            interval_minutes: default_rotation_interval(),
+            mode: default_rotation_mode(),
+            sticky_sessions: false,
+            sticky_minutes: default_sticky_minutes(),
+        }
+    }
+}
+
+fn default_balancer_strategy() -> String {
+    "random".to_string()
+}
+
+/// Several servers behind one local port; xray spreads traffic over them and skips failing ones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalancedGroup {
+    pub name: String,
+    #[serde(default)]
+    pub members: Vec<String>, // Server keys
+    pub local_port: u16,
+    #[serde(default = "default_proxy_type")]
+    pub proxy_type: String,
+    #[serde(default = "default_balancer_strategy")]
+    pub strategy: String, // One of balancer::STRATEGIES
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+fn default_log_file_mb() -> u64 {
+    5
+}
+
+fn default_log_total_mb() -> u64 {
+    100
+}
+
+/// Log files under the config folder: xray output per server and the app's event log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_log_file_mb")]
+    pub max_file_mb: u64, // A file this large is rotated
+    #[serde(default = "default_log_total_mb")]
+    pub max_total_mb: u64, // Oldest files are deleted past this
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        LogSettings {
+            enabled: false,
+            max_file_mb: default_log_file_mb(),
+            max_total_mb: default_log_total_mb(),
+        }
+    }
+}
+
+/// DNS section of generated xray configs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DnsSettings {
+    #[serde(default)]
+    pub doh: Vec<String>, // DNS-over-HTTPS URLs, queried through the server ("https://1.1.1.1/dns-query")
+    #[serde(default)]
+    pub servers: Vec<String>, // Plain resolvers ("8.8.8.8", "1.1.1.1:53", "localhost")
+    #[serde(default)]
+    pub block_plaintext: bool, // DoH only: plain resolvers are dropped and direct traffic resolves through xray
+}
+
+/// xray Mux.Cool on the server outbound: several app connections share one
+/// connection to the server, saving handshakes on high-latency links
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MuxSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub concurrency: i32, // TCP streams per connection, 1-1024; 0 = xray default (8), -1 = TCP not multiplexed
+    #[serde(default)]
+    pub xudp_concurrency: i32, // UDP (XUDP) streams per connection, 1-1024; 0 = xray default, -1 = no XUDP
+}
+
+fn default_fragment_packets() -> String {
+    "tlshello".to_string()
+}
+
+fn default_fragment_length() -> String {
+    "100-200".to_string()
+}
+
+fn default_fragment_interval() -> String {
+    "10-20".to_string()
+}
+
+/// How xray's freedom `fragment` splits the first packets to the server, so DPI
+/// doesn't see the TLS ClientHello in one piece
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FragmentSettings {
+    #[serde(default = "default_fragment_packets")]
+    pub packets: String, // "tlshello" or a range of TCP writes ("1-3")
+    #[serde(default = "default_fragment_length")]
+    pub length: String, // Bytes per fragment, a number or range ("100-200")
+    #[serde(default = "default_fragment_interval")]
+    pub interval: String, // Milliseconds between fragments, a number or range ("10-20")
+}
+
+impl Default for FragmentSettings {
+    fn default() -> Self {
+        FragmentSettings {
+            packets: default_fragment_packets(),
+            length: default_fragment_length(),
+            interval: default_fragment_interval(),
+        }
+    }
+}
+
+/// Where geoip.dat/geosite.dat come from and whether they are kept current
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoAssetSettings {
+    #[serde(default = "default_true")]
+    pub auto_update: bool, // Look for a new release once a day
+    #[serde(default)]
+    pub source: String, // GitHub releases page with both files; empty = geoassets::DEFAULT_SOURCE
+}
+
+impl Default for GeoAssetSettings {
+    fn default() -> Self {
+        GeoAssetSettings { auto_update: true, source: String::new() }
+    }
+}
+
+/// System proxy behaviour beyond the tray toggle
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SystemProxySettings {
+    #[serde(default)]
+    pub enabled: bool, // Keep it on: set at start and moved to another server when its server stops
+    #[serde(default)]
+    pub winhttp: bool, // Also set the machine-wide WinHTTP proxy (services, updaters); needs admin, HTTP ports only
+}
+
+/// Default port the PAC file is served on
+pub const DEFAULT_PAC_PORT: u16 = 10809;
+
+fn default_pac_port() -> u16 {
+    DEFAULT_PAC_PORT
+}
+
+/// PAC file served on loopback: bypass domains direct, everything else through the local proxy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PacSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_pac_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub bypass: Vec<String>, // Domains ("example.com", "*.corp.local") and IPv4 CIDRs sent direct
+    #[serde(default = "default_true")]
+    pub preset_direct: bool, // Also send the active routing preset's direct entries direct
+    #[serde(default)]
+    pub register: bool, // Set it as the system auto-config URL while served
+}
+
+impl Default for PacSettings {
+    fn default() -> Self {
+        PacSettings {
+            enabled: false,
+            port: DEFAULT_PAC_PORT,
+            bypass: Vec::new(),
+            preset_direct: true,
+            register: false,
+        }
+    }
+}
+
+fn default_tun_stack() -> String {
+    "mixed".to_string()
+}
+
+/// TUN mode: a sing-box wintun adapter sending all system traffic to one local proxy port
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TunSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub server: String, // Server key to tunnel through; empty = primary port or healthiest running server
+    #[serde(default = "default_tun_stack")]
+    pub stack: String, // sing-box TUN stack: "system", "gvisor" or "mixed"
+    #[serde(default = "default_true")]
+    pub bypass_lan: bool, // Keep private networks off the tunnel
+}
+
+impl Default for TunSettings {
+    fn default() -> Self {
+        TunSettings {
+            enabled: false,
+            server: String::new(),
+            stack: default_tun_stack(),
+            bypass_lan: true,
+        }
+    }
+}
+
+fn default_failover_failures() -> usize {
+    3
+}
+
+/// Automatic failover: replace a server that keeps failing checks with a backup on its port
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailoverSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_failover_failures")]
+    pub failures: usize, // Consecutive failed checks before switching; a crash switches at once
+}
+
+impl Default for FailoverSettings {
+    fn default() -> Self {
+        FailoverSettings {
+            enabled: false,
+            failures: default_failover_failures(),
+        }
+    }
+}
+
+fn default_loopback_port() -> u16 {
+    10899
+}
+
+fn default_loopback_proxy_type() -> String {
+    "SOCKS".to_string()
+}
+
+/// Port and type of the troubleshooting loopback proxy (Troubleshooting in the tray)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoopbackSettings {
+    #[serde(default = "default_loopback_port")]
+    pub port: u16,
+    #[serde(default = "default_loopback_proxy_type")]
+    pub proxy_type: String, // "SOCKS" or "HTTP"
+}
+
+impl Default for LoopbackSettings {
+    fn default() -> Self {
+        LoopbackSettings {
+            port: default_loopback_port(),
+            proxy_type: default_loopback_proxy_type(),
+        }
+    }
+}
+
+fn default_max_restarts() -> u32 {
+    5
+}
+
+/// Restarting servers whose xray process exits on its own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupervisionSettings {
+    #[serde(default = "default_true")]
+    pub auto_restart: bool, // Ignored for single servers while failover is on; it takes their port instead
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32, // Restarts in a row before giving up; 10 minutes of running resets the count
+}
+
+impl Default for SupervisionSettings {
+    fn default() -> Self {
+        SupervisionSettings {
+            auto_restart: true,
+            max_restarts: default_max_restarts(),
+        }
+    }
+}
+
+fn default_status_page_port() -> u16 {
+    10880
+}
+
+/// Read-only page listing the running proxies with a QR code each (Tools → Status Page...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusPageSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_status_page_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub lan: bool, // Serve to the local network too; false = this computer only
+}
+
+impl Default for StatusPageSettings {
+    fn default() -> Self {
+        StatusPageSettings {
+            enabled: false,
+            port: default_status_page_port(),
+            lan: false,
+        }
+    }
+}
+
+fn default_listen() -> String {
+    "127.0.0.1".to_string()
+}
+
+/// Where server inbounds listen and what LAN clients log in with (Tools → LAN Sharing...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboundSettings {
+    #[serde(default = "default_listen")]
+    pub listen: String, // Default for servers without their own: "127.0.0.1" or "0.0.0.0" (LAN)
+    #[serde(default)]
+    pub username: String, // Asked of SOCKS/HTTP clients on inbounds open to the LAN; empty = none
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub auth_local: bool, // Ask for the login on 127.0.0.1 too, for machines shared by several users
+}
+
+impl Default for InboundSettings {
+    fn default() -> Self {
+        InboundSettings {
+            listen: default_listen(),
+            username: String::new(),
+            password: String::new(),
+            auth_local: false,
+        }
+    }
+}
+
+fn default_scheduled_test_hour() -> u8 {
+    3
+}
+
+/// Daily latency sweep of the enabled servers (Tools → Scheduled Tests...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledTestSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_scheduled_test_hour")]
+    pub hour: u8, // Local hour from which the day's sweep runs; later if the app starts later
+    #[serde(default)]
+    pub quiet_start: u8, // No sweep from this hour...
+    #[serde(default)]
+    pub quiet_end: u8,   // ...until this one; equal hours = no quiet hours
+    #[serde(default = "default_true")]
+    pub skip_metered: bool,
+}
+
+impl Default for ScheduledTestSettings {
+    fn default() -> Self {
+        ScheduledTestSettings {
+            enabled: false,
+            hour: default_scheduled_test_hour(),
+            quiet_start: 0,
+            quiet_end: 0,
+            skip_metered: true,
+        }
+    }
+}
+
+fn default_exit_ip_url() -> String {
+    "https://api.ipify.org".to_string()
+}
+
+fn default_exit_ip_interval() -> u32 {
+    30
+}
+
+/// Watching the public IP each running server exits from (Tools → Exit IP Alerts...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExitIpSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_exit_ip_url")]
+    pub url: String, // Answers with the caller's IP as plain text
+    #[serde(default = "default_exit_ip_interval")]
+    pub interval_minutes: u32,
+    #[serde(default = "default_true")]
+    pub alert: bool, // Show a warning when an IP changes; off = only log it and emit the event
+}
+
+impl Default for ExitIpSettings {
+    fn default() -> Self {
+        ExitIpSettings {
+            enabled: false,
+            url: default_exit_ip_url(),
+            interval_minutes: default_exit_ip_interval(),
+            alert: true,
+        }
+    }
+}
+
+/// Tray icon look, so several --config profiles can be told apart at a glance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrayIconSettings {
+    #[serde(default)]
+    pub tint: Option<String>,  // "#RRGGBB"; None = gold, or a color picked from the profile name
+    #[serde(default)]
+    pub badge: Option<String>, // Emoji or letters drawn in the corner; None = profile initial, "" = no badge
+    #[serde(default = "default_true")]
+    pub status_colors: bool,   // Star colored by state (gray/green/red/blue); the tint then colors the badge
+}
+
+impl Default for TrayIconSettings {
+    fn default() -> Self {
+        TrayIconSettings {
+            tint: None,
+            badge: None,
+            status_colors: true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    pub subscription_url: String,
+    #[serde(default)]
+    pub subscriptions: Vec<Subscription>,
+    // Single URL from older versions; moved into `subscriptions` on load
+    #[serde(default, skip_serializing)]
+    subscription_url: String,
     pub xray_binary_path: String,
     #[serde(default)]
+    pub singbox_binary_path: String, // sing-box runs the hysteria2 servers; empty = they can't start
+    #[serde(default)]
     pub server_settings: HashMap<String, ServerSettings>,
     #[serde(default)]
     pub autostart: bool,
+    #[serde(default = "default_true")]
+    pub start_minimized: bool, // Stay in the tray on launch; off opens the settings window
+    #[serde(default)]
+    pub group_by: String, // "subscription" or "country" groups the server list and tray; empty = one list
+    #[serde(default)]
+    pub api: ApiSettings,
+    #[serde(default)]
+    pub hooks: HookSettings,
+    #[serde(default)]
+    pub flush_dns_on_connect: bool,
+    #[serde(default)]
+    pub subscription_refresh_hours: u32, // 0 = only refresh manually
+    #[serde(default)]
+    pub test: TestSettings,
+    #[serde(default)]
+    pub primary: PrimarySettings,
+    #[serde(default)]
+    pub rotation: RotationSettings,
+    #[serde(default)]
+    pub shared_machine: bool, // Use this Windows user's own port range so accounts don't collide
+    #[serde(default)]
+    pub manual_servers: Vec<String>, // Share URIs added by hand; kept across subscription updates
+    #[serde(default)]
+    pub routing_presets: Vec<RoutingPreset>,
+    #[serde(default)]
+    pub routing_preset: String, // Active preset name; empty = everything goes through the proxy
+    #[serde(default)]
+    pub routing_rules: Vec<RoutingRule>, // Applied to every server in order, ahead of the preset's rules
+    #[serde(default)]
+    pub rule_sets: Vec<RuleSet>,
+    #[serde(default = "default_start_concurrency")]
+    pub start_concurrency: usize, // Servers started at once on launch/restart
+    #[serde(default)]
+    pub startup_mode: String, // "resume" = servers running at last exit; empty = enabled servers
+    #[serde(default)]
+    pub failover: FailoverSettings,
+    #[serde(default)]
+    pub supervision: SupervisionSettings,
+    #[serde(default)]
+    pub exit_ip: ExitIpSettings,
+    #[serde(default)]
+    pub scheduled_tests: ScheduledTestSettings,
+    #[serde(default)]
+    pub status_page: StatusPageSettings,
+    #[serde(default)]
+    pub inbound: InboundSettings,
+    #[serde(default)]
+    pub mux: MuxSettings,
+    #[serde(default)]
+    pub loopback: LoopbackSettings,
+    #[serde(default)]
+    pub logs: LogSettings,
+    #[serde(default)]
+    pub balanced_groups: Vec<BalancedGroup>,
+    #[serde(default)]
+    pub tun: TunSettings,
+    #[serde(default)]
+    pub system_proxy: SystemProxySettings,
+    #[serde(default)]
+    pub pac: PacSettings,
+    #[serde(default)]
+    pub tray_icon: TrayIconSettings,
+    #[serde(default)]
+    pub geo_assets: GeoAssetSettings,
+    #[serde(default)]
+    pub dns: DnsSettings,
+    #[serde(default)]
+    pub server_keys_version: u32, // Key format `server_settings` was last migrated to (SERVER_KEYS_VERSION)
+    #[serde(default = "default_settings_retention_days")]
+    pub settings_retention_days: u32, // Drop settings of servers missing from the subscriptions this long; 0 = keep
+}
+
+fn default_start_concurrency() -> usize {
+    6
+}
+
+fn default_settings_retention_days() -> u32 {
+    30
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
+            subscriptions: Vec::new(),
             subscription_url: String::new(),
             xray_binary_path: String::new(),
+            singbox_binary_path: String::new(),
             server_settings: HashMap::new(),
             autostart: false,
+            start_minimized: true,
+            group_by: String::new(),
+            api: ApiSettings::default(),
+            hooks: HookSettings::default(),
+            flush_dns_on_connect: false,
+            subscription_refresh_hours: 0,
+            test: TestSettings::default(),
+            primary: PrimarySettings::default(),
+            rotation: RotationSettings::default(),
+            shared_machine: false,
+            manual_servers: Vec::new(),
+            routing_presets: Vec::new(),
+            routing_preset: String::new(),
+            routing_rules: Vec::new(),
+            rule_sets: Vec::new(),
+            start_concurrency: default_start_concurrency(),
+            startup_mode: String::new(),
+            failover: FailoverSettings::default(),
+            supervision: SupervisionSettings::default(),
+            exit_ip: ExitIpSettings::default(),
+            scheduled_tests: ScheduledTestSettings::default(),
+            status_page: StatusPageSettings::default(),
+            inbound: InboundSettings::default(),
+            mux: MuxSettings::default(),
+            loopback: LoopbackSettings::default(),
+            logs: LogSettings::default(),
+            balanced_groups: Vec::new(),
+            tun: TunSettings::default(),
+            system_proxy: SystemProxySettings::default(),
+            pac: PacSettings::default(),
+            tray_icon: TrayIconSettings::default(),
+            geo_assets: GeoAssetSettings::default(),
+            dns: DnsSettings::default(),
+            server_keys_version: SERVER_KEYS_VERSION,
+            settings_retention_days: default_settings_retention_days(),
+        }
+    }
+}
+
+// Config file given with --config; replaces the AppData default for this process
+static CONFIG_PATH_OVERRIDE: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+
+/// First port automatically assigned to new servers
+pub const DEFAULT_FIRST_LOCAL_PORT: u16 = 1080;
+
+// Shared machine mode: each Windows user gets a block of ports derived from their name
+const SHARED_PORT_START: u16 = 20000;
+const SHARED_PORT_BLOCK: u16 = 100;
+const SHARED_PORT_BLOCKS: u64 = 450; // 20000..65000
+
+/// FNV-1a hash of `text`: the same on every run and Rust version, unlike DefaultHasher
+pub fn stable_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// First port of the current Windows user's block (shared machine mode)
+pub fn shared_port_base() -> u16 {
+    let user = format!(
+        "{}\\{}",
+        std::env::var("USERDOMAIN").unwrap_or_default(),
+        std::env::var("USERNAME").unwrap_or_default()
+    ).to_lowercase();
+    // The same user must land on the same block on every run
+    let hash = stable_hash(&user);
+    SHARED_PORT_START + (hash % SHARED_PORT_BLOCKS) as u16 * SHARED_PORT_BLOCK
+}
+
+/// `<config dir>\cache\<kind>-<hash>.txt` for data downloaded from `url`
+/// (created on demand; None if the directory can't be made)
+pub fn cache_file_path(kind: &str, url: &str) -> Option<PathBuf> {
+    let hash = stable_hash(url);
+    let config_path = Config::get_config_path().ok()?;
+    let cache_dir = config_path.parent()?.join("cache");
+    std::fs::create_dir_all(&cache_dir).ok()?;
+    Some(cache_dir.join(format!("{}-{:016x}.txt", kind, hash)))
+}
+
+/// Local file behind a subscription source: a file:// URL or a plain path
+/// (C:\nodes.txt, \\server\share\nodes.txt). None for http(s) URLs.
+pub fn local_subscription_path(source: &str) -> Option<PathBuf> {
+    let source = source.trim();
+    if let Some(rest) = source.strip_prefix("file://") {
+        // file:///C:/nodes.txt -> C:/nodes.txt; file://server/share -> \\server\share
+        let path = match rest.strip_prefix('/') {
+            Some(local) if local.get(1..2) == Some(":") => local.to_string(),
+            Some(local) => format!("/{}", local),
+            None if rest.get(1..2) == Some(":") => rest.to_string(), // file://C:/nodes.txt
+            None => format!("//{}", rest),
+        };
+        return Some(PathBuf::from(percent_decode(&path)));
+    }
+    if source.contains("://") {
+        return None;
+    }
+    let is_drive_path = source.get(1..2) == Some(":");
+    if is_drive_path || source.starts_with("\\\\") || source.starts_with('/') || source.starts_with('.') {
+        return Some(PathBuf::from(source));
+    }
+    None
+}
+
+/// Undo %XX escapes in a URL component ('+' stays a plus)
+pub fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
         }
     }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Name of the --config profile (the file name without extension), None for the default config
+#[cfg(feature = "gui")]
+pub fn profile_name() -> Option<String> {
+    CONFIG_PATH_OVERRIDE.get()
+        .and_then(|path| path.file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
 }
 
 impl Config {
-    /// Get the config file path in AppData
+    /// Use a specific config file instead of %APPDATA%\Xray-VPN-Manager\config.json.
+    /// Must be called before the first load/save; later calls are ignored.
+    pub fn set_config_path(path: PathBuf) {
+        let _ = CONFIG_PATH_OVERRIDE.set(path);
+    }
+    
+    /// Get the config file path in AppData (or the one given with --config)
     pub fn get_config_path() -> Result<PathBuf, String> {
+        if let Some(path) = CONFIG_PATH_OVERRIDE.get() {
+            if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty() && !d.exists()) {
+                fs::create_dir_all(dir)
+                    .map_err(|e| format!("Failed to create config directory: {}", e))?;
+            }
+            return Ok(path.clone());
+        }
+        
         // Get AppData\Roaming path
         let appdata = std::env::var("APPDATA")
             .map_err(|_| "Failed to get APPDATA environment variable".to_string())?;
@@ -67,12 +972,76 @@ impl Config {
         let content = fs::read_to_string(&config_path)
             .map_err(|e| format!("Failed to read config file: {}", e))?;
         
-        let config: Config = serde_json::from_str(&content)
+        let mut config: Config = serde_json::from_str(&content)
             .map_err(|e| format!("Failed to parse config JSON: {}", e))?;
         
+        // Migrate the old single subscription URL
+        if !config.subscription_url.is_empty() {
+            if config.subscriptions.is_empty() {
+                config.subscriptions.push(Subscription {
+                    name: "Default".to_string(),
+                    url: std::mem::take(&mut config.subscription_url),
+                    include: String::new(),
+                    exclude: String::new(),
+                });
+            } else {
+                config.subscription_url.clear();
+            }
+        }
+        
         Ok(config)
     }
     
+    /// True if there is anything to load servers from: a subscription or a manual server
+    pub fn has_server_sources(&self) -> bool {
+        !self.subscriptions.is_empty() || !self.manual_servers.is_empty()
+    }
+    
+    /// First port to hand out to servers without saved settings
+    pub fn first_local_port(&self) -> u16 {
+        if self.shared_machine { shared_port_base() } else { DEFAULT_FIRST_LOCAL_PORT }
+    }
+    
+    /// Move server (SOCKS/HTTP and the HTTP port of "Both"), primary and API ports
+    /// into this Windows user's block, keeping the servers in their current port
+    /// order. Refuses, changing nothing, when the servers don't fit below the
+    /// primary and API ports at the top of the block.
+    #[cfg(feature = "gui")]
+    pub fn move_ports_to_user_block(&mut self) -> Result<(), String> {
+        let base = shared_port_base();
+        let server_ports = SHARED_PORT_BLOCK - 2;
+        let mut keys: Vec<String> = self.server_settings.iter()
+            .filter(|(_, settings)| !settings.auto_port)
+            .map(|(key, _)| key.clone())
+            .collect();
+        let needed: usize = keys.iter()
+            .map(|key| if self.server_settings[key].proxy_type == "Both" { 2 } else { 1 })
+            .sum();
+        if needed > server_ports as usize {
+            return Err(format!(
+                "Servers need {} ports but a shared machine block has room for {}. \
+                 Set some servers' port to \"auto\" or remove servers, then try again.",
+                needed, server_ports
+            ));
+        }
+        keys.sort_by_key(|key| self.server_settings[key].local_port);
+        let mut next = base;
+        for key in &keys {
+            if let Some(settings) = self.server_settings.get_mut(key) {
+                settings.local_port = next;
+                next += 1;
+                if settings.proxy_type == "Both" {
+                    settings.http_port = next;
+                    next += 1;
+                }
+            }
+        }
+        // Top of the block, above every server port
+        self.primary.port = base + server_ports;
+        self.api.port = base + server_ports + 1;
+        Ok(())
+    }
+    
     /// Save config to AppData
     pub fn save(&self) -> Result<(), String> {
         let config_path = Self::get_config_path()?;
@@ -87,11 +1056,12 @@ impl Config {
     }
     
     /// Set autostart in Windows registry
+    #[cfg(feature = "gui")]
     pub fn set_autostart(enabled: bool) -> Result<(), String> {
         #[cfg(windows)]
         {
             use windows::{
-                core::w,
+                core::{w, PCWSTR},
                 Win32::System::Registry::{
                     RegOpenKeyExW, RegSetValueExW, RegDeleteKeyValueW,
                     HKEY_CURRENT_USER, KEY_WRITE, REG_SZ,
@@ -99,7 +1069,13 @@ impl Config {
             };
             
             let key_path = w!("Software\\Microsoft\\Windows\\CurrentVersion\\Run");
-            let value_name = w!("Xray-VPN-Manager");
+            // One Run entry per profile so several --config profiles can autostart side by side
+            let value_name_str = match CONFIG_PATH_OVERRIDE.get().and_then(|p| p.file_stem()) {
+                Some(stem) => format!("Xray-VPN-Manager ({})", stem.to_string_lossy()),
+                None => "Xray-VPN-Manager".to_string(),
+            };
+            let value_name_wide: Vec<u16> = format!("{}\0", value_name_str).encode_utf16().collect();
+            let value_name = PCWSTR::from_raw(value_name_wide.as_ptr());
             
             unsafe {
                 let mut hkey = Default::default();
@@ -114,7 +1090,15 @@ impl Config {
                         .map_err(|e| format!("Failed to get exe path: {}", e))?;
                     let exe_path_str = exe_path.to_string_lossy().to_string();
                     
-                    let path_wide: Vec<u16> = format!("{}\0", exe_path_str).encode_utf16().collect();
+                    // Start the same profile: pass --config for a custom or shared-machine setup
+                    let shared_machine = Self::load().map(|c| c.shared_machine).unwrap_or(false);
+                    let command = if CONFIG_PATH_OVERRIDE.get().is_some() || shared_machine {
+                        format!("\"{}\" --config \"{}\"", exe_path_str, Self::get_config_path()?.to_string_lossy())
+                    } else {
+                        exe_path_str
+                    };
+                    
+                    let path_wide: Vec<u16> = format!("{}\0", command).encode_utf16().collect();
                     let data = std::slice::from_raw_parts(
                         path_wide.as_ptr() as *const u8,
                         path_wide.len() * 2
@@ -135,13 +1119,13 @@ impl Config {
                     let _ = RegDeleteKeyValueW(hkey, None, value_name);
                 }
             }
+            Ok(())
         }
         
         #[cfg(not(windows))]
         {
-            return Err("Autostart only supported on Windows".to_string());
+            let _ = enabled;
+            Err("Autostart only supported on Windows".to_string())
         }
-        
-        Ok(())
     }
 }
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::net::UdpSocket;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// NTP servers queried in order until one answers
+const NTP_SERVERS: &[&str] = &["time.windows.com:123", "pool.ntp.org:123", "time.google.com:123"];
+
+// Seconds between 1900-01-01 (NTP epoch) and 1970-01-01 (Unix epoch)
+const NTP_UNIX_OFFSET: f64 = 2_208_988_800.0;
+
+// VMess rejects requests more than 90s off; warn a bit earlier
+const CLOCK_SKEW_THRESHOLD_SECS: f64 = 60.0;
+
+// Don't hit NTP on every failing log line
+const CLOCK_CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+// Log fragments that usually mean TLS or VMess auth failed because of the local clock
+const TIME_SENSITIVE_PATTERNS: &[&str] = &[
+    "invalid timestamp",
+    "bad timestamp",
+    "x509:",
+    "certificate has expired or is not yet valid",
+    "certificate is not valid",
+    "tls: failed to verify certificate",
+];
+
+// Human-readable hints per server key, shown in the tray and settings window
+static SERVER_HINTS: LazyLock<Mutex<HashMap<String, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Last clock check: when it ran and the measured offset (None if NTP was unreachable)
+type ClockCheck = (Instant, Option<f64>);
+static LAST_CLOCK_CHECK: LazyLock<Mutex<Option<ClockCheck>>> =
+    LazyLock::new(|| Mutex::new(None));
+
+/// Set a diagnostic hint for a server
+pub fn set_hint(server_key: &str, hint: &str) {
+    if let Ok(mut hints) = SERVER_HINTS.lock() {
+        let changed = hints.get(server_key).map(|h| h != hint).unwrap_or(true);
+        hints.insert(server_key.to_string(), hint.to_string());
+        if changed {
+            println!("Diagnostic for {}: {}", server_key, hint);
+            crate::request_menu_update();
+        }
+    }
+}
+
+/// Get the diagnostic hint for a server, if any
+#[cfg(any(feature = "gui", feature = "egui"))]
+pub fn get_hint(server_key: &str) -> Option<String> {
+    SERVER_HINTS.lock().ok()?.get(server_key).cloned()
+}
+
+/// Get all current hints as (server key, hint)
+pub fn get_all_hints() -> Vec<(String, String)> {
+    if let Ok(hints) = SERVER_HINTS.lock() {
+        let mut all: Vec<(String, String)> = hints.iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        all.sort();
+        all
+    } else {
+        Vec::new()
+    }
+}
+
+/// Remove the diagnostic hint for a server
+pub fn clear_hint(server_key: &str) {
+    if let Ok(mut hints) = SERVER_HINTS.lock() {
+        if hints.remove(server_key).is_some() {
+            crate::request_menu_update();
+        }
+    }
+}
+
+/// Remove all hints (before restarting every server)
+pub fn clear_all_hints() {
+    if let Ok(mut hints) = SERVER_HINTS.lock() {
+        hints.clear();
+    }
+}
+
+/// Look for known failure patterns in a line of xray output
+pub fn inspect_log_line(server_key: &str, line: &str) {
+    let lower = line.to_lowercase();
+    if TIME_SENSITIVE_PATTERNS.iter().any(|p| lower.contains(p)) {
+        // NTP may take seconds; don't stall the output reader
+        let server_key = server_key.to_string();
+        std::thread::spawn(move || {
+            if let Some(offset) = clock_offset_cached() {
+                if offset.abs() >= CLOCK_SKEW_THRESHOLD_SECS {
+                    set_hint(&server_key, &format_clock_hint(offset));
+                }
+            }
+        });
+    }
+}
+
+/// Map captured xray output from a failed start to a readable message with a suggested fix.
+/// Falls back to the last error-looking line when nothing known matches.
+pub fn translate_startup_error(lines: &[String], local_port: u16) -> Option<String> {
+    let find = |patterns: &[&str]| -> Option<&String> {
+        lines.iter().find(|line| {
+            let lower = line.to_lowercase();
+            patterns.iter().any(|p| lower.contains(p))
+        })
+    };
+
+    if find(&["address already in use", "only one usage of each socket address"]).is_some() {
+        return Some(crate::ports::describe_conflict(local_port));
+    }
+    if find(&["invalid uuid", "invalid user id", "failed to parse id", "invalid id"]).is_some() {
+        return Some("Server UUID is invalid - refresh the subscription or check the server entry".to_string());
+    }
+    if let Some(line) = find(&["unknown field"]) {
+        let field = line.split("unknown field").nth(1)
+            .map(|rest| rest.trim().trim_matches(|c: char| c == '"' || c == '\'' || c == ':').to_string())
+            .unwrap_or_default();
+        return Some(format!(
+            "xray doesn't recognize config field {} - update xray to the latest version",
+            if field.is_empty() { "(unknown)".to_string() } else { field }
+        ));
+    }
+    if find(&["geosite.dat", "geoip.dat", "failed to load geosite", "failed to load geoip"]).is_some() {
+        return Some("Missing geosite.dat/geoip.dat - download them with Tools → Geo Assets".to_string());
+    }
+    if find(&["permission denied", "access is denied"]).is_some() {
+        return Some("xray was denied access - check antivirus or run from a folder you own".to_string());
+    }
+
+    // Nothing known; show xray's own last complaint
+    lines.iter().rev()
+        .find(|line| {
+            let lower = line.to_lowercase();
+            lower.contains("failed") || lower.contains("error") || lower.contains("panic")
+        })
+        .map(|line| format!("xray: {}", line.trim()))
+}
+
+// Clock offset in seconds (positive = local clock is behind), re-measured at most every CLOCK_CHECK_INTERVAL
+fn clock_offset_cached() -> Option<f64> {
+    let mut last = LAST_CLOCK_CHECK.lock().ok()?;
+    if let Some((checked_at, offset)) = *last {
+        if checked_at.elapsed() < CLOCK_CHECK_INTERVAL {
+            return offset;
+        }
+    }
+
+    let offset = match query_clock_offset() {
+        Ok(offset) => Some(offset),
+        Err(e) => {
+            eprintln!("Clock check failed: {}", e);
+            None
+        }
+    };
+    *last = Some((Instant::now(), offset));
+    offset
+}
+
+/// Measure the local clock offset against NTP, in seconds.
+/// Positive means the local clock is behind real time.
+pub fn query_clock_offset() -> Result<f64, String> {
+    let mut last_error = String::from("no NTP servers configured");
+    for server in NTP_SERVERS {
+        match query_ntp(server) {
+            Ok(offset) => return Ok(offset),
+            Err(e) => last_error = format!("{}: {}", server, e),
+        }
+    }
+    Err(last_error)
+}
+
+// Single SNTP (RFC 4330) request
+fn query_ntp(server: &str) -> Result<f64, String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+    socket.set_read_timeout(Some(Duration::from_secs(3))).map_err(|e| e.to_string())?;
+
+    // LI = 0, VN = 3, Mode = 3 (client)
+    let mut packet = [0u8; 48];
+    packet[0] = 0x1B;
+
+    let sent_at = unix_time_f64();
+    socket.send_to(&packet, server).map_err(|e| e.to_string())?;
+    let (len, _) = socket.recv_from(&mut packet).map_err(|e| e.to_string())?;
+    let received_at = unix_time_f64();
+    if len < 48 {
+        return Err("short NTP response".to_string());
+    }
+
+    // Transmit timestamp: seconds + fraction since 1900
+    let seconds = u32::from_be_bytes([packet[40], packet[41], packet[42], packet[43]]) as f64;
+    let fraction = u32::from_be_bytes([packet[44], packet[45], packet[46], packet[47]]) as f64 / 4_294_967_296.0;
+    if seconds == 0.0 {
+        return Err("NTP server sent no timestamp".to_string());
+    }
+    let server_time = seconds + fraction - NTP_UNIX_OFFSET;
+
+    // Compare against the midpoint of the round trip
+    Ok(server_time - (sent_at + received_at) / 2.0)
+}
+
+fn unix_time_f64() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// "Your clock is 6 minutes behind" style hint for a clock offset
+pub fn format_clock_hint(offset: f64) -> String {
+    let direction = if offset > 0.0 { "behind" } else { "ahead" };
+    let secs = offset.abs().round() as u64;
+    let amount = if secs >= 120 {
+        format!("{} minutes", (secs + 30) / 60)
+    } else {
+        format!("{} seconds", secs)
+    };
+    format!("Your clock is {} {} - sync Windows time (TLS/VMess need accurate time)", amount, direction)
+}
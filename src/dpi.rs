@@ -0,0 +1,80 @@
+use serde_json::json;
+
+use crate::config::ServerSettings;
+
+// DPI evasion per server (server context menu → TLS Fingerprint && Fragment...):
+// the uTLS fingerprint the TLS or REALITY handshake imitates, overriding the
+// link's `fp`, and fragmenting the first packets to the server. Fragmenting
+// goes through a freedom outbound tagged "fragment" that the proxy outbound
+// dials through (`sockopt.dialerProxy`). Only xray configs are changed.
+
+/// Fingerprints xray's uTLS knows
+#[cfg(feature = "gui")]
+pub const FINGERPRINTS: [&str; 9] = ["chrome", "firefox", "safari", "ios", "android", "edge", "360", "qq", "random"];
+
+// Tag of the freedom outbound doing the fragmenting
+const FRAGMENT_TAG: &str = "fragment";
+
+/// Check a fragment setting: "tlshello" (packets only), a number or a range "100-200"
+#[cfg(feature = "gui")]
+pub fn validate_fragment(fragment: &crate::config::FragmentSettings) -> Result<(), String> {
+    let range = |text: &str| {
+        let mut bounds = text.trim().splitn(2, '-').map(|bound| bound.trim().parse::<u32>());
+        match (bounds.next(), bounds.next()) {
+            (Some(Ok(low)), None) => low > 0,
+            (Some(Ok(low)), Some(Ok(high))) => low > 0 && low <= high,
+            _ => false,
+        }
+    };
+    if fragment.packets.trim() != "tlshello" && !range(&fragment.packets) {
+        return Err("Packets must be \"tlshello\" or a range of writes like 1-3".to_string());
+    }
+    if !range(&fragment.length) {
+        return Err("Length must be a number of bytes or a range like 100-200".to_string());
+    }
+    // A zero interval is allowed: all fragments at once
+    if fragment.interval.trim() != "0" && !range(&fragment.interval) {
+        return Err("Interval must be milliseconds or a range like 10-20".to_string());
+    }
+    Ok(())
+}
+
+/// Set the fingerprint and fragmenting of the generated xray config's proxy outbound
+pub fn apply(config_json: &str, settings: Option<&ServerSettings>) -> Result<String, String> {
+    let Some(settings) = settings.filter(|s| !s.fingerprint.is_empty() || s.fragment.is_some()) else {
+        return Ok(config_json.to_string());
+    };
+    let mut config: serde_json::Value = serde_json::from_str(config_json)
+        .map_err(|e| format!("Failed to read generated xray config: {}", e))?;
+    let Some(outbounds) = config["outbounds"].as_array_mut() else {
+        return Err("Generated xray config has no outbounds".to_string());
+    };
+    let Some(outbound) = outbounds.first_mut() else {
+        return Err("Generated xray config has no outbounds".to_string());
+    };
+
+    if !settings.fingerprint.is_empty() {
+        let stream = &mut outbound["streamSettings"];
+        match stream["security"].as_str() {
+            Some("tls") => stream["tlsSettings"]["fingerprint"] = json!(settings.fingerprint),
+            Some("reality") => stream["realitySettings"]["fingerprint"] = json!(settings.fingerprint),
+            // Nothing to imitate without TLS
+            _ => {}
+        }
+    }
+    if let Some(fragment) = &settings.fragment {
+        outbound["streamSettings"]["sockopt"]["dialerProxy"] = json!(FRAGMENT_TAG);
+        outbounds.push(json!({
+            "tag": FRAGMENT_TAG,
+            "protocol": "freedom",
+            "settings": {
+                "fragment": {
+                    "packets": fragment.packets.trim(),
+                    "length": fragment.length.trim(),
+                    "interval": fragment.interval.trim(),
+                },
+            },
+        }));
+    }
+    serde_json::to_string_pretty(&config).map_err(|e| format!("Failed to write xray config: {}", e))
+}
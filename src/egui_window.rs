@@ -0,0 +1,292 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use eframe::egui;
+
+use crate::config::{Config, Subscription};
+use crate::vpn::VpnServer;
+
+// Cross-platform settings window (feature `egui`, builds without `gui`): the part of
+// the Win32 settings window a Linux/macOS user needs to run the manager. Xray path,
+// subscriptions, and the server table with enable, port and proxy type, Start/Stop,
+// Save. Loading and saving go through settings_model, like the Win32 window, so both
+// read and write the config the same way. The window is the app here: closing it
+// stops the servers. There is no tray in this build yet.
+
+const PROXY_TYPES: [&str; 3] = ["SOCKS", "HTTP", "Both"];
+
+static OPEN: AtomicBool = AtomicBool::new(false);
+
+/// Whether the settings window is showing
+pub fn is_open() -> bool {
+    OPEN.load(Ordering::Relaxed)
+}
+
+/// Open the settings window and return when it's closed. Err when no window can be
+/// shown (no display), so the caller can keep serving headless.
+pub fn run() -> Result<(), String> {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("VPN Manager")
+            .with_inner_size([980.0, 640.0])
+            .with_min_inner_size([640.0, 400.0]),
+        ..Default::default()
+    };
+    OPEN.store(true, Ordering::Relaxed);
+    let result = eframe::run_native("VPN Manager", options, Box::new(|cc| Ok(Box::new(SettingsApp::new(&cc.egui_ctx)))));
+    OPEN.store(false, Ordering::Relaxed);
+    result.map_err(|e| e.to_string())
+}
+
+// What background work reports back to the window
+enum Update {
+    Servers(Vec<VpnServer>),
+    Message(String),
+}
+
+struct SettingsApp {
+    xray_binary_path: String,
+    subscriptions: Vec<Subscription>,
+    new_subscription: String,
+    servers: Vec<VpnServer>, // Edited rows; saved with Save
+    filter: String,
+    loading: bool,
+    message: String,
+    updates: Receiver<Update>,
+    sender: Sender<Update>,
+    ctx: egui::Context,
+}
+
+impl SettingsApp {
+    fn new(ctx: &egui::Context) -> SettingsApp {
+        let config = Config::load().unwrap_or_default();
+        let (sender, updates) = channel();
+        let mut app = SettingsApp {
+            xray_binary_path: config.xray_binary_path.clone(),
+            subscriptions: config.subscriptions.clone(),
+            new_subscription: String::new(),
+            servers: Vec::new(),
+            filter: String::new(),
+            loading: false,
+            message: String::new(),
+            updates,
+            sender,
+            ctx: ctx.clone(),
+        };
+        if config.has_server_sources() {
+            app.load_servers();
+        }
+        app
+    }
+
+    // Run `work` on a background thread and show what it reports
+    fn in_background(&self, work: impl FnOnce() -> Update + Send + 'static) {
+        let (sender, ctx) = (self.sender.clone(), self.ctx.clone());
+        std::thread::spawn(move || {
+            let _ = sender.send(work());
+            ctx.request_repaint();
+        });
+    }
+
+    fn load_servers(&mut self) {
+        self.loading = true;
+        let subscriptions = self.subscriptions.clone();
+        self.in_background(move || Update::Servers(crate::settings_model::load_server_list(&subscriptions)));
+    }
+
+    fn add_subscription(&mut self) {
+        let url = self.new_subscription.trim().to_string();
+        let is_web = url.starts_with("http://") || url.starts_with("https://");
+        if !is_web && crate::config::local_subscription_path(&url).is_none() {
+            self.message = "Enter an http:// or https:// URL, a file:// URL or a file path.".to_string();
+            return;
+        }
+        let name = match crate::config::local_subscription_path(&url) {
+            Some(path) => path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_else(|| "Local file".to_string()),
+            None => url.split("://").nth(1)
+                .and_then(|rest| rest.split(['/', '?', ':']).next())
+                .filter(|host| !host.is_empty())
+                .unwrap_or("Subscription")
+                .to_string(),
+        };
+        self.subscriptions.push(Subscription { name, url, include: String::new(), exclude: String::new() });
+        self.new_subscription.clear();
+        self.save_subscriptions();
+    }
+
+    // Subscriptions are saved right away, as in the Win32 Subscriptions window
+    fn save_subscriptions(&mut self) {
+        let mut config = Config::load().unwrap_or_default();
+        config.subscriptions = self.subscriptions.clone();
+        match config.save() {
+            Ok(()) => self.load_servers(),
+            Err(e) => self.message = e,
+        }
+    }
+
+    fn save(&mut self) {
+        if let Some((_, description)) = crate::settings_model::shared_ports(&self.servers) {
+            self.message = format!("{}. Give each enabled server its own port, or untick one of them.", description);
+            return;
+        }
+        let mut config = Config::load().unwrap_or_default();
+        crate::settings_model::store_rows(&mut config, &self.servers);
+        config.xray_binary_path = self.xray_binary_path.trim().to_string();
+        if let Err(e) = config.save() {
+            self.message = e;
+            return;
+        }
+        let (_, taken) = crate::settings_model::taken_ports(&self.servers);
+        self.message = "Saved, applying...".to_string();
+        self.in_background(move || {
+            let summary = crate::restart_xray_servers();
+            let mut text = format!("Saved. {} started, {} unchanged.", summary.started.len(), summary.kept.len());
+            if !summary.failed.is_empty() {
+                text.push_str(&format!("\n{}", summary.failure_text()));
+            }
+            if !taken.is_empty() {
+                text.push_str(&format!("\nPorts in use: {}", taken.join("; ")));
+            }
+            Update::Message(text)
+        });
+    }
+
+    fn toggle_server(&mut self, server: &VpnServer) {
+        let key = server.get_server_key();
+        let name = server.display_name().to_string();
+        let running = crate::xray_manager::get_server_info(&key).is_some();
+        self.in_background(move || {
+            let result = if running { crate::stop_single_server(&key) } else { crate::start_single_server(&key) };
+            Update::Message(match (result, running) {
+                (Ok(()), true) => format!("Stopped {}.", name),
+                (Ok(()), false) => format!("Started {}.", name),
+                (Err(e), _) => format!("{}: {}", name, e),
+            })
+        });
+    }
+
+    fn server_table(&mut self, ui: &mut egui::Ui) {
+        let words: Vec<String> = self.filter.split_whitespace().map(str::to_lowercase).collect();
+        let saved = Config::load().unwrap_or_default().server_settings;
+        let mut toggle = None;
+        egui::Grid::new("servers").striped(true).num_columns(8).show(ui, |ui| {
+            for heading in ["On", "Server", "Protocol", "Address", "Port", "Type", "Status", ""] {
+                ui.strong(heading);
+            }
+            ui.end_row();
+            for server in self.servers.iter_mut().filter(|server| !server.hidden) {
+                let text = format!("{} {} {} {}", server.display_name(), server.note, server.address, server.protocol).to_lowercase();
+                if !words.iter().all(|word| text.contains(word)) {
+                    continue;
+                }
+                let key = server.get_server_key();
+                ui.checkbox(&mut server.enabled, "");
+                ui.label(server.display_name());
+                ui.label(&server.protocol);
+                ui.label(format!("{}:{}", server.address, server.port));
+                ui.horizontal(|ui| {
+                    if server.auto_port {
+                        ui.label("auto");
+                    } else {
+                        ui.add(egui::DragValue::new(&mut server.local_port).range(1..=65535));
+                        if server.proxy_type == "Both" {
+                            ui.label("HTTP");
+                            ui.add(egui::DragValue::new(&mut server.http_port).range(1..=65535));
+                        }
+                    }
+                });
+                egui::ComboBox::from_id_salt(("type", &key))
+                    .selected_text(server.proxy_type.clone())
+                    .show_ui(ui, |ui| {
+                        for proxy_type in PROXY_TYPES {
+                            ui.selectable_value(&mut server.proxy_type, proxy_type.to_string(), proxy_type);
+                        }
+                    });
+                let running = crate::xray_manager::get_server_info(&key).is_some();
+                let hint = crate::diagnostics::get_hint(&key).unwrap_or_default();
+                ui.label(if running { "Running".to_string() } else { hint });
+                let saved_server = saved.contains_key(&key);
+                let button = ui.add_enabled(running || saved_server, egui::Button::new(if running { "Stop" } else { "Start" }));
+                if button.on_disabled_hover_text("Save first").clicked() {
+                    toggle = Some(server.clone());
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(server) = toggle {
+            self.toggle_server(&server);
+        }
+    }
+}
+
+impl eframe::App for SettingsApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        while let Ok(update) = self.updates.try_recv() {
+            match update {
+                Update::Servers(servers) => {
+                    self.loading = false;
+                    self.message = format!("{} servers", servers.len());
+                    self.servers = servers;
+                }
+                Update::Message(text) => self.message = text,
+            }
+        }
+
+        egui::TopBottomPanel::top("sources").show(ctx, |ui| {
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.label("Xray binary:");
+                ui.add(egui::TextEdit::singleline(&mut self.xray_binary_path).desired_width(f32::INFINITY));
+            });
+            ui.label("Subscriptions:");
+            let mut remove = None;
+            for (index, subscription) in self.subscriptions.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.small_button("Remove").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.label(format!("{}  —  {}", subscription.name, subscription.url));
+                });
+            }
+            if let Some(index) = remove {
+                self.subscriptions.remove(index);
+                self.save_subscriptions();
+            }
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.new_subscription).hint_text("https://... or a file path"));
+                if ui.button("Add").clicked() {
+                    self.add_subscription();
+                }
+                if ui.add_enabled(!self.loading, egui::Button::new("Update")).clicked() {
+                    self.load_servers();
+                }
+                if self.loading {
+                    ui.spinner();
+                }
+            });
+            ui.add_space(4.0);
+        });
+
+        egui::TopBottomPanel::bottom("actions").show(ctx, |ui| {
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    self.save();
+                }
+                ui.label(&self.message);
+            });
+            ui.add_space(4.0);
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Filter:");
+                ui.text_edit_singleline(&mut self.filter);
+            });
+            egui::ScrollArea::both().show(ui, |ui| self.server_table(ui));
+        });
+
+        // Running state and hints change in the background
+        ctx.request_repaint_after(std::time::Duration::from_secs(2));
+    }
+}
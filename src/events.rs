@@ -0,0 +1,58 @@
+use std::sync::Mutex;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use serde::Serialize;
+
+/// Server details attached to lifecycle events
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerInfo {
+    pub key: String,
+    pub name: String,
+    pub local_port: u16,
+    pub proxy_type: String,
+    pub http_port: u16, // The HTTP inbound with proxy_type "Both"; 0 otherwise
+}
+
+/// Internal application events
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    ServerStarted(ServerInfo),
+    ServerStopped(ServerInfo),
+    ServerCrashed(ServerInfo), // xray exited without being stopped
+    SubscriptionRefreshed { server_count: usize, added: usize, removed: usize, changed: usize }, // Changes against the previous list
+    PrimaryChanged(ServerInfo), // Primary port now relays to this server
+    ExitIpChanged { server: ServerInfo, previous: String, current: String },
+}
+
+impl Event {
+    /// Event name as used in the serialized "event" field
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::ServerStarted(_) => "server_started",
+            Event::ServerStopped(_) => "server_stopped",
+            Event::ServerCrashed(_) => "server_crashed",
+            Event::SubscriptionRefreshed { .. } => "subscription_refreshed",
+            Event::PrimaryChanged(_) => "primary_changed",
+            Event::ExitIpChanged { .. } => "exit_ip_changed",
+        }
+    }
+}
+
+// Subscribers receive a copy of every event; dropped receivers are pruned on emit
+static SUBSCRIBERS: Mutex<Vec<Sender<Event>>> = Mutex::new(Vec::new());
+
+/// Subscribe to all future events
+pub fn subscribe() -> Receiver<Event> {
+    let (tx, rx) = channel();
+    if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+        subscribers.push(tx);
+    }
+    rx
+}
+
+/// Publish an event to all subscribers (can be called from any thread)
+pub fn emit(event: Event) {
+    if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+        subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+}
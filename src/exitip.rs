@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::events::{self, Event, ServerInfo};
+
+// Exit IP watch: every `exit_ip.interval_minutes` each running server fetches
+// `exit_ip.url` through its own proxy, and the public IP it answers with is kept
+// in exit-ips.json next to the config. A server key pins the server's address,
+// so a different exit IP for the same key is unexpected: the provider rerouted
+// the server or its DNS name now points elsewhere. That raises ExitIpChanged
+// and, with `exit_ip.alert`, a warning. Balanced groups are skipped, since
+// their exit IP follows whichever member xray picks.
+
+/// Last exit IP seen for a server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObservedIp {
+    pub ip: String,
+    pub first_seen: u64, // Unix seconds this IP was first seen
+    pub last_seen: u64,
+    #[serde(default)]
+    pub previous: Option<String>, // IP seen before the last change
+}
+
+// Server key -> last observed IP; loaded from disk on first use
+static OBSERVED: LazyLock<Mutex<HashMap<String, ObservedIp>>> = LazyLock::new(|| Mutex::new(load()));
+
+fn cache_path() -> Option<PathBuf> {
+    let config_path = crate::config::Config::get_config_path().ok()?;
+    Some(config_path.parent()?.join("exit-ips.json"))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn load() -> HashMap<String, ObservedIp> {
+    cache_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(observed: &HashMap<String, ObservedIp>) {
+    let Some(path) = cache_path() else {
+        return;
+    };
+    match serde_json::to_string_pretty(observed) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                eprintln!("Failed to save exit IPs: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize exit IPs: {}", e),
+    }
+}
+
+/// Last exit IP seen for a server, if it was ever checked
+#[cfg(any(feature = "gui", feature = "api"))]
+pub fn observed(server_key: &str) -> Option<ObservedIp> {
+    OBSERVED.lock().ok()?.get(server_key).cloned()
+}
+
+/// Ask the lookup URL which IP a running server exits from
+pub fn lookup(info: &ServerInfo, url: &str, timeout: Duration) -> Result<IpAddr, String> {
+    let client = crate::probe::proxied_client(info.local_port, &info.proxy_type, timeout)?;
+    let response = client.get(url).send()
+        .map_err(|e| format!("No response through proxy: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned HTTP {}", url, response.status().as_u16()));
+    }
+    let body = response.text().map_err(|e| format!("Failed to read the answer: {}", e))?;
+    body.trim().parse()
+        .map_err(|_| format!("{} didn't answer with an IP address", url))
+}
+
+/// Look up a running server's exit IP and remember it. Emits ExitIpChanged when it
+/// differs from the one seen before.
+pub fn check_server(server_key: &str) -> Result<IpAddr, String> {
+    let info = crate::xray_manager::get_server_info(server_key)
+        .ok_or_else(|| "server is not running".to_string())?;
+    let config = crate::config::Config::load().unwrap_or_default();
+    let ip = lookup(&info, &config.exit_ip.url, Duration::from_secs(config.test.timeout_secs.max(1)))?;
+    let current = ip.to_string();
+    let now = now_secs();
+
+    let mut changed_from = None;
+    if let Ok(mut observed) = OBSERVED.lock() {
+        match observed.get_mut(server_key) {
+            Some(entry) if entry.ip == current => entry.last_seen = now,
+            Some(entry) => {
+                changed_from = Some(entry.ip.clone());
+                *entry = ObservedIp { ip: current.clone(), first_seen: now, last_seen: now, previous: Some(entry.ip.clone()) };
+            }
+            None => {
+                println!("{} exits from {}", info.name, current);
+                observed.insert(server_key.to_string(), ObservedIp { ip: current.clone(), first_seen: now, last_seen: now, previous: None });
+            }
+        }
+        save(&observed);
+    }
+
+    if let Some(previous) = changed_from {
+        eprintln!("Exit IP of {} changed from {} to {}", info.name, previous, current);
+        if config.exit_ip.alert {
+            alert(&info.name, &previous, &current);
+        }
+        events::emit(Event::ExitIpChanged { server: info, previous, current });
+    }
+    Ok(ip)
+}
+
+// Warn without blocking the checks; one box per change
+fn alert(name: &str, previous: &str, current: &str) {
+    #[cfg(all(windows, feature = "gui"))]
+    {
+        use windows::Win32::Foundation::HWND;
+        use windows::Win32::UI::WindowsAndMessaging::{MB_ICONWARNING, MB_OK};
+
+        let text = format!(
+            "{} now exits from {} instead of {}.\n\n\
+             Its address didn't change, so the provider may be routing it differently, \
+             or its DNS name may point somewhere else. Check the server before trusting it \
+             with anything sensitive.",
+            name, current, previous
+        );
+        std::thread::spawn(move || unsafe {
+            crate::ui::controls::message_box(HWND::default(), &text, "Exit IP Changed", MB_OK | MB_ICONWARNING);
+        });
+    }
+    #[cfg(not(all(windows, feature = "gui")))]
+    let _ = (name, previous, current);
+}
+
+/// Periodically check the exit IP of running servers while `exit_ip.enabled` is on
+pub fn start_exit_ip_monitor() {
+    std::thread::spawn(|| loop {
+        // Re-read each round so the settings apply without restart
+        let settings = crate::config::Config::load().unwrap_or_default().exit_ip;
+        crate::idle::sleep(Duration::from_secs(u64::from(settings.interval_minutes.max(1)) * 60));
+        if !settings.enabled {
+            continue;
+        }
+        for server_key in crate::xray_manager::get_running_servers() {
+            if crate::balancer::is_group_key(&server_key) {
+                continue;
+            }
+            if let Err(e) = check_server(&server_key) {
+                eprintln!("Exit IP check of {} failed: {}", crate::vpn::get_server_name(&server_key), e);
+            }
+        }
+    });
+}
@@ -0,0 +1,105 @@
+use serde::Serialize;
+
+/// One row of the exported server table
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerRow {
+    pub name: String,
+    pub protocol: String,
+    pub address: String,
+    pub port: u16,
+    pub transport: String,
+    pub local_port: u16,
+    pub proxy_type: String,
+    pub enabled: bool,
+    pub latency_ms: Option<u64>, // None until the server has been measured
+    pub traffic_bytes: Option<u64>, // None when xray stats aren't available
+    pub status: String, // "running", "stopped", "disabled" or "error: <hint>"
+}
+
+/// Snapshot the current server list with runtime state
+pub fn collect_rows() -> Vec<ServerRow> {
+    let running = crate::xray_manager::get_running_servers();
+    let mut rows = Vec::new();
+
+    if let Ok(global_servers) = crate::vpn::VPN_SERVERS.lock() {
+        if let Some(servers) = global_servers.as_ref() {
+            for server in servers {
+                let key = server.get_server_key();
+                let status = if let Some(hint) = crate::diagnostics::get_hint(&key) {
+                    format!("error: {}", hint)
+                } else if running.contains(&key) {
+                    "running".to_string()
+                } else if server.enabled {
+                    "stopped".to_string()
+                } else {
+                    "disabled".to_string()
+                };
+
+                rows.push(ServerRow {
+                    name: server.name.clone(),
+                    protocol: server.protocol.clone(),
+                    address: server.address.clone(),
+                    port: server.port,
+                    transport: server.transport.clone(),
+                    local_port: server.local_port,
+                    proxy_type: server.proxy_type.clone(),
+                    enabled: server.enabled,
+                    latency_ms: crate::health::get_summary(&key)
+                        .and_then(|h| h.avg_latency_ms)
+                        .map(|l| l as u64),
+                    traffic_bytes: None,
+                    status,
+                });
+            }
+        }
+    }
+
+    rows
+}
+
+/// Render rows as CSV with a header line
+pub fn to_csv(rows: &[ServerRow]) -> String {
+    let mut csv = String::from("name,protocol,address,port,transport,local_port,proxy_type,enabled,latency_ms,traffic_bytes,status\r\n");
+    for row in rows {
+        let fields = [
+            csv_escape(&row.name),
+            csv_escape(&row.protocol),
+            csv_escape(&row.address),
+            row.port.to_string(),
+            csv_escape(&row.transport),
+            row.local_port.to_string(),
+            csv_escape(&row.proxy_type),
+            row.enabled.to_string(),
+            row.latency_ms.map(|v| v.to_string()).unwrap_or_default(),
+            row.traffic_bytes.map(|v| v.to_string()).unwrap_or_default(),
+            csv_escape(&row.status),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+// Quote a CSV field if it contains separators, quotes or line breaks
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Write the server table to a file; format is picked by extension (.json, otherwise CSV)
+pub fn export_to_file(path: &str) -> Result<usize, String> {
+    let rows = collect_rows();
+    let content = if path.to_lowercase().ends_with(".json") {
+        serde_json::to_string_pretty(&rows)
+            .map_err(|e| format!("Failed to serialize servers: {}", e))?
+    } else {
+        to_csv(&rows)
+    };
+
+    std::fs::write(path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    Ok(rows.len())
+}
@@ -0,0 +1,148 @@
+// Country detection from server names.
+// Subscriptions almost always label servers with a flag emoji, an ISO code
+// or the country name ("🇩🇪 Frankfurt", "[DE] Berlin 01", "Germany-2"),
+// which is good enough for picking "the fastest server in Germany".
+
+// ISO 3166 alpha-2 code -> English name, for the countries commonly seen in subscriptions
+const COUNTRIES: &[(&str, &str)] = &[
+    ("AE", "United Arab Emirates"),
+    ("AR", "Argentina"),
+    ("AT", "Austria"),
+    ("AU", "Australia"),
+    ("BE", "Belgium"),
+    ("BG", "Bulgaria"),
+    ("BR", "Brazil"),
+    ("CA", "Canada"),
+    ("CH", "Switzerland"),
+    ("CN", "China"),
+    ("CZ", "Czechia"),
+    ("DE", "Germany"),
+    ("DK", "Denmark"),
+    ("EE", "Estonia"),
+    ("ES", "Spain"),
+    ("FI", "Finland"),
+    ("FR", "France"),
+    ("GB", "United Kingdom"),
+    ("GE", "Georgia"),
+    ("HK", "Hong Kong"),
+    ("HU", "Hungary"),
+    ("IE", "Ireland"),
+    ("IL", "Israel"),
+    ("IN", "India"),
+    ("IS", "Iceland"),
+    ("IT", "Italy"),
+    ("JP", "Japan"),
+    ("KR", "South Korea"),
+    ("KZ", "Kazakhstan"),
+    ("LT", "Lithuania"),
+    ("LU", "Luxembourg"),
+    ("LV", "Latvia"),
+    ("MD", "Moldova"),
+    ("MX", "Mexico"),
+    ("MY", "Malaysia"),
+    ("NL", "Netherlands"),
+    ("NO", "Norway"),
+    ("PL", "Poland"),
+    ("PT", "Portugal"),
+    ("RO", "Romania"),
+    ("RS", "Serbia"),
+    ("RU", "Russia"),
+    ("SE", "Sweden"),
+    ("SG", "Singapore"),
+    ("TR", "Turkey"),
+    ("TW", "Taiwan"),
+    ("UA", "Ukraine"),
+    ("US", "United States"),
+    ("VN", "Vietnam"),
+    ("ZA", "South Africa"),
+];
+
+// Other spellings found in server names
+const ALIASES: &[(&str, &str)] = &[
+    ("UK", "GB"),
+    ("ENGLAND", "GB"),
+    ("BRITAIN", "GB"),
+    ("LONDON", "GB"),
+    ("USA", "US"),
+    ("AMERICA", "US"),
+    ("HOLLAND", "NL"),
+    ("AMSTERDAM", "NL"),
+    ("DEUTSCHLAND", "DE"),
+    ("FRANKFURT", "DE"),
+    ("CZECH", "CZ"),
+    ("KOREA", "KR"),
+    ("TURKIYE", "TR"),
+    ("EMIRATES", "AE"),
+    ("UAE", "AE"),
+    ("DUBAI", "AE"),
+    ("TOKYO", "JP"),
+    ("HONGKONG", "HK"),
+];
+
+/// Country code for a server name, if it names one
+pub fn country_from_name(name: &str) -> Option<String> {
+    flag_country(name)
+        .or_else(|| word_country(name))
+}
+
+/// English name for a country code (the code itself if unknown)
+#[cfg(feature = "gui")]
+pub fn country_name(code: &str) -> String {
+    COUNTRIES.iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| code.to_string())
+}
+
+// A flag emoji is two regional indicator symbols spelling the ISO code
+fn flag_country(name: &str) -> Option<String> {
+    const REGIONAL_A: u32 = 0x1F1E6;
+    let letters: Vec<char> = name.chars()
+        .map(|c| c as u32)
+        .skip_while(|c| !(REGIONAL_A..REGIONAL_A + 26).contains(c))
+        .take(2)
+        .filter(|c| (REGIONAL_A..REGIONAL_A + 26).contains(c))
+        .filter_map(|c| char::from_u32('A' as u32 + c - REGIONAL_A))
+        .collect();
+    if letters.len() == 2 {
+        Some(letters.into_iter().collect())
+    } else {
+        None
+    }
+}
+
+// Match whole words against country names, aliases and ISO codes
+fn word_country(name: &str) -> Option<String> {
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    // Full names first so "United States" isn't read as something shorter
+    let joined = words.join(" ").to_uppercase();
+    for (code, country) in COUNTRIES {
+        if contains_words(&joined, &country.to_uppercase()) {
+            return Some(code.to_string());
+        }
+    }
+
+    for word in &words {
+        // Strip trailing numbering like "DE1" or "US02"
+        let word = word.trim_end_matches(|c: char| c.is_ascii_digit());
+        let upper = word.to_uppercase();
+        if let Some((_, code)) = ALIASES.iter().find(|(alias, _)| *alias == upper) {
+            return Some(code.to_string());
+        }
+        // Codes only count when written in capitals: "IT" is Italy, "it" is a word
+        if word.len() == 2 && word == upper && COUNTRIES.iter().any(|(code, _)| *code == word) {
+            return Some(upper);
+        }
+    }
+
+    None
+}
+
+// True if `needle` appears in `haystack` on word boundaries
+fn contains_words(haystack: &str, needle: &str) -> bool {
+    format!(" {} ", haystack).contains(&format!(" {} ", needle))
+}
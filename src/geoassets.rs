@@ -0,0 +1,198 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::{Config, GeoAssetSettings};
+
+// GeoIP/geosite assets: the geoip.dat and geosite.dat files xray needs for
+// geoip:/geosite: routing entries. They are kept next to the xray binary, or in
+// <config dir>\assets when that folder isn't writable, fetched when missing and
+// updated from the latest release of `geo_assets.source`. xray is pointed at
+// them through XRAY_LOCATION_ASSET, so either place works.
+
+/// Files a release must provide
+pub const ASSET_FILES: [&str; 2] = ["geoip.dat", "geosite.dat"];
+
+/// Environment variable xray reads the asset folder from
+pub const ASSET_ENV: &str = "XRAY_LOCATION_ASSET";
+
+/// GitHub releases page the assets come from unless configured otherwise
+pub const DEFAULT_SOURCE: &str = "https://github.com/Loyalsoldier/v2ray-rules-dat/releases";
+
+// Release tag of the installed files, written next to them
+const VERSION_FILE: &str = "geo-assets.version";
+
+// Automatic update checks run at most this often
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Last automatic check, so the hourly timer only asks GitHub once a day
+static LAST_CHECK: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Folder holding both asset files for this xray binary, None if they are missing
+pub fn asset_dir(xray_binary_path: &str) -> Option<PathBuf> {
+    [binary_dir(xray_binary_path), fallback_dir()]
+        .into_iter()
+        .flatten()
+        .find(|dir| has_assets(dir))
+}
+
+fn binary_dir(xray_binary_path: &str) -> Option<PathBuf> {
+    if xray_binary_path.is_empty() {
+        return None;
+    }
+    Path::new(xray_binary_path).parent().map(Path::to_path_buf)
+}
+
+fn fallback_dir() -> Option<PathBuf> {
+    Some(Config::get_config_path().ok()?.parent()?.join("assets"))
+}
+
+fn has_assets(dir: &Path) -> bool {
+    ASSET_FILES.iter().all(|name| dir.join(name).is_file())
+}
+
+/// Release tag of the installed assets; None if they came with xray or were put there by hand
+pub fn installed_version(xray_binary_path: &str) -> Option<String> {
+    let dir = asset_dir(xray_binary_path)?;
+    let version = std::fs::read_to_string(dir.join(VERSION_FILE)).ok()?;
+    Some(version.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+// Tag of the latest release, read from the redirect of <source>/latest
+fn latest_version(source: &str) -> Result<String, String> {
+    let client = reqwest::blocking::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let response = client.get(format!("{}/latest", source.trim_end_matches('/')))
+        .send()
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+    let location = response.headers()
+        .get("location")
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| format!("{} has no latest release", source))?;
+    location.trim_end_matches('/').rsplit('/').next()
+        .filter(|tag| !tag.is_empty() && *tag != "latest")
+        .map(str::to_string)
+        .ok_or_else(|| format!("Unexpected release location: {}", location))
+}
+
+/// Download the latest assets unless the installed ones are that release.
+/// Returns the tag when files were replaced, None when they were up to date.
+pub fn update(xray_binary_path: &str, settings: &GeoAssetSettings) -> Result<Option<String>, String> {
+    let source = if settings.source.trim().is_empty() { DEFAULT_SOURCE } else { settings.source.trim() };
+    let version = latest_version(source)?;
+    if asset_dir(xray_binary_path).is_some() && installed_version(xray_binary_path).as_deref() == Some(version.as_str()) {
+        return Ok(None);
+    }
+
+    // Next to xray when that folder takes new files (not e.g. under Program Files)
+    let dir = asset_dir(xray_binary_path)
+        .into_iter()
+        .chain(binary_dir(xray_binary_path))
+        .chain(fallback_dir())
+        .find(|dir| is_writable(dir))
+        .ok_or_else(|| "No writable folder for the geo assets".to_string())?;
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(300))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    // Download both before replacing either, so a failure leaves a matching pair
+    let mut downloaded = Vec::new();
+    for name in ASSET_FILES {
+        let url = format!("{}/download/{}/{}", source.trim_end_matches('/'), version, name);
+        println!("Downloading {}", url);
+        let bytes = client.get(&url).send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.bytes())
+            .map_err(|e| format!("Failed to download {}: {}", name, e))?;
+        if bytes.is_empty() {
+            return Err(format!("{} in release {} is empty", name, version));
+        }
+        let temp_path = dir.join(format!("{}.download", name));
+        std::fs::write(&temp_path, &bytes)
+            .map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+        downloaded.push((temp_path, dir.join(name)));
+    }
+    for (temp_path, path) in downloaded {
+        std::fs::rename(&temp_path, &path)
+            .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
+    }
+    std::fs::write(dir.join(VERSION_FILE), &version)
+        .map_err(|e| format!("Failed to record the asset version: {}", e))?;
+    println!("Geo assets {} installed in {}", version, dir.display());
+    Ok(Some(version))
+}
+
+// Creates the folder if needed and checks a file can be written there
+fn is_writable(dir: &Path) -> bool {
+    if std::fs::create_dir_all(dir).is_err() {
+        return false;
+    }
+    let probe = dir.join(".geo-assets-write-test");
+    let writable = std::fs::write(&probe, b"").is_ok();
+    let _ = std::fs::remove_file(&probe);
+    writable
+}
+
+/// Check for new assets now (Tools → Geo Assets); running servers are restarted when
+/// installed files were replaced. Returns the new tag, None if already current.
+pub fn update_now() -> Result<Option<String>, String> {
+    let config = Config::load()?;
+    if config.xray_binary_path.is_empty() {
+        return Err("Set the xray binary path first".to_string());
+    }
+    if let Ok(mut last) = LAST_CHECK.lock() {
+        *last = Some(Instant::now());
+    }
+    let replacing = asset_dir(&config.xray_binary_path).is_some();
+    let version = update(&config.xray_binary_path, &config.geo_assets)?;
+    if version.is_some() && replacing {
+        println!("Geo assets updated, restarting running servers");
+        crate::routing::restart_running()?;
+    }
+    Ok(version)
+}
+
+// Fetch missing assets; with auto-update on, also look for a new release once a day
+fn check() {
+    let config = Config::load().unwrap_or_default();
+    if config.xray_binary_path.is_empty() {
+        return;
+    }
+    let missing = asset_dir(&config.xray_binary_path).is_none();
+    let due = config.geo_assets.auto_update && LAST_CHECK.lock()
+        .map(|last| last.is_none_or(|at| at.elapsed() >= CHECK_INTERVAL))
+        .unwrap_or(false);
+    if missing || due {
+        if let Err(e) = update_now() {
+            eprintln!("Geo assets: {}", e);
+        }
+    }
+}
+
+/// Fetch missing assets now, then keep them current in the background
+pub fn start_update_timer() {
+    std::thread::spawn(|| {
+        check();
+        loop {
+            crate::idle::sleep(Duration::from_secs(60 * 60));
+            if crate::idle::is_idle() {
+                continue;
+            }
+            check();
+        }
+    });
+}
+
+/// One line on the installed assets for the settings form
+#[cfg(feature = "gui")]
+pub fn status(xray_binary_path: &str) -> String {
+    match (asset_dir(xray_binary_path), installed_version(xray_binary_path)) {
+        (None, _) => "Not installed: geosite:/geoip: rules can't load".to_string(),
+        (Some(dir), Some(version)) => format!("Release {} in {}", version, dir.display()),
+        (Some(dir), None) => format!("Files of unknown release in {}", dir.display()),
+    }
+}
@@ -0,0 +1,192 @@
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use serde_json::json;
+
+use crate::events::{self, Event};
+
+// Guest sharing: a time-limited, password-protected SOCKS proxy on the LAN in
+// front of one running server, for a visitor's phone or laptop. It runs as its
+// own xray process (not registered as a server, so hooks, health checks and the
+// system proxy ignore it) that relays to the server's local port. It is torn
+// down when the time is up or the server stops; every share gets fresh
+// credentials, so old ones stop working with it.
+
+/// Port the guest proxy listens on (all interfaces)
+pub const GUEST_PORT: u16 = 10820;
+
+/// How long "Share for 1 Hour" keeps the proxy open
+pub const GUEST_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// What a guest needs to connect
+#[derive(Debug, Clone)]
+pub struct GuestAccess {
+    pub server_key: String,
+    pub server_name: String,
+    pub address: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub expires: Instant,
+}
+
+impl GuestAccess {
+    /// socks:// link for the QR code (v2rayN style: base64 of user:password)
+    pub fn link(&self) -> String {
+        let credentials = base64::engine::general_purpose::STANDARD
+            .encode(format!("{}:{}", self.username, self.password));
+        format!("socks://{}@{}:{}#{}", credentials, self.address, self.port,
+            crate::vpn::singbox::percent_encode(&format!("Guest - {}", self.server_name)))
+    }
+
+    /// Whole minutes until the proxy closes
+    pub fn minutes_left(&self) -> u64 {
+        self.expires.saturating_duration_since(Instant::now()).as_secs().div_ceil(60)
+    }
+
+    /// Connection details for a message box
+    pub fn describe(&self) -> String {
+        format!(
+            "Guest access to {} for {} more minutes.\n\nSOCKS5 proxy: {}:{}\nUser name: {}\nPassword: {}\n\n\
+             Scan the QR code with v2rayNG or another SOCKS-capable app. Windows Firewall may ask to let xray accept LAN connections.",
+            self.server_name, self.minutes_left(), self.address, self.port, self.username, self.password
+        )
+    }
+}
+
+struct GuestShare {
+    access: GuestAccess,
+    child: Child,
+    config_path: PathBuf,
+}
+
+static GUEST: Mutex<Option<GuestShare>> = Mutex::new(None);
+
+// Bumped per share so a watcher of an earlier share doesn't close a newer one
+static GUEST_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// The open share, if any
+pub fn active() -> Option<GuestAccess> {
+    GUEST.lock().ok()?.as_ref().map(|share| share.access.clone())
+}
+
+/// Open the guest proxy in front of a running server for `duration`, replacing an open share
+pub fn start(server_key: &str, duration: Duration) -> Result<GuestAccess, String> {
+    let info = crate::xray_manager::get_server_info(server_key)
+        .ok_or_else(|| "Start the server first; guests connect through its local port".to_string())?;
+    let config = crate::config::Config::load()?;
+    if config.xray_binary_path.is_empty() {
+        return Err("Set the xray binary path first".to_string());
+    }
+    stop();
+
+    // Nothing else may hold the port, or xray would exit right away
+    drop(TcpListener::bind(("0.0.0.0", GUEST_PORT))
+        .map_err(|e| format!("Port {} is not free for the guest proxy: {}", GUEST_PORT, e))?);
+
+    let access = GuestAccess {
+        server_key: server_key.to_string(),
+        server_name: info.name.clone(),
+        address: crate::inbounds::lan_address(),
+        port: GUEST_PORT,
+        username: format!("guest-{:04x}", crate::secret::random_u64() & 0xffff),
+        password: format!("{:016x}", crate::secret::random_u64()),
+        expires: Instant::now() + duration,
+    };
+    let upstream = if info.proxy_type == "HTTP" { "http" } else { "socks" };
+    let mut upstream_server = json!({ "address": "127.0.0.1", "port": info.local_port });
+    if let Some((user, pass)) = crate::inbounds::credentials(info.local_port) {
+        upstream_server["users"] = json!([{ "user": user, "pass": pass }]);
+    }
+    let guest_config = json!({
+        "log": { "loglevel": "warning" },
+        "inbounds": [{
+            "listen": "0.0.0.0",
+            "port": GUEST_PORT,
+            "protocol": "socks",
+            "settings": {
+                "auth": "password",
+                "accounts": [{ "user": access.username, "pass": access.password }],
+                "udp": true,
+            },
+        }],
+        "outbounds": [{
+            "protocol": upstream,
+            "settings": { "servers": [upstream_server] },
+        }],
+    });
+    let config_path = crate::xray_manager::write_config_file("guest", &guest_config.to_string())?;
+
+    let mut command = Command::new(&config.xray_binary_path);
+    command.arg("run").arg("-c").arg(&config_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = command.spawn().map_err(|e| format!("Failed to start xray: {}", e))?;
+    std::thread::sleep(Duration::from_millis(500));
+    if let Ok(Some(status)) = child.try_wait() {
+        let _ = std::fs::remove_file(&config_path);
+        return Err(format!("The guest proxy exited right away ({})", status));
+    }
+
+    let generation = GUEST_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    if let Ok(mut guest) = GUEST.lock() {
+        *guest = Some(GuestShare { access: access.clone(), child, config_path });
+    }
+    println!("Guest sharing {} on {}:{} for {} min", access.server_name, access.address, GUEST_PORT, duration.as_secs() / 60);
+    watch(generation, server_key.to_string(), duration);
+    crate::request_menu_update();
+    Ok(access)
+}
+
+// Close the share when its time is up or its server goes away
+fn watch(generation: u64, server_key: String, duration: Duration) {
+    let receiver = events::subscribe();
+    let deadline = Instant::now() + duration;
+    std::thread::spawn(move || {
+        loop {
+            // Stopped by hand or replaced by a newer share
+            if GUEST_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match receiver.recv_timeout(remaining) {
+                Ok(Event::ServerStopped(info)) | Ok(Event::ServerCrashed(info)) if info.key == server_key => {
+                    println!("Guest sharing ended: {} stopped", info.name);
+                    break;
+                }
+                Ok(_) => continue,
+                Err(_) => break, // Timed out, or the event bus is gone
+            }
+        }
+        if GUEST_GENERATION.load(Ordering::SeqCst) == generation {
+            stop();
+            crate::request_menu_update();
+        }
+    });
+}
+
+/// Close the guest proxy; its credentials are discarded with it
+pub fn stop() {
+    let share = GUEST.lock().ok().and_then(|mut guest| guest.take());
+    if let Some(mut share) = share {
+        GUEST_GENERATION.fetch_add(1, Ordering::SeqCst);
+        if share.child.try_wait().ok().flatten().is_none() {
+            let _ = share.child.kill();
+        }
+        let _ = share.child.wait();
+        let _ = std::fs::remove_file(&share.config_path);
+        println!("Guest sharing of {} closed", share.access.server_name);
+    }
+}
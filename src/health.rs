@@ -0,0 +1,162 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use crate::events::{self, Event};
+
+// Recent results kept per server
+const HEALTH_WINDOW: usize = 10;
+
+// Recent check results per server key: Some(latency ms) or None for a failure
+static HEALTH: LazyLock<Mutex<HashMap<String, VecDeque<Option<u32>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Health of a server over its recent checks
+#[derive(Debug, Clone, Copy)]
+pub struct HealthSummary {
+    pub avg_latency_ms: Option<u32>, // Average of successful checks
+    pub error_rate: f32, // 0.0 - 1.0
+    #[cfg_attr(not(feature = "api"), allow(dead_code))]
+    pub samples: usize, // Reported by the API
+}
+
+impl HealthSummary {
+    /// Ranking score, lower is better. Failures weigh heavily so a fast but flaky server
+    /// ranks below a slower reliable one.
+    pub fn score(&self) -> f64 {
+        match self.avg_latency_ms {
+            Some(latency) => latency as f64 * (1.0 + 4.0 * self.error_rate as f64),
+            None => f64::INFINITY,
+        }
+    }
+}
+
+fn record(server_key: &str, result: Option<u32>) {
+    if let Ok(mut health) = HEALTH.lock() {
+        let samples = health.entry(server_key.to_string()).or_default();
+        if samples.len() >= HEALTH_WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(result);
+    }
+    #[cfg(feature = "stats")]
+    crate::history::record(server_key, result);
+}
+
+/// Record a successful check
+pub fn record_success(server_key: &str, latency: Duration) {
+    record(server_key, Some(latency.as_millis().min(u32::MAX as u128) as u32));
+}
+
+/// Record a failed check or a crash
+pub fn record_failure(server_key: &str) {
+    record(server_key, None);
+}
+
+/// Summarize recent checks for a server (None if never checked)
+pub fn get_summary(server_key: &str) -> Option<HealthSummary> {
+    let health = HEALTH.lock().ok()?;
+    let samples = health.get(server_key)?;
+    if samples.is_empty() {
+        return None;
+    }
+
+    let latencies: Vec<u32> = samples.iter().flatten().copied().collect();
+    let failures = samples.len() - latencies.len();
+    let avg_latency_ms = if latencies.is_empty() {
+        None
+    } else {
+        Some((latencies.iter().map(|&l| l as u64).sum::<u64>() / latencies.len() as u64) as u32)
+    };
+
+    Some(HealthSummary {
+        avg_latency_ms,
+        error_rate: failures as f32 / samples.len() as f32,
+        samples: samples.len(),
+    })
+}
+
+/// Result of the last check of a server: Some(latency ms), None for a failure; None if never checked
+#[cfg(feature = "gui")]
+pub fn last_result(server_key: &str) -> Option<Option<u32>> {
+    HEALTH.lock().ok()?.get(server_key)?.back().copied()
+}
+
+/// " — 85 ms" after a server's tray entry, from its last check; empty if never checked
+#[cfg(feature = "gui")]
+pub fn latency_suffix(server_key: &str) -> String {
+    match last_result(server_key) {
+        Some(Some(latency)) => format!(" — {} ms", latency),
+        Some(None) => " — failed".to_string(),
+        None => String::new(),
+    }
+}
+
+/// Sort server keys best-first; servers without data keep their order at the end
+pub fn sort_by_health(keys: &mut [String]) {
+    keys.sort_by(|a, b| {
+        let score_a = get_summary(a).map(|s| s.score()).unwrap_or(f64::INFINITY);
+        let score_b = get_summary(b).map(|s| s.score()).unwrap_or(f64::INFINITY);
+        score_a.total_cmp(&score_b)
+    });
+}
+
+/// Key of the healthiest running server, if any has succeeded recently
+pub fn best_server(keys: &[String]) -> Option<String> {
+    keys.iter()
+        .filter_map(|key| get_summary(key).map(|s| (key, s.score())))
+        .filter(|(_, score)| score.is_finite())
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(key, _)| key.clone())
+}
+
+/// Test one running server now (with the configured test URL) and record the result
+pub fn check_server(server_key: &str) -> Result<Duration, String> {
+    let info = crate::xray_manager::get_server_info(server_key)
+        .ok_or_else(|| "server is not running".to_string())?;
+    let test = crate::config::Config::load().unwrap_or_default().test;
+    let result = crate::probe::test_via_proxy(
+        info.local_port,
+        &info.proxy_type,
+        &test.url,
+        Duration::from_secs(test.timeout_secs.max(1)),
+    );
+    match &result {
+        Ok(latency) => record_success(server_key, *latency),
+        Err(_) => record_failure(server_key),
+    }
+    crate::reliability::record_check(server_key, &result);
+    result
+}
+
+/// Periodically test running servers in the background and count crashes as failures
+pub fn start_health_monitor() {
+    let receiver = events::subscribe();
+    std::thread::spawn(move || {
+        for event in receiver {
+            if let Event::ServerCrashed(info) = event {
+                record_failure(&info.key);
+            }
+        }
+    });
+
+    std::thread::spawn(|| {
+        loop {
+            // Re-read each round so interval changes apply without restart
+            let interval = crate::config::Config::load().unwrap_or_default().test.interval_secs;
+            crate::idle::sleep(Duration::from_secs(interval.max(10)));
+            // The failover watchdog runs the checks while it is on
+            if crate::config::Config::load().is_ok_and(|config| config.failover.enabled) {
+                continue;
+            }
+            let running = crate::xray_manager::get_running_servers();
+            if running.is_empty() {
+                continue;
+            }
+            for server_key in running {
+                let _ = check_server(&server_key);
+            }
+            crate::request_menu_update();
+        }
+    });
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+// Latency history: health check results kept for a rolling week in
+// latency-history.json next to the config, summarized as a sparkline so the
+// server list shows which servers stay fast rather than which were fast once.
+
+/// How long samples are kept
+const RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+// At most one stored sample per server per spacing keeps the file small
+const SAMPLE_SPACING: Duration = Duration::from_secs(10 * 60);
+
+// Unsaved samples are written out at most this often
+const SAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+// Sparkline: one character per half day, lowest to highest latency
+#[cfg(feature = "gui")]
+const SPARK_BUCKETS: u64 = 14;
+#[cfg(feature = "gui")]
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One stored check: unix time and latency (None = failed)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Sample {
+    t: u64,
+    ms: Option<u32>,
+}
+
+struct History {
+    samples: HashMap<String, Vec<Sample>>,
+    last_saved: Instant,
+}
+
+static HISTORY: LazyLock<Mutex<History>> = LazyLock::new(|| {
+    Mutex::new(History { samples: load(), last_saved: Instant::now() })
+});
+
+fn history_path() -> Option<PathBuf> {
+    let config_path = crate::config::Config::get_config_path().ok()?;
+    Some(config_path.parent()?.join("latency-history.json"))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn load() -> HashMap<String, Vec<Sample>> {
+    let mut samples: HashMap<String, Vec<Sample>> = history_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    prune(&mut samples);
+    samples
+}
+
+// Drop samples older than RETENTION and servers left without any
+fn prune(samples: &mut HashMap<String, Vec<Sample>>) {
+    let cutoff = now_secs().saturating_sub(RETENTION.as_secs());
+    for list in samples.values_mut() {
+        list.retain(|sample| sample.t >= cutoff);
+    }
+    samples.retain(|_, list| !list.is_empty());
+}
+
+/// Add a check result to a server's history (Some(latency ms) or None for a failure)
+pub fn record(server_key: &str, result: Option<u32>) {
+    let Ok(mut history) = HISTORY.lock() else {
+        return;
+    };
+    let now = now_secs();
+    let list = history.samples.entry(server_key.to_string()).or_default();
+    match list.last_mut() {
+        // Within the spacing a failure replaces a success, so outages aren't hidden
+        Some(last) if now.saturating_sub(last.t) < SAMPLE_SPACING.as_secs() => {
+            if result.is_none() {
+                last.ms = None;
+            }
+        }
+        _ => list.push(Sample { t: now, ms: result }),
+    }
+
+    if history.last_saved.elapsed() >= SAVE_INTERVAL {
+        history.last_saved = Instant::now();
+        prune(&mut history.samples);
+        save(&history.samples);
+    }
+}
+
+/// Write the history file now (e.g. before exit)
+pub fn flush() {
+    if let Ok(mut history) = HISTORY.lock() {
+        history.last_saved = Instant::now();
+        save(&history.samples);
+    }
+}
+
+fn save(samples: &HashMap<String, Vec<Sample>>) {
+    let Some(path) = history_path() else {
+        return;
+    };
+    match serde_json::to_string(samples) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                eprintln!("Failed to save latency history: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize latency history: {}", e),
+    }
+}
+
+/// Week-long trend for a server, e.g. "▂▂▃▁ ×▂  avg 140 ms, 3% failed".
+/// A space marks half days without checks, × half days where every check failed.
+/// None if the server has no history.
+#[cfg(feature = "gui")]
+pub fn sparkline(server_key: &str) -> Option<String> {
+    let history = HISTORY.lock().ok()?;
+    let samples = history.samples.get(server_key).filter(|list| !list.is_empty())?;
+
+    let bucket_secs = RETENTION.as_secs() / SPARK_BUCKETS;
+    let start = now_secs().saturating_sub(RETENTION.as_secs());
+    let mut buckets: Vec<Vec<Option<u32>>> = vec![Vec::new(); SPARK_BUCKETS as usize];
+    for sample in samples.iter().filter(|s| s.t >= start) {
+        let index = ((sample.t - start) / bucket_secs).min(SPARK_BUCKETS - 1) as usize;
+        buckets[index].push(sample.ms);
+    }
+
+    let line: String = buckets.iter()
+        .map(|bucket| {
+            let latencies: Vec<u32> = bucket.iter().flatten().copied().collect();
+            if bucket.is_empty() {
+                ' '
+            } else if latencies.is_empty() {
+                '×'
+            } else {
+                let average = latencies.iter().map(|&l| l as f64).sum::<f64>() / latencies.len() as f64;
+                SPARK_LEVELS[level(average)]
+            }
+        })
+        .collect();
+
+    let latencies: Vec<u32> = samples.iter().filter_map(|s| s.ms).collect();
+    let failed = (samples.len() - latencies.len()) * 100 / samples.len();
+    let average = if latencies.is_empty() {
+        "no successful checks".to_string()
+    } else {
+        format!("avg {} ms", latencies.iter().map(|&l| l as u64).sum::<u64>() / latencies.len() as u64)
+    };
+    Some(format!("{}  {}, {}% failed", line, average, failed))
+}
+
+// Log scale from 50 ms (lowest bar) to 2 s (highest) so rows are comparable
+#[cfg(feature = "gui")]
+fn level(latency_ms: f64) -> usize {
+    let (low, high) = (50f64.ln(), 2000f64.ln());
+    let position = (latency_ms.max(1.0).ln() - low) / (high - low);
+    (position * (SPARK_LEVELS.len() - 1) as f64).round().clamp(0.0, (SPARK_LEVELS.len() - 1) as f64) as usize
+}
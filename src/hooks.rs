@@ -0,0 +1,82 @@
+use std::process::Command;
+
+use crate::config::Config;
+use crate::events::{self, Event, ServerInfo};
+
+/// Run configured hook commands for server start/stop events in the background
+pub fn start_hook_dispatcher() {
+    let receiver = events::subscribe();
+    std::thread::spawn(move || {
+        for event in receiver {
+            let (event_name, info) = match &event {
+                Event::ServerStarted(info) => ("start", info),
+                Event::ServerStopped(info) => ("stop", info),
+                Event::ServerCrashed(info) => ("crash", info),
+                Event::SubscriptionRefreshed { .. } | Event::PrimaryChanged(_) | Event::ExitIpChanged { .. } => continue,
+            };
+
+            // Reload each time so edits apply without restart; a crash is a disconnect too
+            let hooks = Config::load().unwrap_or_default().hooks;
+            let command = if event_name == "start" { hooks.on_start } else { hooks.on_stop };
+            if command.trim().is_empty() {
+                continue;
+            }
+
+            if let Err(e) = run_hook(command.trim(), event_name, info) {
+                eprintln!("Failed to run {} hook for {}: {}", event_name, info.name, e);
+            }
+        }
+    });
+}
+
+/// Run a hook script with environment variables describing the server.
+/// .ps1 files run through PowerShell, .bat/.cmd through cmd, anything else directly.
+pub fn run_hook(path: &str, event_name: &str, info: &ServerInfo) -> Result<(), String> {
+    let lower = path.to_lowercase();
+    let mut command = if lower.ends_with(".ps1") {
+        let mut cmd = Command::new("powershell.exe");
+        cmd.args(["-NoProfile", "-NonInteractive", "-ExecutionPolicy", "Bypass", "-File", path]);
+        cmd
+    } else if lower.ends_with(".bat") || lower.ends_with(".cmd") {
+        let mut cmd = Command::new("cmd.exe");
+        cmd.args(["/C", path]);
+        cmd
+    } else {
+        Command::new(path)
+    };
+
+    command
+        .env("VPN_EVENT", event_name)
+        .env("VPN_SERVER_KEY", &info.key)
+        .env("VPN_SERVER_NAME", &info.name)
+        .env("VPN_LOCAL_PORT", info.local_port.to_string())
+        .env("VPN_PROXY_TYPE", &info.proxy_type);
+
+    // Don't flash a console window for each hook
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = command.spawn()
+        .map_err(|e| format!("Failed to spawn {}: {}", path, e))?;
+
+    println!("Running {} hook for {}: {}", event_name, info.name, path);
+
+    // Reap the process without blocking other hooks
+    let name = info.name.clone();
+    let event_name = event_name.to_string();
+    std::thread::spawn(move || {
+        match child.wait() {
+            Ok(status) if !status.success() => {
+                eprintln!("{} hook for {} exited with {}", event_name, name, status);
+            }
+            Err(e) => eprintln!("Failed to wait for {} hook: {}", event_name, e),
+            _ => {}
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use std::sync::{Condvar, LazyLock, Mutex};
+use std::time::Duration;
+
+use crate::events::{self, Event};
+
+// Low-resource idle mode. With no servers running and the settings window
+// closed there is nothing to check or refresh, so background loops sleep for
+// IDLE_HEARTBEAT instead of their normal interval. Starting a server or opening
+// settings wakes them right away.
+
+/// How often background loops wake up while idle
+pub const IDLE_HEARTBEAT: Duration = Duration::from_secs(15 * 60);
+
+// Bumped by wake(); sleepers waiting on the condvar return when it changes
+static WAKE: LazyLock<(Mutex<u64>, Condvar)> = LazyLock::new(|| (Mutex::new(0), Condvar::new()));
+
+/// Nothing to monitor: no servers running and no settings window open
+pub fn is_idle() -> bool {
+    crate::xray_manager::get_running_servers().is_empty()
+        && !crate::is_settings_window_open()
+}
+
+/// Sleep for `interval` of active time. While idle this waits up to IDLE_HEARTBEAT,
+/// then sleeps the normal interval once activity resumes.
+pub fn sleep(interval: Duration) {
+    if is_idle() {
+        let (lock, condvar) = &*WAKE;
+        let Ok(generation) = lock.lock() else {
+            std::thread::sleep(interval);
+            return;
+        };
+        let started = *generation;
+        let woken = condvar.wait_timeout_while(generation, IDLE_HEARTBEAT, |g| *g == started)
+            .map(|(_, result)| !result.timed_out())
+            .unwrap_or(false);
+        if !woken {
+            return;
+        }
+    }
+    std::thread::sleep(interval);
+}
+
+/// Leave idle mode: wake background loops sleeping on the idle heartbeat
+pub fn wake() {
+    let (lock, condvar) = &*WAKE;
+    if let Ok(mut generation) = lock.lock() {
+        *generation += 1;
+    }
+    condvar.notify_all();
+}
+
+/// Wake idle loops whenever a server starts
+pub fn start_idle_watcher() {
+    let receiver = events::subscribe();
+    std::thread::spawn(move || {
+        for event in receiver {
+            if let Event::ServerStarted(_) = event {
+                wake();
+            }
+        }
+    });
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::net::UdpSocket;
+use std::sync::{LazyLock, Mutex};
+
+use serde_json::json;
+
+use crate::config::Config;
+
+// Local inbound options: the address server inbounds bind (127.0.0.1 for this
+// computer only, 0.0.0.0 to share with the LAN) and the user name and password
+// SOCKS/HTTP clients must give: on inbounds open to the LAN, and with
+// `inbound.auth_local` on loopback ones too, so other Windows users of a shared
+// machine can't borrow this user's proxies. The generated config's
+// inbounds are rewritten before start, for xray and sing-box alike. A password
+// covers every client of the port, this app's own too, so the ports asking for
+// one are remembered for health checks, the guest proxy and TUN mode.
+
+/// How a server's inbounds are exposed
+#[derive(Debug, Clone)]
+pub struct Inbound {
+    pub listen: String,
+    pub auth: Option<(String, String)>, // (user, password) clients must give
+    pub udp: bool, // Relay UDP on xray's SOCKS inbound; sing-box's always does
+}
+
+// Ports whose inbounds asked for a password at their last start, with the login
+static CREDENTIALS: LazyLock<Mutex<HashMap<u16, (String, String)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Whether an address only takes connections from this computer
+pub fn is_loopback(listen: &str) -> bool {
+    listen.eq_ignore_ascii_case("localhost")
+        || listen.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Inbound options of a server or balanced group: its own listen address, else the default
+pub fn for_server(config: &Config, server_key: &str) -> Inbound {
+    let listen = config.server_settings.get(server_key)
+        .map(|settings| settings.listen.trim())
+        .filter(|listen| !listen.is_empty())
+        .unwrap_or(config.inbound.listen.trim());
+    let listen = if listen.is_empty() { "127.0.0.1" } else { listen };
+    let auth = ((config.inbound.auth_local || !is_loopback(listen)) && !config.inbound.username.is_empty())
+        .then(|| (config.inbound.username.clone(), config.inbound.password.clone()));
+    let udp = config.server_settings.get(server_key).is_none_or(|settings| settings.udp);
+    Inbound { listen: listen.to_string(), auth, udp }
+}
+
+/// What to tell the user before a server's inbounds open to the network; None for loopback
+#[cfg(feature = "gui")]
+pub fn exposure_warning(listen: &str, has_login: bool) -> Option<String> {
+    if is_loopback(listen) {
+        return None;
+    }
+    let login = if has_login {
+        "Clients have to log in with the user name and password from Tools → LAN Sharing."
+    } else {
+        "No password is set (Tools → LAN Sharing), so anyone who can reach this computer can use the proxy \
+         and the VPN server's traffic allowance."
+    };
+    Some(format!(
+        "Listening on {} lets other devices on the network use this proxy.\n{}\n\n\
+         Windows may ask to let xray through the firewall.",
+        listen, login
+    ))
+}
+
+/// Bind the config's SOCKS/HTTP inbounds to the listen address, set UDP on SOCKS and ask
+/// for the login, if any
+pub fn apply(config_json: &str, inbound: &Inbound) -> Result<String, String> {
+    let mut config: serde_json::Value = serde_json::from_str(config_json)
+        .map_err(|e| format!("Failed to read generated config: {}", e))?;
+    if let Some(entries) = config["inbounds"].as_array_mut() {
+        for entry in entries.iter_mut() {
+            // xray inbounds have "protocol", sing-box ones "type" and "listen_port"
+            let singbox = entry.get("listen_port").is_some();
+            let kind = entry[if singbox { "type" } else { "protocol" }].as_str().unwrap_or("").to_string();
+            if kind != "socks" && kind != "http" {
+                continue;
+            }
+            entry["listen"] = json!(inbound.listen);
+            if !singbox && kind == "socks" {
+                entry["settings"]["udp"] = json!(inbound.udp);
+            }
+            let Some((user, pass)) = &inbound.auth else {
+                continue;
+            };
+            if singbox {
+                entry["users"] = json!([{ "username": user, "password": pass }]);
+            } else {
+                if kind == "socks" {
+                    entry["settings"]["auth"] = json!("password");
+                }
+                entry["settings"]["accounts"] = json!([{ "user": user, "pass": pass }]);
+            }
+        }
+    }
+    serde_json::to_string_pretty(&config).map_err(|e| format!("Failed to write config: {}", e))
+}
+
+/// Record whether the ports of a server that just started ask for a password
+pub fn remember(ports: &[u16], inbound: &Inbound) {
+    if let Ok(mut credentials) = CREDENTIALS.lock() {
+        for &port in ports.iter().filter(|&&port| port != 0) {
+            match &inbound.auth {
+                Some(login) => credentials.insert(port, login.clone()),
+                None => credentials.remove(&port),
+            };
+        }
+    }
+}
+
+/// Forget the ports of a process that is gone (the throwaway one of a test run)
+#[cfg(feature = "gui")]
+pub fn forget(ports: &[u16]) {
+    if let Ok(mut credentials) = CREDENTIALS.lock() {
+        for port in ports {
+            credentials.remove(port);
+        }
+    }
+}
+
+/// User name and password a local port asks for, if it does
+pub fn credentials(port: u16) -> Option<(String, String)> {
+    CREDENTIALS.lock().ok()?.get(&port).cloned()
+}
+
+/// Address other devices on the LAN can reach: the one used for the default route.
+/// Connecting a UDP socket sends nothing; it only selects the outgoing interface.
+pub fn lan_address() -> String {
+    UdpSocket::bind(("0.0.0.0", 0))
+        .and_then(|socket| {
+            socket.connect(("8.8.8.8", 80))?;
+            socket.local_addr()
+        })
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|_| "127.0.0.1".to_string())
+}
@@ -0,0 +1,311 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::config::ServerLimits;
+
+// Per-server limits. Policy values (buffer size, timeouts) go straight into the
+// xray config. Xray has no connection or bandwidth limits, so when either is set
+// xray listens on internal loopback ports and a relay on each of the server's
+// local ports (both of them with proxy type "Both") enforces them; the ports
+// share one connection count and one bandwidth budget. The relay only carries
+// TCP: SOCKS UDP still goes to xray directly and is only reachable from this machine.
+
+/// Add the policy settings to a generated xray config and, if connection or
+/// bandwidth limits are set, move the inbounds on `ports` behind a limiting relay.
+/// sing-box configs (hysteria2 servers) have no policy section and only get the relay.
+/// The relay runs until the returned handle is dropped.
+pub fn prepare(config_json: &str, ports: &[u16], limits: &ServerLimits) -> Result<(String, Option<InboundLimiter>), String> {
+    if *limits == ServerLimits::default() {
+        return Ok((config_json.to_string(), None));
+    }
+
+    let mut config: serde_json::Value = serde_json::from_str(config_json)
+        .map_err(|e| format!("Failed to read generated xray config: {}", e))?;
+    // xray inbounds have "port", sing-box ones "listen_port"
+    let singbox = config["inbounds"].as_array()
+        .is_some_and(|inbounds| inbounds.iter().any(|i| i.get("listen_port").is_some()));
+    let port_field = if singbox { "listen_port" } else { "port" };
+    if !singbox {
+        apply_policy(&mut config, limits);
+    }
+
+    let mut limiter = None;
+    if limits.max_connections > 0 || limits.bandwidth_kbps > 0 {
+        let internal_ports = free_loopback_ports(ports.len())?;
+        let mut relays = Vec::new();
+        for (&local_port, internal_port) in ports.iter().zip(internal_ports) {
+            let mut listen = None;
+            if let Some(inbounds) = config["inbounds"].as_array_mut() {
+                for inbound in inbounds.iter_mut().filter(|i| i[port_field].as_u64() == Some(local_port as u64)) {
+                    listen = Some(inbound["listen"].as_str().unwrap_or("0.0.0.0").to_string());
+                    inbound[port_field] = serde_json::json!(internal_port);
+                    inbound["listen"] = serde_json::json!("127.0.0.1");
+                }
+            }
+            let listen = listen.ok_or_else(|| format!("No inbound on port {} to limit", local_port))?;
+            relays.push(Relay { listen, local_port, internal_port });
+        }
+        limiter = Some(InboundLimiter::start(&relays, limits)?);
+    }
+
+    let config_json = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to write xray config: {}", e))?;
+    Ok((config_json, limiter))
+}
+
+// Set the level 0 policy used by the generated inbounds
+fn apply_policy(config: &mut serde_json::Value, limits: &ServerLimits) {
+    let level = &mut config["policy"]["levels"]["0"];
+    if let Some(buffer_size) = limits.buffer_size_kb {
+        level["bufferSize"] = serde_json::json!(buffer_size);
+    }
+    if let Some(conn_idle) = limits.conn_idle_secs {
+        level["connIdle"] = serde_json::json!(conn_idle);
+    }
+    if let Some(handshake) = limits.handshake_secs {
+        level["handshake"] = serde_json::json!(handshake);
+    }
+}
+
+// Let the OS pick `count` different unused ports (held together so none repeats)
+fn free_loopback_ports(count: usize) -> Result<Vec<u16>, String> {
+    let listeners = (0..count)
+        .map(|_| TcpListener::bind(("127.0.0.1", 0)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to find a free internal port: {}", e))?;
+    listeners.iter()
+        .map(|listener| listener.local_addr().map(|addr| addr.port()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to find a free internal port: {}", e))
+}
+
+// One limited inbound: the relay listens on `listen`:`local_port`, xray on 127.0.0.1:`internal_port`
+struct Relay {
+    listen: String,
+    local_port: u16,
+    internal_port: u16,
+}
+
+/// Relay on a server's local ports that enforces its connection and bandwidth limits
+pub struct InboundLimiter {
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl InboundLimiter {
+    fn start(relays: &[Relay], limits: &ServerLimits) -> Result<Self, String> {
+        // Bind every port before relaying any, so a taken one fails the whole start
+        let mut listeners = Vec::new();
+        for relay in relays {
+            let listener = TcpListener::bind((relay.listen.as_str(), relay.local_port))
+                .map_err(|e| format!("Port {} is not available: {}", relay.local_port, e))?;
+            listener.set_nonblocking(true)
+                .map_err(|e| format!("Failed to configure port {}: {}", relay.local_port, e))?;
+            listeners.push((listener, relay.local_port, relay.internal_port));
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let active = Arc::new(AtomicU32::new(0));
+        let max_connections = limits.max_connections;
+        // Shared by all connections so the cap holds no matter how many an app opens
+        let (upload, download) = if limits.bandwidth_kbps > 0 {
+            let bytes_per_sec = limits.bandwidth_kbps as u64 * 1000 / 8;
+            (Some(Arc::new(TokenBucket::new(bytes_per_sec))), Some(Arc::new(TokenBucket::new(bytes_per_sec))))
+        } else {
+            (None, None)
+        };
+
+        let mut threads = Vec::new();
+        for (listener, local_port, internal_port) in listeners {
+            let thread_stop = stop.clone();
+            let active = active.clone();
+            let (upload, download) = (upload.clone(), download.clone());
+            threads.push(std::thread::spawn(move || {
+                let mut warned = false;
+                while !thread_stop.load(Ordering::SeqCst) {
+                    match listener.accept() {
+                        Ok((client, peer)) => {
+                            // Checked and counted in one step: the other port's thread shares the count
+                            let admitted = active.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                                (max_connections == 0 || count < max_connections).then_some(count + 1)
+                            }).is_ok();
+                            if !admitted {
+                                if !warned {
+                                    eprintln!("Port {}: connection limit ({}) reached, refusing {}", local_port, max_connections, peer);
+                                    warned = true;
+                                }
+                                continue; // Dropping the stream closes it
+                            }
+                            warned = false;
+                            let active = active.clone();
+                            let (upload, download) = (upload.clone(), download.clone());
+                            std::thread::spawn(move || {
+                                let _ = client.set_nonblocking(false);
+                                if let Ok(upstream) = TcpStream::connect(("127.0.0.1", internal_port)) {
+                                    relay(client, upstream, upload, download);
+                                }
+                                active.fetch_sub(1, Ordering::SeqCst);
+                            });
+                        }
+                        Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            std::thread::sleep(Duration::from_millis(50));
+                        }
+                        Err(e) => {
+                            eprintln!("Port {}: accept failed: {}", local_port, e);
+                            std::thread::sleep(Duration::from_millis(100));
+                        }
+                    }
+                }
+            }));
+        }
+
+        Ok(InboundLimiter { stop, threads })
+    }
+}
+
+impl Drop for InboundLimiter {
+    // Wait for the listeners to close so the ports can be reused right away
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Bytes allowed per second, refilled continuously, with at most one second of burst
+struct TokenBucket {
+    bytes_per_sec: u64,
+    state: Mutex<(f64, Instant)>, // Available bytes, last refill
+}
+
+impl TokenBucket {
+    fn new(bytes_per_sec: u64) -> Self {
+        TokenBucket {
+            bytes_per_sec,
+            state: Mutex::new((bytes_per_sec as f64, Instant::now())),
+        }
+    }
+
+    // Block until `bytes` may be sent
+    fn take(&self, bytes: usize) {
+        let wait = {
+            let Ok(mut state) = self.state.lock() else {
+                return;
+            };
+            let (available, last_refill) = &mut *state;
+            let rate = self.bytes_per_sec as f64;
+            *available = (*available + last_refill.elapsed().as_secs_f64() * rate).min(rate);
+            *last_refill = Instant::now();
+            *available -= bytes as f64;
+            if *available < 0.0 {
+                Duration::from_secs_f64(-*available / rate)
+            } else {
+                Duration::ZERO
+            }
+        };
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+// Copy bytes both ways until either side closes, throttled by the buckets if set
+fn relay(client: TcpStream, upstream: TcpStream, upload: Option<Arc<TokenBucket>>, download: Option<Arc<TokenBucket>>) {
+    let (Ok(client_read), Ok(upstream_write)) = (client.try_clone(), upstream.try_clone()) else {
+        return;
+    };
+    let forward = std::thread::spawn(move || {
+        copy_limited(client_read, upstream_write, upload.as_deref());
+    });
+    copy_limited(upstream, client, download.as_deref());
+    let _ = forward.join();
+}
+
+fn copy_limited(mut from: TcpStream, mut to: TcpStream, bucket: Option<&TokenBucket>) {
+    let mut buffer = [0u8; 16 * 1024];
+    loop {
+        let read = match from.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => read,
+        };
+        if let Some(bucket) = bucket {
+            bucket.take(read);
+        }
+        if to.write_all(&buffer[..read]).is_err() {
+            break;
+        }
+    }
+    let _ = to.shutdown(Shutdown::Write);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Config of a "Both" server: SOCKS on one port, HTTP on the other
+    fn both_config(socks_port: u16, http_port: u16) -> String {
+        serde_json::json!({
+            "inbounds": [
+                {"protocol": "socks", "listen": "127.0.0.1", "port": socks_port},
+                {"protocol": "http", "listen": "127.0.0.1", "port": http_port},
+            ],
+        }).to_string()
+    }
+
+    // A connection the relay passed on to `internal` (standing in for xray), if one arrives
+    fn relayed(internal: &TcpListener) -> Option<TcpStream> {
+        internal.set_nonblocking(true).unwrap();
+        let started = Instant::now();
+        while started.elapsed() < Duration::from_secs(2) {
+            if let Ok((upstream, _)) = internal.accept() {
+                return Some(upstream);
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        None
+    }
+
+    #[test]
+    fn both_relays_the_http_inbound_too_under_one_connection_cap() {
+        let ports = free_loopback_ports(2).unwrap();
+        let (socks_port, http_port) = (ports[0], ports[1]);
+        let limits = ServerLimits { max_connections: 1, ..Default::default() };
+
+        let (config_json, limiter) = prepare(&both_config(socks_port, http_port), &ports, &limits).unwrap();
+        assert!(limiter.is_some());
+
+        // Both inbounds moved behind the relay, each to its own internal port
+        let config: serde_json::Value = serde_json::from_str(&config_json).unwrap();
+        let internal: Vec<u16> = config["inbounds"].as_array().unwrap().iter()
+            .map(|inbound| inbound["port"].as_u64().unwrap() as u16)
+            .collect();
+        assert!(!internal.contains(&socks_port) && !internal.contains(&http_port));
+        assert_ne!(internal[0], internal[1]);
+        let socks_internal = TcpListener::bind(("127.0.0.1", internal[0])).unwrap();
+        let http_internal = TcpListener::bind(("127.0.0.1", internal[1])).unwrap();
+
+        // A connection to the HTTP port is relayed...
+        let mut http_client = TcpStream::connect(("127.0.0.1", http_port)).unwrap();
+        http_client.write_all(b"x").unwrap();
+        let mut http_upstream = relayed(&http_internal).expect("HTTP port not relayed");
+        let mut byte = [0u8; 1];
+        http_upstream.read_exact(&mut byte).unwrap();
+        assert_eq!(&byte, b"x");
+
+        // ...and counts against the SOCKS port's cap: the next connection there is closed
+        let mut socks_client = TcpStream::connect(("127.0.0.1", socks_port)).unwrap();
+        socks_client.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let closed = match socks_client.read(&mut byte) {
+            Ok(0) => true,
+            Err(e) => !matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut),
+            Ok(_) => false,
+        };
+        assert!(closed, "connection over the shared cap was not refused");
+        assert!(relayed(&socks_internal).is_none());
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::vpn::VpnServer;
+
+// Subscription diff: every fetched server list is compared with the one before
+// it by server key (protocol, address and port). New keys were added, missing
+// ones removed, and keys whose share link differs (new UUID, password or
+// transport) changed; a provider rotating endpoints shows up as removals and
+// additions. The list is kept in server-list.json next to the config, so the
+// fetch at startup is compared with the last run's. A diff with changes waits
+// in the tray until opened; the settings window shows it after its own fetches.
+
+// Names listed per section before "and N more"
+#[cfg(feature = "gui")]
+const MAX_NAMES: usize = 15;
+
+/// What a fetch changed in the server list
+#[derive(Debug, Clone, Default)]
+pub struct ListDiff {
+    pub added: Vec<String>, // Display names
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl ListDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// "3 added, 1 removed, 2 changed"
+    pub fn summary(&self) -> String {
+        format!("{} added, {} removed, {} changed", self.added.len(), self.removed.len(), self.changed.len())
+    }
+
+    /// The summary with the servers of each section, for a message box
+    #[cfg(feature = "gui")]
+    pub fn describe(&self) -> String {
+        let mut text = format!("The last subscription refresh: {}.", self.summary());
+        for (heading, names) in [("Added", &self.added), ("Removed", &self.removed), ("Changed", &self.changed)] {
+            if names.is_empty() {
+                continue;
+            }
+            text.push_str(&format!("\n\n{}:", heading));
+            for name in names.iter().take(MAX_NAMES) {
+                text.push_str(&format!("\n  {}", name));
+            }
+            if names.len() > MAX_NAMES {
+                text.push_str(&format!("\n  ... and {} more", names.len() - MAX_NAMES));
+            }
+        }
+        text
+    }
+}
+
+// A server as last fetched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    name: String,
+    uri: String,
+}
+
+// The diff the user hasn't opened yet
+static UNSEEN: Mutex<Option<ListDiff>> = Mutex::new(None);
+
+fn list_path() -> Option<PathBuf> {
+    let config_path = crate::config::Config::get_config_path().ok()?;
+    Some(config_path.parent()?.join("server-list.json"))
+}
+
+/// Compare a freshly fetched list with the previous one and keep it as the new previous.
+/// Returns the diff; without a previous list (first run) nothing counts as added.
+pub fn record(servers: &[VpnServer]) -> ListDiff {
+    let current: BTreeMap<String, Entry> = servers.iter()
+        .map(|server| (server.get_server_key(), Entry { name: server.display_name().to_string(), uri: server.uri.clone() }))
+        .collect();
+    let path = list_path();
+    let previous: Option<BTreeMap<String, Entry>> = path.as_ref()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok());
+    if let Some(path) = &path {
+        match serde_json::to_string_pretty(&current) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(path, json) {
+                    eprintln!("Failed to save server list: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Failed to serialize server list: {}", e),
+        }
+    }
+    let Some(previous) = previous else {
+        return ListDiff::default();
+    };
+
+    let mut diff = ListDiff::default();
+    for (key, entry) in &current {
+        match previous.get(key) {
+            None => diff.added.push(entry.name.clone()),
+            Some(old) if old.uri != entry.uri => diff.changed.push(entry.name.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.removed = previous.iter()
+        .filter(|(key, _)| !current.contains_key(*key))
+        .map(|(_, entry)| entry.name.clone())
+        .collect();
+    if !diff.is_empty() {
+        println!("Server list changed: {}", diff.summary());
+        if let Ok(mut unseen) = UNSEEN.lock() {
+            *unseen = Some(diff.clone());
+        }
+    }
+    diff
+}
+
+/// The last diff with changes, if the user hasn't opened it yet
+#[cfg(feature = "gui")]
+pub fn unseen() -> Option<ListDiff> {
+    UNSEEN.lock().ok()?.clone()
+}
+
+/// Take the unopened diff to show it
+#[cfg(feature = "gui")]
+pub fn take_unseen() -> Option<ListDiff> {
+    UNSEEN.lock().ok()?.take()
+}
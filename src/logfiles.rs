@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::LogSettings;
+use crate::events::{self, Event};
+
+// Optional log files under <config dir>\logs: xray/sing-box output per server and
+// an app log of server events. A file that reaches the size limit is renamed with
+// a timestamp and a fresh one started; whenever the folder grows past the total
+// cap the oldest files are deleted, so a setup left running for months can't fill
+// the drive.
+
+// The app's own log: server starts, stops, crashes, subscription refreshes
+const APP_LOG: &str = "vpn-manager";
+
+struct OpenLog {
+    file: File,
+    size: u64,
+}
+
+// Open log files by file stem
+static OPEN_LOGS: LazyLock<Mutex<HashMap<String, OpenLog>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Current settings, so writing a line doesn't read the config file; set by apply()
+static SETTINGS: Mutex<Option<LogSettings>> = Mutex::new(None);
+
+/// Folder holding the log files (created on demand)
+pub fn logs_dir() -> Option<PathBuf> {
+    let config_path = crate::config::Config::get_config_path().ok()?;
+    let dir = config_path.parent()?.join("logs");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+/// Use new settings: close files when logging is turned off and apply a lowered cap now
+pub fn apply(settings: &LogSettings) {
+    if let Ok(mut current) = SETTINGS.lock() {
+        *current = Some(settings.clone());
+    }
+    if !settings.enabled {
+        if let Ok(mut logs) = OPEN_LOGS.lock() {
+            logs.clear();
+        }
+        return;
+    }
+    enforce_cap(settings.max_total_mb);
+}
+
+/// Write server events to the app log
+pub fn start_app_log() {
+    let receiver = events::subscribe();
+    std::thread::spawn(move || {
+        for event in receiver {
+            let line = match &event {
+                Event::ServerStarted(info) | Event::ServerStopped(info) | Event::ServerCrashed(info) | Event::PrimaryChanged(info) =>
+                    format!("{} {} ({}, {}:{})", event.name(), info.name, info.key, info.proxy_type, info.local_port),
+                Event::SubscriptionRefreshed { server_count, added, removed, changed } =>
+                    format!("{} {} servers ({} added, {} removed, {} changed)", event.name(), server_count, added, removed, changed),
+                Event::ExitIpChanged { server, previous, current } =>
+                    format!("{} {} ({}) {} -> {}", event.name(), server.name, server.key, previous, current),
+            };
+            write(APP_LOG, &line);
+        }
+    });
+}
+
+/// Append a line to a log (a server key, or the app log) if file logging is on
+pub fn write(log: &str, line: &str) {
+    let Some(settings) = SETTINGS.lock().ok().and_then(|s| s.clone()).filter(|s| s.enabled) else {
+        return;
+    };
+    let Ok(mut logs) = OPEN_LOGS.lock() else {
+        return;
+    };
+    let stem = file_stem(log);
+    if !logs.contains_key(&stem) {
+        let Some(open) = open_log(&stem) else {
+            return;
+        };
+        logs.insert(stem.clone(), open);
+    }
+    let Some(open) = logs.get_mut(&stem) else {
+        return;
+    };
+
+    let entry = format!("{} {}\n", timestamp(), line);
+    if open.file.write_all(entry.as_bytes()).is_ok() {
+        open.size += entry.len() as u64;
+    }
+    if open.size >= settings.max_file_mb.max(1) * 1024 * 1024 {
+        // Closed first: Windows can't rename a file that is open
+        logs.remove(&stem);
+        rotate(&stem);
+        enforce_cap(settings.max_total_mb);
+    }
+}
+
+// Server keys contain ':' and other characters not allowed in file names
+fn file_stem(log: &str) -> String {
+    log.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect()
+}
+
+fn open_log(stem: &str) -> Option<OpenLog> {
+    let path = logs_dir()?.join(format!("{}.log", stem));
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => {
+            let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+            Some(OpenLog { file, size })
+        }
+        Err(e) => {
+            eprintln!("Failed to open log {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+// name.log -> name.<unix ms>.log; the next line starts a new name.log
+fn rotate(stem: &str) {
+    let Some(dir) = logs_dir() else {
+        return;
+    };
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    if let Err(e) = std::fs::rename(dir.join(format!("{}.log", stem)), dir.join(format!("{}.{}.log", stem, millis))) {
+        eprintln!("Failed to rotate log {}: {}", stem, e);
+    }
+}
+
+// Delete the oldest log files until the folder fits in `max_total_mb`.
+// Files still open fail to delete on Windows and are skipped.
+fn enforce_cap(max_total_mb: u64) {
+    let Some(dir) = logs_dir() else {
+        return;
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "log"))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((metadata.modified().unwrap_or(UNIX_EPOCH), metadata.len(), entry.path()))
+        })
+        .collect();
+    files.sort_by_key(|(modified, _, _)| *modified);
+
+    let cap = max_total_mb.max(1) * 1024 * 1024;
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    for (_, size, path) in files {
+        if total <= cap {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            println!("Deleted old log {} (log folder over {} MB)", path.display(), max_total_mb);
+            total -= size;
+        }
+    }
+}
+
+// "2026-01-31 14:05:09" in UTC
+fn timestamp() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rest) = (secs / 86400, secs % 86400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year, month, day, rest / 3600, rest % 3600 / 60, rest % 60)
+}
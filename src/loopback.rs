@@ -0,0 +1,104 @@
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::config::LoopbackSettings;
+
+// Loopback proxy for troubleshooting: a local SOCKS or HTTP inbound that goes
+// straight out (freedom outbound, no VPN server). If an app works through it,
+// the app's proxy settings are right and a failing server is to blame; if it
+// doesn't, the app isn't using the proxy at all. Like the guest proxy it runs
+// as its own xray process, invisible to hooks, health checks and the system proxy.
+
+struct Loopback {
+    settings: LoopbackSettings,
+    child: Child,
+    config_path: PathBuf,
+}
+
+static LOOPBACK: Mutex<Option<Loopback>> = Mutex::new(None);
+
+/// Port and proxy type of the running loopback proxy
+pub fn active() -> Option<LoopbackSettings> {
+    LOOPBACK.lock().ok()?.as_ref().map(|loopback| loopback.settings.clone())
+}
+
+/// Start the loopback proxy (replacing a running one) and check it reaches the test URL.
+/// Returns instructions for the user.
+pub fn start(settings: &LoopbackSettings) -> Result<String, String> {
+    let config = crate::config::Config::load()?;
+    if config.xray_binary_path.is_empty() {
+        return Err("Set the xray binary path first".to_string());
+    }
+    stop();
+    drop(TcpListener::bind(("127.0.0.1", settings.port))
+        .map_err(|_| crate::ports::describe_conflict(settings.port))?);
+
+    let protocol = if settings.proxy_type == "HTTP" { "http" } else { "socks" };
+    let loopback_config = json!({
+        "log": { "loglevel": "warning" },
+        "inbounds": [{
+            "listen": "127.0.0.1",
+            "port": settings.port,
+            "protocol": protocol,
+            "settings": if protocol == "socks" { json!({ "udp": true }) } else { json!({}) },
+        }],
+        "outbounds": [{ "protocol": "freedom" }],
+    });
+    let config_path = crate::xray_manager::write_config_file("loopback", &loopback_config.to_string())?;
+
+    let mut command = Command::new(&config.xray_binary_path);
+    command.arg("run").arg("-c").arg(&config_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = command.spawn().map_err(|e| format!("Failed to start xray: {}", e))?;
+    std::thread::sleep(Duration::from_millis(500));
+    if let Ok(Some(status)) = child.try_wait() {
+        let _ = std::fs::remove_file(&config_path);
+        return Err(format!("The loopback proxy exited right away ({})", status));
+    }
+    if let Ok(mut loopback) = LOOPBACK.lock() {
+        *loopback = Some(Loopback { settings: settings.clone(), child, config_path });
+    }
+    println!("Loopback proxy started ({} 127.0.0.1:{})", settings.proxy_type, settings.port);
+    crate::request_menu_update();
+
+    // Prove the proxy itself works, so a failing app points at the app's settings
+    let test = config.test;
+    let self_test = match crate::probe::test_via_proxy(settings.port, &settings.proxy_type, &test.url,
+        Duration::from_secs(test.timeout_secs.max(1))) {
+        Ok(elapsed) => format!("It reached {} in {} ms, so the proxy itself works.", test.url, elapsed.as_millis()),
+        Err(e) => format!("It couldn't reach {} either ({}); check this computer's own connection first.", test.url, e),
+    };
+    Ok(format!(
+        "Loopback proxy running on {} 127.0.0.1:{}, going out directly without a VPN server. {}\n\n\
+         Point your app at it. If the app works now, its proxy settings are right and the VPN server was \
+         the problem; if not, the app isn't using its proxy settings.\n\n\
+         Stop it from the tray under Troubleshooting.",
+        settings.proxy_type, settings.port, self_test
+    ))
+}
+
+/// Stop the loopback proxy
+pub fn stop() {
+    let loopback = LOOPBACK.lock().ok().and_then(|mut loopback| loopback.take());
+    if let Some(mut loopback) = loopback {
+        if loopback.child.try_wait().ok().flatten().is_none() {
+            let _ = loopback.child.kill();
+        }
+        let _ = loopback.child.wait();
+        let _ = std::fs::remove_file(&loopback.config_path);
+        println!("Loopback proxy on port {} stopped", loopback.settings.port);
+    }
+}
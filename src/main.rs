@@ -1,16 +1,80 @@
-#![windows_subsystem = "windows"] // Commented out for debugging
+#![cfg_attr(feature = "gui", windows_subsystem = "windows")] // Headless builds keep their console
 
+#[cfg(feature = "gui")]
 mod ui;
 mod vpn;
 mod config;
 mod xray_manager;
+#[cfg(feature = "api")]
+mod api;
+mod secret;
+mod events;
+mod hooks;
+mod system;
+mod xray_log;
+mod diagnostics;
+#[cfg(feature = "gui")]
+mod export;
+mod probe;
+mod health;
+mod primary;
+mod geo;
+mod remarks;
+mod limits;
+#[cfg(feature = "gui")]
+mod share;
+#[cfg(feature = "gui")]
+mod guest;
+#[cfg(feature = "gui")]
+mod loopback;
+mod idle;
+mod routing;
+mod rulesets;
+mod geoassets;
+mod session;
+mod sysproxy;
+mod pac;
+mod logfiles;
+mod balancer;
+mod reliability;
+mod plan;
+mod safemode;
+mod supervisor;
+mod exitip;
+mod ports;
+mod switcher;
+mod sweep;
+mod statuspage;
+mod inbounds;
+mod mux;
+mod dpi;
+mod listdiff;
+mod prune;
+mod chain;
+#[cfg(feature = "tun")]
+mod tun;
+#[cfg(feature = "stats")]
+mod history;
+#[cfg(feature = "stats")]
+mod results;
+#[cfg(feature = "gui")]
+mod qr;
+#[cfg(any(feature = "gui", feature = "egui"))]
+mod settings_model;
+#[cfg(all(feature = "egui", not(feature = "gui")))]
+mod egui_window;
 
-use tray_icon::menu::{MenuEvent, MenuItem};
+#[cfg(feature = "gui")]
+use tray_icon::menu::{Menu, MenuEvent, MenuItem};
+#[cfg(feature = "gui")]
 use tray_icon::TrayIcon;
-use std::sync::{Arc, Mutex, LazyLock};
+use std::collections::HashSet;
+#[cfg(feature = "gui")]
+use std::sync::{Arc, Mutex};
+use std::sync::LazyLock;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-#[cfg(windows)]
+#[cfg(all(windows, feature = "gui"))]
 use windows::{
     Win32::{
         Foundation::HWND,
@@ -18,9 +82,15 @@ use windows::{
     },
 };
 
-// Global tokio runtime for async operations
+// Global tokio runtime for async operations.
+// Only used through block_on, so a current-thread runtime is enough and no
+// worker threads sit around while the app is idle.
 pub static TOKIO_RUNTIME: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
-    tokio::runtime::Runtime::new().expect("Failed to create tokio runtime")
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .enable_io() // Start checks connect to the new inbounds with tokio::net
+        .build()
+        .expect("Failed to create tokio runtime")
 });
 
 // Flag to trigger menu update
@@ -31,66 +101,476 @@ pub fn request_menu_update() {
     MENU_UPDATE_REQUESTED.store(true, Ordering::Relaxed);
 }
 
-/// Restart all xray servers based on current config
-/// This stops all running servers and starts enabled ones
-pub fn restart_xray_servers() {
-    // Stop all running servers first
-    TOKIO_RUNTIME.block_on(async {
-        let _ = xray_manager::stop_all_servers().await;
-    });
+/// Whether the settings window is open (never, in headless builds)
+pub fn is_settings_window_open() -> bool {
+    #[cfg(feature = "gui")]
+    return ui::settings_window::is_settings_window_open();
+    #[cfg(all(feature = "egui", not(feature = "gui")))]
+    return egui_window::is_open();
+    #[cfg(not(any(feature = "gui", feature = "egui")))]
+    false
+}
+
+/// Apply the current config to the running servers: enabled ones start, or restart when
+/// their config changed (several at a time); the rest stop. Unchanged servers keep running.
+pub fn restart_xray_servers() -> xray_manager::StartSummary {
+    restart_servers_where(|server| server.enabled)
+}
+
+/// Start the servers that were running when the app last exited
+/// (the enabled ones if no session was recorded yet)
+pub fn resume_session() -> xray_manager::StartSummary {
+    match session::load() {
+        Some(running) => {
+            println!("Resuming previous session ({} servers)", running.len());
+            restart_servers_where(|server| running.contains(&server.get_server_key()))
+        }
+        None => restart_xray_servers(),
+    }
+}
+
+// Give the fetched servers their ports and save what that changed in the settings
+// (another spelling's settings moved to the server's key, colliding ports replaced),
+// before pruning would count such keys as gone
+fn assign_and_store_ports(config: &mut config::Config, servers: &mut [vpn::VpnServer]) {
+    vpn::assign_local_ports(servers, &config.server_settings, config.first_local_port());
+    if vpn::store_assigned_ports(&mut config.server_settings, servers) {
+        if let Err(e) = config.save() {
+            eprintln!("Failed to save reassigned server ports: {}", e);
+        }
+    }
+}
+
+// Reload the server list and run the servers `select` picks: servers already running what
+// they'd start with are left alone with their connections, changed ones restart, new ones
+// start and all others stop
+fn restart_servers_where(select: impl Fn(&vpn::VpnServer) -> bool) -> xray_manager::StartSummary {
+    let Some(_operation) = xray_manager::begin_operation() else {
+        return xray_manager::StartSummary::default();
+    };
+    supervisor::forget_all();
+    
+    let mut summary = xray_manager::StartSummary::default();
     
     // Load config and start enabled servers
-    if let Ok(config) = config::Config::load() {
-        if !config.subscription_url.is_empty() && !config.xray_binary_path.is_empty() {
+    if let Ok(mut config) = config::Config::load() {
+        if config.has_server_sources() && !config.xray_binary_path.is_empty() {
             // Fetch subscription URIs synchronously
-            let subscription_uris = vpn::fetch_subscription_uris(&config.subscription_url);
-            let mut servers = vpn::fetch_and_process_vpn_list(&config.subscription_url);
-            vpn::assign_local_ports(&mut servers, &config.server_settings);
+            let subscription_uris = vpn::fetch_subscription_uris(&config.subscriptions, &config.manual_servers);
+            let mut servers = vpn::fetch_and_process_vpn_list(&config.subscriptions, &config.manual_servers);
+            vpn::migrate_server_keys(&mut config, &servers);
+            assign_and_store_ports(&mut config, &mut servers);
+            prune::after_fetch(&mut config, &servers);
             
             // Update global VPN_SERVERS state
             if let Ok(mut global_servers) = vpn::VPN_SERVERS.lock() {
                 *global_servers = Some(servers.clone());
             }
+            let diff = listdiff::record(&servers);
+            events::emit(events::Event::SubscriptionRefreshed {
+                server_count: servers.len(), added: diff.added.len(), removed: diff.removed.len(), changed: diff.changed.len(),
+            });
             
             // Start enabled servers
-            TOKIO_RUNTIME.block_on(async {
-                for server in servers.iter() {
-                    if server.enabled {
-                        let server_key = server.get_server_key();
-                        
-                        if let Some(settings) = config.server_settings.get(&server_key) {
-                            if let Some(uri) = subscription_uris.get(&server_key) {
-                                match xray_manager::start_server(
-                                    &server_key,
-                                    uri,
-                                    settings.local_port,
-                                    &settings.proxy_type,
-                                    &config.xray_binary_path,
-                                ).await {
-                                    Ok(_) => println!("Started server: {}", server.name),
-                                    Err(e) => eprintln!("Failed to start server {}: {}", server.name, e),
-                                }
-                            }
-                        }
-                    }
+            let requests: Vec<xray_manager::StartRequest> = servers.iter()
+                .filter(|server| select(server))
+                .filter_map(|server| plan::start_request(&config, server, &subscription_uris))
+                .collect();
+            let (kept, changed): (Vec<_>, Vec<_>) = requests.into_iter()
+                .partition(|request| xray_manager::is_current(request, &config.xray_binary_path));
+            let kept_keys: HashSet<String> = kept.iter().map(|request| request.key.clone()).collect();
+            // Groups are brought in line by sync_groups below
+            let stop: Vec<String> = xray_manager::get_running_servers().into_iter()
+                .filter(|key| !balancer::is_group_key(key) && !kept_keys.contains(key))
+                .collect();
+            for (key, _) in diagnostics::get_all_hints() {
+                if !kept_keys.contains(&key) {
+                    diagnostics::clear_hint(&key);
                 }
+            }
+            if !kept.is_empty() {
+                println!("{} servers unchanged, keeping them running", kept.len());
+            }
+            summary = TOKIO_RUNTIME.block_on(async {
+                for key in &stop {
+                    let _ = xray_manager::stop_server(key).await;
+                }
+                xray_manager::start_servers(changed, &config.xray_binary_path, config.start_concurrency).await
             });
+            summary.kept = kept.into_iter().map(|request| request.name).collect();
+            let groups = balancer::sync_groups(&config);
+            summary.started.extend(groups.started);
+            summary.failed.extend(groups.failed);
+            primary::auto_select(&config.primary);
+            
+            system::flush_dns_after_connect(&config);
+            request_menu_update();
+            return summary;
         }
     }
     
+    // Nothing to run without servers or a core
+    TOKIO_RUNTIME.block_on(async {
+        let _ = xray_manager::stop_all_servers().await;
+    });
+    diagnostics::clear_all_hints();
+    
     // Request menu update
     request_menu_update();
+    summary
+}
+
+/// Re-fetch subscriptions and, with `update_processes`, bring running servers in line
+/// with the new list: removed servers stop, servers whose URI changed restart, enabled
+/// servers not running start. Without it only the server list and settings are updated.
+pub fn refresh_subscriptions(update_processes: bool) {
+    let Ok(mut config) = config::Config::load() else {
+        return;
+    };
+    if !config.has_server_sources() || config.xray_binary_path.is_empty() {
+        return;
+    }
+    let Some(_operation) = xray_manager::begin_operation() else {
+        return;
+    };
+    
+    let (mut servers, subscription_uris) = vpn::fetch_servers_and_uris(&config.subscriptions, &config.manual_servers);
+    if servers.is_empty() {
+        // Most likely a network error; keep what's running
+        eprintln!("Subscription refresh returned no servers, keeping current list");
+        return;
+    }
+    vpn::migrate_server_keys(&mut config, &servers);
+    assign_and_store_ports(&mut config, &mut servers);
+    prune::after_fetch(&mut config, &servers);
+    
+    if let Ok(mut global_servers) = vpn::VPN_SERVERS.lock() {
+        *global_servers = Some(servers.clone());
+    }
+    let diff = listdiff::record(&servers);
+    events::emit(events::Event::SubscriptionRefreshed {
+        server_count: servers.len(), added: diff.added.len(), removed: diff.removed.len(), changed: diff.changed.len(),
+    });
+    if !update_processes {
+        request_menu_update();
+        return;
+    }
+    
+    // Check new and changed configs before stopping anything, so a link the core
+    // rejects leaves the server running on its old config instead of taking it down
+    let mut plan = plan::build(&config, &servers, &subscription_uris, &xray_manager::get_running_uris());
+    plan.validate(&config.xray_binary_path, config.start_concurrency);
+    TOKIO_RUNTIME.block_on(async {
+        for (key, name) in &plan.stop {
+            println!("Server {} removed from subscription, stopping", name);
+            let _ = xray_manager::stop_server(key).await;
+        }
+        for request in &plan.restart {
+            println!("Server {} changed, restarting", request.name);
+            let _ = xray_manager::stop_server(&request.key).await;
+        }
+        let mut requests = plan.restart;
+        requests.extend(plan.start);
+        xray_manager::start_servers(requests, &config.xray_binary_path, config.start_concurrency).await;
+    });
+    // Groups follow their members' new links
+    balancer::sync_groups(&config);
+    // The fastest (or a random) server may have changed with the new list
+    primary::auto_select(&config.primary);
+    
+    request_menu_update();
+}
+
+/// Refresh subscriptions in the background every `subscription_refresh_hours`.
+/// The interval is re-read from config each minute so changes apply without restart.
+pub fn start_subscription_refresh_timer() {
+    std::thread::spawn(|| {
+        let mut last_refresh = std::time::Instant::now();
+        loop {
+            idle::sleep(std::time::Duration::from_secs(60));
+            
+            let hours = config::Config::load().map(|c| c.subscription_refresh_hours).unwrap_or(0);
+            if hours == 0 || last_refresh.elapsed() < std::time::Duration::from_secs(hours as u64 * 3600) {
+                continue;
+            }
+            
+            // The settings window edits VPN_SERVERS by index; wait until it's closed
+            if is_settings_window_open() {
+                continue;
+            }
+            
+            // While idle the list is still refreshed, but nothing is started
+            let idle = idle::is_idle();
+            println!("Refreshing subscriptions (every {} h){}", hours, if idle { ", idle: not starting servers" } else { "" });
+            refresh_subscriptions(!idle);
+            last_refresh = std::time::Instant::now();
+        }
+    });
+}
+
+/// Start a single server by key using its saved settings
+pub fn start_single_server(server_key: &str) -> Result<(), String> {
+    let config = config::Config::load()?;
+    if config.xray_binary_path.is_empty() {
+        return Err("Xray binary path is not configured".to_string());
+    }
+    
+    let settings = config.server_settings.get(server_key)
+        .ok_or_else(|| format!("No saved settings for {}", server_key))?;
+    let subscription_uris = vpn::fetch_subscription_uris(&config.subscriptions, &config.manual_servers);
+    let uri = subscription_uris.get(server_key)
+        .ok_or_else(|| format!("Server {} not found in subscription", server_key))?;
+    // A server switched onto another's port keeps that port for this run
+    let (local_port, proxy_type, http_port) = switcher::port_override(server_key)
+        .unwrap_or_else(|| (settings.local_port, settings.proxy_type.clone(), settings.http_port));
+    
+    TOKIO_RUNTIME.block_on(async {
+        // Restart if already running
+        let _ = xray_manager::stop_server(server_key).await;
+        xray_manager::start_server(
+            server_key,
+            uri,
+            local_port,
+            &proxy_type,
+            http_port,
+            &config.xray_binary_path,
+            &settings.limits,
+        ).await
+    })?;
+    
+    system::flush_dns_after_connect(&config);
+    request_menu_update();
+    Ok(())
+}
+
+/// Stop a single running server by key
+pub fn stop_single_server(server_key: &str) -> Result<(), String> {
+    TOKIO_RUNTIME.block_on(async {
+        xray_manager::stop_server(server_key).await
+    })?;
+    // Stopped on purpose: no pending restart, no ✗ in the tray
+    supervisor::forget(server_key);
+    
+    request_menu_update();
+    Ok(())
+}
+
+/// Longest exit waits for server starts and reconciles in progress before stopping anyway
+const EXIT_GRACE: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Record the session, revert the system proxy, stop every xray process and save state before the app exits
+fn shutdown() {
+    // Let starts/reconciles in progress finish, so none registers a server after the stop
+    xray_manager::begin_shutdown(EXIT_GRACE);
+    session::save();
+    // Remove the tunnel's routes before the port behind them closes
+    #[cfg(feature = "tun")]
+    tun::stop();
+    // Before the servers go, so apps never point at a dead port
+    if let Err(e) = sysproxy::disable() {
+        eprintln!("Failed to revert system proxy: {}", e);
+    }
+    #[cfg(feature = "gui")]
+    guest::stop();
+    #[cfg(feature = "gui")]
+    loopback::stop();
+    TOKIO_RUNTIME.block_on(async {
+        let _ = xray_manager::stop_all_servers().await;
+    });
+    reliability::flush();
+    #[cfg(feature = "stats")]
+    history::flush();
+    safemode::mark_clean();
+}
+
+/// Turn the system proxy off, or point it at the default target after the safety check;
+/// the choice is saved and applied again at the next start
+#[cfg(all(windows, feature = "gui"))]
+fn toggle_system_proxy() {
+    if let Err(e) = sysproxy::set_enabled(sysproxy::active_port().is_none()) {
+        eprintln!("System proxy: {}", e);
+        unsafe { ui::controls::message_box(HWND::default(), &e, "System Proxy", MB_OK | MB_ICONWARNING) };
+    }
+    request_menu_update();
+}
+
+/// Start a stopped server or stop a running one from its tray item. Starting waits for
+/// xray's startup check, so it runs on a worker thread; failures are shown.
+#[cfg(all(windows, feature = "gui"))]
+fn toggle_server_from_tray(server_key: String) {
+    std::thread::spawn(move || {
+        let running = xray_manager::get_running_servers().contains(&server_key);
+        let result = if running { stop_single_server(&server_key) } else { start_single_server(&server_key) };
+        if let Err(e) = result {
+            let action = if running { "stop" } else { "start" };
+            eprintln!("Failed to {} {}: {}", action, server_key, e);
+            let text = format!("Couldn't {} {}: {}", action, vpn::get_server_name(&server_key), e);
+            unsafe { ui::controls::message_box(HWND::default(), &text, "VPN Manager", MB_OK | MB_ICONWARNING) };
+        }
+        // The item toggled its own check mark; rebuild from the real state
+        request_menu_update();
+    });
+}
+
+/// Move the active server's port to another server from the Switch server submenu
+#[cfg(all(windows, feature = "gui"))]
+fn switch_server_from_tray(server_key: String) {
+    std::thread::spawn(move || {
+        if let Err(e) = switcher::switch_to(&server_key) {
+            eprintln!("Failed to switch to {}: {}", server_key, e);
+            let text = format!("Couldn't switch to {}: {}", vpn::get_server_name(&server_key), e);
+            unsafe { ui::controls::message_box(HWND::default(), &text, "Switch Server", MB_OK | MB_ICONWARNING) };
+        }
+        request_menu_update();
+    });
+}
+
+/// Ask for the loopback proxy's port and type, then start it and report whether it works
+#[cfg(all(windows, feature = "gui"))]
+fn start_loopback_from_tray() {
+    use ui::form_window::{show_form, FormField};
+    
+    let settings = config::Config::load().unwrap_or_default().loopback;
+    let fields = vec![
+        FormField::text("Port on 127.0.0.1:", &settings.port.to_string()),
+        FormField::choice("Proxy type:", &["SOCKS", "HTTP"], &settings.proxy_type),
+    ];
+    unsafe {
+        show_form(HWND::default(), "Loopback Proxy", fields, Box::new(|values| {
+            let settings = config::LoopbackSettings {
+                port: values[0].trim().parse().ok().filter(|&p| p > 0)
+                    .ok_or_else(|| "Enter a port between 1 and 65535".to_string())?,
+                proxy_type: values[1].clone(),
+            };
+            let mut config = config::Config::load()?;
+            config.loopback = settings.clone();
+            config.save()?;
+            start_loopback_in_background(settings);
+            Ok(())
+        }));
+    }
+}
+
+// The self-test takes up to the test timeout; report from a worker thread
+#[cfg(all(windows, feature = "gui"))]
+fn start_loopback_in_background(settings: config::LoopbackSettings) {
+    std::thread::spawn(move || {
+        let (text, icon) = match loopback::start(&settings) {
+            Ok(text) => (text, MB_ICONINFORMATION),
+            Err(e) => (e, MB_ICONWARNING),
+        };
+        unsafe { ui::controls::message_box(HWND::default(), &text, "Loopback Proxy", MB_OK | icon) };
+        request_menu_update();
+    });
+}
+
+/// Turn TUN mode off, or on (offering a restart as administrator when needed).
+/// True when an elevated instance was started and this one should exit.
+#[cfg(all(windows, feature = "gui", feature = "tun"))]
+fn toggle_tun() -> bool {
+    if tun::active_port().is_some() {
+        if let Err(e) = tun::set_enabled(false) {
+            eprintln!("TUN mode: {}", e);
+        }
+        return false;
+    }
+    
+    if !tun::is_elevated() {
+        let text = format!("{}.\n\nRestart VPN Manager as administrator with TUN mode on?", tun::NEEDS_ADMIN);
+        let answer = unsafe { ui::controls::message_box(HWND::default(), &text, "TUN Mode", MB_YESNO | MB_ICONQUESTION) };
+        if answer != IDYES {
+            return false;
+        }
+        // Saved first so the elevated instance turns it on at start
+        let result = config::Config::load().and_then(|mut config| {
+            config.tun.enabled = true;
+            config.save()
+        }).and_then(|_| tun::relaunch_elevated());
+        return match result {
+            Ok(()) => true,
+            Err(e) => {
+                if let Ok(mut config) = config::Config::load() {
+                    config.tun.enabled = false;
+                    let _ = config.save();
+                }
+                unsafe { ui::controls::message_box(HWND::default(), &e, "TUN Mode", MB_OK | MB_ICONWARNING) };
+                false
+            }
+        };
+    }
+    
+    // Starting waits out sing-box's startup check
+    std::thread::spawn(|| {
+        if let Err(e) = tun::set_enabled(true) {
+            eprintln!("TUN mode: {}", e);
+            unsafe { ui::controls::message_box(HWND::default(), &e, "TUN Mode", MB_OK | MB_ICONWARNING) };
+        }
+        request_menu_update();
+    });
+    false
+}
+
+/// What the tray shows while exiting, e.g. "Shutting down… stopping 4 servers"
+#[cfg(all(windows, feature = "gui"))]
+fn shutdown_status() -> String {
+    let running = xray_manager::get_running_servers().len();
+    let servers = if running == 1 { "server" } else { "servers" };
+    match xray_manager::pending_operations() {
+        0 => format!("Shutting down… stopping {} {}", running, servers),
+        pending => format!("Shutting down… waiting for {} server changes, then stopping {} {}", pending, running, servers),
+    }
+}
+
+/// Exit from the tray: shut down on a worker thread while messages keep flowing,
+/// with progress in the tray menu and tooltip
+#[cfg(all(windows, feature = "gui"))]
+unsafe fn exit_from_tray(tray_icon: &mut TrayIcon) {
+    let worker = std::thread::spawn(shutdown);
+    let mut shown = String::new();
+    let mut msg = MSG::default();
+    while !worker.is_finished() {
+        let status = shutdown_status();
+        if status != shown {
+            let menu = Menu::new();
+            let _ = menu.append(&MenuItem::new(&status, false, None));
+            tray_icon.set_menu(Some(Box::new(menu)));
+            let _ = tray_icon.set_tooltip(Some(&status));
+            shown = status;
+        }
+        unsafe {
+            while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    let _ = worker.join();
 }
 
 /// Update tray icon menu with current running servers
+#[cfg(feature = "gui")]
 pub fn update_tray_menu(tray_icon: &mut TrayIcon, settings_item: &MenuItem, quit_item: &MenuItem) {
     let new_menu = ui::create_tray_menu_with_servers(settings_item, quit_item);
     tray_icon.set_menu(Some(Box::new(new_menu)));
+    let _ = tray_icon.set_tooltip(Some(ui::tray::tooltip_text()));
+    if let Some(icon) = ui::tray::updated_icon() {
+        let _ = tray_icon.set_icon(Some(icon));
+    }
 }
 
 fn main() {
+    // --config <path>: run a separate profile (e.g. per user on a shared machine)
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(path) = args.iter().position(|a| a == "--config").and_then(|i| args.get(i + 1)) {
+        config::Config::set_config_path(std::path::PathBuf::from(path));
+    }
+    // Started elevated for TUN mode: let the instance that started us stop its servers first
+    #[cfg(feature = "tun")]
+    tun::wait_for_previous_instance(&args);
+    
     // Enable DPI awareness at process start
-    #[cfg(windows)]
+    #[cfg(all(windows, feature = "gui"))]
     unsafe {
         use windows::Win32::UI::HiDpi::SetProcessDpiAwarenessContext;
         let _ = SetProcessDpiAwarenessContext(
@@ -98,15 +578,123 @@ fn main() {
         );
     }
     
-    // Auto-start servers on first launch
-    restart_xray_servers();
+    // Log files, if enabled, before anything writes to them
+    logfiles::apply(&config::Config::load().unwrap_or_default().logs);
+    logfiles::start_app_log();
+    
+    system::report_wine();
+    
+    // After three starts in a row that died early, start nothing and leave system settings alone
+    let safe_mode = safemode::begin();
+    safemode::start_stability_timer();
+    
+    // Undo a system proxy left set by a run that crashed, then watch its backing server
+    sysproxy::restore_after_crash();
+    sysproxy::start_revert_watcher();
+    
+    // Run start/stop hooks for server events
+    hooks::start_hook_dispatcher();
+    
+    // Track server health for tray ordering and the reliability score
+    health::start_health_monitor();
+    reliability::start_crash_counter();
+    idle::start_idle_watcher();
+    xray_manager::start_failover_watchdog();
+    supervisor::start_supervisor();
+    exitip::start_exit_ip_monitor();
+    sweep::start_scheduler();
+    
+    // Auto-start the enabled servers, or the ones running at last exit
+    if safe_mode {
+        println!("Safe mode: not starting servers, system proxy or TUN mode");
+    } else if config::Config::load().is_ok_and(|config| config.startup_mode == session::RESUME) {
+        let _ = resume_session();
+    } else {
+        let _ = restart_xray_servers();
+    }
+    start_subscription_refresh_timer();
+    rulesets::start_rule_set_refresh_timer();
+    geoassets::start_update_timer();
+    
+    // Start local control API and primary port if enabled
+    if let Ok(config) = config::Config::load() {
+        #[cfg(feature = "api")]
+        api::apply(&config.api);
+        statuspage::apply(&config.status_page);
+        if !safe_mode {
+            primary::apply(&config);
+            pac::apply(&config.pac);
+            sysproxy::apply_saved();
+            #[cfg(feature = "tun")]
+            if config.tun.enabled {
+                if let Err(e) = tun::apply(&config) {
+                    eprintln!("TUN mode: {}", e);
+                }
+            }
+        }
+    }
+    primary::start_rotation_timer();
+    #[cfg(feature = "tun")]
+    tun::start_port_watcher();
     
+    #[cfg(feature = "gui")]
+    run_tray();
+    #[cfg(all(feature = "egui", not(feature = "gui")))]
+    run_settings_window();
+    #[cfg(not(any(feature = "gui", feature = "egui")))]
+    run_headless();
+}
+
+// The egui build has no tray: the settings window is the app, and closing it stops
+// the servers. Without a display (a server, an SSH session) it serves headless.
+#[cfg(all(feature = "egui", not(feature = "gui")))]
+fn run_settings_window() {
+    match egui_window::run() {
+        Ok(()) => {
+            println!("Stopping servers");
+            shutdown();
+        }
+        Err(e) => {
+            eprintln!("Failed to open the settings window: {}", e);
+            run_headless();
+        }
+    }
+}
+
+// Without the tray there is nothing to click: serve until Ctrl+C or the
+// service manager stops the process, then shut down cleanly
+#[cfg(not(feature = "gui"))]
+fn run_headless() {
+    println!("Running headless, press Ctrl+C to stop");
+    let signal_runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create tokio runtime");
+    if let Err(e) = signal_runtime.block_on(tokio::signal::ctrl_c()) {
+        eprintln!("Failed to wait for Ctrl+C, serving until killed: {}", e);
+        loop {
+            std::thread::park();
+        }
+    }
+    println!("Stopping servers");
+    shutdown();
+}
+
+// Tray icon and the Windows message loop; returns when the user picks Exit
+#[cfg(feature = "gui")]
+fn run_tray() {
     // Create menu items
     let settings_item = MenuItem::new("Settings", true, None);
     let quit_item = MenuItem::new("Exit", true, None);
     
     // Create tray icon with running servers list
     let mut tray_icon = ui::create_tray_icon_with_servers(&settings_item, &quit_item);
+    
+    // The dialog waits for an answer; the tray keeps working meanwhile
+    #[cfg(windows)]
+    if safemode::is_active() {
+        std::thread::spawn(safemode::show_recovery_dialog);
+    }
 
     // Event handling
     let menu_channel = MenuEvent::receiver();
@@ -115,6 +703,14 @@ fn main() {
     #[cfg(windows)]
     let settings_window: Arc<Mutex<Option<HWND>>> = Arc::new(Mutex::new(None));
 
+    // Without "Start minimized to tray" the settings window greets each launch
+    #[cfg(windows)]
+    if !config::Config::load().map(|c| c.start_minimized).unwrap_or(true) {
+        if let Ok(mut window) = settings_window.lock() {
+            *window = Some(unsafe { ui::create_settings_window() });
+        }
+    }
+
     // Windows message loop
     #[cfg(windows)]
     {
@@ -133,7 +729,15 @@ fn main() {
                 
                 // Check for menu events first
                 if let Ok(event) = menu_channel.try_recv() {
+                    #[cfg(feature = "tun")]
+                    if event.id == ui::tray::TUN_MODE_ID && toggle_tun() {
+                        // The elevated instance takes over once this one has exited
+                        exit_from_tray(&mut tray_icon);
+                        break;
+                    }
                     if event.id == settings_item.id() {
+                        // Background checks resume while settings are open
+                        idle::wake();
                         // Open or focus settings window
                         let mut window = settings_window_clone.lock().unwrap();
                         if let Some(hwnd) = *window {
@@ -149,11 +753,42 @@ fn main() {
                             // Create new settings window
                             *window = Some(ui::create_settings_window());
                         }
-                    } else if event.id == quit_item.id() {
-                        // Stop all xray processes before exit
-                        TOKIO_RUNTIME.block_on(async {
-                            let _ = xray_manager::stop_all_servers().await;
+                    } else if let Some(country) = event.id.0.strip_prefix(ui::tray::PRIMARY_COUNTRY_PREFIX) {
+                        // Pin the primary port to a country from the tray submenu
+                        if let Err(e) = primary::set_country(country) {
+                            eprintln!("Failed to set primary country: {}", e);
+                        }
+                        request_menu_update();
+                    } else if let Some(server_key) = event.id.0.strip_prefix(ui::tray::SERVER_TOGGLE_PREFIX) {
+                        toggle_server_from_tray(server_key.to_string());
+                    } else if let Some(server_key) = event.id.0.strip_prefix(ui::tray::SWITCH_PREFIX) {
+                        switch_server_from_tray(server_key.to_string());
+                    } else if event.id == ui::tray::SYSTEM_PROXY_ID {
+                        toggle_system_proxy();
+                    } else if event.id == ui::tray::VIEW_LOGS_ID {
+                        ui::log_window::show_log_window(HWND::default());
+                    } else if event.id == ui::tray::LOOPBACK_START_ID {
+                        start_loopback_from_tray();
+                    } else if event.id == ui::tray::LOOPBACK_STOP_ID {
+                        loopback::stop();
+                        request_menu_update();
+                    } else if event.id == ui::tray::GUEST_STOP_ID {
+                        guest::stop();
+                        request_menu_update();
+                    } else if event.id == ui::tray::TEST_CONNECTIVITY_ID {
+                        // Tests take up to the timeout; report from a worker thread
+                        std::thread::spawn(|| {
+                            let report = probe::format_report(&probe::test_running_servers());
+                            ui::controls::message_box(HWND::default(), &report, "Test Connectivity", MB_OK | MB_ICONINFORMATION);
                         });
+                    } else if event.id == ui::tray::SUBSCRIPTION_DIFF_ID {
+                        if let Some(diff) = listdiff::take_unseen() {
+                            request_menu_update();
+                            ui::controls::message_box(HWND::default(), &diff.describe(), "Subscription Changes", MB_OK | MB_ICONINFORMATION);
+                        }
+                    } else if event.id == quit_item.id() {
+                        // Stop all xray processes before exit, after changes in progress finish
+                        exit_from_tray(&mut tray_icon);
                         break;
                     }
                 }
@@ -170,6 +805,10 @@ fn main() {
                 }
                 
                 if result.0 > 0 {
+                    // Keyboard navigation in the settings and tool windows
+                    if ui::controls::handle_dialog_keys(&msg) {
+                        continue;
+                    }
                     let _ = TranslateMessage(&msg);
                     DispatchMessageW(&msg);
                 }
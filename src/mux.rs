@@ -0,0 +1,53 @@
+use serde_json::json;
+
+use crate::config::{Config, MuxSettings};
+
+// Multiplexing: xray's Mux.Cool on a server's outbound, from Tools → Multiplexing...
+// (`mux`) or the server's own settings (server context menu → Multiplexing...,
+// `ServerSettings::mux`). Off by default. VLESS with XTLS Vision can't carry TCP
+// streams over mux, so those servers only get XUDP. sing-box servers (hysteria2)
+// and balanced groups are left as generated.
+
+/// The multiplexing a server runs with: its own settings, else the default
+pub fn for_server(config: &Config, server_key: &str) -> MuxSettings {
+    config.server_settings.get(server_key)
+        .and_then(|settings| settings.mux.clone())
+        .unwrap_or_else(|| config.mux.clone())
+}
+
+/// Check a streams-per-connection value from a form or config
+#[cfg(feature = "gui")]
+pub fn validate_concurrency(value: i32, what: &str) -> Result<(), String> {
+    if (-1..=1024).contains(&value) {
+        Ok(())
+    } else {
+        Err(format!("{} must be from 1 to 1024, 0 for xray's default or -1 for none", what))
+    }
+}
+
+/// Write the mux object into the generated xray config's proxy outbound
+pub fn apply(config_json: &str, settings: &MuxSettings) -> Result<String, String> {
+    if !settings.enabled {
+        return Ok(config_json.to_string());
+    }
+    let mut config: serde_json::Value = serde_json::from_str(config_json)
+        .map_err(|e| format!("Failed to read generated xray config: {}", e))?;
+    // The server's outbound comes first, as routing expects
+    let Some(outbound) = config["outbounds"].as_array_mut().and_then(|outbounds| outbounds.first_mut()) else {
+        return Err("Generated xray config has no outbounds".to_string());
+    };
+    let vision = outbound["settings"]["vnext"][0]["users"][0]["flow"].as_str()
+        .is_some_and(|flow| flow.contains("vision"));
+
+    let mut mux = json!({ "enabled": true });
+    if vision {
+        mux["concurrency"] = json!(-1);
+    } else if settings.concurrency != 0 {
+        mux["concurrency"] = json!(settings.concurrency);
+    }
+    if settings.xudp_concurrency != 0 {
+        mux["xudpConcurrency"] = json!(settings.xudp_concurrency);
+    }
+    outbound["mux"] = mux;
+    serde_json::to_string_pretty(&config).map_err(|e| format!("Failed to write xray config: {}", e))
+}
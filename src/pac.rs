@@ -0,0 +1,199 @@
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::config::{Config, PacSettings};
+
+// PAC file: a proxy auto-config script sending the bypass domains direct and
+// everything else through the local proxy, for browsers and apps that take a
+// PAC URL. It is served on loopback and generated per request, so it always
+// points at the current system proxy target (primary port or healthiest
+// running server). Optionally it is registered as the system auto-config URL
+// in place of a fixed proxy.
+
+/// Path the PAC file is served under
+pub const PAC_PATH: &str = "/proxy.pac";
+
+// Bumped on every apply; a serving thread exits once its generation is stale
+static PAC_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// URL of the PAC file served on `port`
+pub fn url(port: u16) -> String {
+    format!("http://127.0.0.1:{}{}", port, PAC_PATH)
+}
+
+/// Entries sent direct: the configured list, then the routing rules' and active preset's direct rules if asked
+pub fn bypass_entries(settings: &PacSettings) -> Vec<String> {
+    let mut entries = settings.bypass.clone();
+    if settings.preset_direct {
+        let mut rules = crate::routing::custom_rules();
+        if let Some(preset) = crate::routing::active_preset() {
+            rules.extend(preset.rules);
+        }
+        for rule in rules.iter().filter(|r| r.outbound == "direct") {
+            entries.extend(rule.domains.iter().chain(rule.ips.iter()).cloned());
+        }
+    }
+    entries
+}
+
+/// PAC script sending `bypass` direct and the rest to the proxy on `target`
+/// (port, proxy type); with no target everything goes direct
+pub fn build(target: Option<(u16, &str)>, bypass: &[String]) -> String {
+    let proxy = match target {
+        Some((port, "HTTP")) => format!("PROXY 127.0.0.1:{}", port),
+        Some((port, _)) => format!("SOCKS5 127.0.0.1:{0}; SOCKS 127.0.0.1:{0}", port),
+        None => "DIRECT".to_string(),
+    };
+
+    let mut script = String::from("function FindProxyForURL(url, host) {\n");
+    script.push_str("  if (isPlainHostName(host) || host == \"localhost\") return \"DIRECT\";\n");
+    // Same local ranges the fixed system proxy bypasses
+    for pattern in ["127.*", "10.*", "172.16.*", "192.168.*"] {
+        script.push_str(&format!("  if (shExpMatch(host, {})) return \"DIRECT\";\n", js_string(pattern)));
+    }
+
+    let mut networks = Vec::new();
+    for entry in bypass.iter().map(|e| e.trim()).filter(|e| !e.is_empty()) {
+        if let Some(network) = ipv4_network(entry) {
+            networks.push(network);
+        } else if let Some(condition) = host_condition(entry) {
+            script.push_str(&format!("  if ({}) return \"DIRECT\";\n", condition));
+        }
+    }
+    // Resolving costs a DNS lookup per request, so only when there are IP entries
+    if !networks.is_empty() {
+        let checks: Vec<String> = networks.iter()
+            .map(|(address, mask)| format!("isInNet(ip, \"{}\", \"{}\")", address, mask))
+            .collect();
+        script.push_str("  var ip = dnsResolve(host);\n");
+        script.push_str(&format!("  if (ip && ({})) return \"DIRECT\";\n", checks.join(" || ")));
+    }
+    script.push_str(&format!("  return {};\n}}\n", js_string(&proxy)));
+    script
+}
+
+// JavaScript condition on `host` for an xray-style domain matcher. geosite:,
+// regexp: and geoip: lists can't be evaluated in a PAC file and are left out.
+fn host_condition(entry: &str) -> Option<String> {
+    if ["geosite:", "geoip:", "regexp:", "ext:"].iter().any(|prefix| entry.starts_with(prefix)) {
+        return None;
+    }
+    if let Some(full) = entry.strip_prefix("full:") {
+        return Some(format!("host == {}", js_string(full)));
+    }
+    if let Some(keyword) = entry.strip_prefix("keyword:") {
+        return Some(format!("shExpMatch(host, {})", js_string(&format!("*{}*", keyword))));
+    }
+    let pattern = entry.strip_prefix("domain:").unwrap_or(entry);
+    if pattern.contains('*') {
+        return Some(format!("shExpMatch(host, {})", js_string(pattern)));
+    }
+    // IPv6 entries can't be matched with isInNet
+    if entry.contains(':') && !entry.starts_with("domain:") {
+        return None;
+    }
+    let domain = entry.trim_start_matches("domain:").trim_start_matches('.');
+    Some(format!("host == {0} || dnsDomainIs(host, {1})", js_string(domain), js_string(&format!(".{}", domain))))
+}
+
+// "10.0.0.0/8" or "1.2.3.4" as (address, mask) for isInNet
+fn ipv4_network(entry: &str) -> Option<(Ipv4Addr, Ipv4Addr)> {
+    let (address, prefix) = match entry.split_once('/') {
+        Some((address, prefix)) => (address, prefix.parse::<u32>().ok().filter(|&p| p <= 32)?),
+        None => (entry, 32),
+    };
+    let address: Ipv4Addr = address.parse().ok()?;
+    let mask = if prefix == 0 { 0 } else { u32::MAX << (32 - prefix) };
+    Some((address, Ipv4Addr::from(mask)))
+}
+
+// Quoted and escaped for JavaScript
+fn js_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+/// Start, restart or stop serving the PAC file to match the settings, and
+/// register or unregister it as the system auto-config URL
+pub fn apply(settings: &PacSettings) {
+    let generation = PAC_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    if crate::sysproxy::pac_active() && !(settings.enabled && settings.register) {
+        if let Err(e) = crate::sysproxy::disable() {
+            eprintln!("Failed to unregister PAC file: {}", e);
+        }
+    }
+    if !settings.enabled {
+        return;
+    }
+
+    // The previous listener lets go of the port within one accept poll
+    let mut bound = TcpListener::bind(("127.0.0.1", settings.port));
+    for _ in 0..5 {
+        if bound.is_ok() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        bound = TcpListener::bind(("127.0.0.1", settings.port));
+    }
+    let listener = match bound {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to serve PAC file on port {}: {}", settings.port, e);
+            return;
+        }
+    };
+    if let Err(e) = listener.set_nonblocking(true) {
+        eprintln!("Failed to configure PAC listener: {}", e);
+        return;
+    }
+    println!("Serving PAC file at {}", url(settings.port));
+
+    let port = settings.port;
+    std::thread::spawn(move || {
+        while PAC_GENERATION.load(Ordering::SeqCst) == generation {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let _ = respond(stream);
+                }
+                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                Err(_) => std::thread::sleep(Duration::from_millis(100)),
+            }
+        }
+        println!("Stopped serving PAC file on port {}", port);
+    });
+
+    if settings.register {
+        if let Err(e) = crate::sysproxy::enable_pac(settings.port, &url(settings.port)) {
+            eprintln!("Failed to register PAC file: {}", e);
+        }
+    }
+    crate::request_menu_update();
+}
+
+// Answer one HTTP request: the PAC file on PAC_PATH, 404 otherwise
+fn respond(mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut buffer = [0u8; 2048];
+    let len = stream.read(&mut buffer)?;
+    let request = String::from_utf8_lossy(&buffer[..len]);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+
+    let response = if path.split('?').next() == Some(PAC_PATH) {
+        let settings = Config::load().unwrap_or_default().pac;
+        let target = crate::sysproxy::default_target();
+        let script = build(target.as_ref().map(|(port, proxy_type)| (*port, proxy_type.as_str())), &bypass_entries(&settings));
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/x-ns-proxy-autoconfig\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n{}",
+            script.len(),
+            script
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    stream.write_all(response.as_bytes())
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use crate::config::Config;
+use crate::vpn::VpnServer;
+use crate::xray_manager::StartRequest;
+
+// Reconciliation plan: what a subscription refresh does to the running servers.
+// A refresh builds the plan, has the core validate every config it would start,
+// then carries it out; Tools → Preview Refresh builds and validates the same plan
+// from freshly fetched subscriptions and only shows it.
+
+/// Changes that bring the running servers in line with a server list
+#[derive(Default)]
+pub struct Plan {
+    pub start: Vec<StartRequest>,       // Enabled, not running yet
+    pub restart: Vec<StartRequest>,     // Running, but their link changed; the config is regenerated
+    pub stop: Vec<(String, String)>,    // (key, name) running but gone from the subscriptions
+    pub invalid: Vec<(String, String)>, // (name, error) rejected by the core; left as they are
+}
+
+/// Plan the changes for `servers` (with their share links in `uris`) against the
+/// running servers and the links they were started from. Balanced groups are
+/// left to `balancer::sync_groups`.
+pub fn build(config: &Config, servers: &[VpnServer], uris: &HashMap<String, String>, running: &HashMap<String, String>) -> Plan {
+    let mut plan = Plan::default();
+    for key in running.keys() {
+        if !uris.contains_key(key) && !crate::balancer::is_group_key(key) {
+            let name = crate::xray_manager::get_server_info(key).map(|info| info.name).unwrap_or_else(|| key.clone());
+            plan.stop.push((key.clone(), name));
+        }
+    }
+    plan.stop.sort();
+
+    for server in servers.iter().filter(|s| s.enabled) {
+        let Some(request) = start_request(config, server, uris) else {
+            continue;
+        };
+        match running.get(&request.key) {
+            Some(old_uri) if *old_uri == request.uri => {}
+            Some(_) => plan.restart.push(request),
+            None => plan.start.push(request),
+        }
+    }
+    plan
+}
+
+/// How `server` starts: its saved settings (after `vpn::store_assigned_ports`) and share
+/// link. None without either.
+pub fn start_request(config: &Config, server: &VpnServer, uris: &HashMap<String, String>) -> Option<StartRequest> {
+    let server_key = server.get_server_key();
+    let settings = config.server_settings.get(&server_key)?;
+    let uri = uris.get(&server_key)?;
+    Some(StartRequest {
+        key: server_key,
+        name: server.display_name().to_string(),
+        uri: uri.clone(),
+        local_port: settings.local_port,
+        proxy_type: settings.proxy_type.clone(),
+        http_port: settings.http_port,
+        limits: settings.limits.clone(),
+    })
+}
+
+impl Plan {
+    /// Have the core check every config the plan would start, `concurrency` at a time.
+    /// Rejected servers move to `invalid`: a changed server keeps running its old
+    /// config instead of being stopped for one that can't start.
+    pub fn validate(&mut self, xray_binary_path: &str, concurrency: usize) {
+        let start = std::mem::take(&mut self.start);
+        self.start = reject_invalid(start, xray_binary_path, concurrency, &mut self.invalid);
+        let restart = std::mem::take(&mut self.restart);
+        self.restart = reject_invalid(restart, xray_binary_path, concurrency, &mut self.invalid);
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn is_empty(&self) -> bool {
+        self.start.is_empty() && self.restart.is_empty() && self.stop.is_empty() && self.invalid.is_empty()
+    }
+
+    /// The plan as text for a message box
+    #[cfg(feature = "gui")]
+    pub fn describe(&self) -> String {
+        if self.is_empty() {
+            return "Nothing would change: the running servers match the subscriptions.".to_string();
+        }
+        let mut sections = Vec::new();
+        let names = |requests: &[StartRequest]| -> Vec<String> {
+            requests.iter()
+                .map(|r| if r.local_port == 0 { format!("  {} (auto port)", r.name) } else { format!("  {} (port {})", r.name, r.local_port) })
+                .collect()
+        };
+        if !self.start.is_empty() {
+            sections.push(format!("Start {}:\n{}", self.start.len(), names(&self.start).join("\n")));
+        }
+        if !self.restart.is_empty() {
+            sections.push(format!("Regenerate config and restart {}:\n{}", self.restart.len(), names(&self.restart).join("\n")));
+        }
+        if !self.stop.is_empty() {
+            let stops: Vec<String> = self.stop.iter().map(|(_, name)| format!("  {}", name)).collect();
+            sections.push(format!("Stop {} (no longer in the subscriptions):\n{}", self.stop.len(), stops.join("\n")));
+        }
+        if !self.invalid.is_empty() {
+            let errors: Vec<String> = self.invalid.iter().map(|(name, e)| format!("  {}: {}", name, e)).collect();
+            sections.push(format!("Skipped, config rejected {}:\n{}", self.invalid.len(), errors.join("\n")));
+        }
+        sections.join("\n\n")
+    }
+}
+
+// Keep the requests whose config passes the core's test; record the others in `invalid`
+fn reject_invalid(
+    requests: Vec<StartRequest>,
+    xray_binary_path: &str,
+    concurrency: usize,
+    invalid: &mut Vec<(String, String)>,
+) -> Vec<StartRequest> {
+    let mut valid = Vec::new();
+    let mut requests = requests.into_iter().peekable();
+    while requests.peek().is_some() {
+        let batch: Vec<StartRequest> = requests.by_ref().take(concurrency.max(1)).collect();
+        let results: Vec<Result<(), String>> = std::thread::scope(|scope| {
+            let checks: Vec<_> = batch.iter()
+                .map(|request| scope.spawn(move || crate::xray_manager::validate_config(request, xray_binary_path)))
+                .collect();
+            checks.into_iter()
+                .map(|check| check.join().unwrap_or_else(|_| Err("config check panicked".to_string())))
+                .collect()
+        });
+        for (request, result) in batch.into_iter().zip(results) {
+            match result {
+                Ok(()) => valid.push(request),
+                Err(e) => {
+                    eprintln!("Config of {} rejected: {}", request.name, e);
+                    invalid.push((request.name, e));
+                }
+            }
+        }
+    }
+    valid
+}
+
+/// Fetch the subscriptions and plan what a refresh would do, without changing anything
+#[cfg(feature = "gui")]
+pub fn preview() -> Result<Plan, String> {
+    let mut config = Config::load()?;
+    if !config.has_server_sources() {
+        return Err("No subscriptions or manual servers are configured".to_string());
+    }
+    if config.xray_binary_path.is_empty() {
+        return Err("Set the xray binary path first".to_string());
+    }
+    let (mut servers, uris) = crate::vpn::fetch_servers_and_uris(&config.subscriptions, &config.manual_servers);
+    if servers.is_empty() {
+        return Err("The subscriptions returned no servers; a refresh would keep the current list".to_string());
+    }
+    crate::vpn::assign_local_ports(&mut servers, &config.server_settings, config.first_local_port());
+    // As a refresh would store them; the preview saves nothing
+    crate::vpn::store_assigned_ports(&mut config.server_settings, &servers);
+
+    let mut plan = build(&config, &servers, &uris, &crate::xray_manager::get_running_uris());
+    plan.validate(&config.xray_binary_path, config.start_concurrency);
+    Ok(plan)
+}
@@ -0,0 +1,283 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpListener};
+
+// Local port diagnostics. Servers listen on 127.0.0.1, but "localhost" resolves
+// to ::1 first on current Windows, so a program holding the same port on ::1
+// silently takes every app that connects by name. Before a server starts, both
+// loopback addresses are tried: a taken 127.0.0.1 fails the start naming the
+// process that holds it (from the TCP table), a taken ::1 only leaves a hint.
+// Servers sharing a port among themselves are caught earlier, by name: Save
+// refuses enabled servers set to the same port, and a start is refused when a
+// running server or another server of the same batch has one of its ports.
+// Servers set to pick their port automatically (port 0 in their settings) get
+// one from `free_port` at each start instead.
+
+#[cfg(windows)]
+#[link(name = "iphlpapi")]
+unsafe extern "system" {
+    fn GetExtendedTcpTable(table: *mut u8, size: *mut u32, order: i32, family: u32, class: u32, reserved: u32) -> u32;
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn OpenProcess(desired_access: u32, inherit_handle: i32, process_id: u32) -> *mut std::ffi::c_void;
+    fn QueryFullProcessImageNameW(process: *mut std::ffi::c_void, flags: u32, name: *mut u16, size: *mut u32) -> i32;
+    fn CloseHandle(handle: *mut std::ffi::c_void) -> i32;
+}
+
+/// A process listening on a local TCP port
+#[derive(Debug, Clone)]
+pub struct Listener {
+    pub address: IpAddr,
+    pub pid: u32,
+    pub process: Option<String>, // Executable name; None when it can't be read (e.g. a system process)
+}
+
+impl Listener {
+    /// "nginx.exe (PID 4120)"
+    pub fn owner(&self) -> String {
+        match &self.process {
+            Some(process) => format!("{} (PID {})", process, self.pid),
+            None => format!("PID {}", self.pid),
+        }
+    }
+}
+
+/// Processes listening on `port` on any local address, IPv4 and IPv6
+pub fn listeners(port: u16) -> Vec<Listener> {
+    #[cfg(windows)]
+    {
+        let mut listeners = tcp_listeners_v4(port);
+        listeners.extend(tcp_listeners_v6(port));
+        listeners
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = port;
+        Vec::new()
+    }
+}
+
+/// The program on the other end of a loopback connection: the process owning the
+/// socket at 127.0.0.1:`client_port` connected to `server_port`, as "python.exe
+/// (PID 4120)". None when it can't be found, and always off Windows.
+pub fn connecting_process(client_port: u16, server_port: u16) -> Option<String> {
+    #[cfg(windows)]
+    {
+        tcp_table::<TcpRow>(AF_INET, TCP_TABLE_OWNER_PID_CONNECTIONS).into_iter()
+            .find(|row| row_port(row.local_port) == client_port && row_port(row.remote_port) == server_port
+                && Ipv4Addr::from(u32::from_be(row.local_addr)).is_loopback())
+            .map(|row| Listener {
+                address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                pid: row.owning_pid,
+                process: process_name(row.owning_pid),
+            }.owner())
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = (client_port, server_port);
+        None
+    }
+}
+
+/// Check that a server can listen on 127.0.0.1:`port`. Fails naming the process holding
+/// it; Ok(Some(warning)) when the port is free there but taken on ::1.
+pub fn check_local_port(port: u16) -> Result<Option<String>, String> {
+    if TcpListener::bind((Ipv4Addr::LOCALHOST, port)).is_err() {
+        return Err(describe_conflict(port));
+    }
+    // Fine if this machine has no IPv6 loopback at all
+    let ipv6_taken = matches!(TcpListener::bind((Ipv6Addr::LOCALHOST, port)),
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse);
+    if !ipv6_taken {
+        return Ok(None);
+    }
+    let owner = listeners(port).into_iter()
+        .find(|listener| listener.address.is_ipv6())
+        .map(|listener| listener.owner())
+        .unwrap_or_else(|| "another program".to_string());
+    Ok(Some(format!(
+        "{} listens on [::1]:{} - apps that connect to localhost:{} reach it instead of this server; use 127.0.0.1 or another port",
+        owner, port, port
+    )))
+}
+
+/// Why a local port can't be used, naming the processes that hold it where possible
+pub fn describe_conflict(port: u16) -> String {
+    let holders: Vec<String> = listeners(port).iter()
+        .filter(|listener| listener.address.is_loopback() || listener.address.is_unspecified())
+        .map(|listener| {
+            let address = match listener.address {
+                IpAddr::V4(address) => format!("{}:{}", address, port),
+                IpAddr::V6(address) => format!("[{}]:{}", address, port),
+            };
+            format!("{} on {}", listener.owner(), address)
+        })
+        .collect();
+    if holders.is_empty() {
+        format!("Local port {} is already in use - choose another port or close the program using it", port)
+    } else {
+        format!("Local port {} is already in use by {} - choose another port or close it", port, holders.join(", "))
+    }
+}
+
+/// Local ports a server listens on: its port, plus the HTTP one with proxy type "Both"
+pub fn server_ports(local_port: u16, proxy_type: &str, http_port: u16) -> Vec<u16> {
+    let ports = if proxy_type == "Both" && http_port != local_port {
+        vec![local_port, http_port]
+    } else {
+        vec![local_port]
+    };
+    // 0 = picked at start, so it can't clash with anything
+    ports.into_iter().filter(|&port| port != 0).collect()
+}
+
+/// A port free on both loopback addresses right now, from the system's ephemeral range.
+/// Whoever binds it next wins, so it's handed straight to the core that listens on it.
+pub fn free_port(exclude: &[u16]) -> Result<u16, String> {
+    for _ in 0..10 {
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .and_then(|listener| listener.local_addr())
+            .map(|address| address.port())
+            .map_err(|e| format!("No free local port: {}", e))?;
+        // The listener is gone again; check the port the way a start would
+        if !exclude.contains(&port) && matches!(check_local_port(port), Ok(None)) {
+            return Ok(port);
+        }
+    }
+    Err("No free local port found after 10 tries".to_string())
+}
+
+/// Ports more than one server is set to, with the names of those servers, by port
+pub fn duplicates<'a>(servers: impl IntoIterator<Item = (&'a str, Vec<u16>)>) -> Vec<(u16, Vec<String>)> {
+    let mut users: std::collections::BTreeMap<u16, Vec<String>> = std::collections::BTreeMap::new();
+    for (name, ports) in servers {
+        for port in ports {
+            users.entry(port).or_default().push(name.to_string());
+        }
+    }
+    users.into_iter().filter(|(_, names)| names.len() > 1).collect()
+}
+
+/// "Port 1080 is set for both Tokyo and Osaka", one line per port
+pub fn describe_duplicates(duplicates: &[(u16, Vec<String>)]) -> String {
+    duplicates.iter()
+        .map(|(port, names)| match names.as_slice() {
+            [first, second] => format!("Port {} is set for both {} and {}", port, first, second),
+            _ => format!("Port {} is set for {} servers: {}", port, names.len(), names.join(", ")),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// TCP_TABLE_OWNER_PID_* table classes and the address families they are asked for
+#[cfg(windows)]
+const TCP_TABLE_OWNER_PID_LISTENER: u32 = 3;
+#[cfg(windows)]
+const TCP_TABLE_OWNER_PID_CONNECTIONS: u32 = 4;
+#[cfg(windows)]
+const AF_INET: u32 = 2;
+#[cfg(windows)]
+const AF_INET6: u32 = 23;
+
+// Rows of MIB_TCPTABLE_OWNER_PID / MIB_TCP6TABLE_OWNER_PID
+#[cfg(windows)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct TcpRow {
+    state: u32,
+    local_addr: u32,
+    local_port: u32,
+    remote_addr: u32,
+    remote_port: u32,
+    owning_pid: u32,
+}
+
+#[cfg(windows)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Tcp6Row {
+    local_addr: [u8; 16],
+    local_scope_id: u32,
+    local_port: u32,
+    remote_addr: [u8; 16],
+    remote_scope_id: u32,
+    remote_port: u32,
+    state: u32,
+    owning_pid: u32,
+}
+
+// Rows of one table class and address family: the table is a u32 count followed by the rows
+#[cfg(windows)]
+fn tcp_table<Row: Copy>(family: u32, class: u32) -> Vec<Row> {
+    let mut size = 0u32;
+    unsafe { GetExtendedTcpTable(std::ptr::null_mut(), &mut size, 0, family, class, 0) };
+    // Rows may appear between the two calls; leave some room
+    size += 16 * std::mem::size_of::<Row>() as u32;
+    let mut buffer = vec![0u32; (size as usize).div_ceil(4)];
+    let result = unsafe {
+        GetExtendedTcpTable(buffer.as_mut_ptr().cast(), &mut size, 0, family, class, 0)
+    };
+    if result != 0 {
+        return Vec::new();
+    }
+    let count = buffer[0] as usize;
+    // Rows start after the count, at the row's alignment (4 for both kinds)
+    let rows = unsafe { buffer.as_ptr().add(1).cast::<Row>() };
+    let available = (buffer.len() - 1) * 4 / std::mem::size_of::<Row>();
+    (0..count.min(available)).map(|i| unsafe { *rows.add(i) }).collect()
+}
+
+// Ports are stored in network byte order in the low 16 bits
+#[cfg(windows)]
+fn row_port(raw: u32) -> u16 {
+    u16::from_be(raw as u16)
+}
+
+#[cfg(windows)]
+fn tcp_listeners_v4(port: u16) -> Vec<Listener> {
+    tcp_table::<TcpRow>(AF_INET, TCP_TABLE_OWNER_PID_LISTENER).into_iter()
+        .filter(|row| row_port(row.local_port) == port)
+        .map(|row| Listener {
+            address: IpAddr::V4(Ipv4Addr::from(u32::from_be(row.local_addr))),
+            pid: row.owning_pid,
+            process: process_name(row.owning_pid),
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn tcp_listeners_v6(port: u16) -> Vec<Listener> {
+    tcp_table::<Tcp6Row>(AF_INET6, TCP_TABLE_OWNER_PID_LISTENER).into_iter()
+        .filter(|row| row_port(row.local_port) == port)
+        .map(|row| Listener {
+            address: IpAddr::V6(Ipv6Addr::from(row.local_addr)),
+            pid: row.owning_pid,
+            process: process_name(row.owning_pid),
+        })
+        .collect()
+}
+
+// Executable file name of a process, e.g. "nginx.exe"
+#[cfg(windows)]
+fn process_name(pid: u32) -> Option<String> {
+    const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+    if pid == 0 {
+        return None;
+    }
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return None;
+        }
+        let mut buffer = [0u16; 1024];
+        let mut size = buffer.len() as u32;
+        let ok = QueryFullProcessImageNameW(handle, 0, buffer.as_mut_ptr(), &mut size) != 0;
+        CloseHandle(handle);
+        if !ok {
+            return None;
+        }
+        let path = String::from_utf16_lossy(&buffer[..size as usize]);
+        path.rsplit('\\').next().map(str::to_string)
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::config::{Config, PrimarySettings, RotationSettings};
+use crate::events::{self, Event};
+
+// Primary port: a stable local port that relays each connection to the local
+// port of one running server. Switching the target only affects new connections,
+// so rotation never cuts connections that are already open.
+
+// Bumped on every apply(); a listener thread exits once its generation is stale
+static PRIMARY_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// Settings used for new connections; updated by apply() without rebinding the port
+static SETTINGS: LazyLock<Mutex<(PrimarySettings, RotationSettings)>> =
+    LazyLock::new(|| Mutex::new((PrimarySettings::default(), RotationSettings::default())));
+
+// Port the listener thread is bound to, and the thread itself
+static LISTENER: Mutex<Option<(u16, JoinHandle<()>)>> = Mutex::new(None);
+
+// Server key the primary port currently relays to
+static CURRENT_TARGET: Mutex<Option<String>> = Mutex::new(None);
+
+// Sticky sessions: client program -> (server key, time of last connection). The port
+// only listens on 127.0.0.1, so the peer address can't tell clients apart; the
+// program owning the connecting socket (ports::connecting_process) can.
+static STICKY_CLIENTS: LazyLock<Mutex<HashMap<String, (String, Instant)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Servers with more failures than this in their recent checks are skipped
+const MAX_ERROR_RATE: f32 = 0.5;
+
+/// How the primary port picks its server after a refresh, as saved in the config:
+/// keep the preferred/healthiest one, test all and take the quickest, or take any
+#[cfg(feature = "gui")]
+pub const POLICIES: &[&str] = &["manual", "fastest", "random"];
+
+/// Start, restart or stop the primary port listener to match the config.
+/// The listener is only rebound when the port changes; other settings apply to new connections.
+pub fn apply(config: &Config) {
+    let primary = config.primary.clone();
+    if let Ok(mut settings) = SETTINGS.lock() {
+        *settings = (primary.clone(), config.rotation.clone());
+    }
+
+    let Ok(mut listener_slot) = LISTENER.lock() else {
+        return;
+    };
+    if primary.enabled && matches!(listener_slot.as_ref(), Some((port, _)) if *port == primary.port) {
+        resolve_target(&primary);
+        select_in_background(&primary);
+        crate::request_menu_update();
+        return;
+    }
+
+    // Stop the old listener and wait for it to release its port
+    let generation = PRIMARY_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    if let Some((old_port, handle)) = listener_slot.take() {
+        let _ = handle.join();
+        // A system proxy pointing here would otherwise lead nowhere
+        if !primary.enabled || old_port != primary.port {
+            crate::sysproxy::port_closed(old_port);
+        }
+    }
+
+    if !primary.enabled {
+        return;
+    }
+
+    let listener = match TcpListener::bind(("127.0.0.1", primary.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to bind primary port {}: {}", primary.port, e);
+            return;
+        }
+    };
+    if let Err(e) = listener.set_nonblocking(true) {
+        eprintln!("Failed to configure primary port listener: {}", e);
+        return;
+    }
+
+    println!("Primary port listening on 127.0.0.1:{} ({})", primary.port, primary.proxy_type);
+    resolve_target(&primary);
+    select_in_background(&primary);
+
+    let handle = std::thread::spawn(move || {
+        while PRIMARY_GENERATION.load(Ordering::SeqCst) == generation {
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    std::thread::spawn(move || {
+                        let (primary, rotation) = current_settings();
+                        handle_client(stream, peer, &primary, &rotation);
+                    });
+                }
+                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                Err(e) => {
+                    eprintln!("Primary port accept failed: {}", e);
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+            }
+        }
+        println!("Primary port listener stopped");
+    });
+    *listener_slot = Some((primary.port, handle));
+}
+
+/// Pin the primary port to servers in one country (empty = any) and save it
+#[cfg(feature = "gui")]
+pub fn set_country(country: &str) -> Result<(), String> {
+    let mut config = Config::load()?;
+    config.primary.country = country.to_string();
+    config.save()?;
+    apply(&config);
+    Ok(())
+}
+
+fn current_settings() -> (PrimarySettings, RotationSettings) {
+    SETTINGS.lock()
+        .map(|settings| settings.clone())
+        .unwrap_or_default()
+}
+
+/// Server key the primary port relays to, if any
+pub fn get_target() -> Option<String> {
+    CURRENT_TARGET.lock().ok()?.clone()
+}
+
+/// Point the primary port at a running server (new connections only)
+pub fn set_target(server_key: &str) {
+    let changed = if let Ok(mut target) = CURRENT_TARGET.lock() {
+        let changed = target.as_deref() != Some(server_key);
+        *target = Some(server_key.to_string());
+        changed
+    } else {
+        false
+    };
+
+    if changed {
+        if let Some(info) = crate::xray_manager::get_server_info(server_key) {
+            println!("Primary port now uses {}", info.name);
+            events::emit(Event::PrimaryChanged(info));
+        }
+        crate::request_menu_update();
+    }
+}
+
+/// Running servers that can serve the primary port: same proxy type, in the pinned country,
+/// within the rate limit, not failing and without an inbound login. Cheapest first, then by key.
+pub fn candidates(primary: &PrimarySettings) -> Vec<String> {
+    let mut keys: Vec<String> = crate::xray_manager::get_running_servers()
+        .into_iter()
+        .filter(|key| {
+            crate::xray_manager::get_server_info(key)
+                // A SOCKS + HTTP server's main port is its SOCKS one
+                .map(|info| info.proxy_type == primary.proxy_type
+                    || (info.proxy_type == "Both" && primary.proxy_type == "SOCKS"))
+                .unwrap_or(false)
+        })
+        // Apps on the primary port can't give an inbound's login
+        .filter(|key| {
+            crate::xray_manager::get_server_info(key)
+                .is_some_and(|info| crate::inbounds::credentials(info.local_port).is_none())
+        })
+        .filter(|key| primary.country.is_empty() || crate::vpn::get_server_country(key) == primary.country)
+        .filter(|key| primary.max_rate <= 0.0 || crate::vpn::get_server_rate(key) <= primary.max_rate)
+        .filter(|key| {
+            crate::health::get_summary(key)
+                .map(|h| h.error_rate <= MAX_ERROR_RATE)
+                .unwrap_or(true)
+        })
+        .collect();
+    keys.sort_by(|a, b| {
+        crate::vpn::get_server_rate(a).total_cmp(&crate::vpn::get_server_rate(b)).then_with(|| a.cmp(b))
+    });
+    keys
+}
+
+// Keep the current target if it's still usable, otherwise pick the preferred or healthiest server
+fn resolve_target(primary: &PrimarySettings) -> Option<String> {
+    let candidates = candidates(primary);
+    if let Some(current) = get_target() {
+        if candidates.contains(&current) {
+            return Some(current);
+        }
+    }
+    if candidates.is_empty() {
+        // Nothing usable (e.g. no server in the pinned country); don't advertise a stale target
+        if let Ok(mut target) = CURRENT_TARGET.lock() {
+            if target.take().is_some() {
+                crate::request_menu_update();
+            }
+        }
+        return None;
+    }
+
+    let chosen = if primary.policy == "random" {
+        Some(candidates[(crate::secret::random_u64() % candidates.len() as u64) as usize].clone())
+    } else if primary.policy == "manual" && candidates.contains(&primary.server) {
+        Some(primary.server.clone())
+    } else {
+        crate::health::best_server(&candidates).or_else(|| candidates.first().cloned())
+    }?;
+    set_target(&chosen);
+    Some(chosen)
+}
+
+/// Pick the primary port's server by the selection policy; call after servers were
+/// (re)started. "fastest" tests every candidate now and takes the quickest answer,
+/// "random" takes any candidate, "manual" keeps the current choice.
+pub fn auto_select(primary: &PrimarySettings) -> Option<String> {
+    if !primary.enabled {
+        return None;
+    }
+    let candidates = candidates(primary);
+    let chosen = match primary.policy.as_str() {
+        "fastest" => {
+            // Tested side by side so a few dead servers don't add up their timeouts
+            let results: Vec<(String, Result<Duration, String>)> = std::thread::scope(|scope| {
+                let checks: Vec<_> = candidates.iter()
+                    .map(|key| scope.spawn(move || (key.clone(), crate::health::check_server(key))))
+                    .collect();
+                checks.into_iter().filter_map(|check| check.join().ok()).collect()
+            });
+            let fastest = results.into_iter()
+                .filter_map(|(key, result)| result.ok().map(|latency| (key, latency)))
+                .min_by_key(|(_, latency)| *latency);
+            match fastest {
+                Some((key, latency)) => {
+                    println!("Fastest server for the primary port: {} ({} ms)", key, latency.as_millis());
+                    key
+                }
+                None => {
+                    eprintln!("No primary port candidate answered the latency test");
+                    return resolve_target(primary);
+                }
+            }
+        }
+        "random" if !candidates.is_empty() => {
+            candidates[(crate::secret::random_u64() % candidates.len() as u64) as usize].clone()
+        }
+        _ => return resolve_target(primary),
+    };
+    set_target(&chosen);
+    Some(chosen)
+}
+
+// Apply the policy without holding up the caller (the settings window or startup)
+fn select_in_background(primary: &PrimarySettings) {
+    if primary.policy == "manual" {
+        return;
+    }
+    let primary = primary.clone();
+    std::thread::spawn(move || {
+        auto_select(&primary);
+    });
+}
+
+/// Switch the primary port to another candidate server
+pub fn rotate(primary: &PrimarySettings, rotation: &RotationSettings) -> Option<String> {
+    let candidates = candidates(primary);
+    let current = get_target();
+    let others: Vec<&String> = candidates.iter()
+        .filter(|key| Some(*key) != current.as_ref())
+        .collect();
+    if others.is_empty() {
+        return None;
+    }
+
+    let next = if rotation.mode == "random" {
+        others[(crate::secret::random_u64() % others.len() as u64) as usize].clone()
+    } else {
+        // Round-robin in candidate order, continuing after the current server
+        let position = current.as_ref()
+            .and_then(|c| candidates.iter().position(|k| k == c))
+            .unwrap_or(candidates.len() - 1);
+        candidates.iter()
+            .cycle()
+            .skip(position + 1)
+            .find(|key| Some(*key) != current.as_ref())
+            .cloned()?
+    };
+
+    set_target(&next);
+    Some(next)
+}
+
+/// Rotate the primary port on the configured schedule (config re-read every 30s)
+pub fn start_rotation_timer() {
+    std::thread::spawn(|| {
+        let mut last_rotation = Instant::now();
+        loop {
+            crate::idle::sleep(Duration::from_secs(30));
+
+            let config = Config::load().unwrap_or_default();
+            let interval = Duration::from_secs(config.rotation.interval_minutes.max(1) * 60);
+            if !config.primary.enabled || !config.rotation.enabled || last_rotation.elapsed() < interval {
+                continue;
+            }
+
+            rotate(&config.primary, &config.rotation);
+            last_rotation = Instant::now();
+
+            // Forget expired sticky assignments
+            let sticky_for = Duration::from_secs(config.rotation.sticky_minutes * 60);
+            if let Ok(mut sticky) = STICKY_CLIENTS.lock() {
+                sticky.retain(|_, (_, last_seen)| last_seen.elapsed() < sticky_for);
+            }
+        }
+    });
+}
+
+fn handle_client(client: TcpStream, peer: SocketAddr, primary: &PrimarySettings, rotation: &RotationSettings) {
+    let _ = client.set_nonblocking(false);
+
+    let target = if rotation.sticky_sessions {
+        sticky_target(peer, primary, rotation)
+    } else {
+        resolve_target(primary)
+    };
+    let Some(info) = target.and_then(|key| crate::xray_manager::get_server_info(&key)) else {
+        if primary.country.is_empty() {
+            eprintln!("Primary port: no running {} server to relay to", primary.proxy_type);
+        } else {
+            eprintln!("Primary port: no running {} server in {} to relay to", primary.proxy_type, primary.country);
+        }
+        return;
+    };
+
+    let upstream = match TcpStream::connect(("127.0.0.1", info.local_port)) {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            eprintln!("Primary port: failed to reach {} on port {}: {}", info.name, info.local_port, e);
+            return;
+        }
+    };
+
+    relay(client, upstream);
+}
+
+// Reuse the server this client program was given recently, if it's still usable.
+// A connection whose program can't be found (or off Windows) gets the current target.
+fn sticky_target(peer: SocketAddr, primary: &PrimarySettings, rotation: &RotationSettings) -> Option<String> {
+    let Some(client) = crate::ports::connecting_process(peer.port(), primary.port) else {
+        return resolve_target(primary);
+    };
+    let sticky_for = Duration::from_secs(rotation.sticky_minutes * 60);
+    let candidates = candidates(primary);
+
+    if let Ok(mut sticky) = STICKY_CLIENTS.lock() {
+        if let Some((key, last_seen)) = sticky.get_mut(&client) {
+            if last_seen.elapsed() < sticky_for && candidates.contains(key) {
+                *last_seen = Instant::now();
+                return Some(key.clone());
+            }
+        }
+    }
+
+    let key = resolve_target(primary)?;
+    if let Ok(mut sticky) = STICKY_CLIENTS.lock() {
+        sticky.insert(client, (key.clone(), Instant::now()));
+    }
+    Some(key)
+}
+
+// Copy bytes both ways until either side closes
+fn relay(client: TcpStream, upstream: TcpStream) {
+    let (Ok(mut client_read), Ok(mut upstream_write)) = (client.try_clone(), upstream.try_clone()) else {
+        return;
+    };
+    let forward = std::thread::spawn(move || {
+        let _ = std::io::copy(&mut client_read, &mut upstream_write);
+        let _ = upstream_write.shutdown(Shutdown::Write);
+    });
+
+    let (mut upstream_read, mut client_write) = (upstream, client);
+    let _ = std::io::copy(&mut upstream_read, &mut client_write);
+    let _ = client_write.shutdown(Shutdown::Write);
+    let _ = forward.join();
+}
@@ -0,0 +1,246 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+/// Check that a test URL can be used by `test_via_proxy`
+#[cfg(feature = "gui")]
+pub fn validate_test_url(url: &str) -> Result<(), String> {
+    if url.starts_with("https://") {
+        return reqwest::Url::parse(url)
+            .ok()
+            .filter(|parsed| parsed.host_str().is_some())
+            .map(|_| ())
+            .ok_or_else(|| format!("Invalid test URL {}", url));
+    }
+    parse_http_url(url).map(|_| ())
+}
+
+/// Fetch `url` through the local proxy and return the time until the response status arrived.
+/// http:// URLs are requested directly on the socket; https:// ones go through reqwest,
+/// so their time includes the TLS handshake with the target.
+pub fn test_via_proxy(local_port: u16, proxy_type: &str, url: &str, timeout: Duration) -> Result<Duration, String> {
+    if url.starts_with("https://") {
+        return test_https_via_proxy(local_port, proxy_type, url, timeout);
+    }
+    let (host, port, path) = parse_http_url(url)?;
+
+    let started = Instant::now();
+    let proxy_addr = SocketAddr::from(([127, 0, 0, 1], local_port));
+    let mut stream = TcpStream::connect_timeout(&proxy_addr, timeout)
+        .map_err(|e| format!("Local proxy on port {} not reachable: {}", local_port, e))?;
+    stream.set_read_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+
+    let login = crate::inbounds::credentials(local_port);
+    let request = if proxy_type == "HTTP" {
+        // HTTP proxies take the absolute URL in the request line
+        let authorization = login.map(|(user, pass)| {
+            use base64::Engine;
+            let token = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, pass));
+            format!("Proxy-Authorization: Basic {}\r\n", token)
+        }).unwrap_or_default();
+        format!("GET {} HTTP/1.1\r\nHost: {}\r\n{}Connection: close\r\n\r\n", url, host, authorization)
+    } else {
+        socks5_connect(&mut stream, &host, port, login.as_ref())?;
+        format!("GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", path, host)
+    };
+    stream.write_all(request.as_bytes()).map_err(|e| format!("Request failed: {}", e))?;
+
+    let mut status_line = String::new();
+    BufReader::new(&mut stream).read_line(&mut status_line)
+        .map_err(|e| format!("No response through proxy: {}", e))?;
+    let elapsed = started.elapsed();
+
+    let status: u16 = status_line.split_whitespace().nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| "Invalid HTTP response through proxy".to_string())?;
+    if (200..400).contains(&status) {
+        Ok(elapsed)
+    } else {
+        Err(format!("Test URL returned HTTP {}", status))
+    }
+}
+
+/// Fetch `url` without any proxy: the baseline of the home connection stored with test results
+#[cfg_attr(not(feature = "stats"), allow(dead_code))]
+pub fn test_direct(url: &str, timeout: Duration) -> Result<Duration, String> {
+    let client = reqwest::blocking::Client::builder()
+        .no_proxy()
+        .timeout(timeout)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let started = Instant::now();
+    let response = client.get(url).send()
+        .map_err(|e| format!("No response: {}", e))?;
+    let elapsed = started.elapsed();
+
+    let status = response.status().as_u16();
+    if (200..400).contains(&status) {
+        Ok(elapsed)
+    } else {
+        Err(format!("Test URL returned HTTP {}", status))
+    }
+}
+
+/// HTTP client going through a local proxy (names resolved by the proxy, redirects not followed)
+pub fn proxied_client(local_port: u16, proxy_type: &str, timeout: Duration) -> Result<reqwest::blocking::Client, String> {
+    let proxy_url = if proxy_type == "HTTP" {
+        format!("http://127.0.0.1:{}", local_port)
+    } else {
+        format!("socks5h://127.0.0.1:{}", local_port)
+    };
+    let mut proxy_url = reqwest::Url::parse(&proxy_url).map_err(|e| format!("Invalid proxy {}: {}", proxy_url, e))?;
+    // The login of an inbound shared with the LAN, percent-encoded into the URL
+    if let Some((user, pass)) = crate::inbounds::credentials(local_port) {
+        let _ = proxy_url.set_username(&user);
+        let _ = proxy_url.set_password(Some(&pass));
+    }
+    let proxy = reqwest::Proxy::all(proxy_url.clone())
+        .map_err(|e| format!("Invalid proxy {}: {}", proxy_url, e))?;
+    reqwest::blocking::Client::builder()
+        .proxy(proxy)
+        .timeout(timeout)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+// https:// test through the local proxy
+fn test_https_via_proxy(local_port: u16, proxy_type: &str, url: &str, timeout: Duration) -> Result<Duration, String> {
+    let client = proxied_client(local_port, proxy_type, timeout)?;
+
+    let started = Instant::now();
+    let response = client.get(url).send()
+        .map_err(|e| format!("No response through proxy: {}", e))?;
+    let elapsed = started.elapsed();
+
+    let status = response.status().as_u16();
+    if (200..400).contains(&status) {
+        Ok(elapsed)
+    } else {
+        Err(format!("Test URL returned HTTP {}", status))
+    }
+}
+
+/// Test every running server with the configured URL, in parallel.
+/// Returns (server key, result) pairs, fastest first and failures last.
+#[cfg(any(feature = "gui", feature = "api"))]
+pub fn test_running_servers() -> Vec<(String, Result<Duration, String>)> {
+    let keys = crate::xray_manager::get_running_servers();
+    let mut results: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = keys.iter()
+            .map(|key| scope.spawn(move || (key.clone(), crate::health::check_server(key))))
+            .collect();
+        handles.into_iter().filter_map(|handle| handle.join().ok()).collect()
+    });
+    results.sort_by_key(|(key, result)| (result.as_ref().map_or(Duration::MAX, |latency| *latency), key.clone()));
+    #[cfg(feature = "stats")]
+    crate::results::record_run(crate::results::RunKind::Connectivity, results.iter()
+        .map(|(key, result)| {
+            let ms = result.as_ref().ok().map(|latency| latency.as_millis() as u32);
+            (key.clone(), crate::results::Measurement { ms, proxy: true })
+        })
+        .collect());
+    results
+}
+
+/// Readable report of `test_running_servers` results, one line per server
+#[cfg(feature = "gui")]
+pub fn format_report(results: &[(String, Result<Duration, String>)]) -> String {
+    if results.is_empty() {
+        return "No servers are running.".to_string();
+    }
+    let url = crate::config::Config::load().unwrap_or_default().test.url;
+    let lines: Vec<String> = results.iter()
+        .map(|(key, result)| {
+            let name = crate::vpn::get_server_name(key);
+            let port = crate::xray_manager::get_server_info(key).map(|info| info.local_port).unwrap_or(0);
+            let exit_ip = crate::exitip::observed(key)
+                .map(|observed| format!(", exits from {}", observed.ip))
+                .unwrap_or_default();
+            match result {
+                Ok(latency) => format!("✓ {} (port {}): {} ms{}", name, port, latency.as_millis(), exit_ip),
+                Err(e) => format!("✗ {} (port {}): {}", name, port, e),
+            }
+        })
+        .collect();
+    format!("Fetching {} through each running server:\n\n{}", url, lines.join("\n"))
+}
+
+// Split "http://host[:port]/path" into parts
+fn parse_http_url(url: &str) -> Result<(String, u16, String), String> {
+    let rest = url.strip_prefix("http://")
+        .ok_or_else(|| format!("Test URL must start with http:// or https:// ({})", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => (&rest[..idx], &rest[idx..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse::<u16>().map_err(|_| format!("Invalid port in {}", url))?),
+        None => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err(format!("Missing host in {}", url));
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+// Minimal SOCKS5 CONNECT (no auth or user/password, domain address type)
+fn socks5_connect(stream: &mut TcpStream, host: &str, port: u16, login: Option<&(String, String)>) -> Result<(), String> {
+    let io_err = |e: std::io::Error| format!("SOCKS handshake failed: {}", e);
+
+    let greeting: &[u8] = if login.is_some() { &[0x05, 0x02, 0x00, 0x02] } else { &[0x05, 0x01, 0x00] };
+    stream.write_all(greeting).map_err(io_err)?;
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).map_err(io_err)?;
+    match (reply, login) {
+        ([0x05, 0x00], _) => {}
+        // RFC 1929 user/password subnegotiation
+        ([0x05, 0x02], Some((user, pass))) => {
+            if user.len() > 255 || pass.len() > 255 {
+                return Err("SOCKS user name or password too long".to_string());
+            }
+            let mut request = vec![0x01, user.len() as u8];
+            request.extend_from_slice(user.as_bytes());
+            request.push(pass.len() as u8);
+            request.extend_from_slice(pass.as_bytes());
+            stream.write_all(&request).map_err(io_err)?;
+            stream.read_exact(&mut reply).map_err(io_err)?;
+            if reply[1] != 0x00 {
+                return Err("SOCKS proxy rejected the user name and password".to_string());
+            }
+        }
+        _ => return Err("SOCKS proxy requires unsupported authentication".to_string()),
+    }
+
+    if host.len() > 255 {
+        return Err("Host name too long for SOCKS".to_string());
+    }
+    let mut request = vec![0x05, 0x01, 0x00, 0x03, host.len() as u8];
+    request.extend_from_slice(host.as_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).map_err(io_err)?;
+
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).map_err(io_err)?;
+    if header[1] != 0x00 {
+        return Err(format!("SOCKS connect failed (code {})", header[1]));
+    }
+
+    // Skip the bound address
+    let addr_len = match header[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).map_err(io_err)?;
+            len[0] as usize
+        }
+        other => return Err(format!("SOCKS reply has unknown address type {}", other)),
+    };
+    let mut bound = vec![0u8; addr_len + 2];
+    stream.read_exact(&mut bound).map_err(io_err)?;
+    Ok(())
+}
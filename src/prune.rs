@@ -0,0 +1,109 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+use crate::vpn::VpnServer;
+
+// Stale server settings: `server_settings` keeps an entry for every server that
+// ever had one, so churning subscriptions grow it without end. Each fetched list
+// stamps the keys it has in settings-seen.json next to the config; a saved key
+// missing from it starts its clock the first time it is looked at. With
+// `settings_retention_days` (default 30, 0 = never) entries missing for longer
+// are dropped after every fetch; Tools → Clean Up Server Settings... removes
+// them on demand with any age. Balanced groups' keys are never touched.
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+fn seen_path() -> Option<PathBuf> {
+    let config_path = Config::get_config_path().ok()?;
+    Some(config_path.parent()?.join("settings-seen.json"))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// Server key -> Unix seconds it was last in a fetched list
+fn load() -> HashMap<String, u64> {
+    seen_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(seen: &HashMap<String, u64>) {
+    let Some(path) = seen_path() else {
+        return;
+    };
+    match serde_json::to_string_pretty(seen) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                eprintln!("Failed to save server last-seen times: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize server last-seen times: {}", e),
+    }
+}
+
+/// Stamp the servers of a fetched list as seen now; an empty list (failed fetch) is ignored
+pub fn mark_seen(config: &Config, servers: &[VpnServer]) {
+    if servers.is_empty() {
+        return;
+    }
+    let now = now_secs();
+    let listed: HashSet<String> = servers.iter().map(|server| server.get_server_key()).collect();
+    let mut seen = load();
+    for key in &listed {
+        seen.insert(key.clone(), now);
+    }
+    for key in config.server_settings.keys() {
+        seen.entry(key.clone()).or_insert(now);
+    }
+    // Nothing to remember for keys that are neither listed nor saved
+    seen.retain(|key, _| config.server_settings.contains_key(key) || listed.contains(key));
+    save(&seen);
+}
+
+/// Saved settings keys not in a fetched list for at least `days` days (0 = missing now), sorted
+pub fn stale_keys(config: &Config, servers: &[VpnServer], days: u32) -> Vec<String> {
+    let seen = load();
+    let listed: HashSet<String> = servers.iter().map(|server| server.get_server_key()).collect();
+    let cutoff = now_secs().saturating_sub(days as u64 * DAY_SECS);
+    let mut keys: Vec<String> = config.server_settings.keys()
+        .filter(|key| !crate::balancer::is_group_key(key) && !listed.contains(*key))
+        .filter(|key| seen.get(*key).is_some_and(|&last| last <= cutoff))
+        .cloned()
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Remove the given keys' settings and save the config
+pub fn remove(config: &mut Config, keys: &[String]) -> Result<(), String> {
+    for key in keys {
+        config.server_settings.remove(key);
+    }
+    config.save()?;
+    for key in keys {
+        println!("Removed settings of {}, missing from the subscriptions", key);
+    }
+    Ok(())
+}
+
+/// After a fetch: stamp the listed servers, then drop settings missing past the retention
+pub fn after_fetch(config: &mut Config, servers: &[VpnServer]) {
+    if servers.is_empty() {
+        return;
+    }
+    mark_seen(config, servers);
+    if config.settings_retention_days == 0 {
+        return;
+    }
+    let stale = stale_keys(config, servers, config.settings_retention_days);
+    if !stale.is_empty() {
+        if let Err(e) = remove(config, &stale) {
+            eprintln!("Failed to clean up server settings: {}", e);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// QR import: find share links in QR codes on screen. Mobile clients share
+// servers as QR codes, so a screenshot of the desktop (all monitors) is
+// searched for codes and any supported links in them are returned.
+
+/// Share links decoded from QR codes currently visible on any monitor
+#[cfg(windows)]
+pub fn scan_screen() -> Result<Vec<String>, String> {
+    let (width, height, luma) = unsafe { capture_screen()? };
+    Ok(decode_links(width, height, &luma))
+}
+
+/// Decode every QR code in a greyscale image and keep the ones holding share links
+pub fn decode_links(width: usize, height: usize, luma: &[u8]) -> Vec<String> {
+    let mut image = rqrr::PreparedImage::prepare_from_greyscale(width, height, |x, y| luma[y * width + x]);
+    let mut links = Vec::new();
+    for grid in image.detect_grids() {
+        match grid.decode() {
+            Ok((_, content)) => {
+                // A code may hold a single link or a whole list
+                for line in content.lines().map(str::trim) {
+                    if crate::vpn::parse_manual_uri(line).is_ok() && !links.iter().any(|l| l == line) {
+                        links.push(line.to_string());
+                    }
+                }
+            }
+            Err(e) => eprintln!("Found a QR code but could not decode it: {}", e),
+        }
+    }
+    links
+}
+
+// Copy the virtual screen (all monitors) into a greyscale buffer
+#[cfg(windows)]
+unsafe fn capture_screen() -> Result<(usize, usize, Vec<u8>), String> {
+    use windows::Win32::Graphics::Gdi::*;
+    use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN};
+
+    unsafe {
+        let left = GetSystemMetrics(SM_XVIRTUALSCREEN);
+        let top = GetSystemMetrics(SM_YVIRTUALSCREEN);
+        let width = GetSystemMetrics(SM_CXVIRTUALSCREEN);
+        let height = GetSystemMetrics(SM_CYVIRTUALSCREEN);
+        if width <= 0 || height <= 0 {
+            return Err("Could not determine the screen size".to_string());
+        }
+
+        let screen_dc = GetDC(None);
+        let memory_dc = CreateCompatibleDC(screen_dc);
+        let bitmap = CreateCompatibleBitmap(screen_dc, width, height);
+        let previous = SelectObject(memory_dc, bitmap);
+        let copied = BitBlt(memory_dc, 0, 0, width, height, screen_dc, left, top, SRCCOPY | CAPTUREBLT);
+
+        // Top-down 32-bit BGRA rows
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        let lines = if copied.is_ok() {
+            GetDIBits(memory_dc, bitmap, 0, height as u32, Some(pixels.as_mut_ptr() as *mut _), &mut info, DIB_RGB_COLORS)
+        } else {
+            0
+        };
+
+        SelectObject(memory_dc, previous);
+        let _ = DeleteObject(bitmap);
+        let _ = DeleteDC(memory_dc);
+        ReleaseDC(None, screen_dc);
+
+        if lines == 0 {
+            return Err("Failed to capture the screen".to_string());
+        }
+
+        let luma = pixels.chunks_exact(4)
+            .map(|bgra| ((bgra[2] as u32 * 299 + bgra[1] as u32 * 587 + bgra[0] as u32 * 114) / 1000) as u8)
+            .collect();
+        Ok((width as usize, height as usize, luma))
+    }
+}
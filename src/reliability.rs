@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::events::{self, Event};
+
+// Error budget per server: starts, crashes and health checks counted into a
+// reliability score for the settings list. Counts fade with a one-week half
+// life, so a server that recovered climbs back and an old outage doesn't pin a
+// good server to the bottom forever. Kept in reliability.json next to the config.
+
+// Weight of past events halves every week
+const HALF_LIFE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+// Unsaved counts are written out at most this often
+const SAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+// A crash takes the port down without warning; it counts as much as two failed checks
+#[cfg(feature = "gui")]
+const CRASH_WEIGHT: f64 = 2.0;
+
+/// Faded event counts of one server
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ErrorBudget {
+    pub successes: f64,      // Successful starts and checks
+    pub start_failures: f64, // xray exited during startup
+    pub crashes: f64,        // xray exited while running
+    pub failed_checks: f64,  // Check through the proxy failed
+    pub timeouts: f64,       // Check through the proxy timed out
+    updated: u64,            // Unix time the counts were last faded
+}
+
+impl ErrorBudget {
+    #[cfg(feature = "gui")]
+    fn failures(&self) -> f64 {
+        self.start_failures + CRASH_WEIGHT * self.crashes + self.failed_checks + self.timeouts
+    }
+
+    /// Share of good outcomes, 0-100; None until something was recorded
+    #[cfg(feature = "gui")]
+    pub fn score(&self) -> Option<u32> {
+        let total = self.successes + self.failures();
+        if total < 0.5 {
+            return None;
+        }
+        Some((self.successes / total * 100.0).round() as u32)
+    }
+
+    // Scale the counts down for the time since the last update
+    fn fade(&mut self, now: u64) {
+        let elapsed = now.saturating_sub(self.updated) as f64;
+        let factor = 0.5f64.powf(elapsed / HALF_LIFE.as_secs() as f64);
+        self.successes *= factor;
+        self.start_failures *= factor;
+        self.crashes *= factor;
+        self.failed_checks *= factor;
+        self.timeouts *= factor;
+        self.updated = now;
+    }
+}
+
+/// What happened to a server
+#[derive(Debug, Clone, Copy)]
+pub enum Outcome {
+    Success,
+    StartFailure,
+    Crash,
+    FailedCheck,
+    Timeout,
+}
+
+struct Budgets {
+    servers: HashMap<String, ErrorBudget>,
+    last_saved: Instant,
+}
+
+static BUDGETS: LazyLock<Mutex<Budgets>> = LazyLock::new(|| {
+    Mutex::new(Budgets { servers: load(), last_saved: Instant::now() })
+});
+
+fn budgets_path() -> Option<PathBuf> {
+    let config_path = crate::config::Config::get_config_path().ok()?;
+    Some(config_path.parent()?.join("reliability.json"))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn load() -> HashMap<String, ErrorBudget> {
+    budgets_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Count an outcome for a server
+pub fn record(server_key: &str, outcome: Outcome) {
+    let Ok(mut budgets) = BUDGETS.lock() else {
+        return;
+    };
+    let budget = budgets.servers.entry(server_key.to_string()).or_default();
+    budget.fade(now_secs());
+    match outcome {
+        Outcome::Success => budget.successes += 1.0,
+        Outcome::StartFailure => budget.start_failures += 1.0,
+        Outcome::Crash => budget.crashes += 1.0,
+        Outcome::FailedCheck => budget.failed_checks += 1.0,
+        Outcome::Timeout => budget.timeouts += 1.0,
+    }
+
+    if budgets.last_saved.elapsed() >= SAVE_INTERVAL {
+        budgets.last_saved = Instant::now();
+        save(&budgets.servers);
+    }
+}
+
+/// Count a health check result; errors mentioning a timeout count as timeouts
+pub fn record_check(server_key: &str, result: &Result<Duration, String>) {
+    let outcome = match result {
+        Ok(_) => Outcome::Success,
+        Err(e) if e.to_lowercase().contains("timed out") || e.to_lowercase().contains("timeout") => Outcome::Timeout,
+        Err(_) => Outcome::FailedCheck,
+    };
+    record(server_key, outcome);
+}
+
+/// Count crashes as they are reported
+pub fn start_crash_counter() {
+    let receiver = events::subscribe();
+    std::thread::spawn(move || {
+        for event in receiver {
+            if let Event::ServerCrashed(info) = event {
+                record(&info.key, Outcome::Crash);
+            }
+        }
+    });
+}
+
+/// Write the counts now (e.g. before exit)
+pub fn flush() {
+    if let Ok(mut budgets) = BUDGETS.lock() {
+        budgets.last_saved = Instant::now();
+        save(&budgets.servers);
+    }
+}
+
+fn save(servers: &HashMap<String, ErrorBudget>) {
+    let Some(path) = budgets_path() else {
+        return;
+    };
+    match serde_json::to_string(servers) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                eprintln!("Failed to save reliability counts: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize reliability counts: {}", e),
+    }
+}
+
+/// Current (faded) counts of a server, None if nothing was recorded
+#[cfg(feature = "gui")]
+pub fn get_budget(server_key: &str) -> Option<ErrorBudget> {
+    let budgets = BUDGETS.lock().ok()?;
+    let mut budget = *budgets.servers.get(server_key)?;
+    budget.fade(now_secs());
+    Some(budget)
+}
+
+/// Reliability score of a server, 0-100
+#[cfg(feature = "gui")]
+pub fn score(server_key: &str) -> Option<u32> {
+    get_budget(server_key)?.score()
+}
+
+/// Short text for the server list, e.g. "82% reliable (1 crash, 3 timeouts)"
+#[cfg(feature = "gui")]
+pub fn label(server_key: &str) -> Option<String> {
+    let budget = get_budget(server_key)?;
+    let score = budget.score()?;
+    let counts: Vec<String> = [
+        (budget.start_failures, "failed start", "failed starts"),
+        (budget.crashes, "crash", "crashes"),
+        (budget.failed_checks, "failed check", "failed checks"),
+        (budget.timeouts, "timeout", "timeouts"),
+    ]
+    .iter()
+    .map(|&(count, one, many)| (count.round() as u64, one, many))
+    .filter(|&(count, _, _)| count > 0)
+    .map(|(count, one, many)| format!("{} {}", count, if count == 1 { one } else { many }))
+    .collect();
+    if counts.is_empty() {
+        Some(format!("{}% reliable", score))
+    } else {
+        Some(format!("{}% reliable ({})", score, counts.join(", ")))
+    }
+}
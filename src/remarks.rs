@@ -0,0 +1,157 @@
+// Structured info that providers embed in server names.
+// Besides the country (see geo.rs) names carry a billing rate multiplier
+// ("x0.5", "2x", "倍率:1.5") and line/feature tags ("[Premium]", "IPLC",
+// "家宽"), which are worth sorting and filtering on.
+
+// Keywords recognized anywhere in a name -> tag shown for them.
+// ASCII keywords must be whole words (trailing numbering allowed, "IPLC01").
+const KEYWORDS: &[(&str, &str)] = &[
+    ("IPLC", "IPLC"),
+    ("IEPL", "IEPL"),
+    ("BGP", "BGP"),
+    ("CN2", "CN2"),
+    ("GIA", "GIA"),
+    ("PREMIUM", "Premium"),
+    ("VIP", "VIP"),
+    ("RESIDENTIAL", "Residential"),
+    ("家宽", "Residential"),
+    ("STREAMING", "Streaming"),
+    ("NETFLIX", "Netflix"),
+    ("GAME", "Game"),
+    ("GAMING", "Game"),
+    ("游戏", "Game"),
+    ("RELAY", "Relay"),
+    ("中转", "Relay"),
+    ("专线", "Dedicated"),
+];
+
+// Bracket pairs whose contents become tags ("[Premium]", "【IPLC】")
+const BRACKETS: &[(char, char)] = &[('[', ']'), ('【', '】'), ('(', ')'), ('（', '）')];
+
+// Multipliers outside this range are numbering or speeds, not rates
+const MAX_RATE: f32 = 100.0;
+
+/// Rate multiplier and tags read from a server name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Remarks {
+    pub rate: Option<f32>,
+    pub tags: Vec<String>,
+}
+
+/// Read the rate multiplier and tags from a server name
+pub fn parse(name: &str) -> Remarks {
+    let mut tags: Vec<String> = Vec::new();
+    let mut add_tag = |tag: &str| {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    };
+
+    for content in bracket_contents(name) {
+        let content = content.trim();
+        // "[x0.5]", "[DE]" and "[01]" are read elsewhere or carry nothing
+        let is_rate = parse_rate(content).is_some()
+            && content.chars().all(|c| c.is_ascii_digit() || ".xX×*倍率:： ".contains(c));
+        let is_country = !content.contains(' ') && crate::geo::country_from_name(content).is_some();
+        let is_numbering = content.chars().all(|c| c.is_ascii_digit() || c == '-' || c == '#');
+        if content.is_empty() || content.chars().count() > 20 || is_rate || is_country || is_numbering {
+            continue;
+        }
+        add_tag(content);
+    }
+
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_uppercase())
+        .collect();
+    for (keyword, tag) in KEYWORDS {
+        let found = if keyword.is_ascii() {
+            words.iter().any(|word| {
+                word == keyword || word.trim_end_matches(|c: char| c.is_ascii_digit()) == *keyword
+            })
+        } else {
+            name.contains(keyword)
+        };
+        if found {
+            add_tag(tag);
+        }
+    }
+
+    Remarks { rate: parse_rate(name), tags }
+}
+
+/// Short label for the server list, e.g. "[IPLC] [Premium] [×0.5]" (empty if nothing was found)
+#[cfg(feature = "gui")]
+pub fn label(rate: Option<f32>, tags: &[String]) -> String {
+    let mut parts: Vec<String> = tags.iter().map(|tag| format!("[{}]", tag)).collect();
+    if let Some(rate) = rate {
+        parts.push(format!("[×{}]", rate));
+    }
+    parts.join(" ")
+}
+
+// Text between each pair of brackets, outermost only
+fn bracket_contents(name: &str) -> Vec<&str> {
+    let mut contents = Vec::new();
+    let mut rest = name;
+    while let Some((start, open, close)) = rest.char_indices()
+        .find_map(|(i, c)| BRACKETS.iter().find(|(o, _)| *o == c).map(|(o, cl)| (i, *o, *cl)))
+    {
+        let after = &rest[start + open.len_utf8()..];
+        let Some(end) = after.find(close) else {
+            break;
+        };
+        contents.push(&after[..end]);
+        rest = &after[end + close.len_utf8()..];
+    }
+    contents
+}
+
+// First multiplier in the text: "x0.5" / "×2" / "*1.5", "0.5x" / "2倍", or "倍率:1.5"
+fn parse_rate(text: &str) -> Option<f32> {
+    let chars: Vec<char> = text.chars().collect();
+    let is_marker = |c: char| matches!(c, 'x' | 'X' | '×' | '*');
+    let number_at = |start: usize| -> Option<(f32, usize)> {
+        let end = chars[start..].iter()
+            .position(|c| !(c.is_ascii_digit() || *c == '.'))
+            .map_or(chars.len(), |len| start + len);
+        let number: String = chars[start..end].iter().collect();
+        number.parse::<f32>().ok()
+            .filter(|rate| *rate > 0.0 && *rate <= MAX_RATE)
+            .map(|rate| (rate, end))
+    };
+
+    for i in 0..chars.len() {
+        let word_start = i == 0 || !chars[i - 1].is_alphanumeric();
+
+        // Prefix form: a marker at the start of a word, then the number
+        if word_start && is_marker(chars[i]) && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) {
+            if let Some((rate, _)) = number_at(i + 1) {
+                return Some(rate);
+            }
+        }
+
+        // "倍率" followed by an optional separator and the number
+        if chars[i] == '倍' && chars.get(i + 1) == Some(&'率') {
+            let start = chars[i + 2..].iter()
+                .position(|c| !matches!(c, ':' | '：' | ' '))
+                .map(|skip| i + 2 + skip);
+            if let Some((rate, _)) = start.and_then(number_at) {
+                return Some(rate);
+            }
+        }
+
+        // Suffix form: a whole number followed by the marker or "倍", ending the word
+        if word_start && chars[i].is_ascii_digit() {
+            if let Some((rate, end)) = number_at(i) {
+                let marker = chars.get(end).copied();
+                let word_ends = chars.get(end + 1).is_none_or(|c| !c.is_alphanumeric());
+                if marker.is_some_and(|c| (is_marker(c) && word_ends) || c == '倍') {
+                    return Some(rate);
+                }
+            }
+        }
+    }
+    None
+}
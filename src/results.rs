@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+// Test results: every "Test All Latency" and "Test Connectivity" run is kept
+// with its time in test-results.json next to the config, together with a
+// direct fetch of the test URL (no proxy) taken at the same moment. Tools →
+// Compare Test Results sets this week against the week before, so a provider
+// that got slower can be told apart from the home connection getting slower.
+
+/// How long runs are kept: two comparison weeks plus some slack
+const RETENTION: Duration = Duration::from_secs(35 * 24 * 60 * 60);
+
+/// Length of the compared periods
+#[cfg(feature = "gui")]
+const WEEK: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+// A median moving by more than this share counts as slower or faster
+#[cfg(feature = "gui")]
+const CHANGE_THRESHOLD: f64 = 0.25;
+
+/// What kind of test produced a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunKind {
+    Latency,      // Test All Latency in the settings window (TCP connects and proxy checks)
+    Connectivity, // Test Connectivity from the tray or the API (running servers)
+}
+
+/// One server's result in a run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Measurement {
+    pub ms: Option<u32>, // None = failed
+    #[serde(default)]
+    pub proxy: bool,     // Through the local proxy; false = TCP connect to the server
+}
+
+/// One test run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Run {
+    pub t: u64,
+    pub kind: RunKind,
+    pub direct_ms: Option<u32>, // The test URL fetched without a proxy; None = failed
+    pub results: HashMap<String, Measurement>,
+}
+
+fn results_path() -> Option<PathBuf> {
+    let config_path = crate::config::Config::get_config_path().ok()?;
+    Some(config_path.parent()?.join("test-results.json"))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn load() -> Vec<Run> {
+    results_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Store a run with a direct baseline measured now. Blocks for up to the test timeout.
+pub fn record_run(kind: RunKind, results: HashMap<String, Measurement>) {
+    if results.is_empty() {
+        return;
+    }
+    let test = crate::config::Config::load().unwrap_or_default().test;
+    let direct_ms = crate::probe::test_direct(&test.url, Duration::from_secs(test.timeout_secs.max(1)))
+        .ok()
+        .map(|elapsed| elapsed.as_millis() as u32);
+
+    let mut runs = load();
+    let cutoff = now_secs().saturating_sub(RETENTION.as_secs());
+    runs.retain(|run| run.t >= cutoff);
+    runs.push(Run { t: now_secs(), kind, direct_ms, results });
+
+    let Some(path) = results_path() else {
+        return;
+    };
+    match serde_json::to_string(&runs) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                eprintln!("Failed to save test results: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize test results: {}", e),
+    }
+}
+
+// Median latency and failure share of some measurements
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, Copy)]
+struct Summary {
+    median_ms: Option<u32>,
+    failed_percent: u32,
+    count: usize,
+}
+
+#[cfg(feature = "gui")]
+fn summarize(values: &[Option<u32>]) -> Option<Summary> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut latencies: Vec<u32> = values.iter().flatten().copied().collect();
+    latencies.sort_unstable();
+    Some(Summary {
+        median_ms: latencies.get(latencies.len() / 2).copied(),
+        failed_percent: ((values.len() - latencies.len()) * 100 / values.len()) as u32,
+        count: values.len(),
+    })
+}
+
+// "120 ms, 5% failed"
+#[cfg(feature = "gui")]
+fn describe(summary: &Summary) -> String {
+    match summary.median_ms {
+        Some(ms) => format!("{} ms, {}% failed", ms, summary.failed_percent),
+        None => "every test failed".to_string(),
+    }
+}
+
+// Relative change of the median, e.g. 0.5 = 50% slower; None if either side has none
+#[cfg(feature = "gui")]
+fn change(current: &Summary, previous: &Summary) -> Option<f64> {
+    let (now, before) = (current.median_ms? as f64, previous.median_ms? as f64);
+    Some((now - before) / before.max(1.0))
+}
+
+// Whether a period looks worse: slower beyond the threshold or failing noticeably more
+#[cfg(feature = "gui")]
+fn degraded(current: &Summary, previous: &Summary) -> bool {
+    change(current, previous).is_some_and(|c| c > CHANGE_THRESHOLD)
+        || current.failed_percent >= previous.failed_percent + 10
+}
+
+/// This week against the week before, per server and for the direct connection
+#[cfg(feature = "gui")]
+pub fn compare_weeks() -> String {
+    let now = now_secs();
+    let week_start = now.saturating_sub(WEEK.as_secs());
+    let previous_start = now.saturating_sub(2 * WEEK.as_secs());
+    let runs = load();
+    let this_week: Vec<&Run> = runs.iter().filter(|run| run.t >= week_start).collect();
+    let last_week: Vec<&Run> = runs.iter().filter(|run| run.t >= previous_start && run.t < week_start).collect();
+    if this_week.is_empty() || last_week.is_empty() {
+        return format!(
+            "Not enough results to compare yet: {} test runs this week, {} the week before.\n\n\
+             Results are kept from Test All Latency and Test Connectivity; run them now and then.",
+            this_week.len(), last_week.len()
+        );
+    }
+
+    let direct = |runs: &[&Run]| summarize(&runs.iter().map(|run| run.direct_ms).collect::<Vec<_>>());
+    let (direct_now, direct_before) = (direct(&this_week), direct(&last_week));
+    let home_degraded = match (&direct_now, &direct_before) {
+        (Some(now), Some(before)) => degraded(now, before),
+        _ => false,
+    };
+
+    let mut lines = vec![format!("This week ({} runs) vs the week before ({} runs):", this_week.len(), last_week.len())];
+    if let (Some(now), Some(before)) = (&direct_now, &direct_before) {
+        lines.push(format!("\nDirect, no proxy (your own connection): {} vs {}", describe(now), describe(before)));
+        if home_degraded {
+            lines.push("  Your connection itself got worse; slower servers may just be following it.".to_string());
+        }
+    }
+
+    // Per server and method: a TCP connect and a request through the proxy aren't comparable
+    let mut keys: Vec<(String, bool)> = runs.iter()
+        .flat_map(|run| run.results.iter().map(|(key, m)| (key.clone(), m.proxy)))
+        .collect();
+    keys.sort();
+    keys.dedup();
+    let mut server_lines = Vec::new();
+    for (key, proxy) in keys {
+        let values = |runs: &[&Run]| -> Vec<Option<u32>> {
+            runs.iter()
+                .filter_map(|run| run.results.get(&key).filter(|m| m.proxy == proxy).map(|m| m.ms))
+                .collect()
+        };
+        let (Some(now), Some(before)) = (summarize(&values(&this_week)), summarize(&values(&last_week))) else {
+            continue;
+        };
+        let method = if proxy { "" } else { " (TCP)" };
+        let trend = match change(&now, &before) {
+            Some(c) if c.abs() >= 0.01 => format!(" ({:+.0}%)", c * 100.0),
+            _ => String::new(),
+        };
+        let verdict = if !degraded(&now, &before) {
+            ""
+        } else if home_degraded {
+            "  ▲ worse, like your own connection"
+        } else {
+            "  ▲ worse while your connection held up: likely the provider"
+        };
+        server_lines.push(format!(
+            "{}{}: {} vs {}{}  [{}/{} tests]{}",
+            crate::vpn::get_server_name(&key), method, describe(&now), describe(&before), trend,
+            now.count, before.count, verdict
+        ));
+    }
+    if server_lines.is_empty() {
+        lines.push("\nNo server was tested in both weeks.".to_string());
+    } else {
+        lines.push(String::new());
+        lines.extend(server_lines);
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,197 @@
+use std::time::{Duration, SystemTime};
+
+use crate::config::{Config, RoutingRule, RuleSet};
+
+// Rule set subscriptions: community-maintained domain/IP lists hosted as
+// dnsmasq configs, Clash rule-providers or plain lines. Each list is
+// downloaded to the cache, refreshed daily, and compiled into one xray routing
+// rule that sends every entry to the list's outbound.
+
+/// How old a cached list may get before the refresh timer downloads it again
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Routing rules for all configured rule sets. Lists come from the cache;
+/// one that was never downloaded is fetched now.
+pub fn compiled_rules() -> Vec<RoutingRule> {
+    let config = Config::load().unwrap_or_default();
+    config.rule_sets.iter()
+        .filter_map(|set| {
+            let content = load_cached(&set.url).or_else(|| match download(set) {
+                Ok(content) => Some(content),
+                Err(e) => {
+                    eprintln!("Rule set {} unavailable: {}", set.name, e);
+                    None
+                }
+            })?;
+            let (domains, ips) = parse_rule_list(&content);
+            if domains.is_empty() && ips.is_empty() {
+                return None;
+            }
+            Some(RoutingRule { outbound: set.outbound.clone(), domains, ips })
+        })
+        .collect()
+}
+
+/// Download a rule set and replace its cached copy. Returns the list content.
+pub fn download(set: &RuleSet) -> Result<String, String> {
+    let content = match crate::config::local_subscription_path(&set.url) {
+        Some(path) => std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+        None => reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .and_then(|client| client.get(&set.url).send())
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.text())
+            .map_err(|e| e.to_string())?,
+    };
+
+    let (domains, ips) = parse_rule_list(&content);
+    if domains.is_empty() && ips.is_empty() {
+        return Err("No domains or IPs found in the list".to_string());
+    }
+    if let Some(path) = crate::config::cache_file_path("rules", &set.url) {
+        if let Err(e) = std::fs::write(&path, &content) {
+            eprintln!("Failed to cache rule set {}: {}", set.name, e);
+        }
+    }
+    Ok(content)
+}
+
+/// Download every rule set now. Returns true if any list changed.
+#[cfg(feature = "gui")]
+pub fn update_all() -> Result<bool, String> {
+    let config = Config::load()?;
+    let (changed, errors) = update_sets(config.rule_sets.iter());
+    if errors.is_empty() {
+        Ok(changed)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// Re-download rule sets older than REFRESH_INTERVAL and restart running
+/// servers when a list changed
+pub fn start_rule_set_refresh_timer() {
+    std::thread::spawn(|| loop {
+        crate::idle::sleep(Duration::from_secs(60 * 60));
+        if crate::idle::is_idle() {
+            continue;
+        }
+
+        let config = Config::load().unwrap_or_default();
+        let (changed, errors) = update_sets(config.rule_sets.iter().filter(|set| cache_age(&set.url) >= REFRESH_INTERVAL));
+        for error in errors {
+            eprintln!("Failed to update rule set {}", error);
+        }
+        if changed {
+            println!("Rule sets updated, restarting running servers");
+            if let Err(e) = crate::routing::restart_running() {
+                eprintln!("Failed to apply updated rule sets: {}", e);
+            }
+        }
+    });
+}
+
+// Download the given sets; returns whether any list changed and "name: error" for failures
+fn update_sets<'a>(sets: impl Iterator<Item = &'a RuleSet>) -> (bool, Vec<String>) {
+    let mut changed = false;
+    let mut errors = Vec::new();
+    for set in sets {
+        let previous = load_cached(&set.url);
+        match download(set) {
+            Ok(content) => changed |= previous.as_deref() != Some(content.as_str()),
+            Err(e) => errors.push(format!("{}: {}", set.name, e)),
+        }
+    }
+    (changed, errors)
+}
+
+fn load_cached(url: &str) -> Option<String> {
+    std::fs::read_to_string(crate::config::cache_file_path("rules", url)?).ok()
+}
+
+// Time since the cached copy was written (never downloaded = infinitely old)
+fn cache_age(url: &str) -> Duration {
+    crate::config::cache_file_path("rules", url)
+        .and_then(|path| std::fs::metadata(path).ok())
+        .and_then(|metadata| metadata.modified().ok())
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .unwrap_or(Duration::MAX)
+}
+
+/// Turn a downloaded list into xray domain and IP matchers. Understands
+/// dnsmasq lines (server=/a.com/b.com/...), Clash rule-provider payloads in
+/// domain, ipcidr or classical form, hosts files and plain domain/CIDR lines.
+pub fn parse_rule_list(content: &str) -> (Vec<String>, Vec<String>) {
+    let mut domains = Vec::new();
+    let mut ips = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') || line.starts_with("//") || line == "payload:" {
+            continue;
+        }
+        // Clash YAML list item: - '+.example.com'
+        let entry = line.strip_prefix("- ").unwrap_or(line).trim().trim_matches(|c| c == '\'' || c == '"');
+
+        // dnsmasq: server=/a.com/b.com/1.2.3.4, ipset=/a.com/name, ...
+        if let Some((_, rest)) = entry.split_once("=/") {
+            let names = rest.rsplit_once('/').map(|(names, _)| names).unwrap_or(rest);
+            domains.extend(names.split('/').filter(|d| is_domain(d)).map(|d| format!("domain:{}", d)));
+            continue;
+        }
+
+        // Clash classical: DOMAIN-SUFFIX,example.com[,policy]
+        if let Some((kind, rest)) = entry.split_once(',') {
+            let value = rest.split(',').next().unwrap_or("").trim();
+            match kind.trim() {
+                "DOMAIN" if is_domain(value) => domains.push(format!("full:{}", value)),
+                "DOMAIN-SUFFIX" if is_domain(value) => domains.push(format!("domain:{}", value)),
+                "DOMAIN-KEYWORD" if !value.is_empty() => domains.push(format!("keyword:{}", value)),
+                "IP-CIDR" | "IP-CIDR6" if is_ip(value) => ips.push(value.to_string()),
+                "GEOIP" if !value.is_empty() => ips.push(format!("geoip:{}", value.to_lowercase())),
+                _ => {}
+            }
+            continue;
+        }
+
+        // Hosts file: 0.0.0.0 example.com
+        let mut words = entry.split_whitespace();
+        if let (Some(first), Some(second)) = (words.next(), words.next()) {
+            if is_ip(first) && is_domain(second) && !is_ip(second) {
+                domains.push(format!("full:{}", second));
+            }
+            continue;
+        }
+
+        if is_ip(entry) {
+            ips.push(entry.to_string());
+        } else {
+            // "+.a.com", ".a.com" and "*.a.com" all mean a.com and its subdomains
+            let domain = entry.trim_start_matches("+.").trim_start_matches("*.").trim_start_matches('.');
+            if is_domain(domain) {
+                domains.push(format!("domain:{}", domain));
+            }
+        }
+    }
+
+    domains.sort();
+    domains.dedup();
+    ips.sort();
+    ips.dedup();
+    (domains, ips)
+}
+
+// An address or CIDR block
+fn is_ip(value: &str) -> bool {
+    let address = value.split('/').next().unwrap_or(value);
+    address.parse::<std::net::IpAddr>().is_ok()
+}
+
+// Plain host name; anything else would make xray reject the whole config
+fn is_domain(value: &str) -> bool {
+    !value.is_empty()
+        && value.contains('.')
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_')
+}
@@ -0,0 +1,120 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+// Crash-loop protection: every start bumps a counter in a sentinel file next to
+// the config (one per profile), and a start that stays up for a minute, or an
+// orderly exit, deletes it. When the last three starts all died before that,
+// this one runs in safe mode: no servers are started, the system proxy, PAC
+// registration and TUN mode are left off, and the tray offers a recovery dialog.
+
+/// Unfinished starts in a row that trigger safe mode
+pub const CRASH_LIMIT: u32 = 3;
+
+// How long a start has to stay up to count as successful
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+static SAFE_MODE: AtomicBool = AtomicBool::new(false);
+
+fn sentinel_path() -> Option<PathBuf> {
+    Some(crate::config::Config::get_config_path().ok()?.with_extension("startup"))
+}
+
+/// Count this start and decide whether it runs in safe mode. Call once, early in main.
+pub fn begin() -> bool {
+    let Some(path) = sentinel_path() else {
+        return false;
+    };
+    let unfinished = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| content.trim().parse::<u32>().ok())
+        .unwrap_or(0);
+    if let Err(e) = std::fs::write(&path, (unfinished + 1).to_string()) {
+        eprintln!("Failed to write startup sentinel: {}", e);
+    }
+    if unfinished >= CRASH_LIMIT {
+        println!("The last {} starts didn't finish, starting in safe mode", unfinished);
+        SAFE_MODE.store(true, Ordering::Relaxed);
+    }
+    is_active()
+}
+
+/// Whether this run started in safe mode
+pub fn is_active() -> bool {
+    SAFE_MODE.load(Ordering::Relaxed)
+}
+
+/// Forget the unfinished start: called once the app has stayed up and on exit
+pub fn mark_clean() {
+    if let Some(path) = sentinel_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Count this start as successful once it has stayed up for a minute
+pub fn start_stability_timer() {
+    std::thread::spawn(|| {
+        std::thread::sleep(STABLE_AFTER);
+        mark_clean();
+    });
+}
+
+/// Move the config aside as <name>.json.bak and start over with defaults.
+/// Returns the backup path.
+#[cfg(feature = "gui")]
+pub fn reset_config() -> Result<PathBuf, String> {
+    let config_path = crate::config::Config::get_config_path()?;
+    let backup = config_path.with_extension("json.bak");
+    if config_path.exists() {
+        std::fs::rename(&config_path, &backup)
+            .map_err(|e| format!("Failed to back up the config: {}", e))?;
+    }
+    crate::config::Config::default().save()?;
+    println!("Config reset, previous one kept as {}", backup.display());
+    Ok(backup)
+}
+
+/// Turn autostart off in the config and in Windows
+#[cfg(feature = "gui")]
+pub fn disable_autostart() -> Result<(), String> {
+    let mut config = crate::config::Config::load()?;
+    config.autostart = false;
+    config.save()?;
+    crate::config::Config::set_autostart(false)
+}
+
+/// Recovery dialog shown when the tray comes up in safe mode
+#[cfg(all(windows, feature = "gui"))]
+pub fn show_recovery_dialog() {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::*;
+
+    let text = format!(
+        "VPN Manager closed unexpectedly during each of its last {} starts, so it is running in safe mode: \
+         no servers were started and the system proxy and TUN mode were left off.\n\n\
+         Yes: reset the settings (the current file is kept as a .bak next to it)\n\
+         No: stop starting VPN Manager with Windows\n\
+         Cancel: keep everything and continue in safe mode\n\n\
+         Servers can still be started by hand from Settings.",
+        CRASH_LIMIT
+    );
+    let answer = unsafe {
+        crate::ui::controls::message_box(HWND::default(), &text, "VPN Manager - Safe Mode", MB_YESNOCANCEL | MB_ICONWARNING)
+    };
+    let result = if answer == IDYES {
+        // A reset config has autostart off, so the registry entry goes too
+        reset_config().and_then(|backup| {
+            crate::config::Config::set_autostart(false)?;
+            Ok(format!("Settings reset. The previous ones are in {}.", backup.display()))
+        })
+    } else if answer == IDNO {
+        disable_autostart().map(|_| "VPN Manager no longer starts with Windows.".to_string())
+    } else {
+        return;
+    };
+    let (text, icon) = match result {
+        Ok(text) => (text, MB_ICONINFORMATION),
+        Err(e) => (e, MB_ICONWARNING),
+    };
+    unsafe { crate::ui::controls::message_box(HWND::default(), &text, "VPN Manager - Safe Mode", MB_OK | icon) };
+}
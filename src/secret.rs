@@ -0,0 +1,34 @@
+// Secrets the manager hands out: API tokens, share link tokens and guest logins
+// (the primary port's random picks use the same generator).
+// They come from the OS random number generator (BCryptGenRandom on Windows,
+// via getrandom), never from a hasher seed or the clock, and presented secrets
+// are compared in constant time.
+
+/// `len` random bytes as lowercase hex (2 * `len` chars)
+#[cfg(feature = "gui")]
+pub fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    fill(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// There is no safe fallback for a secret, so a failing OS generator is fatal
+fn fill(bytes: &mut [u8]) {
+    getrandom::getrandom(bytes).expect("OS random number generator unavailable");
+}
+
+/// Random number from the OS generator
+pub fn random_u64() -> u64 {
+    let mut bytes = [0u8; 8];
+    fill(&mut bytes);
+    u64::from_le_bytes(bytes)
+}
+
+/// Whether a presented secret equals the expected one, in time that doesn't
+/// depend on where they differ (only the length can leak)
+#[cfg(any(feature = "gui", feature = "api"))]
+pub fn matches(expected: &str, presented: &str) -> bool {
+    let (expected, presented) = (expected.as_bytes(), presented.as_bytes());
+    expected.len() == presented.len()
+        && expected.iter().zip(presented).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+// Runtime session: the servers that were actually running when the app last
+// exited, kept apart from the saved "enabled" flags. With the "resume" startup
+// mode these are started instead, so servers started or stopped by hand (tray,
+// API, vpnctl) come back as they were without touching the saved settings.
+
+/// Startup mode value that resumes the previous session
+pub const RESUME: &str = "resume";
+
+fn session_path() -> Option<PathBuf> {
+    let config_path = crate::config::Config::get_config_path().ok()?;
+    Some(config_path.parent()?.join("session.json"))
+}
+
+/// Record the running servers; call on exit before they are stopped
+pub fn save() {
+    let Some(path) = session_path() else {
+        return;
+    };
+    let mut running = crate::xray_manager::get_running_servers();
+    running.sort();
+    match serde_json::to_string_pretty(&serde_json::json!({ "running": running })) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                eprintln!("Failed to save session: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize session: {}", e),
+    }
+}
+
+/// Server keys running at the last exit, None if no session was recorded yet
+pub fn load() -> Option<Vec<String>> {
+    let content = std::fs::read_to_string(session_path()?).ok()?;
+    let session: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let running = session["running"].as_array()?;
+    Some(running.iter().filter_map(|key| key.as_str().map(str::to_string)).collect())
+}
@@ -0,0 +1,101 @@
+use crate::config::{Config, ServerSettings, Subscription};
+use crate::vpn::{VpnServer, VPN_SERVERS};
+
+// What a settings window does, apart from drawing it: load the server list, check
+// the ports before saving and turn the edited rows into saved settings. The Win32
+// window (ui/settings_window.rs, feature `gui`) and the cross-platform egui window
+// (egui_window.rs, feature `egui`) both go through here, so they save the same way.
+
+/// Fetch the server list for the settings window and publish it in `VPN_SERVERS`.
+/// Saved settings apply (re-keyed if saved in an older format); the ports new
+/// servers get are only saved with the window's Save.
+pub fn load_server_list(subscriptions: &[Subscription]) -> Vec<VpnServer> {
+    let mut config = Config::load().unwrap_or_default();
+    let mut servers = crate::vpn::fetch_and_process_vpn_list(subscriptions, &config.manual_servers);
+
+    crate::vpn::migrate_server_keys(&mut config, &servers);
+    crate::prune::after_fetch(&mut config, &servers);
+    crate::vpn::assign_local_ports(&mut servers, &config.server_settings, config.first_local_port());
+
+    if let Ok(mut global_servers) = VPN_SERVERS.lock() {
+        *global_servers = Some(servers.clone());
+    }
+    let diff = crate::listdiff::record(&servers);
+    crate::events::emit(crate::events::Event::SubscriptionRefreshed {
+        server_count: servers.len(), added: diff.added.len(), removed: diff.removed.len(), changed: diff.changed.len(),
+    });
+    servers
+}
+
+/// Settings for a server row; what the row doesn't show (limits, system proxy, mux,
+/// TLS tweaks, upstream) is kept from `saved`
+pub fn row_settings(server: &VpnServer, saved: Option<&ServerSettings>) -> ServerSettings {
+    ServerSettings {
+        local_port: server.local_port,
+        proxy_type: server.proxy_type.clone(),
+        http_port: server.http_port,
+        auto_port: server.auto_port,
+        enabled: server.enabled,
+        limits: saved.map(|s| s.limits.clone()).unwrap_or_default(),
+        system_proxy: saved.is_some_and(|s| s.system_proxy),
+        alias: server.alias.clone(),
+        note: server.note.clone(),
+        pinned: server.pinned,
+        hidden: server.hidden,
+        listen: server.listen.clone(),
+        udp: server.udp,
+        mux: saved.and_then(|s| s.mux.clone()),
+        fingerprint: saved.map(|s| s.fingerprint.clone()).unwrap_or_default(),
+        fragment: saved.and_then(|s| s.fragment.clone()),
+        upstream: saved.map(|s| s.upstream.clone()).unwrap_or_default(),
+    }
+}
+
+/// Update the listed servers' settings; settings of servers missing from the list
+/// stay until prune::after_fetch or Clean Up Server Settings drops them
+pub fn store_rows(config: &mut Config, servers: &[VpnServer]) {
+    for server in servers {
+        let key = server.get_server_key();
+        let settings = row_settings(server, config.server_settings.get(&key));
+        config.server_settings.insert(key, settings);
+    }
+}
+
+/// Rows (indexes into `servers`) of enabled servers set to the same port, and a
+/// description of the clash; None when every enabled server has its own ports
+pub fn shared_ports(servers: &[VpnServer]) -> Option<(Vec<usize>, String)> {
+    let enabled: Vec<(usize, &VpnServer)> = servers.iter().enumerate().filter(|(_, server)| server.enabled).collect();
+    let duplicates = crate::ports::duplicates(enabled.iter().map(|(_, server)| (server.display_name(), ports(server))));
+    if duplicates.is_empty() {
+        return None;
+    }
+    let rows = enabled.iter()
+        .filter(|(_, server)| ports(server).iter().any(|port| duplicates.iter().any(|(shared, _)| shared == port)))
+        .map(|(index, _)| *index)
+        .collect();
+    Some((rows, crate::ports::describe_duplicates(&duplicates)))
+}
+
+/// Rows of enabled servers whose ports another program holds, with one line each
+/// naming the server and the holder. Ports of our own running servers don't count.
+pub fn taken_ports(servers: &[VpnServer]) -> (Vec<usize>, Vec<String>) {
+    let ours: Vec<u16> = crate::xray_manager::get_running_servers().iter()
+        .filter_map(|key| crate::xray_manager::get_server_info(key))
+        .flat_map(|info| [info.local_port, info.http_port])
+        .collect();
+    let mut rows = Vec::new();
+    let mut taken = Vec::new();
+    for (index, server) in servers.iter().enumerate().filter(|(_, server)| server.enabled) {
+        if let Some(e) = ports(server).into_iter()
+            .filter(|port| !ours.contains(port))
+            .find_map(|port| crate::ports::check_local_port(port).err()) {
+            rows.push(index);
+            taken.push(format!("{}: {}", server.display_name(), e));
+        }
+    }
+    (rows, taken)
+}
+
+fn ports(server: &VpnServer) -> Vec<u16> {
+    crate::ports::server_ports(server.local_port, &server.proxy_type, server.http_port)
+}
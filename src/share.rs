@@ -0,0 +1,153 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+// Hand a curated server list to another client app: a standard base64
+// subscription built from the chosen servers' share URIs, written to a file
+// or served on the LAN for a few minutes under an unguessable path.
+// Pairing a phone serves the same servers once, behind a one-time token shown
+// as a QR code the phone app scans: a subscription for v2rayNG, a sing-box
+// profile for the sing-box app.
+
+/// How long a served subscription stays available
+pub const SHARE_MINUTES: u64 = 10;
+
+// Bumped for every new share; a serving thread exits once its generation is stale
+static SHARE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Phone apps a pairing QR code can set up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PairingApp {
+    V2rayNg, // Scans the subscription URL
+    SingBox, // Scans a sing-box:// remote profile import link
+}
+
+impl PairingApp {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PairingApp::V2rayNg => "v2rayNG",
+            PairingApp::SingBox => "sing-box",
+        }
+    }
+}
+
+/// Share URIs of the given servers, in the given order (servers missing from the subscriptions are skipped)
+pub fn collect_uris(server_keys: &[String]) -> Vec<String> {
+    let config = crate::config::Config::load().unwrap_or_default();
+    let uris = crate::vpn::fetch_subscription_uris(&config.subscriptions, &config.manual_servers);
+    server_keys.iter()
+        .filter_map(|key| uris.get(key).cloned())
+        .collect()
+}
+
+/// Encode URIs as a base64 subscription body
+pub fn build_subscription(uris: &[String]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(uris.join("\n"))
+}
+
+/// Write a subscription body to a file
+pub fn write_to_file(path: &str, subscription: &str) -> Result<(), String> {
+    std::fs::write(path, subscription)
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Serve a subscription body on the LAN for SHARE_MINUTES and return its URL.
+/// Starting a new share stops the previous one.
+pub fn serve(subscription: String) -> Result<String, String> {
+    serve_body(subscription, "text/plain; charset=utf-8", false)
+}
+
+/// Serve the given servers once for a phone app and return the text of the QR code
+/// it scans. The token stops working after the first download or SHARE_MINUTES.
+pub fn pair(app: PairingApp, server_keys: &[String]) -> Result<String, String> {
+    let config = crate::config::Config::load().unwrap_or_default();
+    let uris = crate::vpn::fetch_subscription_uris(&config.subscriptions, &config.manual_servers);
+    let servers: Vec<(String, String)> = crate::vpn::VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|server| server_keys.contains(&server.get_server_key()))
+        .filter_map(|server| uris.get(&server.get_server_key()).map(|uri| (server.name, uri.clone())))
+        .collect();
+    if servers.is_empty() {
+        return Err("None of the selected servers are in the current subscriptions.".to_string());
+    }
+
+    match app {
+        PairingApp::V2rayNg => {
+            let uris: Vec<String> = servers.into_iter().map(|(_, uri)| uri).collect();
+            serve_body(build_subscription(&uris), "text/plain; charset=utf-8", true)
+        }
+        PairingApp::SingBox => {
+            let profile = crate::vpn::singbox::mobile_profile(&servers)?;
+            let url = serve_body(profile, "application/json", true)?;
+            Ok(format!("sing-box://import-remote-profile?url={}#VPN%20Manager",
+                crate::vpn::singbox::percent_encode(&url)))
+        }
+    }
+}
+
+// Serve a body on the LAN under a random token; `one_time` stops after the first download
+fn serve_body(body: String, content_type: &'static str, one_time: bool) -> Result<String, String> {
+    let generation = SHARE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    let listener = TcpListener::bind(("0.0.0.0", 0))
+        .map_err(|e| format!("Failed to open a port for sharing: {}", e))?;
+    listener.set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure sharing port: {}", e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let token = crate::secret::random_hex(16);
+    let url = format!("http://{}:{}/{}", crate::inbounds::lan_address(), port, token);
+
+    std::thread::spawn(move || {
+        let deadline = Instant::now() + Duration::from_secs(SHARE_MINUTES * 60);
+        while Instant::now() < deadline && SHARE_GENERATION.load(Ordering::SeqCst) == generation {
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    if let Ok(true) = respond(stream, &token, &body, content_type) {
+                        if one_time {
+                            println!("Paired with {}, token used", peer.ip());
+                            break;
+                        }
+                    }
+                }
+                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                Err(_) => break,
+            }
+        }
+        println!("Stopped sharing servers on port {}", port);
+    });
+
+    println!("Sharing servers at {} for {} minutes", url, SHARE_MINUTES);
+    Ok(url)
+}
+
+// Answer one HTTP request: the body on the token path, 404 otherwise. True if the body was sent.
+fn respond(mut stream: TcpStream, token: &str, body: &str, content_type: &str) -> std::io::Result<bool> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut buffer = [0u8; 2048];
+    let len = stream.read(&mut buffer)?;
+    let request = String::from_utf8_lossy(&buffer[..len]);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+
+    let found = crate::secret::matches(token, path.trim_start_matches('/'));
+    let response = if found {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            content_type,
+            body.len(),
+            body
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    stream.write_all(response.as_bytes())?;
+    Ok(found)
+}
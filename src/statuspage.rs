@@ -0,0 +1,191 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::config::StatusPageSettings;
+
+// Household status page: a read-only web page listing the proxies that are up,
+// their ports and a QR code of each proxy address, so others in the house can
+// set up a phone or browser without the tray app. Opt-in; served on loopback,
+// or to the LAN with `status_page.lan`. It shows nothing a visitor could change
+// and no server credentials, only addresses of local proxies.
+
+// Bumped on every apply; a serving thread exits once its generation is stale
+static PAGE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Address the page can be opened at
+pub fn url(settings: &StatusPageSettings) -> String {
+    let host = if settings.lan { crate::inbounds::lan_address() } else { "127.0.0.1".to_string() };
+    format!("http://{}:{}/", host, settings.port)
+}
+
+/// Serve the page as configured, replacing a previous listener
+pub fn apply(settings: &StatusPageSettings) {
+    let generation = PAGE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    if !settings.enabled {
+        return;
+    }
+
+    let bind_ip = if settings.lan { "0.0.0.0" } else { "127.0.0.1" };
+    // The previous listener lets go of the port within one accept poll
+    let mut bound = TcpListener::bind((bind_ip, settings.port));
+    for _ in 0..5 {
+        if bound.is_ok() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        bound = TcpListener::bind((bind_ip, settings.port));
+    }
+    let listener = match bound {
+        Ok(listener) => listener,
+        Err(_) => {
+            eprintln!("Failed to serve the status page: {}", crate::ports::describe_conflict(settings.port));
+            return;
+        }
+    };
+    if let Err(e) = listener.set_nonblocking(true) {
+        eprintln!("Failed to configure status page listener: {}", e);
+        return;
+    }
+    println!("Serving status page at {}", url(settings));
+
+    let port = settings.port;
+    std::thread::spawn(move || {
+        while PAGE_GENERATION.load(Ordering::SeqCst) == generation {
+            match listener.accept() {
+                // A slow visitor shouldn't hold up the next one
+                Ok((stream, _)) => {
+                    std::thread::spawn(move || respond(stream));
+                }
+                Err(_) => std::thread::sleep(Duration::from_millis(100)),
+            }
+        }
+        println!("Stopped serving status page on port {}", port);
+    });
+}
+
+// Answer one HTTP request: the page on /, 404 otherwise
+fn respond(mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut buffer = [0u8; 2048];
+    let len = stream.read(&mut buffer)?;
+    let request = String::from_utf8_lossy(&buffer[..len]);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    // The proxies are reached at the same address the page was
+    let host = request.lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("host"))
+        .map(|(_, host)| host.trim())
+        .map(|host| host.rsplit_once(':').filter(|(_, port)| port.parse::<u16>().is_ok()).map_or(host, |(name, _)| name))
+        .filter(|host| !host.is_empty())
+        .unwrap_or("127.0.0.1")
+        .to_string();
+
+    let response = if path.split('?').next() == Some("/") {
+        let page = render(&host);
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n{}",
+            page.len(),
+            page
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    stream.write_all(response.as_bytes())
+}
+
+// One proxy on the page
+struct Entry {
+    title: String,
+    detail: String,
+    proxy_url: String,
+}
+
+// The primary port when it relays somewhere, then running servers by name
+fn entries(host: &str) -> Vec<Entry> {
+    let address = |proxy_type: &str, port: u16| {
+        let scheme = if proxy_type == "HTTP" { "http" } else { "socks5" };
+        format!("{}://{}:{}", scheme, host, port)
+    };
+    let mut entries: Vec<Entry> = crate::xray_manager::get_running_servers().iter()
+        .filter_map(|key| crate::xray_manager::get_server_info(key))
+        .map(|info| Entry {
+            title: info.name.clone(),
+            detail: if info.proxy_type == "Both" {
+                format!("SOCKS proxy, port {}; HTTP proxy, port {}", info.local_port, info.http_port)
+            } else {
+                format!("{} proxy, port {}", info.proxy_type, info.local_port)
+            },
+            proxy_url: address(&info.proxy_type, info.local_port),
+        })
+        .collect();
+    entries.sort_by(|a, b| a.title.cmp(&b.title));
+
+    let config = crate::config::Config::load().unwrap_or_default();
+    if config.primary.enabled {
+        if let Some(target) = crate::primary::get_target() {
+            entries.insert(0, Entry {
+                title: "Main proxy".to_string(),
+                detail: format!("{} proxy, port {}, currently through {}", config.primary.proxy_type,
+                    config.primary.port, crate::vpn::get_server_name(&target)),
+                proxy_url: address(&config.primary.proxy_type, config.primary.port),
+            });
+        }
+    }
+    entries
+}
+
+fn render(host: &str) -> String {
+    let entries = entries(host);
+    let mut body = String::new();
+    if entries.is_empty() {
+        body.push_str("<p>No proxies are running right now.</p>\n");
+    } else if host != "127.0.0.1" && host != "localhost" {
+        body.push_str("<p>Other devices can only use a proxy that accepts connections from the network; \
+            ask whoever runs this computer if one doesn't work.</p>\n");
+    }
+    for entry in &entries {
+        body.push_str(&format!(
+            "<section><h2>✓ {}</h2><p>{}</p><p><code>{}</code></p>{}</section>\n",
+            escape(&entry.title), escape(&entry.detail), escape(&entry.proxy_url), qr_svg(&entry.proxy_url)
+        ));
+    }
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><meta http-equiv=\"refresh\" content=\"30\">\
+         <meta name=\"viewport\" content=\"width=device-width\"><title>VPN Manager</title>\
+         <style>body{{font-family:sans-serif;max-width:40em;margin:1em auto;padding:0 1em}}\
+         section{{border-top:1px solid #ccc;padding:.5em 0}}svg{{width:12em;height:12em}}</style></head>\n\
+         <body><h1>Proxies on this computer</h1>\n<p>Scan a code with the phone's proxy app, \
+         or enter the address in the browser's proxy settings. This page refreshes by itself.</p>\n{}</body></html>\n",
+        body
+    )
+}
+
+// The address as an inline SVG QR code; not available without the gui feature's qrcode crate
+fn qr_svg(text: &str) -> String {
+    #[cfg(feature = "gui")]
+    if let Ok(code) = qrcode::QrCode::new(text.as_bytes()) {
+        let width = code.width();
+        let mut rects = String::new();
+        for (index, color) in code.to_colors().into_iter().enumerate() {
+            if color == qrcode::Color::Dark {
+                // 4 modules of quiet zone around the code
+                rects.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\"/>", index % width + 4, index / width + 4));
+            }
+        }
+        return format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {0} {0}\" role=\"img\" aria-label=\"QR code of {1}\">\
+             <rect width=\"{0}\" height=\"{0}\" fill=\"#fff\"/>{2}</svg>",
+            width + 8, escape(text), rects
+        );
+    }
+    let _ = text;
+    String::new()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
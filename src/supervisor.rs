@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::events::{self, Event, ServerInfo};
+
+// Process supervision: a poll notices xray processes that died (next to the
+// output readers, which see most exits first), and crashed servers are started
+// again after 2 s, 4 s, 8 s, ... up to `supervision.max_restarts` times in a row.
+// Until they run again they are listed in the tray with ✗. Single servers are
+// left to failover while it is on, since it moves their port to a backup.
+
+// How often running processes are checked
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// Longest wait between restarts
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+// A server that ran this long before crashing starts over with a fresh restart count
+const STABLE_RUN: Duration = Duration::from_secs(10 * 60);
+
+/// A server whose process died and hasn't run since
+#[derive(Debug, Clone)]
+pub struct DeadServer {
+    #[cfg(feature = "gui")]
+    pub name: String, // Shown in the tray
+    pub attempts: u32,     // Restarts tried since the server last ran steadily
+    pub restarting: bool,  // A restart is scheduled; false = given up or auto-restart off
+}
+
+static DEAD: LazyLock<Mutex<HashMap<String, DeadServer>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// When each server was last started by the supervisor, with its restart count then
+static RESTARTS: LazyLock<Mutex<HashMap<String, (u32, Instant)>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Dead servers for the tray, by name
+#[cfg(feature = "gui")]
+pub fn dead_servers() -> Vec<DeadServer> {
+    let mut dead: Vec<DeadServer> = DEAD.lock()
+        .map(|dead| dead.values().cloned().collect())
+        .unwrap_or_default();
+    dead.sort_by(|a, b| a.name.cmp(&b.name));
+    dead
+}
+
+/// Stop tracking a server, e.g. when it was stopped by hand
+pub fn forget(server_key: &str) {
+    if let Ok(mut dead) = DEAD.lock() {
+        dead.remove(server_key);
+    }
+    if let Ok(mut restarts) = RESTARTS.lock() {
+        restarts.remove(server_key);
+    }
+}
+
+/// Stop tracking every server, e.g. before all servers are restarted
+pub fn forget_all() {
+    if let Ok(mut dead) = DEAD.lock() {
+        dead.clear();
+    }
+    if let Ok(mut restarts) = RESTARTS.lock() {
+        restarts.clear();
+    }
+}
+
+/// Poll running processes and restart the ones that crash
+pub fn start_supervisor() {
+    std::thread::spawn(|| loop {
+        std::thread::sleep(POLL_INTERVAL);
+        crate::xray_manager::poll_exited();
+    });
+
+    let receiver = events::subscribe();
+    std::thread::spawn(move || {
+        for event in receiver {
+            match event {
+                Event::ServerCrashed(info) => on_crash(&info),
+                Event::ServerStarted(info) => {
+                    if let Ok(mut dead) = DEAD.lock() {
+                        dead.remove(&info.key);
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+// 2 s after the first crash, doubling each time
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(2u64 << attempt.min(16)).min(MAX_BACKOFF)
+}
+
+fn on_crash(info: &ServerInfo) {
+    let config = crate::config::Config::load().unwrap_or_default();
+    let group = crate::balancer::is_group_key(&info.key);
+    // A recent supervisor start that crashed again continues the count
+    let attempts = RESTARTS.lock().ok()
+        .and_then(|restarts| restarts.get(&info.key).copied())
+        .filter(|(_, started)| started.elapsed() < STABLE_RUN)
+        .map_or(0, |(attempts, _)| attempts);
+    let handled_by_failover = !group && config.failover.enabled;
+    let restarting = config.supervision.auto_restart && !handled_by_failover
+        && attempts < config.supervision.max_restarts;
+    if let Ok(mut dead) = DEAD.lock() {
+        dead.insert(info.key.clone(), DeadServer {
+            #[cfg(feature = "gui")]
+            name: info.name.clone(),
+            attempts,
+            restarting,
+        });
+    }
+    crate::request_menu_update();
+    if handled_by_failover {
+        return;
+    }
+    if !restarting {
+        if config.supervision.auto_restart {
+            eprintln!("{} crashed {} times in a row, not restarting it again", info.name, attempts + 1);
+        }
+        return;
+    }
+    schedule_restart(info.key.clone(), info.name.clone(), attempts);
+}
+
+// Start the server again after its backoff, unless it was started or stopped meanwhile
+fn schedule_restart(server_key: String, name: String, attempts: u32) {
+    let delay = backoff(attempts);
+    println!("Restarting {} in {} s (attempt {})", name, delay.as_secs(), attempts + 1);
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let still_dead = DEAD.lock().is_ok_and(|dead| {
+            dead.get(&server_key).is_some_and(|d| d.restarting && d.attempts == attempts)
+        });
+        if !still_dead || crate::xray_manager::is_shutting_down() {
+            return;
+        }
+        if let Ok(mut restarts) = RESTARTS.lock() {
+            restarts.insert(server_key.clone(), (attempts + 1, Instant::now()));
+        }
+        let result = if crate::balancer::is_group_key(&server_key) {
+            let config = crate::config::Config::load().unwrap_or_default();
+            let summary = crate::balancer::sync_groups(&config);
+            if summary.failed.is_empty() { Ok(()) } else { Err(summary.failure_text()) }
+        } else {
+            crate::start_single_server(&server_key)
+        };
+        match result {
+            Ok(()) => println!("Restarted {}", name),
+            // Failing to start counts like another crash
+            Err(e) => {
+                eprintln!("Failed to restart {}: {}", name, e);
+                if !crate::xray_manager::is_shutting_down() {
+                    on_crash(&ServerInfo {
+                        key: server_key.clone(),
+                        name: name.clone(),
+                        local_port: 0,
+                        proxy_type: String::new(),
+                        http_port: 0,
+                    });
+                }
+            }
+        }
+        crate::request_menu_update();
+    });
+}
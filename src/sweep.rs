@@ -0,0 +1,107 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::ScheduledTestSettings;
+
+// Scheduled latency sweep: once a day, from `scheduled_tests.hour` local time,
+// the enabled servers are tested like "Test All" in the settings window. Running
+// ones get a request through their proxy, which lands in health (tray order, the
+// fastest server, primary port and failover picks) and reliability; the rest get
+// a TCP connect for the latency column. Every run is also stored for Compare Test
+// Results. The sweep waits out quiet hours and, with `skip_metered`, metered
+// connections, trying again every 10 minutes until the day is over. The date of
+// the last sweep is kept next to the config so restarting doesn't repeat it.
+
+// How often the schedule is looked at
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+fn last_run_path() -> Option<PathBuf> {
+    let config_path = crate::config::Config::get_config_path().ok()?;
+    Some(config_path.parent()?.join("scheduled-test.last"))
+}
+
+// "2026-10-16"
+fn date_text(time: (u16, u16, u16, u16)) -> String {
+    format!("{:04}-{:02}-{:02}", time.0, time.1, time.2)
+}
+
+/// Whether `hour` falls in the quiet hours, which may wrap past midnight (23 to 7)
+pub fn in_quiet_hours(settings: &ScheduledTestSettings, hour: u8) -> bool {
+    let (start, end) = (settings.quiet_start, settings.quiet_end);
+    if start == end {
+        false
+    } else if start < end {
+        (start..end).contains(&hour)
+    } else {
+        hour >= start || hour < end
+    }
+}
+
+// Why today's sweep can't run now, or None when it should
+fn reason_to_wait(settings: &ScheduledTestSettings, now: (u16, u16, u16, u16)) -> Option<&'static str> {
+    let hour = now.3 as u8;
+    if hour < settings.hour {
+        return Some("not time yet");
+    }
+    if in_quiet_hours(settings, hour) {
+        return Some("quiet hours");
+    }
+    if settings.skip_metered && crate::system::is_metered_connection() == Some(true) {
+        return Some("metered connection");
+    }
+    None
+}
+
+/// Test the enabled servers now. Blocks until every test is done.
+pub fn run() -> usize {
+    let config = crate::config::Config::load().unwrap_or_default();
+    let servers: Vec<_> = crate::vpn::VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|server| config.server_settings.get(&server.get_server_key()).is_some_and(|settings| settings.enabled))
+        .collect();
+    if servers.is_empty() {
+        return 0;
+    }
+    println!("Scheduled test of {} enabled servers", servers.len());
+    crate::vpn::latency::test_all(&servers, &|_| {});
+    crate::request_menu_update();
+    servers.len()
+}
+
+/// Check the schedule every 10 minutes and run the day's sweep when it is due
+pub fn start_scheduler() {
+    std::thread::spawn(|| {
+        let mut waiting_logged = String::new();
+        loop {
+            // Plain sleep: the sweep is meant to run while nobody uses the machine
+            std::thread::sleep(CHECK_INTERVAL);
+            let settings = crate::config::Config::load().unwrap_or_default().scheduled_tests;
+            if !settings.enabled {
+                continue;
+            }
+            let now = crate::system::local_time();
+            let today = date_text(now);
+            let Some(path) = last_run_path() else {
+                continue;
+            };
+            if std::fs::read_to_string(&path).is_ok_and(|last| last.trim() == today) {
+                continue;
+            }
+            if let Some(reason) = reason_to_wait(&settings, now) {
+                if waiting_logged != reason {
+                    println!("Scheduled test waiting: {}", reason);
+                    waiting_logged = reason.to_string();
+                }
+                continue;
+            }
+            waiting_logged.clear();
+            if run() > 0 {
+                if let Err(e) = std::fs::write(&path, &today) {
+                    eprintln!("Failed to record the scheduled test: {}", e);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,95 @@
+use std::sync::Mutex;
+
+// Quick server switching: tray → Switch server lists every server, and picking
+// one stops the active server and starts the chosen one on the active server's
+// port and proxy type, so apps and the system proxy keep their settings with a
+// new exit. Like failover the move lasts for this run; saved ports don't change.
+// The active server is the last one switched to while it still holds its port,
+// otherwise the healthiest running server.
+
+/// The server running on the switched port
+#[derive(Debug, Clone)]
+struct Active {
+    key: String,
+    local_port: u16,
+    proxy_type: String,
+    http_port: u16,
+}
+
+static ACTIVE: Mutex<Option<Active>> = Mutex::new(None);
+
+/// Key and port of the active server, if one is running
+#[cfg(feature = "gui")]
+pub fn current() -> Option<(String, u16)> {
+    let switched = ACTIVE.lock().ok().and_then(|active| active.clone());
+    if let Some(active) = switched {
+        if crate::xray_manager::get_server_info(&active.key).is_some_and(|info| info.local_port == active.local_port) {
+            return Some((active.key, active.local_port));
+        }
+    }
+    let running: Vec<String> = crate::xray_manager::get_running_servers().into_iter()
+        .filter(|key| !crate::balancer::is_group_key(key))
+        .collect();
+    let key = crate::health::best_server(&running)?;
+    let info = crate::xray_manager::get_server_info(&key)?;
+    Some((key, info.local_port))
+}
+
+/// Port, proxy type and HTTP port (for "Both") a server was switched onto, for starts
+/// and restarts of it this run
+pub fn port_override(server_key: &str) -> Option<(u16, String, u16)> {
+    ACTIVE.lock().ok()?.as_ref()
+        .filter(|active| active.key == server_key)
+        .map(|active| (active.local_port, active.proxy_type.clone(), active.http_port))
+}
+
+/// Stop the active server and start `server_key` on its port. Without an active server
+/// the chosen one starts on its own port. If it can't start, the previous one comes back.
+#[cfg(feature = "gui")]
+pub fn switch_to(server_key: &str) -> Result<(), String> {
+    let previous = current().and_then(|(key, _)| crate::xray_manager::get_server_info(&key));
+    let (local_port, proxy_type, http_port) = match &previous {
+        Some(info) => (info.local_port, info.proxy_type.clone(), info.http_port),
+        None => {
+            let config = crate::config::Config::load()?;
+            let settings = config.server_settings.get(server_key)
+                .ok_or_else(|| format!("No saved settings for {}", server_key))?;
+            (settings.local_port, settings.proxy_type.clone(), settings.http_port)
+        }
+    };
+    if previous.as_ref().is_some_and(|info| info.key == server_key) {
+        return Ok(());
+    }
+    let system_proxy = crate::sysproxy::active_port() == Some(local_port);
+
+    if let Some(info) = &previous {
+        crate::stop_single_server(&info.key)?;
+    }
+    // Running elsewhere, it would keep its own port
+    crate::stop_single_server(server_key)?;
+    let previous_active = ACTIVE.lock().ok().and_then(|mut active| {
+        active.replace(Active { key: server_key.to_string(), local_port, proxy_type: proxy_type.clone(), http_port })
+    });
+
+    if let Err(e) = crate::start_single_server(server_key) {
+        if let Ok(mut active) = ACTIVE.lock() {
+            *active = previous_active;
+        }
+        if let Some(info) = &previous {
+            if let Err(e) = crate::start_single_server(&info.key) {
+                eprintln!("Switch: could not bring back {}: {}", info.name, e);
+            }
+        }
+        return Err(e);
+    }
+
+    // The stop reverted the system proxy; the port works again
+    if system_proxy && crate::sysproxy::active_port().is_none() {
+        if let Err(e) = crate::sysproxy::enable(local_port, &proxy_type) {
+            eprintln!("Switch: could not restore the system proxy: {}", e);
+        }
+    }
+    println!("Switched port {} to {}", local_port, crate::vpn::get_server_name(server_key));
+    crate::request_menu_update();
+    Ok(())
+}
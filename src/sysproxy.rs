@@ -0,0 +1,696 @@
+use serde::{Deserialize, Serialize};
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::events::{self, Event, ServerInfo};
+
+// System proxy: points the Windows (WinINET) proxy settings at a local port.
+// Before anything is written the port has to accept a connection and relay a
+// test request, so a dead or misconfigured server can't cut the machine off
+// the internet. The settings found before enabling are put back on disable,
+// on exit and when the server behind the port stops. A sentinel file holds
+// them while enabled, so they're also restored on the next start after a crash.
+//
+// It follows servers too: one marked "Set as system proxy" takes it whenever it
+// starts, and with the global toggle on it's set at startup and moved to another
+// running server when its own stops. Optionally the machine-wide WinHTTP proxy
+// (used by services and updaters, which ignore WinINET) is pointed there as well.
+
+#[cfg(windows)]
+#[link(name = "wininet")]
+unsafe extern "system" {
+    // Tells running WinINET clients (browsers, most apps) to re-read the settings
+    fn InternetSetOptionW(internet: *mut std::ffi::c_void, option: u32, buffer: *mut std::ffi::c_void, length: u32) -> i32;
+}
+
+#[cfg(windows)]
+const INTERNET_OPTION_SETTINGS_CHANGED: u32 = 39;
+#[cfg(windows)]
+const INTERNET_OPTION_REFRESH: u32 = 37;
+
+#[cfg(windows)]
+const INTERNET_SETTINGS_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings";
+
+// Under HKEY_LOCAL_MACHINE; what `netsh winhttp set proxy` writes
+#[cfg(windows)]
+const WINHTTP_SETTINGS_KEY: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Internet Settings\\Connections";
+#[cfg(windows)]
+const WINHTTP_SETTINGS_VALUE: &str = "WinHttpSettings";
+
+// Local addresses that never go through the proxy
+const PROXY_OVERRIDE: &str = "localhost;127.*;10.*;172.16.*;192.168.*;<local>";
+
+// How long the loopback connection may take before the port counts as dead
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// WinINET proxy values, as found before enabling
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProxySettings {
+    pub enable: u32,
+    pub server: Option<String>,
+    pub bypass: Option<String>,
+    pub auto_config_url: Option<String>,
+}
+
+// Port the system proxy points at and the settings to restore, while enabled
+static ACTIVE: Mutex<Option<(u16, ProxySettings)>> = Mutex::new(None);
+
+// Set while ACTIVE is the PAC file's port, registered as auto-config URL
+static PAC_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+// WinHTTP settings blob we wrote and the one to restore, while set; empty means no value
+static WINHTTP: Mutex<Option<(Vec<u8>, Vec<u8>)>> = Mutex::new(None);
+
+// Written while the system proxy is ours; found on start only if we didn't revert
+#[derive(Serialize, Deserialize)]
+struct Sentinel {
+    port: u16,
+    applied: ProxySettings,  // What we wrote, to tell if someone changed it since
+    previous: ProxySettings, // What to put back
+    #[serde(default)]
+    winhttp: Option<(Vec<u8>, Vec<u8>)>, // WinHTTP (applied, previous) when set too
+}
+
+fn sentinel_path() -> Option<PathBuf> {
+    let config_path = crate::config::Config::get_config_path().ok()?;
+    Some(config_path.parent()?.join("system-proxy-restore.json"))
+}
+
+/// Port the system proxy currently points at (None when we haven't set it)
+pub fn active_port() -> Option<u16> {
+    ACTIVE.lock().ok()?.as_ref().map(|(port, _)| *port)
+}
+
+/// Local port to use as system proxy: the primary port if enabled, else the
+/// healthiest running server (HTTP inbounds first). Returns (port, proxy type).
+pub fn default_target() -> Option<(u16, String)> {
+    let config = crate::config::Config::load().unwrap_or_default();
+    if config.primary.enabled {
+        return Some((config.primary.port, config.primary.proxy_type));
+    }
+
+    let mut running = crate::xray_manager::get_running_servers();
+    crate::health::sort_by_health(&mut running);
+    let infos: Vec<_> = running.iter().filter_map(|key| crate::xray_manager::get_server_info(key)).collect();
+    infos.iter()
+        .find(|info| info.proxy_type == "HTTP")
+        .or_else(|| infos.first())
+        .map(|info| (info.local_port, info.proxy_type.clone()))
+}
+
+/// Safety check: the port must accept connections and relay a test request.
+/// Explains what's wrong so the caller can refuse to switch the system proxy.
+pub fn check_port(port: u16, proxy_type: &str) -> Result<Duration, String> {
+    let address = SocketAddr::from(([127, 0, 0, 1], port));
+    TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).map_err(|e| format!(
+        "Nothing is accepting connections on 127.0.0.1:{} ({}).\n\
+         The system proxy was left unchanged; start the server first.", port, e))?;
+
+    let test = crate::config::Config::load().unwrap_or_default().test;
+    crate::probe::test_via_proxy(port, proxy_type, &test.url, Duration::from_secs(test.timeout_secs.max(1)))
+        .map_err(|e| format!(
+            "Port {} accepts connections but could not relay a test request to {} ({}).\n\
+             The system proxy was left unchanged, otherwise every app using it would lose internet access.",
+            port, test.url, e))
+}
+
+/// Point the system proxy at a local port after checking the port works
+pub fn enable(port: u16, proxy_type: &str) -> Result<(), String> {
+    crate::system::require_windows("System proxy")?;
+    if crate::inbounds::credentials(port).is_some() {
+        return Err(format!(
+            "Port {} asks for a user name and password, which Windows' proxy settings can't send.\n\
+             The system proxy was left unchanged; use a server without a login (Tools → LAN Sharing).", port));
+    }
+    check_port(port, proxy_type)?;
+
+    // WinINET sends everything to a SOCKS proxy given as "socks=host:port"
+    let server = if proxy_type == "HTTP" {
+        format!("127.0.0.1:{}", port)
+    } else {
+        format!("socks=127.0.0.1:{}", port)
+    };
+    let settings = ProxySettings {
+        enable: 1,
+        server: Some(server),
+        bypass: Some(PROXY_OVERRIDE.to_string()),
+        auto_config_url: None,
+    };
+    // WinHTTP only takes plain HTTP proxies; a SOCKS port puts back what was there
+    let use_winhttp = proxy_type == "HTTP" && Config::load().unwrap_or_default().system_proxy.winhttp;
+    write_active(port, settings, use_winhttp)?;
+    println!("System proxy set to 127.0.0.1:{} ({})", port, proxy_type);
+    Ok(())
+}
+
+/// Register the PAC file served on `port` as the system auto-config URL instead
+/// of a fixed proxy. Restored like `enable`; the PAC file itself follows servers.
+pub fn enable_pac(port: u16, url: &str) -> Result<(), String> {
+    crate::system::require_windows("System proxy")?;
+    let settings = ProxySettings {
+        enable: 0,
+        server: None,
+        bypass: None,
+        auto_config_url: Some(url.to_string()),
+    };
+    write_active(port, settings, false)?;
+    PAC_ACTIVE.store(true, Ordering::SeqCst);
+    println!("System auto-config URL set to {}", url);
+    Ok(())
+}
+
+/// True while the system proxy is our PAC file rather than a fixed port
+pub fn pac_active() -> bool {
+    PAC_ACTIVE.load(Ordering::SeqCst) && active_port().is_some()
+}
+
+// Write proxy settings for `port`, keeping the settings found before we first
+// enabled for the restore
+fn write_active(port: u16, settings: ProxySettings, use_winhttp: bool) -> Result<(), String> {
+    let mut active = ACTIVE.lock().map_err(|_| "System proxy state is poisoned".to_string())?;
+    // Switching ports keeps the settings found before we first enabled
+    let previous = match active.as_ref() {
+        Some((_, previous)) => previous.clone(),
+        None => read_settings()?,
+    };
+    // Sentinel first: a crash right after writing the registry must still be undone
+    let winhttp = WINHTTP.lock().ok().and_then(|winhttp| winhttp.clone());
+    write_sentinel(&Sentinel { port, applied: settings.clone(), previous: previous.clone(), winhttp })?;
+    if let Err(e) = write_settings(&settings) {
+        // Don't leave a half-written proxy behind
+        let _ = write_settings(&previous);
+        apply_winhttp(None);
+        remove_sentinel();
+        *active = None;
+        return Err(e);
+    }
+    PAC_ACTIVE.store(false, Ordering::SeqCst);
+
+    let winhttp = apply_winhttp(use_winhttp.then_some(port));
+    let _ = write_sentinel(&Sentinel { port, applied: settings, previous: previous.clone(), winhttp });
+    *active = Some((port, previous));
+    Ok(())
+}
+
+/// Restore the proxy settings found before enabling (no-op if not enabled)
+pub fn disable() -> Result<(), String> {
+    let mut active = ACTIVE.lock().map_err(|_| "System proxy state is poisoned".to_string())?;
+    let Some((port, previous)) = active.take() else {
+        return Ok(());
+    };
+    if let Err(e) = write_settings(&previous) {
+        // Keep the sentinel so the next start tries again
+        *active = Some((port, previous));
+        return Err(e);
+    }
+    apply_winhttp(None);
+    PAC_ACTIVE.store(false, Ordering::SeqCst);
+    remove_sentinel();
+    println!("System proxy on port {} turned off, previous settings restored", port);
+    Ok(())
+}
+
+/// Turn the global system proxy on or off and save it; while on it's set again at
+/// startup and follows servers as they stop. Turning on keeps the current port
+/// (applying the WinHTTP choice to it), else uses the default target.
+#[cfg(feature = "gui")]
+pub fn set_enabled(enabled: bool) -> Result<(), String> {
+    let mut config = Config::load()?;
+    config.system_proxy.enabled = enabled;
+    if enabled {
+        let (port, proxy_type) = active_port()
+            .map(|port| (port, proxy_type_on(&config, port)))
+            .or_else(default_target)
+            .ok_or_else(|| "No server is running. Start a server (or enable the primary port) first.".to_string())?;
+        enable(port, &proxy_type)?;
+    } else {
+        disable()?;
+    }
+    config.save()
+}
+
+/// Mark a server to take the system proxy whenever it starts; marking a running
+/// server switches to it now. Unmarking leaves the current setting alone.
+#[cfg(feature = "gui")]
+pub fn set_server_preference(server: &crate::vpn::VpnServer, preferred: bool) -> Result<(), String> {
+    let mut config = Config::load()?;
+    let key = server.get_server_key();
+    let settings = config.server_settings.entry(key.clone()).or_insert_with(|| crate::config::ServerSettings {
+        local_port: server.local_port,
+        proxy_type: server.proxy_type.clone(),
+        http_port: server.http_port,
+        auto_port: server.auto_port,
+        enabled: server.enabled,
+        limits: Default::default(),
+        system_proxy: false,
+        alias: server.alias.clone(),
+        note: server.note.clone(),
+        pinned: server.pinned,
+        hidden: server.hidden,
+        listen: server.listen.clone(),
+        udp: server.udp,
+        mux: None,
+        fingerprint: String::new(),
+        fragment: None,
+        upstream: String::new(),
+    });
+    settings.system_proxy = preferred;
+    config.save()?;
+
+    if preferred {
+        if let Some(info) = crate::xray_manager::get_server_info(&key) {
+            if active_port() != Some(info.local_port) {
+                enable(info.local_port, &info.proxy_type)?;
+            }
+        }
+    }
+    crate::request_menu_update();
+    Ok(())
+}
+
+/// Set the system proxy as saved: with the global toggle on, point it at the
+/// default target unless a marked server already holds it. Call at startup once
+/// servers and the primary port are up.
+pub fn apply_saved() {
+    let config = Config::load().unwrap_or_default();
+    if !config.system_proxy.enabled {
+        return;
+    }
+    // A registered PAC file already follows the servers
+    let held_by_marked = pac_active() || active_port().is_some_and(|port| marked_server_on(&config, port));
+    let Some((port, proxy_type)) = default_target() else {
+        return;
+    };
+    if held_by_marked || active_port() == Some(port) {
+        return;
+    }
+    if let Err(e) = enable(port, &proxy_type) {
+        eprintln!("System proxy: {}", e);
+    }
+    crate::request_menu_update();
+}
+
+// Inbound type served on a local port
+#[cfg(feature = "gui")]
+fn proxy_type_on(config: &Config, port: u16) -> String {
+    crate::xray_manager::get_running_servers().iter()
+        .filter_map(|key| crate::xray_manager::get_server_info(key))
+        .find(|info| info.local_port == port)
+        .map(|info| info.proxy_type)
+        .unwrap_or_else(|| config.primary.proxy_type.clone())
+}
+
+// True if a running server marked for the system proxy listens on `port`
+fn marked_server_on(config: &Config, port: u16) -> bool {
+    crate::xray_manager::get_running_servers().iter()
+        .filter(|key| config.server_settings.get(*key).is_some_and(|s| s.system_proxy))
+        .filter_map(|key| crate::xray_manager::get_server_info(key))
+        .any(|info| info.local_port == port)
+}
+
+// A server came up: take it if it's marked, or if the global toggle is on and nothing is set yet
+fn server_started(info: &ServerInfo) {
+    let config = Config::load().unwrap_or_default();
+    let marked = config.server_settings.get(&info.key).is_some_and(|s| s.system_proxy);
+    let target = if marked {
+        (active_port() != Some(info.local_port)).then(|| (info.local_port, info.proxy_type.clone()))
+    } else if config.system_proxy.enabled && active_port().is_none() {
+        default_target()
+    } else {
+        None
+    };
+    if let Some((port, proxy_type)) = target {
+        if let Err(e) = enable(port, &proxy_type) {
+            eprintln!("System proxy: {}", e);
+        }
+        crate::request_menu_update();
+    }
+}
+
+/// Revert the system proxy if it points at `port`, which is going away
+pub fn port_closed(port: u16) {
+    if active_port() != Some(port) {
+        return;
+    }
+    // Failover already put a backup server on the port
+    let running = crate::xray_manager::get_running_servers();
+    if running.iter().filter_map(|key| crate::xray_manager::get_server_info(key)).any(|info| info.local_port == port) {
+        return;
+    }
+    println!("Port {} behind the system proxy closed, reverting", port);
+    if let Err(e) = disable() {
+        eprintln!("Failed to revert system proxy: {}", e);
+    }
+    // Kept on globally: move to what's still running
+    if Config::load().is_ok_and(|config| config.system_proxy.enabled) {
+        if let Some((next_port, proxy_type)) = default_target().filter(|(next_port, _)| *next_port != port) {
+            if let Err(e) = enable(next_port, &proxy_type) {
+                eprintln!("System proxy: {}", e);
+            }
+        }
+    }
+    crate::request_menu_update();
+}
+
+/// Revert the system proxy when the server behind it stops or crashes, and set
+/// it for servers that start as saved.
+/// Servers reached through the primary port don't count: it switches to another.
+pub fn start_revert_watcher() {
+    let receiver = events::subscribe();
+    std::thread::spawn(move || {
+        for event in receiver {
+            match event {
+                Event::ServerStopped(info) | Event::ServerCrashed(info) => port_closed(info.local_port),
+                // The port check takes a moment; don't hold up the events behind it
+                Event::ServerStarted(info) => {
+                    std::thread::spawn(move || server_started(&info));
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+/// Put back the settings a previous run left behind (it crashed or was killed
+/// while the system proxy was set). Call once at startup, before enabling.
+pub fn restore_after_crash() {
+    let Some(path) = sentinel_path() else {
+        return;
+    };
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let result = serde_json::from_str::<Sentinel>(&content)
+        .map_err(|e| format!("Unreadable restore file: {}", e))
+        .and_then(|sentinel| {
+            crate::system::require_windows("System proxy")?;
+            if let Some((applied, previous)) = &sentinel.winhttp {
+                if read_winhttp().as_ref() == Ok(applied) {
+                    write_winhttp(previous)?;
+                    println!("Restored WinHTTP proxy settings left by the last run");
+                }
+            }
+            // Only undo our own change; leave settings the user picked since alone
+            if read_settings()? != sentinel.applied {
+                println!("System proxy was changed since the last run, leaving it as is");
+                return Ok(());
+            }
+            write_settings(&sentinel.previous)?;
+            println!("Restored system proxy settings left by the last run (port {})", sentinel.port);
+            Ok(())
+        });
+    match result {
+        Ok(_) => remove_sentinel(),
+        Err(e) => eprintln!("Failed to restore system proxy: {}", e),
+    }
+}
+
+// Point the WinHTTP proxy at `port`, or put back what was there before with None.
+// Failures (writing it needs administrator rights) only leave WinHTTP unchanged.
+// Returns the (applied, previous) change now in effect.
+fn apply_winhttp(port: Option<u16>) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut state = WINHTTP.lock().ok()?;
+    match port {
+        Some(port) => {
+            let previous = match state.take() {
+                Some((_, previous)) => previous,
+                None => match read_winhttp() {
+                    Ok(previous) => previous,
+                    Err(e) => {
+                        eprintln!("WinHTTP proxy left unchanged: {}", e);
+                        return None;
+                    }
+                },
+            };
+            let applied = winhttp_blob(port);
+            match write_winhttp(&applied) {
+                Ok(()) => {
+                    println!("WinHTTP proxy set to 127.0.0.1:{}", port);
+                    *state = Some((applied, previous));
+                }
+                Err(e) => {
+                    eprintln!("WinHTTP proxy left unchanged: {}", e);
+                    let _ = write_winhttp(&previous);
+                }
+            }
+        }
+        None => {
+            if let Some((applied, previous)) = state.take() {
+                match write_winhttp(&previous) {
+                    Ok(()) => println!("WinHTTP proxy settings restored"),
+                    Err(e) => {
+                        eprintln!("Failed to restore WinHTTP proxy: {}", e);
+                        *state = Some((applied, previous));
+                    }
+                }
+            }
+        }
+    }
+    state.clone()
+}
+
+// WinHttpSettings value for a proxy on a local port: header (size 0x28, counter,
+// flags 3 = proxy) then the length-prefixed proxy and bypass lists
+fn winhttp_blob(port: u16) -> Vec<u8> {
+    let proxy = format!("127.0.0.1:{}", port);
+    let mut blob = Vec::new();
+    for field in [0x28u32, 0, 3] {
+        blob.extend_from_slice(&field.to_le_bytes());
+    }
+    for list in [proxy.as_str(), PROXY_OVERRIDE] {
+        blob.extend_from_slice(&(list.len() as u32).to_le_bytes());
+        blob.extend_from_slice(list.as_bytes());
+    }
+    blob
+}
+
+// Read the WinHTTP settings blob; empty when there is none (direct access)
+fn read_winhttp() -> Result<Vec<u8>, String> {
+    #[cfg(windows)]
+    {
+        use windows::Win32::System::Registry::*;
+
+        let key = open_winhttp_key(KEY_READ)?;
+        let name: Vec<u16> = format!("{}\0", WINHTTP_SETTINGS_VALUE).encode_utf16().collect();
+        let name = windows::core::PCWSTR::from_raw(name.as_ptr());
+        let blob = unsafe {
+            let mut size = 0u32;
+            if RegQueryValueExW(key, name, None, None, None, Some(&mut size)).is_err() {
+                Vec::new()
+            } else {
+                let mut data = vec![0u8; size as usize];
+                if RegQueryValueExW(key, name, None, None, Some(data.as_mut_ptr()), Some(&mut size)).is_err() {
+                    Vec::new()
+                } else {
+                    data.truncate(size as usize);
+                    data
+                }
+            }
+        };
+        unsafe {
+            let _ = RegCloseKey(key);
+        }
+        Ok(blob)
+    }
+
+    #[cfg(not(windows))]
+    {
+        Err("WinHTTP proxy only supported on Windows".to_string())
+    }
+}
+
+// Write the WinHTTP settings blob (empty deletes the value)
+fn write_winhttp(blob: &[u8]) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        use windows::Win32::System::Registry::*;
+
+        let key = open_winhttp_key(KEY_WRITE)?;
+        let name: Vec<u16> = format!("{}\0", WINHTTP_SETTINGS_VALUE).encode_utf16().collect();
+        let name = windows::core::PCWSTR::from_raw(name.as_ptr());
+        let result = unsafe {
+            if blob.is_empty() {
+                let _ = RegDeleteValueW(key, name);
+                Ok(())
+            } else {
+                let result = RegSetValueExW(key, name, 0, REG_BINARY, Some(blob));
+                if result.is_err() {
+                    Err(format!("Failed to set {}: {:?}", WINHTTP_SETTINGS_VALUE, result))
+                } else {
+                    Ok(())
+                }
+            }
+        };
+        unsafe {
+            let _ = RegCloseKey(key);
+        }
+        result
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = blob;
+        Err("WinHTTP proxy only supported on Windows".to_string())
+    }
+}
+
+fn write_sentinel(sentinel: &Sentinel) -> Result<(), String> {
+    let path = sentinel_path().ok_or_else(|| "No config directory for the restore file".to_string())?;
+    let json = serde_json::to_string_pretty(sentinel)
+        .map_err(|e| format!("Failed to serialize restore file: {}", e))?;
+    std::fs::write(&path, json)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn remove_sentinel() {
+    if let Some(path) = sentinel_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+// Read the current WinINET proxy values
+fn read_settings() -> Result<ProxySettings, String> {
+    #[cfg(windows)]
+    {
+        use windows::Win32::System::Registry::*;
+
+        let key = open_settings_key(KEY_READ)?;
+        let enable = unsafe {
+            let mut value = 0u32;
+            let mut size = std::mem::size_of::<u32>() as u32;
+            let name: Vec<u16> = "ProxyEnable\0".encode_utf16().collect();
+            let result = RegQueryValueExW(key, windows::core::PCWSTR::from_raw(name.as_ptr()), None, None,
+                Some(&mut value as *mut u32 as *mut u8), Some(&mut size));
+            if result.is_ok() { value } else { 0 }
+        };
+        let settings = ProxySettings {
+            enable,
+            server: read_string(key, "ProxyServer"),
+            bypass: read_string(key, "ProxyOverride"),
+            auto_config_url: read_string(key, "AutoConfigURL"),
+        };
+        unsafe {
+            let _ = RegCloseKey(key);
+        }
+        Ok(settings)
+    }
+
+    #[cfg(not(windows))]
+    {
+        Err("System proxy only supported on Windows".to_string())
+    }
+}
+
+// Write WinINET proxy values (None deletes the value) and notify running apps
+fn write_settings(settings: &ProxySettings) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        use windows::Win32::System::Registry::*;
+
+        let key = open_settings_key(KEY_WRITE)?;
+        let result = unsafe {
+            let name: Vec<u16> = "ProxyEnable\0".encode_utf16().collect();
+            RegSetValueExW(key, windows::core::PCWSTR::from_raw(name.as_ptr()), 0, REG_DWORD,
+                Some(&settings.enable.to_le_bytes()))
+        };
+        let result = if result.is_err() {
+            Err(format!("Failed to set ProxyEnable: {:?}", result))
+        } else {
+            write_string(key, "ProxyServer", settings.server.as_deref())
+                .and_then(|_| write_string(key, "ProxyOverride", settings.bypass.as_deref()))
+                .and_then(|_| write_string(key, "AutoConfigURL", settings.auto_config_url.as_deref()))
+        };
+        unsafe {
+            let _ = RegCloseKey(key);
+            InternetSetOptionW(std::ptr::null_mut(), INTERNET_OPTION_SETTINGS_CHANGED, std::ptr::null_mut(), 0);
+            InternetSetOptionW(std::ptr::null_mut(), INTERNET_OPTION_REFRESH, std::ptr::null_mut(), 0);
+        }
+        result
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = settings;
+        Err("System proxy only supported on Windows".to_string())
+    }
+}
+
+#[cfg(windows)]
+fn open_settings_key(access: windows::Win32::System::Registry::REG_SAM_FLAGS) -> Result<windows::Win32::System::Registry::HKEY, String> {
+    use windows::Win32::System::Registry::*;
+
+    let path: Vec<u16> = format!("{}\0", INTERNET_SETTINGS_KEY).encode_utf16().collect();
+    let mut key = HKEY::default();
+    let result = unsafe {
+        RegOpenKeyExW(HKEY_CURRENT_USER, windows::core::PCWSTR::from_raw(path.as_ptr()), 0, access, &mut key)
+    };
+    if result.is_err() {
+        return Err(format!("Failed to open Internet Settings: {:?}", result));
+    }
+    Ok(key)
+}
+
+#[cfg(windows)]
+fn open_winhttp_key(access: windows::Win32::System::Registry::REG_SAM_FLAGS) -> Result<windows::Win32::System::Registry::HKEY, String> {
+    use windows::Win32::System::Registry::*;
+
+    let path: Vec<u16> = format!("{}\0", WINHTTP_SETTINGS_KEY).encode_utf16().collect();
+    let mut key = HKEY::default();
+    let result = unsafe {
+        RegOpenKeyExW(HKEY_LOCAL_MACHINE, windows::core::PCWSTR::from_raw(path.as_ptr()), 0, access, &mut key)
+    };
+    if result.is_err() {
+        return Err(format!("Failed to open the WinHTTP settings (administrator rights needed): {:?}", result));
+    }
+    Ok(key)
+}
+
+#[cfg(windows)]
+fn read_string(key: windows::Win32::System::Registry::HKEY, name: &str) -> Option<String> {
+    use windows::Win32::System::Registry::*;
+
+    let name: Vec<u16> = format!("{}\0", name).encode_utf16().collect();
+    let name = windows::core::PCWSTR::from_raw(name.as_ptr());
+    unsafe {
+        let mut size = 0u32;
+        if RegQueryValueExW(key, name, None, None, None, Some(&mut size)).is_err() {
+            return None;
+        }
+        let mut data = vec![0u16; (size as usize).div_ceil(2)];
+        if RegQueryValueExW(key, name, None, None, Some(data.as_mut_ptr() as *mut u8), Some(&mut size)).is_err() {
+            return None;
+        }
+        let len = data.iter().position(|&c| c == 0).unwrap_or(data.len());
+        Some(String::from_utf16_lossy(&data[..len]))
+    }
+}
+
+#[cfg(windows)]
+fn write_string(key: windows::Win32::System::Registry::HKEY, name: &str, value: Option<&str>) -> Result<(), String> {
+    use windows::Win32::System::Registry::*;
+
+    let name_wide: Vec<u16> = format!("{}\0", name).encode_utf16().collect();
+    let name_pcwstr = windows::core::PCWSTR::from_raw(name_wide.as_ptr());
+    unsafe {
+        let result = match value {
+            Some(value) => {
+                let wide: Vec<u16> = format!("{}\0", value).encode_utf16().collect();
+                let data = std::slice::from_raw_parts(wide.as_ptr() as *const u8, wide.len() * 2);
+                RegSetValueExW(key, name_pcwstr, 0, REG_SZ, Some(data))
+            }
+            None => {
+                // Missing is fine: the value didn't exist before either
+                let _ = RegDeleteValueW(key, name_pcwstr);
+                return Ok(());
+            }
+        };
+        if result.is_err() {
+            return Err(format!("Failed to set {}: {:?}", name, result));
+        }
+    }
+    Ok(())
+}
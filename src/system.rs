@@ -0,0 +1,172 @@
+// Windows system integration helpers
+
+use std::sync::OnceLock;
+
+#[cfg(windows)]
+#[link(name = "dnsapi")]
+unsafe extern "system" {
+    // Exported by dnsapi.dll; same call `ipconfig /flushdns` makes
+    fn DnsFlushResolverCache() -> i32;
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn GetModuleHandleW(module_name: *const u16) -> *mut std::ffi::c_void;
+    fn GetProcAddress(module: *mut std::ffi::c_void, proc_name: *const u8) -> *mut std::ffi::c_void;
+    fn GetLocalTime(time: *mut LocalTime);
+}
+
+// SYSTEMTIME
+#[cfg(windows)]
+#[repr(C)]
+#[derive(Default)]
+struct LocalTime {
+    year: u16,
+    month: u16,
+    day_of_week: u16,
+    day: u16,
+    hour: u16,
+    minute: u16,
+    second: u16,
+    milliseconds: u16,
+}
+
+// Wine version string, detected once ("" when not under Wine)
+static WINE_VERSION: OnceLock<String> = OnceLock::new();
+
+/// Wine (or Proton) version when running under it, None on real Windows.
+/// Wine's ntdll exports wine_get_version; Windows' doesn't.
+pub fn wine_version() -> Option<&'static str> {
+    let version = WINE_VERSION.get_or_init(|| {
+        #[cfg(windows)]
+        unsafe {
+            let ntdll: Vec<u16> = "ntdll.dll\0".encode_utf16().collect();
+            let module = GetModuleHandleW(ntdll.as_ptr());
+            if module.is_null() {
+                return String::new();
+            }
+            let proc = GetProcAddress(module, c"wine_get_version".as_ptr() as *const u8);
+            if proc.is_null() {
+                return String::new();
+            }
+            let get_version: unsafe extern "C" fn() -> *const std::ffi::c_char = std::mem::transmute(proc);
+            let version = std::ffi::CStr::from_ptr(get_version()).to_string_lossy().into_owned();
+            // Proton is Wine started by Steam
+            if std::env::var_os("STEAM_COMPAT_DATA_PATH").is_some() {
+                format!("Proton (Wine {})", version)
+            } else {
+                format!("Wine {}", version)
+            }
+        }
+        #[cfg(not(windows))]
+        String::new()
+    });
+    (!version.is_empty()).then_some(version.as_str())
+}
+
+/// True when running under Wine/Proton rather than Windows
+pub fn is_wine() -> bool {
+    wine_version().is_some()
+}
+
+/// Err with an explanation if `feature` needs real Windows and we're under Wine.
+/// Used to turn Windows-only integration off up front instead of failing halfway.
+pub fn require_windows(feature: &str) -> Result<(), String> {
+    match wine_version() {
+        Some(wine) => Err(format!("{} is not available under {}: it changes Windows settings the Linux desktop doesn't use", feature, wine)),
+        None => Ok(()),
+    }
+}
+
+/// Log which Windows-only features are off when started under Wine
+pub fn report_wine() {
+    if let Some(wine) = wine_version() {
+        println!("Running under {}: autostart and DNS cache flush are disabled", wine);
+    }
+}
+
+/// Flush the Windows DNS resolver cache
+pub fn flush_dns_cache() -> Result<(), String> {
+    require_windows("DNS cache flush")?;
+    
+    #[cfg(windows)]
+    {
+        let ok = unsafe { DnsFlushResolverCache() };
+        if ok == 0 {
+            return Err("DnsFlushResolverCache failed".to_string());
+        }
+        println!("Flushed DNS resolver cache");
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    {
+        Err("DNS cache flush only supported on Windows".to_string())
+    }
+}
+
+/// Flush DNS if enabled in config and at least one server is running (never under Wine)
+pub fn flush_dns_after_connect(config: &crate::config::Config) {
+    if config.flush_dns_on_connect && !is_wine() && !crate::xray_manager::get_running_servers().is_empty() {
+        if let Err(e) = flush_dns_cache() {
+            eprintln!("Failed to flush DNS cache: {}", e);
+        }
+    }
+}
+
+/// Local date and hour as (year, month, day, hour); UTC where the local zone isn't known
+pub fn local_time() -> (u16, u16, u16, u16) {
+    #[cfg(windows)]
+    {
+        let mut time = LocalTime::default();
+        unsafe { GetLocalTime(&mut time) };
+        (time.year, time.month, time.day, time.hour)
+    }
+    #[cfg(not(windows))]
+    {
+        let secs = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+        let z = (secs / 86400) as i64 + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+        (year as u16, month as u16, day as u16, (secs % 86400 / 3600) as u16)
+    }
+}
+
+/// Whether Windows counts the internet connection as metered (a data plan or a connection
+/// marked "metered" in Settings). None when it can't tell, e.g. under Wine.
+pub fn is_metered_connection() -> Option<bool> {
+    if is_wine() {
+        return None;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        // The connection cost is only offered through WinRT; PowerShell can reach it
+        let script = "[void][Windows.Networking.Connectivity.NetworkInformation,Windows.Networking.Connectivity,ContentType=WindowsRuntime];\
+            $p=[Windows.Networking.Connectivity.NetworkInformation]::GetInternetConnectionProfile();\
+            if ($p) { $p.GetConnectionCost().NetworkCostType }";
+        let output = std::process::Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", script])
+            .stdin(std::process::Stdio::null())
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .ok()?;
+        // Unrestricted, Fixed, Variable or Unknown
+        match String::from_utf8_lossy(&output.stdout).trim() {
+            "Unrestricted" => Some(false),
+            "Fixed" | "Variable" => Some(true),
+            _ => None,
+        }
+    }
+    #[cfg(not(windows))]
+    None
+}
@@ -0,0 +1,347 @@
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::config::{Config, TunSettings};
+use crate::events::{self, Event};
+
+// TUN mode: all system traffic, not only apps set up for SOCKS/HTTP, goes through
+// one server. sing-box creates a wintun adapter (the driver ships inside sing-box),
+// routes everything into it and hands each connection to the server's local proxy
+// port. The proxy cores' own connections would loop back into the adapter, so their
+// processes are sent out directly. Creating the adapter needs administrator rights;
+// without them the tray offers to restart the manager elevated.
+
+#[cfg(windows)]
+#[link(name = "shell32")]
+unsafe extern "system" {
+    fn IsUserAnAdmin() -> i32;
+    #[cfg(feature = "gui")]
+    fn ShellExecuteW(
+        hwnd: *mut std::ffi::c_void,
+        operation: *const u16,
+        file: *const u16,
+        parameters: *const u16,
+        directory: *const u16,
+        show_cmd: i32,
+    ) -> isize;
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn OpenProcess(desired_access: u32, inherit_handle: i32, process_id: u32) -> *mut std::ffi::c_void;
+    fn WaitForSingleObject(handle: *mut std::ffi::c_void, milliseconds: u32) -> u32;
+    fn CloseHandle(handle: *mut std::ffi::c_void) -> i32;
+}
+
+/// Log key the sing-box TUN output is kept under
+pub const LOG_KEY: &str = "tun";
+
+/// Stacks sing-box offers for the adapter, as saved in the config
+pub const STACKS: &[&str] = &["mixed", "system", "gvisor"];
+
+/// Why TUN mode can't start in this process
+pub const NEEDS_ADMIN: &str = "TUN mode creates a network adapter, which needs administrator rights";
+
+// Passed to the elevated instance so it waits for this one to release the ports
+const AFTER_ARG: &str = "--after";
+
+// sing-box exits within this if the adapter can't be created or the config is bad
+const STARTUP_GRACE: Duration = Duration::from_millis(1500);
+
+// Adapter addresses; small private ranges unlikely to clash with a LAN
+const TUN_ADDRESSES: &[&str] = &["172.19.0.1/30", "fdfe:dcba:9876::1/126"];
+
+// The sing-box TUN process and the local port it forwards to
+static TUN_PROCESS: Mutex<Option<(Child, u16)>> = Mutex::new(None);
+
+/// Local port the tunnel forwards to while TUN mode is on
+pub fn active_port() -> Option<u16> {
+    let mut process = TUN_PROCESS.lock().ok()?;
+    let (child, port) = process.as_mut()?;
+    match child.try_wait() {
+        Ok(None) => Some(*port),
+        // Exited on its own (adapter removed, killed); forget it
+        _ => {
+            *process = None;
+            None
+        }
+    }
+}
+
+/// Whether this process may create a network adapter
+pub fn is_elevated() -> bool {
+    #[cfg(windows)]
+    return unsafe { IsUserAnAdmin() != 0 };
+    #[cfg(not(windows))]
+    true
+}
+
+/// sing-box config: a TUN inbound taking over the default route, with everything
+/// except the proxy cores (and LAN addresses if asked) sent to the local port
+pub fn build_config(port: u16, proxy_type: &str, settings: &TunSettings) -> Result<String, String> {
+    let mut proxy = if proxy_type == "HTTP" {
+        // HTTP proxies carry no UDP; QUIC and games fall back to TCP or fail
+        json!({ "type": "http", "tag": "proxy", "server": "127.0.0.1", "server_port": port })
+    } else {
+        json!({ "type": "socks", "tag": "proxy", "server": "127.0.0.1", "server_port": port, "version": "5" })
+    };
+    if let Some((user, pass)) = crate::inbounds::credentials(port) {
+        proxy["username"] = json!(user);
+        proxy["password"] = json!(pass);
+    }
+
+    let mut direct_processes: Vec<String> = Vec::new();
+    let config = Config::load().unwrap_or_default();
+    for binary in [config.xray_binary_path.as_str(), config.singbox_binary_path.as_str()] {
+        if let Some(name) = Path::new(binary).file_name() {
+            direct_processes.push(name.to_string_lossy().into_owned());
+        }
+    }
+    // Subscription and rule set downloads go out directly too, like they do without TUN
+    if let Some(name) = std::env::current_exe().ok().as_deref().and_then(Path::file_name) {
+        direct_processes.push(name.to_string_lossy().into_owned());
+    }
+
+    let mut rules = vec![
+        json!({ "action": "sniff" }),
+        json!({ "protocol": "dns", "action": "hijack-dns" }),
+        json!({ "process_name": direct_processes, "outbound": "direct" }),
+    ];
+    if settings.bypass_lan {
+        rules.push(json!({ "ip_is_private": true, "outbound": "direct" }));
+    }
+
+    let stack = if STACKS.contains(&settings.stack.as_str()) { settings.stack.as_str() } else { STACKS[0] };
+    let config = json!({
+        "log": { "level": "warn", "timestamp": true },
+        "dns": {
+            "servers": [
+                { "tag": "remote", "address": "tcp://1.1.1.1", "detour": "proxy" },
+                { "tag": "local", "address": "local", "detour": "direct" },
+            ],
+            "rules": [{ "process_name": direct_processes, "server": "local" }],
+            "final": "remote",
+        },
+        "inbounds": [{
+            "type": "tun",
+            "tag": "tun-in",
+            "interface_name": "VPN Manager",
+            "address": TUN_ADDRESSES,
+            "auto_route": true,
+            "strict_route": true,
+            "stack": stack,
+        }],
+        "outbounds": [proxy, { "type": "direct", "tag": "direct" }],
+        "route": {
+            "rules": rules,
+            "final": "proxy",
+            "auto_detect_interface": true,
+        },
+    });
+    serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to write sing-box config: {}", e))
+}
+
+// Port to tunnel through: the chosen server if it runs, else what the system proxy would use
+fn target(settings: &TunSettings) -> Option<(u16, String)> {
+    if !settings.server.is_empty() {
+        if let Some(info) = crate::xray_manager::get_server_info(&settings.server) {
+            return Some((info.local_port, info.proxy_type));
+        }
+    }
+    crate::sysproxy::default_target()
+}
+
+/// Start the tunnel (replacing a running one); returns the port it forwards to
+pub fn start(settings: &TunSettings) -> Result<u16, String> {
+    crate::system::require_windows("TUN mode")?;
+    if !is_elevated() {
+        return Err(NEEDS_ADMIN.to_string());
+    }
+    let (port, proxy_type) = target(settings)
+        .ok_or_else(|| "No server is running. Start a server (or enable the primary port) first.".to_string())?;
+    let binary = crate::vpn::hysteria2::singbox_binary()
+        .map_err(|_| "TUN mode runs in sing-box: set the sing-box binary in Tools → Options".to_string())?;
+    let config_json = build_config(port, &proxy_type, settings)?;
+
+    stop();
+    let dir = std::env::temp_dir().join("vpn-manager");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    let config_path = dir.join("sing-box-tun.json");
+    std::fs::write(&config_path, config_json)
+        .map_err(|e| format!("Failed to write sing-box config: {}", e))?;
+
+    crate::xray_log::clear(LOG_KEY);
+    let mut command = Command::new(&binary);
+    command.arg("run")
+        .arg("-c")
+        .arg(&config_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = command.spawn()
+        .map_err(|e| format!("Failed to start sing-box: {}", e))?;
+    if let Some(stdout) = child.stdout.take() {
+        capture_output(stdout);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        capture_output(stderr);
+    }
+
+    std::thread::sleep(STARTUP_GRACE);
+    if let Ok(Some(status)) = child.try_wait() {
+        let reason = crate::xray_log::get_lines(LOG_KEY).pop()
+            .unwrap_or_else(|| format!("sing-box exited ({})", status));
+        return Err(format!("TUN mode failed to start: {}", reason));
+    }
+
+    println!("TUN mode on: all traffic goes to 127.0.0.1:{} ({})", port, proxy_type);
+    if let Ok(mut process) = TUN_PROCESS.lock() {
+        *process = Some((child, port));
+    }
+    crate::request_menu_update();
+    Ok(port)
+}
+
+fn capture_output<R: Read + Send + 'static>(stream: R) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            crate::xray_log::push(LOG_KEY, &line);
+        }
+    });
+}
+
+/// Remove the adapter and its routes (sing-box undoes them as it exits)
+pub fn stop() {
+    let Ok(mut process) = TUN_PROCESS.lock() else {
+        return;
+    };
+    if let Some((mut child, _)) = process.take() {
+        if child.try_wait().ok().flatten().is_none() {
+            let _ = child.kill();
+        }
+        let _ = child.wait();
+        println!("TUN mode off");
+        crate::request_menu_update();
+    }
+}
+
+/// Start or stop the tunnel to match the config
+pub fn apply(config: &Config) -> Result<(), String> {
+    if !config.tun.enabled {
+        stop();
+        return Ok(());
+    }
+    start(&config.tun).map(|_| ())
+}
+
+/// Turn TUN mode on or off and save it; turning on is only saved once the tunnel is up
+#[cfg(feature = "gui")]
+pub fn set_enabled(enabled: bool) -> Result<(), String> {
+    let mut config = Config::load()?;
+    config.tun.enabled = enabled;
+    apply(&config)?;
+    config.save()
+}
+
+/// Start another instance of the manager as administrator (UAC prompt), with the
+/// same arguments. It waits for this one to exit, so the caller should exit next.
+#[cfg(all(windows, feature = "gui"))]
+pub fn relaunch_elevated() -> Result<(), String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to find the manager's executable: {}", e))?;
+    let mut args: Vec<String> = Vec::new();
+    let mut original = std::env::args().skip(1);
+    while let Some(arg) = original.next() {
+        // Drop the wait of an earlier relaunch
+        if arg == AFTER_ARG {
+            original.next();
+            continue;
+        }
+        args.push(if arg.contains(' ') { format!("\"{}\"", arg) } else { arg });
+    }
+    args.push(format!("{} {}", AFTER_ARG, std::process::id()));
+
+    let wide = |s: &str| -> Vec<u16> { s.encode_utf16().chain(std::iter::once(0)).collect() };
+    let operation = wide("runas");
+    let file = wide(&exe.to_string_lossy());
+    let parameters = wide(&args.join(" "));
+    // Values above 32 mean success; the UAC prompt being declined is an error
+    let result = unsafe {
+        ShellExecuteW(std::ptr::null_mut(), operation.as_ptr(), file.as_ptr(), parameters.as_ptr(), std::ptr::null(), 1)
+    };
+    if result <= 32 {
+        return Err("The restart as administrator was cancelled or failed".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(all(not(windows), feature = "gui"))]
+pub fn relaunch_elevated() -> Result<(), String> {
+    Err("Restarting as administrator is only supported on Windows".to_string())
+}
+
+/// With `--after <pid>` (from `relaunch_elevated`), wait up to 30s for that instance to
+/// exit so its servers have released their ports before ours start
+pub fn wait_for_previous_instance(args: &[String]) {
+    let Some(pid) = args.iter()
+        .position(|a| a == AFTER_ARG)
+        .and_then(|i| args.get(i + 1))
+        .and_then(|pid| pid.parse::<u32>().ok())
+    else {
+        return;
+    };
+    #[cfg(windows)]
+    unsafe {
+        const SYNCHRONIZE: u32 = 0x0010_0000;
+        let handle = OpenProcess(SYNCHRONIZE, 0, pid);
+        if !handle.is_null() {
+            println!("Waiting for the previous instance ({}) to exit", pid);
+            WaitForSingleObject(handle, 30_000);
+            CloseHandle(handle);
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = pid;
+}
+
+/// Turn the tunnel off when the port it forwards to goes away for good, so a stopped
+/// server doesn't leave the machine without a route. Failover gets a moment to take over.
+pub fn start_port_watcher() {
+    let receiver = events::subscribe();
+    std::thread::spawn(move || {
+        for event in receiver {
+            let (Event::ServerStopped(info) | Event::ServerCrashed(info)) = event else {
+                continue;
+            };
+            if active_port() != Some(info.local_port) {
+                continue;
+            }
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_secs(5));
+                let config = Config::load().unwrap_or_default();
+                let still_served = crate::xray_manager::get_running_servers().iter()
+                    .filter_map(|key| crate::xray_manager::get_server_info(key))
+                    .any(|running| running.local_port == info.local_port)
+                    || (config.primary.enabled && config.primary.port == info.local_port);
+                if !still_served && active_port() == Some(info.local_port) {
+                    eprintln!("TUN mode: {} stopped and nothing serves port {}, turning TUN off", info.name, info.local_port);
+                    stop();
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,297 @@
+#[cfg(windows)]
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE},
+        Graphics::Gdi::{SetBkMode, TRANSPARENT, HDC, GetStockObject, WHITE_BRUSH},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+#[cfg(windows)]
+use super::controls::*;
+
+use crate::config::Config;
+
+// Control IDs
+const ID_API_ENABLED_CHECKBOX: i32 = 1201;
+const ID_API_PORT_EDIT: i32 = 1202;
+const ID_API_LOOPBACK_CHECKBOX: i32 = 1203;
+const ID_API_ORIGINS_EDIT: i32 = 1204;
+const ID_API_TOKENS_LIST: i32 = 1205;
+const ID_API_GENERATE_BUTTON: i32 = 1206;
+const ID_API_ROTATE_BUTTON: i32 = 1207;
+const ID_API_REVOKE_BUTTON: i32 = 1208;
+const ID_API_CLIENTS_LIST: i32 = 1209;
+const ID_API_APPLY_BUTTON: i32 = 1210;
+const ID_API_CLOSE_BUTTON: i32 = 1211;
+
+// Timer for refreshing the connected clients list
+const ID_CLIENTS_TIMER: usize = 1;
+
+// Layout constants (match settings window)
+const MARGIN: i32 = 15;
+const FONT_SIZE: i32 = 32;
+const CONTROL_HEIGHT: i32 = 45;
+const LABEL_WIDTH: i32 = 300;
+
+// Only one API window at a time (HWND stored as raw value)
+static API_WINDOW: std::sync::atomic::AtomicIsize = std::sync::atomic::AtomicIsize::new(0);
+
+/// Open the API access window, or focus it if already open
+#[cfg(windows)]
+pub unsafe fn show_api_window(owner: HWND) {
+    unsafe {
+        let existing = HWND(API_WINDOW.load(std::sync::atomic::Ordering::Relaxed) as *mut _);
+        if !existing.is_invalid() && IsWindow(existing).as_bool() {
+            let _ = ShowWindow(existing, SW_RESTORE);
+            let _ = SetForegroundWindow(existing);
+            return;
+        }
+
+        let hinstance: HINSTANCE = GetModuleHandleW(None).unwrap().into();
+        register_window_class(w!("ApiWindowClass"), Some(api_window_proc), hinstance);
+
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("ApiWindowClass"),
+            w!("API Access"),
+            WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_VISIBLE,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            820,
+            1000,
+            owner,
+            None,
+            hinstance,
+            None,
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                eprintln!("Failed to create API window: {}", e);
+                return;
+            }
+        };
+
+        API_WINDOW.store(hwnd.0 as isize, std::sync::atomic::Ordering::Relaxed);
+        create_api_controls(hwnd, hinstance);
+        SetTimer(hwnd, ID_CLIENTS_TIMER, 2000, None);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn create_api_controls(hwnd: HWND, hinstance: HINSTANCE) {
+    let config = Config::load().unwrap_or_default();
+    let api = &config.api;
+    let hfont = unsafe { create_font(FONT_SIZE) };
+    let full_width = 820 - 2 * MARGIN - 20;
+
+    unsafe {
+        let mut y = MARGIN;
+        create_checkbox(hwnd, hinstance, "Enable local control API", ID_API_ENABLED_CHECKBOX,
+            api.enabled, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + MARGIN;
+        create_label(hwnd, hinstance, "Port:", (MARGIN, y + 5, LABEL_WIDTH, CONTROL_HEIGHT), hfont);
+        if let Some(edit) = create_edit(hwnd, hinstance, &api.port.to_string(), ID_API_PORT_EDIT,
+            (MARGIN + LABEL_WIDTH, y, 150, CONTROL_HEIGHT), hfont) {
+            let style = GetWindowLongW(edit, GWL_STYLE);
+            SetWindowLongW(edit, GWL_STYLE, style | ES_NUMBER as i32);
+        }
+
+        y += CONTROL_HEIGHT + MARGIN;
+        create_checkbox(hwnd, hinstance, "Loopback only (127.0.0.1)", ID_API_LOOPBACK_CHECKBOX,
+            api.loopback_only, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + MARGIN;
+        create_label(hwnd, hinstance, "Allowed origins (comma-separated, * for any):",
+            (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_edit(hwnd, hinstance, &api.allowed_origins.join(", "), ID_API_ORIGINS_EDIT,
+            (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + MARGIN;
+        create_label(hwnd, hinstance, "Access tokens:", (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_listbox(hwnd, hinstance, ID_API_TOKENS_LIST, (MARGIN, y, full_width, 200), hfont);
+
+        y += 200 + 10;
+        let button_width = (full_width - 20) / 3;
+        create_button(hwnd, hinstance, "Generate", ID_API_GENERATE_BUTTON,
+            (MARGIN, y, button_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Rotate", ID_API_ROTATE_BUTTON,
+            (MARGIN + button_width + 10, y, button_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Revoke", ID_API_REVOKE_BUTTON,
+            (MARGIN + 2 * (button_width + 10), y, button_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + MARGIN;
+        create_label(hwnd, hinstance, "Connected clients (last 10 minutes):",
+            (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_listbox(hwnd, hinstance, ID_API_CLIENTS_LIST, (MARGIN, y, full_width, 180), hfont);
+
+        y += 180 + MARGIN;
+        create_button(hwnd, hinstance, "Apply", ID_API_APPLY_BUTTON,
+            (full_width + MARGIN - 240, y, 110, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Close", ID_API_CLOSE_BUTTON,
+            (full_width + MARGIN - 120, y, 110, CONTROL_HEIGHT), hfont);
+
+        refresh_token_list(hwnd, &config);
+        refresh_client_list(hwnd);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn refresh_token_list(hwnd: HWND, config: &Config) {
+    let items: Vec<String> = config.api.tokens.iter()
+        .map(|t| format!("{}  —  {}", t.name, crate::api::mask_token(&t.token)))
+        .collect();
+    unsafe { set_listbox_items(hwnd, ID_API_TOKENS_LIST, &items) };
+}
+
+#[cfg(windows)]
+unsafe fn refresh_client_list(hwnd: HWND) {
+    let now = crate::api::unix_now();
+    let items: Vec<String> = crate::api::get_connected_clients().iter()
+        .map(|c| format!("{}  —  {}  —  {} requests, last {}s ago",
+            c.address, c.token_name, c.requests, now.saturating_sub(c.last_seen)))
+        .collect();
+    unsafe { set_listbox_items(hwnd, ID_API_CLIENTS_LIST, &items) };
+}
+
+// Read the form fields into the given config
+#[cfg(windows)]
+unsafe fn read_form(hwnd: HWND, config: &mut Config) {
+    unsafe {
+        config.api.enabled = is_checked(hwnd, ID_API_ENABLED_CHECKBOX);
+        config.api.loopback_only = is_checked(hwnd, ID_API_LOOPBACK_CHECKBOX);
+        if let Ok(port) = get_control_text(hwnd, ID_API_PORT_EDIT).trim().parse::<u16>() {
+            config.api.port = port;
+        }
+        config.api.allowed_origins = get_control_text(hwnd, ID_API_ORIGINS_EDIT)
+            .split(',')
+            .map(|o| o.trim().to_string())
+            .filter(|o| !o.is_empty())
+            .collect();
+    }
+}
+
+// Save the config and restart the listener with the new settings
+#[cfg(windows)]
+unsafe fn save_and_apply(hwnd: HWND, config: &Config) {
+    match config.save() {
+        Ok(_) => crate::api::apply(&config.api),
+        Err(e) => unsafe {
+            message_box(hwnd, &format!("Failed to save config:\n{}", e), "Error", MB_OK | MB_ICONERROR);
+        },
+    }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn api_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => {
+            let control_id = (wparam.0 & 0xFFFF) as i32;
+            let notification_code = (wparam.0 >> 16) & 0xFFFF;
+            if notification_code != 0 {
+                return LRESULT(0);
+            }
+
+            match control_id {
+                ID_API_GENERATE_BUTTON => unsafe {
+                    let mut config = Config::load().unwrap_or_default();
+                    read_form(hwnd, &mut config);
+                    let name = format!("Token {}", config.api.tokens.len() + 1);
+                    let token = crate::api::new_token(&name);
+                    let token_value = token.token.clone();
+                    config.api.tokens.push(token);
+                    save_and_apply(hwnd, &config);
+                    refresh_token_list(hwnd, &config);
+                    message_box(
+                        hwnd,
+                        &format!("{} created. It won't be shown again.\n(Press Ctrl+C to copy this message.)\n\n{}", name, token_value),
+                        "New API token",
+                        MB_OK | MB_ICONINFORMATION,
+                    );
+                },
+                ID_API_ROTATE_BUTTON | ID_API_REVOKE_BUTTON => unsafe {
+                    let Some(index) = get_listbox_selection(hwnd, ID_API_TOKENS_LIST) else {
+                        message_box(hwnd, "Select a token first.", "API Access", MB_OK | MB_ICONWARNING);
+                        return LRESULT(0);
+                    };
+                    let mut config = Config::load().unwrap_or_default();
+                    read_form(hwnd, &mut config);
+                    if index >= config.api.tokens.len() {
+                        return LRESULT(0);
+                    }
+
+                    if control_id == ID_API_ROTATE_BUTTON {
+                        let rotated = crate::api::new_token(&config.api.tokens[index].name);
+                        let token_value = rotated.token.clone();
+                        let name = rotated.name.clone();
+                        config.api.tokens[index] = rotated;
+                        save_and_apply(hwnd, &config);
+                        message_box(
+                            hwnd,
+                            &format!("{} rotated. The old value no longer works.\n(Press Ctrl+C to copy this message.)\n\n{}", name, token_value),
+                            "API token rotated",
+                            MB_OK | MB_ICONINFORMATION,
+                        );
+                    } else {
+                        let removed = config.api.tokens.remove(index);
+                        save_and_apply(hwnd, &config);
+                        // Forget clients that used the revoked token
+                        if let Ok(mut clients) = crate::api::API_CLIENTS.lock() {
+                            clients.retain(|_, c| c.token_name != removed.name);
+                        }
+                    }
+                    refresh_token_list(hwnd, &config);
+                    refresh_client_list(hwnd);
+                },
+                ID_API_APPLY_BUTTON => unsafe {
+                    let mut config = Config::load().unwrap_or_default();
+                    read_form(hwnd, &mut config);
+                    if config.api.enabled && !config.api.loopback_only && config.api.tokens.is_empty() {
+                        message_box(
+                            hwnd,
+                            "The API is reachable from the network but has no tokens, so every request will be rejected. Generate a token first.",
+                            "API Access",
+                            MB_OK | MB_ICONWARNING,
+                        );
+                    }
+                    save_and_apply(hwnd, &config);
+                },
+                ID_API_CLOSE_BUTTON => unsafe {
+                    let _ = DestroyWindow(hwnd);
+                },
+                _ => {}
+            }
+            LRESULT(0)
+        }
+        WM_TIMER => {
+            if wparam.0 == ID_CLIENTS_TIMER {
+                unsafe { refresh_client_list(hwnd) };
+            }
+            LRESULT(0)
+        }
+        WM_CTLCOLORSTATIC => {
+            unsafe {
+                let hdc = HDC(wparam.0 as *mut _);
+                SetBkMode(hdc, TRANSPARENT);
+                LRESULT(GetStockObject(WHITE_BRUSH).0 as isize)
+            }
+        }
+        WM_DESTROY => {
+            unsafe { let _ = KillTimer(hwnd, ID_CLIENTS_TIMER); }
+            API_WINDOW.store(0, std::sync::atomic::Ordering::Relaxed);
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
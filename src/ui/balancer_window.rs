@@ -0,0 +1,295 @@
+#[cfg(windows)]
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE},
+        Graphics::Gdi::{SetBkMode, TRANSPARENT, HDC, GetStockObject, WHITE_BRUSH},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+#[cfg(windows)]
+use super::controls::*;
+
+use std::sync::Mutex;
+
+use crate::config::{BalancedGroup, Config};
+
+// Control IDs
+const ID_GROUP_LIST: i32 = 1801;
+const ID_GROUP_NEW_BUTTON: i32 = 1802;
+const ID_GROUP_EDIT_BUTTON: i32 = 1803;
+const ID_GROUP_DELETE_BUTTON: i32 = 1804;
+const ID_GROUP_MEMBERS_LIST: i32 = 1805;
+const ID_GROUP_SAVE_MEMBERS_BUTTON: i32 = 1806;
+const ID_GROUP_CLOSE_BUTTON: i32 = 1807;
+
+// List box notification: selection changed
+const LBN_SELCHANGE: usize = 1;
+
+// Layout constants (match settings window)
+const MARGIN: i32 = 15;
+const FONT_SIZE: i32 = 32;
+const CONTROL_HEIGHT: i32 = 45;
+const WINDOW_WIDTH: i32 = 900;
+
+// Only one balanced groups window at a time (HWND stored as raw value)
+static BALANCER_WINDOW: std::sync::atomic::AtomicIsize = std::sync::atomic::AtomicIsize::new(0);
+
+// Server keys in member list box order
+static LISTED_SERVERS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Open the balanced groups window, or focus it if already open
+#[cfg(windows)]
+pub unsafe fn show_balancer_window(owner: HWND) {
+    unsafe {
+        let existing = HWND(BALANCER_WINDOW.load(std::sync::atomic::Ordering::Relaxed) as *mut _);
+        if !existing.is_invalid() && IsWindow(existing).as_bool() {
+            let _ = ShowWindow(existing, SW_RESTORE);
+            let _ = SetForegroundWindow(existing);
+            return;
+        }
+
+        let hinstance: HINSTANCE = GetModuleHandleW(None).unwrap().into();
+        register_window_class(w!("BalancerWindowClass"), Some(balancer_window_proc), hinstance);
+
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("BalancerWindowClass"),
+            w!("Balanced Groups"),
+            WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_VISIBLE,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            WINDOW_WIDTH,
+            860,
+            owner,
+            None,
+            hinstance,
+            None,
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                eprintln!("Failed to create balanced groups window: {}", e);
+                return;
+            }
+        };
+
+        BALANCER_WINDOW.store(hwnd.0 as isize, std::sync::atomic::Ordering::Relaxed);
+        create_balancer_controls(hwnd, hinstance);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn create_balancer_controls(hwnd: HWND, hinstance: HINSTANCE) {
+    let hfont = unsafe { create_font(FONT_SIZE) };
+    let full_width = WINDOW_WIDTH - 2 * MARGIN - 20;
+    let third_width = (full_width - 20) / 3;
+
+    unsafe {
+        let mut y = MARGIN;
+        create_label(hwnd, hinstance, "Groups (each shares one local port between its servers):",
+            (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_listbox(hwnd, hinstance, ID_GROUP_LIST, (MARGIN, y, full_width, 180), hfont);
+
+        y += 180 + MARGIN;
+        create_button(hwnd, hinstance, "New...", ID_GROUP_NEW_BUTTON,
+            (MARGIN, y, third_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Edit...", ID_GROUP_EDIT_BUTTON,
+            (MARGIN + third_width + 10, y, third_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Delete", ID_GROUP_DELETE_BUTTON,
+            (MARGIN + 2 * (third_width + 10), y, third_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + MARGIN;
+        create_label(hwnd, hinstance, "Servers in the selected group (Ctrl/Shift+click to select several):",
+            (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_multiselect_listbox(hwnd, hinstance, ID_GROUP_MEMBERS_LIST, (MARGIN, y, full_width, 280), hfont);
+
+        y += 280 + MARGIN;
+        create_button(hwnd, hinstance, "Save Servers", ID_GROUP_SAVE_MEMBERS_BUTTON,
+            (MARGIN, y, third_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Close", ID_GROUP_CLOSE_BUTTON,
+            (full_width + MARGIN - 120, y, 110, CONTROL_HEIGHT), hfont);
+
+        fill_server_list(hwnd);
+        refresh_group_list(hwnd);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn fill_server_list(hwnd: HWND) {
+    let mut listed = Vec::new();
+    let mut items = Vec::new();
+    if let Ok(global_servers) = crate::vpn::VPN_SERVERS.lock() {
+        if let Some(servers) = global_servers.as_ref() {
+            for server in servers {
+                items.push(format!("{}  —  {}", server.display_name(), server.get_server_key()));
+                listed.push(server.get_server_key());
+            }
+        }
+    }
+    unsafe { set_listbox_items(hwnd, ID_GROUP_MEMBERS_LIST, &items) };
+    if let Ok(mut servers) = LISTED_SERVERS.lock() {
+        *servers = listed;
+    }
+}
+
+#[cfg(windows)]
+unsafe fn refresh_group_list(hwnd: HWND) {
+    let config = Config::load().unwrap_or_default();
+    let items: Vec<String> = config.balanced_groups.iter()
+        .map(|group| {
+            let running = crate::xray_manager::get_server_info(&crate::balancer::group_key(&group.name)).is_some();
+            let mark = if running { "✓" } else if group.enabled { "✗" } else { "   " };
+            format!("{} {}  —  {}:{}, {} servers, {}",
+                mark, group.name, group.proxy_type, group.local_port, group.members.len(), group.strategy)
+        })
+        .collect();
+    unsafe { set_listbox_items(hwnd, ID_GROUP_LIST, &items) };
+}
+
+#[cfg(windows)]
+unsafe fn selected_group(hwnd: HWND) -> Option<BalancedGroup> {
+    let index = unsafe { get_listbox_selection(hwnd, ID_GROUP_LIST) }?;
+    Config::load().ok()?.balanced_groups.into_iter().nth(index)
+}
+
+// Select the servers of the chosen group in the member list
+#[cfg(windows)]
+unsafe fn show_members(hwnd: HWND) {
+    let members = unsafe { selected_group(hwnd) }.map(|g| g.members).unwrap_or_default();
+    let listed = LISTED_SERVERS.lock().map(|s| s.clone()).unwrap_or_default();
+    for (index, key) in listed.iter().enumerate() {
+        unsafe { set_listbox_selected(hwnd, ID_GROUP_MEMBERS_LIST, index, members.contains(key)) };
+    }
+}
+
+// Edit a group's name, port and strategy (or a new group's) in a form
+#[cfg(windows)]
+unsafe fn show_group_form(hwnd: HWND, group: Option<BalancedGroup>) {
+    use crate::ui::form_window::{show_form, FormField};
+
+    let group = group.unwrap_or_else(|| BalancedGroup {
+        name: String::new(),
+        members: Vec::new(),
+        local_port: 0,
+        proxy_type: "SOCKS".to_string(),
+        strategy: crate::balancer::STRATEGIES[0].to_string(),
+        enabled: true,
+    });
+    let previous_name = group.name.clone();
+    let port = if group.local_port == 0 { String::new() } else { group.local_port.to_string() };
+    let fields = vec![
+        FormField::text("Group name:", &group.name),
+        FormField::check("Run this group", group.enabled),
+        FormField::text("Local port:", &port),
+        FormField::choice("Proxy type:", &["SOCKS", "HTTP"], &group.proxy_type),
+        FormField::choice("Spread traffic by:", crate::balancer::STRATEGIES, &group.strategy),
+    ];
+    let title = if previous_name.is_empty() { "New Balanced Group" } else { "Edit Balanced Group" };
+
+    unsafe {
+        show_form(hwnd, title, fields, Box::new(move |values| {
+            let local_port = values[2].trim().parse().ok().filter(|&p| p > 0)
+                .ok_or_else(|| "Local port must be between 1 and 65535".to_string())?;
+            crate::balancer::save_group(&previous_name, BalancedGroup {
+                name: values[0].trim().to_string(),
+                members: group.members.clone(),
+                local_port,
+                proxy_type: values[3].clone(),
+                strategy: values[4].clone(),
+                enabled: values[1] == "true",
+            })?;
+            refresh_group_list(hwnd);
+            Ok(())
+        }));
+    }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn balancer_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => {
+            let control_id = (wparam.0 & 0xFFFF) as i32;
+            let notification_code = (wparam.0 >> 16) & 0xFFFF;
+
+            // Selecting a group shows its servers
+            if control_id == ID_GROUP_LIST && notification_code == LBN_SELCHANGE {
+                unsafe { show_members(hwnd) };
+                return LRESULT(0);
+            }
+            if notification_code != 0 {
+                return LRESULT(0);
+            }
+
+            let result = match control_id {
+                ID_GROUP_NEW_BUTTON => unsafe {
+                    show_group_form(hwnd, None);
+                    Ok(())
+                },
+                ID_GROUP_EDIT_BUTTON => unsafe {
+                    match selected_group(hwnd) {
+                        Some(group) => {
+                            show_group_form(hwnd, Some(group));
+                            Ok(())
+                        }
+                        None => Err("Select a group first.".to_string()),
+                    }
+                },
+                ID_GROUP_DELETE_BUTTON => unsafe {
+                    selected_group(hwnd)
+                        .ok_or_else(|| "Select a group first.".to_string())
+                        .and_then(|group| crate::balancer::delete_group(&group.name))
+                },
+                ID_GROUP_SAVE_MEMBERS_BUTTON => unsafe {
+                    let selected = get_listbox_selections(hwnd, ID_GROUP_MEMBERS_LIST);
+                    let members: Vec<String> = LISTED_SERVERS.lock()
+                        .map(|servers| selected.iter().filter_map(|&i| servers.get(i).cloned()).collect())
+                        .unwrap_or_default();
+                    match selected_group(hwnd) {
+                        None => Err("Select a group first.".to_string()),
+                        Some(_) if members.len() < 2 => Err("Select at least two servers to balance between.".to_string()),
+                        Some(group) => crate::balancer::set_members(&group.name, members),
+                    }
+                },
+                ID_GROUP_CLOSE_BUTTON => unsafe {
+                    let _ = DestroyWindow(hwnd);
+                    return LRESULT(0);
+                },
+                _ => return LRESULT(0),
+            };
+
+            unsafe {
+                // Keep the selection so the member list still matches the group
+                let selection = get_listbox_selection(hwnd, ID_GROUP_LIST);
+                refresh_group_list(hwnd);
+                if let Some(index) = selection {
+                    set_listbox_current(hwnd, ID_GROUP_LIST, index);
+                }
+                if let Err(e) = result {
+                    message_box(hwnd, &e, "Balanced Groups", MB_OK | MB_ICONWARNING);
+                }
+            }
+            LRESULT(0)
+        }
+        WM_CTLCOLORSTATIC => {
+            unsafe {
+                let hdc = HDC(wparam.0 as *mut _);
+                SetBkMode(hdc, TRANSPARENT);
+                LRESULT(GetStockObject(WHITE_BRUSH).0 as isize)
+            }
+        }
+        WM_DESTROY => {
+            BALANCER_WINDOW.store(0, std::sync::atomic::Ordering::Relaxed);
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
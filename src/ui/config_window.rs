@@ -0,0 +1,153 @@
+#[cfg(windows)]
+use windows::{
+    core::{w, PCWSTR},
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE},
+        Graphics::Gdi::{SetBkMode, TRANSPARENT, HDC, GetStockObject, WHITE_BRUSH},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+#[cfg(windows)]
+use super::controls::*;
+
+// Control IDs
+const ID_CONFIG_SOURCE_LABEL: i32 = 2101;
+const ID_CONFIG_TEXT: i32 = 2102;
+const ID_CONFIG_COPY_BUTTON: i32 = 2103;
+const ID_CONFIG_CLOSE_BUTTON: i32 = 2104;
+
+// Layout constants (match the log window)
+const MARGIN: i32 = 15;
+const FONT_SIZE: i32 = 32;
+const CONTROL_HEIGHT: i32 = 45;
+const WINDOW_WIDTH: i32 = 1100;
+const TEXT_HEIGHT: i32 = 680;
+
+// Only one config window at a time (HWND stored as raw value)
+static CONFIG_WINDOW: std::sync::atomic::AtomicIsize = std::sync::atomic::AtomicIsize::new(0);
+
+/// Show the generated config of a server, read-only, reusing the window if one is open
+#[cfg(windows)]
+pub unsafe fn show_config_window(owner: HWND, server_key: &str) {
+    let title = format!("Config: {}\0", crate::vpn::get_server_name(server_key));
+    let title: Vec<u16> = title.encode_utf16().collect();
+    let (source, text) = match crate::xray_manager::preview_config(server_key) {
+        Ok((config_json, source)) => (source, config_json),
+        Err(e) => ("The config could not be generated".to_string(), e),
+    };
+    // The edit control wants CRLF line breaks
+    let text = text.replace("\r\n", "\n").replace('\n', "\r\n");
+
+    unsafe {
+        let existing = HWND(CONFIG_WINDOW.load(std::sync::atomic::Ordering::Relaxed) as *mut _);
+        let hwnd = if !existing.is_invalid() && IsWindow(existing).as_bool() {
+            let _ = SetWindowTextW(existing, PCWSTR::from_raw(title.as_ptr()));
+            let _ = ShowWindow(existing, SW_RESTORE);
+            let _ = SetForegroundWindow(existing);
+            existing
+        } else {
+            let hinstance: HINSTANCE = GetModuleHandleW(None).unwrap().into();
+            register_window_class(w!("ConfigWindowClass"), Some(config_window_proc), hinstance);
+            let hwnd = match CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("ConfigWindowClass"),
+                PCWSTR::from_raw(title.as_ptr()),
+                WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_VISIBLE,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                WINDOW_WIDTH,
+                TEXT_HEIGHT + 3 * CONTROL_HEIGHT + 5 * MARGIN + 40,
+                owner,
+                None,
+                hinstance,
+                None,
+            ) {
+                Ok(hwnd) => hwnd,
+                Err(e) => {
+                    eprintln!("Failed to create config window: {}", e);
+                    return;
+                }
+            };
+            CONFIG_WINDOW.store(hwnd.0 as isize, std::sync::atomic::Ordering::Relaxed);
+            create_config_controls(hwnd, hinstance);
+            hwnd
+        };
+        set_control_text(hwnd, ID_CONFIG_SOURCE_LABEL, &source);
+        set_control_text(hwnd, ID_CONFIG_TEXT, &text);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn create_config_controls(hwnd: HWND, hinstance: HINSTANCE) {
+    let hfont = unsafe { create_font(FONT_SIZE) };
+    let full_width = WINDOW_WIDTH - 2 * MARGIN - 20;
+
+    unsafe {
+        let mut y = MARGIN;
+        create_control(hwnd, hinstance, w!("STATIC"), "", WINDOW_EX_STYLE::default(),
+            WINDOW_STYLE(0), ID_CONFIG_SOURCE_LABEL, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT;
+        if let Some(text) = create_control(hwnd, hinstance, w!("EDIT"), "", WS_EX_CLIENTEDGE,
+            WS_BORDER | WS_TABSTOP | WS_VSCROLL | WS_HSCROLL
+                | WINDOW_STYLE((ES_MULTILINE | ES_READONLY | ES_AUTOVSCROLL | ES_AUTOHSCROLL) as u32),
+            ID_CONFIG_TEXT, (MARGIN, y, full_width, TEXT_HEIGHT), hfont) {
+            set_accessible_name(text, "Generated config", None);
+        }
+
+        y += TEXT_HEIGHT + MARGIN;
+        create_button(hwnd, hinstance, "Copy", ID_CONFIG_COPY_BUTTON,
+            (MARGIN, y, 160, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Close", ID_CONFIG_CLOSE_BUTTON,
+            (full_width + MARGIN - 120, y, 110, CONTROL_HEIGHT), hfont);
+    }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn config_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => {
+            let control_id = (wparam.0 & 0xFFFF) as i32;
+            let notification_code = (wparam.0 >> 16) & 0xFFFF;
+            if notification_code != 0 {
+                return LRESULT(0);
+            }
+            match control_id {
+                ID_CONFIG_COPY_BUTTON => unsafe {
+                    let text = get_control_text(hwnd, ID_CONFIG_TEXT);
+                    if let Err(e) = set_clipboard_text(hwnd, &text) {
+                        message_box(hwnd, &e, "Config", MB_OK | MB_ICONWARNING);
+                    }
+                },
+                ID_CONFIG_CLOSE_BUTTON => unsafe {
+                    let _ = DestroyWindow(hwnd);
+                },
+                _ => {}
+            }
+            LRESULT(0)
+        }
+        WM_CTLCOLORSTATIC => {
+            // The read-only config box asks too; leave it the system's edit colors
+            if unsafe { GetDlgItem(hwnd, ID_CONFIG_TEXT) }.is_ok_and(|edit| edit.0 == lparam.0 as *mut _) {
+                return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) };
+            }
+            unsafe {
+                let hdc = HDC(wparam.0 as *mut _);
+                SetBkMode(hdc, TRANSPARENT);
+                LRESULT(GetStockObject(WHITE_BRUSH).0 as isize)
+            }
+        }
+        WM_DESTROY => {
+            CONFIG_WINDOW.store(0, std::sync::atomic::Ordering::Relaxed);
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
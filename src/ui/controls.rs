@@ -0,0 +1,412 @@
+#[cfg(windows)]
+use windows::{
+    core::{PCWSTR, w},
+    Win32::{
+        Foundation::{HWND, LPARAM, WPARAM, HINSTANCE},
+        Graphics::Gdi::HFONT,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+// Shared helpers for the small tool windows (API access, logs, dialogs).
+// The main settings window builds its controls inline; these keep the
+// secondary windows from repeating the same CreateWindowExW boilerplate.
+
+/// Create the default UI font at the given height
+#[cfg(windows)]
+pub unsafe fn create_font(height: i32) -> HFONT {
+    use windows::Win32::Graphics::Gdi::{CreateFontW, DEFAULT_CHARSET, OUT_DEFAULT_PRECIS,
+        CLIP_DEFAULT_PRECIS, DEFAULT_QUALITY, DEFAULT_PITCH, FF_DONTCARE, FW_NORMAL};
+    unsafe {
+        CreateFontW(
+            height,
+            0, 0, 0,
+            FW_NORMAL.0 as i32,
+            0, 0, 0,
+            DEFAULT_CHARSET.0 as u32,
+            OUT_DEFAULT_PRECIS.0 as u32,
+            CLIP_DEFAULT_PRECIS.0 as u32,
+            DEFAULT_QUALITY.0 as u32,
+            (DEFAULT_PITCH.0 | FF_DONTCARE.0) as u32,
+            w!("Segoe UI"),
+        )
+    }
+}
+
+/// Create a child control of the given class and apply the font
+#[cfg(windows)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn create_control(
+    parent: HWND,
+    hinstance: HINSTANCE,
+    class: PCWSTR,
+    text: &str,
+    ex_style: WINDOW_EX_STYLE,
+    style: WINDOW_STYLE,
+    id: i32,
+    rect: (i32, i32, i32, i32),
+    hfont: HFONT,
+) -> Option<HWND> {
+    let text_wide: Vec<u16> = format!("{}\0", text).encode_utf16().collect();
+    let (x, y, width, height) = rect;
+    let hwnd = unsafe {
+        CreateWindowExW(
+            ex_style,
+            class,
+            PCWSTR::from_raw(text_wide.as_ptr()),
+            WS_CHILD | WS_VISIBLE | style,
+            x,
+            y,
+            width,
+            height,
+            parent,
+            if id != 0 { HMENU(id as _) } else { HMENU::default() },
+            hinstance,
+            None,
+        ).ok()
+    };
+    if let Some(control) = hwnd {
+        unsafe { SendMessageW(control, WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(1)); }
+    }
+    hwnd
+}
+
+/// Create a static text label
+#[cfg(windows)]
+pub unsafe fn create_label(parent: HWND, hinstance: HINSTANCE, text: &str, rect: (i32, i32, i32, i32), hfont: HFONT) -> Option<HWND> {
+    unsafe {
+        create_control(parent, hinstance, w!("STATIC"), text, WINDOW_EX_STYLE::default(), WINDOW_STYLE::default(), 0, rect, hfont)
+    }
+}
+
+/// Create a push button
+#[cfg(windows)]
+pub unsafe fn create_button(parent: HWND, hinstance: HINSTANCE, text: &str, id: i32, rect: (i32, i32, i32, i32), hfont: HFONT) -> Option<HWND> {
+    unsafe {
+        create_control(parent, hinstance, w!("BUTTON"), text, WINDOW_EX_STYLE::default(),
+            WS_TABSTOP | WINDOW_STYLE(BS_PUSHBUTTON as u32), id, rect, hfont)
+    }
+}
+
+/// Create an auto checkbox with the given initial state
+#[cfg(windows)]
+pub unsafe fn create_checkbox(parent: HWND, hinstance: HINSTANCE, text: &str, id: i32, checked: bool, rect: (i32, i32, i32, i32), hfont: HFONT) -> Option<HWND> {
+    let checkbox = unsafe {
+        create_control(parent, hinstance, w!("BUTTON"), text, WINDOW_EX_STYLE::default(),
+            WS_TABSTOP | WINDOW_STYLE(BS_AUTOCHECKBOX as u32), id, rect, hfont)
+    };
+    if let Some(cb) = checkbox {
+        unsafe { SendMessageW(cb, BM_SETCHECK, WPARAM(if checked { 1 } else { 0 }), LPARAM(0)); }
+    }
+    checkbox
+}
+
+/// Create a single-line edit control
+#[cfg(windows)]
+pub unsafe fn create_edit(parent: HWND, hinstance: HINSTANCE, text: &str, id: i32, rect: (i32, i32, i32, i32), hfont: HFONT) -> Option<HWND> {
+    unsafe {
+        create_control(parent, hinstance, w!("EDIT"), text, WS_EX_CLIENTEDGE,
+            WS_BORDER | WS_TABSTOP | WINDOW_STYLE(ES_AUTOHSCROLL as u32), id, rect, hfont)
+    }
+}
+
+/// Create a list box
+#[cfg(windows)]
+pub unsafe fn create_listbox(parent: HWND, hinstance: HINSTANCE, id: i32, rect: (i32, i32, i32, i32), hfont: HFONT) -> Option<HWND> {
+    unsafe {
+        create_control(parent, hinstance, w!("LISTBOX"), "", WS_EX_CLIENTEDGE,
+            WS_BORDER | WS_VSCROLL | WS_TABSTOP | WINDOW_STYLE(LBS_NOTIFY as u32 | LBS_NOINTEGRALHEIGHT as u32), id, rect, hfont)
+    }
+}
+
+/// Create a list box where several items can be selected (Ctrl/Shift+click)
+#[cfg(windows)]
+pub unsafe fn create_multiselect_listbox(parent: HWND, hinstance: HINSTANCE, id: i32, rect: (i32, i32, i32, i32), hfont: HFONT) -> Option<HWND> {
+    unsafe {
+        create_control(parent, hinstance, w!("LISTBOX"), "", WS_EX_CLIENTEDGE,
+            WS_BORDER | WS_VSCROLL | WS_TABSTOP | WINDOW_STYLE(LBS_NOTIFY as u32 | LBS_NOINTEGRALHEIGHT as u32 | LBS_EXTENDEDSEL as u32),
+            id, rect, hfont)
+    }
+}
+
+thread_local! {
+    // Dynamic annotation service; COM objects stay on the UI thread that created them
+    #[cfg(windows)]
+    static ACC_PROP_SERVICES: Option<windows::Win32::UI::Accessibility::IAccPropServices> = unsafe {
+        use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED};
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        CoCreateInstance(&windows::Win32::UI::Accessibility::CAccPropServices, None, CLSCTX_INPROC_SERVER).ok()
+    };
+}
+
+/// Name a control for screen readers, overriding what MSAA guesses from its
+/// text or the label before it, and optionally its role (ROLE_SYSTEM_*)
+#[cfg(windows)]
+pub unsafe fn set_accessible_name(control: HWND, name: &str, role: Option<u32>) {
+    use windows::Win32::UI::Accessibility::{PROPID_ACC_NAME, PROPID_ACC_ROLE};
+    let name_wide: Vec<u16> = format!("{}\0", name).encode_utf16().collect();
+    ACC_PROP_SERVICES.with(|services| {
+        let Some(services) = services else {
+            return;
+        };
+        unsafe {
+            let _ = services.SetHwndPropStr(control, OBJID_CLIENT.0 as u32, CHILDID_SELF,
+                PROPID_ACC_NAME, PCWSTR::from_raw(name_wide.as_ptr()));
+            if let Some(role) = role {
+                let _ = services.SetHwndProp(control, OBJID_CLIENT.0 as u32, CHILDID_SELF,
+                    PROPID_ACC_ROLE, &windows::core::VARIANT::from(role as i32));
+            }
+        }
+    });
+}
+
+/// Name a child control by ID (see `set_accessible_name`)
+#[cfg(windows)]
+pub unsafe fn name_control(parent: HWND, id: i32, name: &str) {
+    unsafe {
+        if let Ok(control) = GetDlgItem(parent, id) {
+            set_accessible_name(control, name, None);
+        }
+    }
+}
+
+/// Let Tab, arrow keys, Enter and Escape move between and press the controls of
+/// the window a keyboard message is for. True when the message was handled.
+#[cfg(windows)]
+pub unsafe fn handle_dialog_keys(msg: &MSG) -> bool {
+    if !(WM_KEYFIRST..=WM_KEYLAST).contains(&msg.message) {
+        return false;
+    }
+    unsafe {
+        let root = GetAncestor(msg.hwnd, GA_ROOT);
+        !root.is_invalid() && IsDialogMessageW(root, msg).as_bool()
+    }
+}
+
+/// Read the text of a child control by ID
+#[cfg(windows)]
+pub unsafe fn get_control_text(parent: HWND, id: i32) -> String {
+    unsafe {
+        if let Ok(control) = GetDlgItem(parent, id) {
+            let len = GetWindowTextLengthW(control);
+            if len > 0 {
+                let mut buffer = vec![0u16; len as usize + 1];
+                let copied = GetWindowTextW(control, &mut buffer);
+                return String::from_utf16_lossy(&buffer[..copied as usize]);
+            }
+        }
+    }
+    String::new()
+}
+
+/// Replace the text of a child control by ID
+#[cfg(windows)]
+pub unsafe fn set_control_text(parent: HWND, id: i32, text: &str) {
+    let text_wide: Vec<u16> = format!("{}\0", text).encode_utf16().collect();
+    unsafe {
+        if let Ok(control) = GetDlgItem(parent, id) {
+            SetWindowTextW(control, PCWSTR::from_raw(text_wide.as_ptr())).ok();
+        }
+    }
+}
+
+/// Read the checked state of a checkbox by ID
+#[cfg(windows)]
+pub unsafe fn is_checked(parent: HWND, id: i32) -> bool {
+    unsafe {
+        if let Ok(checkbox) = GetDlgItem(parent, id) {
+            SendMessageW(checkbox, BM_GETCHECK, WPARAM(0), LPARAM(0)).0 == 1
+        } else {
+            false
+        }
+    }
+}
+
+/// Replace all items of a list box
+#[cfg(windows)]
+pub unsafe fn set_listbox_items(parent: HWND, id: i32, items: &[String]) {
+    unsafe {
+        if let Ok(listbox) = GetDlgItem(parent, id) {
+            SendMessageW(listbox, LB_RESETCONTENT, WPARAM(0), LPARAM(0));
+            for item in items {
+                let item_wide: Vec<u16> = format!("{}\0", item).encode_utf16().collect();
+                SendMessageW(listbox, LB_ADDSTRING, WPARAM(0), LPARAM(item_wide.as_ptr() as isize));
+            }
+        }
+    }
+}
+
+/// Get the selected index of a list box, if any
+#[cfg(windows)]
+pub unsafe fn get_listbox_selection(parent: HWND, id: i32) -> Option<usize> {
+    unsafe {
+        let listbox = GetDlgItem(parent, id).ok()?;
+        let sel = SendMessageW(listbox, LB_GETCURSEL, WPARAM(0), LPARAM(0));
+        if sel.0 >= 0 { Some(sel.0 as usize) } else { None }
+    }
+}
+
+/// Get the selected indices of a multi-select list box
+#[cfg(windows)]
+pub unsafe fn get_listbox_selections(parent: HWND, id: i32) -> Vec<usize> {
+    unsafe {
+        let Ok(listbox) = GetDlgItem(parent, id) else {
+            return Vec::new();
+        };
+        let count = SendMessageW(listbox, LB_GETCOUNT, WPARAM(0), LPARAM(0)).0.max(0) as usize;
+        (0..count)
+            .filter(|&i| SendMessageW(listbox, LB_GETSEL, WPARAM(i), LPARAM(0)).0 > 0)
+            .collect()
+    }
+}
+
+/// Select or deselect an item of a multi-select list box
+#[cfg(windows)]
+pub unsafe fn set_listbox_selected(parent: HWND, id: i32, index: usize, selected: bool) {
+    unsafe {
+        if let Ok(listbox) = GetDlgItem(parent, id) {
+            SendMessageW(listbox, LB_SETSEL, WPARAM(selected as usize), LPARAM(index as isize));
+        }
+    }
+}
+
+/// Select an item of a single-select list box
+#[cfg(windows)]
+pub unsafe fn set_listbox_current(parent: HWND, id: i32, index: usize) {
+    unsafe {
+        if let Ok(listbox) = GetDlgItem(parent, id) {
+            SendMessageW(listbox, LB_SETCURSEL, WPARAM(index), LPARAM(0));
+        }
+    }
+}
+
+/// Show a message box with the given icon style
+#[cfg(windows)]
+pub unsafe fn message_box(hwnd: HWND, text: &str, title: &str, style: MESSAGEBOX_STYLE) -> MESSAGEBOX_RESULT {
+    let text_wide: Vec<u16> = format!("{}\0", text).encode_utf16().collect();
+    let title_wide: Vec<u16> = format!("{}\0", title).encode_utf16().collect();
+    unsafe {
+        MessageBoxW(
+            hwnd,
+            PCWSTR::from_raw(text_wide.as_ptr()),
+            PCWSTR::from_raw(title_wide.as_ptr()),
+            style,
+        )
+    }
+}
+
+/// Register a window class with a white background (no-op if already registered)
+#[cfg(windows)]
+pub unsafe fn register_window_class(class_name: PCWSTR, proc: WNDPROC, hinstance: HINSTANCE) {
+    use windows::Win32::Graphics::Gdi::{GetStockObject, HBRUSH, WHITE_BRUSH};
+    let wc = WNDCLASSW {
+        lpfnWndProc: proc,
+        hInstance: hinstance,
+        lpszClassName: class_name,
+        hbrBackground: unsafe { HBRUSH(GetStockObject(WHITE_BRUSH).0) },
+        hCursor: unsafe { LoadCursorW(None, IDC_ARROW).unwrap_or_default() },
+        style: CS_HREDRAW | CS_VREDRAW,
+        ..Default::default()
+    };
+    unsafe { RegisterClassW(&wc) };
+}
+
+/// Show a file open dialog and return the selected path
+#[cfg(windows)]
+pub unsafe fn browse_for_file(hwnd: HWND) -> Option<String> {
+    use windows::Win32::UI::Shell::Common::COMDLG_FILTERSPEC;
+    use windows::Win32::UI::Shell::{IFileOpenDialog, FileOpenDialog, SIGDN_FILESYSPATH};
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_APARTMENTTHREADED};
+
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+
+        let dialog = CoCreateInstance::<_, IFileOpenDialog>(&FileOpenDialog, None, CLSCTX_ALL).ok()?;
+        let filter_spec = [
+            COMDLG_FILTERSPEC {
+                pszName: w!("All Files"),
+                pszSpec: w!("*.*"),
+            },
+        ];
+        let _ = dialog.SetFileTypes(&filter_spec);
+
+        dialog.Show(hwnd).ok()?;
+        let result = dialog.GetResult().ok()?;
+        let path = result.GetDisplayName(SIGDN_FILESYSPATH).ok()?;
+        path.to_string().ok()
+    }
+}
+
+/// Show a file save dialog with (name, spec) filters and return the chosen path
+#[cfg(windows)]
+pub unsafe fn browse_for_save_file(hwnd: HWND, default_name: &str, filters: &[(&str, &str)]) -> Option<String> {
+    use windows::Win32::UI::Shell::Common::COMDLG_FILTERSPEC;
+    use windows::Win32::UI::Shell::{IFileSaveDialog, FileSaveDialog, SIGDN_FILESYSPATH};
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_APARTMENTTHREADED};
+
+    // Keep the UTF-16 buffers alive while the dialog uses them
+    let wide: Vec<(Vec<u16>, Vec<u16>)> = filters.iter()
+        .map(|(name, spec)| (
+            format!("{}\0", name).encode_utf16().collect(),
+            format!("{}\0", spec).encode_utf16().collect(),
+        ))
+        .collect();
+    let filter_spec: Vec<COMDLG_FILTERSPEC> = wide.iter()
+        .map(|(name, spec)| COMDLG_FILTERSPEC {
+            pszName: PCWSTR::from_raw(name.as_ptr()),
+            pszSpec: PCWSTR::from_raw(spec.as_ptr()),
+        })
+        .collect();
+    let default_name_wide: Vec<u16> = format!("{}\0", default_name).encode_utf16().collect();
+
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+
+        let dialog = CoCreateInstance::<_, IFileSaveDialog>(&FileSaveDialog, None, CLSCTX_ALL).ok()?;
+        let _ = dialog.SetFileTypes(&filter_spec);
+        let _ = dialog.SetFileName(PCWSTR::from_raw(default_name_wide.as_ptr()));
+        // Appends an extension when the user doesn't type one (follows the selected type)
+        if let Some(ext) = default_name.rsplit_once('.').map(|(_, ext)| ext) {
+            let ext_wide: Vec<u16> = format!("{}\0", ext).encode_utf16().collect();
+            let _ = dialog.SetDefaultExtension(PCWSTR::from_raw(ext_wide.as_ptr()));
+        }
+
+        dialog.Show(hwnd).ok()?;
+        let result = dialog.GetResult().ok()?;
+        let path = result.GetDisplayName(SIGDN_FILESYSPATH).ok()?;
+        path.to_string().ok()
+    }
+}
+
+/// Put text on the clipboard as Unicode text
+#[cfg(windows)]
+pub unsafe fn set_clipboard_text(hwnd: HWND, text: &str) -> Result<(), String> {
+    use windows::Win32::Foundation::{GlobalFree, HANDLE};
+    use windows::Win32::System::DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData};
+    use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+    const CF_UNICODETEXT: u32 = 13;
+
+    let wide: Vec<u16> = format!("{}\0", text).encode_utf16().collect();
+    unsafe {
+        OpenClipboard(hwnd).map_err(|e| format!("Failed to open the clipboard: {}", e))?;
+        let result = (|| {
+            EmptyClipboard()?;
+            let memory = GlobalAlloc(GMEM_MOVEABLE, wide.len() * 2)?;
+            let target = GlobalLock(memory) as *mut u16;
+            if target.is_null() {
+                let _ = GlobalFree(memory);
+                return Err(windows::core::Error::from_win32());
+            }
+            std::ptr::copy_nonoverlapping(wide.as_ptr(), target, wide.len());
+            let _ = GlobalUnlock(memory);
+            // The clipboard owns the memory once this succeeds
+            if let Err(e) = SetClipboardData(CF_UNICODETEXT, HANDLE(memory.0)) {
+                let _ = GlobalFree(memory);
+                return Err(e);
+            }
+            Ok(())
+        })();
+        let _ = CloseClipboard();
+        result.map_err(|e| format!("Failed to copy to the clipboard: {}", e))
+    }
+}
@@ -0,0 +1,250 @@
+#[cfg(windows)]
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE},
+        Graphics::Gdi::{SetBkMode, TRANSPARENT, HDC, GetStockObject, WHITE_BRUSH},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+#[cfg(windows)]
+use super::controls::*;
+
+// Generic settings form: a column of labelled fields with OK/Cancel.
+// Used for small groups of options that don't deserve a dedicated window.
+
+/// Kind and initial value of a form field
+pub enum FieldKind {
+    Text(String),
+    Path(String), // Text field with a Browse... button
+    Check(bool),
+    Choice(Vec<String>, usize), // Options and selected index
+}
+
+pub struct FormField {
+    pub label: String,
+    pub kind: FieldKind,
+}
+
+impl FormField {
+    pub fn text(label: &str, value: &str) -> Self {
+        FormField { label: label.to_string(), kind: FieldKind::Text(value.to_string()) }
+    }
+
+    pub fn path(label: &str, value: &str) -> Self {
+        FormField { label: label.to_string(), kind: FieldKind::Path(value.to_string()) }
+    }
+
+    pub fn check(label: &str, value: bool) -> Self {
+        FormField { label: label.to_string(), kind: FieldKind::Check(value) }
+    }
+
+    pub fn choice(label: &str, options: &[&str], selected: &str) -> Self {
+        let options: Vec<String> = options.iter().map(|o| o.to_string()).collect();
+        let index = options.iter().position(|o| o == selected).unwrap_or(0);
+        FormField { label: label.to_string(), kind: FieldKind::Choice(options, index) }
+    }
+}
+
+/// Values read back from the form, one per field, in order.
+/// Checkboxes read as "true"/"false", choices as the selected option text.
+pub type FormValues = Vec<String>;
+
+/// Called on OK; returning Err keeps the form open and shows the message
+pub type FormSubmit = Box<dyn Fn(&FormValues) -> Result<(), String>>;
+
+struct FormState {
+    fields: Vec<FormField>,
+    on_submit: FormSubmit,
+}
+
+// Control ID layout: field controls at base + index, browse buttons at browse base + index
+const ID_FIELD_BASE: i32 = 100;
+const ID_BROWSE_BASE: i32 = 300;
+const ID_FORM_OK: i32 = 1;
+const ID_FORM_CANCEL: i32 = 2;
+
+const MARGIN: i32 = 15;
+const FONT_SIZE: i32 = 32;
+const CONTROL_HEIGHT: i32 = 45;
+const FORM_WIDTH: i32 = 820;
+
+/// Show a form window with the given fields
+#[cfg(windows)]
+pub unsafe fn show_form(owner: HWND, title: &str, fields: Vec<FormField>, on_submit: FormSubmit) {
+    unsafe {
+        let hinstance: HINSTANCE = GetModuleHandleW(None).unwrap().into();
+        register_window_class(w!("FormWindowClass"), Some(form_window_proc), hinstance);
+
+        // Two rows per text field (label + edit), one per checkbox
+        let content_height: i32 = fields.iter()
+            .map(|f| match f.kind {
+                FieldKind::Check(_) => CONTROL_HEIGHT + MARGIN,
+                _ => 2 * CONTROL_HEIGHT + MARGIN,
+            })
+            .sum();
+        let height = MARGIN + content_height + CONTROL_HEIGHT + 3 * MARGIN + 60;
+
+        let title_wide: Vec<u16> = format!("{}\0", title).encode_utf16().collect();
+        let hwnd = match CreateWindowExW(
+            WS_EX_DLGMODALFRAME,
+            w!("FormWindowClass"),
+            windows::core::PCWSTR::from_raw(title_wide.as_ptr()),
+            WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_VISIBLE,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            FORM_WIDTH,
+            height,
+            owner,
+            None,
+            hinstance,
+            None,
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                eprintln!("Failed to create form window: {}", e);
+                return;
+            }
+        };
+
+        create_form_controls(hwnd, hinstance, &fields);
+
+        let state = Box::new(FormState { fields, on_submit });
+        SetWindowLongPtrW(hwnd, GWLP_USERDATA, Box::into_raw(state) as isize);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn create_form_controls(hwnd: HWND, hinstance: HINSTANCE, fields: &[FormField]) {
+    let hfont = unsafe { create_font(FONT_SIZE) };
+    let full_width = FORM_WIDTH - 2 * MARGIN - 20;
+    let mut y = MARGIN;
+
+    unsafe {
+        for (idx, field) in fields.iter().enumerate() {
+            let id = ID_FIELD_BASE + idx as i32;
+            match &field.kind {
+                FieldKind::Check(checked) => {
+                    create_checkbox(hwnd, hinstance, &field.label, id, *checked,
+                        (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+                    y += CONTROL_HEIGHT + MARGIN;
+                }
+                FieldKind::Text(value) => {
+                    create_label(hwnd, hinstance, &field.label, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+                    y += CONTROL_HEIGHT;
+                    create_edit(hwnd, hinstance, value, id, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+                    y += CONTROL_HEIGHT + MARGIN;
+                }
+                FieldKind::Path(value) => {
+                    create_label(hwnd, hinstance, &field.label, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+                    y += CONTROL_HEIGHT;
+                    create_edit(hwnd, hinstance, value, id, (MARGIN, y, full_width - 130, CONTROL_HEIGHT), hfont);
+                    create_button(hwnd, hinstance, "Browse...", ID_BROWSE_BASE + idx as i32,
+                        (MARGIN + full_width - 120, y, 120, CONTROL_HEIGHT), hfont);
+                    y += CONTROL_HEIGHT + MARGIN;
+                }
+                FieldKind::Choice(options, selected) => {
+                    create_label(hwnd, hinstance, &field.label, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+                    y += CONTROL_HEIGHT;
+                    if let Some(combo) = create_control(hwnd, hinstance, w!("COMBOBOX"), "", WINDOW_EX_STYLE::default(),
+                        WS_TABSTOP | WINDOW_STYLE(CBS_DROPDOWNLIST as u32 | WS_VSCROLL.0), id,
+                        (MARGIN, y, full_width / 2, 300), hfont) {
+                        for option in options {
+                            let option_wide: Vec<u16> = format!("{}\0", option).encode_utf16().collect();
+                            SendMessageW(combo, CB_ADDSTRING, WPARAM(0), LPARAM(option_wide.as_ptr() as isize));
+                        }
+                        SendMessageW(combo, CB_SETCURSEL, WPARAM(*selected), LPARAM(0));
+                    }
+                    y += CONTROL_HEIGHT + MARGIN;
+                }
+            }
+        }
+
+        y += MARGIN;
+        create_button(hwnd, hinstance, "OK", ID_FORM_OK, (full_width + MARGIN - 240, y, 110, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Cancel", ID_FORM_CANCEL, (full_width + MARGIN - 120, y, 110, CONTROL_HEIGHT), hfont);
+    }
+}
+
+// Read current values of all fields
+#[cfg(windows)]
+unsafe fn read_values(hwnd: HWND, fields: &[FormField]) -> FormValues {
+    let mut values = Vec::new();
+    unsafe {
+        for (idx, field) in fields.iter().enumerate() {
+            let id = ID_FIELD_BASE + idx as i32;
+            let value = match &field.kind {
+                FieldKind::Check(_) => is_checked(hwnd, id).to_string(),
+                FieldKind::Text(_) | FieldKind::Path(_) => get_control_text(hwnd, id).trim().to_string(),
+                FieldKind::Choice(options, _) => {
+                    let sel = GetDlgItem(hwnd, id)
+                        .map(|combo| SendMessageW(combo, CB_GETCURSEL, WPARAM(0), LPARAM(0)).0)
+                        .unwrap_or(-1);
+                    options.get(sel.max(0) as usize).cloned().unwrap_or_default()
+                }
+            };
+            values.push(value);
+        }
+    }
+    values
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn form_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => {
+            let control_id = (wparam.0 & 0xFFFF) as i32;
+            let notification_code = (wparam.0 >> 16) & 0xFFFF;
+            if notification_code != 0 {
+                return LRESULT(0);
+            }
+
+            let state_ptr = unsafe { GetWindowLongPtrW(hwnd, GWLP_USERDATA) } as *const FormState;
+            if state_ptr.is_null() {
+                return LRESULT(0);
+            }
+            let state = unsafe { &*state_ptr };
+
+            if control_id == ID_FORM_OK {
+                let values = unsafe { read_values(hwnd, &state.fields) };
+                match (state.on_submit)(&values) {
+                    Ok(_) => unsafe { let _ = DestroyWindow(hwnd); },
+                    Err(e) => unsafe { message_box(hwnd, &e, "Error", MB_OK | MB_ICONERROR); },
+                }
+            } else if control_id == ID_FORM_CANCEL {
+                unsafe { let _ = DestroyWindow(hwnd); }
+            } else if control_id >= ID_BROWSE_BASE && control_id < ID_BROWSE_BASE + state.fields.len() as i32 {
+                let field_id = ID_FIELD_BASE + (control_id - ID_BROWSE_BASE);
+                if let Some(path) = unsafe { browse_for_file(hwnd) } {
+                    unsafe { set_control_text(hwnd, field_id, &path) };
+                }
+            }
+            LRESULT(0)
+        }
+        WM_CTLCOLORSTATIC => {
+            unsafe {
+                let hdc = HDC(wparam.0 as *mut _);
+                SetBkMode(hdc, TRANSPARENT);
+                LRESULT(GetStockObject(WHITE_BRUSH).0 as isize)
+            }
+        }
+        WM_DESTROY => {
+            // Free the form state
+            unsafe {
+                let state_ptr = SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0) as *mut FormState;
+                if !state_ptr.is_null() {
+                    drop(Box::from_raw(state_ptr));
+                }
+            }
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
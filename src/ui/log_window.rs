@@ -0,0 +1,253 @@
+#[cfg(windows)]
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE},
+        Graphics::Gdi::{SetBkMode, TRANSPARENT, HDC, GetStockObject, WHITE_BRUSH},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::Controls::{
+            EM_SCROLLCARET, EM_SETSEL, NMHDR, TCIF_TEXT, TCITEMW, TCM_DELETEALLITEMS, TCM_GETCURSEL,
+            TCM_INSERTITEMW, TCM_SETCURSEL, TCN_SELCHANGE, WC_TABCONTROLW,
+        },
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+#[cfg(windows)]
+use super::controls::*;
+
+// Control IDs
+const ID_LOGS_TABS: i32 = 2001;
+const ID_LOGS_TEXT: i32 = 2002;
+const ID_LOGS_COPY_BUTTON: i32 = 2003;
+const ID_LOGS_CLOSE_BUTTON: i32 = 2004;
+const ID_LOGS_TIMER: usize = 2005;
+
+// Layout constants (match settings window)
+const MARGIN: i32 = 15;
+const FONT_SIZE: i32 = 32;
+const CONTROL_HEIGHT: i32 = 45;
+const WINDOW_WIDTH: i32 = 1100;
+const TEXT_HEIGHT: i32 = 620;
+
+// Only one log window at a time (HWND stored as raw value)
+static LOGS_WINDOW: std::sync::atomic::AtomicIsize = std::sync::atomic::AtomicIsize::new(0);
+
+// (server key, label) of the tabs in tab order, and the xray_log revision last shown
+static TABS: std::sync::Mutex<Vec<(String, String)>> = std::sync::Mutex::new(Vec::new());
+static SHOWN_REVISION: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(u64::MAX);
+
+/// Open the log viewer, or focus it if already open
+#[cfg(windows)]
+pub unsafe fn show_log_window(owner: HWND) {
+    unsafe {
+        let existing = HWND(LOGS_WINDOW.load(std::sync::atomic::Ordering::Relaxed) as *mut _);
+        if !existing.is_invalid() && IsWindow(existing).as_bool() {
+            let _ = ShowWindow(existing, SW_RESTORE);
+            let _ = SetForegroundWindow(existing);
+            return;
+        }
+
+        let hinstance: HINSTANCE = GetModuleHandleW(None).unwrap().into();
+        register_window_class(w!("LogWindowClass"), Some(log_window_proc), hinstance);
+
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("LogWindowClass"),
+            w!("xray Logs"),
+            WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_VISIBLE,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            WINDOW_WIDTH,
+            TEXT_HEIGHT + 4 * CONTROL_HEIGHT + 6 * MARGIN + 40,
+            owner,
+            None,
+            hinstance,
+            None,
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                eprintln!("Failed to create log window: {}", e);
+                return;
+            }
+        };
+
+        LOGS_WINDOW.store(hwnd.0 as isize, std::sync::atomic::Ordering::Relaxed);
+        create_log_controls(hwnd, hinstance);
+        SetTimer(hwnd, ID_LOGS_TIMER, 1000, None);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn create_log_controls(hwnd: HWND, hinstance: HINSTANCE) {
+    let hfont = unsafe { create_font(FONT_SIZE) };
+    let full_width = WINDOW_WIDTH - 2 * MARGIN - 20;
+
+    unsafe {
+        let mut y = MARGIN;
+        let note = match crate::logfiles::logs_dir().filter(|_| crate::config::Config::load().is_ok_and(|c| c.logs.enabled)) {
+            Some(dir) => format!("Last {} lines per server; everything is also written to {}", crate::xray_log::MAX_LINES, dir.display()),
+            None => format!("Last {} lines per server. Tools → Log Files keeps everything on disk.", crate::xray_log::MAX_LINES),
+        };
+        create_label(hwnd, hinstance, &note, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT;
+        create_control(hwnd, hinstance, WC_TABCONTROLW, "", WINDOW_EX_STYLE::default(),
+            WS_TABSTOP | WS_CLIPSIBLINGS, ID_LOGS_TABS, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT;
+        if let Some(text) = create_control(hwnd, hinstance, w!("EDIT"), "", WS_EX_CLIENTEDGE,
+            WS_BORDER | WS_TABSTOP | WS_VSCROLL | WS_HSCROLL
+                | WINDOW_STYLE((ES_MULTILINE | ES_READONLY | ES_AUTOVSCROLL | ES_AUTOHSCROLL) as u32),
+            ID_LOGS_TEXT, (MARGIN, y, full_width, TEXT_HEIGHT), hfont) {
+            set_accessible_name(text, "xray output", None);
+        }
+
+        y += TEXT_HEIGHT + MARGIN;
+        create_button(hwnd, hinstance, "Copy", ID_LOGS_COPY_BUTTON,
+            (MARGIN, y, 160, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Close", ID_LOGS_CLOSE_BUTTON,
+            (full_width + MARGIN - 120, y, 110, CONTROL_HEIGHT), hfont);
+
+        refresh_logs(hwnd, true);
+    }
+}
+
+// "Name" for running servers, "Name (stopped)" for output left by a stopped or crashed one
+fn tab_label(server_key: &str) -> String {
+    #[cfg(feature = "tun")]
+    if server_key == crate::tun::LOG_KEY {
+        return "TUN mode (sing-box)".to_string();
+    }
+    match crate::xray_manager::get_server_info(server_key) {
+        Some(info) => info.name,
+        None => format!("{} (stopped)", crate::vpn::get_server_name(server_key)),
+    }
+}
+
+// Server key of the selected tab
+#[cfg(windows)]
+unsafe fn selected_key(hwnd: HWND) -> Option<String> {
+    let tabs = unsafe { GetDlgItem(hwnd, ID_LOGS_TABS) }.ok()?;
+    let index = unsafe { SendMessageW(tabs, TCM_GETCURSEL, WPARAM(0), LPARAM(0)) }.0;
+    let tabs = TABS.lock().ok()?;
+    usize::try_from(index).ok().and_then(|i| tabs.get(i).map(|(key, _)| key.clone()))
+}
+
+// Rebuild the tabs when servers came, went or stopped, and show the selected server's
+// output when it changed (or always, with `force`), scrolled to the newest line
+#[cfg(windows)]
+unsafe fn refresh_logs(hwnd: HWND, force: bool) {
+    unsafe {
+        let Ok(tabs) = GetDlgItem(hwnd, ID_LOGS_TABS) else {
+            return;
+        };
+        let labelled: Vec<(String, String)> = crate::xray_log::server_keys().into_iter()
+            .map(|key| {
+                let label = tab_label(&key);
+                (key, label)
+            })
+            .collect();
+        let changed = TABS.lock().map(|current| *current != labelled).unwrap_or(false);
+        if changed {
+            let previous = selected_key(hwnd);
+            let _ = SendMessageW(tabs, TCM_DELETEALLITEMS, WPARAM(0), LPARAM(0));
+            for (index, (_, label)) in labelled.iter().enumerate() {
+                let mut label: Vec<u16> = format!("{}\0", label).encode_utf16().collect();
+                let item = TCITEMW {
+                    mask: TCIF_TEXT,
+                    pszText: windows::core::PWSTR(label.as_mut_ptr()),
+                    ..Default::default()
+                };
+                let _ = SendMessageW(tabs, TCM_INSERTITEMW, WPARAM(index), LPARAM(&item as *const _ as isize));
+            }
+            let selected = previous.and_then(|key| labelled.iter().position(|(k, _)| *k == key)).unwrap_or(0);
+            let _ = SendMessageW(tabs, TCM_SETCURSEL, WPARAM(selected), LPARAM(0));
+            if let Ok(mut current) = TABS.lock() {
+                *current = labelled;
+            }
+        }
+
+        let revision = crate::xray_log::revision();
+        if !force && !changed && SHOWN_REVISION.load(std::sync::atomic::Ordering::Relaxed) == revision {
+            return;
+        }
+        SHOWN_REVISION.store(revision, std::sync::atomic::Ordering::Relaxed);
+
+        let text = match selected_key(hwnd) {
+            Some(key) => crate::xray_log::get_lines(&key).join("\r\n"),
+            None => "No xray output yet. Start a server to see its log here.".to_string(),
+        };
+        set_control_text(hwnd, ID_LOGS_TEXT, &text);
+        if let Ok(edit) = GetDlgItem(hwnd, ID_LOGS_TEXT) {
+            let end = text.encode_utf16().count();
+            let _ = SendMessageW(edit, EM_SETSEL, WPARAM(end), LPARAM(end as isize));
+            let _ = SendMessageW(edit, EM_SCROLLCARET, WPARAM(0), LPARAM(0));
+        }
+    }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn log_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => {
+            let control_id = (wparam.0 & 0xFFFF) as i32;
+            let notification_code = (wparam.0 >> 16) & 0xFFFF;
+            if notification_code != 0 {
+                return LRESULT(0);
+            }
+            match control_id {
+                ID_LOGS_COPY_BUTTON => unsafe {
+                    let text = get_control_text(hwnd, ID_LOGS_TEXT);
+                    if let Err(e) = set_clipboard_text(hwnd, &text) {
+                        message_box(hwnd, &e, "xray Logs", MB_OK | MB_ICONWARNING);
+                    }
+                },
+                ID_LOGS_CLOSE_BUTTON => unsafe {
+                    let _ = DestroyWindow(hwnd);
+                },
+                _ => {}
+            }
+            LRESULT(0)
+        }
+        WM_NOTIFY => {
+            let header = unsafe { &*(lparam.0 as *const NMHDR) };
+            if header.idFrom == ID_LOGS_TABS as usize && header.code == TCN_SELCHANGE {
+                unsafe { refresh_logs(hwnd, true) };
+            }
+            LRESULT(0)
+        }
+        WM_TIMER => {
+            if wparam.0 == ID_LOGS_TIMER {
+                unsafe { refresh_logs(hwnd, false) };
+            }
+            LRESULT(0)
+        }
+        WM_CTLCOLORSTATIC => {
+            // The read-only log box asks too; leave it the system's edit colors
+            if unsafe { GetDlgItem(hwnd, ID_LOGS_TEXT) }.is_ok_and(|edit| edit.0 == lparam.0 as *mut _) {
+                return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) };
+            }
+            unsafe {
+                let hdc = HDC(wparam.0 as *mut _);
+                SetBkMode(hdc, TRANSPARENT);
+                LRESULT(GetStockObject(WHITE_BRUSH).0 as isize)
+            }
+        }
+        WM_DESTROY => {
+            unsafe { let _ = KillTimer(hwnd, ID_LOGS_TIMER); }
+            if let Ok(mut tabs) = TABS.lock() {
+                tabs.clear();
+            }
+            SHOWN_REVISION.store(u64::MAX, std::sync::atomic::Ordering::Relaxed);
+            LOGS_WINDOW.store(0, std::sync::atomic::Ordering::Relaxed);
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
@@ -0,0 +1,276 @@
+#[cfg(windows)]
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE},
+        Graphics::Gdi::{SetBkMode, TRANSPARENT, HDC, GetStockObject, WHITE_BRUSH},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+#[cfg(windows)]
+use super::controls::*;
+
+use crate::config::Config;
+
+// Control IDs
+const ID_MANUAL_LIST: i32 = 1601;
+const ID_MANUAL_URI_EDIT: i32 = 1602;
+const ID_MANUAL_ADD_BUTTON: i32 = 1603;
+const ID_MANUAL_REMOVE_BUTTON: i32 = 1604;
+const ID_MANUAL_CLOSE_BUTTON: i32 = 1605;
+const ID_MANUAL_SCAN_BUTTON: i32 = 1606;
+
+// Layout constants (match settings window)
+const MARGIN: i32 = 15;
+const FONT_SIZE: i32 = 32;
+const CONTROL_HEIGHT: i32 = 45;
+const WINDOW_WIDTH: i32 = 900;
+
+// Only one manual servers window at a time (HWND stored as raw value)
+static MANUAL_SERVERS_WINDOW: std::sync::atomic::AtomicIsize = std::sync::atomic::AtomicIsize::new(0);
+
+/// Open the manual servers window, or focus it if already open.
+/// The owner (settings window) is told to reload after every change.
+#[cfg(windows)]
+pub unsafe fn show_manual_servers_window(owner: HWND) {
+    unsafe {
+        let existing = HWND(MANUAL_SERVERS_WINDOW.load(std::sync::atomic::Ordering::Relaxed) as *mut _);
+        if !existing.is_invalid() && IsWindow(existing).as_bool() {
+            let _ = ShowWindow(existing, SW_RESTORE);
+            let _ = SetForegroundWindow(existing);
+            return;
+        }
+
+        let hinstance: HINSTANCE = GetModuleHandleW(None).unwrap().into();
+        register_window_class(w!("ManualServersWindowClass"), Some(manual_servers_window_proc), hinstance);
+
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("ManualServersWindowClass"),
+            w!("Add Server"),
+            WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_VISIBLE,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            WINDOW_WIDTH,
+            620,
+            owner,
+            None,
+            hinstance,
+            None,
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                eprintln!("Failed to create manual servers window: {}", e);
+                return;
+            }
+        };
+
+        MANUAL_SERVERS_WINDOW.store(hwnd.0 as isize, std::sync::atomic::Ordering::Relaxed);
+        create_manual_server_controls(hwnd, hinstance);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn create_manual_server_controls(hwnd: HWND, hinstance: HINSTANCE) {
+    let config = Config::load().unwrap_or_default();
+    let hfont = unsafe { create_font(FONT_SIZE) };
+    let full_width = WINDOW_WIDTH - 2 * MARGIN - 20;
+    let third_width = (full_width - 20) / 3;
+
+    unsafe {
+        let mut y = MARGIN;
+        create_label(hwnd, hinstance, "Paste a vless://, vmess://, trojan://, ss://, ssr:// or hysteria2:// link:",
+            (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_edit(hwnd, hinstance, "", ID_MANUAL_URI_EDIT, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + 10;
+        create_button(hwnd, hinstance, "Add", ID_MANUAL_ADD_BUTTON,
+            (MARGIN, y, third_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Scan QR from Screen", ID_MANUAL_SCAN_BUTTON,
+            (MARGIN + third_width + 10, y, third_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Remove Selected", ID_MANUAL_REMOVE_BUTTON,
+            (MARGIN + 2 * (third_width + 10), y, third_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + MARGIN;
+        create_label(hwnd, hinstance, "Added servers (kept when subscriptions update):",
+            (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_listbox(hwnd, hinstance, ID_MANUAL_LIST, (MARGIN, y, full_width, 220), hfont);
+
+        y += 220 + MARGIN;
+        create_button(hwnd, hinstance, "Close", ID_MANUAL_CLOSE_BUTTON,
+            (full_width + MARGIN - 120, y, 110, CONTROL_HEIGHT), hfont);
+
+        refresh_manual_list(hwnd, &config);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn refresh_manual_list(hwnd: HWND, config: &Config) {
+    let items: Vec<String> = config.manual_servers.iter()
+        .map(|uri| match crate::vpn::parse_manual_uri(uri) {
+            Ok(server) => format!("{}  —  {}", server.name, server.get_server_key()),
+            Err(_) => format!("(unreadable)  —  {}", uri),
+        })
+        .collect();
+    unsafe { set_listbox_items(hwnd, ID_MANUAL_LIST, &items) };
+}
+
+// Server keys of the manual servers, for duplicate checks
+fn manual_server_keys(config: &Config) -> Vec<String> {
+    config.manual_servers.iter()
+        .filter_map(|uri| crate::vpn::parse_manual_uri(uri).ok())
+        .map(|server| server.get_server_key())
+        .collect()
+}
+
+// Screenshot all monitors with our windows hidden and add servers from any QR codes
+#[cfg(windows)]
+unsafe fn scan_qr_codes(hwnd: HWND) {
+    let owner = unsafe { GetWindow(hwnd, GW_OWNER) }.ok();
+    let result = unsafe {
+        let _ = ShowWindow(hwnd, SW_HIDE);
+        if let Some(owner) = owner {
+            let _ = ShowWindow(owner, SW_HIDE);
+        }
+        // Give the desktop time to repaint what the windows covered
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        let result = crate::qr::scan_screen();
+        if let Some(owner) = owner {
+            let _ = ShowWindow(owner, SW_SHOW);
+        }
+        let _ = ShowWindow(hwnd, SW_SHOW);
+        let _ = SetForegroundWindow(hwnd);
+        result
+    };
+
+    let links = match result {
+        Ok(links) if links.is_empty() => {
+            unsafe { message_box(hwnd, "No QR codes with vmess://, vless://, trojan://, ss://, ssr:// or hysteria2:// links were found on screen.", "Scan QR", MB_OK | MB_ICONINFORMATION) };
+            return;
+        }
+        Ok(links) => links,
+        Err(e) => {
+            unsafe { message_box(hwnd, &e, "Scan QR", MB_OK | MB_ICONERROR) };
+            return;
+        }
+    };
+
+    let mut config = Config::load().unwrap_or_default();
+    let mut keys = manual_server_keys(&config);
+    let mut added = 0;
+    for link in links {
+        let Ok(server) = crate::vpn::parse_manual_uri(&link) else {
+            continue;
+        };
+        if !keys.contains(&server.get_server_key()) {
+            keys.push(server.get_server_key());
+            config.manual_servers.push(link);
+            added += 1;
+        }
+    }
+    if added == 0 {
+        unsafe { message_box(hwnd, "The servers in the QR codes are already in the list.", "Scan QR", MB_OK | MB_ICONINFORMATION) };
+        return;
+    }
+    unsafe { save_and_reload(hwnd, &config) };
+}
+
+// Save the edited list and have the settings window re-load servers
+#[cfg(windows)]
+unsafe fn save_and_reload(hwnd: HWND, config: &Config) {
+    match config.save() {
+        Ok(_) => unsafe {
+            refresh_manual_list(hwnd, config);
+            if let Ok(owner) = GetWindow(hwnd, GW_OWNER) {
+                super::settings_window::reload_subscriptions(owner);
+            }
+        },
+        Err(e) => unsafe {
+            message_box(hwnd, &format!("Failed to save config:\n{}", e), "Error", MB_OK | MB_ICONERROR);
+        },
+    }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn manual_servers_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => {
+            let control_id = (wparam.0 & 0xFFFF) as i32;
+            let notification_code = (wparam.0 >> 16) & 0xFFFF;
+            if notification_code != 0 {
+                return LRESULT(0);
+            }
+
+            match control_id {
+                ID_MANUAL_ADD_BUTTON => unsafe {
+                    let uri = get_control_text(hwnd, ID_MANUAL_URI_EDIT).trim().to_string();
+                    let server = match crate::vpn::parse_manual_uri(&uri) {
+                        Ok(server) => server,
+                        Err(e) => {
+                            message_box(hwnd, &e, "Add Server", MB_OK | MB_ICONWARNING);
+                            return LRESULT(0);
+                        }
+                    };
+                    let mut config = Config::load().unwrap_or_default();
+                    if manual_server_keys(&config).contains(&server.get_server_key()) {
+                        message_box(hwnd, "This server is already in the list.", "Add Server", MB_OK | MB_ICONWARNING);
+                        return LRESULT(0);
+                    }
+                    config.manual_servers.push(uri.clone());
+                    set_control_text(hwnd, ID_MANUAL_URI_EDIT, "");
+                    save_and_reload(hwnd, &config);
+                    // Kept in the list, but say up front that it won't start
+                    if let Some(reason) = crate::vpn::ssr::parse(&uri).ok().and_then(|link| link.unsupported_reason()) {
+                        message_box(hwnd, &reason, "Add Server", MB_OK | MB_ICONWARNING);
+                    }
+                },
+                ID_MANUAL_REMOVE_BUTTON => unsafe {
+                    let mut config = Config::load().unwrap_or_default();
+                    let Some(index) = get_listbox_selection(hwnd, ID_MANUAL_LIST)
+                        .filter(|&i| i < config.manual_servers.len()) else {
+                        message_box(hwnd, "Select a server first.", "Add Server", MB_OK | MB_ICONWARNING);
+                        return LRESULT(0);
+                    };
+                    let uri = config.manual_servers.remove(index);
+                    // A removed server that is running would otherwise keep its port until restart
+                    if let Ok(server) = crate::vpn::parse_manual_uri(&uri) {
+                        let key = server.get_server_key();
+                        if crate::xray_manager::get_server_info(&key).is_some() {
+                            let _ = crate::stop_single_server(&key);
+                        }
+                    }
+                    save_and_reload(hwnd, &config);
+                },
+                ID_MANUAL_SCAN_BUTTON => unsafe {
+                    scan_qr_codes(hwnd);
+                },
+                ID_MANUAL_CLOSE_BUTTON => unsafe {
+                    let _ = DestroyWindow(hwnd);
+                },
+                _ => {}
+            }
+            LRESULT(0)
+        }
+        WM_CTLCOLORSTATIC => {
+            unsafe {
+                let hdc = HDC(wparam.0 as *mut _);
+                SetBkMode(hdc, TRANSPARENT);
+                LRESULT(GetStockObject(WHITE_BRUSH).0 as isize)
+            }
+        }
+        WM_DESTROY => {
+            MANUAL_SERVERS_WINDOW.store(0, std::sync::atomic::Ordering::Relaxed);
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
@@ -1,5 +1,20 @@
 pub mod tray;
 pub mod settings_window;
+pub mod server_list;
+pub mod controls;
+#[cfg(feature = "api")]
+pub mod api_window;
+pub mod form_window;
+pub mod subscriptions_window;
+pub mod share_window;
+pub mod routing_window;
+pub mod routing_rules_window;
+pub mod manual_servers_window;
+pub mod rulesets_window;
+pub mod balancer_window;
+pub mod qr_window;
+pub mod log_window;
+pub mod config_window;
 
 pub use tray::{create_tray_icon_with_servers, create_tray_menu_with_servers};
 pub use settings_window::create_settings_window;
@@ -0,0 +1,128 @@
+#[cfg(windows)]
+use windows::{
+    core::{w, PCWSTR},
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE, RECT},
+        Graphics::Gdi::{BeginPaint, EndPaint, FillRect, InvalidateRect, GetStockObject, BLACK_BRUSH, HBRUSH, PAINTSTRUCT},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+#[cfg(windows)]
+use super::controls::*;
+
+use std::sync::Mutex;
+
+// Window size; the code is scaled to fit the client area
+const WINDOW_SIZE: i32 = 640;
+
+// Light modules around the code so phone cameras can find it
+const QUIET_ZONE: usize = 4;
+
+// Only one QR window at a time (HWND stored as raw value)
+static QR_WINDOW: std::sync::atomic::AtomicIsize = std::sync::atomic::AtomicIsize::new(0);
+
+// Modules of the code shown: (width, dark modules row by row)
+static QR_MODULES: Mutex<(usize, Vec<bool>)> = Mutex::new((0, Vec::new()));
+
+/// Show `text` as a QR code, reusing the window if one is open
+#[cfg(windows)]
+pub unsafe fn show_qr_window(owner: HWND, title: &str, text: &str) -> Result<(), String> {
+    let code = qrcode::QrCode::new(text.as_bytes())
+        .map_err(|e| format!("Link is too long for a QR code: {}", e))?;
+    let modules = code.to_colors().into_iter().map(|color| color == qrcode::Color::Dark).collect();
+    if let Ok(mut shown) = QR_MODULES.lock() {
+        *shown = (code.width(), modules);
+    }
+
+    let title_wide: Vec<u16> = format!("{}\0", title).encode_utf16().collect();
+    unsafe {
+        let existing = HWND(QR_WINDOW.load(std::sync::atomic::Ordering::Relaxed) as *mut _);
+        if !existing.is_invalid() && IsWindow(existing).as_bool() {
+            let _ = SetWindowTextW(existing, PCWSTR::from_raw(title_wide.as_ptr()));
+            let _ = InvalidateRect(existing, None, true);
+            let _ = ShowWindow(existing, SW_RESTORE);
+            let _ = SetForegroundWindow(existing);
+            return Ok(());
+        }
+
+        let hinstance: HINSTANCE = GetModuleHandleW(None).unwrap().into();
+        register_window_class(w!("QrWindowClass"), Some(qr_window_proc), hinstance);
+
+        let hwnd = CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("QrWindowClass"),
+            PCWSTR::from_raw(title_wide.as_ptr()),
+            WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_VISIBLE,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            WINDOW_SIZE,
+            WINDOW_SIZE + 40, // Room for the caption
+            owner,
+            None,
+            hinstance,
+            None,
+        ).map_err(|e| format!("Failed to create QR window: {}", e))?;
+
+        QR_WINDOW.store(hwnd.0 as isize, std::sync::atomic::Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+// Draw the dark modules as squares, centered in the client area
+#[cfg(windows)]
+unsafe fn paint_code(hwnd: HWND) {
+    let Ok(shown) = QR_MODULES.lock() else {
+        return;
+    };
+    let (width, modules) = &*shown;
+
+    unsafe {
+        let mut paint = PAINTSTRUCT::default();
+        let hdc = BeginPaint(hwnd, &mut paint);
+
+        let mut client = RECT::default();
+        let _ = GetClientRect(hwnd, &mut client);
+        let side = (client.right - client.left).min(client.bottom - client.top);
+        let total = (*width + 2 * QUIET_ZONE) as i32;
+        if *width > 0 && side >= total {
+            let scale = side / total;
+            let left = (client.right - client.left - scale * total) / 2 + scale * QUIET_ZONE as i32;
+            let top = (client.bottom - client.top - scale * total) / 2 + scale * QUIET_ZONE as i32;
+            let black = HBRUSH(GetStockObject(BLACK_BRUSH).0);
+            for (index, _) in modules.iter().enumerate().filter(|(_, dark)| **dark) {
+                let (x, y) = ((index % width) as i32, (index / width) as i32);
+                let rect = RECT {
+                    left: left + x * scale,
+                    top: top + y * scale,
+                    right: left + (x + 1) * scale,
+                    bottom: top + (y + 1) * scale,
+                };
+                FillRect(hdc, &rect, black);
+            }
+        }
+
+        let _ = EndPaint(hwnd, &paint);
+    }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn qr_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_PAINT => {
+            unsafe { paint_code(hwnd) };
+            LRESULT(0)
+        }
+        WM_DESTROY => {
+            QR_WINDOW.store(0, std::sync::atomic::Ordering::Relaxed);
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
@@ -0,0 +1,259 @@
+#[cfg(windows)]
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE},
+        Graphics::Gdi::{SetBkMode, TRANSPARENT, HDC, GetStockObject, WHITE_BRUSH},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+#[cfg(windows)]
+use super::controls::*;
+
+use crate::config::{Config, RoutingRule};
+
+// Control IDs
+const ID_RULES_LIST: i32 = 1901;
+const ID_RULES_ADD_BUTTON: i32 = 1902;
+const ID_RULES_EDIT_BUTTON: i32 = 1903;
+const ID_RULES_REMOVE_BUTTON: i32 = 1904;
+const ID_RULES_UP_BUTTON: i32 = 1905;
+const ID_RULES_DOWN_BUTTON: i32 = 1906;
+const ID_RULES_CLOSE_BUTTON: i32 = 1907;
+
+// Layout constants (match settings window)
+const MARGIN: i32 = 15;
+const FONT_SIZE: i32 = 32;
+const CONTROL_HEIGHT: i32 = 45;
+const WINDOW_WIDTH: i32 = 900;
+
+// Outbound choices as shown in the form, in routing::OUTBOUNDS order
+const OUTBOUND_LABELS: [&str; 3] = ["Direct", "Proxy", "Block"];
+
+// Only one routing rules window at a time (HWND stored as raw value)
+static RULES_WINDOW: std::sync::atomic::AtomicIsize = std::sync::atomic::AtomicIsize::new(0);
+
+/// Open the routing rules window, or focus it if already open
+#[cfg(windows)]
+pub unsafe fn show_routing_rules_window(owner: HWND) {
+    unsafe {
+        let existing = HWND(RULES_WINDOW.load(std::sync::atomic::Ordering::Relaxed) as *mut _);
+        if !existing.is_invalid() && IsWindow(existing).as_bool() {
+            let _ = ShowWindow(existing, SW_RESTORE);
+            let _ = SetForegroundWindow(existing);
+            return;
+        }
+
+        let hinstance: HINSTANCE = GetModuleHandleW(None).unwrap().into();
+        register_window_class(w!("RoutingRulesWindowClass"), Some(rules_window_proc), hinstance);
+
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("RoutingRulesWindowClass"),
+            w!("Routing Rules"),
+            WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_VISIBLE,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            WINDOW_WIDTH,
+            680,
+            owner,
+            None,
+            hinstance,
+            None,
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                eprintln!("Failed to create routing rules window: {}", e);
+                return;
+            }
+        };
+
+        RULES_WINDOW.store(hwnd.0 as isize, std::sync::atomic::Ordering::Relaxed);
+        create_rules_controls(hwnd, hinstance);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn create_rules_controls(hwnd: HWND, hinstance: HINSTANCE) {
+    let hfont = unsafe { create_font(FONT_SIZE) };
+    let full_width = WINDOW_WIDTH - 2 * MARGIN - 20;
+    let third_width = (full_width - 20) / 3;
+    let half_width = (full_width - 10) / 2;
+
+    unsafe {
+        let mut y = MARGIN;
+        create_label(hwnd, hinstance, "Rules for every server of this profile, checked top to bottom before the preset:",
+            (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_listbox(hwnd, hinstance, ID_RULES_LIST, (MARGIN, y, full_width, 300), hfont);
+
+        y += 300 + MARGIN;
+        create_button(hwnd, hinstance, "Add...", ID_RULES_ADD_BUTTON,
+            (MARGIN, y, third_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Edit...", ID_RULES_EDIT_BUTTON,
+            (MARGIN + third_width + 10, y, third_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Remove", ID_RULES_REMOVE_BUTTON,
+            (MARGIN + 2 * (third_width + 10), y, third_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + 10;
+        create_button(hwnd, hinstance, "Move Up", ID_RULES_UP_BUTTON,
+            (MARGIN, y, half_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Move Down", ID_RULES_DOWN_BUTTON,
+            (MARGIN + half_width + 10, y, half_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + MARGIN;
+        create_button(hwnd, hinstance, "Close", ID_RULES_CLOSE_BUTTON,
+            (full_width + MARGIN - 120, y, 110, CONTROL_HEIGHT), hfont);
+
+        refresh_rules_list(hwnd);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn refresh_rules_list(hwnd: HWND) {
+    let items: Vec<String> = crate::routing::custom_rules().iter()
+        .enumerate()
+        .map(|(i, rule)| format!("{}.  {}", i + 1, crate::routing::describe_rule(rule)))
+        .collect();
+    unsafe { set_listbox_items(hwnd, ID_RULES_LIST, &items) };
+}
+
+#[cfg(windows)]
+unsafe fn selected_index(hwnd: HWND) -> Option<usize> {
+    let count = crate::routing::custom_rules().len();
+    unsafe { get_listbox_selection(hwnd, ID_RULES_LIST) }.filter(|&i| i < count)
+}
+
+// Add a rule (index None) or edit an existing one
+#[cfg(windows)]
+unsafe fn show_rule_form(hwnd: HWND, index: Option<usize>) {
+    use crate::ui::form_window::{show_form, FormField};
+
+    let current = index.and_then(|i| crate::routing::custom_rules().into_iter().nth(i))
+        .unwrap_or(RoutingRule { outbound: "direct".to_string(), domains: Vec::new(), ips: Vec::new() });
+    let entries: Vec<&str> = current.domains.iter().chain(current.ips.iter()).map(String::as_str).collect();
+    let outbound_label = crate::routing::OUTBOUNDS.iter()
+        .position(|o| *o == current.outbound)
+        .map(|i| OUTBOUND_LABELS[i])
+        .unwrap_or("Direct");
+    let fields = vec![
+        FormField::text("Match (geosite:, geoip:, domain:*.corp.local, full:, IPs/CIDRs, comma-separated):", &entries.join(", ")),
+        FormField::choice("Send matching traffic:", &OUTBOUND_LABELS, outbound_label),
+    ];
+    let title = if index.is_some() { "Edit Routing Rule" } else { "Add Routing Rule" };
+
+    unsafe {
+        show_form(hwnd, title, fields, Box::new(move |values| {
+            let outbound = OUTBOUND_LABELS.iter()
+                .position(|label| *label == values[1])
+                .map(|i| crate::routing::OUTBOUNDS[i])
+                .unwrap_or("direct");
+            let rule = crate::routing::rule_from_text(&values[0], outbound)?;
+
+            let mut rules = Config::load()?.routing_rules;
+            match index.and_then(|i| rules.get_mut(i)) {
+                Some(existing) => *existing = rule,
+                None => rules.push(rule),
+            }
+            let result = crate::routing::save_rules(rules);
+            refresh_rules_list(hwnd);
+            result
+        }));
+    }
+}
+
+// Swap the selected rule with its neighbour and keep it selected
+#[cfg(windows)]
+unsafe fn move_rule(hwnd: HWND, up: bool) -> Result<(), String> {
+    let index = unsafe { selected_index(hwnd) }.ok_or_else(|| "Select a rule first.".to_string())?;
+    let mut rules = Config::load()?.routing_rules;
+    let target = if up { index.checked_sub(1) } else { Some(index + 1).filter(|&i| i < rules.len()) };
+    let Some(target) = target else {
+        return Ok(());
+    };
+    rules.swap(index, target);
+    let result = crate::routing::save_rules(rules);
+    unsafe {
+        refresh_rules_list(hwnd);
+        set_listbox_current(hwnd, ID_RULES_LIST, target);
+    }
+    result
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn rules_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => {
+            let control_id = (wparam.0 & 0xFFFF) as i32;
+            let notification_code = (wparam.0 >> 16) & 0xFFFF;
+            if notification_code != 0 {
+                return LRESULT(0);
+            }
+
+            let result = match control_id {
+                ID_RULES_ADD_BUTTON => unsafe {
+                    show_rule_form(hwnd, None);
+                    Ok(())
+                },
+                ID_RULES_EDIT_BUTTON => unsafe {
+                    match selected_index(hwnd) {
+                        Some(index) => {
+                            show_rule_form(hwnd, Some(index));
+                            Ok(())
+                        }
+                        None => Err("Select a rule first.".to_string()),
+                    }
+                },
+                ID_RULES_REMOVE_BUTTON => unsafe {
+                    match selected_index(hwnd) {
+                        Some(index) => Config::load().and_then(|config| {
+                            let mut rules = config.routing_rules;
+                            rules.remove(index);
+                            crate::routing::save_rules(rules)
+                        }),
+                        None => Err("Select a rule first.".to_string()),
+                    }
+                },
+                // Moving refreshes the list itself to keep the selection
+                ID_RULES_UP_BUTTON | ID_RULES_DOWN_BUTTON => unsafe {
+                    if let Err(e) = move_rule(hwnd, control_id == ID_RULES_UP_BUTTON) {
+                        message_box(hwnd, &e, "Routing Rules", MB_OK | MB_ICONWARNING);
+                    }
+                    return LRESULT(0);
+                },
+                ID_RULES_CLOSE_BUTTON => unsafe {
+                    let _ = DestroyWindow(hwnd);
+                    return LRESULT(0);
+                },
+                _ => return LRESULT(0),
+            };
+
+            unsafe {
+                refresh_rules_list(hwnd);
+                if let Err(e) = result {
+                    message_box(hwnd, &e, "Routing Rules", MB_OK | MB_ICONWARNING);
+                }
+            }
+            LRESULT(0)
+        }
+        WM_CTLCOLORSTATIC => {
+            unsafe {
+                let hdc = HDC(wparam.0 as *mut _);
+                SetBkMode(hdc, TRANSPARENT);
+                LRESULT(GetStockObject(WHITE_BRUSH).0 as isize)
+            }
+        }
+        WM_DESTROY => {
+            RULES_WINDOW.store(0, std::sync::atomic::Ordering::Relaxed);
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
@@ -0,0 +1,254 @@
+#[cfg(windows)]
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE},
+        Graphics::Gdi::{SetBkMode, TRANSPARENT, HDC, GetStockObject, WHITE_BRUSH},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+#[cfg(windows)]
+use super::controls::*;
+
+use crate::config::{Config, RoutingPreset};
+
+// Control IDs
+const ID_ROUTING_LIST: i32 = 1501;
+const ID_ROUTING_NEW_BUTTON: i32 = 1502;
+const ID_ROUTING_EDIT_BUTTON: i32 = 1503;
+const ID_ROUTING_DELETE_BUTTON: i32 = 1504;
+const ID_ROUTING_USE_BUTTON: i32 = 1505;
+const ID_ROUTING_USE_NONE_BUTTON: i32 = 1506;
+const ID_ROUTING_IMPORT_BUTTON: i32 = 1507;
+const ID_ROUTING_EXPORT_BUTTON: i32 = 1508;
+const ID_ROUTING_CLOSE_BUTTON: i32 = 1509;
+
+// Layout constants (match settings window)
+const MARGIN: i32 = 15;
+const FONT_SIZE: i32 = 32;
+const CONTROL_HEIGHT: i32 = 45;
+const WINDOW_WIDTH: i32 = 900;
+
+// Only one routing presets window at a time (HWND stored as raw value)
+static ROUTING_WINDOW: std::sync::atomic::AtomicIsize = std::sync::atomic::AtomicIsize::new(0);
+
+/// Open the routing presets window, or focus it if already open
+#[cfg(windows)]
+pub unsafe fn show_routing_window(owner: HWND) {
+    unsafe {
+        let existing = HWND(ROUTING_WINDOW.load(std::sync::atomic::Ordering::Relaxed) as *mut _);
+        if !existing.is_invalid() && IsWindow(existing).as_bool() {
+            let _ = ShowWindow(existing, SW_RESTORE);
+            let _ = SetForegroundWindow(existing);
+            return;
+        }
+
+        let hinstance: HINSTANCE = GetModuleHandleW(None).unwrap().into();
+        register_window_class(w!("RoutingWindowClass"), Some(routing_window_proc), hinstance);
+
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("RoutingWindowClass"),
+            w!("Routing Presets"),
+            WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_VISIBLE,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            WINDOW_WIDTH,
+            720,
+            owner,
+            None,
+            hinstance,
+            None,
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                eprintln!("Failed to create routing presets window: {}", e);
+                return;
+            }
+        };
+
+        ROUTING_WINDOW.store(hwnd.0 as isize, std::sync::atomic::Ordering::Relaxed);
+        create_routing_controls(hwnd, hinstance);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn create_routing_controls(hwnd: HWND, hinstance: HINSTANCE) {
+    let hfont = unsafe { create_font(FONT_SIZE) };
+    let full_width = WINDOW_WIDTH - 2 * MARGIN - 20;
+    let third_width = (full_width - 20) / 3;
+    let half_width = (full_width - 10) / 2;
+
+    unsafe {
+        let mut y = MARGIN;
+        create_label(hwnd, hinstance, "Presets (the checked one applies to all servers of this profile):",
+            (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_listbox(hwnd, hinstance, ID_ROUTING_LIST, (MARGIN, y, full_width, 280), hfont);
+
+        y += 280 + MARGIN;
+        create_button(hwnd, hinstance, "New...", ID_ROUTING_NEW_BUTTON,
+            (MARGIN, y, third_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Edit...", ID_ROUTING_EDIT_BUTTON,
+            (MARGIN + third_width + 10, y, third_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Delete", ID_ROUTING_DELETE_BUTTON,
+            (MARGIN + 2 * (third_width + 10), y, third_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + 10;
+        create_button(hwnd, hinstance, "Use Selected", ID_ROUTING_USE_BUTTON,
+            (MARGIN, y, half_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Use None (all via proxy)", ID_ROUTING_USE_NONE_BUTTON,
+            (MARGIN + half_width + 10, y, half_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + 10;
+        create_button(hwnd, hinstance, "Import...", ID_ROUTING_IMPORT_BUTTON,
+            (MARGIN, y, half_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Export...", ID_ROUTING_EXPORT_BUTTON,
+            (MARGIN + half_width + 10, y, half_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + MARGIN;
+        create_button(hwnd, hinstance, "Close", ID_ROUTING_CLOSE_BUTTON,
+            (full_width + MARGIN - 120, y, 110, CONTROL_HEIGHT), hfont);
+
+        refresh_preset_list(hwnd);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn refresh_preset_list(hwnd: HWND) {
+    let config = Config::load().unwrap_or_default();
+    let items: Vec<String> = config.routing_presets.iter()
+        .map(|preset| {
+            let mark = if preset.name == config.routing_preset { "✓" } else { "   " };
+            let entries: usize = preset.rules.iter().map(|r| r.domains.len() + r.ips.len()).sum();
+            let dns = if preset.dns_servers.is_empty() { String::new() } else { format!(", DNS {}", preset.dns_servers.join(" ")) };
+            format!("{} {}  —  {} entries{}", mark, preset.name, entries, dns)
+        })
+        .collect();
+    unsafe { set_listbox_items(hwnd, ID_ROUTING_LIST, &items) };
+}
+
+#[cfg(windows)]
+unsafe fn selected_preset(hwnd: HWND) -> Option<RoutingPreset> {
+    let index = unsafe { get_listbox_selection(hwnd, ID_ROUTING_LIST) }?;
+    Config::load().ok()?.routing_presets.into_iter().nth(index)
+}
+
+// Edit a preset (or a new one) in a form: one entry list per outbound plus DNS servers
+#[cfg(windows)]
+unsafe fn show_preset_form(hwnd: HWND, preset: Option<RoutingPreset>) {
+    use crate::ui::form_window::{show_form, FormField};
+
+    let preset = preset.unwrap_or(RoutingPreset { name: String::new(), rules: Vec::new(), dns_servers: Vec::new() });
+    let previous_name = preset.name.clone();
+    let list = |outbound| crate::routing::list_for_outbound(&preset, outbound);
+    let fields = vec![
+        FormField::text("Preset name:", &preset.name),
+        FormField::text("Direct (domains, IPs/CIDRs, geosite:/geoip:, comma-separated):", &list("direct")),
+        FormField::text("Through the proxy:", &list("proxy")),
+        FormField::text("Blocked:", &list("block")),
+        FormField::text("DNS servers (empty = system resolver):", &preset.dns_servers.join(", ")),
+    ];
+    let title = if previous_name.is_empty() { "New Routing Preset" } else { "Edit Routing Preset" };
+
+    unsafe {
+        show_form(hwnd, title, fields, Box::new(move |values| {
+            let preset = crate::routing::preset_from_lists(&values[0], &values[1..4], &values[4])?;
+            crate::routing::save_preset(&previous_name, preset)?;
+            refresh_preset_list(hwnd);
+            Ok(())
+        }));
+    }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn routing_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => {
+            let control_id = (wparam.0 & 0xFFFF) as i32;
+            let notification_code = (wparam.0 >> 16) & 0xFFFF;
+            if notification_code != 0 {
+                return LRESULT(0);
+            }
+
+            let result = match control_id {
+                ID_ROUTING_NEW_BUTTON => unsafe {
+                    show_preset_form(hwnd, None);
+                    Ok(())
+                },
+                ID_ROUTING_EDIT_BUTTON => unsafe {
+                    match selected_preset(hwnd) {
+                        Some(preset) => {
+                            show_preset_form(hwnd, Some(preset));
+                            Ok(())
+                        }
+                        None => Err("Select a preset first.".to_string()),
+                    }
+                },
+                ID_ROUTING_DELETE_BUTTON => unsafe {
+                    selected_preset(hwnd)
+                        .ok_or_else(|| "Select a preset first.".to_string())
+                        .and_then(|preset| crate::routing::delete_preset(&preset.name))
+                },
+                ID_ROUTING_USE_BUTTON => unsafe {
+                    selected_preset(hwnd)
+                        .ok_or_else(|| "Select a preset first.".to_string())
+                        .and_then(|preset| crate::routing::set_active(&preset.name))
+                },
+                ID_ROUTING_USE_NONE_BUTTON => crate::routing::set_active(""),
+                ID_ROUTING_IMPORT_BUTTON => unsafe {
+                    match browse_for_file(hwnd) {
+                        Some(path) => crate::routing::import_preset(&path)
+                            .and_then(|preset| crate::routing::save_preset("", preset)),
+                        None => Ok(()),
+                    }
+                },
+                ID_ROUTING_EXPORT_BUTTON => unsafe {
+                    match selected_preset(hwnd) {
+                        Some(preset) => {
+                            let filters = [("Routing preset (*.json)", "*.json"), ("All Files", "*.*")];
+                            let default_name = format!("{}.json", preset.name);
+                            match browse_for_save_file(hwnd, &default_name, &filters) {
+                                Some(path) => crate::routing::export_preset(&preset, &path),
+                                None => Ok(()),
+                            }
+                        }
+                        None => Err("Select a preset first.".to_string()),
+                    }
+                },
+                ID_ROUTING_CLOSE_BUTTON => unsafe {
+                    let _ = DestroyWindow(hwnd);
+                    return LRESULT(0);
+                },
+                _ => return LRESULT(0),
+            };
+
+            unsafe {
+                refresh_preset_list(hwnd);
+                if let Err(e) = result {
+                    message_box(hwnd, &e, "Routing Presets", MB_OK | MB_ICONWARNING);
+                }
+            }
+            LRESULT(0)
+        }
+        WM_CTLCOLORSTATIC => {
+            unsafe {
+                let hdc = HDC(wparam.0 as *mut _);
+                SetBkMode(hdc, TRANSPARENT);
+                LRESULT(GetStockObject(WHITE_BRUSH).0 as isize)
+            }
+        }
+        WM_DESTROY => {
+            ROUTING_WINDOW.store(0, std::sync::atomic::Ordering::Relaxed);
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
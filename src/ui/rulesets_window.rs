@@ -0,0 +1,241 @@
+#[cfg(windows)]
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE},
+        Graphics::Gdi::{SetBkMode, TRANSPARENT, HDC, GetStockObject, WHITE_BRUSH},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+#[cfg(windows)]
+use super::controls::*;
+
+use crate::config::{Config, RuleSet};
+
+// Control IDs
+const ID_RULESET_LIST: i32 = 1701;
+const ID_RULESET_ADD_BUTTON: i32 = 1702;
+const ID_RULESET_EDIT_BUTTON: i32 = 1703;
+const ID_RULESET_REMOVE_BUTTON: i32 = 1704;
+const ID_RULESET_UPDATE_BUTTON: i32 = 1705;
+const ID_RULESET_CLOSE_BUTTON: i32 = 1706;
+
+// Layout constants (match settings window)
+const MARGIN: i32 = 15;
+const FONT_SIZE: i32 = 32;
+const CONTROL_HEIGHT: i32 = 45;
+const WINDOW_WIDTH: i32 = 900;
+
+// Outbound choices as shown in the form, in routing::OUTBOUNDS order
+const OUTBOUND_LABELS: [&str; 3] = ["Direct", "Proxy", "Block"];
+
+// Only one rule sets window at a time (HWND stored as raw value)
+static RULESETS_WINDOW: std::sync::atomic::AtomicIsize = std::sync::atomic::AtomicIsize::new(0);
+
+/// Open the rule sets window, or focus it if already open
+#[cfg(windows)]
+pub unsafe fn show_rulesets_window(owner: HWND) {
+    unsafe {
+        let existing = HWND(RULESETS_WINDOW.load(std::sync::atomic::Ordering::Relaxed) as *mut _);
+        if !existing.is_invalid() && IsWindow(existing).as_bool() {
+            let _ = ShowWindow(existing, SW_RESTORE);
+            let _ = SetForegroundWindow(existing);
+            return;
+        }
+
+        let hinstance: HINSTANCE = GetModuleHandleW(None).unwrap().into();
+        register_window_class(w!("RuleSetsWindowClass"), Some(rulesets_window_proc), hinstance);
+
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("RuleSetsWindowClass"),
+            w!("Rule Sets"),
+            WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_VISIBLE,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            WINDOW_WIDTH,
+            620,
+            owner,
+            None,
+            hinstance,
+            None,
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                eprintln!("Failed to create rule sets window: {}", e);
+                return;
+            }
+        };
+
+        RULESETS_WINDOW.store(hwnd.0 as isize, std::sync::atomic::Ordering::Relaxed);
+        create_ruleset_controls(hwnd, hinstance);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn create_ruleset_controls(hwnd: HWND, hinstance: HINSTANCE) {
+    let hfont = unsafe { create_font(FONT_SIZE) };
+    let full_width = WINDOW_WIDTH - 2 * MARGIN - 20;
+    let quarter_width = (full_width - 30) / 4;
+
+    unsafe {
+        let mut y = MARGIN;
+        let label = format!("Hosted domain/IP lists (dnsmasq, Clash rule-provider, plain), updated every {} h:",
+            crate::rulesets::REFRESH_INTERVAL.as_secs() / 3600);
+        create_label(hwnd, hinstance, &label, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_listbox(hwnd, hinstance, ID_RULESET_LIST, (MARGIN, y, full_width, 300), hfont);
+
+        y += 300 + MARGIN;
+        for (i, (text, id)) in [
+            ("Add...", ID_RULESET_ADD_BUTTON),
+            ("Edit...", ID_RULESET_EDIT_BUTTON),
+            ("Remove", ID_RULESET_REMOVE_BUTTON),
+            ("Update Now", ID_RULESET_UPDATE_BUTTON),
+        ].into_iter().enumerate() {
+            create_button(hwnd, hinstance, text, id,
+                (MARGIN + i as i32 * (quarter_width + 10), y, quarter_width, CONTROL_HEIGHT), hfont);
+        }
+
+        y += CONTROL_HEIGHT + MARGIN;
+        create_button(hwnd, hinstance, "Close", ID_RULESET_CLOSE_BUTTON,
+            (full_width + MARGIN - 120, y, 110, CONTROL_HEIGHT), hfont);
+
+        refresh_ruleset_list(hwnd);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn refresh_ruleset_list(hwnd: HWND) {
+    let config = Config::load().unwrap_or_default();
+    let items: Vec<String> = config.rule_sets.iter()
+        .map(|set| format!("{}  →  {}  —  {}", set.name, set.outbound, set.url))
+        .collect();
+    unsafe { set_listbox_items(hwnd, ID_RULESET_LIST, &items) };
+}
+
+#[cfg(windows)]
+unsafe fn selected_index(hwnd: HWND) -> Option<usize> {
+    let count = Config::load().unwrap_or_default().rule_sets.len();
+    unsafe { get_listbox_selection(hwnd, ID_RULESET_LIST) }.filter(|&i| i < count)
+}
+
+// Add a rule set (index None) or edit an existing one. The list is downloaded
+// before saving so a bad URL is reported while the form is still open.
+#[cfg(windows)]
+unsafe fn show_ruleset_form(hwnd: HWND, index: Option<usize>) {
+    use crate::ui::form_window::{show_form, FormField};
+
+    let config = Config::load().unwrap_or_default();
+    let current = index.and_then(|i| config.rule_sets.get(i)).cloned()
+        .unwrap_or(RuleSet { name: String::new(), url: String::new(), outbound: "direct".to_string() });
+    let outbound_label = crate::routing::OUTBOUNDS.iter()
+        .position(|o| *o == current.outbound)
+        .map(|i| OUTBOUND_LABELS[i])
+        .unwrap_or("Direct");
+    let fields = vec![
+        FormField::text("Name:", &current.name),
+        FormField::path("URL or local file:", &current.url),
+        FormField::choice("Send matching traffic:", &OUTBOUND_LABELS, outbound_label),
+    ];
+    let title = if index.is_some() { "Edit Rule Set" } else { "Add Rule Set" };
+
+    unsafe {
+        show_form(hwnd, title, fields, Box::new(move |values| {
+            if values[1].is_empty() {
+                return Err("Enter the URL of the list".to_string());
+            }
+            let outbound = OUTBOUND_LABELS.iter()
+                .position(|label| *label == values[2])
+                .map(|i| crate::routing::OUTBOUNDS[i])
+                .unwrap_or("direct");
+            let name = if values[0].is_empty() { values[1].clone() } else { values[0].clone() };
+            let set = RuleSet { name, url: values[1].clone(), outbound: outbound.to_string() };
+            crate::rulesets::download(&set)?;
+
+            let mut config = Config::load()?;
+            match index.and_then(|i| config.rule_sets.get_mut(i)) {
+                Some(existing) => *existing = set,
+                None => config.rule_sets.push(set),
+            }
+            config.save()?;
+            refresh_ruleset_list(hwnd);
+            crate::routing::restart_running()
+        }));
+    }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn rulesets_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => {
+            let control_id = (wparam.0 & 0xFFFF) as i32;
+            let notification_code = (wparam.0 >> 16) & 0xFFFF;
+            if notification_code != 0 {
+                return LRESULT(0);
+            }
+
+            let result = match control_id {
+                ID_RULESET_ADD_BUTTON => unsafe {
+                    show_ruleset_form(hwnd, None);
+                    Ok(())
+                },
+                ID_RULESET_EDIT_BUTTON => unsafe {
+                    match selected_index(hwnd) {
+                        Some(index) => {
+                            show_ruleset_form(hwnd, Some(index));
+                            Ok(())
+                        }
+                        None => Err("Select a rule set first.".to_string()),
+                    }
+                },
+                ID_RULESET_REMOVE_BUTTON => unsafe {
+                    match selected_index(hwnd) {
+                        Some(index) => Config::load().and_then(|mut config| {
+                            config.rule_sets.remove(index);
+                            config.save()
+                        }).and_then(|_| crate::routing::restart_running()),
+                        None => Err("Select a rule set first.".to_string()),
+                    }
+                },
+                ID_RULESET_UPDATE_BUTTON => match crate::rulesets::update_all() {
+                    Ok(true) => crate::routing::restart_running(),
+                    Ok(false) => Ok(()),
+                    Err(e) => Err(format!("Some lists could not be updated:\n{}", e)),
+                },
+                ID_RULESET_CLOSE_BUTTON => unsafe {
+                    let _ = DestroyWindow(hwnd);
+                    return LRESULT(0);
+                },
+                _ => return LRESULT(0),
+            };
+
+            unsafe {
+                refresh_ruleset_list(hwnd);
+                if let Err(e) = result {
+                    message_box(hwnd, &e, "Rule Sets", MB_OK | MB_ICONWARNING);
+                }
+            }
+            LRESULT(0)
+        }
+        WM_CTLCOLORSTATIC => {
+            unsafe {
+                let hdc = HDC(wparam.0 as *mut _);
+                SetBkMode(hdc, TRANSPARENT);
+                LRESULT(GetStockObject(WHITE_BRUSH).0 as isize)
+            }
+        }
+        WM_DESTROY => {
+            RULESETS_WINDOW.store(0, std::sync::atomic::Ordering::Relaxed);
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
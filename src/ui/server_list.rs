@@ -0,0 +1,516 @@
+#[cfg(windows)]
+use windows::{
+    core::PWSTR,
+    Win32::{
+        Foundation::{HWND, LPARAM, WPARAM, HINSTANCE},
+        Graphics::Gdi::HFONT,
+        UI::Controls::*,
+        UI::WindowsAndMessaging::{SendMessageW, WINDOW_STYLE, WS_EX_CLIENTEDGE, WS_TABSTOP, WM_SETREDRAW},
+    },
+};
+
+use crate::vpn::{VpnServer, VPN_SERVERS};
+
+// Server table of the settings window: a report-style ListView with a checkbox
+// per server (enabled on Save) and a column per detail. Each row's lParam is the
+// server's index in VPN_SERVERS, so rows map back to servers however they are
+// shown. Clicking a column header sorts VPN_SERVERS itself, like the
+// reliability sort, and the order sticks across refreshes until the window closes.
+// The filter above the list hides rows without changing VPN_SERVERS, so hidden
+// servers keep their settings and are saved as they are; so do servers hidden
+// with "Hide Server", which only View → Show Hidden Servers lists. With `group_by` set the
+// rows sit in collapsible groups per subscription or country; a group the user
+// collapsed stays collapsed across rebuilds for the rest of the run.
+
+/// Column titles and widths, in display order
+const COLUMNS: &[(&str, i32)] = &[
+    ("Server", 260),
+    ("Protocol", 110),
+    ("Address", 230),
+    ("Latency", 150),
+    ("Speed", 150),
+    ("Port", 90),
+    ("Type", 90),
+    ("Status", 320),
+    ("Note", 260),
+];
+
+const COLUMN_SERVER: usize = 0;
+const COLUMN_PROTOCOL: usize = 1;
+const COLUMN_ADDRESS: usize = 2;
+const COLUMN_LATENCY: usize = 3;
+const COLUMN_SPEED: usize = 4;
+const COLUMN_PORT: usize = 5;
+const COLUMN_TYPE: usize = 6;
+const COLUMN_STATUS: usize = 7;
+const COLUMN_NOTE: usize = 8;
+
+// Column the list is sorted by and whether ascending; None = subscription order
+static SORT: std::sync::Mutex<Option<(usize, bool)>> = std::sync::Mutex::new(None);
+
+// Lowercase words a row must all contain, from the filter box
+static FILTER: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+// "subscription", "country" or empty for no groups
+static GROUP_BY: std::sync::Mutex<String> = std::sync::Mutex::new(String::new());
+
+// Headings of the groups in the list, by group id, and the headings collapsed by the user
+static GROUPS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+static COLLAPSED: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+// Whether hidden servers get rows, greyed out by their status
+static SHOW_HIDDEN: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+// Set while rows are inserted, whose check boxes report changes of their own
+static FILLING: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Text of one cell
+fn cell_text(server: &VpnServer, column: usize) -> String {
+    let key = server.get_server_key();
+    match column {
+        COLUMN_SERVER => {
+            let remarks = crate::remarks::label(server.rate, &server.tags);
+            let pin = if server.pinned { "📌 " } else { "" };
+            if remarks.is_empty() { format!("{}{}", pin, server.display_name()) } else { format!("{}{}  {}", pin, server.display_name(), remarks) }
+        }
+        COLUMN_PROTOCOL => server.protocol.clone(),
+        COLUMN_ADDRESS => format!("{}:{}", server.address, server.port),
+        COLUMN_LATENCY => crate::vpn::latency::label(&key).unwrap_or_default(),
+        COLUMN_SPEED => crate::vpn::speed::label(&key).unwrap_or_default(),
+        COLUMN_PORT => server.ports_text(),
+        COLUMN_TYPE => server.proxy_type.clone(),
+        COLUMN_STATUS => status_text(server),
+        COLUMN_NOTE => server.note.clone(),
+        _ => String::new(),
+    }
+}
+
+// Running, reliability, then a hint or the week's trend
+fn status_text(server: &VpnServer) -> String {
+    let key = server.get_server_key();
+    let mut parts = Vec::new();
+    if server.hidden {
+        parts.push("Hidden".to_string());
+    }
+    if crate::xray_manager::get_server_info(&key).is_some() {
+        parts.push("Running".to_string());
+    }
+    parts.extend(crate::reliability::label(&key));
+    if let Some(hint) = crate::diagnostics::get_hint(&key) {
+        parts.push(format!("⚠ {}", hint));
+    } else {
+        #[cfg(feature = "stats")]
+        parts.extend(crate::history::sparkline(&key));
+    }
+    parts.join("  ")
+}
+
+// Tested servers by time, then failed tests, then untested ones
+fn latency_order(server: &VpnServer) -> (u8, u128) {
+    match crate::vpn::latency::get_result(&server.get_server_key()).map(|latency| latency.result) {
+        Some(Ok(elapsed)) => (0, elapsed.as_millis()),
+        Some(Err(_)) => (1, 0),
+        None => (2, 0),
+    }
+}
+
+// Fastest download first, then failed tests, then untested servers
+fn speed_order(server: &VpnServer) -> (u8, u64) {
+    match crate::vpn::speed::get_result(&server.get_server_key()).map(|speed| speed.result) {
+        Some(Ok(mbps)) => (0, u64::MAX - (mbps * 1000.0) as u64),
+        Some(Err(_)) => (1, 0),
+        None => (2, 0),
+    }
+}
+
+fn compare(a: &VpnServer, b: &VpnServer, column: usize) -> std::cmp::Ordering {
+    match column {
+        COLUMN_SERVER => a.display_name().to_lowercase().cmp(&b.display_name().to_lowercase()),
+        COLUMN_PROTOCOL => a.protocol.cmp(&b.protocol),
+        COLUMN_ADDRESS => (&a.address, a.port).cmp(&(&b.address, b.port)),
+        COLUMN_LATENCY => latency_order(a).cmp(&latency_order(b)),
+        COLUMN_SPEED => speed_order(a).cmp(&speed_order(b)),
+        COLUMN_PORT => a.local_port.cmp(&b.local_port),
+        COLUMN_TYPE => a.proxy_type.cmp(&b.proxy_type),
+        COLUMN_STATUS => status_text(a).cmp(&status_text(b)),
+        COLUMN_NOTE => a.note.to_lowercase().cmp(&b.note.to_lowercase()),
+        _ => std::cmp::Ordering::Equal,
+    }
+}
+
+/// Put servers in the order picked by the last header click, if any
+pub fn apply_sort(servers: &mut [VpnServer]) {
+    if let Some((column, ascending)) = SORT.lock().ok().and_then(|sort| *sort) {
+        // Stable, so ties keep their order
+        servers.sort_by(|a, b| if ascending { compare(a, b, column) } else { compare(b, a, column) });
+    }
+}
+
+/// Show only servers whose name (provider's or alias), note, address or protocol contain
+/// every word of `text`
+pub fn set_filter(text: &str) {
+    if let Ok(mut filter) = FILTER.lock() {
+        *filter = text.split_whitespace().map(str::to_lowercase).collect();
+    }
+}
+
+fn matches_filter(server: &VpnServer, words: &[String]) -> bool {
+    let haystack = format!("{} {} {} {}:{} {}", server.name, server.alias, server.note,
+        server.address, server.port, server.protocol).to_lowercase();
+    words.iter().all(|word| haystack.contains(word.as_str()))
+}
+
+/// Group rows by "subscription" or "country"; empty shows one plain list
+pub fn set_grouping(group_by: &str) {
+    if let Ok(mut current) = GROUP_BY.lock() {
+        *current = group_by.to_string();
+    }
+}
+
+/// List hidden servers too, or leave them out
+pub fn set_show_hidden(show: bool) {
+    SHOW_HIDDEN.store(show, std::sync::atomic::Ordering::SeqCst);
+}
+
+/// Whether hidden servers are listed
+pub fn showing_hidden() -> bool {
+    SHOW_HIDDEN.load(std::sync::atomic::Ordering::SeqCst)
+}
+
+/// Forget the header sort, e.g. after the servers were ordered another way
+pub fn clear_sort() {
+    if let Ok(mut sort) = SORT.lock() {
+        *sort = None;
+    }
+}
+
+/// Create the list with its columns
+#[cfg(windows)]
+pub unsafe fn create(parent: HWND, hinstance: HINSTANCE, id: i32, rect: (i32, i32, i32, i32), hfont: HFONT) -> Option<HWND> {
+    // A new window starts unsorted and unfiltered
+    clear_sort();
+    set_filter("");
+    set_show_hidden(false);
+    if let Ok(mut groups) = GROUPS.lock() {
+        groups.clear();
+    }
+    unsafe {
+        let classes = INITCOMMONCONTROLSEX {
+            dwSize: std::mem::size_of::<INITCOMMONCONTROLSEX>() as u32,
+            dwICC: ICC_LISTVIEW_CLASSES,
+        };
+        let _ = InitCommonControlsEx(&classes);
+
+        let list = crate::ui::controls::create_control(parent, hinstance, WC_LISTVIEWW, "", WS_EX_CLIENTEDGE,
+            WS_TABSTOP | WINDOW_STYLE(LVS_REPORT | LVS_SHOWSELALWAYS), id, rect, hfont)?;
+        let extended = LVS_EX_CHECKBOXES | LVS_EX_FULLROWSELECT | LVS_EX_DOUBLEBUFFER;
+        SendMessageW(list, LVM_SETEXTENDEDLISTVIEWSTYLE, WPARAM(extended as usize), LPARAM(extended as isize));
+
+        for (index, (title, width)) in COLUMNS.iter().enumerate() {
+            let mut title: Vec<u16> = format!("{}\0", title).encode_utf16().collect();
+            let column = LVCOLUMNW {
+                mask: LVCF_TEXT | LVCF_WIDTH | LVCF_SUBITEM,
+                cx: *width,
+                pszText: PWSTR(title.as_mut_ptr()),
+                iSubItem: index as i32,
+                ..Default::default()
+            };
+            SendMessageW(list, LVM_INSERTCOLUMNW, WPARAM(index), LPARAM(&column as *const _ as isize));
+        }
+        Some(list)
+    }
+}
+
+// Set the text of one cell
+#[cfg(windows)]
+unsafe fn set_cell(list: HWND, row: i32, column: usize, text: &str) {
+    let mut text: Vec<u16> = format!("{}\0", text).encode_utf16().collect();
+    let item = LVITEMW {
+        iSubItem: column as i32,
+        pszText: PWSTR(text.as_mut_ptr()),
+        ..Default::default()
+    };
+    unsafe { SendMessageW(list, LVM_SETITEMTEXTW, WPARAM(row as usize), LPARAM(&item as *const _ as isize)); }
+}
+
+/// Server index (into VPN_SERVERS) of a row
+#[cfg(windows)]
+pub unsafe fn row_server(list: HWND, row: i32) -> Option<usize> {
+    let mut item = LVITEMW {
+        mask: LVIF_PARAM,
+        iItem: row,
+        ..Default::default()
+    };
+    let found = unsafe { SendMessageW(list, LVM_GETITEMW, WPARAM(0), LPARAM(&mut item as *mut _ as isize)) };
+    (row >= 0 && found.0 != 0).then_some(item.lParam.0 as usize)
+}
+
+/// Server index of the selected row
+#[cfg(windows)]
+pub unsafe fn selected_server(list: HWND) -> Option<usize> {
+    unsafe {
+        let row = SendMessageW(list, LVM_GETNEXTITEM, WPARAM(usize::MAX), LPARAM(LVNI_SELECTED as isize)).0 as i32;
+        row_server(list, row)
+    }
+}
+
+/// Server indexes of every selected row, top to bottom
+#[cfg(windows)]
+pub unsafe fn selected_servers(list: HWND) -> Vec<usize> {
+    let mut servers = Vec::new();
+    let mut row = -1;
+    loop {
+        row = unsafe { SendMessageW(list, LVM_GETNEXTITEM, WPARAM(row as isize as usize), LPARAM(LVNI_SELECTED as isize)).0 as i32 };
+        if row < 0 {
+            break;
+        }
+        servers.extend(unsafe { row_server(list, row) });
+    }
+    servers
+}
+
+/// Select every row the filter shows
+#[cfg(windows)]
+pub unsafe fn select_all(list: HWND) {
+    let state = LVITEMW {
+        state: LVIS_SELECTED,
+        stateMask: LVIS_SELECTED,
+        ..Default::default()
+    };
+    // Item -1 = all items
+    unsafe { SendMessageW(list, LVM_SETITEMSTATE, WPARAM(usize::MAX), LPARAM(&state as *const _ as isize)); }
+}
+
+/// Select just the rows of the given servers and scroll to the first; rows the filter hides stay hidden
+#[cfg(windows)]
+pub unsafe fn select_servers(list: HWND, indexes: &[usize]) {
+    unsafe {
+        let clear = LVITEMW { state: LIST_VIEW_ITEM_STATE_FLAGS(0), stateMask: LVIS_SELECTED, ..Default::default() };
+        SendMessageW(list, LVM_SETITEMSTATE, WPARAM(usize::MAX), LPARAM(&clear as *const _ as isize));
+        let rows = SendMessageW(list, LVM_GETITEMCOUNT, WPARAM(0), LPARAM(0)).0 as i32;
+        let mut first = None;
+        for row in 0..rows {
+            if row_server(list, row).is_some_and(|index| indexes.contains(&index)) {
+                let select = LVITEMW { state: LVIS_SELECTED, stateMask: LVIS_SELECTED, ..Default::default() };
+                SendMessageW(list, LVM_SETITEMSTATE, WPARAM(row as usize), LPARAM(&select as *const _ as isize));
+                first.get_or_insert(row);
+            }
+        }
+        if let Some(row) = first {
+            SendMessageW(list, LVM_ENSUREVISIBLE, WPARAM(row as usize), LPARAM(0));
+        }
+    }
+}
+
+#[cfg(windows)]
+fn check_state(checked: bool) -> LIST_VIEW_ITEM_STATE_FLAGS {
+    // State image 1 = unchecked, 2 = checked
+    LIST_VIEW_ITEM_STATE_FLAGS(if checked { 2 << 12 } else { 1 << 12 })
+}
+
+// Note which of the current groups are collapsed, before they are replaced
+#[cfg(windows)]
+unsafe fn remember_collapsed(list: HWND) {
+    let shown = GROUPS.lock().map(|groups| groups.clone()).unwrap_or_default();
+    let Ok(mut collapsed) = COLLAPSED.lock() else {
+        return;
+    };
+    for (id, heading) in shown.iter().enumerate() {
+        let state = unsafe {
+            SendMessageW(list, LVM_GETGROUPSTATE, WPARAM(id), LPARAM(LVGS_COLLAPSED.0 as isize)).0 as u32
+        };
+        collapsed.retain(|existing| existing != heading);
+        if state & LVGS_COLLAPSED.0 != 0 {
+            collapsed.push(heading.clone());
+        }
+    }
+}
+
+// Add a collapsible group headed "Germany (DE) - 3 servers"
+#[cfg(windows)]
+unsafe fn insert_group(list: HWND, id: usize, heading: &str, count: usize, collapsed: bool) {
+    let count = if count == 1 { "1 server".to_string() } else { format!("{} servers", count) };
+    let mut header: Vec<u16> = format!("{} - {}\0", heading, count).encode_utf16().collect();
+    let state = if collapsed { LVGS_COLLAPSIBLE.0 | LVGS_COLLAPSED.0 } else { LVGS_COLLAPSIBLE.0 };
+    let group = LVGROUP {
+        cbSize: std::mem::size_of::<LVGROUP>() as u32,
+        mask: LVGF_HEADER | LVGF_GROUPID | LVGF_STATE,
+        pszHeader: PWSTR(header.as_mut_ptr()),
+        iGroupId: id as i32,
+        stateMask: LIST_VIEW_GROUP_STATE_FLAGS(LVGS_COLLAPSIBLE.0 | LVGS_COLLAPSED.0),
+        state: LIST_VIEW_GROUP_STATE_FLAGS(state),
+        ..Default::default()
+    };
+    unsafe { SendMessageW(list, LVM_INSERTGROUP, WPARAM(usize::MAX), LPARAM(&group as *const _ as isize)); }
+}
+
+/// Replace all rows with the `servers` matching the filter (and not hidden, unless hidden
+/// ones are shown), in their order and grouped by the current grouping
+#[cfg(windows)]
+pub unsafe fn rebuild(list: HWND, servers: &[VpnServer]) {
+    use std::sync::atomic::Ordering;
+
+    let filter = FILTER.lock().map(|filter| filter.clone()).unwrap_or_default();
+    let group_by = GROUP_BY.lock().map(|group_by| group_by.clone()).unwrap_or_default();
+    // Favorites first, otherwise in the list's order
+    let show_hidden = showing_hidden();
+    let mut visible: Vec<(usize, &VpnServer)> = servers.iter().enumerate()
+        .filter(|(_, server)| (show_hidden || !server.hidden) && matches_filter(server, &filter))
+        .collect();
+    visible.sort_by_key(|(_, server)| !server.pinned);
+    // Ungrouped, every row goes in one group that isn't shown
+    let groups = if group_by.is_empty() {
+        vec![(String::new(), visible.iter().map(|(index, _)| *index).collect())]
+    } else {
+        crate::vpn::group_servers(visible, &group_by)
+    };
+
+    FILLING.store(true, Ordering::SeqCst);
+    unsafe {
+        SendMessageW(list, WM_SETREDRAW, WPARAM(0), LPARAM(0));
+        remember_collapsed(list);
+        SendMessageW(list, LVM_DELETEALLITEMS, WPARAM(0), LPARAM(0));
+        SendMessageW(list, LVM_REMOVEALLGROUPS, WPARAM(0), LPARAM(0));
+        SendMessageW(list, LVM_ENABLEGROUPVIEW, WPARAM(!group_by.is_empty() as usize), LPARAM(0));
+        let collapsed = COLLAPSED.lock().map(|collapsed| collapsed.clone()).unwrap_or_default();
+        if !group_by.is_empty() {
+            for (id, (heading, members)) in groups.iter().enumerate() {
+                insert_group(list, id, heading, members.len(), collapsed.contains(heading));
+            }
+        }
+        if let Ok(mut shown) = GROUPS.lock() {
+            *shown = if group_by.is_empty() { Vec::new() } else { groups.iter().map(|(heading, _)| heading.clone()).collect() };
+        }
+
+        let rows = groups.iter().enumerate()
+            .flat_map(|(id, (_, members))| members.iter().map(move |&index| (id, index)));
+        for (group_id, index) in rows {
+            let server = &servers[index];
+            let mut name: Vec<u16> = format!("{}\0", cell_text(server, COLUMN_SERVER)).encode_utf16().collect();
+            let item = LVITEMW {
+                mask: LVIF_TEXT | LVIF_PARAM | if group_by.is_empty() { LIST_VIEW_ITEM_FLAGS(0) } else { LVIF_GROUPID },
+                iItem: i32::MAX, // Appended
+                pszText: PWSTR(name.as_mut_ptr()),
+                lParam: LPARAM(index as isize),
+                iGroupId: group_id as i32,
+                ..Default::default()
+            };
+            let row = SendMessageW(list, LVM_INSERTITEMW, WPARAM(0), LPARAM(&item as *const _ as isize)).0 as i32;
+            if row < 0 {
+                continue;
+            }
+            for column in 1..COLUMNS.len() {
+                set_cell(list, row, column, &cell_text(server, column));
+            }
+            let state = LVITEMW {
+                state: check_state(server.enabled),
+                stateMask: LVIS_STATEIMAGEMASK,
+                ..Default::default()
+            };
+            SendMessageW(list, LVM_SETITEMSTATE, WPARAM(row as usize), LPARAM(&state as *const _ as isize));
+        }
+        SendMessageW(list, WM_SETREDRAW, WPARAM(1), LPARAM(0));
+        show_sort_arrow(list);
+    }
+    FILLING.store(false, Ordering::SeqCst);
+}
+
+/// Refresh every row's text and check box from `servers`, e.g. as test results come in
+/// or after a bulk edit; rows and selection stay as they are
+#[cfg(windows)]
+pub unsafe fn refresh(list: HWND, servers: &[VpnServer]) {
+    use std::sync::atomic::Ordering;
+
+    FILLING.store(true, Ordering::SeqCst);
+    unsafe {
+        let rows = SendMessageW(list, LVM_GETITEMCOUNT, WPARAM(0), LPARAM(0)).0 as i32;
+        for row in 0..rows {
+            let Some(server) = row_server(list, row).and_then(|index| servers.get(index)) else {
+                continue;
+            };
+            for column in 0..COLUMNS.len() {
+                set_cell(list, row, column, &cell_text(server, column));
+            }
+            let state = LVITEMW {
+                state: check_state(server.enabled),
+                stateMask: LVIS_STATEIMAGEMASK,
+                ..Default::default()
+            };
+            SendMessageW(list, LVM_SETITEMSTATE, WPARAM(row as usize), LPARAM(&state as *const _ as isize));
+        }
+    }
+    FILLING.store(false, Ordering::SeqCst);
+}
+
+// Arrow in the header of the sorted column
+#[cfg(windows)]
+unsafe fn show_sort_arrow(list: HWND) {
+    let sort = SORT.lock().ok().and_then(|sort| *sort);
+    unsafe {
+        let header = HWND(SendMessageW(list, LVM_GETHEADER, WPARAM(0), LPARAM(0)).0 as *mut _);
+        for column in 0..COLUMNS.len() {
+            let mut item = HDITEMW { mask: HDI_FORMAT, ..Default::default() };
+            SendMessageW(header, HDM_GETITEMW, WPARAM(column), LPARAM(&mut item as *mut _ as isize));
+            item.fmt = HEADER_CONTROL_FORMAT_FLAGS(item.fmt.0 & !(HDF_SORTUP.0 | HDF_SORTDOWN.0));
+            match sort {
+                Some((sorted, true)) if sorted == column => item.fmt = HEADER_CONTROL_FORMAT_FLAGS(item.fmt.0 | HDF_SORTUP.0),
+                Some((sorted, false)) if sorted == column => item.fmt = HEADER_CONTROL_FORMAT_FLAGS(item.fmt.0 | HDF_SORTDOWN.0),
+                _ => {}
+            }
+            SendMessageW(header, HDM_SETITEMW, WPARAM(column), LPARAM(&item as *const _ as isize));
+        }
+    }
+}
+
+// Sort by a clicked column; a second click on it reverses the order
+#[cfg(windows)]
+unsafe fn sort_by_column(list: HWND, column: usize) {
+    if let Ok(mut sort) = SORT.lock() {
+        let ascending = !matches!(*sort, Some((sorted, true)) if sorted == column);
+        *sort = Some((column, ascending));
+    }
+    if let Ok(mut global_servers) = VPN_SERVERS.lock() {
+        if let Some(servers) = global_servers.as_mut() {
+            apply_sort(servers);
+            unsafe { rebuild(list, servers) };
+        }
+    }
+}
+
+/// Handle a WM_NOTIFY from the list: check boxes and header clicks are handled here.
+/// Returns the server to edit when a row was double-clicked or Enter pressed on it.
+#[cfg(windows)]
+pub unsafe fn on_notify(lparam: LPARAM) -> Option<usize> {
+    use std::sync::atomic::Ordering;
+
+    let header = unsafe { &*(lparam.0 as *const NMHDR) };
+    let list = header.hwndFrom;
+    match header.code {
+        LVN_ITEMCHANGED => {
+            let change = unsafe { &*(lparam.0 as *const NMLISTVIEW) };
+            let check_changed = (change.uNewState ^ change.uOldState) & LVIS_STATEIMAGEMASK.0 != 0;
+            if FILLING.load(Ordering::SeqCst) || change.uChanged.0 & LVIF_STATE.0 == 0 || !check_changed {
+                return None;
+            }
+            let checked = change.uNewState & LVIS_STATEIMAGEMASK.0 == check_state(true).0;
+            let index = change.lParam.0 as usize;
+            if let Ok(mut global_servers) = VPN_SERVERS.lock() {
+                if let Some(server) = global_servers.as_mut().and_then(|servers| servers.get_mut(index)) {
+                    server.enabled = checked;
+                }
+            }
+            None
+        }
+        LVN_COLUMNCLICK => {
+            let click = unsafe { &*(lparam.0 as *const NMLISTVIEW) };
+            unsafe { sort_by_column(list, click.iSubItem as usize) };
+            None
+        }
+        NM_DBLCLK => {
+            let activate = unsafe { &*(lparam.0 as *const NMITEMACTIVATE) };
+            unsafe { row_server(list, activate.iItem) }
+        }
+        NM_RETURN => unsafe { selected_server(list) },
+        _ => None,
+    }
+}
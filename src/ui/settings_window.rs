@@ -2,49 +2,86 @@
 use windows::{
     core::{PCWSTR, w},
     Win32::{
-        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE, RECT, BOOL},
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE, RECT},
         Graphics::Gdi::{UpdateWindow, HBRUSH, SetBkMode, TRANSPARENT, HDC, GetStockObject, WHITE_BRUSH, InvalidateRect},
         System::LibraryLoader::GetModuleHandleW,
         UI::WindowsAndMessaging::*,
     },
 };
 
-use crate::vpn::{VpnServer, VPN_SERVERS, fetch_and_process_vpn_list, assign_local_ports};
+use crate::vpn::{VpnServer, VPN_SERVERS};
 
 // Custom Windows message for updating server list
 const WM_UPDATE_SERVERS: u32 = WM_USER + 1;
 
 // Control ID ranges
-const ID_URL_EDIT: i32 = 1001;
+const ID_SUBSCRIPTIONS_EDIT: i32 = 1001; // Read-only summary of configured subscriptions
 const ID_UPDATE_BUTTON: i32 = 1002;
-const ID_SCROLL_CONTAINER: i32 = 1003;
-const ID_SCROLL_CONTAINER_CLASS: i32 = 1004; // Custom class for container
+const ID_SERVER_LIST: i32 = 1003;
 const ID_SAVE_BUTTON: i32 = 1005;
 const ID_CANCEL_BUTTON: i32 = 1006;
 const ID_XRAY_PATH_EDIT: i32 = 1007;
 const ID_XRAY_BROWSE_BUTTON: i32 = 1008;
 const ID_XRAY_DOWNLOAD_BUTTON: i32 = 1009;
 const ID_AUTOSTART_CHECKBOX: i32 = 1010;
-const ID_SERVER_CHECKBOX_BASE: i32 = 2000;  // 2000, 2001, 2002...
-const ID_SERVER_PORT_EDIT_BASE: i32 = 3000; // 3000, 3001, 3002...
-const ID_SERVER_PROXY_COMBO_BASE: i32 = 4000; // 4000, 4001, 4002...
-const ID_SERVER_LABEL_BASE: i32 = 5000; // 5000, 5001, 5002... for "Proxy Port:" labels
+const ID_SUBSCRIPTIONS_BUTTON: i32 = 1011;
+const ID_TEST_ALL_BUTTON: i32 = 1012;
+const ID_SORT_RELIABILITY_BUTTON: i32 = 1013;
+const ID_START_MINIMIZED_CHECKBOX: i32 = 1014;
+const ID_SERVER_FILTER_EDIT: i32 = 1015;
+const ID_TEST_SERVER_BUTTON: i32 = 1016;
+
+// Menu bar command IDs
+const ID_MENU_API_ACCESS: i32 = 1101;
+const ID_MENU_HOOKS: i32 = 1102;
+const ID_MENU_OPTIONS: i32 = 1103;
+const ID_MENU_EXPORT: i32 = 1104;
+const ID_MENU_PRIMARY: i32 = 1105;
+const ID_MENU_LIMITS: i32 = 1106;
+const ID_MENU_SHARE: i32 = 1107;
+const ID_MENU_ROUTING: i32 = 1108;
+const ID_MENU_MANUAL_SERVERS: i32 = 1109;
+const ID_MENU_RULESETS: i32 = 1110;
+const ID_MENU_FAILOVER: i32 = 1111;
+const ID_MENU_LOGS: i32 = 1112;
+const ID_MENU_BALANCER: i32 = 1113;
+const ID_MENU_PREVIEW: i32 = 1114;
+const ID_MENU_TUN: i32 = 1115;
+const ID_MENU_SYSTEM_PROXY: i32 = 1116;
+const ID_MENU_TRAY_ICON: i32 = 1117;
+const ID_MENU_ROUTING_RULES: i32 = 1118;
+const ID_MENU_GEO_ASSETS: i32 = 1119;
+const ID_MENU_COMPARE_RESULTS: i32 = 1120;
+const ID_MENU_DNS: i32 = 1121;
+const ID_MENU_VIEW_LOGS: i32 = 1122;
+const ID_MENU_EXIT_IP: i32 = 1123;
+const ID_MENU_SCHEDULED_TESTS: i32 = 1124;
+const ID_MENU_STATUS_PAGE: i32 = 1125;
+const ID_MENU_GROUP_NONE: i32 = 1126;
+const ID_MENU_GROUP_SUBSCRIPTION: i32 = 1127;
+const ID_MENU_GROUP_COUNTRY: i32 = 1128;
+const ID_MENU_SHOW_HIDDEN: i32 = 1129;
+const ID_MENU_LAN_SHARING: i32 = 1130;
+const ID_MENU_MUX: i32 = 1131;
+const ID_MENU_CLEANUP: i32 = 1132;
 
 // Custom Windows message for download completion
 const WM_DOWNLOAD_COMPLETE: u32 = WM_USER + 2;
+const WM_LATENCY_RESULT: u32 = WM_USER + 3; // WPARAM 1 = all tests finished
+const WM_PREVIEW_RESULT: u32 = WM_USER + 4; // LPARAM = Box<String> with the plan or error
+const WM_GEO_ASSETS_RESULT: u32 = WM_USER + 5; // LPARAM = Box<String> with the outcome
+const WM_DRY_RUN_RESULT: u32 = WM_USER + 6; // LPARAM = Box<String> with the outcome
+const WM_SPEED_RESULT: u32 = WM_USER + 7; // LPARAM = Box<String> with the outcome
 
 // Layout constants for consistent formatting
 const MARGIN: i32 = 15;
 const FONT_SIZE: i32 = 32; // Reduced from 40
 const LABEL_HEIGHT: i32 = 45; // Reduced from 50
 const CONTROL_HEIGHT: i32 = 45; // Reduced from 50
-const ROW_HEIGHT: i32 = 55; // Reduced from 60
 const URL_LABEL_WIDTH: i32 = 200;
 
-// Windows notification codes
+// Edit control notification: text changed
 const EN_CHANGE: usize = 0x0300;
-const CBN_SELCHANGE: usize = 1;
-const BN_CLICKED: usize = 0;
 
 #[cfg(windows)]
 pub unsafe fn create_settings_window() -> HWND {
@@ -68,26 +105,14 @@ pub unsafe fn create_settings_window() -> HWND {
     
     unsafe { RegisterClassW(&wc) };
     
-    // Register custom class for scroll container with white background
-    let container_class_str: Vec<u16> = "ScrollContainerClass\0".encode_utf16().collect();
-    let container_class = PCWSTR::from_raw(container_class_str.as_ptr());
-    
-    let wc_container = WNDCLASSW {
-        lpfnWndProc: Some(container_window_proc),
-        hInstance: hinstance.into(),
-        lpszClassName: container_class,
-        hbrBackground: unsafe { HBRUSH(GetStockObject(WHITE_BRUSH).0) },
-        style: CS_HREDRAW | CS_VREDRAW,
-        ..Default::default()
-    };
-    
-    unsafe { RegisterClassW(&wc_container) };
-    
     let window_title_str: Vec<u16> = "Settings\0"
         .encode_utf16()
         .collect();
     let window_title = PCWSTR::from_raw(window_title_str.as_ptr());
     
+    // Menu bar with less frequently used tools
+    let menu_bar = unsafe { create_menu_bar() };
+    
     // Create main window
     let hwnd = unsafe {
         CreateWindowExW(
@@ -100,7 +125,7 @@ pub unsafe fn create_settings_window() -> HWND {
             900,
             1200, // Increased from 1050 to 1200 (+15%)
             None,
-            None,
+            menu_bar,
             hinstance,
             None,
         ).expect("Failed to create window")
@@ -119,115 +144,391 @@ pub unsafe fn create_settings_window() -> HWND {
     hwnd
 }
 
-// Window procedure for scroll container to handle background and scrolling
+/// Whether a settings window is currently open (safe to call from any thread)
+pub fn is_settings_window_open() -> bool {
+    #[cfg(windows)]
+    unsafe {
+        FindWindowW(w!("SettingsWindowClass"), None).is_ok_and(|hwnd| !hwnd.is_invalid())
+    }
+    #[cfg(not(windows))]
+    false
+}
+
+// Build the settings window menu bar
 #[cfg(windows)]
-unsafe extern "system" fn container_window_proc(
-    hwnd: HWND,
-    msg: u32,
-    wparam: WPARAM,
-    lparam: LPARAM,
-) -> LRESULT {
-    match msg {
-        WM_COMMAND => {
-            // Forward WM_COMMAND to parent window
-            unsafe {
-                if let Ok(parent) = GetParent(hwnd) {
-                    return SendMessageW(parent, msg, wparam, lparam);
+unsafe fn create_menu_bar() -> HMENU {
+    unsafe {
+        let menu_bar = CreateMenu().unwrap_or_default();
+        if let Ok(tools_menu) = CreatePopupMenu() {
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_MANUAL_SERVERS as usize, w!("Add Server..."));
+            let _ = AppendMenuW(tools_menu, MF_SEPARATOR, 0, None);
+            #[cfg(feature = "api")]
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_API_ACCESS as usize, w!("API Access..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_HOOKS as usize, w!("Connect/Disconnect Hooks..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_PRIMARY as usize, w!("Primary Port && Rotation..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_FAILOVER as usize, w!("Failover..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_SYSTEM_PROXY as usize, w!("System Proxy..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_BALANCER as usize, w!("Balanced Groups..."));
+            #[cfg(feature = "tun")]
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_TUN as usize, w!("TUN Mode..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_LIMITS as usize, w!("Server Limits..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_MUX as usize, w!("Multiplexing..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_ROUTING_RULES as usize, w!("Routing Rules..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_ROUTING as usize, w!("Routing Presets..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_RULESETS as usize, w!("Rule Sets..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_GEO_ASSETS as usize, w!("Geo Assets..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_DNS as usize, w!("DNS..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_EXIT_IP as usize, w!("Exit IP Alerts..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_EXPORT as usize, w!("Export Server List..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_SHARE as usize, w!("Share Servers..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_STATUS_PAGE as usize, w!("Status Page..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_LAN_SHARING as usize, w!("LAN Sharing..."));
+            let _ = AppendMenuW(tools_menu, MF_SEPARATOR, 0, None);
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_PREVIEW as usize, w!("Preview Refresh..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_SCHEDULED_TESTS as usize, w!("Scheduled Tests..."));
+            #[cfg(feature = "stats")]
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_COMPARE_RESULTS as usize, w!("Compare Test Results..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_VIEW_LOGS as usize, w!("View Logs..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_LOGS as usize, w!("Log Files..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_CLEANUP as usize, w!("Clean Up Server Settings..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_TRAY_ICON as usize, w!("Tray Icon..."));
+            let _ = AppendMenuW(tools_menu, MF_STRING, ID_MENU_OPTIONS as usize, w!("Options..."));
+            let _ = AppendMenuW(menu_bar, MF_POPUP, tools_menu.0 as usize, w!("Tools"));
+        }
+        if let Ok(view_menu) = CreatePopupMenu() {
+            let _ = AppendMenuW(view_menu, MF_STRING, ID_MENU_GROUP_NONE as usize, w!("No Grouping"));
+            let _ = AppendMenuW(view_menu, MF_STRING, ID_MENU_GROUP_SUBSCRIPTION as usize, w!("Group by Subscription"));
+            let _ = AppendMenuW(view_menu, MF_STRING, ID_MENU_GROUP_COUNTRY as usize, w!("Group by Country"));
+            let group_by = crate::config::Config::load().unwrap_or_default().group_by;
+            let _ = CheckMenuRadioItem(view_menu, ID_MENU_GROUP_NONE as u32, ID_MENU_GROUP_COUNTRY as u32,
+                grouping_menu_id(&group_by) as u32, MF_BYCOMMAND.0);
+            let _ = AppendMenuW(view_menu, MF_SEPARATOR, 0, None);
+            let _ = AppendMenuW(view_menu, MF_STRING, ID_MENU_SHOW_HIDDEN as usize, w!("Show Hidden Servers"));
+            let _ = AppendMenuW(menu_bar, MF_POPUP, view_menu.0 as usize, w!("View"));
+        }
+        menu_bar
+    }
+}
+
+// View menu item of a grouping
+#[cfg(windows)]
+fn grouping_menu_id(group_by: &str) -> i32 {
+    match group_by {
+        "subscription" => ID_MENU_GROUP_SUBSCRIPTION,
+        "country" => ID_MENU_GROUP_COUNTRY,
+        _ => ID_MENU_GROUP_NONE,
+    }
+}
+
+// Group the server list and tray menu, and remember it
+#[cfg(windows)]
+unsafe fn set_grouping(hwnd: HWND, group_by: &str) {
+    let mut config = crate::config::Config::load().unwrap_or_default();
+    config.group_by = group_by.to_string();
+    if let Err(e) = config.save() {
+        eprintln!("Failed to save grouping: {}", e);
+    }
+    crate::ui::server_list::set_grouping(group_by);
+    unsafe {
+        let menu = GetMenu(hwnd);
+        let _ = CheckMenuRadioItem(menu, ID_MENU_GROUP_NONE as u32, ID_MENU_GROUP_COUNTRY as u32,
+            grouping_menu_id(group_by) as u32, MF_BYCOMMAND.0);
+        if let Ok(global_servers) = VPN_SERVERS.lock() {
+            if let Some(servers) = global_servers.as_ref() {
+                rebuild_server_list(hwnd, servers);
+            }
+        }
+    }
+    crate::request_menu_update();
+}
+
+// Screen position for a context menu; keyboard-invoked menus (Shift+F10) have no coordinates
+#[cfg(windows)]
+unsafe fn menu_position(lparam: LPARAM) -> (i32, i32) {
+    if lparam.0 == -1 {
+        let mut cursor = windows::Win32::Foundation::POINT::default();
+        let _ = unsafe { GetCursorPos(&mut cursor) };
+        return (cursor.x, cursor.y);
+    }
+    ((lparam.0 & 0xFFFF) as i16 as i32, ((lparam.0 >> 16) & 0xFFFF) as i16 as i32)
+}
+
+// Change several selected servers at once; applied on Save like single edits
+#[cfg(windows)]
+unsafe fn show_bulk_menu(hwnd: HWND, server_indexes: Vec<usize>, lparam: LPARAM) {
+    const MENU_ENABLE: usize = 1;
+    const MENU_DISABLE: usize = 2;
+    const MENU_SOCKS: usize = 3;
+    const MENU_HTTP: usize = 4;
+    const MENU_PORTS: usize = 5;
+    const MENU_SELECT_ALL: usize = 6;
+
+    unsafe {
+        let Ok(menu) = CreatePopupMenu() else {
+            return;
+        };
+        let count = server_indexes.len();
+        let item = |text: String| -> Vec<u16> { format!("{}\0", text).encode_utf16().collect() };
+        let enable = item(format!("Enable {} Servers", count));
+        let disable = item(format!("Disable {} Servers", count));
+        let _ = AppendMenuW(menu, MF_STRING, MENU_ENABLE, PCWSTR::from_raw(enable.as_ptr()));
+        let _ = AppendMenuW(menu, MF_STRING, MENU_DISABLE, PCWSTR::from_raw(disable.as_ptr()));
+        let _ = AppendMenuW(menu, MF_SEPARATOR, 0, None);
+        let _ = AppendMenuW(menu, MF_STRING, MENU_SOCKS, w!("Set Proxy Type to SOCKS"));
+        let _ = AppendMenuW(menu, MF_STRING, MENU_HTTP, w!("Set Proxy Type to HTTP"));
+        let _ = AppendMenuW(menu, MF_STRING, MENU_PORTS, w!("Assign Sequential Ports..."));
+        let _ = AppendMenuW(menu, MF_SEPARATOR, 0, None);
+        let _ = AppendMenuW(menu, MF_STRING, MENU_SELECT_ALL, w!("Select All Shown"));
+
+        let (x, y) = menu_position(lparam);
+        let choice = TrackPopupMenu(menu, TPM_RETURNCMD | TPM_RIGHTBUTTON, x, y, 0, hwnd, None).0 as usize;
+        let _ = DestroyMenu(menu);
+
+        match choice {
+            MENU_ENABLE | MENU_DISABLE => {
+                bulk_update(hwnd, &server_indexes, |server| server.enabled = choice == MENU_ENABLE);
+            }
+            MENU_SOCKS => bulk_update(hwnd, &server_indexes, |server| server.proxy_type = "SOCKS".to_string()),
+            MENU_HTTP => {
+                // One question for all the servers HTTP doesn't suit
+                let warned: Vec<String> = VPN_SERVERS.lock().ok()
+                    .and_then(|servers| servers.as_ref().map(|list| {
+                        server_indexes.iter()
+                            .filter_map(|&index| list.get(index))
+                            .filter(|server| server.proxy_type_warning("HTTP").is_some())
+                            .map(|server| server.display_name().to_string())
+                            .collect()
+                    }))
+                    .unwrap_or_default();
+                if !warned.is_empty() {
+                    let mut names: Vec<String> = warned.iter().take(5).cloned().collect();
+                    if warned.len() > 5 {
+                        names.push(format!("and {} more", warned.len() - 5));
+                    }
+                    let text = format!(
+                        "{} of these servers lose UDP through an HTTP proxy:\n\n{}\n\nSet them to HTTP anyway?",
+                        warned.len(), names.join("\n")
+                    );
+                    if crate::ui::controls::message_box(hwnd, &text, "Proxy Type Warning", MB_OKCANCEL | MB_ICONWARNING) != IDOK {
+                        return;
+                    }
                 }
+                bulk_update(hwnd, &server_indexes, |server| server.proxy_type = "HTTP".to_string());
             }
-            LRESULT(0)
+            MENU_PORTS => show_bulk_ports_form(hwnd, server_indexes),
+            MENU_SELECT_ALL => {
+                if let Ok(list) = GetDlgItem(hwnd, ID_SERVER_LIST) {
+                    crate::ui::server_list::select_all(list);
+                }
+            }
+            _ => {}
         }
-        WM_CTLCOLORSTATIC => {
-            unsafe {
-                let hdc = HDC(wparam.0 as *mut _);
-                SetBkMode(hdc, TRANSPARENT);
-                LRESULT(GetStockObject(WHITE_BRUSH).0 as isize)
+    }
+}
+
+// Apply one change to each of the servers and show it in the list
+#[cfg(windows)]
+unsafe fn bulk_update(hwnd: HWND, server_indexes: &[usize], change: impl Fn(&mut VpnServer)) {
+    if let Ok(mut global_servers) = VPN_SERVERS.lock() {
+        if let Some(servers) = global_servers.as_mut() {
+            for &index in server_indexes {
+                if let Some(server) = servers.get_mut(index) {
+                    change(server);
+                }
+            }
+            if let Ok(list) = unsafe { GetDlgItem(hwnd, ID_SERVER_LIST) } {
+                unsafe { crate::ui::server_list::refresh(list, servers) };
             }
         }
-        WM_VSCROLL => {
-            // Handle vertical scrolling
-            let action = wparam.0 & 0xFFFF;
-            
-            unsafe {
-                let mut si = SCROLLINFO {
-                    cbSize: std::mem::size_of::<SCROLLINFO>() as u32,
-                    fMask: SIF_ALL,
-                    nMin: 0,
-                    nMax: 0,
-                    nPage: 0,
-                    nPos: 0,
-                    nTrackPos: 0,
-                };
-                
-                use windows::Win32::UI::WindowsAndMessaging::GetScrollInfo;
-                let _ = GetScrollInfo(hwnd, SB_VERT, &mut si);
-                
-                let old_pos = si.nPos;
-                
-                match action {
-                    0 => si.nPos -= 20, // SB_LINEUP
-                    1 => si.nPos += 20, // SB_LINEDOWN
-                    2 => si.nPos -= si.nPage as i32, // SB_PAGEUP
-                    3 => si.nPos += si.nPage as i32, // SB_PAGEDOWN
-                    5 => si.nPos = si.nTrackPos, // SB_THUMBTRACK
-                    _ => {}
+    }
+}
+
+// Give the servers consecutive local ports from a first port, top to bottom, skipping
+// ports other listed servers hold
+#[cfg(windows)]
+unsafe fn show_bulk_ports_form(hwnd: HWND, server_indexes: Vec<usize>) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let first = VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.as_ref().and_then(|list| {
+            server_indexes.iter().filter_map(|&index| list.get(index)).map(|server| server.local_port).filter(|&port| port != 0).min()
+        }))
+        .unwrap_or(crate::config::DEFAULT_FIRST_LOCAL_PORT);
+    let title = format!("Ports for {} Servers", server_indexes.len());
+    let fields = vec![FormField::text("First local port:", &first.to_string())];
+    
+    unsafe {
+        show_form(hwnd, &title, fields, Box::new(move |values| {
+            let first: u16 = values[0].trim().parse().ok().filter(|port| *port > 0)
+                .ok_or_else(|| "Port must be a number from 1 to 65535".to_string())?;
+            let mut global_servers = VPN_SERVERS.lock().map_err(|e| e.to_string())?;
+            let servers = global_servers.as_mut().ok_or("No servers loaded")?;
+            let taken: std::collections::HashSet<u16> = servers.iter().enumerate()
+                .filter(|(index, _)| !server_indexes.contains(index))
+                .map(|(_, server)| server.local_port)
+                .collect();
+            let mut ports = Vec::new();
+            let mut next = first as u32;
+            while ports.len() < server_indexes.len() {
+                if next > u16::MAX as u32 {
+                    return Err(format!("Not enough free ports from {} up to 65535", first));
                 }
-                
-                // Clamp position
-                si.nPos = si.nPos.max(si.nMin);
-                si.nPos = si.nPos.min(si.nMax - si.nPage as i32 + 1);
-                
-                if si.nPos != old_pos {
-                    use windows::Win32::UI::Controls::SetScrollInfo;
-                    si.fMask = SIF_POS;
-                    SetScrollInfo(hwnd, SB_VERT, &si, true);
-                    
-                    // Move child windows based on scroll position
-                    let scroll_delta = old_pos - si.nPos;
-                    
-                    // Enumerate and move all child windows
-                    unsafe extern "system" fn move_child(child: HWND, lparam: LPARAM) -> BOOL {
-                        let delta = lparam.0 as i32;
-                        let mut rect = RECT::default();
-                        unsafe {
-                            if GetWindowRect(child, &mut rect).is_ok() {
-                                let parent = GetParent(child).unwrap_or(HWND::default());
-                                let mut pt = windows::Win32::Foundation::POINT { x: rect.left, y: rect.top };
-                                use windows::Win32::Graphics::Gdi::ScreenToClient;
-                                let _ = ScreenToClient(parent, &mut pt);
-                                
-                                let _ = SetWindowPos(
-                                    child,
-                                    None,
-                                    pt.x,
-                                    pt.y + delta,
-                                    0, 0,
-                                    SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
-                                );
-                            }
-                        }
-                        true.into()
-                    }
-                    
-                    let _ = EnumChildWindows(hwnd, Some(move_child), LPARAM(scroll_delta as isize));
+                if !taken.contains(&(next as u16)) {
+                    ports.push(next as u16);
                 }
+                next += 1;
             }
-            LRESULT(0)
+            for (&index, port) in server_indexes.iter().zip(ports) {
+                if let Some(server) = servers.get_mut(index) {
+                    server.local_port = port;
+                    server.auto_port = false;
+                }
+            }
+            if let Ok(list) = GetDlgItem(hwnd, ID_SERVER_LIST) {
+                crate::ui::server_list::refresh(list, servers);
+            }
+            Ok(())
+        }));
+    }
+}
+
+// Edit, pin or hide a server, view its generated config, copy its share link or show it as a QR code
+#[cfg(windows)]
+unsafe fn show_server_context_menu(hwnd: HWND, server_index: usize, lparam: LPARAM) {
+    const MENU_COPY_LINK: usize = 1;
+    const MENU_SHOW_QR: usize = 2;
+    const MENU_SYSTEM_PROXY: usize = 3;
+    const MENU_GUEST_SHARE: usize = 4;
+    const MENU_GUEST_STOP: usize = 5;
+    const MENU_EDIT: usize = 6;
+    const MENU_PIN: usize = 7;
+    const MENU_HIDE: usize = 8;
+    const MENU_SELECT_ALL: usize = 9;
+    const MENU_MUX: usize = 10;
+    const MENU_TLS: usize = 11;
+    const MENU_VIEW_CONFIG: usize = 12;
+    const MENU_SPEED_TEST: usize = 13;
+    const MENU_UPSTREAM: usize = 14;
+
+    let server = VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.as_ref().and_then(|list| list.get(server_index).cloned()));
+    let Some(server) = server.filter(|s| !s.uri.is_empty()) else {
+        return;
+    };
+
+    unsafe {
+        let Ok(menu) = CreatePopupMenu() else {
+            return;
+        };
+        let _ = AppendMenuW(menu, MF_STRING, MENU_EDIT, w!("Edit Server..."));
+        let _ = AppendMenuW(menu, MF_STRING, MENU_MUX, w!("Multiplexing..."));
+        let _ = AppendMenuW(menu, MF_STRING, MENU_TLS, w!("TLS Fingerprint && Fragment..."));
+        let _ = AppendMenuW(menu, MF_STRING, MENU_UPSTREAM, w!("Upstream Server..."));
+        let _ = AppendMenuW(menu, MF_STRING, MENU_VIEW_CONFIG, w!("View Config..."));
+        let pinned = if server.pinned { MF_CHECKED } else { MF_UNCHECKED };
+        let _ = AppendMenuW(menu, MF_STRING | pinned, MENU_PIN, w!("Pin to Favorites"));
+        let hidden = if server.hidden { MF_CHECKED } else { MF_UNCHECKED };
+        let _ = AppendMenuW(menu, MF_STRING | hidden, MENU_HIDE, w!("Hide Server"));
+        let _ = AppendMenuW(menu, MF_SEPARATOR, 0, None);
+        let running = crate::xray_manager::get_server_info(&server.get_server_key()).is_some();
+        let flags = if running { MF_STRING } else { MF_STRING | MF_GRAYED };
+        let _ = AppendMenuW(menu, flags, MENU_SPEED_TEST, w!("Speed Test"));
+        let _ = AppendMenuW(menu, MF_STRING, MENU_COPY_LINK, w!("Copy Share Link"));
+        let _ = AppendMenuW(menu, MF_STRING, MENU_SHOW_QR, w!("Show QR Code..."));
+        let _ = AppendMenuW(menu, MF_SEPARATOR, 0, None);
+        let system_proxy = crate::config::Config::load().unwrap_or_default()
+            .server_settings.get(&server.get_server_key())
+            .is_some_and(|settings| settings.system_proxy);
+        let check = if system_proxy { MF_CHECKED } else { MF_UNCHECKED };
+        let _ = AppendMenuW(menu, MF_STRING | check, MENU_SYSTEM_PROXY, w!("Set as System Proxy When Started"));
+        let _ = AppendMenuW(menu, MF_SEPARATOR, 0, None);
+        let guest = crate::guest::active();
+        if guest.as_ref().is_some_and(|g| g.server_key == server.get_server_key()) {
+            let _ = AppendMenuW(menu, MF_STRING, MENU_GUEST_STOP, w!("Stop Guest Sharing"));
+        } else {
+            let _ = AppendMenuW(menu, flags, MENU_GUEST_SHARE, w!("Share with a Guest for 1 Hour..."));
         }
-        WM_MOUSEWHEEL => {
-            // Handle mouse wheel scrolling
-            let delta = ((wparam.0 >> 16) & 0xFFFF) as i16;
-            let scroll_lines = if delta > 0 { 0 } else { 1 }; // 0=SB_LINEUP, 1=SB_LINEDOWN
-            
-            // Send scroll message multiple times for smoother scrolling
-            for _ in 0..(delta.abs() / 40).max(1) {
-                unsafe {
-                    SendMessageW(hwnd, WM_VSCROLL, WPARAM(scroll_lines), LPARAM(0));
+
+        let _ = AppendMenuW(menu, MF_SEPARATOR, 0, None);
+        let _ = AppendMenuW(menu, MF_STRING, MENU_SELECT_ALL, w!("Select All Shown"));
+
+        let (x, y) = menu_position(lparam);
+        let choice = TrackPopupMenu(menu, TPM_RETURNCMD | TPM_RIGHTBUTTON, x, y, 0, hwnd, None).0 as usize;
+        let _ = DestroyMenu(menu);
+
+        let owner = hwnd;
+        let result = match choice {
+            MENU_EDIT => {
+                show_server_edit_form(hwnd, server_index);
+                Ok(())
+            }
+            MENU_MUX => {
+                show_mux_form(hwnd, Some(server.get_server_key()));
+                Ok(())
+            }
+            MENU_TLS => {
+                show_dpi_form(hwnd, server.get_server_key());
+                Ok(())
+            }
+            MENU_UPSTREAM => {
+                show_upstream_form(hwnd, server.get_server_key());
+                Ok(())
+            }
+            MENU_VIEW_CONFIG => {
+                crate::ui::config_window::show_config_window(owner, &server.get_server_key());
+                Ok(())
+            }
+            MENU_SPEED_TEST => {
+                // Downloads for several seconds, so off the UI thread
+                let hwnd_raw = hwnd.0 as isize;
+                std::thread::spawn(move || {
+                    let text = match crate::vpn::speed::test_server(&server.get_server_key()) {
+                        Ok(mbps) => format!("{} downloads at {}.", server.display_name(), crate::vpn::speed::format_rate(mbps)),
+                        Err(e) => format!("Speed test of {} failed:\n\n{}", server.display_name(), e),
+                    };
+                    let text_ptr = Box::into_raw(Box::new(text));
+                    let _ = PostMessageW(HWND(hwnd_raw as *mut _), WM_SPEED_RESULT, WPARAM(0), LPARAM(text_ptr as isize));
+                });
+                Ok(())
+            }
+            MENU_PIN | MENU_HIDE => {
+                let changed = if choice == MENU_PIN {
+                    crate::vpn::set_pinned(&server, !server.pinned)
+                } else {
+                    crate::vpn::set_hidden(&server, !server.hidden)
+                };
+                changed.map(|_| {
+                    if let Ok(global_servers) = VPN_SERVERS.lock() {
+                        if let Some(servers) = global_servers.as_ref() {
+                            rebuild_server_list(hwnd, servers);
+                        }
+                    }
+                })
+            }
+            MENU_COPY_LINK => crate::ui::controls::set_clipboard_text(owner, &server.uri),
+            MENU_SHOW_QR => crate::ui::qr_window::show_qr_window(owner, server.display_name(), &server.uri),
+            MENU_SYSTEM_PROXY => crate::sysproxy::set_server_preference(&server, !system_proxy),
+            MENU_GUEST_SHARE => crate::guest::start(&server.get_server_key(), crate::guest::GUEST_DURATION)
+                .and_then(|access| {
+                    crate::ui::qr_window::show_qr_window(owner, &format!("Guest: {}", access.server_name), &access.link())?;
+                    crate::ui::controls::message_box(owner, &access.describe(), "Guest Sharing", MB_OK | MB_ICONINFORMATION);
+                    Ok(())
+                }),
+            MENU_GUEST_STOP => {
+                crate::guest::stop();
+                Ok(())
+            }
+            MENU_SELECT_ALL => {
+                if let Ok(list) = GetDlgItem(hwnd, ID_SERVER_LIST) {
+                    crate::ui::server_list::select_all(list);
                 }
+                Ok(())
             }
-            LRESULT(0)
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            crate::ui::controls::message_box(owner, &e, "Error", MB_OK | MB_ICONERROR);
         }
-        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
     }
 }
 
@@ -257,8 +558,8 @@ unsafe fn create_controls(parent: HWND, hinstance: HINSTANCE) {
     // First row Y position
     let row1_y = MARGIN;
     
-    // Label "Subscription URL:"
-    let label_text: Vec<u16> = "Subscription URL:\0".encode_utf16().collect();
+    // Label "Subscriptions:"
+    let label_text: Vec<u16> = "Subscriptions:\0".encode_utf16().collect();
     let label = unsafe {
         CreateWindowExW(
             WINDOW_EX_STYLE::default(),
@@ -279,26 +580,48 @@ unsafe fn create_controls(parent: HWND, hinstance: HINSTANCE) {
         unsafe { SendMessageW(lbl, WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(1)); }
     }
     
-    // URL Edit control - set loaded URL
-    let url_text_wide: Vec<u16> = format!("{}\0", config.subscription_url).encode_utf16().collect();
+    // Subscriptions summary - read-only, edited through Manage...
+    let url_text_wide: Vec<u16> = format!("{}\0", subscriptions_summary(&config.subscriptions)).encode_utf16().collect();
     let url_edit = unsafe {
         CreateWindowExW(
             WS_EX_CLIENTEDGE,
             w!("EDIT"),
             PCWSTR::from_raw(url_text_wide.as_ptr()),
-            WS_CHILD | WS_VISIBLE | WS_BORDER | WINDOW_STYLE(ES_AUTOHSCROLL as u32),
+            WS_CHILD | WS_VISIBLE | WS_BORDER | WS_TABSTOP | WINDOW_STYLE(ES_AUTOHSCROLL as u32 | ES_READONLY as u32),
             MARGIN + URL_LABEL_WIDTH + 10,
             row1_y,
-            450,
+            320,
             CONTROL_HEIGHT,
             parent,
-            HMENU(ID_URL_EDIT as _),
+            HMENU(ID_SUBSCRIPTIONS_EDIT as _),
             hinstance,
             None,
-        ).expect("Failed to create URL edit control")
+        ).expect("Failed to create subscriptions edit control")
     };
     unsafe { SendMessageW(url_edit, WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(1)); }
     
+    // Manage subscriptions button
+    let manage_btn_text: Vec<u16> = "Manage...\0".encode_utf16().collect();
+    let manage_btn = unsafe {
+        CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("BUTTON"),
+            PCWSTR::from_raw(manage_btn_text.as_ptr()),
+            WS_CHILD | WS_VISIBLE | WS_TABSTOP | WINDOW_STYLE(BS_PUSHBUTTON as u32),
+            MARGIN + URL_LABEL_WIDTH + 10 + 320 + 10,
+            row1_y,
+            120,
+            CONTROL_HEIGHT,
+            parent,
+            HMENU(ID_SUBSCRIPTIONS_BUTTON as _),
+            hinstance,
+            None,
+        ).ok()
+    };
+    if let Some(btn) = manage_btn {
+        unsafe { SendMessageW(btn, WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(1)); }
+    }
+    
     // Update button
     let update_btn_text: Vec<u16> = "Update\0".encode_utf16().collect();
     let update_btn = unsafe {
@@ -306,7 +629,7 @@ unsafe fn create_controls(parent: HWND, hinstance: HINSTANCE) {
             WINDOW_EX_STYLE::default(),
             w!("BUTTON"),
             PCWSTR::from_raw(update_btn_text.as_ptr()),
-            WS_CHILD | WS_VISIBLE | WINDOW_STYLE(BS_PUSHBUTTON as u32),
+            WS_CHILD | WS_VISIBLE | WS_TABSTOP | WINDOW_STYLE(BS_PUSHBUTTON as u32),
             MARGIN + URL_LABEL_WIDTH + 10 + 450 + 10,
             row1_y,
             120,
@@ -353,7 +676,7 @@ unsafe fn create_controls(parent: HWND, hinstance: HINSTANCE) {
             WINDOW_EX_STYLE::default(),
             w!("BUTTON"),
             PCWSTR::from_raw(download_btn_text.as_ptr()),
-            WS_CHILD | WS_VISIBLE | WINDOW_STYLE(BS_PUSHBUTTON as u32),
+            WS_CHILD | WS_VISIBLE | WS_TABSTOP | WINDOW_STYLE(BS_PUSHBUTTON as u32),
             MARGIN + URL_LABEL_WIDTH + 10,
             row2_y,
             450,
@@ -378,7 +701,7 @@ unsafe fn create_controls(parent: HWND, hinstance: HINSTANCE) {
             WS_EX_CLIENTEDGE,
             w!("EDIT"),
             PCWSTR::from_raw(xray_path_text_wide.as_ptr()),
-            WS_CHILD | WS_VISIBLE | WS_BORDER | WINDOW_STYLE(ES_AUTOHSCROLL as u32),
+            WS_CHILD | WS_VISIBLE | WS_BORDER | WS_TABSTOP | WINDOW_STYLE(ES_AUTOHSCROLL as u32),
             MARGIN + URL_LABEL_WIDTH + 10,
             row3_y,
             450,
@@ -398,7 +721,7 @@ unsafe fn create_controls(parent: HWND, hinstance: HINSTANCE) {
             WINDOW_EX_STYLE::default(),
             w!("BUTTON"),
             PCWSTR::from_raw(browse_btn_text.as_ptr()),
-            WS_CHILD | WS_VISIBLE | WINDOW_STYLE(BS_PUSHBUTTON as u32),
+            WS_CHILD | WS_VISIBLE | WS_TABSTOP | WINDOW_STYLE(BS_PUSHBUTTON as u32),
             MARGIN + URL_LABEL_WIDTH + 10 + 450 + 10,
             row3_y,
             120,
@@ -416,17 +739,22 @@ unsafe fn create_controls(parent: HWND, hinstance: HINSTANCE) {
     // Fourth row Y position
     let row4_y = row3_y + CONTROL_HEIGHT + MARGIN;
     
-    // Autostart checkbox
-    let autostart_text: Vec<u16> = "Start automatically on Windows startup\0".encode_utf16().collect();
+    // Autostart checkbox (the Run key does nothing under Wine, so it's disabled there)
+    let wine = crate::system::is_wine();
+    let autostart_text: Vec<u16> = if wine {
+        "No autostart under Wine\0"
+    } else {
+        "Start with Windows\0"
+    }.encode_utf16().collect();
     let autostart_checkbox = unsafe {
         CreateWindowExW(
             WINDOW_EX_STYLE::default(),
             w!("BUTTON"),
             PCWSTR::from_raw(autostart_text.as_ptr()),
-            WS_CHILD | WS_VISIBLE | WINDOW_STYLE(BS_AUTOCHECKBOX as u32),
+            WS_CHILD | WS_VISIBLE | WS_TABSTOP | WINDOW_STYLE(BS_AUTOCHECKBOX as u32) | if wine { WS_DISABLED } else { WINDOW_STYLE(0) },
             MARGIN + URL_LABEL_WIDTH + 10,
             row4_y,
-            450,
+            300,
             CONTROL_HEIGHT,
             parent,
             HMENU(ID_AUTOSTART_CHECKBOX as _),
@@ -446,6 +774,31 @@ unsafe fn create_controls(parent: HWND, hinstance: HINSTANCE) {
             );
         }
     }
+
+    // Companion: whether a launch (autostart or by hand) stays in the tray
+    let minimized_text: Vec<u16> = "Start minimized to tray\0".encode_utf16().collect();
+    let minimized_checkbox = unsafe {
+        CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("BUTTON"),
+            PCWSTR::from_raw(minimized_text.as_ptr()),
+            WS_CHILD | WS_VISIBLE | WS_TABSTOP | WINDOW_STYLE(BS_AUTOCHECKBOX as u32),
+            MARGIN + URL_LABEL_WIDTH + 10 + 300 + 10,
+            row4_y,
+            340,
+            CONTROL_HEIGHT,
+            parent,
+            HMENU(ID_START_MINIMIZED_CHECKBOX as _),
+            hinstance,
+            None,
+        ).ok()
+    };
+    if let Some(cb) = minimized_checkbox {
+        unsafe {
+            SendMessageW(cb, WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(1));
+            SendMessageW(cb, BM_SETCHECK, WPARAM(if config.start_minimized { 1 } else { 0 }), LPARAM(0));
+        }
+    }
     
     // Fifth row Y position
     let row5_y = row4_y + CONTROL_HEIGHT + MARGIN;
@@ -472,42 +825,41 @@ unsafe fn create_controls(parent: HWND, hinstance: HINSTANCE) {
         unsafe { SendMessageW(lbl, WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(1)); }
     }
     
-    // Server list container Y position
-    let container_y = row5_y + LABEL_HEIGHT + 10;
+    // Server list Y position
+    let list_y = row5_y + LABEL_HEIGHT + 10;
     
-    // Get client area size to calculate container height dynamically
+    // Get client area size to calculate list height dynamically
     let mut client_rect = RECT::default();
     unsafe { GetClientRect(parent, &mut client_rect).ok() };
     let client_width = client_rect.right - client_rect.left;
     let client_height = client_rect.bottom - client_rect.top;
     
-    // Calculate container size based on window size
+    // Filter next to the label; rows narrow down as you type
+    unsafe {
+        let filter_x = MARGIN + URL_LABEL_WIDTH + 10;
+        if let Some(filter) = crate::ui::controls::create_edit(parent, hinstance, "", ID_SERVER_FILTER_EDIT,
+            (filter_x, row5_y, client_width - filter_x - MARGIN, CONTROL_HEIGHT), hfont) {
+            let cue: Vec<u16> = "Filter by name, address or protocol\0".encode_utf16().collect();
+            SendMessageW(filter, windows::Win32::UI::Controls::EM_SETCUEBANNER, WPARAM(1), LPARAM(cue.as_ptr() as isize));
+        }
+    }
+    
+    // Calculate list size based on window size
     // Reserve space for Save/Cancel buttons at the bottom (60px)
     const BUTTON_ROW_HEIGHT: i32 = 60;
-    let container_width = client_width - (2 * MARGIN);
-    let container_height = client_height - container_y - MARGIN - BUTTON_ROW_HEIGHT;
+    let list_width = client_width - (2 * MARGIN);
+    let list_height = client_height - list_y - MARGIN - BUTTON_ROW_HEIGHT;
     
-    // Scrollable container for server panels with custom class
-    let container_class_str: Vec<u16> = "ScrollContainerClass\0".encode_utf16().collect();
+    // Server table; double-click a row to edit its port and proxy type
     unsafe {
-        CreateWindowExW(
-            WS_EX_CLIENTEDGE,
-            PCWSTR::from_raw(container_class_str.as_ptr()),
-            PCWSTR::null(),
-            WS_CHILD | WS_VISIBLE | WS_VSCROLL,
-            MARGIN,
-            container_y,
-            container_width,
-            container_height,
-            parent,
-            HMENU(ID_SCROLL_CONTAINER as _),
-            hinstance,
-            None,
-        ).expect("Failed to create scroll container")
-    };
+        crate::ui::server_list::create(parent, hinstance, ID_SERVER_LIST,
+            (MARGIN, list_y, list_width, list_height), hfont)
+            .expect("Failed to create server list");
+    }
+    crate::ui::server_list::set_grouping(&config.group_by);
     
     // Bottom buttons row
-    let buttons_y = container_y + container_height + 10;
+    let buttons_y = list_y + list_height + 10;
     
     // Save button
     let save_btn_text: Vec<u16> = "Save\0".encode_utf16().collect();
@@ -516,7 +868,7 @@ unsafe fn create_controls(parent: HWND, hinstance: HINSTANCE) {
             WINDOW_EX_STYLE::default(),
             w!("BUTTON"),
             PCWSTR::from_raw(save_btn_text.as_ptr()),
-            WS_CHILD | WS_VISIBLE | WINDOW_STYLE(BS_PUSHBUTTON as u32),
+            WS_CHILD | WS_VISIBLE | WS_TABSTOP | WINDOW_STYLE(BS_PUSHBUTTON as u32),
             client_width - 240, // Right side: 120px button + 10px margin + 120px button
             buttons_y,
             110,
@@ -538,7 +890,7 @@ unsafe fn create_controls(parent: HWND, hinstance: HINSTANCE) {
             WINDOW_EX_STYLE::default(),
             w!("BUTTON"),
             PCWSTR::from_raw(cancel_btn_text.as_ptr()),
-            WS_CHILD | WS_VISIBLE | WINDOW_STYLE(BS_PUSHBUTTON as u32),
+            WS_CHILD | WS_VISIBLE | WS_TABSTOP | WINDOW_STYLE(BS_PUSHBUTTON as u32),
             client_width - 120, // Right side
             buttons_y,
             110,
@@ -553,32 +905,84 @@ unsafe fn create_controls(parent: HWND, hinstance: HINSTANCE) {
         unsafe { SendMessageW(btn, WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(1)); }
     }
     
-    // Auto-load servers from subscription URL if available
-    if !config.subscription_url.is_empty() {
-        let url = config.subscription_url.clone();
-        let saved_settings = config.server_settings.clone();
-        let hwnd_raw = parent.0 as isize;
-        
-        std::thread::spawn(move || {
-            let mut servers = fetch_and_process_vpn_list(&url);
-            
-            // Assign settings (preserving saved ones)
-            assign_local_ports(&mut servers, &saved_settings);
-            
-            // Store servers globally
-            if let Ok(mut global_servers) = VPN_SERVERS.lock() {
-                *global_servers = Some(servers.clone());
-            }
-            
-            // Update UI on main thread via PostMessage
-            unsafe {
-                let hwnd = HWND(hwnd_raw as *mut _);
-                let _ = PostMessageW(hwnd, WM_UPDATE_SERVERS, WPARAM(0), LPARAM(0));
-            }
-        });
+    // Test All button (left side): latency of every listed server
+    unsafe {
+        crate::ui::controls::create_button(parent, hinstance, "Test All", ID_TEST_ALL_BUTTON,
+            (MARGIN, buttons_y, 140, CONTROL_HEIGHT), hfont);
+        crate::ui::controls::create_button(parent, hinstance, "Least Reliable First", ID_SORT_RELIABILITY_BUTTON,
+            (MARGIN + 150, buttons_y, 260, CONTROL_HEIGHT), hfont);
+        crate::ui::controls::create_button(parent, hinstance, "Test Selected", ID_TEST_SERVER_BUTTON,
+            (MARGIN + 420, buttons_y, 200, CONTROL_HEIGHT), hfont);
+    }
+    
+    // Screen readers name an edit after the control before it, which for the path is the download button
+    unsafe {
+        use crate::ui::controls::name_control;
+        name_control(parent, ID_SUBSCRIPTIONS_EDIT, "Subscriptions");
+        name_control(parent, ID_SUBSCRIPTIONS_BUTTON, "Manage subscriptions");
+        name_control(parent, ID_UPDATE_BUTTON, "Update servers from subscriptions");
+        name_control(parent, ID_XRAY_PATH_EDIT, "Xray binary path");
+        name_control(parent, ID_XRAY_BROWSE_BUTTON, "Browse for the xray binary");
+        name_control(parent, ID_SERVER_LIST, "VPN servers");
+        name_control(parent, ID_SERVER_FILTER_EDIT, "Filter servers");
+        name_control(parent, ID_SORT_RELIABILITY_BUTTON, "Sort servers, least reliable first");
+        name_control(parent, ID_TEST_SERVER_BUTTON, "Test the selected server before saving");
+    }
+    
+    // Auto-load servers from subscriptions if available
+    if config.has_server_sources() {
+        refresh_servers_in_background(parent, config.subscriptions.clone());
     }
 }
 
+// Short description of the configured subscriptions for the summary field
+fn subscriptions_summary(subscriptions: &[crate::config::Subscription]) -> String {
+    match subscriptions.len() {
+        0 => "None - click Manage... to add one".to_string(),
+        _ => subscriptions.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", "),
+    }
+}
+
+// Fetch all subscriptions in a background thread, then rebuild the server list
+#[cfg(windows)]
+fn refresh_servers_in_background(hwnd: HWND, subscriptions: Vec<crate::config::Subscription>) {
+    let hwnd_raw = hwnd.0 as isize;
+    std::thread::spawn(move || {
+        crate::settings_model::load_server_list(&subscriptions);
+        
+        // Update UI on main thread via PostMessage
+        unsafe {
+            let hwnd = HWND(hwnd_raw as *mut _);
+            let _ = PostMessageW(hwnd, WM_UPDATE_SERVERS, WPARAM(0), LPARAM(0));
+        }
+    });
+}
+
+// Fetch subscriptions and plan a refresh (validating each config) without applying it,
+// then show the plan
+#[cfg(windows)]
+fn preview_refresh_in_background(hwnd: HWND) {
+    let hwnd_raw = hwnd.0 as isize;
+    std::thread::spawn(move || {
+        let text = match crate::plan::preview() {
+            Ok(plan) => format!("A subscription refresh would do this now:\n\n{}", plan.describe()),
+            Err(e) => format!("Can't preview the refresh: {}", e),
+        };
+        unsafe {
+            let text_ptr = Box::into_raw(Box::new(text));
+            let _ = PostMessageW(HWND(hwnd_raw as *mut _), WM_PREVIEW_RESULT, WPARAM(0), LPARAM(text_ptr as isize));
+        }
+    });
+}
+
+/// Called after subscriptions were edited: refresh the summary and re-fetch servers
+#[cfg(windows)]
+pub unsafe fn reload_subscriptions(hwnd: HWND) {
+    let config = crate::config::Config::load().unwrap_or_default();
+    unsafe { crate::ui::controls::set_control_text(hwnd, ID_SUBSCRIPTIONS_EDIT, &subscriptions_summary(&config.subscriptions)); }
+    refresh_servers_in_background(hwnd, config.subscriptions);
+}
+
 #[cfg(windows)]
 unsafe extern "system" fn settings_window_proc(
     hwnd: HWND,
@@ -595,122 +999,28 @@ unsafe extern "system" fn settings_window_proc(
             if control_id == ID_UPDATE_BUTTON as usize && notification_code == 0 {
                 println!("Update button clicked!");
                 
-                // Get text from edit control
-                let url_edit = unsafe { GetDlgItem(hwnd, ID_URL_EDIT) };
-                if url_edit.is_ok() && !url_edit.as_ref().unwrap().is_invalid() {
-                    let mut buffer = vec![0u16; 2048];
-                    let len = unsafe { 
-                        GetWindowTextW(url_edit.unwrap(), &mut buffer) 
-                    };
-                    
-                    if len > 0 {
-                        let url = String::from_utf16_lossy(&buffer[..len as usize]);
-                        println!("URL entered: {}", url);
-                        
-                        // Fetch and process in background thread
-                        let hwnd_raw = hwnd.0 as isize;
-                        std::thread::spawn(move || {
-                            let mut servers = fetch_and_process_vpn_list(&url);
-                            
-                            // Load config to get saved settings
-                            let config = crate::config::Config::load().unwrap_or_default();
-                            
-                            // Assign settings (preserving saved ones)
-                            assign_local_ports(&mut servers, &config.server_settings);
-                            
-                            // Store servers globally
-                            if let Ok(mut global_servers) = VPN_SERVERS.lock() {
-                                *global_servers = Some(servers.clone());
-                            }
-                            
-                            // Update UI on main thread via PostMessage
-                            unsafe {
-                                let hwnd = HWND(hwnd_raw as *mut _);
-                                let _ = PostMessageW(hwnd, WM_UPDATE_SERVERS, WPARAM(0), LPARAM(0));
-                            }
-                        });
-                    } else {
-                        println!("No URL entered");
-                    }
-                }
-            }
-            // Handle checkbox changes
-            else if control_id >= ID_SERVER_CHECKBOX_BASE as usize 
-                    && control_id < ID_SERVER_PORT_EDIT_BASE as usize 
-                    && notification_code == BN_CLICKED {
-                let server_index = control_id - ID_SERVER_CHECKBOX_BASE as usize;
-                
-                // Get checkbox state
-                if let Ok(container) = unsafe { GetDlgItem(hwnd, ID_SCROLL_CONTAINER) } {
-                    if let Ok(checkbox) = unsafe { GetDlgItem(container, control_id as i32) } {
-                        let state = unsafe { SendMessageW(checkbox, BM_GETCHECK, WPARAM(0), LPARAM(0)) };
-                        let checked = state.0 == 1;
-                        
-                        // Update global state
-                        if let Ok(mut global_servers) = VPN_SERVERS.try_lock() {
-                            if let Some(servers) = global_servers.as_mut() {
-                                if let Some(server) = servers.get_mut(server_index) {
-                                    server.enabled = checked;
-                                }
-                            }
-                        }
-                    }
+                let config = crate::config::Config::load().unwrap_or_default();
+                if !config.has_server_sources() {
+                    println!("No subscriptions configured");
+                } else {
+                    // Fetch and process in background thread
+                    refresh_servers_in_background(hwnd, config.subscriptions);
                 }
             }
-            // Handle port edit changes
-            else if control_id >= ID_SERVER_PORT_EDIT_BASE as usize 
-                    && control_id < ID_SERVER_PROXY_COMBO_BASE as usize
-                    && notification_code == EN_CHANGE {
-                let server_index = control_id - ID_SERVER_PORT_EDIT_BASE as usize;
-                
-                // Get container window first, then find edit control in container
-                if let Ok(container) = unsafe { GetDlgItem(hwnd, ID_SCROLL_CONTAINER) } {
-                    if let Ok(edit) = unsafe { GetDlgItem(container, control_id as i32) } {
-                        let mut buffer = vec![0u16; 16];
-                        let len = unsafe { GetWindowTextW(edit, &mut buffer) };
-                        
-                        if len > 0 {
-                            let port_text = String::from_utf16_lossy(&buffer[..len as usize]);
-                            
-                            // Parse port as u16
-                            if let Ok(port) = port_text.parse::<u16>() {
-                                // Update global state
-                                if let Ok(mut global_servers) = VPN_SERVERS.try_lock() {
-                                    if let Some(servers) = global_servers.as_mut() {
-                                        if let Some(server) = servers.get_mut(server_index) {
-                                            server.local_port = port;
-                                        }
-                                    }
-                                }
-                            }
-                        }
+            // Show only servers matching the filter text
+            else if control_id == ID_SERVER_FILTER_EDIT as usize && notification_code == EN_CHANGE {
+                let mut buffer = vec![0u16; 256];
+                let len = unsafe { GetWindowTextW(HWND(lparam.0 as *mut _), &mut buffer) };
+                crate::ui::server_list::set_filter(&String::from_utf16_lossy(&buffer[..len.max(0) as usize]));
+                if let Ok(global_servers) = VPN_SERVERS.lock() {
+                    if let Some(servers) = global_servers.as_ref() {
+                        unsafe { rebuild_server_list(hwnd, servers) };
                     }
                 }
             }
-            // Handle proxy type combobox changes
-            else if control_id >= ID_SERVER_PROXY_COMBO_BASE as usize 
-                    && notification_code == CBN_SELCHANGE {
-                let server_index = control_id - ID_SERVER_PROXY_COMBO_BASE as usize;
-                
-                // Get container window first, then find combo control in container
-                if let Ok(container) = unsafe { GetDlgItem(hwnd, ID_SCROLL_CONTAINER) } {
-                    if let Ok(combo) = unsafe { GetDlgItem(container, control_id as i32) } {
-                        let sel_idx = unsafe { SendMessageW(combo, CB_GETCURSEL, WPARAM(0), LPARAM(0)) };
-                        
-                        if sel_idx.0 >= 0 {
-                            let proxy_type = if sel_idx.0 == 0 { "SOCKS" } else { "HTTP" };
-                            
-                            // Update global state
-                            if let Ok(mut global_servers) = VPN_SERVERS.try_lock() {
-                                if let Some(servers) = global_servers.as_mut() {
-                                    if let Some(server) = servers.get_mut(server_index) {
-                                        server.proxy_type = proxy_type.to_string();
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+            // Manage subscriptions
+            else if control_id == ID_SUBSCRIPTIONS_BUTTON as usize && notification_code == 0 {
+                unsafe { crate::ui::subscriptions_window::show_subscriptions_window(hwnd); }
             }
             // Handle Browse button for Xray binary
             else if control_id == ID_XRAY_BROWSE_BUTTON as usize && notification_code == 0 {
@@ -811,22 +1121,63 @@ unsafe extern "system" fn settings_window_proc(
                     }
                 });
             }
-            // Handle Save button
-            else if control_id == ID_SAVE_BUTTON as usize && notification_code == 0 {
-                
-                // Get URL from edit control
-                let url_edit = unsafe { GetDlgItem(hwnd, ID_URL_EDIT) };
-                let subscription_url = if url_edit.is_ok() && !url_edit.as_ref().unwrap().is_invalid() {
-                    let mut buffer = vec![0u16; 2048];
-                    let len = unsafe { GetWindowTextW(url_edit.unwrap(), &mut buffer) };
-                    if len > 0 {
-                        String::from_utf16_lossy(&buffer[..len as usize])
-                    } else {
-                        String::new()
+            // Latency-test every listed server in the background
+            else if control_id == ID_TEST_ALL_BUTTON as usize && notification_code == 0 {
+                let servers = VPN_SERVERS.lock().ok().and_then(|s| s.clone()).unwrap_or_default();
+                if !servers.is_empty() {
+                    unsafe { set_test_all_running(hwnd, true) };
+                    let hwnd_raw = hwnd.0 as isize;
+                    std::thread::spawn(move || {
+                        // Rows update as results come in
+                        let post = |done: usize| unsafe {
+                            let _ = PostMessageW(HWND(hwnd_raw as *mut _), WM_LATENCY_RESULT, WPARAM(done), LPARAM(0));
+                        };
+                        crate::vpn::latency::test_all(&servers, &|_| post(0));
+                        post(1);
+                    });
+                }
+            }
+            // Try the selected server as edited, in a throwaway xray, without saving anything
+            else if control_id == ID_TEST_SERVER_BUTTON as usize && notification_code == 0 {
+                let index = unsafe { GetDlgItem(hwnd, ID_SERVER_LIST) }.ok()
+                    .and_then(|list| unsafe { crate::ui::server_list::selected_server(list) });
+                let server = index.and_then(|index| VPN_SERVERS.lock().ok()?.as_ref()?.get(index).cloned());
+                match server.filter(|server| !server.uri.is_empty()) {
+                    None => unsafe {
+                        crate::ui::controls::message_box(hwnd, "Select a server to test first.", "Test Server", MB_OK | MB_ICONINFORMATION);
+                    },
+                    Some(server) => {
+                        // The path as typed, so a new xray binary can be tried before saving too
+                        let xray_binary_path = unsafe { crate::ui::controls::get_control_text(hwnd, ID_XRAY_PATH_EDIT) };
+                        unsafe { set_test_server_running(hwnd, true) };
+                        let hwnd_raw = hwnd.0 as isize;
+                        std::thread::spawn(move || {
+                            let text = match crate::xray_manager::dry_run(&server, xray_binary_path.trim()) {
+                                Ok(elapsed) => format!("{} works: the test URL answered in {} ms.", server.display_name(), elapsed.as_millis()),
+                                Err(e) => format!("{} failed the test:\n\n{}", server.display_name(), e),
+                            };
+                            let text_ptr = Box::into_raw(Box::new(text));
+                            unsafe {
+                                let _ = PostMessageW(HWND(hwnd_raw as *mut _), WM_DRY_RUN_RESULT, WPARAM(0), LPARAM(text_ptr as isize));
+                            }
+                        });
                     }
-                } else {
-                    String::new()
-                };
+                }
+            }
+            // Put chronically failing servers at the top so they're easy to uncheck.
+            // Edits live in VPN_SERVERS already, so reordering and rebuilding keeps them.
+            else if control_id == ID_SORT_RELIABILITY_BUTTON as usize && notification_code == 0 {
+                if let Ok(mut global_servers) = VPN_SERVERS.lock() {
+                    if let Some(servers) = global_servers.as_mut() {
+                        // Servers without data last; stable, so ties keep their order
+                        servers.sort_by_key(|server| crate::reliability::score(&server.get_server_key()).unwrap_or(u32::MAX));
+                        crate::ui::server_list::clear_sort();
+                        unsafe { rebuild_server_list(hwnd, servers) };
+                    }
+                }
+            }
+            // Handle Save button
+            else if control_id == ID_SAVE_BUTTON as usize && notification_code == 0 {
                 
                 // Get Xray binary path from edit control
                 let xray_edit = unsafe { GetDlgItem(hwnd, ID_XRAY_PATH_EDIT) };
@@ -851,45 +1202,60 @@ unsafe extern "system" fn settings_window_proc(
                         false
                     }
                 };
+                let start_minimized = unsafe {
+                    GetDlgItem(hwnd, ID_START_MINIMIZED_CHECKBOX)
+                        .map(|checkbox| SendMessageW(checkbox, BM_GETCHECK, WPARAM(0), LPARAM(0)).0 == 1)
+                        .unwrap_or(true)
+                };
+                
+                let servers = VPN_SERVERS.lock().ok().and_then(|servers| servers.clone()).unwrap_or_default();
+                if !unsafe { confirm_ports(hwnd, &servers) } {
+                    return LRESULT(0);
+                }
                 
-                // Build server_settings HashMap from current servers
-                use std::collections::HashMap;
-                let mut server_settings = HashMap::new();
+                // Update saved config (keeps settings edited in other windows)
+                let mut config = crate::config::Config::load().unwrap_or_default();
                 
+                // Update the listed servers' settings; settings of servers missing from the
+                // list stay until prune::after_fetch or Clean Up Server Settings drops them
                 if let Ok(global_servers) = VPN_SERVERS.lock() {
                     if let Some(servers) = global_servers.as_ref() {
-                        for server in servers {
-                            let key = server.get_server_key();
-                            let settings = crate::config::ServerSettings {
-                                local_port: server.local_port,
-                                proxy_type: server.proxy_type.clone(),
-                                enabled: server.enabled,
-                            };
-                            server_settings.insert(key, settings);
-                        }
+                        crate::settings_model::store_rows(&mut config, servers);
                     }
                 }
                 
-                // Create and save config
-                let config = crate::config::Config {
-                    subscription_url,
-                    xray_binary_path,
-                    server_settings,
-                    autostart,
-                };
+                config.xray_binary_path = xray_binary_path;
+                config.autostart = autostart;
+                config.start_minimized = start_minimized;
                 
                 match config.save() {
                     Ok(_) => {
                         // Apply autostart setting to registry
-                        if let Err(e) = crate::config::Config::set_autostart(autostart) {
+                        if let Err(e) = crate::system::require_windows("Autostart")
+                            .and_then(|_| crate::config::Config::set_autostart(autostart)) {
                             eprintln!("Failed to set autostart: {}", e);
                         }
                         
                         // Restart xray servers with new config
-                        crate::restart_xray_servers();
+                        let summary = crate::restart_xray_servers();
                         
-                        // Close window
-                        unsafe { let _ = DestroyWindow(hwnd); }
+                        // Keep the window open if something failed so errors show next to servers
+                        if summary.failed.is_empty() && crate::diagnostics::get_all_hints().is_empty() {
+                            unsafe { let _ = DestroyWindow(hwnd); }
+                        } else {
+                            unsafe {
+                                let _ = PostMessageW(hwnd, WM_UPDATE_SERVERS, WPARAM(0), LPARAM(0));
+                                crate::ui::controls::message_box(
+                                    hwnd,
+                                    &format!(
+                                        "Settings saved, but some servers reported problems ({} started, {} failed).\n\n{}",
+                                        summary.started.len(), summary.failed.len(), summary.failure_text(),
+                                    ),
+                                    "Warning",
+                                    MB_OK | MB_ICONWARNING,
+                                );
+                            }
+                        }
                     }
                     Err(e) => {
                         // Show error message box
@@ -911,6 +1277,145 @@ unsafe extern "system" fn settings_window_proc(
                 // Close window without saving
                 unsafe { let _ = DestroyWindow(hwnd); }
             }
+            // View → grouping of the server list
+            else if control_id == ID_MENU_GROUP_NONE as usize && notification_code == 0 {
+                unsafe { set_grouping(hwnd, ""); }
+            }
+            else if control_id == ID_MENU_GROUP_SUBSCRIPTION as usize && notification_code == 0 {
+                unsafe { set_grouping(hwnd, "subscription"); }
+            }
+            else if control_id == ID_MENU_GROUP_COUNTRY as usize && notification_code == 0 {
+                unsafe { set_grouping(hwnd, "country"); }
+            }
+            // View → Show Hidden Servers
+            else if control_id == ID_MENU_SHOW_HIDDEN as usize && notification_code == 0 {
+                let show = !crate::ui::server_list::showing_hidden();
+                crate::ui::server_list::set_show_hidden(show);
+                unsafe {
+                    let check = if show { MF_CHECKED } else { MF_UNCHECKED };
+                    CheckMenuItem(GetMenu(hwnd), ID_MENU_SHOW_HIDDEN as u32, (MF_BYCOMMAND | check).0);
+                    if let Ok(global_servers) = VPN_SERVERS.lock() {
+                        if let Some(servers) = global_servers.as_ref() {
+                            rebuild_server_list(hwnd, servers);
+                        }
+                    }
+                }
+            }
+            // Tools → Add Server...
+            else if control_id == ID_MENU_MANUAL_SERVERS as usize && notification_code == 0 {
+                unsafe { crate::ui::manual_servers_window::show_manual_servers_window(hwnd); }
+            }
+            // Tools → API Access...
+            else if control_id == ID_MENU_API_ACCESS as usize && notification_code == 0 {
+                #[cfg(feature = "api")]
+                unsafe { crate::ui::api_window::show_api_window(hwnd); }
+            }
+            // Tools → Connect/Disconnect Hooks...
+            else if control_id == ID_MENU_HOOKS as usize && notification_code == 0 {
+                unsafe { show_hooks_form(hwnd); }
+            }
+            // Tools → Primary Port & Rotation...
+            else if control_id == ID_MENU_PRIMARY as usize && notification_code == 0 {
+                unsafe { show_primary_form(hwnd); }
+            }
+            // Tools → Failover...
+            else if control_id == ID_MENU_FAILOVER as usize && notification_code == 0 {
+                unsafe { show_failover_form(hwnd); }
+            }
+            // Tools → System Proxy...
+            else if control_id == ID_MENU_SYSTEM_PROXY as usize && notification_code == 0 {
+                unsafe { show_system_proxy_form(hwnd); }
+            }
+            // Tools → TUN Mode...
+            else if control_id == ID_MENU_TUN as usize && notification_code == 0 {
+                #[cfg(feature = "tun")]
+                unsafe { show_tun_form(hwnd); }
+            }
+            // Tools → Balanced Groups...
+            else if control_id == ID_MENU_BALANCER as usize && notification_code == 0 {
+                unsafe { crate::ui::balancer_window::show_balancer_window(hwnd); }
+            }
+            // Tools → Preview Refresh...
+            else if control_id == ID_MENU_PREVIEW as usize && notification_code == 0 {
+                preview_refresh_in_background(hwnd);
+            }
+            // Tools → Compare Test Results...
+            else if control_id == ID_MENU_COMPARE_RESULTS as usize && notification_code == 0 {
+                #[cfg(feature = "stats")]
+                unsafe { crate::ui::controls::message_box(hwnd, &crate::results::compare_weeks(), "Compare Test Results", MB_OK | MB_ICONINFORMATION); }
+            }
+            // Tools → View Logs...
+            else if control_id == ID_MENU_VIEW_LOGS as usize && notification_code == 0 {
+                unsafe { crate::ui::log_window::show_log_window(hwnd); }
+            }
+            // Tools → Log Files...
+            else if control_id == ID_MENU_LOGS as usize && notification_code == 0 {
+                unsafe { show_logs_form(hwnd); }
+            }
+            // Tools → Server Limits...
+            else if control_id == ID_MENU_LIMITS as usize && notification_code == 0 {
+                unsafe { show_limits_form(hwnd); }
+            }
+            // Tools → Routing Rules...
+            else if control_id == ID_MENU_ROUTING_RULES as usize && notification_code == 0 {
+                unsafe { crate::ui::routing_rules_window::show_routing_rules_window(hwnd); }
+            }
+            // Tools → Routing Presets...
+            else if control_id == ID_MENU_ROUTING as usize && notification_code == 0 {
+                unsafe { crate::ui::routing_window::show_routing_window(hwnd); }
+            }
+            // Tools → Rule Sets...
+            else if control_id == ID_MENU_RULESETS as usize && notification_code == 0 {
+                unsafe { crate::ui::rulesets_window::show_rulesets_window(hwnd); }
+            }
+            // Tools → Geo Assets...
+            else if control_id == ID_MENU_GEO_ASSETS as usize && notification_code == 0 {
+                unsafe { show_geo_assets_form(hwnd); }
+            }
+            // Tools → DNS...
+            else if control_id == ID_MENU_DNS as usize && notification_code == 0 {
+                unsafe { show_dns_form(hwnd); }
+            }
+            // Tools → Status Page...
+            else if control_id == ID_MENU_STATUS_PAGE as usize && notification_code == 0 {
+                unsafe { show_status_page_form(hwnd); }
+            }
+            // Tools → Multiplexing...
+            else if control_id == ID_MENU_MUX as usize && notification_code == 0 {
+                unsafe { show_mux_form(hwnd, None); }
+            }
+            // Tools → LAN Sharing...
+            else if control_id == ID_MENU_LAN_SHARING as usize && notification_code == 0 {
+                unsafe { show_lan_sharing_form(hwnd); }
+            }
+            // Tools → Scheduled Tests...
+            else if control_id == ID_MENU_SCHEDULED_TESTS as usize && notification_code == 0 {
+                unsafe { show_scheduled_tests_form(hwnd); }
+            }
+            // Tools → Exit IP Alerts...
+            else if control_id == ID_MENU_EXIT_IP as usize && notification_code == 0 {
+                unsafe { show_exit_ip_form(hwnd); }
+            }
+            // Tools → Export Server List...
+            else if control_id == ID_MENU_EXPORT as usize && notification_code == 0 {
+                unsafe { export_server_list(hwnd); }
+            }
+            // Tools → Share Servers...
+            else if control_id == ID_MENU_SHARE as usize && notification_code == 0 {
+                unsafe { crate::ui::share_window::show_share_window(hwnd); }
+            }
+            // Tools → Tray Icon...
+            else if control_id == ID_MENU_TRAY_ICON as usize && notification_code == 0 {
+                unsafe { show_tray_icon_form(hwnd); }
+            }
+            // Tools → Clean Up Server Settings...
+            else if control_id == ID_MENU_CLEANUP as usize && notification_code == 0 {
+                unsafe { show_cleanup_form(hwnd); }
+            }
+            // Tools → Options...
+            else if control_id == ID_MENU_OPTIONS as usize && notification_code == 0 {
+                unsafe { show_options_form(hwnd); }
+            }
             
             LRESULT(0)
         }
@@ -934,25 +1439,39 @@ unsafe extern "system" fn settings_window_proc(
             let row3_y = row2_y + CONTROL_HEIGHT + MARGIN;
             let row4_y = row3_y + CONTROL_HEIGHT + MARGIN;
             let row5_y = row4_y + CONTROL_HEIGHT + MARGIN;
-            let container_y = row5_y + LABEL_HEIGHT + 10;
-            let container_height = height - container_y - MARGIN - BUTTON_ROW_HEIGHT;
-            let buttons_y = container_y + container_height + 10;
+            let list_y = row5_y + LABEL_HEIGHT + 10;
+            let list_height = height - list_y - MARGIN - BUTTON_ROW_HEIGHT;
+            let buttons_y = list_y + list_height + 10;
             
             unsafe {
-                // Resize URL edit control
-                if let Ok(url_edit) = GetDlgItem(hwnd, ID_URL_EDIT) {
+                // Resize subscriptions summary control
+                if let Ok(url_edit) = GetDlgItem(hwnd, ID_SUBSCRIPTIONS_EDIT) {
                     if !url_edit.is_invalid() {
                         SetWindowPos(
                             url_edit,
                             None,
                             0, 0,
-                            width - (MARGIN + URL_LABEL_WIDTH + 10 + 120 + 10 + MARGIN),
+                            width - (MARGIN + URL_LABEL_WIDTH + 10 + 120 + 10 + 120 + 10 + MARGIN),
                             CONTROL_HEIGHT,
                             SWP_NOMOVE | SWP_NOZORDER,
                         ).ok();
                     }
                 }
                 
+                // Move Manage... button next to Update
+                if let Ok(manage_btn) = GetDlgItem(hwnd, ID_SUBSCRIPTIONS_BUTTON) {
+                    if !manage_btn.is_invalid() {
+                        SetWindowPos(
+                            manage_btn,
+                            None,
+                            width - 120 - 10 - 120 - MARGIN,
+                            row1_y,
+                            0, 0,
+                            SWP_NOSIZE | SWP_NOZORDER,
+                        ).ok();
+                    }
+                }
+                
                 // Move Update button to stay on the right
                 if let Ok(update_btn) = GetDlgItem(hwnd, ID_UPDATE_BUTTON) {
                     if !update_btn.is_invalid() {
@@ -995,20 +1514,29 @@ unsafe extern "system" fn settings_window_proc(
                     }
                 }
                 
-                // Resize scroll container to fill remaining space
-                if let Ok(container) = GetDlgItem(hwnd, ID_SCROLL_CONTAINER) {
-                    if !container.is_invalid() {
+                // Stretch the filter to the right edge
+                if let Ok(filter) = GetDlgItem(hwnd, ID_SERVER_FILTER_EDIT) {
+                    SetWindowPos(
+                        filter,
+                        None,
+                        0, 0,
+                        width - (MARGIN + URL_LABEL_WIDTH + 10 + MARGIN),
+                        CONTROL_HEIGHT,
+                        SWP_NOMOVE | SWP_NOZORDER,
+                    ).ok();
+                }
+                
+                // Resize the server list to fill remaining space
+                if let Ok(list) = GetDlgItem(hwnd, ID_SERVER_LIST) {
+                    if !list.is_invalid() {
                         SetWindowPos(
-                            container,
+                            list,
                             None,
                             0, 0,
                             width - (2 * MARGIN),
-                            container_height,
+                            list_height,
                             SWP_NOMOVE | SWP_NOZORDER,
                         ).ok();
-                        
-                        // Only resize checkboxes, don't rebuild entire list
-                        resize_server_list_items(container);
                     }
                 }
                 
@@ -1026,6 +1554,44 @@ unsafe extern "system" fn settings_window_proc(
                     }
                 }
                 
+                // Keep Test All and the sort button at the left of the button row
+                if let Ok(test_btn) = GetDlgItem(hwnd, ID_TEST_ALL_BUTTON) {
+                    if !test_btn.is_invalid() {
+                        SetWindowPos(
+                            test_btn,
+                            None,
+                            MARGIN,
+                            buttons_y,
+                            0, 0,
+                            SWP_NOSIZE | SWP_NOZORDER,
+                        ).ok();
+                    }
+                }
+                if let Ok(sort_btn) = GetDlgItem(hwnd, ID_SORT_RELIABILITY_BUTTON) {
+                    if !sort_btn.is_invalid() {
+                        SetWindowPos(
+                            sort_btn,
+                            None,
+                            MARGIN + 150,
+                            buttons_y,
+                            0, 0,
+                            SWP_NOSIZE | SWP_NOZORDER,
+                        ).ok();
+                    }
+                }
+                if let Ok(test_server_btn) = GetDlgItem(hwnd, ID_TEST_SERVER_BUTTON) {
+                    if !test_server_btn.is_invalid() {
+                        SetWindowPos(
+                            test_server_btn,
+                            None,
+                            MARGIN + 420,
+                            buttons_y,
+                            0, 0,
+                            SWP_NOSIZE | SWP_NOZORDER,
+                        ).ok();
+                    }
+                }
+                
                 // Move Cancel button
                 if let Ok(cancel_btn) = GetDlgItem(hwnd, ID_CANCEL_BUTTON) {
                     if !cancel_btn.is_invalid() {
@@ -1043,14 +1609,76 @@ unsafe extern "system" fn settings_window_proc(
             LRESULT(0)
         }
         _ if msg == WM_UPDATE_SERVERS => {
-            // Custom message: rebuild server list UI
-            if let Ok(global_servers) = VPN_SERVERS.lock() {
-                if let Some(servers) = &*global_servers {
+            // Custom message: rebuild server list UI, in the order picked in its header
+            if let Ok(mut global_servers) = VPN_SERVERS.lock() {
+                if let Some(servers) = global_servers.as_mut() {
+                    crate::ui::server_list::apply_sort(servers);
                     unsafe {
                         rebuild_server_list(hwnd, servers);
                     }
                 }
             }
+            // Saved settings a key migration couldn't place on the new list
+            if let Some(report) = crate::vpn::take_key_migration_report() {
+                unsafe { crate::ui::controls::message_box(hwnd, &report, "Server Settings", MB_OK | MB_ICONWARNING); }
+            }
+            if let Some(diff) = crate::listdiff::take_unseen() {
+                crate::request_menu_update();
+                unsafe { crate::ui::controls::message_box(hwnd, &diff.describe(), "Subscription Changes", MB_OK | MB_ICONINFORMATION); }
+            }
+            LRESULT(0)
+        }
+        _ if msg == WM_LATENCY_RESULT => {
+            // Refresh row texts only, so unsaved edits in the list are kept
+            unsafe {
+                if let Ok(list) = GetDlgItem(hwnd, ID_SERVER_LIST) {
+                    if let Ok(global_servers) = VPN_SERVERS.lock() {
+                        if let Some(servers) = global_servers.as_ref() {
+                            crate::ui::server_list::refresh(list, servers);
+                        }
+                    }
+                }
+                if wparam.0 == 1 {
+                    set_test_all_running(hwnd, false);
+                }
+            }
+            LRESULT(0)
+        }
+        _ if msg == WM_PREVIEW_RESULT => {
+            unsafe {
+                let text = Box::from_raw(lparam.0 as *mut String);
+                crate::ui::controls::message_box(hwnd, &text, "Preview Refresh", MB_OK | MB_ICONINFORMATION);
+            }
+            LRESULT(0)
+        }
+        _ if msg == WM_DRY_RUN_RESULT => {
+            unsafe {
+                set_test_server_running(hwnd, false);
+                let text = Box::from_raw(lparam.0 as *mut String);
+                crate::ui::controls::message_box(hwnd, &text, "Test Server", MB_OK | MB_ICONINFORMATION);
+            }
+            LRESULT(0)
+        }
+        _ if msg == WM_SPEED_RESULT => {
+            unsafe {
+                let text = Box::from_raw(lparam.0 as *mut String);
+                // The Speed column, without losing unsaved edits
+                if let Ok(list) = GetDlgItem(hwnd, ID_SERVER_LIST) {
+                    if let Ok(global_servers) = VPN_SERVERS.lock() {
+                        if let Some(servers) = global_servers.as_ref() {
+                            crate::ui::server_list::refresh(list, servers);
+                        }
+                    }
+                }
+                crate::ui::controls::message_box(hwnd, &text, "Speed Test", MB_OK | MB_ICONINFORMATION);
+            }
+            LRESULT(0)
+        }
+        _ if msg == WM_GEO_ASSETS_RESULT => {
+            unsafe {
+                let text = Box::from_raw(lparam.0 as *mut String);
+                crate::ui::controls::message_box(hwnd, &text, "Geo Assets", MB_OK | MB_ICONINFORMATION);
+            }
             LRESULT(0)
         }
         _ if msg == WM_DOWNLOAD_COMPLETE => {
@@ -1112,6 +1740,29 @@ unsafe extern "system" fn settings_window_proc(
             
             LRESULT(0)
         }
+        WM_NOTIFY => {
+            let header = unsafe { &*(lparam.0 as *const windows::Win32::UI::Controls::NMHDR) };
+            if header.idFrom == ID_SERVER_LIST as usize {
+                if let Some(server_index) = unsafe { crate::ui::server_list::on_notify(lparam) } {
+                    unsafe { show_server_edit_form(hwnd, server_index) };
+                }
+            }
+            LRESULT(0)
+        }
+        WM_CONTEXTMENU => {
+            // Right-click (or Shift+F10) on a server row, or on several selected rows
+            let list = unsafe { GetDlgItem(hwnd, ID_SERVER_LIST) };
+            if let Some(list) = list.ok().filter(|list| list.0 == wparam.0 as *mut _) {
+                let selected = unsafe { crate::ui::server_list::selected_servers(list) };
+                match selected.as_slice() {
+                    [] => {}
+                    [server_index] => unsafe { show_server_context_menu(hwnd, *server_index, lparam) },
+                    _ => unsafe { show_bulk_menu(hwnd, selected, lparam) },
+                }
+                return LRESULT(0);
+            }
+            unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
         WM_DESTROY => {
             println!("Settings window destroyed");
             LRESULT(0)
@@ -1124,300 +1775,1073 @@ unsafe extern "system" fn settings_window_proc(
     }
 }
 
-// Rebuild the server list with custom panels
+// Alias, note, local port(s), proxy type, listen address and UDP of one listed server; applied on Save
+// like the rest of the list
 #[cfg(windows)]
-unsafe fn rebuild_server_list(parent_hwnd: HWND, servers: &[VpnServer]) {
-    let container = unsafe { GetDlgItem(parent_hwnd, ID_SCROLL_CONTAINER) };
-    if container.is_err() || container.as_ref().unwrap().is_invalid() {
+unsafe fn show_server_edit_form(hwnd: HWND, server_index: usize) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let server = VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.as_ref().and_then(|list| list.get(server_index).cloned()));
+    let Some(server) = server else {
         return;
+    };
+    let fields = vec![
+        FormField::text(&format!("Name shown (empty = \"{}\"):", server.name), &server.alias),
+        FormField::text("Note:", &server.note),
+        FormField::text("Local proxy port (the SOCKS one for Both; \"auto\" = a free one at each start):",
+            &if server.auto_port { "auto".to_string() } else { server.local_port.to_string() }),
+        FormField::choice("Proxy type:", &["SOCKS", "HTTP", "Both"], &server.proxy_type),
+        FormField::text("HTTP port (Both only):", &if server.http_port == 0 { String::new() } else { server.http_port.to_string() }),
+        FormField::choice("Listen on:", &["Default", "127.0.0.1", "0.0.0.0"], if server.listen.is_empty() { "Default" } else { &server.listen }),
+        FormField::check("Relay UDP through SOCKS (games, QUIC, calls)", server.udp),
+    ];
+    
+    unsafe {
+        let title = server.display_name().to_string();
+        show_form(hwnd, &title, fields, Box::new(move |values| {
+            let alias = values[0].trim().to_string();
+            let note = values[1].trim().to_string();
+            let auto_port = values[2].trim().eq_ignore_ascii_case("auto");
+            let local_port: u16 = if auto_port {
+                0
+            } else {
+                values[2].trim().parse().ok().filter(|port| *port > 0)
+                    .ok_or_else(|| "Port must be a number from 1 to 65535, or auto".to_string())?
+            };
+            let proxy_type = values[3].clone();
+            let http_port: u16 = if auto_port {
+                0 // Picked along with the SOCKS port
+            } else if proxy_type == "Both" {
+                values[4].trim().parse().ok().filter(|port| *port > 0 && *port != local_port)
+                    .ok_or_else(|| "Both needs an HTTP port from 1 to 65535, other than the SOCKS port".to_string())?
+            } else {
+                values[4].trim().parse().unwrap_or(0)
+            };
+            // Warn before the user saves a combination xray can't serve
+            if let Some(warning) = server.proxy_type_warning(&proxy_type) {
+                let text = format!("{}\n\nKeep {} anyway?", warning, proxy_type);
+                if crate::ui::controls::message_box(hwnd, &text, "Proxy Type Warning", MB_OKCANCEL | MB_ICONWARNING) != IDOK {
+                    return Err(warning);
+                }
+            }
+            let listen = if values[5] == "Default" { String::new() } else { values[5].clone() };
+            if listen != server.listen && !listen.is_empty() {
+                let inbound = crate::config::Config::load().unwrap_or_default().inbound;
+                if let Some(warning) = crate::inbounds::exposure_warning(&listen, !inbound.username.is_empty()) {
+                    let text = format!("{}\n\nShare this server anyway?", warning);
+                    if crate::ui::controls::message_box(hwnd, &text, "LAN Sharing", MB_OKCANCEL | MB_ICONWARNING) != IDOK {
+                        return Err(warning);
+                    }
+                }
+            }
+            if let Ok(mut global_servers) = VPN_SERVERS.lock() {
+                if let Some(listed) = global_servers.as_mut().and_then(|servers| servers.get_mut(server_index)) {
+                    listed.alias = alias;
+                    listed.note = note;
+                    listed.local_port = local_port;
+                    listed.auto_port = auto_port;
+                    listed.proxy_type = proxy_type;
+                    listed.http_port = http_port;
+                    listed.listen = listen;
+                    listed.udp = values[6] == "true";
+                }
+                if let (Some(servers), Ok(list)) = (global_servers.as_ref(), GetDlgItem(hwnd, ID_SERVER_LIST)) {
+                    crate::ui::server_list::refresh(list, servers);
+                }
+            }
+            Ok(())
+        }));
     }
-    let container = container.unwrap();
+}
+
+// Edit hook commands run on server start/stop
+#[cfg(windows)]
+unsafe fn show_hooks_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let hooks = crate::config::Config::load().unwrap_or_default().hooks;
+    let fields = vec![
+        FormField::path("Run when a server starts (.ps1, .bat, .cmd, .exe):", &hooks.on_start),
+        FormField::path("Run when a server stops:", &hooks.on_stop),
+    ];
     
-    // Destroy all existing child windows in container
     unsafe {
-        let _ = EnumChildWindows(
-            container,
-            Some(destroy_child_window),
-            LPARAM(0),
-        );
+        show_form(hwnd, "Connect/Disconnect Hooks", fields, Box::new(|values| {
+            let mut config = crate::config::Config::load()?;
+            config.hooks.on_start = values[0].clone();
+            config.hooks.on_stop = values[1].clone();
+            config.save()
+        }));
     }
+}
+
+// Edit the primary port and its rotation schedule
+#[cfg(windows)]
+unsafe fn show_primary_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
     
-    let hinstance: HINSTANCE = unsafe { GetModuleHandleW(None).unwrap().into() };
+    let config = crate::config::Config::load().unwrap_or_default();
+    let mode = if config.rotation.mode == "random" { "Random" } else { "Round-robin" };
     
-    // Create font for controls (reduced to match main font)
-    let hfont = unsafe {
-        use windows::Win32::Graphics::Gdi::{CreateFontW, DEFAULT_CHARSET, OUT_DEFAULT_PRECIS, 
-            CLIP_DEFAULT_PRECIS, DEFAULT_QUALITY, DEFAULT_PITCH, FF_DONTCARE, FW_NORMAL};
-        CreateFontW(
-            30, // Reduced from 36
-            0, 0, 0,
-            FW_NORMAL.0 as i32,
-            0, 0, 0,
-            DEFAULT_CHARSET.0 as u32,
-            OUT_DEFAULT_PRECIS.0 as u32,
-            CLIP_DEFAULT_PRECIS.0 as u32,
-            DEFAULT_QUALITY.0 as u32,
-            (DEFAULT_PITCH.0 | FF_DONTCARE.0) as u32,
-            w!("Segoe UI"),
-        )
+    // "Any" or "Germany (DE)"; the code in parentheses is what gets saved
+    let country_label = |code: &str| format!("{} ({})", crate::geo::country_name(code), code);
+    let mut countries = crate::vpn::get_known_countries();
+    if !config.primary.country.is_empty() && !countries.contains(&config.primary.country) {
+        countries.push(config.primary.country.clone());
+    }
+    let mut country_options = vec!["Any".to_string()];
+    country_options.extend(countries.iter().map(|code| country_label(code)));
+    let country_refs: Vec<&str> = country_options.iter().map(|s| s.as_str()).collect();
+    let selected_country = if config.primary.country.is_empty() {
+        "Any".to_string()
+    } else {
+        country_label(&config.primary.country)
     };
     
-    // Get container width for dynamic checkbox width
-    let mut container_rect = RECT::default();
-    unsafe { GetClientRect(container, &mut container_rect).ok() };
-    let container_width = container_rect.right - container_rect.left;
+    let max_rate = if config.primary.max_rate > 0.0 {
+        config.primary.max_rate.to_string()
+    } else {
+        String::new()
+    };
     
-    const SERVER_ITEM_MARGIN: i32 = 10;
-    const LABEL_WIDTH: i32 = 130; // Increased from 100 to fit "Proxy Port:"
-    const PORT_EDIT_WIDTH: i32 = 90; // Increased from 80
-    const COMBO_WIDTH: i32 = 130; // Increased from 120
-    const RIGHT_CONTROLS_WIDTH: i32 = LABEL_WIDTH + PORT_EDIT_WIDTH + COMBO_WIDTH + 30; // +30 for spacing
-    let checkbox_width = container_width - RIGHT_CONTROLS_WIDTH - 20; // Dynamic width
+    let fields = vec![
+        FormField::check("Enable primary port (relays to one running server)", config.primary.enabled),
+        FormField::text("Primary port:", &config.primary.port.to_string()),
+        FormField::choice("Proxy type (only servers of this type are used):", &["SOCKS", "HTTP"], &config.primary.proxy_type),
+        FormField::choice("Country (fastest server in it is used):", &country_refs, &selected_country),
+        FormField::text("Highest rate multiplier, e.g. 1 (empty = any):", &max_rate),
+        FormField::choice("Pick the server after a refresh (manual keeps the preferred one):", crate::primary::POLICIES, &config.primary.policy),
+        FormField::check("Rotate to another healthy server on a schedule", config.rotation.enabled),
+        FormField::text("Rotate every N minutes:", &config.rotation.interval_minutes.to_string()),
+        FormField::choice("Rotation order:", &["Round-robin", "Random"], mode),
+        FormField::check("Sticky sessions (each program keeps its server between rotations)", config.rotation.sticky_sessions),
+        FormField::text("Sticky session length (minutes):", &config.rotation.sticky_minutes.to_string()),
+    ];
     
-    for (idx, server) in servers.iter().enumerate() {
-        let y_pos = idx as i32 * ROW_HEIGHT + SERVER_ITEM_MARGIN;
-        
-        // Checkbox (enabled/disabled) - dynamic width
-        let checkbox_text = format!("{} - {} ({}:{})\0", 
-            server.name, server.address, server.protocol, server.port);
-        let checkbox_text_wide: Vec<u16> = checkbox_text.encode_utf16().collect();
-        
-        let checkbox = unsafe {
-            CreateWindowExW(
-                WINDOW_EX_STYLE::default(),
-                w!("BUTTON"),
-                PCWSTR::from_raw(checkbox_text_wide.as_ptr()),
-                WS_CHILD | WS_VISIBLE | WINDOW_STYLE(BS_AUTOCHECKBOX as u32),
-                10,
-                y_pos,
-                checkbox_width,
-                CONTROL_HEIGHT,
-                container,
-                HMENU((ID_SERVER_CHECKBOX_BASE + idx as i32) as _),
-                hinstance,
-                None,
-            ).ok()
-        };
-        
-        if let Some(cb) = checkbox {
-            unsafe { SendMessageW(cb, WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(1)); }
-        }
-        
-        // Set checkbox state
-        if let Ok(checkbox) = unsafe { GetDlgItem(container, ID_SERVER_CHECKBOX_BASE + idx as i32) } {
-            unsafe {
-                SendMessageW(
-                    checkbox,
-                    BM_SETCHECK,
-                    WPARAM(if server.enabled { 1 } else { 0 }),
-                    LPARAM(0),
-                );
-            }
-        }
-        
-        let right_controls_x = 10 + checkbox_width + 10;
-        
-        // Label "Proxy Port:" with ID for resizing
-        let port_label: Vec<u16> = "Proxy Port:\0".encode_utf16().collect();
-        let port_lbl = unsafe {
-            CreateWindowExW(
-                WINDOW_EX_STYLE::default(),
-                w!("STATIC"),
-                PCWSTR::from_raw(port_label.as_ptr()),
-                WS_CHILD | WS_VISIBLE,
-                right_controls_x,
-                y_pos + 5,
-                LABEL_WIDTH,
-                CONTROL_HEIGHT,
-                container,
-                HMENU((ID_SERVER_LABEL_BASE + idx as i32) as _), // Add ID
-                hinstance,
-                None,
-            ).ok()
-        };
-        if let Some(lbl) = port_lbl {
-            unsafe { SendMessageW(lbl, WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(1)); }
-        }
-        
-        // Edit control for proxy port
-        let port_text_wide: Vec<u16> = format!("{}\0", server.local_port).encode_utf16().collect();
-        let port_edit = unsafe {
-            CreateWindowExW(
-                WS_EX_CLIENTEDGE,
-                w!("EDIT"),
-                PCWSTR::from_raw(port_text_wide.as_ptr()),
-                WS_CHILD | WS_VISIBLE | WS_BORDER | WINDOW_STYLE(ES_AUTOHSCROLL as u32 | ES_NUMBER as u32),
-                right_controls_x + LABEL_WIDTH + 5,
-                y_pos,
-                PORT_EDIT_WIDTH,
-                CONTROL_HEIGHT,
-                container,
-                HMENU((ID_SERVER_PORT_EDIT_BASE + idx as i32) as _),
-                hinstance,
-                None,
-            ).ok()
-        };
-        if let Some(edit) = port_edit {
-            unsafe { SendMessageW(edit, WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(1)); }
-        }
-        
-        // ComboBox for proxy type (SOCKS/HTTP)
-        let combo = unsafe {
-            CreateWindowExW(
-                WINDOW_EX_STYLE::default(),
-                w!("COMBOBOX"),
-                PCWSTR::null(),
-                WS_CHILD | WS_VISIBLE | WINDOW_STYLE(CBS_DROPDOWNLIST as u32 | WS_VSCROLL.0),
-                right_controls_x + LABEL_WIDTH + 5 + PORT_EDIT_WIDTH + 10,
-                y_pos,
-                COMBO_WIDTH,
-                200, // Dropdown height
-                container,
-                HMENU((ID_SERVER_PROXY_COMBO_BASE + idx as i32) as _),
-                hinstance,
-                None,
-            ).ok()
-        };
-        
-        if let Some(cb) = combo {
-            unsafe { SendMessageW(cb, WM_SETFONT, WPARAM(hfont.0 as usize), LPARAM(1)); }
+    unsafe {
+        show_form(hwnd, "Primary Port & Rotation", fields, Box::new(|values| {
+            let mut config = crate::config::Config::load()?;
+            config.primary.enabled = values[0] == "true";
+            config.primary.port = values[1].parse().ok().filter(|&p| p > 0)
+                .ok_or_else(|| "Primary port must be between 1 and 65535".to_string())?;
+            config.primary.proxy_type = values[2].clone();
+            config.primary.country = values[3].rsplit_once('(')
+                .map(|(_, code)| code.trim_end_matches(')').to_string())
+                .unwrap_or_default();
+            config.primary.max_rate = if values[4].is_empty() {
+                0.0
+            } else {
+                values[4].parse().ok().filter(|&r: &f32| r > 0.0)
+                    .ok_or_else(|| "Rate multiplier must be a positive number like 0.5 or 1".to_string())?
+            };
+            config.primary.policy = values[5].clone();
+            config.rotation.enabled = values[6] == "true";
+            config.rotation.interval_minutes = values[7].parse().ok().filter(|&m| m > 0)
+                .ok_or_else(|| "Rotation interval must be a positive number of minutes".to_string())?;
+            config.rotation.mode = if values[8] == "Random" { "random" } else { "round_robin" }.to_string();
+            config.rotation.sticky_sessions = values[9] == "true";
+            config.rotation.sticky_minutes = values[10].parse()
+                .map_err(|_| "Sticky session length must be a whole number of minutes".to_string())?;
             
-            // Add items
-            let socks_text: Vec<u16> = "SOCKS\0".encode_utf16().collect();
-            let http_text: Vec<u16> = "HTTP\0".encode_utf16().collect();
+            let clash = config.server_settings.values().any(|s| s.local_port == config.primary.port);
+            if config.primary.enabled && clash {
+                return Err(format!("Port {} is already used by a server", config.primary.port));
+            }
             
-            unsafe {
-                SendMessageW(cb, CB_ADDSTRING, WPARAM(0), LPARAM(socks_text.as_ptr() as isize));
-                SendMessageW(cb, CB_ADDSTRING, WPARAM(0), LPARAM(http_text.as_ptr() as isize));
-                
-                // Set current selection
-                let sel_idx = if server.proxy_type == "SOCKS" { 0 } else { 1 };
-                SendMessageW(cb, CB_SETCURSEL, WPARAM(sel_idx), LPARAM(0));
+            config.save()?;
+            crate::primary::apply(&config);
+            crate::request_menu_update();
+            Ok(())
+        }));
+    }
+}
+
+// Edit automatic failover to a backup server
+#[cfg(windows)]
+unsafe fn show_failover_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let config = crate::config::Config::load().unwrap_or_default();
+    let fields = vec![
+        FormField::check("Switch a failing server's port to a backup server", config.failover.enabled),
+        FormField::text("Failed checks in a row before switching:", &config.failover.failures.to_string()),
+        FormField::check("Restart a server whose xray process exits (groups, or all servers with failover off)", config.supervision.auto_restart),
+        FormField::text("Restarts in a row before giving up:", &config.supervision.max_restarts.to_string()),
+    ];
+    
+    unsafe {
+        show_form(hwnd, "Failover", fields, Box::new(|values| {
+            let mut config = crate::config::Config::load()?;
+            config.failover.enabled = values[0] == "true";
+            config.failover.failures = values[1].trim().parse().ok().filter(|&n| n > 0)
+                .ok_or_else(|| "Failed checks must be a positive whole number".to_string())?;
+            config.supervision.auto_restart = values[2] == "true";
+            config.supervision.max_restarts = values[3].trim().parse()
+                .map_err(|_| "Restarts must be a whole number".to_string())?;
+            config.save()
+        }));
+    }
+}
+
+// Edit log file writing, rotation and the disk cap
+#[cfg(windows)]
+unsafe fn show_logs_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let config = crate::config::Config::load().unwrap_or_default();
+    let folder = crate::logfiles::logs_dir()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_else(|| "the config folder".to_string());
+    let fields = vec![
+        FormField::check(&format!("Write xray output and server events to {}", folder), config.logs.enabled),
+        FormField::text("Start a new file at (MB):", &config.logs.max_file_mb.to_string()),
+        FormField::text("Delete the oldest files past (MB in total):", &config.logs.max_total_mb.to_string()),
+    ];
+    
+    unsafe {
+        show_form(hwnd, "Log Files", fields, Box::new(|values| {
+            let mut config = crate::config::Config::load()?;
+            config.logs.enabled = values[0] == "true";
+            config.logs.max_file_mb = values[1].trim().parse().ok().filter(|&n| n > 0)
+                .ok_or_else(|| "File size must be a positive whole number of MB".to_string())?;
+            config.logs.max_total_mb = values[2].trim().parse().ok().filter(|&n| n > 0)
+                .ok_or_else(|| "Total size must be a positive whole number of MB".to_string())?;
+            if config.logs.max_total_mb < config.logs.max_file_mb {
+                return Err("The total size must be at least one file".to_string());
             }
-        }
+            config.save()?;
+            crate::logfiles::apply(&config.logs);
+            Ok(())
+        }));
     }
+}
+
+// Edit TUN mode: which server carries all traffic, the adapter stack and LAN bypass
+#[cfg(all(windows, feature = "tun"))]
+unsafe fn show_tun_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    const DEFAULT_SERVER: &str = "Primary port or healthiest running server";
+    let config = crate::config::Config::load().unwrap_or_default();
+    let mut servers: Vec<(String, String)> = VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.as_ref().map(|list| {
+            list.iter().map(|s| (s.get_server_key(), format!("{} ({})", s.display_name(), s.get_server_key()))).collect()
+        }))
+        .unwrap_or_default();
+    servers.insert(0, (String::new(), DEFAULT_SERVER.to_string()));
+    let labels: Vec<&str> = servers.iter().map(|(_, label)| label.as_str()).collect();
+    let selected = servers.iter()
+        .find(|(key, _)| *key == config.tun.server)
+        .map(|(_, label)| label.clone())
+        .unwrap_or_else(|| DEFAULT_SERVER.to_string());
+    let admin_note = if crate::tun::is_elevated() { "" } else { " (needs administrator)" };
+    
+    let fields = vec![
+        FormField::check(&format!("Send all system traffic through a server{}", admin_note), config.tun.enabled),
+        FormField::choice("Server:", &labels, &selected),
+        FormField::choice("Network stack:", crate::tun::STACKS, &config.tun.stack),
+        FormField::check("Keep local network (LAN) traffic off the tunnel", config.tun.bypass_lan),
+    ];
+    
+    unsafe {
+        show_form(hwnd, "TUN Mode", fields, Box::new(move |values| {
+            let mut config = crate::config::Config::load()?;
+            config.tun.server = servers.iter()
+                .find(|(_, label)| *label == values[1])
+                .map(|(key, _)| key.clone())
+                .unwrap_or_default();
+            config.tun.stack = values[2].clone();
+            config.tun.bypass_lan = values[3] == "true";
+            config.tun.enabled = values[0] == "true";
+            // Saved either way; without admin rights the tray offers the elevated restart
+            config.save()?;
+            if config.tun.enabled && !crate::tun::is_elevated() {
+                return Err(format!("Saved. {}: turn it on from the tray to restart as administrator.", crate::tun::NEEDS_ADMIN));
+            }
+            crate::tun::apply(&config)
+        }));
+    }
+}
+
+// Global system proxy toggle, WinHTTP and the PAC file; single servers are marked from their context menu
+#[cfg(windows)]
+unsafe fn show_system_proxy_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let config = crate::config::Config::load().unwrap_or_default();
+    let fields = vec![
+        FormField::check("Keep the system proxy on: set it at startup and move it when its server stops", config.system_proxy.enabled),
+        FormField::check("Also set the WinHTTP proxy for services (needs administrator, HTTP ports only)", config.system_proxy.winhttp),
+        FormField::check(&format!("Serve a PAC file ({})", crate::pac::PAC_PATH), config.pac.enabled),
+        FormField::text("PAC port:", &config.pac.port.to_string()),
+        FormField::text("Sent direct by the PAC file (domains, *.wildcards, IPv4 CIDRs):", &config.pac.bypass.join(", ")),
+        FormField::check("Also send the direct entries of the routing rules and preset direct", config.pac.preset_direct),
+        FormField::check("Use the PAC file as the system proxy (auto-config URL)", config.pac.register),
+    ];
+    
+    unsafe {
+        show_form(hwnd, "System Proxy", fields, Box::new(|values| {
+            let mut config = crate::config::Config::load()?;
+            config.system_proxy.winhttp = values[1] == "true";
+            let pac = crate::config::PacSettings {
+                enabled: values[2] == "true",
+                port: values[3].parse().ok().filter(|&p| p > 0)
+                    .ok_or_else(|| "PAC port must be between 1 and 65535".to_string())?,
+                bypass: values[4].split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|e| !e.is_empty())
+                    .map(|e| e.to_string())
+                    .collect(),
+                preset_direct: values[5] == "true",
+                register: values[6] == "true",
+            };
+            if pac.enabled && (config.server_settings.values().any(|s| s.local_port == pac.port)
+                || (config.primary.enabled && config.primary.port == pac.port)) {
+                return Err(format!("Port {} is already used by a server or the primary port", pac.port));
+            }
+            config.pac = pac;
+            config.save()?;
+            crate::pac::apply(&config.pac);
+            // A registered PAC file takes the place of the fixed proxy
+            if config.pac.enabled && config.pac.register {
+                crate::request_menu_update();
+                return Ok(());
+            }
+            let result = crate::sysproxy::set_enabled(values[0] == "true");
+            crate::request_menu_update();
+            result
+        }));
+    }
+}
+
+// Geo asset source and auto-update; saving checks for a new release in the background
+#[cfg(windows)]
+unsafe fn show_geo_assets_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let config = crate::config::Config::load().unwrap_or_default();
+    let status = crate::geoassets::status(&config.xray_binary_path);
+    let source = if config.geo_assets.source.is_empty() { crate::geoassets::DEFAULT_SOURCE } else { &config.geo_assets.source };
+    let fields = vec![
+        FormField::text("GitHub releases page with geoip.dat and geosite.dat:", source),
+        FormField::check("Check for a new release once a day", config.geo_assets.auto_update),
+        FormField::check(&format!("Check now ({})", status), true),
+    ];
+    let hwnd_raw = hwnd.0 as isize;
     
-    // Update scroll range
-    let total_height = servers.len() as i32 * ROW_HEIGHT + SERVER_ITEM_MARGIN * 2;
-    let mut rect = RECT::default();
-    unsafe { GetClientRect(container, &mut rect).ok() };
-    let visible_height = rect.bottom - rect.top;
-    
-    if total_height > visible_height {
-        let si = SCROLLINFO {
-            cbSize: std::mem::size_of::<SCROLLINFO>() as u32,
-            fMask: SIF_RANGE | SIF_PAGE,
-            nMin: 0,
-            nMax: total_height,
-            nPage: visible_height as u32,
-            nPos: 0,
-            nTrackPos: 0,
+    unsafe {
+        show_form(hwnd, "Geo Assets", fields, Box::new(move |values| {
+            let source = values[0].trim().trim_end_matches('/');
+            if !source.is_empty() && !source.starts_with("https://") {
+                return Err("The releases page must be an https:// URL".to_string());
+            }
+            let mut config = crate::config::Config::load()?;
+            config.geo_assets.source = if source == crate::geoassets::DEFAULT_SOURCE { String::new() } else { source.to_string() };
+            config.geo_assets.auto_update = values[1] == "true";
+            config.save()?;
+            if values[2] == "true" {
+                update_geo_assets_in_background(HWND(hwnd_raw as *mut _));
+            }
+            Ok(())
+        }));
+    }
+}
+
+// Download new geo assets if there are any, then report what happened
+#[cfg(windows)]
+fn update_geo_assets_in_background(hwnd: HWND) {
+    let hwnd_raw = hwnd.0 as isize;
+    std::thread::spawn(move || {
+        let text = match crate::geoassets::update_now() {
+            Ok(Some(version)) => format!("Installed geo assets {}.", version),
+            Ok(None) => "The geo assets are up to date.".to_string(),
+            Err(e) => format!("Geo assets not updated: {}", e),
         };
-        unsafe { 
-            use windows::Win32::UI::Controls::SetScrollInfo;
-            SetScrollInfo(container, SB_VERT, &si, true); 
+        unsafe {
+            let text_ptr = Box::into_raw(Box::new(text));
+            let _ = PostMessageW(HWND(hwnd_raw as *mut _), WM_GEO_ASSETS_RESULT, WPARAM(0), LPARAM(text_ptr as isize));
         }
+    });
+}
+
+// DNS servers written into generated xray configs; running servers restart to use them
+#[cfg(windows)]
+unsafe fn show_dns_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let dns = crate::config::Config::load().unwrap_or_default().dns;
+    let fields = vec![
+        FormField::text("DNS-over-HTTPS URLs, through the server (comma-separated):", &dns.doh.join(", ")),
+        FormField::text("Plain DNS servers (IP, IP:port or localhost, comma-separated):", &dns.servers.join(", ")),
+        FormField::check("Block plaintext DNS (DoH only, no fallback to the system resolver)", dns.block_plaintext),
+    ];
+    
+    unsafe {
+        show_form(hwnd, "DNS", fields, Box::new(|values| {
+            let split = |text: &str| -> Vec<String> {
+                text.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect()
+            };
+            let dns = crate::config::DnsSettings {
+                doh: split(&values[0]),
+                servers: split(&values[1]),
+                block_plaintext: values[2] == "true",
+            };
+            crate::routing::validate_dns(&dns)?;
+            let mut config = crate::config::Config::load()?;
+            config.dns = dns;
+            config.save()?;
+            crate::routing::restart_running()
+        }));
     }
 }
 
-// Resize server list items without rebuilding (for window resize performance)
+// Read-only page with the running proxies and their QR codes, for others in the household
 #[cfg(windows)]
-unsafe fn resize_server_list_items(container: HWND) {
-    // Get container width
-    let mut container_rect = RECT::default();
-    unsafe { GetClientRect(container, &mut container_rect).ok() };
-    let container_width = container_rect.right - container_rect.left;
-    
-    const LABEL_WIDTH: i32 = 130;
-    const PORT_EDIT_WIDTH: i32 = 90;
-    const COMBO_WIDTH: i32 = 130;
-    const RIGHT_CONTROLS_WIDTH: i32 = LABEL_WIDTH + PORT_EDIT_WIDTH + COMBO_WIDTH + 30;
-    let checkbox_width = container_width - RIGHT_CONTROLS_WIDTH - 20;
-    let right_controls_x = 10 + checkbox_width + 10;
-    
-    // Get server count from global state
-    if let Ok(global_servers) = VPN_SERVERS.lock() {
-        if let Some(servers) = &*global_servers {
-            for idx in 0..servers.len() {
-                let y_pos = idx as i32 * ROW_HEIGHT + 10; // SERVER_ITEM_MARGIN = 10
-                
-                // Resize and reposition checkbox
-                if let Ok(checkbox) = unsafe { GetDlgItem(container, ID_SERVER_CHECKBOX_BASE + idx as i32) } {
-                    if !checkbox.is_invalid() {
-                        unsafe {
-                            SetWindowPos(
-                                checkbox,
-                                None,
-                                10,
-                                y_pos,
-                                checkbox_width,
-                                CONTROL_HEIGHT,
-                                SWP_NOZORDER,
-                            ).ok();
-                        }
+unsafe fn show_status_page_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let config = crate::config::Config::load().unwrap_or_default();
+    let settings = config.status_page;
+    let address = if settings.enabled {
+        format!("Serve the status page (now at {})", crate::statuspage::url(&settings))
+    } else {
+        "Serve the status page".to_string()
+    };
+    let fields = vec![
+        FormField::check(&address, settings.enabled),
+        FormField::text("Port:", &settings.port.to_string()),
+        FormField::check("Let other devices on the network open it", settings.lan),
+    ];
+    
+    unsafe {
+        show_form(hwnd, "Status Page", fields, Box::new(|values| {
+            let mut config = crate::config::Config::load()?;
+            let settings = crate::config::StatusPageSettings {
+                enabled: values[0] == "true",
+                port: values[1].trim().parse().ok().filter(|&p| p > 0)
+                    .ok_or_else(|| "Port must be between 1 and 65535".to_string())?,
+                lan: values[2] == "true",
+            };
+            if settings.enabled && (config.server_settings.values().any(|s| s.local_port == settings.port)
+                || (config.primary.enabled && config.primary.port == settings.port)
+                || (config.pac.enabled && config.pac.port == settings.port)) {
+                return Err(format!("Port {} is already used by a server, the primary port or the PAC file", settings.port));
+            }
+            config.status_page = settings;
+            config.save()?;
+            crate::statuspage::apply(&config.status_page);
+            Ok(())
+        }));
+    }
+}
+
+// Mux settings for every server, or with `server_key` for one server (which may use the default)
+#[cfg(windows)]
+unsafe fn show_mux_form(hwnd: HWND, server_key: Option<String>) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let config = crate::config::Config::load().unwrap_or_default();
+    let own = server_key.as_ref()
+        .and_then(|key| config.server_settings.get(key))
+        .and_then(|settings| settings.mux.clone());
+    let mux = own.clone().unwrap_or_else(|| config.mux.clone());
+    let mut fields = Vec::new();
+    if server_key.is_some() {
+        let selected = if own.is_some() { "Its own settings below" } else { "The default (Tools → Multiplexing)" };
+        fields.push(FormField::choice("This server uses:", &["The default (Tools → Multiplexing)", "Its own settings below"], selected));
+    }
+    fields.push(FormField::check("Multiplex connections to the server (Mux)", mux.enabled));
+    fields.push(FormField::text("TCP streams per connection (1-1024, 0 = default, -1 = none):", &mux.concurrency.to_string()));
+    fields.push(FormField::text("UDP (XUDP) streams per connection (1-1024, 0 = default, -1 = none):", &mux.xudp_concurrency.to_string()));
+    
+    let title = match &server_key {
+        Some(key) => format!("Multiplexing: {}", crate::vpn::get_server_name(key)),
+        None => "Multiplexing".to_string(),
+    };
+    unsafe {
+        show_form(hwnd, &title, fields, Box::new(move |values| {
+            let (use_default, values) = match &server_key {
+                Some(_) => (values[0].starts_with("The default"), &values[1..]),
+                None => (false, &values[..]),
+            };
+            let number = |text: &str, what: &str| -> Result<i32, String> {
+                let value = text.trim().parse().map_err(|_| format!("{} must be a whole number", what))?;
+                crate::mux::validate_concurrency(value, what).map(|_| value)
+            };
+            let mux = crate::config::MuxSettings {
+                enabled: values[0] == "true",
+                concurrency: number(&values[1], "TCP streams")?,
+                xudp_concurrency: number(&values[2], "UDP streams")?,
+            };
+            
+            let mut config = crate::config::Config::load()?;
+            match &server_key {
+                Some(key) => {
+                    let settings = config.server_settings.get_mut(key)
+                        .ok_or_else(|| "Save the server list first so this server has settings".to_string())?;
+                    settings.mux = if use_default { None } else { Some(mux) };
+                    config.save()?;
+                    if crate::xray_manager::get_server_info(key).is_some() {
+                        crate::start_single_server(key)?;
                     }
+                    Ok(())
                 }
-                
-                // Reposition label
-                if let Ok(label) = unsafe { GetDlgItem(container, ID_SERVER_LABEL_BASE + idx as i32) } {
-                    if !label.is_invalid() {
-                        unsafe {
-                            SetWindowPos(
-                                label,
-                                None,
-                                right_controls_x,
-                                y_pos + 5,
-                                LABEL_WIDTH,
-                                CONTROL_HEIGHT,
-                                SWP_NOZORDER,
-                            ).ok();
-                        }
-                    }
+                None => {
+                    config.mux = mux;
+                    config.save()?;
+                    crate::routing::restart_running()
                 }
-                
-                // Reposition port edit
-                if let Ok(port_edit) = unsafe { GetDlgItem(container, ID_SERVER_PORT_EDIT_BASE + idx as i32) } {
-                    if !port_edit.is_invalid() {
-                        unsafe {
-                            SetWindowPos(
-                                port_edit,
-                                None,
-                                right_controls_x + LABEL_WIDTH + 5,
-                                y_pos,
-                                PORT_EDIT_WIDTH,
-                                CONTROL_HEIGHT,
-                                SWP_NOZORDER,
-                            ).ok();
-                        }
-                    }
+            }
+        }));
+    }
+}
+
+// uTLS fingerprint and TLS handshake fragmenting of one server
+#[cfg(windows)]
+unsafe fn show_dpi_form(hwnd: HWND, server_key: String) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    const FROM_LINK: &str = "As in the share link";
+    let config = crate::config::Config::load().unwrap_or_default();
+    let Some(settings) = config.server_settings.get(&server_key) else {
+        unsafe {
+            crate::ui::controls::message_box(hwnd, "Save the server list first so this server has settings.",
+                "TLS Fingerprint & Fragment", MB_OK | MB_ICONINFORMATION);
+        }
+        return;
+    };
+    let fragment = settings.fragment.clone().unwrap_or_default();
+    let fingerprints: Vec<&str> = std::iter::once(FROM_LINK).chain(crate::dpi::FINGERPRINTS).collect();
+    let fingerprint = if settings.fingerprint.is_empty() { FROM_LINK } else { settings.fingerprint.as_str() };
+    let fields = vec![
+        FormField::choice("TLS fingerprint to imitate:", &fingerprints, fingerprint),
+        FormField::check("Split the TLS handshake into fragments", settings.fragment.is_some()),
+        FormField::text("Packets (tlshello, or a range of writes like 1-3):", &fragment.packets),
+        FormField::text("Fragment length, bytes (like 100-200):", &fragment.length),
+        FormField::text("Interval between fragments, ms (like 10-20):", &fragment.interval),
+    ];
+    
+    let title = format!("TLS: {}", crate::vpn::get_server_name(&server_key));
+    unsafe {
+        show_form(hwnd, &title, fields, Box::new(move |values| {
+            let fragment = crate::config::FragmentSettings {
+                packets: values[2].trim().to_string(),
+                length: values[3].trim().to_string(),
+                interval: values[4].trim().to_string(),
+            };
+            let fragment = if values[1] == "true" {
+                crate::dpi::validate_fragment(&fragment)?;
+                Some(fragment)
+            } else {
+                None
+            };
+            let mut config = crate::config::Config::load()?;
+            let settings = config.server_settings.get_mut(&server_key)
+                .ok_or_else(|| format!("Server {} not found", server_key))?;
+            settings.fingerprint = if values[0] == FROM_LINK { String::new() } else { values[0].clone() };
+            settings.fragment = fragment;
+            config.save()?;
+            if crate::xray_manager::get_server_info(&server_key).is_some() {
+                crate::start_single_server(&server_key)?;
+            }
+            Ok(())
+        }));
+    }
+}
+
+// Server one server dials through (proxy chain), from the listed servers
+#[cfg(windows)]
+unsafe fn show_upstream_form(hwnd: HWND, server_key: String) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    const DIRECT: &str = "None (connect directly)";
+    let config = crate::config::Config::load().unwrap_or_default();
+    let Some(settings) = config.server_settings.get(&server_key) else {
+        unsafe {
+            crate::ui::controls::message_box(hwnd, "Save the server list first so this server has settings.",
+                "Upstream Server", MB_OK | MB_ICONINFORMATION);
+        }
+        return;
+    };
+    // Names can repeat across subscriptions, so each choice carries its key
+    let candidates: Vec<(String, String)> = VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.clone())
+        .unwrap_or_default()
+        .iter()
+        .map(|server| (server.get_server_key(), format!("{} ({})", server.display_name(), server.get_server_key())))
+        .filter(|(key, _)| *key != server_key)
+        .collect();
+    let choices: Vec<&str> = std::iter::once(DIRECT).chain(candidates.iter().map(|(_, label)| label.as_str())).collect();
+    let current = candidates.iter()
+        .find(|(key, _)| *key == settings.upstream)
+        .map_or(DIRECT, |(_, label)| label.as_str());
+    let fields = vec![FormField::choice("Reach this server through:", &choices, current)];
+    
+    let title = format!("Upstream: {}", crate::vpn::get_server_name(&server_key));
+    unsafe {
+        show_form(hwnd, &title, fields, Box::new(move |values| {
+            let upstream = candidates.iter()
+                .find(|(_, label)| *label == values[0])
+                .map(|(key, _)| key.clone())
+                .unwrap_or_default();
+            if !upstream.is_empty() {
+                crate::chain::check_upstream(&server_key, &upstream)?;
+            }
+            let mut config = crate::config::Config::load()?;
+            let settings = config.server_settings.get_mut(&server_key)
+                .ok_or_else(|| format!("Server {} not found", server_key))?;
+            settings.upstream = upstream;
+            config.save()?;
+            if crate::xray_manager::get_server_info(&server_key).is_some() {
+                crate::start_single_server(&server_key)?;
+            }
+            Ok(())
+        }));
+    }
+}
+
+// Default listen address of server inbounds and the login asked on the LAN (or everywhere)
+#[cfg(windows)]
+unsafe fn show_lan_sharing_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let settings = crate::config::Config::load().unwrap_or_default().inbound;
+    let fields = vec![
+        FormField::choice("Servers listen on (0.0.0.0 = whole network):", &["127.0.0.1", "0.0.0.0"], &settings.listen),
+        FormField::text("User name for network clients (empty = none):", &settings.username),
+        FormField::text("Password:", &settings.password),
+        FormField::check("Ask for it on 127.0.0.1 too (other users of this PC)", settings.auth_local),
+    ];
+    
+    unsafe {
+        show_form(hwnd, "LAN Sharing", fields, Box::new(move |values| {
+            let settings = crate::config::InboundSettings {
+                listen: values[0].clone(),
+                username: values[1].trim().to_string(),
+                password: values[2].clone(),
+                auth_local: values[3] == "true",
+            };
+            if !settings.username.is_empty() && settings.password.is_empty() {
+                return Err("Enter a password for the user name, or clear both".to_string());
+            }
+            if settings.auth_local && settings.username.is_empty() {
+                return Err("Set a user name and password to ask for on 127.0.0.1".to_string());
+            }
+            if settings.auth_local && !crate::config::Config::load().unwrap_or_default().inbound.auth_local {
+                let text = "Every app on this computer will need the user name and password for the server ports.\n\
+                    The system proxy and the primary port can't send them, so they skip these servers.\n\n\
+                    Ask for the login on 127.0.0.1 too?";
+                if crate::ui::controls::message_box(hwnd, text, "LAN Sharing", MB_OKCANCEL | MB_ICONWARNING) != IDOK {
+                    return Err("Not changed".to_string());
                 }
-                
-                // Reposition combo (keep dropdown height at 200)
-                if let Ok(combo) = unsafe { GetDlgItem(container, ID_SERVER_PROXY_COMBO_BASE + idx as i32) } {
-                    if !combo.is_invalid() {
-                        unsafe {
-                            SetWindowPos(
-                                combo,
-                                None,
-                                right_controls_x + LABEL_WIDTH + 5 + PORT_EDIT_WIDTH + 10,
-                                y_pos,
-                                COMBO_WIDTH,
-                                200, // Keep dropdown height
-                                SWP_NOZORDER,
-                            ).ok();
-                        }
-                    }
+            }
+            if let Some(warning) = crate::inbounds::exposure_warning(&settings.listen, !settings.username.is_empty()) {
+                let text = format!("{}\n\nShare every server without its own address this way?", warning);
+                if crate::ui::controls::message_box(hwnd, &text, "LAN Sharing", MB_OKCANCEL | MB_ICONWARNING) != IDOK {
+                    return Err("Not changed".to_string());
+                }
+            }
+            let mut config = crate::config::Config::load()?;
+            config.inbound = settings;
+            config.save()?;
+            // Running servers pick up the address and login now
+            crate::routing::restart_running()
+        }));
+    }
+}
+
+// Daily latency sweep of the enabled servers, outside quiet hours
+#[cfg(windows)]
+unsafe fn show_scheduled_tests_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let settings = crate::config::Config::load().unwrap_or_default().scheduled_tests;
+    let fields = vec![
+        FormField::check("Test the enabled servers once a day", settings.enabled),
+        FormField::text("From (hour, 0-23, local time):", &settings.hour.to_string()),
+        FormField::text("Quiet hours start (0-23):", &settings.quiet_start.to_string()),
+        FormField::text("Quiet hours end (0-23, same as start = none):", &settings.quiet_end.to_string()),
+        FormField::check("Skip on metered connections", settings.skip_metered),
+    ];
+    
+    unsafe {
+        show_form(hwnd, "Scheduled Tests", fields, Box::new(|values| {
+            let hour = |text: &str, name: &str| -> Result<u8, String> {
+                text.trim().parse().ok().filter(|h| *h < 24)
+                    .ok_or_else(|| format!("{} must be an hour from 0 to 23", name))
+            };
+            let settings = crate::config::ScheduledTestSettings {
+                enabled: values[0] == "true",
+                hour: hour(&values[1], "The start hour")?,
+                quiet_start: hour(&values[2], "Quiet hours start")?,
+                quiet_end: hour(&values[3], "Quiet hours end")?,
+                skip_metered: values[4] == "true",
+            };
+            if settings.enabled && (settings.hour..24).all(|h| crate::sweep::in_quiet_hours(&settings, h)) {
+                return Err("The quiet hours cover the rest of the day after the start hour, so the test would never run".to_string());
+            }
+            let mut config = crate::config::Config::load()?;
+            config.scheduled_tests = settings;
+            config.save()
+        }));
+    }
+}
+
+// Exit IP watch of running servers; the IPs seen are listed by Test Connectivity
+#[cfg(windows)]
+unsafe fn show_exit_ip_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let settings = crate::config::Config::load().unwrap_or_default().exit_ip;
+    let fields = vec![
+        FormField::check("Check the exit IP of running servers", settings.enabled),
+        FormField::text("Lookup URL (answers with the IP as plain text):", &settings.url),
+        FormField::text("Check every (minutes):", &settings.interval_minutes.to_string()),
+        FormField::check("Warn when a server's exit IP changes", settings.alert),
+    ];
+    
+    unsafe {
+        show_form(hwnd, "Exit IP Alerts", fields, Box::new(|values| {
+            let url = values[1].trim().to_string();
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err("The lookup URL must start with http:// or https://".to_string());
+            }
+            let interval_minutes: u32 = values[2].trim().parse().ok().filter(|m| *m > 0)
+                .ok_or_else(|| "Check interval must be a whole number of minutes".to_string())?;
+            let mut config = crate::config::Config::load()?;
+            config.exit_ip = crate::config::ExitIpSettings {
+                enabled: values[0] == "true",
+                url,
+                interval_minutes,
+                alert: values[3] == "true",
+            };
+            config.save()
+        }));
+    }
+}
+
+// Tint, badge and status colors of this profile's tray icon; fields show the profile defaults until changed
+#[cfg(windows)]
+unsafe fn show_tray_icon_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    use crate::ui::tray::{default_badge, default_tint, format_color, parse_color};
+    
+    let config = crate::config::Config::load().unwrap_or_default();
+    let tint = config.tray_icon.tint.clone().unwrap_or_else(|| format_color(default_tint()));
+    let badge = config.tray_icon.badge.clone().unwrap_or_else(default_badge);
+    let fields = vec![
+        FormField::text("Star color (#RRGGBB):", &tint),
+        FormField::text("Badge (an emoji or up to 2 letters, empty = none):", &badge),
+        FormField::check("Color the star by state (gray idle, green running, red crashed, blue updating)",
+            config.tray_icon.status_colors),
+    ];
+    
+    unsafe {
+        show_form(hwnd, "Tray Icon", fields, Box::new(|values| {
+            let tint = parse_color(&values[0])
+                .ok_or_else(|| "Star color must be written as #RRGGBB, e.g. #4285F4".to_string())?;
+            let badge = values[1].trim().to_string();
+            if badge.chars().count() > 8 {
+                return Err("Badge must be an emoji or a couple of letters".to_string());
+            }
+            let mut config = crate::config::Config::load()?;
+            // Defaults stay unset so a renamed profile file picks up its new color and initial
+            config.tray_icon.tint = Some(format_color(tint)).filter(|t| *t != format_color(default_tint()));
+            config.tray_icon.badge = Some(badge).filter(|b| *b != default_badge());
+            config.tray_icon.status_colors = values[2] == "true";
+            config.save()?;
+            crate::request_menu_update();
+            Ok(())
+        }));
+    }
+}
+
+// Pick a server, then edit its limits
+#[cfg(windows)]
+unsafe fn show_limits_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let servers: Vec<(String, String)> = VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.as_ref().map(|list| {
+            list.iter().map(|s| (s.get_server_key(), format!("{} ({})", s.display_name(), s.get_server_key()))).collect()
+        }))
+        .unwrap_or_default();
+    if servers.is_empty() {
+        unsafe { crate::ui::controls::message_box(hwnd, "Load a subscription first.", "Server Limits", MB_OK | MB_ICONINFORMATION); }
+        return;
+    }
+    
+    let labels: Vec<&str> = servers.iter().map(|(_, label)| label.as_str()).collect();
+    let fields = vec![FormField::choice("Server:", &labels, labels[0])];
+    unsafe {
+        show_form(hwnd, "Server Limits", fields, Box::new(move |values| {
+            let (key, _) = servers.iter()
+                .find(|(_, label)| *label == values[0])
+                .ok_or_else(|| "Select a server".to_string())?;
+            show_server_limits_form(hwnd, key);
+            Ok(())
+        }));
+    }
+}
+
+// Edit xray policy and local inbound limits for one server
+#[cfg(windows)]
+unsafe fn show_server_limits_form(hwnd: HWND, server_key: &str) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let config = crate::config::Config::load().unwrap_or_default();
+    let limits = config.server_settings.get(server_key)
+        .map(|s| s.limits.clone())
+        .unwrap_or_default();
+    let optional = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_default();
+    let fields = vec![
+        FormField::text("Max simultaneous connections (0 = unlimited):", &limits.max_connections.to_string()),
+        FormField::text("Bandwidth cap per direction, kbit/s (0 = unlimited):", &limits.bandwidth_kbps.to_string()),
+        FormField::text("Buffer size per connection, KB (empty = xray default):", &optional(limits.buffer_size_kb)),
+        FormField::text("Idle connection timeout, seconds (empty = xray default):", &optional(limits.conn_idle_secs)),
+        FormField::text("Handshake timeout, seconds (empty = xray default):", &optional(limits.handshake_secs)),
+    ];
+    
+    let title = format!("Limits: {}", crate::vpn::get_server_name(server_key));
+    let server_key = server_key.to_string();
+    unsafe {
+        show_form(hwnd, &title, fields, Box::new(move |values| {
+            let whole = |value: &str, what: &str| -> Result<u32, String> {
+                value.trim().parse().map_err(|_| format!("{} must be a whole number", what))
+            };
+            let optional = |value: &str, what: &str| -> Result<Option<u32>, String> {
+                if value.trim().is_empty() { Ok(None) } else { whole(value, what).map(Some) }
+            };
+            let limits = crate::config::ServerLimits {
+                max_connections: whole(&values[0], "Max connections")?,
+                bandwidth_kbps: whole(&values[1], "Bandwidth cap")?,
+                buffer_size_kb: optional(&values[2], "Buffer size")?,
+                conn_idle_secs: optional(&values[3], "Idle timeout")?,
+                handshake_secs: optional(&values[4], "Handshake timeout")?,
+            };
+            
+            let mut config = crate::config::Config::load()?;
+            if !config.server_settings.contains_key(&server_key) {
+                // Server not saved yet: take its current row from the settings window
+                let servers = VPN_SERVERS.lock().map_err(|e| e.to_string())?;
+                let server = servers.as_ref()
+                    .and_then(|list| list.iter().find(|s| s.get_server_key() == server_key))
+                    .ok_or_else(|| format!("Server {} not found", server_key))?;
+                config.server_settings.insert(server_key.clone(), crate::settings_model::row_settings(server, None));
+            }
+            if let Some(settings) = config.server_settings.get_mut(&server_key) {
+                settings.limits = limits;
+            }
+            config.save()?;
+            
+            // Restart so the new limits take effect
+            if crate::xray_manager::get_server_info(&server_key).is_some() {
+                crate::start_single_server(&server_key)?;
+            }
+            Ok(())
+        }));
+    }
+}
+
+// Save the server table as CSV or JSON
+#[cfg(windows)]
+unsafe fn export_server_list(hwnd: HWND) {
+    let filters = [("CSV (*.csv)", "*.csv"), ("JSON (*.json)", "*.json")];
+    let Some(path) = (unsafe { crate::ui::controls::browse_for_save_file(hwnd, "servers.csv", &filters) }) else {
+        return;
+    };
+    
+    let (text, style) = match crate::export::export_to_file(&path) {
+        Ok(count) => (format!("Exported {} servers to\n{}", count, path), MB_OK | MB_ICONINFORMATION),
+        Err(e) => (e, MB_OK | MB_ICONERROR),
+    };
+    unsafe { crate::ui::controls::message_box(hwnd, &text, "Export Server List", style); }
+}
+
+// Edit general options that don't have a place in the main window
+#[cfg(windows)]
+unsafe fn show_options_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let config = crate::config::Config::load().unwrap_or_default();
+    let user_base = crate::config::shared_port_base();
+    let shared_label = format!("Shared machine: use ports {}-{} for this Windows user", user_base, user_base + 99);
+    const STARTUP_MODES: &[&str] = &["Enabled servers", "Servers running at last exit"];
+    let startup_mode = if config.startup_mode == crate::session::RESUME { STARTUP_MODES[1] } else { STARTUP_MODES[0] };
+    let fields = vec![
+        FormField::check("Flush DNS cache after connecting", config.flush_dns_on_connect),
+        FormField::text("Refresh subscriptions every N hours (0 = off):", &config.subscription_refresh_hours.to_string()),
+        FormField::text("Connectivity test URL (http:// or https://):", &config.test.url),
+        FormField::text("Test timeout (seconds):", &config.test.timeout_secs.to_string()),
+        FormField::text("Health check interval (seconds, min 10):", &config.test.interval_secs.to_string()),
+        FormField::check(&shared_label, config.shared_machine),
+        FormField::path("sing-box binary (runs Hysteria2 servers):", &config.singbox_binary_path),
+        FormField::text("Servers started at once:", &config.start_concurrency.to_string()),
+        FormField::choice("On startup, start:", STARTUP_MODES, startup_mode),
+        FormField::text("Speed test file URL:", &config.test.speed_url),
+        FormField::text("Speed test length (seconds):", &config.test.speed_secs.to_string()),
+    ];
+    
+    unsafe {
+        show_form(hwnd, "Options", fields, Box::new(move |values| {
+            let mut config = crate::config::Config::load()?;
+            if values[0] == "true" && !config.flush_dns_on_connect {
+                crate::system::require_windows("DNS cache flush")?;
+            }
+            config.flush_dns_on_connect = values[0] == "true";
+            config.subscription_refresh_hours = values[1].parse()
+                .map_err(|_| "Refresh interval must be a whole number of hours".to_string())?;
+            crate::probe::validate_test_url(&values[2])?;
+            config.test.url = values[2].clone();
+            config.test.timeout_secs = values[3].parse().ok().filter(|&t| t > 0)
+                .ok_or_else(|| "Test timeout must be a positive number of seconds".to_string())?;
+            config.test.interval_secs = values[4].parse().ok().filter(|&i| i >= 10)
+                .ok_or_else(|| "Health check interval must be at least 10 seconds".to_string())?;
+            
+            config.singbox_binary_path = values[6].clone();
+            config.start_concurrency = values[7].parse().ok().filter(|&n| n > 0)
+                .ok_or_else(|| "Servers started at once must be a positive number".to_string())?;
+            config.startup_mode = if values[8] == STARTUP_MODES[1] { crate::session::RESUME.to_string() } else { String::new() };
+            crate::probe::validate_test_url(&values[9])?;
+            config.test.speed_url = values[9].clone();
+            config.test.speed_secs = values[10].parse().ok().filter(|secs| (1..=120).contains(secs))
+                .ok_or_else(|| "Speed test length must be 1 to 120 seconds".to_string())?;
+            
+            let shared_machine = values[5] == "true";
+            let moved_ports = shared_machine && !config.shared_machine;
+            if shared_machine != config.shared_machine {
+                config.shared_machine = shared_machine;
+                if moved_ports {
+                    config.move_ports_to_user_block()?;
                 }
             }
+            config.save()?;
+            
+            if moved_ports {
+                // Servers, primary port and API now listen on this user's ports
+                crate::restart_xray_servers();
+                #[cfg(feature = "api")]
+                crate::api::apply(&config.api);
+                crate::primary::apply(&config);
+                reload_subscriptions(hwnd);
+            }
+            if config.autostart && !crate::system::is_wine() {
+                // The Run entry passes --config in shared machine mode
+                crate::config::Config::set_autostart(true)?;
+            }
+            Ok(())
+        }));
+    }
+}
+
+// Before Save: refuse enabled servers that share a port, and ask before saving ports
+// another program holds, selecting the rows concerned
+#[cfg(windows)]
+unsafe fn confirm_ports(hwnd: HWND, servers: &[VpnServer]) -> bool {
+    let mark = |rows: &[usize]| unsafe {
+        if let Ok(list) = GetDlgItem(hwnd, ID_SERVER_LIST) {
+            crate::ui::server_list::select_servers(list, rows);
+        }
+    };
+    
+    if let Some((rows, description)) = crate::settings_model::shared_ports(servers) {
+        mark(&rows);
+        let text = format!("{}\n\nGive each enabled server its own port, or untick one of them, then save again.", description);
+        unsafe { crate::ui::controls::message_box(hwnd, &text, "Port Conflict", MB_OK | MB_ICONWARNING) };
+        return false;
+    }
+    
+    // Our own running servers hold their ports; anything else would fail their start
+    let (rows, taken) = crate::settings_model::taken_ports(servers);
+    if taken.is_empty() {
+        return true;
+    }
+    mark(&rows);
+    let text = format!("{}\n\nThese servers won't start until their ports are free. Save anyway?", taken.join("\n"));
+    unsafe { crate::ui::controls::message_box(hwnd, &text, "Port in Use", MB_OKCANCEL | MB_ICONWARNING) == IDOK }
+}
+
+// Tools → Clean Up Server Settings...: drop saved settings of servers the subscriptions
+// no longer list, now and optionally after every refresh
+#[cfg(windows)]
+unsafe fn show_cleanup_form(hwnd: HWND) {
+    use crate::ui::form_window::{show_form, FormField};
+    
+    let config = crate::config::Config::load().unwrap_or_default();
+    let days = if config.settings_retention_days == 0 { 30 } else { config.settings_retention_days };
+    let fields = vec![
+        FormField::text("Remove settings of servers missing from the subscriptions for at least (days, 0 = any):", &days.to_string()),
+        FormField::check("Also remove them automatically after each refresh", config.settings_retention_days > 0),
+    ];
+    
+    unsafe {
+        show_form(hwnd, "Clean Up Server Settings", fields, Box::new(move |values| {
+            let days: u32 = values[0].trim().parse()
+                .map_err(|_| "Days must be a whole number".to_string())?;
+            let automatic = values[1] == "true";
+            if automatic && days == 0 {
+                return Err("Automatic cleanup needs at least 1 day, so a server missing from one refresh keeps its settings".to_string());
+            }
+            let servers = VPN_SERVERS.lock().ok().and_then(|servers| servers.clone()).unwrap_or_default();
+            if servers.is_empty() {
+                return Err("Update the server list first; without it every saved server looks missing".to_string());
+            }
+            let mut config = crate::config::Config::load()?;
+            config.settings_retention_days = if automatic { days } else { 0 };
+            crate::prune::mark_seen(&config, &servers);
+            let stale = crate::prune::stale_keys(&config, &servers, days);
+            if stale.is_empty() {
+                config.save()?;
+                crate::ui::controls::message_box(hwnd, "No saved settings are that old.", "Clean Up Server Settings", MB_OK | MB_ICONINFORMATION);
+                return Ok(());
+            }
+            let mut listed: Vec<String> = stale.iter().take(15).cloned().collect();
+            if stale.len() > listed.len() {
+                listed.push(format!("... and {} more", stale.len() - listed.len()));
+            }
+            let text = format!("Remove the saved settings (port, alias, note, limits...) of {} server(s)?\n\n{}", stale.len(), listed.join("\n"));
+            if crate::ui::controls::message_box(hwnd, &text, "Clean Up Server Settings", MB_OKCANCEL | MB_ICONQUESTION) != IDOK {
+                return Err("Nothing was removed".to_string());
+            }
+            crate::prune::remove(&mut config, &stale)
+        }));
+    }
+}
+
+// Disable Test All while a run is in progress
+#[cfg(windows)]
+unsafe fn set_test_all_running(hwnd: HWND, running: bool) {
+    unsafe {
+        if let Ok(button) = GetDlgItem(hwnd, ID_TEST_ALL_BUTTON) {
+            let style = GetWindowLongW(button, GWL_STYLE);
+            let style = if running { style | WS_DISABLED.0 as i32 } else { style & !(WS_DISABLED.0 as i32) };
+            SetWindowLongW(button, GWL_STYLE, style);
+            let _ = InvalidateRect(button, None, true);
+            crate::ui::controls::set_control_text(hwnd, ID_TEST_ALL_BUTTON, if running { "Testing..." } else { "Test All" });
         }
     }
 }
 
-// Helper function to destroy child windows
+// Disable Test Selected while its throwaway xray runs
 #[cfg(windows)]
-unsafe extern "system" fn destroy_child_window(hwnd: HWND, _: LPARAM) -> BOOL {
-    unsafe { DestroyWindow(hwnd).ok() };
-    true.into()
+unsafe fn set_test_server_running(hwnd: HWND, running: bool) {
+    unsafe {
+        if let Ok(button) = GetDlgItem(hwnd, ID_TEST_SERVER_BUTTON) {
+            let style = GetWindowLongW(button, GWL_STYLE);
+            let style = if running { style | WS_DISABLED.0 as i32 } else { style & !(WS_DISABLED.0 as i32) };
+            SetWindowLongW(button, GWL_STYLE, style);
+            let _ = InvalidateRect(button, None, true);
+            crate::ui::controls::set_control_text(hwnd, ID_TEST_SERVER_BUTTON, if running { "Testing..." } else { "Test Selected" });
+        }
+    }
+}
+
+// Show `servers` in the server list
+#[cfg(windows)]
+unsafe fn rebuild_server_list(parent_hwnd: HWND, servers: &[VpnServer]) {
+    if let Ok(list) = unsafe { GetDlgItem(parent_hwnd, ID_SERVER_LIST) } {
+        unsafe { crate::ui::server_list::rebuild(list, servers) };
+    }
 }
 
 /// Download latest Xray-core binary from GitHub releases
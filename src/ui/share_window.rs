@@ -0,0 +1,249 @@
+#[cfg(windows)]
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE},
+        Graphics::Gdi::{SetBkMode, TRANSPARENT, HDC, GetStockObject, WHITE_BRUSH},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+#[cfg(windows)]
+use super::controls::*;
+
+use std::sync::Mutex;
+
+// Control IDs
+const ID_SHARE_LIST: i32 = 1401;
+const ID_SHARE_SELECT_ENABLED: i32 = 1402;
+const ID_SHARE_SELECT_NONE: i32 = 1403;
+const ID_SHARE_SAVE_BUTTON: i32 = 1404;
+const ID_SHARE_SERVE_BUTTON: i32 = 1405;
+const ID_SHARE_URL_EDIT: i32 = 1406;
+const ID_SHARE_CLOSE_BUTTON: i32 = 1407;
+const ID_SHARE_PAIR_V2RAYNG: i32 = 1408;
+const ID_SHARE_PAIR_SINGBOX: i32 = 1409;
+
+// Layout constants (match settings window)
+const MARGIN: i32 = 15;
+const FONT_SIZE: i32 = 32;
+const CONTROL_HEIGHT: i32 = 45;
+const WINDOW_WIDTH: i32 = 900;
+
+// Only one share window at a time (HWND stored as raw value)
+static SHARE_WINDOW: std::sync::atomic::AtomicIsize = std::sync::atomic::AtomicIsize::new(0);
+
+// Server keys in list box order: (key, enabled)
+static LISTED_SERVERS: Mutex<Vec<(String, bool)>> = Mutex::new(Vec::new());
+
+/// Open the share window, or focus it if already open
+#[cfg(windows)]
+pub unsafe fn show_share_window(owner: HWND) {
+    unsafe {
+        let existing = HWND(SHARE_WINDOW.load(std::sync::atomic::Ordering::Relaxed) as *mut _);
+        if !existing.is_invalid() && IsWindow(existing).as_bool() {
+            let _ = ShowWindow(existing, SW_RESTORE);
+            let _ = SetForegroundWindow(existing);
+            return;
+        }
+
+        let hinstance: HINSTANCE = GetModuleHandleW(None).unwrap().into();
+        register_window_class(w!("ShareWindowClass"), Some(share_window_proc), hinstance);
+
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("ShareWindowClass"),
+            w!("Share Servers"),
+            WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_VISIBLE,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            WINDOW_WIDTH,
+            860,
+            owner,
+            None,
+            hinstance,
+            None,
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                eprintln!("Failed to create share window: {}", e);
+                return;
+            }
+        };
+
+        SHARE_WINDOW.store(hwnd.0 as isize, std::sync::atomic::Ordering::Relaxed);
+        create_share_controls(hwnd, hinstance);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn create_share_controls(hwnd: HWND, hinstance: HINSTANCE) {
+    let hfont = unsafe { create_font(FONT_SIZE) };
+    let full_width = WINDOW_WIDTH - 2 * MARGIN - 20;
+    let half_width = (full_width - 10) / 2;
+
+    unsafe {
+        let mut y = MARGIN;
+        create_label(hwnd, hinstance, "Servers to share (Ctrl/Shift+click to select several):",
+            (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_multiselect_listbox(hwnd, hinstance, ID_SHARE_LIST, (MARGIN, y, full_width, 330), hfont);
+
+        y += 330 + MARGIN;
+        create_button(hwnd, hinstance, "Select Enabled", ID_SHARE_SELECT_ENABLED,
+            (MARGIN, y, half_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Select None", ID_SHARE_SELECT_NONE,
+            (MARGIN + half_width + 10, y, half_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + 10;
+        create_button(hwnd, hinstance, "Save to File...", ID_SHARE_SAVE_BUTTON,
+            (MARGIN, y, half_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Serve on LAN", ID_SHARE_SERVE_BUTTON,
+            (MARGIN + half_width + 10, y, half_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + 10;
+        create_button(hwnd, hinstance, "Pair v2rayNG (QR)...", ID_SHARE_PAIR_V2RAYNG,
+            (MARGIN, y, half_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Pair sing-box (QR)...", ID_SHARE_PAIR_SINGBOX,
+            (MARGIN + half_width + 10, y, half_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + MARGIN;
+        let url_label = format!("Subscription URL (works for {} minutes):", crate::share::SHARE_MINUTES);
+        create_label(hwnd, hinstance, &url_label, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_edit(hwnd, hinstance, "", ID_SHARE_URL_EDIT, (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + MARGIN;
+        create_button(hwnd, hinstance, "Close", ID_SHARE_CLOSE_BUTTON,
+            (full_width + MARGIN - 120, y, 110, CONTROL_HEIGHT), hfont);
+
+        fill_server_list(hwnd);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn fill_server_list(hwnd: HWND) {
+    let mut listed = Vec::new();
+    let mut items = Vec::new();
+    if let Ok(global_servers) = crate::vpn::VPN_SERVERS.lock() {
+        if let Some(servers) = global_servers.as_ref() {
+            for server in servers {
+                items.push(format!("{}  —  {}", server.name, server.get_server_key()));
+                listed.push((server.get_server_key(), server.enabled));
+            }
+        }
+    }
+    unsafe { set_listbox_items(hwnd, ID_SHARE_LIST, &items) };
+    if let Ok(mut servers) = LISTED_SERVERS.lock() {
+        *servers = listed;
+    }
+}
+
+// Keys of the selected servers
+#[cfg(windows)]
+unsafe fn selected_keys(hwnd: HWND) -> Result<Vec<String>, String> {
+    let selected = unsafe { get_listbox_selections(hwnd, ID_SHARE_LIST) };
+    let keys: Vec<String> = LISTED_SERVERS.lock()
+        .map(|servers| selected.iter().filter_map(|&i| servers.get(i).map(|(key, _)| key.clone())).collect())
+        .unwrap_or_default();
+    if keys.is_empty() {
+        return Err("Select at least one server.".to_string());
+    }
+    Ok(keys)
+}
+
+// Build the subscription from the selected servers
+#[cfg(windows)]
+unsafe fn selected_subscription(hwnd: HWND) -> Result<String, String> {
+    let keys = unsafe { selected_keys(hwnd)? };
+    let uris = crate::share::collect_uris(&keys);
+    if uris.is_empty() {
+        return Err("None of the selected servers are in the current subscriptions.".to_string());
+    }
+    Ok(crate::share::build_subscription(&uris))
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn share_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => {
+            let control_id = (wparam.0 & 0xFFFF) as i32;
+            let notification_code = (wparam.0 >> 16) & 0xFFFF;
+            if notification_code != 0 {
+                return LRESULT(0);
+            }
+
+            match control_id {
+                ID_SHARE_SELECT_ENABLED | ID_SHARE_SELECT_NONE => unsafe {
+                    let servers = LISTED_SERVERS.lock().map(|s| s.clone()).unwrap_or_default();
+                    for (index, (_, enabled)) in servers.iter().enumerate() {
+                        let selected = control_id == ID_SHARE_SELECT_ENABLED && *enabled;
+                        set_listbox_selected(hwnd, ID_SHARE_LIST, index, selected);
+                    }
+                },
+                ID_SHARE_SAVE_BUTTON => unsafe {
+                    let subscription = match selected_subscription(hwnd) {
+                        Ok(subscription) => subscription,
+                        Err(e) => {
+                            message_box(hwnd, &e, "Share Servers", MB_OK | MB_ICONWARNING);
+                            return LRESULT(0);
+                        }
+                    };
+                    let filters = [("Subscription (*.txt)", "*.txt"), ("All Files", "*.*")];
+                    if let Some(path) = browse_for_save_file(hwnd, "subscription.txt", &filters) {
+                        if let Err(e) = crate::share::write_to_file(&path, &subscription) {
+                            message_box(hwnd, &e, "Error", MB_OK | MB_ICONERROR);
+                        }
+                    }
+                },
+                ID_SHARE_SERVE_BUTTON => unsafe {
+                    let result = selected_subscription(hwnd).and_then(crate::share::serve);
+                    match result {
+                        Ok(url) => set_control_text(hwnd, ID_SHARE_URL_EDIT, &url),
+                        Err(e) => {
+                            message_box(hwnd, &e, "Share Servers", MB_OK | MB_ICONWARNING);
+                        }
+                    }
+                },
+                ID_SHARE_PAIR_V2RAYNG | ID_SHARE_PAIR_SINGBOX => unsafe {
+                    let app = if control_id == ID_SHARE_PAIR_V2RAYNG {
+                        crate::share::PairingApp::V2rayNg
+                    } else {
+                        crate::share::PairingApp::SingBox
+                    };
+                    let title = format!("Scan with {} (works once, for {} minutes)", app.label(), crate::share::SHARE_MINUTES);
+                    let result = selected_keys(hwnd)
+                        .and_then(|keys| crate::share::pair(app, &keys))
+                        .and_then(|code| crate::ui::qr_window::show_qr_window(hwnd, &title, &code));
+                    if let Err(e) = result {
+                        message_box(hwnd, &e, "Share Servers", MB_OK | MB_ICONWARNING);
+                    }
+                },
+                ID_SHARE_CLOSE_BUTTON => unsafe {
+                    let _ = DestroyWindow(hwnd);
+                },
+                _ => {}
+            }
+            LRESULT(0)
+        }
+        WM_CTLCOLORSTATIC => {
+            unsafe {
+                let hdc = HDC(wparam.0 as *mut _);
+                SetBkMode(hdc, TRANSPARENT);
+                LRESULT(GetStockObject(WHITE_BRUSH).0 as isize)
+            }
+        }
+        WM_DESTROY => {
+            // A served subscription keeps running until it expires
+            SHARE_WINDOW.store(0, std::sync::atomic::Ordering::Relaxed);
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
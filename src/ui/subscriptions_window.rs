@@ -0,0 +1,309 @@
+#[cfg(windows)]
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM, HINSTANCE},
+        Graphics::Gdi::{SetBkMode, TRANSPARENT, HDC, GetStockObject, WHITE_BRUSH},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+#[cfg(windows)]
+use super::controls::*;
+
+use crate::config::{Config, Subscription};
+
+// Control IDs
+const ID_SUB_LIST: i32 = 1301;
+const ID_SUB_NAME_EDIT: i32 = 1302;
+const ID_SUB_URL_EDIT: i32 = 1303;
+const ID_SUB_ADD_BUTTON: i32 = 1304;
+const ID_SUB_REPLACE_BUTTON: i32 = 1305;
+const ID_SUB_REMOVE_BUTTON: i32 = 1306;
+const ID_SUB_CLOSE_BUTTON: i32 = 1307;
+const ID_SUB_BROWSE_BUTTON: i32 = 1308;
+const ID_SUB_INCLUDE_EDIT: i32 = 1309;
+const ID_SUB_EXCLUDE_EDIT: i32 = 1310;
+
+// List box notification: selection changed
+const LBN_SELCHANGE: usize = 1;
+
+// Layout constants (match settings window)
+const MARGIN: i32 = 15;
+const FONT_SIZE: i32 = 32;
+const CONTROL_HEIGHT: i32 = 45;
+const LABEL_WIDTH: i32 = 130;
+const WINDOW_WIDTH: i32 = 900;
+
+// Only one subscriptions window at a time (HWND stored as raw value)
+static SUBSCRIPTIONS_WINDOW: std::sync::atomic::AtomicIsize = std::sync::atomic::AtomicIsize::new(0);
+
+/// Open the subscriptions window, or focus it if already open.
+/// The owner (settings window) is told to reload after every change.
+#[cfg(windows)]
+pub unsafe fn show_subscriptions_window(owner: HWND) {
+    unsafe {
+        let existing = HWND(SUBSCRIPTIONS_WINDOW.load(std::sync::atomic::Ordering::Relaxed) as *mut _);
+        if !existing.is_invalid() && IsWindow(existing).as_bool() {
+            let _ = ShowWindow(existing, SW_RESTORE);
+            let _ = SetForegroundWindow(existing);
+            return;
+        }
+
+        let hinstance: HINSTANCE = GetModuleHandleW(None).unwrap().into();
+        register_window_class(w!("SubscriptionsWindowClass"), Some(subscriptions_window_proc), hinstance);
+
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("SubscriptionsWindowClass"),
+            w!("Subscriptions"),
+            WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_VISIBLE,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            WINDOW_WIDTH,
+            820,
+            owner,
+            None,
+            hinstance,
+            None,
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                eprintln!("Failed to create subscriptions window: {}", e);
+                return;
+            }
+        };
+
+        SUBSCRIPTIONS_WINDOW.store(hwnd.0 as isize, std::sync::atomic::Ordering::Relaxed);
+        create_subscription_controls(hwnd, hinstance);
+    }
+}
+
+#[cfg(windows)]
+unsafe fn create_subscription_controls(hwnd: HWND, hinstance: HINSTANCE) {
+    let config = Config::load().unwrap_or_default();
+    let hfont = unsafe { create_font(FONT_SIZE) };
+    let full_width = WINDOW_WIDTH - 2 * MARGIN - 20;
+
+    unsafe {
+        let mut y = MARGIN;
+        create_label(hwnd, hinstance, "Subscriptions (servers from all of them are merged):",
+            (MARGIN, y, full_width, CONTROL_HEIGHT), hfont);
+        y += CONTROL_HEIGHT;
+        create_listbox(hwnd, hinstance, ID_SUB_LIST, (MARGIN, y, full_width, 250), hfont);
+
+        y += 250 + MARGIN;
+        create_label(hwnd, hinstance, "Name:", (MARGIN, y + 5, LABEL_WIDTH, CONTROL_HEIGHT), hfont);
+        create_edit(hwnd, hinstance, "", ID_SUB_NAME_EDIT,
+            (MARGIN + LABEL_WIDTH, y, full_width - LABEL_WIDTH, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + 10;
+        // URL, file:// URL or local file path
+        create_label(hwnd, hinstance, "URL:", (MARGIN, y + 5, LABEL_WIDTH, CONTROL_HEIGHT), hfont);
+        create_edit(hwnd, hinstance, "", ID_SUB_URL_EDIT,
+            (MARGIN + LABEL_WIDTH, y, full_width - LABEL_WIDTH - 130, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "File...", ID_SUB_BROWSE_BUTTON,
+            (MARGIN + full_width - 120, y, 120, CONTROL_HEIGHT), hfont);
+
+        // Name patterns, e.g. include "JP|SG", exclude "expire|traffic"
+        y += CONTROL_HEIGHT + 10;
+        create_label(hwnd, hinstance, "Include:", (MARGIN, y + 5, LABEL_WIDTH, CONTROL_HEIGHT), hfont);
+        if let Some(edit) = create_edit(hwnd, hinstance, "", ID_SUB_INCLUDE_EDIT,
+            (MARGIN + LABEL_WIDTH, y, full_width - LABEL_WIDTH, CONTROL_HEIGHT), hfont) {
+            set_cue_banner(edit, "Only servers whose name matches, e.g. JP|SG");
+        }
+        y += CONTROL_HEIGHT + 10;
+        create_label(hwnd, hinstance, "Exclude:", (MARGIN, y + 5, LABEL_WIDTH, CONTROL_HEIGHT), hfont);
+        if let Some(edit) = create_edit(hwnd, hinstance, "", ID_SUB_EXCLUDE_EDIT,
+            (MARGIN + LABEL_WIDTH, y, full_width - LABEL_WIDTH, CONTROL_HEIGHT), hfont) {
+            set_cue_banner(edit, "Leave out names that match, e.g. expire|traffic");
+        }
+
+        y += CONTROL_HEIGHT + MARGIN;
+        let button_width = (full_width - 20) / 3;
+        create_button(hwnd, hinstance, "Add", ID_SUB_ADD_BUTTON,
+            (MARGIN, y, button_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Replace Selected", ID_SUB_REPLACE_BUTTON,
+            (MARGIN + button_width + 10, y, button_width, CONTROL_HEIGHT), hfont);
+        create_button(hwnd, hinstance, "Remove Selected", ID_SUB_REMOVE_BUTTON,
+            (MARGIN + 2 * (button_width + 10), y, button_width, CONTROL_HEIGHT), hfont);
+
+        y += CONTROL_HEIGHT + MARGIN;
+        create_button(hwnd, hinstance, "Close", ID_SUB_CLOSE_BUTTON,
+            (full_width + MARGIN - 120, y, 110, CONTROL_HEIGHT), hfont);
+
+        refresh_subscription_list(hwnd, &config);
+    }
+}
+
+// Grey hint shown in an empty edit
+#[cfg(windows)]
+unsafe fn set_cue_banner(edit: HWND, text: &str) {
+    let cue: Vec<u16> = format!("{}\0", text).encode_utf16().collect();
+    unsafe { SendMessageW(edit, windows::Win32::UI::Controls::EM_SETCUEBANNER, WPARAM(1), LPARAM(cue.as_ptr() as isize)); }
+}
+
+#[cfg(windows)]
+unsafe fn refresh_subscription_list(hwnd: HWND, config: &Config) {
+    let items: Vec<String> = config.subscriptions.iter()
+        .map(|s| {
+            let mut item = format!("{}  —  {}", s.name, s.url);
+            if !s.include.is_empty() {
+                item.push_str(&format!("  (only {})", s.include));
+            }
+            if !s.exclude.is_empty() {
+                item.push_str(&format!("  (not {})", s.exclude));
+            }
+            item
+        })
+        .collect();
+    unsafe { set_listbox_items(hwnd, ID_SUB_LIST, &items) };
+}
+
+// Read the name/URL/filter fields; the name defaults to the URL's host or the file name
+#[cfg(windows)]
+unsafe fn read_subscription(hwnd: HWND) -> Result<Subscription, String> {
+    let url = unsafe { get_control_text(hwnd, ID_SUB_URL_EDIT) }.trim().to_string();
+    if url.is_empty() {
+        return Err("Enter a subscription URL.".to_string());
+    }
+    let include = unsafe { get_control_text(hwnd, ID_SUB_INCLUDE_EDIT) }.trim().to_string();
+    let exclude = unsafe { get_control_text(hwnd, ID_SUB_EXCLUDE_EDIT) }.trim().to_string();
+    for (field, pattern) in [("Include", &include), ("Exclude", &exclude)] {
+        if !pattern.is_empty() {
+            crate::vpn::pattern::compile(pattern)
+                .map_err(|e| format!("{} pattern \"{}\" is not a valid regular expression:\n\n{}", field, pattern, e))?;
+        }
+    }
+    let mut name = unsafe { get_control_text(hwnd, ID_SUB_NAME_EDIT) }.trim().to_string();
+    if name.is_empty() {
+        if let Some(path) = crate::config::local_subscription_path(&url) {
+            name = path.file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| "Local file".to_string());
+            return Ok(Subscription { name, url, include, exclude });
+        }
+        name = url.split("://").nth(1)
+            .and_then(|rest| rest.split(['/', '?', ':']).next())
+            .filter(|host| !host.is_empty())
+            .unwrap_or("Subscription")
+            .to_string();
+    }
+    Ok(Subscription { name, url, include, exclude })
+}
+
+// Save the edited list and have the settings window re-fetch servers
+#[cfg(windows)]
+unsafe fn save_and_reload(hwnd: HWND, config: &Config) {
+    match config.save() {
+        Ok(_) => unsafe {
+            refresh_subscription_list(hwnd, config);
+            if let Ok(owner) = GetWindow(hwnd, GW_OWNER) {
+                super::settings_window::reload_subscriptions(owner);
+            }
+        },
+        Err(e) => unsafe {
+            message_box(hwnd, &format!("Failed to save config:\n{}", e), "Error", MB_OK | MB_ICONERROR);
+        },
+    }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn subscriptions_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => {
+            let control_id = (wparam.0 & 0xFFFF) as i32;
+            let notification_code = (wparam.0 >> 16) & 0xFFFF;
+
+            // Selecting a subscription loads it into the edit fields
+            if control_id == ID_SUB_LIST && notification_code == LBN_SELCHANGE {
+                unsafe {
+                    let config = Config::load().unwrap_or_default();
+                    if let Some(sub) = get_listbox_selection(hwnd, ID_SUB_LIST).and_then(|i| config.subscriptions.get(i)) {
+                        set_control_text(hwnd, ID_SUB_NAME_EDIT, &sub.name);
+                        set_control_text(hwnd, ID_SUB_URL_EDIT, &sub.url);
+                        set_control_text(hwnd, ID_SUB_INCLUDE_EDIT, &sub.include);
+                        set_control_text(hwnd, ID_SUB_EXCLUDE_EDIT, &sub.exclude);
+                    }
+                }
+                return LRESULT(0);
+            }
+            if notification_code != 0 {
+                return LRESULT(0);
+            }
+
+            match control_id {
+                ID_SUB_ADD_BUTTON | ID_SUB_REPLACE_BUTTON => unsafe {
+                    let subscription = match read_subscription(hwnd) {
+                        Ok(subscription) => subscription,
+                        Err(e) => {
+                            message_box(hwnd, &e, "Subscriptions", MB_OK | MB_ICONWARNING);
+                            return LRESULT(0);
+                        }
+                    };
+                    let mut config = Config::load().unwrap_or_default();
+                    let selected = get_listbox_selection(hwnd, ID_SUB_LIST)
+                        .filter(|&i| i < config.subscriptions.len());
+
+                    if control_id == ID_SUB_REPLACE_BUTTON {
+                        let Some(index) = selected else {
+                            message_box(hwnd, "Select a subscription first.", "Subscriptions", MB_OK | MB_ICONWARNING);
+                            return LRESULT(0);
+                        };
+                        config.subscriptions[index] = subscription;
+                    } else {
+                        if config.subscriptions.iter().any(|s| s.url == subscription.url) {
+                            message_box(hwnd, "This URL is already in the list.", "Subscriptions", MB_OK | MB_ICONWARNING);
+                            return LRESULT(0);
+                        }
+                        config.subscriptions.push(subscription);
+                        set_control_text(hwnd, ID_SUB_NAME_EDIT, "");
+                        set_control_text(hwnd, ID_SUB_URL_EDIT, "");
+                        set_control_text(hwnd, ID_SUB_INCLUDE_EDIT, "");
+                        set_control_text(hwnd, ID_SUB_EXCLUDE_EDIT, "");
+                    }
+                    save_and_reload(hwnd, &config);
+                },
+                ID_SUB_REMOVE_BUTTON => unsafe {
+                    let mut config = Config::load().unwrap_or_default();
+                    let Some(index) = get_listbox_selection(hwnd, ID_SUB_LIST)
+                        .filter(|&i| i < config.subscriptions.len()) else {
+                        message_box(hwnd, "Select a subscription first.", "Subscriptions", MB_OK | MB_ICONWARNING);
+                        return LRESULT(0);
+                    };
+                    config.subscriptions.remove(index);
+                    save_and_reload(hwnd, &config);
+                },
+                ID_SUB_BROWSE_BUTTON => unsafe {
+                    // Node list exported to disk (air-gapped machines)
+                    if let Some(path) = browse_for_file(hwnd) {
+                        set_control_text(hwnd, ID_SUB_URL_EDIT, &path);
+                    }
+                },
+                ID_SUB_CLOSE_BUTTON => unsafe {
+                    let _ = DestroyWindow(hwnd);
+                },
+                _ => {}
+            }
+            LRESULT(0)
+        }
+        WM_CTLCOLORSTATIC => {
+            unsafe {
+                let hdc = HDC(wparam.0 as *mut _);
+                SetBkMode(hdc, TRANSPARENT);
+                LRESULT(GetStockObject(WHITE_BRUSH).0 as isize)
+            }
+        }
+        WM_DESTROY => {
+            SUBSCRIPTIONS_WINDOW.store(0, std::sync::atomic::Ordering::Relaxed);
+            LRESULT(0)
+        }
+        _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
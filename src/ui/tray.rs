@@ -1,8 +1,40 @@
 use tray_icon::{
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     TrayIconBuilder,
 };
 
+/// Menu id prefix for the server items ("server:VLESS://host:443"); clicking starts or stops that server
+pub const SERVER_TOGGLE_PREFIX: &str = "server:";
+
+/// Menu id prefix for the Switch server submenu ("switch:VLESS://host:443")
+pub const SWITCH_PREFIX: &str = "switch:";
+
+/// Menu id prefix for the primary port country items ("primary-country:DE", empty code = any)
+pub const PRIMARY_COUNTRY_PREFIX: &str = "primary-country:";
+
+/// Menu id of the "Set as system proxy" toggle
+pub const SYSTEM_PROXY_ID: &str = "system-proxy";
+
+/// Menu id of the TUN mode toggle
+#[cfg(feature = "tun")]
+pub const TUN_MODE_ID: &str = "tun-mode";
+
+/// Menu id of "Stop guest sharing"
+pub const GUEST_STOP_ID: &str = "guest-stop";
+
+/// Menu ids of the loopback proxy items in the Troubleshooting submenu
+pub const LOOPBACK_START_ID: &str = "loopback-start";
+pub const LOOPBACK_STOP_ID: &str = "loopback-stop";
+
+/// Menu id of "View Logs" (the xray output window)
+pub const VIEW_LOGS_ID: &str = "view-logs";
+
+/// Menu id of "Test Connectivity" (fetch the test URL through every running server)
+pub const TEST_CONNECTIVITY_ID: &str = "test-connectivity";
+
+/// Menu id of the unopened subscription diff ("Subscriptions: 3 added, ...")
+pub const SUBSCRIPTION_DIFF_ID: &str = "subscription-diff";
+
 pub fn create_tray_menu_with_servers(
     settings_item: &MenuItem,
     quit_item: &MenuItem,
@@ -10,27 +42,159 @@ pub fn create_tray_menu_with_servers(
     // Create tray menu
     let tray_menu = Menu::new();
     
-    // Add running servers section
-    let running_servers = crate::xray_manager::get_running_servers();
-    if !running_servers.is_empty() {
-        // Get server names from global VPN_SERVERS
-        if let Ok(global_servers) = crate::vpn::VPN_SERVERS.lock() {
-            if let Some(servers) = global_servers.as_ref() {
-                for server in servers {
-                    let server_key = server.get_server_key();
-                    if running_servers.contains(&server_key) {
-                        let status_text = format!("✓ {} ({}:{})", server.name, server.proxy_type, server.local_port);
-                        let server_item = MenuItem::new(status_text, false, None);
-                        tray_menu.append(&server_item).unwrap();
+    // Favorites: one click moves the active port to a pinned server
+    let favorites: Vec<crate::vpn::VpnServer> = crate::vpn::VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|server| server.pinned && !server.hidden)
+        .collect();
+    if !favorites.is_empty() {
+        let active = crate::switcher::current().map(|(key, _)| key);
+        tray_menu.append(&MenuItem::new("Favorites", false, None)).unwrap();
+        for server in &favorites {
+            let server_key = server.get_server_key();
+            let id = format!("{}{}", SWITCH_PREFIX, server_key);
+            let checked = active.as_ref() == Some(&server_key);
+            tray_menu.append(&CheckMenuItem::with_id(id, format!("📌 {}", server.display_name()), true, checked, None)).unwrap();
+        }
+        tray_menu.append(&PredefinedMenuItem::separator()).unwrap();
+    }
+    
+    // Every configured server as a toggle: running ones first, healthiest first, then the rest,
+    // in a submenu per subscription or country when the server list is grouped
+    let group_by = crate::config::Config::load().unwrap_or_default().group_by;
+    let mut running_servers = crate::xray_manager::get_running_servers();
+    crate::health::sort_by_health(&mut running_servers);
+    let best = crate::health::best_server(&running_servers);
+    let mut listed = false;
+    if let Ok(global_servers) = crate::vpn::VPN_SERVERS.lock() {
+        if let Some(servers) = global_servers.as_ref() {
+            for server_key in &running_servers {
+                if let Some(server) = servers.iter().find(|s| &s.get_server_key() == server_key) {
+                    // Symbols alone mean nothing to a screen reader; say the state too
+                    let mark = if best.as_ref() == Some(server_key) { "★ Fastest:" } else { "✓ Running:" };
+                    let status_text = format!("{} {} ({}:{}){}", mark, server.display_name(), server.proxy_type, server.ports_text(),
+                        crate::health::latency_suffix(server_key));
+                    let id = format!("{}{}", SERVER_TOGGLE_PREFIX, server_key);
+                    tray_menu.append(&CheckMenuItem::with_id(id, status_text, true, true, None)).unwrap();
+                } else if crate::balancer::is_group_key(server_key) {
+                    if let Some(info) = crate::xray_manager::get_server_info(server_key) {
+                        let status_text = format!("⚖ Balanced group: {} ({}:{}){}", info.name, info.proxy_type, info.local_port,
+                            crate::health::latency_suffix(server_key));
+                        tray_menu.append(&MenuItem::new(status_text, false, None)).unwrap();
                     }
                 }
+                listed = true;
+            }
+            let stopped_item = |server: &crate::vpn::VpnServer| {
+                let status_text = format!("{} ({}:{})", server.display_name(), server.proxy_type, server.ports_text());
+                let id = format!("{}{}", SERVER_TOGGLE_PREFIX, server.get_server_key());
+                CheckMenuItem::with_id(id, status_text, true, false, None)
+            };
+            let stopped = servers.iter().enumerate()
+                .filter(|(_, server)| !server.hidden && !running_servers.contains(&server.get_server_key()));
+            if group_by.is_empty() {
+                for (_, server) in stopped {
+                    tray_menu.append(&stopped_item(server)).unwrap();
+                    listed = true;
+                }
+            } else {
+                for (heading, members) in crate::vpn::group_servers(stopped, &group_by) {
+                    let submenu = Submenu::new(format!("{} ({})", heading, members.len()), true);
+                    for index in members {
+                        submenu.append(&stopped_item(&servers[index])).unwrap();
+                    }
+                    tray_menu.append(&submenu).unwrap();
+                    listed = true;
+                }
             }
         }
+    }
+    if listed {
+        tray_menu.append(&create_switch_submenu(&group_by)).unwrap();
         tray_menu.append(&PredefinedMenuItem::separator()).unwrap();
     }
     
+    // Servers whose process died, until they run again
+    let dead = crate::supervisor::dead_servers();
+    if !dead.is_empty() {
+        for server in &dead {
+            let state = if server.restarting {
+                format!("restarting, attempt {}", server.attempts + 1)
+            } else if server.attempts > 0 {
+                format!("gave up after {} restarts", server.attempts)
+            } else {
+                "not restarted".to_string()
+            };
+            let status_text = format!("✗ Crashed: {} ({})", server.name, state);
+            tray_menu.append(&MenuItem::new(status_text, false, None)).unwrap();
+        }
+        tray_menu.append(&PredefinedMenuItem::separator()).unwrap();
+    }
+    
+    // Show where the primary port currently points
+    if let Ok(config) = crate::config::Config::load() {
+        if config.primary.enabled {
+            let target = crate::primary::get_target()
+                .map(|key| crate::vpn::get_server_name(&key))
+                .unwrap_or_else(|| "no server".to_string());
+            let primary_text = format!("Primary {}:{} → {}", config.primary.proxy_type, config.primary.port, target);
+            tray_menu.append(&MenuItem::new(primary_text, false, None)).unwrap();
+            tray_menu.append(&create_country_submenu(&config.primary.country)).unwrap();
+            tray_menu.append(&PredefinedMenuItem::separator()).unwrap();
+        }
+    }
+    
+    // Say when failover moved a port to a backup server, so a switch isn't silent
+    if let Some(notice) = crate::xray_manager::last_failover() {
+        tray_menu.append(&MenuItem::new(notice, false, None)).unwrap();
+        tray_menu.append(&PredefinedMenuItem::separator()).unwrap();
+    }
+    
+    // Add diagnostic hints (e.g. clock skew) so they're visible without opening settings
+    let hints = crate::diagnostics::get_all_hints();
+    if !hints.is_empty() {
+        for (server_key, hint) in hints {
+            let hint_text = format!("⚠ Warning, {}: {}", crate::vpn::get_server_name(&server_key), hint);
+            let hint_item = MenuItem::new(hint_text, false, None);
+            tray_menu.append(&hint_item).unwrap();
+        }
+        tray_menu.append(&PredefinedMenuItem::separator()).unwrap();
+    }
+    
+    // System proxy toggle; enabling checks the port first and may refuse
+    let system_proxy_text = match crate::sysproxy::active_port() {
+        Some(port) if crate::sysproxy::pac_active() => format!("System proxy (PAC file on port {})", port),
+        Some(port) => format!("System proxy (127.0.0.1:{})", port),
+        None => "Set as system proxy".to_string(),
+    };
+    let system_proxy_item = CheckMenuItem::with_id(SYSTEM_PROXY_ID, system_proxy_text, true, crate::sysproxy::active_port().is_some(), None);
+    tray_menu.append(&system_proxy_item).unwrap();
+    #[cfg(feature = "tun")]
+    {
+        let tun_port = crate::tun::active_port();
+        let tun_text = match tun_port {
+            Some(port) => format!("TUN mode: all traffic → 127.0.0.1:{}", port),
+            None if !crate::tun::is_elevated() => "TUN mode (all traffic, needs administrator)".to_string(),
+            None => "TUN mode (all traffic)".to_string(),
+        };
+        tray_menu.append(&CheckMenuItem::with_id(TUN_MODE_ID, tun_text, true, tun_port.is_some(), None)).unwrap();
+    }
+    if let Some(guest) = crate::guest::active() {
+        let text = format!("Stop guest sharing of {} ({} min left)", guest.server_name, guest.minutes_left());
+        tray_menu.append(&MenuItem::with_id(GUEST_STOP_ID, text, true, None)).unwrap();
+    }
+    if let Some(diff) = crate::listdiff::unseen() {
+        let text = format!("Subscriptions: {}...", diff.summary());
+        tray_menu.append(&MenuItem::with_id(SUBSCRIPTION_DIFF_ID, text, true, None)).unwrap();
+    }
+    tray_menu.append(&MenuItem::with_id(TEST_CONNECTIVITY_ID, "Test Connectivity", !running_servers.is_empty(), None)).unwrap();
+    tray_menu.append(&create_troubleshooting_submenu()).unwrap();
+    
     // Append settings and quit items
     tray_menu.append_items(&[
+        &PredefinedMenuItem::separator(),
         settings_item,
         &PredefinedMenuItem::separator(),
         quit_item,
@@ -39,6 +203,105 @@ pub fn create_tray_menu_with_servers(
     tray_menu
 }
 
+/// Tray tooltip with the overall state, which screen readers announce on the icon
+pub fn tooltip_text() -> String {
+    let running = crate::xray_manager::get_running_servers().len();
+    let mut parts = vec![match running {
+        0 => "no servers running".to_string(),
+        1 => "1 server running".to_string(),
+        n => format!("{} servers running", n),
+    }];
+    if crate::safemode::is_active() {
+        parts.insert(0, "safe mode".to_string());
+    }
+    if crate::sysproxy::active_port().is_some() {
+        parts.push("system proxy on".to_string());
+    }
+    #[cfg(feature = "tun")]
+    if crate::tun::active_port().is_some() {
+        parts.push("TUN mode on".to_string());
+    }
+    let title = match crate::config::profile_name() {
+        Some(profile) => format!("VPN Manager ({})", profile),
+        None => "VPN Manager".to_string(),
+    };
+    // Windows cuts tooltips at 127 characters
+    format!("{}: {}", title, parts.join(", ")).chars().take(127).collect()
+}
+
+// "Troubleshooting" submenu: a proxy without a VPN server, to check an app's proxy settings
+fn create_troubleshooting_submenu() -> Submenu {
+    let submenu = Submenu::new("Troubleshooting", true);
+    submenu.append(&MenuItem::with_id(VIEW_LOGS_ID, "View logs", true, None)).unwrap();
+    let item = match crate::loopback::active() {
+        Some(loopback) => MenuItem::with_id(LOOPBACK_STOP_ID,
+            format!("Stop loopback proxy ({} 127.0.0.1:{})", loopback.proxy_type, loopback.port), true, None),
+        None => MenuItem::with_id(LOOPBACK_START_ID, "Start loopback proxy...", true, None),
+    };
+    submenu.append(&item).unwrap();
+    submenu
+}
+
+// "Switch server" submenu: move the active server's port to another server
+fn create_switch_submenu(group_by: &str) -> Submenu {
+    let active = crate::switcher::current();
+    let title = match &active {
+        Some((_, port)) => format!("Switch server on port {}", port),
+        None => "Switch server".to_string(),
+    };
+    let submenu = Submenu::new(title, true);
+    let switch_item = |server: &crate::vpn::VpnServer| {
+        let server_key = server.get_server_key();
+        let checked = active.as_ref().is_some_and(|(key, _)| *key == server_key);
+        let id = format!("{}{}", SWITCH_PREFIX, server_key);
+        CheckMenuItem::with_id(id, server.display_name(), true, checked, None)
+    };
+    if let Ok(global_servers) = crate::vpn::VPN_SERVERS.lock() {
+        let servers = global_servers.as_deref().unwrap_or_default();
+        let shown = servers.iter().enumerate().filter(|(_, server)| !server.hidden);
+        if group_by.is_empty() {
+            for (_, server) in shown {
+                submenu.append(&switch_item(server)).unwrap();
+            }
+        } else {
+            for (heading, members) in crate::vpn::group_servers(shown, group_by) {
+                let group = Submenu::new(heading, true);
+                for index in members {
+                    group.append(&switch_item(&servers[index])).unwrap();
+                }
+                submenu.append(&group).unwrap();
+            }
+        }
+    }
+    submenu
+}
+
+// "Primary country" submenu: pin the primary port to the fastest server in one country
+fn create_country_submenu(selected: &str) -> Submenu {
+    let title = if selected.is_empty() {
+        "Primary country: Any".to_string()
+    } else {
+        format!("Primary country: {}", crate::geo::country_name(selected))
+    };
+    let submenu = Submenu::new(title, true);
+    
+    let any_item = CheckMenuItem::with_id(PRIMARY_COUNTRY_PREFIX, "Any (fastest overall)", true, selected.is_empty(), None);
+    submenu.append(&any_item).unwrap();
+    submenu.append(&PredefinedMenuItem::separator()).unwrap();
+    
+    let mut countries = crate::vpn::get_known_countries();
+    if !selected.is_empty() && !countries.iter().any(|c| c == selected) {
+        countries.push(selected.to_string());
+    }
+    for code in countries {
+        let label = format!("{} ({})", crate::geo::country_name(&code), code);
+        let item = CheckMenuItem::with_id(format!("{}{}", PRIMARY_COUNTRY_PREFIX, code), label, true, code == selected, None);
+        submenu.append(&item).unwrap();
+    }
+    
+    submenu
+}
+
 pub fn create_tray_icon_with_servers(
     settings_item: &MenuItem,
     quit_item: &MenuItem,
@@ -46,13 +309,13 @@ pub fn create_tray_icon_with_servers(
     // Create menu
     let tray_menu = create_tray_menu_with_servers(settings_item, quit_item);
 
-    // Create icon (32x32 red square)
+    // Create icon in the profile's colors
     let icon = create_icon();
 
     // Create tray icon with context menu
     TrayIconBuilder::new()
         .with_menu(Box::new(tray_menu))
-        .with_tooltip("VPN Manager")
+        .with_tooltip(tooltip_text())
         .with_icon(icon)
         .build()
         .unwrap()
@@ -72,7 +335,7 @@ pub fn create_tray_icon(
         quit_item,
     ]).unwrap();
 
-    // Create icon (32x32 red square)
+    // Create icon in the profile's colors
     let icon = create_icon();
 
     // Create tray icon with context menu
@@ -84,16 +347,142 @@ pub fn create_tray_icon(
         .unwrap()
 }
 
-fn create_icon() -> tray_icon::Icon {
-    // Create yellow star icon 32x32
-    let width = 32;
-    let height = 32;
+// Star color of the default profile
+const DEFAULT_TINT: [u8; 3] = [255, 215, 0];
+
+// Colors --config profiles get by name when they don't set their own
+const PROFILE_TINTS: &[[u8; 3]] = &[
+    [66, 133, 244],  // Blue
+    [52, 168, 83],   // Green
+    [234, 67, 53],   // Red
+    [171, 71, 188],  // Purple
+    [255, 112, 67],  // Orange
+    [0, 172, 193],   // Teal
+];
+
+// Tray icons are drawn at 32x32 and scaled by the shell
+const ICON_SIZE: u32 = 32;
+
+/// What the star shows with `tray_icon.status_colors`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconState {
+    Idle,      // Nothing running: a gray outline
+    Connected, // Servers running: green
+    Crashed,   // A server died and hasn't run since: red
+    Updating,  // Subscriptions are being downloaded: blue
+}
+
+impl IconState {
+    fn color(self) -> [u8; 3] {
+        match self {
+            IconState::Idle => [158, 158, 158],
+            IconState::Connected => [52, 168, 83],
+            IconState::Crashed => [234, 67, 53],
+            IconState::Updating => [66, 133, 244],
+        }
+    }
+}
+
+/// State of the app as the tray icon shows it; an update outranks a crash, which outranks running servers
+pub fn icon_state() -> IconState {
+    if crate::vpn::is_updating() {
+        IconState::Updating
+    } else if !crate::supervisor::dead_servers().is_empty() {
+        IconState::Crashed
+    } else if crate::xray_manager::get_running_servers().is_empty() {
+        IconState::Idle
+    } else {
+        IconState::Connected
+    }
+}
+
+// Star color, badge and state (None = status colors off) the icon is drawn with
+type IconLook = ([u8; 3], String, Option<IconState>);
+
+// Look the icon was last drawn with, so menu updates only replace it when it changed
+static DRAWN_ICON: std::sync::Mutex<Option<IconLook>> = std::sync::Mutex::new(None);
+
+/// Read "#RRGGBB" (the # is optional)
+pub fn parse_color(text: &str) -> Option<[u8; 3]> {
+    let hex = text.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// "#RRGGBB" for a color
+pub fn format_color(color: [u8; 3]) -> String {
+    format!("#{:02X}{:02X}{:02X}", color[0], color[1], color[2])
+}
+
+/// Star color when the config sets none: gold for the default profile,
+/// a color picked from the name for --config profiles
+pub fn default_tint() -> [u8; 3] {
+    match crate::config::profile_name() {
+        Some(name) => {
+            // A profile keeps its color across runs
+            let hash = crate::config::stable_hash(&name.to_lowercase());
+            PROFILE_TINTS[(hash % PROFILE_TINTS.len() as u64) as usize]
+        }
+        None => DEFAULT_TINT,
+    }
+}
+
+/// Badge when the config sets none: the initial of a --config profile, nothing for the default one
+pub fn default_badge() -> String {
+    crate::config::profile_name()
+        .and_then(|name| name.chars().find(|c| c.is_alphanumeric()))
+        .map(|c| c.to_uppercase().collect())
+        .unwrap_or_default()
+}
+
+// Star color and badge the config asks for, with the profile defaults filled in
+fn icon_look(settings: &crate::config::TrayIconSettings) -> IconLook {
+    let tint = settings.tint.as_deref().and_then(parse_color).unwrap_or_else(default_tint);
+    let badge = settings.badge.clone().unwrap_or_else(default_badge);
+    let state = settings.status_colors.then(icon_state);
+    (tint, badge.trim().to_string(), state)
+}
+
+/// New icon if the state, tint or badge changed since it was last drawn
+pub fn updated_icon() -> Option<tray_icon::Icon> {
+    let settings = crate::config::Config::load().map(|c| c.tray_icon).unwrap_or_default();
+    let look = icon_look(&settings);
+    let mut drawn = DRAWN_ICON.lock().ok()?;
+    if drawn.as_ref() == Some(&look) {
+        return None;
+    }
+    *drawn = Some(look.clone());
+    Some(draw_icon(&look))
+}
+
+/// Tray icon of the current profile: a star in its tint with its badge in the corner
+pub fn create_icon() -> tray_icon::Icon {
+    let settings = crate::config::Config::load().map(|c| c.tray_icon).unwrap_or_default();
+    let look = icon_look(&settings);
+    if let Ok(mut drawn) = DRAWN_ICON.lock() {
+        *drawn = Some(look.clone());
+    }
+    draw_icon(&look)
+}
+
+// With a state the star takes its color and the profile tint goes to the badge;
+// an idle star is only an outline, so it reads without telling colors apart
+fn draw_icon((tint, badge, state): &IconLook) -> tray_icon::Icon {
+    let width = ICON_SIZE;
+    let height = ICON_SIZE;
     let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+    let fill = state.map_or(*tint, IconState::color);
 
     // Define colors
     let bg = [0, 0, 0, 0];           // Transparent background
-    let star = [255, 215, 0, 255];   // Gold/Yellow
-    let border = [218, 165, 32, 255]; // Darker gold
+    let outline = *state == Some(IconState::Idle);
+    let star = if outline { bg } else { [fill[0], fill[1], fill[2], 255] };
+    // How far the border reaches into the star
+    let border_depth = if outline { 2.0 } else { 0.5 };
+    let border = [shade(fill[0], 0.85), shade(fill[1], 0.85), shade(fill[2], 0.85), 255];
 
     let cx = 16.0;
     let cy = 16.0;
@@ -127,7 +516,7 @@ fn create_icon() -> tray_icon::Icon {
             
             // Check if point is inside star
             let is_star = dist <= target_radius;
-            let is_border = dist <= target_radius + 0.8 && dist > target_radius - 0.5;
+            let is_border = dist <= target_radius + 0.8 && dist > target_radius - border_depth;
             
             if is_star && !is_border {
                 rgba.extend_from_slice(&star);
@@ -139,5 +528,131 @@ fn create_icon() -> tray_icon::Icon {
         }
     }
 
+    if !badge.is_empty() {
+        draw_badge(&mut rgba, *tint, badge);
+    }
+
     tray_icon::Icon::from_rgba(rgba, width, height).expect("Failed to create icon")
 }
+
+fn shade(channel: u8, factor: f32) -> u8 {
+    (channel as f32 * factor).round() as u8
+}
+
+// Disc in the lower right corner, darker than the star, with the badge text in white
+fn draw_badge(rgba: &mut [u8], tint: [u8; 3], badge: &str) {
+    const BADGE_SIZE: u32 = 18;
+    let origin = ICON_SIZE - BADGE_SIZE;
+    let radius = BADGE_SIZE as f32 / 2.0;
+    let disc = [shade(tint[0], 0.45), shade(tint[1], 0.45), shade(tint[2], 0.45)];
+    let text = render_text(badge, BADGE_SIZE);
+
+    for y in 0..BADGE_SIZE {
+        for x in 0..BADGE_SIZE {
+            let dx = x as f32 + 0.5 - radius;
+            let dy = y as f32 + 0.5 - radius;
+            // Soft edge: coverage falls off over the last pixel of the radius
+            let coverage = (radius - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+            if coverage <= 0.0 {
+                continue;
+            }
+            let ink = text.as_ref().map(|mask| mask[(y * BADGE_SIZE + x) as usize] as f32 / 255.0).unwrap_or(0.0);
+            let i = (((origin + y) * ICON_SIZE + origin + x) * 4) as usize;
+            for (c, &channel) in disc.iter().enumerate() {
+                let badge_color = channel as f32 + (255.0 - channel as f32) * ink;
+                rgba[i + c] = (rgba[i + c] as f32 * (1.0 - coverage) + badge_color * coverage).round() as u8;
+            }
+            rgba[i + 3] = rgba[i + 3].max((coverage * 255.0).round() as u8);
+        }
+    }
+}
+
+// Coverage mask (0-255 per pixel, size x size) of `text` drawn centered in bold Segoe UI,
+// shrunk until it fits; emoji come out as outlines since GDI draws them in one color
+#[cfg(windows)]
+fn render_text(text: &str, size: u32) -> Option<Vec<u8>> {
+    use windows::core::w;
+    use windows::Win32::Foundation::{COLORREF, SIZE};
+    use windows::Win32::Graphics::Gdi::*;
+
+    let wide: Vec<u16> = text.encode_utf16().collect();
+    let size = size as i32;
+    unsafe {
+        let screen_dc = GetDC(None);
+        let memory_dc = CreateCompatibleDC(screen_dc);
+        let bitmap = CreateCompatibleBitmap(screen_dc, size, size);
+        let previous_bitmap = SelectObject(memory_dc, bitmap);
+        let _ = PatBlt(memory_dc, 0, 0, size, size, BLACKNESS);
+        SetBkMode(memory_dc, TRANSPARENT);
+        SetTextColor(memory_dc, COLORREF(0x00FF_FFFF));
+
+        let mut font_height = size - 4;
+        let mut extent = SIZE::default();
+        let mut font = HFONT::default();
+        while font_height >= 6 {
+            font = CreateFontW(
+                -font_height,
+                0, 0, 0,
+                FW_BOLD.0 as i32,
+                0, 0, 0,
+                DEFAULT_CHARSET.0 as u32,
+                OUT_DEFAULT_PRECIS.0 as u32,
+                CLIP_DEFAULT_PRECIS.0 as u32,
+                ANTIALIASED_QUALITY.0 as u32,
+                (DEFAULT_PITCH.0 | FF_SWISS.0) as u32,
+                w!("Segoe UI"),
+            );
+            SelectObject(memory_dc, font);
+            let _ = GetTextExtentPoint32W(memory_dc, &wide, &mut extent);
+            if extent.cx <= size - 2 {
+                break;
+            }
+            SelectObject(memory_dc, GetStockObject(DEFAULT_GUI_FONT));
+            let _ = DeleteObject(font);
+            font = HFONT::default();
+            font_height -= 1;
+        }
+        let drawn = !font.is_invalid()
+            && TextOutW(memory_dc, (size - extent.cx) / 2, (size - extent.cy) / 2, &wide).as_bool();
+
+        // Top-down 32-bit BGRA rows
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: size,
+                biHeight: -size,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; (size * size * 4) as usize];
+        let lines = if drawn {
+            GetDIBits(memory_dc, bitmap, 0, size as u32, Some(pixels.as_mut_ptr() as *mut _), &mut info, DIB_RGB_COLORS)
+        } else {
+            0
+        };
+
+        SelectObject(memory_dc, previous_bitmap);
+        if !font.is_invalid() {
+            let _ = DeleteObject(font);
+        }
+        let _ = DeleteObject(bitmap);
+        let _ = DeleteDC(memory_dc);
+        ReleaseDC(None, screen_dc);
+
+        if lines == 0 {
+            return None;
+        }
+        // White text on black: any channel is the coverage
+        Some(pixels.chunks_exact(4).map(|bgra| bgra[0].max(bgra[1]).max(bgra[2])).collect())
+    }
+}
+
+// Without GDI the badge is a plain disc
+#[cfg(not(windows))]
+fn render_text(_text: &str, _size: u32) -> Option<Vec<u8>> {
+    None
+}
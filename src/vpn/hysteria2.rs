@@ -0,0 +1,182 @@
+use serde_json::{json, Value};
+
+// Hysteria2 servers. xray can't dial hysteria2, so these nodes run under
+// sing-box instead: the share link is parsed here and turned into a sing-box
+// config with the same local SOCKS/HTTP inbound an xray node would get.
+
+/// Port used when the link doesn't name one
+const DEFAULT_PORT: u16 = 443;
+
+/// A parsed hysteria2:// (or hy2://) share link
+#[derive(Debug, Clone)]
+pub struct Hysteria2Link {
+    pub password: String,
+    pub address: String,
+    pub port: u16, // First port given; part of the server key
+    pub hop_ports: Vec<(u16, u16)>, // Port hopping ranges ("443,20000-30000"), empty if none
+    pub name: String,
+    pub sni: String,
+    pub insecure: bool,
+    pub obfs_password: Option<String>, // Salamander obfuscation
+}
+
+/// True for hysteria2:// and hy2:// share links
+pub fn is_hysteria2_uri(uri: &str) -> bool {
+    uri.starts_with("hysteria2://") || uri.starts_with("hy2://")
+}
+
+/// Parse hysteria2://auth@host:port/?sni=..&insecure=1&obfs=salamander&obfs-password=..#name
+pub fn parse(uri: &str) -> Result<Hysteria2Link, String> {
+    let rest = uri.strip_prefix("hysteria2://")
+        .or_else(|| uri.strip_prefix("hy2://"))
+        .ok_or_else(|| "Not a hysteria2:// link".to_string())?;
+    let (rest, name) = rest.split_once('#').unwrap_or((rest, ""));
+    let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let (password, host_port) = match authority.rsplit_once('@') {
+        Some((auth, host_port)) => (crate::config::percent_decode(auth), host_port),
+        None => (String::new(), authority),
+    };
+
+    // [IPv6]:ports or host:ports; ports may be "443", "443,5000-6000" or "20000-30000"
+    let (address, ports) = match host_port.strip_prefix('[') {
+        Some(bracketed) => {
+            let (address, after) = bracketed.split_once(']')
+                .ok_or_else(|| "Unclosed [ in hysteria2 address".to_string())?;
+            (address, after.strip_prefix(':').unwrap_or(""))
+        }
+        None => host_port.split_once(':').unwrap_or((host_port, "")),
+    };
+    if address.is_empty() {
+        return Err("hysteria2 link has no server address".to_string());
+    }
+
+    let mut port = None;
+    let mut hop_ports = Vec::new();
+    for part in ports.split(',').filter(|p| !p.is_empty()) {
+        let range = match part.split_once('-') {
+            Some((start, end)) => (start.parse::<u16>().ok(), end.parse::<u16>().ok()),
+            None => (part.parse::<u16>().ok(), part.parse::<u16>().ok()),
+        };
+        let (Some(start), Some(end)) = range else {
+            return Err(format!("Invalid hysteria2 port \"{}\"", part));
+        };
+        port.get_or_insert(start);
+        if start != end || ports.contains(',') {
+            hop_ports.push((start.min(end), start.max(end)));
+        }
+    }
+
+    let mut sni = String::new();
+    let mut insecure = false;
+    let mut obfs = None;
+    let mut obfs_password = None;
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = crate::config::percent_decode(value);
+        match key {
+            "sni" | "peer" => sni = value,
+            "insecure" | "allowInsecure" => insecure = value == "1" || value == "true",
+            "obfs" => obfs = Some(value),
+            "obfs-password" => obfs_password = Some(value),
+            _ => {}
+        }
+    }
+    match obfs.as_deref() {
+        None | Some("") | Some("none") => obfs_password = None,
+        Some("salamander") => {}
+        Some(other) => return Err(format!("Unsupported hysteria2 obfuscation \"{}\"", other)),
+    }
+
+    let name = crate::config::percent_decode(name);
+    Ok(Hysteria2Link {
+        password,
+        address: address.to_string(),
+        port: port.unwrap_or(DEFAULT_PORT),
+        hop_ports,
+        name: if name.is_empty() { address.to_string() } else { name },
+        sni,
+        insecure,
+        obfs_password,
+    })
+}
+
+/// sing-box config for a hysteria2 link with local SOCKS and/or HTTP inbounds
+/// (the same ports `create_json_config` would give xray)
+pub fn singbox_config(uri: &str, socks_port: Option<u16>, http_port: Option<u16>) -> Result<String, String> {
+    let link = parse(uri)?;
+
+    let mut inbounds = Vec::new();
+    if let Some(port) = socks_port {
+        inbounds.push(json!({ "type": "socks", "tag": "socks-in", "listen": "127.0.0.1", "listen_port": port }));
+    }
+    if let Some(port) = http_port {
+        inbounds.push(json!({ "type": "http", "tag": "http-in", "listen": "127.0.0.1", "listen_port": port }));
+    }
+
+    let sni = if link.sni.is_empty() { &link.address } else { &link.sni };
+    let mut outbound = json!({
+        "type": "hysteria2",
+        "tag": "proxy",
+        "server": link.address,
+        "server_port": link.port,
+        "password": link.password,
+        "tls": { "enabled": true, "server_name": sni, "insecure": link.insecure, "alpn": ["h3"] },
+    });
+    if !link.hop_ports.is_empty() {
+        let ranges: Vec<String> = link.hop_ports.iter().map(|(start, end)| format!("{}:{}", start, end)).collect();
+        outbound["server_ports"] = json!(ranges);
+    }
+    if let Some(password) = &link.obfs_password {
+        outbound["obfs"] = json!({ "type": "salamander", "password": password });
+    }
+
+    let config: Value = json!({
+        "log": { "level": "warn", "timestamp": true },
+        "inbounds": inbounds,
+        "outbounds": [outbound, { "type": "direct", "tag": "direct" }],
+        "route": { "final": "proxy" },
+    });
+    serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to write sing-box config: {}", e))
+}
+
+/// Built-in handler for hysteria2:// and hy2:// links
+pub struct Hysteria2Handler;
+
+impl super::protocol::ProtocolHandler for Hysteria2Handler {
+    fn protocol(&self) -> &'static str {
+        "HYSTERIA2"
+    }
+
+    fn matches(&self, uri: &str) -> bool {
+        is_hysteria2_uri(uri)
+    }
+
+    fn parse(&self, uri: &str) -> Result<super::protocol::LinkInfo, String> {
+        let link = parse(uri)?;
+        Ok(super::protocol::LinkInfo {
+            address: link.address,
+            port: link.port,
+            name: link.name,
+            transport: "quic".to_string(),
+        })
+    }
+
+    fn core_config(&self, uri: &str, socks_port: Option<u16>, http_port: Option<u16>) -> Result<String, String> {
+        singbox_config(uri, socks_port, http_port)
+    }
+
+    fn core(&self) -> super::protocol::Core {
+        super::protocol::Core::SingBox
+    }
+}
+
+/// Path of the sing-box binary that runs hysteria2 servers
+pub fn singbox_binary() -> Result<String, String> {
+    let config = crate::config::Config::load()?;
+    if config.singbox_binary_path.is_empty() {
+        return Err("Hysteria2 servers run under sing-box: set the sing-box binary in Tools → Options".to_string());
+    }
+    Ok(config.singbox_binary_path)
+}
@@ -0,0 +1,119 @@
+#[cfg(any(feature = "gui", feature = "stats"))]
+use std::collections::HashMap;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(any(feature = "gui", feature = "stats"))]
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use super::VpnServer;
+
+// Latency ("ping") tests for the server list. A running server is tested end
+// to end with an HTTP request through its local proxy (which also counts as a
+// health check); any other server gets a TCP connect to its address, which
+// measures the round trip without starting xray. Results are kept for the server
+// list and stored test runs; builds with neither only get the health checks.
+
+// Servers tested at once by test_all
+const TEST_THREADS: usize = 16;
+
+/// Outcome of the last latency test of a server
+#[cfg(any(feature = "gui", feature = "stats"))]
+#[derive(Debug, Clone)]
+pub struct LatencyResult {
+    pub result: Result<Duration, String>,
+    pub via_proxy: bool, // Request through the running local proxy; false = TCP connect to the server
+}
+
+// Last result per server key
+#[cfg(any(feature = "gui", feature = "stats"))]
+static RESULTS: LazyLock<Mutex<HashMap<String, LatencyResult>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Test one server now and remember the result
+pub fn test_server(server: &VpnServer, timeout: Duration) {
+    let key = server.get_server_key();
+    let (result, via_proxy) = if crate::xray_manager::get_server_info(&key).is_some() {
+        (crate::health::check_server(&key), true)
+    } else if server.protocol == "HYSTERIA2" || matches!(server.transport.as_str(), "kcp" | "quic") {
+        // Nothing listens on TCP there; only a request through the running server tells
+        (Err("UDP-based, start it to test".to_string()), false)
+    } else {
+        (tcp_connect_time(&server.address, server.port, timeout), false)
+    };
+    #[cfg(any(feature = "gui", feature = "stats"))]
+    if let Ok(mut results) = RESULTS.lock() {
+        results.insert(key, LatencyResult { result, via_proxy });
+    }
+    #[cfg(not(any(feature = "gui", feature = "stats")))]
+    let _ = (result, via_proxy);
+}
+
+/// Test servers in parallel, calling `on_result` with each server key as its result comes in.
+/// Blocks until all are done, so call it from a background thread.
+pub fn test_all(servers: &[VpnServer], on_result: &(dyn Fn(&str) + Sync)) {
+    let test = crate::config::Config::load().unwrap_or_default().test;
+    let timeout = Duration::from_secs(test.timeout_secs.max(1));
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..TEST_THREADS.min(servers.len()) {
+            scope.spawn(|| {
+                while let Some(server) = servers.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    test_server(server, timeout);
+                    on_result(&server.get_server_key());
+                }
+            });
+        }
+    });
+    #[cfg(feature = "stats")]
+    record_results(servers);
+}
+
+// Keep this run's results for the week-over-week comparison
+#[cfg(feature = "stats")]
+fn record_results(servers: &[VpnServer]) {
+    use crate::results::{Measurement, RunKind};
+    let results = servers.iter()
+        .filter_map(|server| {
+            let key = server.get_server_key();
+            let latency = get_result(&key)?;
+            // "Start it to test" isn't a measurement
+            if matches!(&latency.result, Err(e) if e.contains("UDP")) {
+                return None;
+            }
+            let ms = latency.result.ok().map(|elapsed| elapsed.as_millis() as u32);
+            Some((key, Measurement { ms, proxy: latency.via_proxy }))
+        })
+        .collect();
+    crate::results::record_run(RunKind::Latency, results);
+}
+
+/// Last test result of a server, if it was tested
+#[cfg(any(feature = "gui", feature = "stats"))]
+pub fn get_result(server_key: &str) -> Option<LatencyResult> {
+    RESULTS.lock().ok()?.get(server_key).cloned()
+}
+
+/// Short text for the server list, e.g. "⏱ 85 ms" or "⏱ 40 ms (TCP)"
+#[cfg(feature = "gui")]
+pub fn label(server_key: &str) -> Option<String> {
+    let latency = get_result(server_key)?;
+    let method = if latency.via_proxy { "" } else { " (TCP)" };
+    Some(match latency.result {
+        Ok(elapsed) => format!("⏱ {} ms{}", elapsed.as_millis(), method),
+        Err(e) if e.contains("UDP") => "⏱ start to test".to_string(),
+        Err(_) => format!("⏱ failed{}", method),
+    })
+}
+
+// Time to open a TCP connection to the server (name resolution not counted)
+fn tcp_connect_time(host: &str, port: u16, timeout: Duration) -> Result<Duration, String> {
+    let address = (host, port).to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("No address for {}", host))?;
+    let started = Instant::now();
+    TcpStream::connect_timeout(&address, timeout)
+        .map_err(|e| format!("TCP connect failed: {}", e))?;
+    Ok(started.elapsed())
+}
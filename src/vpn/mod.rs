@@ -1,10 +1,28 @@
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::collections::HashSet;
 use serde::{Deserialize, Serialize};
 
+use crate::config::{local_subscription_path, Subscription};
+
+pub mod singbox;
+pub mod hysteria2;
+pub mod latency;
+#[cfg(feature = "gui")]
+pub mod speed;
+pub mod pattern;
+pub mod protocol;
+pub mod ssr;
+
 // Global state for VPN servers
 pub static VPN_SERVERS: Mutex<Option<Vec<VpnServer>>> = Mutex::new(None);
 
+// Subscription fetches in progress, for the tray icon
+static FETCHES: AtomicUsize = AtomicUsize::new(0);
+
+// What the last key migration couldn't map, until the settings window shows it
+static KEY_MIGRATION_REPORT: Mutex<Option<String>> = Mutex::new(None);
+
 // VPN server information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VpnServer {
@@ -13,123 +31,666 @@ pub struct VpnServer {
     pub port: u16,
     pub name: String,
     pub enabled: bool,
-    pub local_port: u16, // User-defined local port
-    pub proxy_type: String, // "HTTP" or "SOCKS"
+    pub local_port: u16, // User-defined local port; the SOCKS one with "Both"
+    pub proxy_type: String, // "HTTP", "SOCKS" or "Both"
+    #[serde(default)]
+    pub http_port: u16, // Second, HTTP port with "Both" (ServerSettings::http_port)
+    #[serde(default)]
+    pub auto_port: bool, // Ports picked at each start, local_port/http_port 0 (ServerSettings::auto_port)
+    #[serde(default)]
+    pub transport: String, // "tcp", "ws", "grpc", "kcp", "quic", ...
+    #[serde(default)]
+    pub subscription: String, // Name of the subscription the server came from
+    #[serde(default)]
+    pub country: String, // ISO country code parsed from the name, empty if unknown
+    #[serde(default)]
+    pub uri: String, // Share URI the server was parsed from, for copying and QR export
+    #[serde(default)]
+    pub rate: Option<f32>, // Billing rate multiplier parsed from the name ("x0.5"), None if not given
+    #[serde(default)]
+    pub tags: Vec<String>, // Line/feature tags parsed from the name ("IPLC", "Premium")
+    #[serde(default)]
+    pub alias: String, // User's own name for the server (ServerSettings::alias), empty if none
+    #[serde(default)]
+    pub note: String, // User's note (ServerSettings::note)
+    #[serde(default)]
+    pub pinned: bool, // Favorite (ServerSettings::pinned)
+    #[serde(default)]
+    pub hidden: bool, // Hidden from lists (ServerSettings::hidden)
+    #[serde(default)]
+    pub listen: String, // Own listen address (ServerSettings::listen), empty = the default
+    #[serde(default = "crate::config::default_true")]
+    pub udp: bool, // UDP on the SOCKS inbound (ServerSettings::udp)
 }
 
 impl VpnServer {
+    /// Name to show: the user's alias, or the provider's name without one
+    pub fn display_name(&self) -> &str {
+        if self.alias.is_empty() { &self.name } else { &self.alias }
+    }
+    
     /// Get unique server key for stable identification
     pub fn get_server_key(&self) -> String {
         format!("{}://{}:{}", self.protocol, self.address, self.port)
     }
+    
+    /// Local port(s) as shown to the user: "1080", or "1080 + 8080" for SOCKS + HTTP;
+    /// "auto", or "auto: 52310" with the ports it got while running
+    #[cfg(feature = "gui")]
+    pub fn ports_text(&self) -> String {
+        if self.auto_port {
+            return match crate::xray_manager::get_server_info(&self.get_server_key()) {
+                Some(info) if info.http_port != 0 => format!("auto: {} + {}", info.local_port, info.http_port),
+                Some(info) => format!("auto: {}", info.local_port),
+                None => "auto".to_string(),
+            };
+        }
+        if self.proxy_type == "Both" {
+            format!("{} + {}", self.local_port, self.http_port)
+        } else {
+            self.local_port.to_string()
+        }
+    }
+    
+    /// Warn about a local proxy type this server can't fully serve
+    #[cfg(feature = "gui")]
+    pub fn proxy_type_warning(&self, proxy_type: &str) -> Option<String> {
+        if proxy_type != "HTTP" {
+            return None;
+        }
+        
+        // xray's HTTP inbound only carries TCP (CONNECT); UDP traffic has no way in
+        match self.transport.as_str() {
+            "kcp" | "quic" => Some(format!(
+                "{} uses the UDP-based {} transport, but an HTTP proxy only carries TCP.\n\
+                UDP traffic (DNS, QUIC, games, calls) from apps won't go through it.\n\n\
+                Use SOCKS if you need UDP.",
+                self.display_name(), self.transport
+            )),
+            _ if self.protocol == "SOCKS" => Some(format!(
+                "{} is a SOCKS server; exposing it as HTTP drops UDP support.\n\n\
+                Use SOCKS if you need UDP.",
+                self.display_name()
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Look up a server's display name by key (falls back to the key itself)
+pub fn get_server_name(server_key: &str) -> String {
+    if let Ok(global_servers) = VPN_SERVERS.lock() {
+        if let Some(servers) = global_servers.as_ref() {
+            if let Some(server) = servers.iter().find(|s| s.get_server_key() == server_key) {
+                return server.display_name().to_string();
+            }
+        }
+    }
+    server_key.to_string()
+}
+
+/// Pin a server to the favorites or unpin it, saved right away
+#[cfg(feature = "gui")]
+pub fn set_pinned(server: &VpnServer, pinned: bool) -> Result<(), String> {
+    update_flag(server, |settings| settings.pinned = pinned, |listed| listed.pinned = pinned)
+}
+
+/// Hide a server from the lists or show it again, saved right away
+#[cfg(feature = "gui")]
+pub fn set_hidden(server: &VpnServer, hidden: bool) -> Result<(), String> {
+    update_flag(server, |settings| settings.hidden = hidden, |listed| listed.hidden = hidden)
+}
+
+// Save a change to a server's settings (starting from its row if it has none saved yet)
+// and make the same change to its entry in VPN_SERVERS
+#[cfg(feature = "gui")]
+fn update_flag(
+    server: &VpnServer,
+    save: impl FnOnce(&mut crate::config::ServerSettings),
+    list: impl FnOnce(&mut VpnServer),
+) -> Result<(), String> {
+    let mut config = crate::config::Config::load()?;
+    let key = server.get_server_key();
+    let settings = config.server_settings.entry(key.clone()).or_insert_with(|| crate::config::ServerSettings {
+        local_port: server.local_port,
+        proxy_type: server.proxy_type.clone(),
+        http_port: server.http_port,
+        auto_port: server.auto_port,
+        enabled: server.enabled,
+        limits: Default::default(),
+        system_proxy: false,
+        alias: server.alias.clone(),
+        note: server.note.clone(),
+        pinned: server.pinned,
+        hidden: server.hidden,
+        listen: server.listen.clone(),
+        udp: server.udp,
+        mux: None,
+        fingerprint: String::new(),
+        fragment: None,
+        upstream: String::new(),
+    });
+    save(settings);
+    config.save()?;
+    if let Ok(mut global_servers) = VPN_SERVERS.lock() {
+        if let Some(listed) = global_servers.iter_mut().flatten().find(|s| s.get_server_key() == key) {
+            list(listed);
+        }
+    }
+    crate::request_menu_update();
+    Ok(())
+}
+
+/// Get a server's country code by key (empty if unknown)
+pub fn get_server_country(server_key: &str) -> String {
+    if let Ok(global_servers) = VPN_SERVERS.lock() {
+        if let Some(servers) = global_servers.as_ref() {
+            if let Some(server) = servers.iter().find(|s| s.get_server_key() == server_key) {
+                return server.country.clone();
+            }
+        }
+    }
+    String::new()
+}
+
+/// Get a server's rate multiplier by key (1.0 if the name doesn't give one)
+pub fn get_server_rate(server_key: &str) -> f32 {
+    if let Ok(global_servers) = VPN_SERVERS.lock() {
+        if let Some(servers) = global_servers.as_ref() {
+            if let Some(server) = servers.iter().find(|s| s.get_server_key() == server_key) {
+                return server.rate.unwrap_or(1.0);
+            }
+        }
+    }
+    1.0
+}
+
+/// Countries of all known servers, sorted
+#[cfg(feature = "gui")]
+pub fn get_known_countries() -> Vec<String> {
+    let mut countries: Vec<String> = VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.as_ref().map(|list| {
+            list.iter()
+                .filter(|s| !s.country.is_empty())
+                .map(|s| s.country.clone())
+                .collect()
+        }))
+        .unwrap_or_default();
+    countries.sort();
+    countries.dedup();
+    countries
 }
 
-// Assign local ports to servers, preserving saved settings from config
-pub fn assign_local_ports(servers: &mut [VpnServer], saved_settings: &std::collections::HashMap<String, crate::config::ServerSettings>) {
+/// Heading a server is grouped under: its subscription, or its country with `group_by` = "country"
+#[cfg(feature = "gui")]
+pub fn group_heading(server: &VpnServer, group_by: &str) -> String {
+    match group_by {
+        "country" if server.country.is_empty() => "Unknown country".to_string(),
+        "country" => format!("{} ({})", crate::geo::country_name(&server.country), server.country),
+        _ if server.subscription.is_empty() => "Unnamed subscription".to_string(),
+        _ => server.subscription.clone(),
+    }
+}
+
+/// Server indexes under their headings, in order of each heading's first server
+#[cfg(feature = "gui")]
+pub fn group_servers<'a>(servers: impl IntoIterator<Item = (usize, &'a VpnServer)>, group_by: &str) -> Vec<(String, Vec<usize>)> {
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+    for (index, server) in servers {
+        let heading = group_heading(server, group_by);
+        match groups.iter_mut().find(|(existing, _)| *existing == heading) {
+            Some((_, members)) => members.push(index),
+            None => groups.push((heading, vec![index])),
+        }
+    }
+    groups
+}
+
+// Protocol, address and port the way every spelling of a server shares them:
+// protocol and address case and IPv6 brackets don't make a different server
+fn endpoint(server: &VpnServer) -> (String, String, u16) {
+    let address = server.address.trim_start_matches('[').trim_end_matches(']');
+    (server.protocol.to_uppercase(), address.to_lowercase(), server.port)
+}
+
+// Protocol, address and port of a saved key, tolerant of case, IPv6 brackets and older spellings
+fn parse_server_key(key: &str) -> Option<(String, String, u16)> {
+    let (protocol, rest) = key.split_once("://")?;
+    let (address, port) = rest.rsplit_once(':')?;
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    Some((protocol.to_uppercase(), address.to_lowercase(), port.trim().parse().ok()?))
+}
+
+// Server of the current list a saved key from an older format belongs to, among those
+// no other key claimed yet; None when nothing on its endpoint (or index) is left
+fn migration_target<'a>(old_key: &str, servers: &'a [VpnServer], claimed: &HashSet<String>) -> Option<&'a VpnServer> {
+    let unclaimed = |server: &&VpnServer| !claimed.contains(&server.get_server_key());
+    match parse_server_key(old_key) {
+        Some((protocol, address, port)) => {
+            let same_endpoint: Vec<&VpnServer> = servers.iter()
+                .filter(unclaimed)
+                .filter(|s| s.address.trim_start_matches('[').trim_end_matches(']').to_lowercase() == address && s.port == port)
+                .collect();
+            // Same protocol first; a renamed protocol only when the endpoint is unambiguous
+            same_endpoint.iter().find(|s| s.protocol.to_uppercase() == protocol).copied()
+                .or_else(|| (same_endpoint.len() == 1).then(|| same_endpoint[0]))
+        }
+        // Index-based keys from before keys were introduced
+        None => old_key.trim().parse::<usize>().ok()
+            .and_then(|index| servers.get(index))
+            .filter(unclaimed),
+    }
+}
+
+/// Re-key saved server settings written under an older key format (or by index) to the
+/// servers of the current list, matching protocol and address:port. Runs once per format
+/// version, when a non-empty list is loaded; saves the config when anything moved.
+/// Settings it can't place are kept as they are and reported, never dropped.
+pub fn migrate_server_keys(config: &mut crate::config::Config, servers: &[VpnServer]) {
+    if config.server_keys_version >= crate::config::SERVER_KEYS_VERSION || servers.is_empty() {
+        return;
+    }
+    let mut claimed: HashSet<String> = config.server_settings.keys()
+        .filter(|key| servers.iter().any(|s| s.get_server_key() == **key))
+        .cloned()
+        .collect();
+    let mut orphans: Vec<String> = config.server_settings.keys()
+        .filter(|key| !claimed.contains(*key))
+        .cloned()
+        .collect();
+    orphans.sort();
+
+    let mut moved = Vec::new();
+    let mut unmapped = Vec::new();
+    for old_key in orphans {
+        match migration_target(&old_key, servers, &claimed) {
+            Some(server) => {
+                let new_key = server.get_server_key();
+                if let Some(settings) = config.server_settings.remove(&old_key) {
+                    config.server_settings.insert(new_key.clone(), settings);
+                }
+                claimed.insert(new_key.clone());
+                moved.push((old_key, new_key));
+            }
+            None => unmapped.push(old_key),
+        }
+    }
+
+    config.server_keys_version = crate::config::SERVER_KEYS_VERSION;
+    if let Err(e) = config.save() {
+        eprintln!("Failed to save migrated server settings: {}", e);
+        return;
+    }
+    for (old_key, new_key) in &moved {
+        println!("Server settings moved from {} to {}", old_key, new_key);
+    }
+    if !unmapped.is_empty() {
+        let ports: Vec<String> = unmapped.iter()
+            .map(|key| format!("{} (port {})", key, config.server_settings[key].local_port))
+            .collect();
+        let report = format!(
+            "Saved settings of {} server(s) didn't match any server in the current list and were kept unchanged:\n\n{}\n\n\
+             They apply again if these servers come back.",
+            unmapped.len(), ports.join("\n")
+        );
+        eprintln!("{}", report);
+        if let Ok(mut last) = KEY_MIGRATION_REPORT.lock() {
+            *last = Some(report);
+        }
+    }
+}
+
+/// What the last server key migration couldn't map, once
+#[cfg(feature = "gui")]
+pub fn take_key_migration_report() -> Option<String> {
+    KEY_MIGRATION_REPORT.lock().ok()?.take()
+}
+
+// Assign local ports to servers, preserving saved settings from config.
+// New servers get free ports counting up from first_port.
+pub fn assign_local_ports(servers: &mut [VpnServer], saved_settings: &std::collections::HashMap<String, crate::config::ServerSettings>, first_port: u16) {
     let mut used_ports = HashSet::new();
     
-    // First pass: assign saved settings (port + proxy type + enabled)
+    // First pass: assign saved settings (port + proxy type + enabled). Settings saved under
+    // another spelling of the same server carry over; a saved port that an earlier server
+    // already holds is reassigned below rather than shared.
     for server in servers.iter_mut() {
-        let key = server.get_server_key();
-        if let Some(settings) = saved_settings.get(&key) {
-            server.local_port = settings.local_port;
+        let settings = saved_settings_key(server, saved_settings).and_then(|key| saved_settings.get(&key));
+        if let Some(settings) = settings {
             server.proxy_type = settings.proxy_type.clone();
             server.enabled = settings.enabled;
-            used_ports.insert(settings.local_port);
+            server.alias = settings.alias.clone();
+            server.note = settings.note.clone();
+            server.pinned = settings.pinned;
+            server.hidden = settings.hidden;
+            server.listen = settings.listen.clone();
+            server.udp = settings.udp;
+            server.auto_port = settings.auto_port;
+            if settings.auto_port {
+                // Nothing to reserve; the ports are picked at start
+                server.local_port = 0;
+                server.http_port = 0;
+                continue;
+            }
+            if used_ports.insert(settings.local_port) {
+                server.local_port = settings.local_port;
+            } else {
+                eprintln!("{}: saved local port {} is also saved for another server; assigning a free one",
+                    server.display_name(), settings.local_port);
+            }
+            if settings.proxy_type == "Both" && settings.http_port != 0 {
+                if used_ports.insert(settings.http_port) {
+                    server.http_port = settings.http_port;
+                } else {
+                    eprintln!("{}: saved HTTP port {} is also saved for another server; assigning a free one",
+                        server.display_name(), settings.http_port);
+                }
+            }
         }
     }
     
     // Second pass: assign new ports to servers without saved settings
-    let mut next_port = 1080;
-    for server in servers.iter_mut() {
+    let mut next_port = first_port;
+    let mut free_port = || {
+        while used_ports.contains(&next_port) {
+            next_port += 1;
+        }
+        used_ports.insert(next_port);
+        next_port
+    };
+    for server in servers.iter_mut().filter(|server| !server.auto_port) {
         if server.local_port == 0 { // Not assigned yet
-            while used_ports.contains(&next_port) {
-                next_port += 1;
+            server.local_port = free_port();
+        }
+        if server.proxy_type == "Both" && server.http_port == 0 {
+            server.http_port = free_port();
+        }
+    }
+}
+
+// Key the server's settings are saved under: its own, else another spelling of it
+fn saved_settings_key(server: &VpnServer, saved_settings: &std::collections::HashMap<String, crate::config::ServerSettings>) -> Option<String> {
+    let key = server.get_server_key();
+    if saved_settings.contains_key(&key) {
+        return Some(key);
+    }
+    let endpoint = endpoint(server);
+    saved_settings.keys()
+        .find(|saved_key| parse_server_key(saved_key).as_ref() == Some(&endpoint))
+        .cloned()
+}
+
+/// Write what `assign_local_ports` decided back to the saved settings it was given, so
+/// starts and pruning (which look settings up by exact key) agree with the list: settings
+/// found under another spelling move to the server's key, and a saved port that collided
+/// with another server's is replaced by the free one it got. Servers without saved
+/// settings are left alone. Returns whether anything changed (the caller saves).
+pub fn store_assigned_ports(saved_settings: &mut std::collections::HashMap<String, crate::config::ServerSettings>, servers: &[VpnServer]) -> bool {
+    // Look every server up before changing the map, as assign_local_ports did
+    let found: Vec<(&VpnServer, String)> = servers.iter()
+        .filter_map(|server| Some((server, saved_settings_key(server, saved_settings)?)))
+        .collect();
+    let mut changed = false;
+    for (server, saved_key) in found {
+        let key = server.get_server_key();
+        let Some(mut settings) = saved_settings.remove(&saved_key) else {
+            continue;
+        };
+        if saved_key != key {
+            println!("Settings of {} saved as {}, moving them to {}", server.display_name(), saved_key, key);
+            changed = true;
+        }
+        if !settings.auto_port {
+            let http_port = if settings.proxy_type == "Both" { server.http_port } else { settings.http_port };
+            if settings.local_port != server.local_port || settings.http_port != http_port {
+                settings.local_port = server.local_port;
+                settings.http_port = http_port;
+                changed = true;
             }
-            server.local_port = next_port;
-            used_ports.insert(next_port);
-            next_port += 1;
         }
+        saved_settings.insert(key, settings);
     }
+    changed
 }
 
-// Fetch and process VPN subscription list
-pub fn fetch_and_process_vpn_list(url: &str) -> Vec<VpnServer> {
-    let mut servers = Vec::new();
+// Fetch a subscription and return its decoded lines.
+// Falls back to the last successfully fetched copy when the URL is unreachable.
+fn fetch_subscription_lines(url: &str) -> Vec<String> {
+    // Local files are read as-is every time; there's nothing to cache
+    if let Some(path) = local_subscription_path(url) {
+        return match std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| decode_subscription(&content))
+        {
+            Ok(decoded) => decoded.lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect(),
+            Err(e) => {
+                eprintln!("Failed to read subscription file {}: {}", path.display(), e);
+                Vec::new()
+            }
+        };
+    }
     
-    // Fetch content from URL
-    match reqwest::blocking::get(url) {
-        Ok(response) => {
-            match response.text() {
-                Ok(content) => {
-                    // Decode from base64
-                    match base64::Engine::decode(&base64::engine::general_purpose::STANDARD, content.trim()) {
-                        Ok(decoded_bytes) => {
-                            match String::from_utf8(decoded_bytes) {
-                                Ok(decoded_text) => {
-                                    // Parse VPN links
-                                    for line in decoded_text.lines() {
-                                        let trimmed = line.trim();
-                                        if let Some(server) = parse_vpn_uri(trimmed) {
-                                            servers.push(server);
-                                        }
-                                    }
-                                }
-                                Err(_) => {}
-                            }
-                        }
-                        Err(_) => {}
-                    }
+    let decoded = match download_subscription(url) {
+        Ok(decoded_text) => {
+            save_cached_subscription(url, &decoded_text);
+            decoded_text
+        }
+        Err(e) => {
+            eprintln!("Failed to fetch subscription {}: {}", url, e);
+            match load_cached_subscription(url) {
+                Some(cached) => {
+                    println!("Using cached copy of subscription {}", url);
+                    cached
                 }
-                Err(_) => {}
+                None => return Vec::new(),
             }
         }
-        Err(_) => {}
+    };
+    
+    decoded.lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+// Download a subscription and decode it to one share URI per line
+fn download_subscription(url: &str) -> Result<String, String> {
+    let content = reqwest::blocking::get(url)
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .map_err(|e| e.to_string())?;
+    decode_subscription(&content)
+}
+
+// Subscriptions are base64 URI lists (standard or URL-safe, padded or not),
+// plain-text URI lists, or sing-box JSON documents
+fn decode_subscription(content: &str) -> Result<String, String> {
+    if singbox::is_singbox_document(content) {
+        return singbox::outbounds_to_uris(content).map(|uris| uris.join("\n"));
+    }
+    
+    // Panels serve raw links for some client flags; base64 never contains "://"
+    let first_line = content.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("");
+    if first_line.contains("://") {
+        return Ok(content.to_string());
     }
     
-    servers
+    // Some panels wrap base64 across lines
+    let compact: String = content.chars().filter(|c| !c.is_whitespace()).collect();
+    use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD};
+    let decoded_bytes = [STANDARD, URL_SAFE]
+        .iter()
+        .find_map(|engine| base64::Engine::decode(engine, &compact).ok())
+        .or_else(|| {
+            let unpadded = compact.trim_end_matches('=');
+            [STANDARD_NO_PAD, URL_SAFE_NO_PAD]
+                .iter()
+                .find_map(|engine| base64::Engine::decode(engine, unpadded).ok())
+        })
+        .ok_or_else(|| "Not a base64, plain-text or sing-box subscription".to_string())?;
+    String::from_utf8(decoded_bytes)
+        .map_err(|e| format!("Invalid UTF-8: {}", e))
 }
 
-// Fetch subscription and return HashMap of server_key -> original_uri
-pub fn fetch_subscription_uris(url: &str) -> std::collections::HashMap<String, String> {
-    let mut uris = std::collections::HashMap::new();
+// Cache file for a subscription URL: %APPDATA%\Xray-VPN-Manager\cache\sub-<hash>.txt
+fn subscription_cache_path(url: &str) -> Option<std::path::PathBuf> {
+    crate::config::cache_file_path("sub", url)
+}
+
+fn save_cached_subscription(url: &str, decoded_text: &str) {
+    if let Some(path) = subscription_cache_path(url) {
+        if let Err(e) = std::fs::write(&path, decoded_text) {
+            eprintln!("Failed to cache subscription: {}", e);
+        }
+    }
+}
+
+fn load_cached_subscription(url: &str) -> Option<String> {
+    std::fs::read_to_string(subscription_cache_path(url)?).ok()
+}
+
+/// Name shown as the source of servers added by hand
+pub const MANUAL_SOURCE: &str = "Manual";
+
+/// Check a pasted share URI and return the server it describes
+pub fn parse_manual_uri(uri: &str) -> Result<VpnServer, String> {
+    let uri = uri.trim();
+    let mut server = parse_vpn_uri(uri)
+        .ok_or_else(|| "Not a supported share link (vless://, vmess://, trojan://, ss://, ssr://, socks://, hysteria2://)".to_string())?;
+    server.subscription = MANUAL_SOURCE.to_string();
+    Ok(server)
+}
+
+// Fetch all subscriptions and return (URI, parsed server) pairs, manually added
+// servers first. Servers appearing in several sources, or twice in one, are kept
+// once (first one wins), however the protocol and address are spelled.
+fn fetch_all_uris(subscriptions: &[Subscription], manual_servers: &[String]) -> Vec<(String, VpnServer)> {
+    let fetching = subscriptions.iter().any(|subscription| !subscription.url.trim().is_empty());
+    if fetching {
+        FETCHES.fetch_add(1, Ordering::SeqCst);
+        crate::request_menu_update();
+    }
+    let entries = collect_uris(subscriptions, manual_servers);
+    if fetching {
+        FETCHES.fetch_sub(1, Ordering::SeqCst);
+        crate::request_menu_update();
+    }
+    entries
+}
+
+/// Whether subscriptions are being downloaded right now
+#[cfg(feature = "gui")]
+pub fn is_updating() -> bool {
+    FETCHES.load(Ordering::SeqCst) > 0
+}
+
+fn collect_uris(subscriptions: &[Subscription], manual_servers: &[String]) -> Vec<(String, VpnServer)> {
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    let mut duplicates = 0;
     
-    // Fetch content from URL
-    match reqwest::blocking::get(url) {
-        Ok(response) => {
-            match response.text() {
-                Ok(content) => {
-                    // Decode from base64
-                    match base64::Engine::decode(&base64::engine::general_purpose::STANDARD, content.trim()) {
-                        Ok(decoded_bytes) => {
-                            match String::from_utf8(decoded_bytes) {
-                                Ok(decoded_text) => {
-                                    // Parse VPN links
-                                    for line in decoded_text.lines() {
-                                        let trimmed = line.trim();
-                                        if let Some(server) = parse_vpn_uri(trimmed) {
-                                            let key = server.get_server_key();
-                                            uris.insert(key, trimmed.to_string());
-                                        }
-                                    }
-                                }
-                                Err(_) => {}
-                            }
-                        }
-                        Err(_) => {}
-                    }
+    for uri in manual_servers {
+        if let Ok(server) = parse_manual_uri(uri) {
+            if seen.insert(endpoint(&server)) {
+                entries.push((server.uri.clone(), server));
+            }
+        }
+    }
+    
+    for subscription in subscriptions {
+        if subscription.url.trim().is_empty() {
+            continue;
+        }
+        let mut filtered_out = 0;
+        let filter = pattern::NameFilter::new(&subscription.name, &subscription.include, &subscription.exclude);
+        for uri in fetch_subscription_lines(subscription.url.trim()) {
+            if let Some(mut server) = parse_vpn_uri(&uri) {
+                // Info nodes ("Traffic left: 20 GB") and unwanted regions never get a port
+                if !filter.passes(&server.name) {
+                    filtered_out += 1;
+                    continue;
+                }
+                if seen.insert(endpoint(&server)) {
+                    server.subscription = subscription.name.clone();
+                    entries.push((server.uri.clone(), server));
+                } else {
+                    duplicates += 1;
                 }
-                Err(_) => {}
             }
         }
-        Err(_) => {}
+        if filtered_out > 0 {
+            println!("Subscription {}: {} servers left out by its filters", subscription.name, filtered_out);
+        }
+    }
+    if duplicates > 0 {
+        println!("Skipped {} servers listed more than once", duplicates);
     }
     
-    uris
+    entries
+}
+
+// Fetch and process VPN subscription lists, merging servers from all subscriptions
+pub fn fetch_and_process_vpn_list(subscriptions: &[Subscription], manual_servers: &[String]) -> Vec<VpnServer> {
+    fetch_all_uris(subscriptions, manual_servers)
+        .into_iter()
+        .map(|(_, server)| server)
+        .collect()
 }
 
-// Parse VPN URI using v2parser (supports vless, vmess, trojan, shadowsocks, socks)
+// Fetch subscriptions once and return both the server list and server_key -> original_uri
+pub fn fetch_servers_and_uris(subscriptions: &[Subscription], manual_servers: &[String]) -> (Vec<VpnServer>, std::collections::HashMap<String, String>) {
+    let mut servers = Vec::new();
+    let mut uris = std::collections::HashMap::new();
+    for (uri, server) in fetch_all_uris(subscriptions, manual_servers) {
+        uris.insert(server.get_server_key(), uri);
+        servers.push(server);
+    }
+    (servers, uris)
+}
+
+// Fetch subscriptions and return HashMap of server_key -> original_uri
+pub fn fetch_subscription_uris(subscriptions: &[Subscription], manual_servers: &[String]) -> std::collections::HashMap<String, String> {
+    fetch_all_uris(subscriptions, manual_servers)
+        .into_iter()
+        .map(|(uri, server)| (server.get_server_key(), uri))
+        .collect()
+}
+
+// Parse VPN URI using v2parser (supports vless, vmess, trojan, shadowsocks, socks);
+// links a protocol handler claims (hysteria2, which runs under sing-box) are parsed
+// by it, and ssr links become ss:// where possible (the server's uri is then the converted link)
 fn parse_vpn_uri(uri: &str) -> Option<VpnServer> {
+    if ssr::is_ssr_uri(uri) {
+        let link = match ssr::parse(uri) {
+            Ok(link) => link,
+            Err(e) => {
+                eprintln!("Skipping ssr link: {}", e);
+                return None;
+            }
+        };
+        if let Some(ss_uri) = link.to_ss_uri() {
+            return parse_vpn_uri(&ss_uri);
+        }
+        // Listed so the user sees it; starting it explains why it can't run
+        if let Some(reason) = link.unsupported_reason() {
+            eprintln!("{}", reason);
+        }
+        return Some(new_server("SSR", link.address, link.port, link.name, "tcp".to_string(), uri));
+    }
+    
+    if let Some(handler) = protocol::find(uri) {
+        return match handler.parse(uri) {
+            Ok(link) => Some(new_server(handler.protocol(), link.address, link.port, link.name, link.transport, uri)),
+            Err(e) => {
+                eprintln!("Skipping {} link: {}", handler.protocol().to_lowercase(), e);
+                None
+            }
+        };
+    }
+    
     // Check if it's a supported protocol
     let is_supported = uri.starts_with("vless://") 
         || uri.starts_with("vmess://") 
@@ -151,15 +712,7 @@ fn parse_vpn_uri(uri: &str) -> Option<VpnServer> {
                 let port = metadata["port"].as_u64()? as u16;
                 let name = metadata["name"].as_str().unwrap_or("Unnamed").to_string();
                 
-                Some(VpnServer {
-                    protocol,
-                    address,
-                    port,
-                    name,
-                    enabled: false, // Default to disabled, will be enabled from config
-                    local_port: 0, // Will be assigned by assign_local_ports
-                    proxy_type: "SOCKS".to_string(), // Default to SOCKS
-                })
+                Some(new_server(&protocol, address, port, name, parse_transport(uri), uri))
             } else {
                 None
             }
@@ -167,3 +720,131 @@ fn parse_vpn_uri(uri: &str) -> Option<VpnServer> {
         Err(_) => None,
     }
 }
+
+// A server as parsed from a share URI, with country and remarks read from its name
+fn new_server(protocol: &str, address: String, port: u16, name: String, transport: String, uri: &str) -> VpnServer {
+    let country = crate::geo::country_from_name(&name).unwrap_or_default();
+    let remarks = crate::remarks::parse(&name);
+    
+    VpnServer {
+        protocol: protocol.to_string(),
+        address,
+        port,
+        name,
+        enabled: false, // Default to disabled, will be enabled from config
+        local_port: 0, // Will be assigned by assign_local_ports
+        proxy_type: "SOCKS".to_string(), // Default to SOCKS
+        http_port: 0,
+        auto_port: false,
+        transport,
+        subscription: String::new(), // Set by the caller
+        country,
+        uri: uri.to_string(),
+        rate: remarks.rate,
+        tags: remarks.tags,
+        alias: String::new(), // Set by assign_local_ports
+        note: String::new(),
+        pinned: false,
+        hidden: false,
+        listen: String::new(),
+        udp: true,
+    }
+}
+
+// Read the transport type from a share URI ("tcp" if not specified)
+fn parse_transport(uri: &str) -> String {
+    if let Some(payload) = uri.strip_prefix("vmess://") {
+        // vmess:// carries base64 JSON with a "net" field
+        use base64::Engine;
+        let payload = payload.trim();
+        let decoded = base64::engine::general_purpose::STANDARD.decode(payload)
+            .or_else(|_| base64::engine::general_purpose::STANDARD_NO_PAD.decode(payload.trim_end_matches('=')));
+        if let Ok(bytes) = decoded {
+            if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&bytes) {
+                if let Some(net) = json["net"].as_str() {
+                    if !net.is_empty() {
+                        return net.to_lowercase();
+                    }
+                }
+            }
+        }
+        return "tcp".to_string();
+    }
+    
+    // vless/trojan/etc. use ?type=<transport>
+    let query = uri.split('#').next().unwrap_or("")
+        .split_once('?').map(|(_, q)| q).unwrap_or("");
+    query.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == "type")
+        .map(|(_, v)| v.to_lowercase())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "tcp".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(protocol: &str, address: &str, port: u16) -> VpnServer {
+        new_server(protocol, address.to_string(), port, "test".to_string(), "tcp".to_string(), "")
+    }
+
+    fn settings(local_port: u16, proxy_type: &str, http_port: u16) -> crate::config::ServerSettings {
+        serde_json::from_value(serde_json::json!({
+            "local_port": local_port, "proxy_type": proxy_type, "http_port": http_port,
+        })).unwrap()
+    }
+
+    #[test]
+    fn restarts_use_the_ports_assign_local_ports_settled_on() {
+        let mut config = crate::config::Config::default();
+        // Saved under an older spelling (lowercase, no brackets)
+        config.server_settings.insert("vless://2001:db8::1:443".to_string(), settings(1080, "Both", 1081));
+        // Collides with the first server's SOCKS port
+        config.server_settings.insert("TROJAN://1.2.3.4:443".to_string(), settings(1080, "SOCKS", 0));
+        let mut servers = vec![server("VLESS", "[2001:DB8::1]", 443), server("TROJAN", "1.2.3.4", 443)];
+        let uris: std::collections::HashMap<String, String> = servers.iter()
+            .map(|s| (s.get_server_key(), format!("uri-{}", s.get_server_key())))
+            .collect();
+
+        assign_local_ports(&mut servers, &config.server_settings, 1080);
+        assert!(store_assigned_ports(&mut config.server_settings, &servers));
+        assert!(!config.server_settings.contains_key("vless://2001:db8::1:443"));
+
+        let requests: Vec<_> = servers.iter()
+            .map(|s| crate::plan::start_request(&config, s, &uris).expect("settings under the server's key"))
+            .collect();
+        let ports: Vec<u16> = requests.iter()
+            .flat_map(|r| crate::ports::server_ports(r.local_port, &r.proxy_type, r.http_port))
+            .collect();
+        assert_eq!(ports.len(), 3);
+        assert!(crate::ports::duplicates(requests.iter().map(|r| (r.name.as_str(), crate::ports::server_ports(r.local_port, &r.proxy_type, r.http_port)))).is_empty());
+        for (server, request) in servers.iter().zip(&requests) {
+            assert_eq!(request.local_port, server.local_port);
+        }
+        // Nothing left to move on the next refresh
+        assert!(!store_assigned_ports(&mut config.server_settings, &servers));
+    }
+
+    #[test]
+    fn migration_target_skips_keys_without_a_server_on_their_endpoint() {
+        let servers = vec![server("VLESS", "1.2.3.4", 443)];
+        // A server that left the subscription: nothing on its endpoint
+        assert!(migration_target("vless://5.6.7.8:443", &servers, &HashSet::new()).is_none());
+        assert!(migration_target("7", &servers, &HashSet::new()).is_none());
+    }
+
+    #[test]
+    fn migration_target_matches_other_spellings_of_the_endpoint() {
+        let servers = vec![server("VLESS", "[2001:DB8::1]", 443), server("TROJAN", "1.2.3.4", 443)];
+        let target = migration_target("vless://2001:db8::1:443", &servers, &HashSet::new());
+        assert_eq!(target.map(|s| s.get_server_key()), Some("VLESS://[2001:DB8::1]:443".to_string()));
+        // Renamed protocol, one server on the endpoint
+        let target = migration_target("VMESS://1.2.3.4:443", &servers, &HashSet::new());
+        assert_eq!(target.map(|s| s.get_server_key()), Some("TROJAN://1.2.3.4:443".to_string()));
+        // Already claimed by its current key
+        let claimed = HashSet::from(["TROJAN://1.2.3.4:443".to_string()]);
+        assert!(migration_target("VMESS://1.2.3.4:443", &servers, &claimed).is_none());
+    }
+}
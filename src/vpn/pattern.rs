@@ -0,0 +1,92 @@
+// Name patterns for subscription include/exclude filters: regular expressions
+// (regex crate syntax), matched ignoring case. A pattern matches anywhere in the
+// name unless anchored, so "expire|剩余|traffic" catches the info nodes that
+// subscriptions put in their lists.
+
+use regex::{Regex, RegexBuilder};
+
+/// Compile a name pattern, or say what is wrong with it
+pub fn compile(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// A subscription's include/exclude filters, compiled once per fetch
+pub struct NameFilter {
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+}
+
+impl NameFilter {
+    /// Compile `include` and `exclude`; an empty pattern is unset, and so is an
+    /// invalid one (only a hand-edited config has those), with a log line
+    pub fn new(subscription: &str, include: &str, exclude: &str) -> NameFilter {
+        let compile_or_skip = |field: &str, pattern: &str| {
+            let pattern = pattern.trim();
+            if pattern.is_empty() {
+                return None;
+            }
+            compile(pattern)
+                .map_err(|e| eprintln!("Subscription {}: ignoring invalid {} pattern \"{}\": {}", subscription, field, pattern, e))
+                .ok()
+        };
+        NameFilter {
+            include: compile_or_skip("include", include),
+            exclude: compile_or_skip("exclude", exclude),
+        }
+    }
+
+    /// Whether a server name matches `include` (when set) and doesn't match `exclude` (when set)
+    pub fn passes(&self, name: &str) -> bool {
+        self.include.as_ref().is_none_or(|include| include.is_match(name))
+            && !self.exclude.as_ref().is_some_and(|exclude| exclude.is_match(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compile_matches_anywhere_ignoring_case() {
+        let pattern = compile("expire|剩余|traffic").unwrap();
+        assert!(pattern.is_match("Traffic left: 20 GB"));
+        assert!(pattern.is_match("EXPIRE 2026-11-01"));
+        assert!(pattern.is_match("剩余流量：20 GB"));
+        assert!(!pattern.is_match("JP Tokyo 01"));
+    }
+
+    #[test]
+    fn compile_honours_anchors_classes_and_counts() {
+        let pattern = compile(r"^(JP|SG)-\d{2}$").unwrap();
+        assert!(pattern.is_match("jp-01"));
+        assert!(pattern.is_match("SG-42"));
+        assert!(!pattern.is_match("JP-1"));
+        assert!(!pattern.is_match("HK JP-01"));
+        assert!(!pattern.is_match("SG-420"));
+    }
+
+    #[test]
+    fn compile_rejects_invalid_patterns() {
+        assert!(compile("[a-").is_err());
+        assert!(compile("(JP|SG").is_err());
+        assert!(compile("x{2,1}").is_err());
+    }
+
+    #[test]
+    fn filter_applies_include_then_exclude() {
+        let filter = NameFilter::new("test", "JP|SG", "premium");
+        assert!(filter.passes("JP Tokyo"));
+        assert!(!filter.passes("SG Premium"));
+        assert!(!filter.passes("US New York"));
+    }
+
+    #[test]
+    fn filter_treats_empty_and_invalid_patterns_as_unset() {
+        assert!(NameFilter::new("test", "", "").passes("anything"));
+        assert!(NameFilter::new("test", "[a-", "").passes("anything"));
+        assert!(NameFilter::new("test", "", "(oops").passes("anything"));
+    }
+}
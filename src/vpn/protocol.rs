@@ -0,0 +1,65 @@
+use std::sync::{Arc, LazyLock, RwLock};
+
+// Protocol handlers: share-link schemes v2parser doesn't read, each with its own
+// parser and core config generator. Hysteria2 is built in; a fork or optional
+// module adds another protocol (brook, naive, ...) by implementing
+// `ProtocolHandler` and calling `register` at startup, without touching
+// `parse_vpn_uri` or `xray_manager`. Links no handler claims go to v2parser.
+
+/// Core process that runs a handler's config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Core {
+    Xray,
+    SingBox, // sing-box_binary_path; routing rules are translated for it
+}
+
+/// Server details read from a share link
+#[derive(Debug, Clone)]
+pub struct LinkInfo {
+    pub address: String,
+    pub port: u16,
+    pub name: String,
+    pub transport: String, // "tcp", "quic", ...; decides UDP warnings and latency test method
+}
+
+/// A share-link scheme the manager can list and run
+pub trait ProtocolHandler: Send + Sync {
+    /// Upper-case protocol name, the first part of server keys ("HYSTERIA2")
+    fn protocol(&self) -> &'static str;
+
+    /// True for links this handler reads, usually a scheme check
+    fn matches(&self, uri: &str) -> bool;
+
+    /// Server details from a share link
+    fn parse(&self, uri: &str) -> Result<LinkInfo, String>;
+
+    /// Core config (JSON) serving the link on a local SOCKS and/or HTTP inbound.
+    /// Routing rules and limits are added by the caller.
+    fn core_config(&self, uri: &str, socks_port: Option<u16>, http_port: Option<u16>) -> Result<String, String>;
+
+    /// Core that runs `core_config`
+    fn core(&self) -> Core {
+        Core::Xray
+    }
+}
+
+// Registered handlers, newest first so a fork can override a built-in one
+static HANDLERS: LazyLock<RwLock<Vec<Arc<dyn ProtocolHandler>>>> =
+    LazyLock::new(|| RwLock::new(vec![Arc::new(super::hysteria2::Hysteria2Handler)]));
+
+/// Add a protocol; it takes precedence over handlers registered before it
+#[allow(dead_code)] // Extension point for forks and optional modules
+pub fn register(handler: Arc<dyn ProtocolHandler>) {
+    if let Ok(mut handlers) = HANDLERS.write() {
+        println!("Registered protocol handler: {}", handler.protocol());
+        handlers.insert(0, handler);
+    }
+}
+
+/// Handler that reads this link, None for links left to v2parser
+pub fn find(uri: &str) -> Option<Arc<dyn ProtocolHandler>> {
+    HANDLERS.read().ok()?
+        .iter()
+        .find(|handler| handler.matches(uri))
+        .cloned()
+}
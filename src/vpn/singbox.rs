@@ -0,0 +1,413 @@
+use base64::Engine;
+use serde_json::Value;
+#[cfg(feature = "gui")]
+use serde_json::json;
+
+// sing-box subscriptions are a JSON config whose "outbounds" list the servers.
+// Each supported outbound is turned back into a share URI so it goes through
+// the same parsing and xray config generation as any other subscription line.
+// The other way round, share links become outbounds of a profile for the
+// sing-box phone apps (see `share::pair`).
+
+/// True if the subscription body looks like a sing-box JSON document
+pub fn is_singbox_document(content: &str) -> bool {
+    content.trim_start().starts_with('{')
+}
+
+/// Convert the outbounds of a sing-box document to share URIs.
+/// Outbounds of unsupported types (direct, block, selector, ...) are skipped.
+pub fn outbounds_to_uris(content: &str) -> Result<Vec<String>, String> {
+    let document: Value = serde_json::from_str(content)
+        .map_err(|e| format!("Invalid sing-box JSON: {}", e))?;
+    let outbounds = document["outbounds"].as_array()
+        .ok_or_else(|| "sing-box JSON has no \"outbounds\" list".to_string())?;
+
+    Ok(outbounds.iter().filter_map(outbound_to_uri).collect())
+}
+
+fn outbound_to_uri(outbound: &Value) -> Option<String> {
+    let server = outbound["server"].as_str()?;
+    let port = outbound["server_port"].as_u64()?;
+    let tag = outbound["tag"].as_str().unwrap_or(server);
+
+    match outbound["type"].as_str()? {
+        "vless" => {
+            let mut params = vec![("encryption", "none".to_string())];
+            push_str(&mut params, "flow", &outbound["flow"]);
+            push_tls(&mut params, &outbound["tls"]);
+            push_transport(&mut params, &outbound["transport"]);
+            Some(format!("vless://{}@{}:{}?{}#{}",
+                outbound["uuid"].as_str()?, host_part(server), port, query(&params), percent_encode(tag)))
+        }
+        "trojan" => {
+            let mut params = Vec::new();
+            push_tls(&mut params, &outbound["tls"]);
+            push_transport(&mut params, &outbound["transport"]);
+            Some(format!("trojan://{}@{}:{}?{}#{}",
+                percent_encode(outbound["password"].as_str()?), host_part(server), port, query(&params), percent_encode(tag)))
+        }
+        "vmess" => {
+            // vmess:// is base64 of the v2rayN JSON format
+            let transport = &outbound["transport"];
+            let net = match transport["type"].as_str() {
+                Some("http") => "h2",
+                Some(other) => other,
+                None => "tcp",
+            };
+            let tls = &outbound["tls"];
+            let link = serde_json::json!({
+                "v": "2",
+                "ps": tag,
+                "add": server,
+                "port": port.to_string(),
+                "id": outbound["uuid"].as_str()?,
+                "aid": outbound["alter_id"].as_u64().unwrap_or(0).to_string(),
+                "scy": outbound["security"].as_str().unwrap_or("auto"),
+                "net": net,
+                "type": "none",
+                "host": transport_host(transport).unwrap_or_default(),
+                "path": transport["path"].as_str().or(transport["service_name"].as_str()).unwrap_or(""),
+                "tls": if tls["enabled"].as_bool() == Some(true) { "tls" } else { "" },
+                "sni": tls["server_name"].as_str().unwrap_or(""),
+                "fp": tls["utls"]["fingerprint"].as_str().unwrap_or(""),
+            });
+            Some(format!("vmess://{}", base64::engine::general_purpose::STANDARD.encode(link.to_string())))
+        }
+        "shadowsocks" => {
+            let user_info = format!("{}:{}", outbound["method"].as_str()?, outbound["password"].as_str()?);
+            Some(format!("ss://{}@{}:{}#{}",
+                base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(user_info), host_part(server), port, percent_encode(tag)))
+        }
+        "socks" => {
+            let credentials = match (outbound["username"].as_str(), outbound["password"].as_str()) {
+                (Some(user), Some(pass)) => format!("{}@", base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, pass))),
+                _ => String::new(),
+            };
+            Some(format!("socks://{}{}:{}#{}", credentials, host_part(server), port, percent_encode(tag)))
+        }
+        "hysteria2" => {
+            let tls = &outbound["tls"];
+            let mut params = Vec::new();
+            push_str(&mut params, "sni", &tls["server_name"]);
+            if tls["insecure"].as_bool() == Some(true) {
+                params.push(("insecure", "1".to_string()));
+            }
+            if outbound["obfs"]["type"].as_str() == Some("salamander") {
+                params.push(("obfs", "salamander".to_string()));
+                push_str(&mut params, "obfs-password", &outbound["obfs"]["password"]);
+            }
+            // Port hopping ranges ("20000:30000") follow the main port as "443,20000-30000"
+            let mut ports = port.to_string();
+            for range in outbound["server_ports"].as_array().into_iter().flatten().filter_map(|r| r.as_str()) {
+                ports.push_str(&format!(",{}", range.replace(':', "-")));
+            }
+            Some(format!("hysteria2://{}@{}:{}/?{}#{}",
+                percent_encode(outbound["password"].as_str().unwrap_or("")), host_part(server), ports, query(&params), percent_encode(tag)))
+        }
+        _ => None,
+    }
+}
+
+/// sing-box profile for the phone apps: a TUN inbound taking all traffic, a
+/// selector over the given (name, share URI) servers and LAN addresses sent direct.
+/// Links that can't be converted are skipped; an error if none could.
+#[cfg(feature = "gui")]
+pub fn mobile_profile(servers: &[(String, String)]) -> Result<String, String> {
+    let mut outbounds = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+    for (name, uri) in servers {
+        // Tags must be unique; subscriptions often repeat names
+        let mut tag = name.clone();
+        let mut suffix = 2;
+        while tags.contains(&tag) {
+            tag = format!("{} ({})", name, suffix);
+            suffix += 1;
+        }
+        match uri_to_outbound(uri, &tag) {
+            Ok(outbound) => {
+                outbounds.push(outbound);
+                tags.push(tag);
+            }
+            Err(e) => eprintln!("Left {} out of the sing-box profile: {}", name, e),
+        }
+    }
+    if tags.is_empty() {
+        return Err("None of the servers can be converted for sing-box".to_string());
+    }
+
+    outbounds.insert(0, json!({ "type": "selector", "tag": "proxy", "outbounds": tags }));
+    outbounds.push(json!({ "type": "direct", "tag": "direct" }));
+    let profile = json!({
+        "log": { "level": "warn" },
+        "dns": {
+            "servers": [{ "tag": "remote", "address": "tcp://1.1.1.1", "detour": "proxy" }],
+            "final": "remote",
+        },
+        "inbounds": [{
+            "type": "tun",
+            "tag": "tun-in",
+            "address": ["172.19.0.1/30", "fdfe:dcba:9876::1/126"],
+            "auto_route": true,
+            "strict_route": true,
+        }],
+        "outbounds": outbounds,
+        "route": {
+            "rules": [
+                { "action": "sniff" },
+                { "protocol": "dns", "action": "hijack-dns" },
+                { "ip_is_private": true, "outbound": "direct" },
+            ],
+            "final": "proxy",
+            "auto_detect_interface": true,
+        },
+    });
+    serde_json::to_string_pretty(&profile)
+        .map_err(|e| format!("Failed to write sing-box profile: {}", e))
+}
+
+/// sing-box outbound for a share link, tagged `tag`
+#[cfg(feature = "gui")]
+pub fn uri_to_outbound(uri: &str, tag: &str) -> Result<Value, String> {
+    if super::ssr::is_ssr_uri(uri) {
+        return Err("ShadowsocksR links aren't supported by sing-box".to_string());
+    }
+    let mut outbound = match super::protocol::find(uri) {
+        // Handlers running in sing-box already generate its outbound
+        Some(handler) if handler.core() == super::protocol::Core::SingBox => {
+            let config: Value = serde_json::from_str(&handler.core_config(uri, None, None)?)
+                .map_err(|e| format!("Failed to read generated sing-box config: {}", e))?;
+            config["outbounds"].get(0).cloned()
+                .ok_or_else(|| "Generated sing-box config has no outbounds".to_string())?
+        }
+        Some(handler) => {
+            let config: Value = serde_json::from_str(&handler.core_config(uri, None, None)?)
+                .map_err(|e| format!("Failed to read generated xray config: {}", e))?;
+            xray_outbound(&config)?
+        }
+        None => {
+            let config: Value = serde_json::from_str(&v2parser::parser::create_json_config(uri, None, None))
+                .map_err(|e| format!("Failed to read generated xray config: {}", e))?;
+            xray_outbound(&config)?
+        }
+    };
+    outbound["tag"] = json!(tag);
+    Ok(outbound)
+}
+
+// Translate the first outbound of an xray config
+#[cfg(feature = "gui")]
+fn xray_outbound(config: &Value) -> Result<Value, String> {
+    let outbound = config["outbounds"].get(0)
+        .ok_or_else(|| "Generated xray config has no outbounds".to_string())?;
+    let settings = &outbound["settings"];
+    let protocol = outbound["protocol"].as_str().unwrap_or("");
+    let unsupported = || format!("{} outbound is missing its server", protocol);
+
+    let mut result = match protocol {
+        "vless" | "vmess" => {
+            let server = settings["vnext"].get(0).ok_or_else(unsupported)?;
+            let user = server["users"].get(0).ok_or_else(unsupported)?;
+            let mut result = json!({
+                "type": protocol,
+                "server": server["address"],
+                "server_port": server["port"],
+                "uuid": user["id"],
+            });
+            if protocol == "vmess" {
+                result["security"] = json!(user["security"].as_str().unwrap_or("auto"));
+                result["alter_id"] = json!(user["alterId"].as_u64().unwrap_or(0));
+            } else if let Some(flow) = user["flow"].as_str().filter(|f| !f.is_empty()) {
+                result["flow"] = json!(flow);
+            }
+            result
+        }
+        "trojan" | "shadowsocks" | "socks" => {
+            let server = settings["servers"].get(0).ok_or_else(unsupported)?;
+            let mut result = json!({
+                "type": protocol,
+                "server": server["address"],
+                "server_port": server["port"],
+            });
+            match protocol {
+                "trojan" => result["password"] = server["password"].clone(),
+                "shadowsocks" => {
+                    result["method"] = server["method"].clone();
+                    result["password"] = server["password"].clone();
+                }
+                _ => {
+                    if let Some(user) = server["users"].get(0) {
+                        result["username"] = user["user"].clone();
+                        result["password"] = user["pass"].clone();
+                    }
+                }
+            }
+            result
+        }
+        other => return Err(format!("{} outbounds can't be converted for sing-box", other)),
+    };
+
+    let stream = &outbound["streamSettings"];
+    if let Some(tls) = outbound_tls(stream) {
+        result["tls"] = tls;
+    }
+    if let Some(transport) = outbound_transport(stream)? {
+        result["transport"] = transport;
+    }
+    Ok(result)
+}
+
+// xray tlsSettings / realitySettings as a sing-box tls block
+#[cfg(feature = "gui")]
+fn outbound_tls(stream: &Value) -> Option<Value> {
+    let security = stream["security"].as_str()?;
+    let settings = match security {
+        "tls" => &stream["tlsSettings"],
+        "reality" => &stream["realitySettings"],
+        _ => return None,
+    };
+    let mut tls = json!({ "enabled": true });
+    if let Some(sni) = settings["serverName"].as_str().filter(|s| !s.is_empty()) {
+        tls["server_name"] = json!(sni);
+    }
+    if settings["allowInsecure"].as_bool() == Some(true) {
+        tls["insecure"] = json!(true);
+    }
+    if let Some(alpn) = settings["alpn"].as_array().filter(|a| !a.is_empty()) {
+        tls["alpn"] = json!(alpn);
+    }
+    // REALITY needs a uTLS fingerprint in sing-box
+    let fingerprint = settings["fingerprint"].as_str().filter(|f| !f.is_empty())
+        .or((security == "reality").then_some("chrome"));
+    if let Some(fingerprint) = fingerprint {
+        tls["utls"] = json!({ "enabled": true, "fingerprint": fingerprint });
+    }
+    if security == "reality" {
+        tls["reality"] = json!({
+            "enabled": true,
+            "public_key": settings["publicKey"],
+            "short_id": settings["shortId"].as_str().unwrap_or(""),
+        });
+    }
+    Some(tls)
+}
+
+// xray streamSettings network as a sing-box V2Ray transport (None for raw TCP)
+#[cfg(feature = "gui")]
+fn outbound_transport(stream: &Value) -> Result<Option<Value>, String> {
+    let transport = match stream["network"].as_str().unwrap_or("tcp") {
+        "tcp" | "raw" => return Ok(None),
+        "ws" => {
+            let ws = &stream["wsSettings"];
+            let mut transport = json!({ "type": "ws", "path": ws["path"].as_str().unwrap_or("/") });
+            let host = ws["headers"]["Host"].as_str().or(ws["host"].as_str()).filter(|h| !h.is_empty());
+            if let Some(host) = host {
+                transport["headers"] = json!({ "Host": host });
+            }
+            transport
+        }
+        "grpc" => json!({ "type": "grpc", "service_name": stream["grpcSettings"]["serviceName"].as_str().unwrap_or("") }),
+        "httpupgrade" => {
+            let settings = &stream["httpupgradeSettings"];
+            let mut transport = json!({ "type": "httpupgrade", "path": settings["path"].as_str().unwrap_or("/") });
+            if let Some(host) = settings["host"].as_str().filter(|h| !h.is_empty()) {
+                transport["host"] = json!(host);
+            }
+            transport
+        }
+        "h2" | "http" => {
+            let settings = &stream["httpSettings"];
+            let mut transport = json!({ "type": "http", "path": settings["path"].as_str().unwrap_or("/") });
+            if let Some(hosts) = settings["host"].as_array().filter(|h| !h.is_empty()) {
+                transport["host"] = json!(hosts);
+            }
+            transport
+        }
+        other => return Err(format!("the {} transport isn't supported by sing-box", other)),
+    };
+    Ok(Some(transport))
+}
+
+// TLS / REALITY settings as vless/trojan query parameters
+fn push_tls(params: &mut Vec<(&'static str, String)>, tls: &Value) {
+    if tls["enabled"].as_bool() != Some(true) {
+        params.push(("security", "none".to_string()));
+        return;
+    }
+    let reality = &tls["reality"];
+    if reality["enabled"].as_bool() == Some(true) {
+        params.push(("security", "reality".to_string()));
+        push_str(params, "pbk", &reality["public_key"]);
+        push_str(params, "sid", &reality["short_id"]);
+    } else {
+        params.push(("security", "tls".to_string()));
+    }
+    push_str(params, "sni", &tls["server_name"]);
+    push_str(params, "fp", &tls["utls"]["fingerprint"]);
+    if let Some(alpn) = tls["alpn"].as_array() {
+        let alpn: Vec<&str> = alpn.iter().filter_map(|a| a.as_str()).collect();
+        if !alpn.is_empty() {
+            params.push(("alpn", alpn.join(",")));
+        }
+    }
+}
+
+// V2Ray transport settings as query parameters ("tcp" when absent)
+fn push_transport(params: &mut Vec<(&'static str, String)>, transport: &Value) {
+    let kind = match transport["type"].as_str() {
+        Some(kind) => kind,
+        None => {
+            params.push(("type", "tcp".to_string()));
+            return;
+        }
+    };
+    params.push(("type", kind.to_string()));
+    match kind {
+        "grpc" => push_str(params, "serviceName", &transport["service_name"]),
+        _ => {
+            push_str(params, "path", &transport["path"]);
+            if let Some(host) = transport_host(transport) {
+                params.push(("host", host));
+            }
+        }
+    }
+}
+
+// Host header: headers.Host for ws, host (string or list) for http/httpupgrade
+fn transport_host(transport: &Value) -> Option<String> {
+    transport["headers"]["Host"].as_str()
+        .or_else(|| transport["host"].as_str())
+        .or_else(|| transport["host"].as_array().and_then(|hosts| hosts.first()).and_then(|h| h.as_str()))
+        .map(|host| host.to_string())
+}
+
+fn push_str(params: &mut Vec<(&'static str, String)>, name: &'static str, value: &Value) {
+    if let Some(value) = value.as_str().filter(|v| !v.is_empty()) {
+        params.push((name, value.to_string()));
+    }
+}
+
+fn query(params: &[(&str, String)]) -> String {
+    params.iter()
+        .map(|(name, value)| format!("{}={}", name, percent_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+// IPv6 addresses need brackets in URIs
+pub(super) fn host_part(server: &str) -> String {
+    if server.contains(':') {
+        format!("[{}]", server)
+    } else {
+        server.to_string()
+    }
+}
+
+// Percent-encode everything except RFC 3986 unreserved characters
+pub(crate) fn percent_encode(value: &str) -> String {
+    value.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+// Speed tests: a running server downloads `test.speed_url` through its local
+// proxy for up to `test.speed_secs`, and the bytes that arrived give its
+// download rate. A test costs far more traffic than a latency check, so it only
+// runs when asked for (server context menu → Speed Test), one at a time. Results
+// last for the run and show in the server list's Speed column.
+
+// Fewer bytes than this don't say anything about the rate
+const MIN_BYTES: u64 = 64 * 1024;
+
+/// Outcome of the last speed test of a server
+#[derive(Debug, Clone)]
+pub struct SpeedResult {
+    pub result: Result<f64, String>, // Download rate in Mbit/s
+}
+
+// Last result per server key
+static RESULTS: LazyLock<Mutex<HashMap<String, SpeedResult>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Set while a download runs, so tests don't share the line and skew each other
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Download through a running server's local proxy and remember the rate
+pub fn test_server(server_key: &str) -> Result<f64, String> {
+    let info = crate::xray_manager::get_server_info(server_key)
+        .ok_or_else(|| "Start the server first; the speed test downloads through its local port".to_string())?;
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("Another speed test is running; try again when it's done".to_string());
+    }
+    let test = crate::config::Config::load().unwrap_or_default().test;
+    let result = download_rate(
+        info.local_port,
+        &info.proxy_type,
+        &test.speed_url,
+        Duration::from_secs(test.speed_secs.max(1)),
+        Duration::from_secs(test.timeout_secs.max(1)),
+    );
+    RUNNING.store(false, Ordering::SeqCst);
+    if let Ok(mut results) = RESULTS.lock() {
+        results.insert(server_key.to_string(), SpeedResult { result: result.clone() });
+    }
+    result
+}
+
+// Mbit/s of `url` through the proxy, counted from the response headers until it ends or `duration` is up
+fn download_rate(local_port: u16, proxy_type: &str, url: &str, duration: Duration, timeout: Duration) -> Result<f64, String> {
+    // The client's timeout covers the whole body, so it gets the download time on top
+    let client = crate::probe::proxied_client(local_port, proxy_type, duration + timeout)?;
+    let mut response = client.get(url).send()
+        .map_err(|e| format!("No response through proxy: {}", e))?;
+    let status = response.status().as_u16();
+    if !(200..300).contains(&status) {
+        return Err(format!("Speed test URL returned HTTP {}", status));
+    }
+
+    let started = Instant::now();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut received: u64 = 0;
+    while started.elapsed() < duration {
+        match response.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => received += read as u64,
+            Err(e) => return Err(format!("Download failed after {} KB: {}", received / 1024, e)),
+        }
+    }
+    if received < MIN_BYTES {
+        return Err(format!("Only {} KB arrived; use a bigger test file", received / 1024));
+    }
+    let seconds = started.elapsed().as_secs_f64().max(0.001);
+    Ok(received as f64 * 8.0 / seconds / 1_000_000.0)
+}
+
+/// Last speed test result of a server, if it was tested
+pub fn get_result(server_key: &str) -> Option<SpeedResult> {
+    RESULTS.lock().ok()?.get(server_key).cloned()
+}
+
+/// Rate as shown to the user, e.g. "48.3 Mbps"
+pub fn format_rate(mbps: f64) -> String {
+    if mbps < 10.0 {
+        format!("{:.2} Mbps", mbps)
+    } else {
+        format!("{:.1} Mbps", mbps)
+    }
+}
+
+/// Short text for the server list, e.g. "⇣ 48.3 Mbps"
+pub fn label(server_key: &str) -> Option<String> {
+    Some(match get_result(server_key)?.result {
+        Ok(mbps) => format!("⇣ {}", format_rate(mbps)),
+        Err(_) => "⇣ failed".to_string(),
+    })
+}
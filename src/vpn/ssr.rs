@@ -0,0 +1,124 @@
+use base64::Engine;
+
+// ShadowsocksR links, still handed out by legacy providers. xray has no SSR
+// support, but an SSR node with the plain protocol and obfs (or a
+// "_compatible" variant, which servers also accept from plain clients) is an
+// ordinary Shadowsocks server: those are converted to ss:// links. Anything
+// else is kept in the list with a clear reason instead of being dropped.
+
+// Shadowsocks ciphers xray can dial; SSR's stream ciphers (aes-256-cfb, rc4-md5, ...) were removed
+const XRAY_METHODS: &[&str] = &[
+    "aes-128-gcm",
+    "aes-256-gcm",
+    "chacha20-poly1305",
+    "chacha20-ietf-poly1305",
+    "xchacha20-poly1305",
+    "xchacha20-ietf-poly1305",
+    "none",
+    "plain",
+];
+
+/// A parsed ssr:// share link
+#[derive(Debug, Clone)]
+pub struct SsrLink {
+    pub address: String,
+    pub port: u16,
+    pub protocol: String, // SSR protocol plugin ("origin", "auth_aes128_md5", ...)
+    pub method: String,
+    pub obfs: String, // "plain", "http_simple", "tls1.2_ticket_auth", ...
+    pub password: String,
+    pub name: String,
+}
+
+impl SsrLink {
+    /// Why xray can't connect to this node, None if it converts to plain Shadowsocks
+    pub fn unsupported_reason(&self) -> Option<String> {
+        let plain_protocol = self.protocol == "origin" || self.protocol.ends_with("_compatible");
+        let plain_obfs = self.obfs == "plain" || self.obfs.ends_with("_compatible");
+        if !plain_protocol || !plain_obfs {
+            return Some(format!(
+                "{} is a ShadowsocksR node (protocol \"{}\", obfs \"{}\"). xray can only connect to \
+                 SSR nodes without protocol/obfs plugins; ask the provider for a Shadowsocks or newer link.",
+                self.name, self.protocol, self.obfs));
+        }
+        if !XRAY_METHODS.contains(&self.method.as_str()) {
+            return Some(format!(
+                "{} is a ShadowsocksR node using the \"{}\" cipher, which xray no longer supports \
+                 (only AEAD ciphers such as aes-256-gcm work).",
+                self.name, self.method));
+        }
+        None
+    }
+
+    /// Equivalent ss:// link, None if the node needs SSR-only features
+    pub fn to_ss_uri(&self) -> Option<String> {
+        if self.unsupported_reason().is_some() {
+            return None;
+        }
+        let user_info = format!("{}:{}", self.method, self.password);
+        Some(format!("ss://{}@{}:{}#{}",
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(user_info),
+            super::singbox::host_part(&self.address),
+            self.port,
+            super::singbox::percent_encode(&self.name)))
+    }
+}
+
+/// True for ssr:// share links
+pub fn is_ssr_uri(uri: &str) -> bool {
+    uri.starts_with("ssr://")
+}
+
+/// Parse ssr://base64(host:port:protocol:method:obfs:base64(password)/?remarks=base64(name)&...)
+pub fn parse(uri: &str) -> Result<SsrLink, String> {
+    let payload = uri.strip_prefix("ssr://")
+        .ok_or_else(|| "Not an ssr:// link".to_string())?;
+    let decoded = decode_base64(payload.trim())
+        .ok_or_else(|| "ssr:// link is not valid base64".to_string())?;
+    let (main, query) = decoded.split_once("/?")
+        .or_else(|| decoded.split_once('?'))
+        .unwrap_or((&decoded, ""));
+
+    // Split from the right: the host may be an IPv6 address with colons of its own
+    let mut fields = main.trim_end_matches('/').rsplitn(6, ':');
+    let (Some(password), Some(obfs), Some(method), Some(protocol), Some(port), Some(address)) =
+        (fields.next(), fields.next(), fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        return Err("ssr:// link is missing fields (host:port:protocol:method:obfs:password)".to_string());
+    };
+    let port = port.parse::<u16>()
+        .map_err(|_| format!("Invalid ssr:// port \"{}\"", port))?;
+    let password = decode_base64(password)
+        .ok_or_else(|| "ssr:// password is not valid base64".to_string())?;
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    if address.is_empty() {
+        return Err("ssr:// link has no server address".to_string());
+    }
+
+    let name = query.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "remarks")
+        .and_then(|(_, value)| decode_base64(value))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| address.to_string());
+
+    Ok(SsrLink {
+        address: address.to_string(),
+        port,
+        protocol: protocol.to_string(),
+        method: method.to_lowercase(),
+        obfs: obfs.to_string(),
+        password,
+        name,
+    })
+}
+
+// SSR uses URL-safe base64, usually unpadded; some generators use the standard alphabet
+fn decode_base64(value: &str) -> Option<String> {
+    use base64::engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD};
+    let unpadded = value.trim_end_matches('=');
+    let bytes = [URL_SAFE_NO_PAD, STANDARD_NO_PAD]
+        .iter()
+        .find_map(|engine| engine.decode(unpadded).ok())?;
+    String::from_utf8(bytes).ok()
+}
@@ -0,0 +1,63 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+/// Lines kept per server; older output is dropped
+pub const MAX_LINES: usize = 500;
+
+// Recent xray output (stdout + stderr) per server key
+static LOG_LINES: LazyLock<Mutex<HashMap<String, VecDeque<String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Bumped on every change, so the log window only redraws when there is something new
+static REVISION: AtomicU64 = AtomicU64::new(0);
+
+/// Record one line of xray output for a server
+pub fn push(server_key: &str, line: &str) {
+    if let Ok(mut logs) = LOG_LINES.lock() {
+        let lines = logs.entry(server_key.to_string()).or_default();
+        if lines.len() >= MAX_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line.to_string());
+    }
+    REVISION.fetch_add(1, Ordering::Relaxed);
+
+    crate::logfiles::write(server_key, line);
+    crate::diagnostics::inspect_log_line(server_key, line);
+}
+
+/// Get captured output for a server, oldest first
+pub fn get_lines(server_key: &str) -> Vec<String> {
+    if let Ok(logs) = LOG_LINES.lock() {
+        logs.get(server_key)
+            .map(|lines| lines.iter().cloned().collect())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Drop captured output for a server (before a fresh start)
+pub fn clear(server_key: &str) {
+    if let Ok(mut logs) = LOG_LINES.lock() {
+        logs.remove(server_key);
+    }
+    REVISION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Keys with captured output, including stopped servers whose last run printed something
+#[cfg(feature = "gui")]
+pub fn server_keys() -> Vec<String> {
+    let mut keys: Vec<String> = LOG_LINES.lock()
+        .map(|logs| logs.keys().cloned().collect())
+        .unwrap_or_default();
+    keys.sort();
+    keys
+}
+
+/// Changes whenever any captured output changes
+#[cfg(feature = "gui")]
+pub fn revision() -> u64 {
+    REVISION.load(Ordering::Relaxed)
+}
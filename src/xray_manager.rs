@@ -1,12 +1,89 @@
 use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Mutex, LazyLock};
-use v2parser::xray_runner::XrayRunner;
+use std::time::{Duration, Instant};
 use v2parser::parser;
 
+use crate::config::ServerLimits;
+use crate::events::{self, Event, ServerInfo};
+use crate::limits::InboundLimiter;
+use crate::vpn::protocol::Core;
+
+/// A running xray process with the server it serves
+pub struct ManagedProcess {
+    pub child: Child,
+    pub info: ServerInfo,
+    pub uri: String, // Share URI the config was generated from
+    generated: String, // Config before limits, to tell whether a restart would change anything
+    limits: ServerLimits,
+    binary_path: String,
+    _limiter: Option<InboundLimiter>, // Relay enforcing connection/bandwidth limits; stops when dropped
+}
+
+// How long xray gets to fail on a bad config or busy port before we call it started
+const STARTUP_GRACE: Duration = Duration::from_millis(700);
+
+// How long after the grace a started core has to accept connections on its inbounds
+const LISTEN_TIMEOUT: Duration = Duration::from_secs(5);
+
 // Global state for running xray processes
-pub static XRAY_PROCESSES: LazyLock<Mutex<HashMap<String, XrayRunner>>> = 
+pub static XRAY_PROCESSES: LazyLock<Mutex<HashMap<String, ManagedProcess>>> = 
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
+// Server starts and reconciles in progress; exit waits for them
+static LIFECYCLE_OPERATIONS: AtomicUsize = AtomicUsize::new(0);
+
+// Set once the app starts exiting; no server is started after that
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// A server start or reconcile in progress; exit waits until every one is dropped
+pub struct LifecycleOperation(());
+
+impl Drop for LifecycleOperation {
+    fn drop(&mut self) {
+        LIFECYCLE_OPERATIONS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Register a lifecycle operation; None once shutdown has begun
+pub fn begin_operation() -> Option<LifecycleOperation> {
+    // Count first, so begin_shutdown can't miss an operation that passed the check
+    LIFECYCLE_OPERATIONS.fetch_add(1, Ordering::SeqCst);
+    let operation = LifecycleOperation(());
+    if SHUTTING_DOWN.load(Ordering::SeqCst) {
+        return None;
+    }
+    Some(operation)
+}
+
+/// Whether the app has started exiting
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+/// Number of lifecycle operations still running
+pub fn pending_operations() -> usize {
+    LIFECYCLE_OPERATIONS.load(Ordering::SeqCst)
+}
+
+/// Refuse new lifecycle operations and wait up to `timeout` for running ones.
+/// Returns false if some were still going; their servers are killed instead of registered.
+pub fn begin_shutdown(timeout: Duration) -> bool {
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+    let deadline = Instant::now() + timeout;
+    while pending_operations() > 0 {
+        if Instant::now() >= deadline {
+            eprintln!("{} server operations still running after {:?}, stopping anyway", pending_operations(), timeout);
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    true
+}
+
 /// Start xray server for a specific VPN server
 /// Returns Ok if successful
 pub async fn start_server(
@@ -14,39 +91,645 @@ pub async fn start_server(
     uri: &str,
     local_port: u16,
     proxy_type: &str,
+    http_port: u16,
     xray_binary_path: &str,
+    limits: &ServerLimits,
 ) -> Result<(), String> {
+    let Some(_operation) = begin_operation() else {
+        return Err("The app is shutting down".to_string());
+    };
+    
+    // Reading the config and probing ports block; on the current-thread runtime
+    // that would hold up every other start of the batch
+    let (key, owned_uri, owned_type, owned_binary) = (server_key.to_string(), uri.to_string(), proxy_type.to_string(), xray_binary_path.to_string());
+    let prepared = tokio::task::spawn_blocking(move || {
+        prepare_start(&key, &owned_uri, local_port, &owned_type, http_port, &owned_binary)
+    }).await.map_err(|e| format!("Server start task failed: {}", e))??;
+    
+    // Before the start event, so whatever checks the new server can log in
+    crate::inbounds::remember(&[prepared.info.local_port, prepared.info.http_port], &prepared.inbound);
+    launch(prepared.info, uri, &prepared.config_json, limits, prepared.core, &prepared.binary_path).await?;
+    if let Some(warning) = prepared.port_warning {
+        crate::diagnostics::set_hint(server_key, &warning);
+    }
+    Ok(())
+}
+
+// What start_server works out before launching the core
+struct PreparedStart {
+    info: ServerInfo,
+    inbound: crate::inbounds::Inbound,
+    config_json: String,
+    core: &'static str,
+    binary_path: String,
+    port_warning: Option<String>,
+}
+
+// Ports, inbound settings and config of a server about to start (blocking)
+fn prepare_start(
+    server_key: &str,
+    uri: &str,
+    local_port: u16,
+    proxy_type: &str,
+    http_port: u16,
+    xray_binary_path: &str,
+) -> Result<PreparedStart, String> {
+    let (local_port, http_port) = resolve_ports(local_port, proxy_type, http_port)?;
+    check_running_conflict(server_key, &crate::ports::server_ports(local_port, proxy_type, http_port))?;
+    let inbound = crate::inbounds::for_server(&crate::config::Config::load().unwrap_or_default(), server_key);
+    let (config_json, core, binary_path) = generate_config(server_key, uri, local_port, proxy_type, http_port, &inbound, xray_binary_path)?;
+    let mut port_warning = check_port(server_key, local_port)?;
+    if proxy_type == "Both" {
+        port_warning = port_warning.or(check_port(server_key, http_port)?);
+    }
+    
+    let info = ServerInfo {
+        key: server_key.to_string(),
+        name: crate::vpn::get_server_name(server_key),
+        local_port,
+        proxy_type: proxy_type.to_string(),
+        http_port: if proxy_type == "Both" { http_port } else { 0 },
+    };
+    Ok(PreparedStart { info, inbound, config_json, core, binary_path, port_warning })
+}
+
+// Servers set to auto port come with port 0: pick free ones now (both for "Both")
+fn resolve_ports(local_port: u16, proxy_type: &str, http_port: u16) -> Result<(u16, u16), String> {
+    let local_port = if local_port == 0 { crate::ports::free_port(&[])? } else { local_port };
+    let http_port = if proxy_type == "Both" && http_port == 0 { crate::ports::free_port(&[local_port])? } else { http_port };
+    Ok((local_port, http_port))
+}
+
+// Refuse ports another running server (or group) of ours listens on, naming it
+fn check_running_conflict(server_key: &str, ports: &[u16]) -> Result<(), String> {
+    let Ok(processes) = XRAY_PROCESSES.lock() else {
+        return Ok(());
+    };
+    let conflict = processes.values()
+        .filter(|process| process.info.key != server_key)
+        .find_map(|process| ports.iter()
+            .find(|&&port| port == process.info.local_port || (process.info.http_port != 0 && port == process.info.http_port))
+            .map(|&port| (port, process.info.name.clone())));
+    match conflict {
+        Some((port, name)) => {
+            let message = format!("Port {} is in use by the running server {}; give this server another port", port, name);
+            crate::diagnostics::set_hint(server_key, &message);
+            Err(message)
+        }
+        None => Ok(()),
+    }
+}
+
+// Refuse a port another process listens on, naming it; Ok(Some(..)) for one only taken on ::1
+fn check_port(server_key: &str, local_port: u16) -> Result<Option<String>, String> {
+    crate::ports::check_local_port(local_port).inspect_err(|e| crate::diagnostics::set_hint(server_key, e))
+}
+
+// Config a server runs with (before limits), the core that runs it and that core's binary
+fn generate_config(
+    server_key: &str,
+    uri: &str,
+    local_port: u16,
+    proxy_type: &str,
+    http_port: u16,
+    inbound: &crate::inbounds::Inbound,
+    xray_binary_path: &str,
+) -> Result<(String, &'static str, String), String> {
     // Determine ports based on proxy type
     let (socks_port, http_port) = match proxy_type {
         "SOCKS" => (Some(local_port), None),
         "HTTP" => (None, Some(local_port)),
+        // One process, a SOCKS and an HTTP inbound
+        "Both" => {
+            if http_port == 0 || http_port == local_port {
+                return Err(format!("SOCKS + HTTP needs an HTTP port other than {}", local_port));
+            }
+            (Some(local_port), Some(http_port))
+        }
         _ => (Some(local_port), None), // Default to SOCKS
     };
     
-    // Generate xray config from URI
-    let config_json = parser::create_json_config(uri, socks_port, http_port);
+    // SSR nodes that couldn't be converted to ss:// are listed but can't run
+    if crate::vpn::ssr::is_ssr_uri(uri) {
+        let reason = crate::vpn::ssr::parse(uri)?.unsupported_reason()
+            .unwrap_or_else(|| "ShadowsocksR link was not converted to Shadowsocks".to_string());
+        return Err(reason);
+    }
+    
+    // Generate xray config from URI, or let the protocol's handler do it (hysteria2
+    // gets a sing-box config, since xray can't dial it)
+    let (config_json, core) = match crate::vpn::protocol::find(uri) {
+        Some(handler) => (handler.core_config(uri, socks_port, http_port)?, handler.core()),
+        None => (parser::create_json_config(uri, socks_port, http_port), Core::Xray),
+    };
+    let config = crate::config::Config::load().unwrap_or_default();
+    let config_json = crate::inbounds::apply(&config_json, inbound)?;
+    let custom_rules = crate::routing::custom_rules();
+    let preset = crate::routing::active_preset();
+    let rule_sets = crate::rulesets::compiled_rules();
+    match core {
+        Core::Xray => {
+            let config_json = crate::mux::apply(&config_json, &crate::mux::for_server(&config, server_key))?;
+            let config_json = crate::dpi::apply(&config_json, config.server_settings.get(server_key))?;
+            let config_json = crate::chain::apply(&config_json, &config, server_key)?;
+            let config_json = crate::routing::apply(&config_json, &custom_rules, preset.as_ref(), &rule_sets)?;
+            Ok((crate::routing::apply_dns(&config_json, &config.dns)?, "xray", xray_binary_path.to_string()))
+        }
+        Core::SingBox if crate::chain::upstream_of(&config, server_key).is_some() => {
+            Err("This server runs on sing-box, which can't dial through an upstream server".to_string())
+        }
+        Core::SingBox => Ok((
+            crate::routing::apply_singbox(&config_json, &custom_rules, preset.as_ref(), &rule_sets)?,
+            "sing-box",
+            crate::vpn::hysteria2::singbox_binary()?,
+        )),
+    }
+}
+
+/// Check the config a server would start with using the core's own validator
+/// (`xray run -test`, `sing-box check`). Nothing is started and no port is bound.
+pub fn validate_config(request: &StartRequest, xray_binary_path: &str) -> Result<(), String> {
+    let inbound = crate::inbounds::for_server(&crate::config::Config::load().unwrap_or_default(), &request.key);
+    let (local_port, http_port) = resolve_ports(request.local_port, &request.proxy_type, request.http_port)?;
+    let (config_json, core, binary_path) = generate_config(
+        &request.key, &request.uri, local_port, &request.proxy_type, http_port, &inbound, xray_binary_path,
+    )?;
+    let config_path = write_config_file(&format!("{}.test", request.key), &config_json)?;
+    
+    let mut command = Command::new(&binary_path);
+    if core == "sing-box" {
+        command.arg("check");
+    } else {
+        command.arg("run").arg("-test");
+    }
+    command.arg("-c").arg(&config_path).stdin(Stdio::null());
+    if core == "xray" {
+        set_asset_env(&mut command, &binary_path);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = command.output();
+    let _ = std::fs::remove_file(&config_path);
+    let output = output.map_err(|e| format!("Failed to run {}: {}", core, e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    
+    let lines: Vec<String> = String::from_utf8_lossy(&output.stdout).lines()
+        .chain(String::from_utf8_lossy(&output.stderr).lines())
+        .map(str::to_string)
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let message = crate::diagnostics::translate_startup_error(&lines, local_port)
+        .or_else(|| lines.last().cloned())
+        .unwrap_or_else(|| format!("exited with {}", output.status));
+    Err(format!("{} rejected the config: {}", core, message))
+}
+
+/// The config a server runs with, and where it comes from: a running server's file
+/// (limits included), or for a stopped one what starting it would generate now
+#[cfg(feature = "gui")]
+pub fn preview_config(server_key: &str) -> Result<(String, String), String> {
+    if get_server_info(server_key).is_some() {
+        let path = config_file_path(server_key);
+        let config_json = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        return Ok((config_json, format!("Running with {}", path.display())));
+    }
+    
+    let config = crate::config::Config::load()?;
+    let settings = config.server_settings.get(server_key)
+        .ok_or_else(|| format!("No saved settings for {}; save the server list first", server_key))?;
+    let uri = crate::vpn::VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.as_ref()?.iter().find(|s| s.get_server_key() == server_key).map(|s| s.uri.clone()))
+        .filter(|uri| !uri.is_empty())
+        .ok_or_else(|| format!("Server {} has no share link", server_key))?;
+    let (local_port, proxy_type, http_port) = crate::switcher::port_override(server_key)
+        .unwrap_or_else(|| (settings.local_port, settings.proxy_type.clone(), settings.http_port));
+    let inbound = crate::inbounds::for_server(&config, server_key);
+    let auto_port = local_port == 0;
+    let (local_port, http_port) = resolve_ports(local_port, &proxy_type, http_port)?;
+    let (config_json, core, _) = generate_config(server_key, &uri, local_port, &proxy_type, http_port, &inbound, &config.xray_binary_path)?;
+    let mut source = format!("Not running; {} would start with this config", core);
+    if auto_port {
+        source.push_str(" on ports free now (picked again at start)");
+    }
+    if settings.limits != ServerLimits::default() {
+        source.push_str(" plus the server's limits");
+    }
+    Ok((config_json, source))
+}
+
+/// Test a server as listed, before its settings are saved: run its config in a
+/// throwaway process on a free loopback port, fetch the test URL through it and stop
+/// it again. The process isn't registered, so running servers and events never see it.
+#[cfg(feature = "gui")]
+pub fn dry_run(server: &crate::vpn::VpnServer, xray_binary_path: &str) -> Result<Duration, String> {
+    let server_key = server.get_server_key();
+    let test = crate::config::Config::load().unwrap_or_default().test;
+    let port = std::net::TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|address| address.port())
+        .map_err(|e| format!("No free local port for the test: {}", e))?;
+    // Loopback without a login, whatever the server shares
+    let inbound = crate::inbounds::Inbound { listen: "127.0.0.1".to_string(), auth: None, udp: server.udp };
+    let (config_json, core, binary_path) = generate_config(&server_key, &server.uri, port, "SOCKS", 0, &inbound, xray_binary_path)?;
+    let config_path = write_config_file(&format!("{}.dryrun", server_key), &config_json)?;
+    crate::inbounds::remember(&[port], &inbound);
+    
+    let mut command = Command::new(&binary_path);
+    command.arg("run")
+        .arg("-c")
+        .arg(&config_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if core == "xray" {
+        set_asset_env(&mut command, &binary_path);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let _ = std::fs::remove_file(&config_path);
+            crate::inbounds::forget(&[port]);
+            return Err(format!("Failed to start {}: {}", core, e));
+        }
+    };
+    let stdout = child.stdout.take().map(collect_lines);
+    let stderr = child.stderr.take().map(collect_lines);
+    
+    std::thread::sleep(STARTUP_GRACE);
+    let result = if let Ok(Some(status)) = child.try_wait() {
+        let lines: Vec<String> = [stdout, stderr].into_iter()
+            .flatten()
+            .flat_map(|reader| reader.join().unwrap_or_default())
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        let message = crate::diagnostics::translate_startup_error(&lines, port)
+            .or_else(|| lines.last().cloned())
+            .unwrap_or_else(|| format!("exited with {}", status));
+        Err(format!("{} exited during startup: {}", core, message))
+    } else {
+        let result = crate::probe::test_via_proxy(port, "SOCKS", &test.url, Duration::from_secs(test.timeout_secs.max(1)));
+        let _ = kill_process(&mut child);
+        result
+    };
+    let _ = std::fs::remove_file(&config_path);
+    crate::inbounds::forget(&[port]);
+    result
+}
+
+/// Start a balanced group: one xray process spreading traffic over the members' servers
+pub async fn start_group(
+    group: &crate::config::BalancedGroup,
+    member_uris: &[String],
+    xray_binary_path: &str,
+) -> Result<(), String> {
+    let Some(_operation) = begin_operation() else {
+        return Err("The app is shutting down".to_string());
+    };
+    
+    let info = ServerInfo {
+        key: crate::balancer::group_key(&group.name),
+        name: group.name.clone(),
+        local_port: group.local_port,
+        proxy_type: group.proxy_type.clone(),
+        http_port: 0,
+    };
+    // Off the runtime thread, like start_server's preparation
+    let (owned_group, owned_uris, key) = (group.clone(), member_uris.to_vec(), info.key.clone());
+    let (config_json, inbound, port_warning) = tokio::task::spawn_blocking(move || {
+        let (config_json, inbound) = group_config(&owned_group, &owned_uris)?;
+        let port_warning = check_port(&key, owned_group.local_port)?;
+        Ok::<_, String>((config_json, inbound, port_warning))
+    }).await.map_err(|e| format!("Group start task failed: {}", e))??;
+    let group_key = info.key.clone();
+    crate::inbounds::remember(&[group.local_port], &inbound);
+    // The member URIs stand in for the share URI, so a refresh can tell when they changed
+    launch(info, &member_uris.join("\n"), &config_json, &ServerLimits::default(), "xray", xray_binary_path).await?;
+    if let Some(warning) = port_warning {
+        crate::diagnostics::set_hint(&group_key, &warning);
+    }
+    Ok(())
+}
+
+// Config a balanced group runs with, and the inbound settings it got
+fn group_config(group: &crate::config::BalancedGroup, member_uris: &[String]) -> Result<(String, crate::inbounds::Inbound), String> {
+    let (socks_port, http_port) = match group.proxy_type.as_str() {
+        "HTTP" => (None, Some(group.local_port)),
+        _ => (Some(group.local_port), None),
+    };
+    let test_url = crate::config::Config::load().unwrap_or_default().test.url;
+    let config_json = crate::balancer::build_config(member_uris, socks_port, http_port, &group.strategy, &test_url)?;
+    let config_json = crate::routing::apply(&config_json, &crate::routing::custom_rules(),
+        crate::routing::active_preset().as_ref(), &crate::rulesets::compiled_rules())?;
+    let config = crate::config::Config::load().unwrap_or_default();
+    let config_json = crate::routing::apply_dns(&config_json, &config.dns)?;
+    let config_json = crate::balancer::route_through_balancer(&config_json)?;
+    let inbound = crate::inbounds::for_server(&config, &crate::balancer::group_key(&group.name));
+    let config_json = crate::inbounds::apply(&config_json, &inbound)?;
+    Ok((config_json, inbound))
+}
+
+/// Whether a running server already runs what starting `request` would run now: same link,
+/// ports, limits, core binary and generated config. Restarting it would only drop its connections.
+pub fn is_current(request: &StartRequest, xray_binary_path: &str) -> bool {
+    let Some((info, uri, generated, limits, binary_path)) = XRAY_PROCESSES.lock().ok().and_then(|processes| {
+        processes.get(&request.key)
+            .map(|p| (p.info.clone(), p.uri.clone(), p.generated.clone(), p.limits.clone(), p.binary_path.clone()))
+    }) else {
+        return false;
+    };
+    // Auto-port servers (port 0) keep the ports they got
+    let local_port = if request.local_port == 0 { info.local_port } else { request.local_port };
+    let http_port = if request.proxy_type != "Both" { 0 } else if request.http_port == 0 { info.http_port } else { request.http_port };
+    if uri != request.uri || limits != request.limits || info.proxy_type != request.proxy_type
+        || info.local_port != local_port || info.http_port != http_port {
+        return false;
+    }
+    let inbound = crate::inbounds::for_server(&crate::config::Config::load().unwrap_or_default(), &request.key);
+    generate_config(&request.key, &request.uri, local_port, &request.proxy_type, http_port, &inbound, xray_binary_path)
+        .is_ok_and(|(config_json, _, now_binary)| config_json == generated && now_binary == binary_path)
+}
+
+/// Whether a running balanced group already runs the config it would start with now
+pub fn group_is_current(group: &crate::config::BalancedGroup, member_uris: &[String], xray_binary_path: &str) -> bool {
+    let key = crate::balancer::group_key(&group.name);
+    let Some((info, uri, generated, binary_path)) = XRAY_PROCESSES.lock().ok().and_then(|processes| {
+        processes.get(&key).map(|p| (p.info.clone(), p.uri.clone(), p.generated.clone(), p.binary_path.clone()))
+    }) else {
+        return false;
+    };
+    if uri != member_uris.join("\n") || info.local_port != group.local_port || info.proxy_type != group.proxy_type
+        || binary_path != xray_binary_path {
+        return false;
+    }
+    group_config(group, member_uris).is_ok_and(|(config_json, _)| config_json == generated)
+}
+
+// Point xray at the geoip.dat/geosite.dat the asset manager keeps, wherever they are
+fn set_asset_env(command: &mut Command, xray_binary_path: &str) {
+    if let Some(dir) = crate::geoassets::asset_dir(xray_binary_path) {
+        command.env(crate::geoassets::ASSET_ENV, dir);
+    }
+}
+
+// Run a core with a generated config (limits applied here) and register it once it
+// survives the startup grace
+async fn launch(
+    info: ServerInfo,
+    uri: &str,
+    generated: &str,
+    limits: &ServerLimits,
+    core: &str,
+    binary_path: &str,
+) -> Result<(), String> {
+    let server_key = info.key.as_str();
+    let ports = crate::ports::server_ports(info.local_port, &info.proxy_type, info.http_port);
+    let (config_json, limiter) = crate::limits::prepare(generated, &ports, limits)?;
+    let config_path = write_config_file(server_key, &config_json)?;
+    
+    crate::xray_log::clear(server_key);
+    crate::diagnostics::clear_hint(server_key);
     
-    // Create and start xray runner
-    let mut runner = XrayRunner::new();
-    runner.start(&config_json, xray_binary_path)
-        .await
-        .map_err(|e| format!("Failed to start xray: {}", e))?;
+    // Spawn xray with its output captured for logs and diagnostics
+    let mut command = Command::new(binary_path);
+    command.arg("run")
+        .arg("-c")
+        .arg(&config_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if core == "xray" {
+        set_asset_env(&mut command, binary_path);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
     
-    // Store runner in global state
+    let mut child = command.spawn()
+        .map_err(|e| {
+            crate::reliability::record(server_key, crate::reliability::Outcome::StartFailure);
+            format!("Failed to start {}: {}", core, e)
+        })?;
+    
+    if let Some(stdout) = child.stdout.take() {
+        capture_output(server_key, stdout, true);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        capture_output(server_key, stderr, false);
+    }
+    
+    // Catch immediate exits (bad config, port in use), then wait for the inbounds to
+    // take connections: a core that stays up without listening serves nobody either
+    tokio::time::sleep(STARTUP_GRACE).await;
+    let mut waiting = inbound_addresses(&config_json);
+    let deadline = Instant::now() + LISTEN_TIMEOUT;
+    loop {
+        if let Ok(Some(status)) = child.try_wait() {
+            // Let the output readers drain what xray printed before exiting
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let lines = crate::xray_log::get_lines(server_key);
+            let message = crate::diagnostics::translate_startup_error(&lines, info.local_port)
+                .unwrap_or_else(|| format!("{} exited during startup ({})", core, status));
+            crate::diagnostics::set_hint(server_key, &message);
+            crate::reliability::record(server_key, crate::reliability::Outcome::StartFailure);
+            return Err(format!("Failed to start {}: {}", core, message));
+        }
+        let mut not_listening = Vec::new();
+        for address in waiting {
+            if !accepts_connections(address).await {
+                not_listening.push(address);
+            }
+        }
+        waiting = not_listening;
+        if waiting.is_empty() {
+            break;
+        }
+        if Instant::now() >= deadline {
+            let _ = kill_process(&mut child);
+            let ports: Vec<String> = waiting.iter().map(|address| address.to_string()).collect();
+            let message = format!("{} kept running but didn't accept connections on {} within {} s",
+                core, ports.join(", "), (STARTUP_GRACE + LISTEN_TIMEOUT).as_secs());
+            crate::diagnostics::set_hint(server_key, &message);
+            crate::reliability::record(server_key, crate::reliability::Outcome::StartFailure);
+            return Err(format!("Failed to start {}: {}", core, message));
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    
+    // Store runner in global state, unless exit stopped waiting for this start
     if let Ok(mut processes) = XRAY_PROCESSES.lock() {
-        processes.insert(server_key.to_string(), runner);
+        if SHUTTING_DOWN.load(Ordering::SeqCst) {
+            let _ = kill_process(&mut child);
+            return Err("The app is shutting down".to_string());
+        }
+        processes.insert(server_key.to_string(), ManagedProcess {
+            child,
+            info: info.clone(),
+            uri: uri.to_string(),
+            generated: generated.to_string(),
+            limits: limits.clone(),
+            binary_path: binary_path.to_string(),
+            _limiter: limiter,
+        });
     }
     
+    crate::reliability::record(server_key, crate::reliability::Outcome::Success);
+    events::emit(Event::ServerStarted(info));
+    
     Ok(())
 }
 
+// Whether something accepts a TCP connection on `address` within 250 ms, without
+// blocking the runtime thread other starts share
+async fn accepts_connections(address: std::net::SocketAddr) -> bool {
+    let connect = tokio::net::TcpStream::connect(address);
+    matches!(tokio::time::timeout(Duration::from_millis(250), connect).await, Ok(Ok(_)))
+}
+
+// Addresses to connect to for each TCP inbound of a generated xray or sing-box config;
+// inbounds on every interface are reached through loopback
+fn inbound_addresses(config_json: &str) -> Vec<std::net::SocketAddr> {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+    let Ok(config) = serde_json::from_str::<serde_json::Value>(config_json) else {
+        return Vec::new();
+    };
+    let Some(inbounds) = config["inbounds"].as_array() else {
+        return Vec::new();
+    };
+    inbounds.iter()
+        .filter_map(|inbound| {
+            // xray inbounds have "port", sing-box ones "listen_port"; TUN inbounds neither
+            let port = inbound["port"].as_u64().or(inbound["listen_port"].as_u64())?;
+            let port = u16::try_from(port).ok().filter(|&port| port != 0)?;
+            let ip = match inbound["listen"].as_str().unwrap_or("") {
+                "" | "localhost" => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                listen => match listen.parse::<IpAddr>().ok()? {
+                    IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+                    ip => ip,
+                },
+            };
+            Some(SocketAddr::new(ip, port))
+        })
+        .collect()
+}
+
+/// A server to start with `start_servers`
+pub struct StartRequest {
+    pub key: String,
+    pub name: String,
+    pub uri: String,
+    pub local_port: u16,
+    pub proxy_type: String,
+    pub http_port: u16, // Used with proxy_type "Both"
+    pub limits: ServerLimits,
+}
+
+/// Outcome of starting a batch of servers
+#[derive(Debug, Default)]
+pub struct StartSummary {
+    pub started: Vec<String>,          // Server names
+    pub failed: Vec<(String, String)>, // (server name, error)
+    pub kept: Vec<String>,             // Already running what they'd start with, left alone
+}
+
+impl StartSummary {
+    /// Failures as one line per server, for message boxes
+    pub fn failure_text(&self) -> String {
+        self.failed.iter()
+            .map(|(name, error)| format!("{}: {}", name, error))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Start several servers, at most `concurrency` at a time.
+/// Most of a start is waiting out STARTUP_GRACE, so overlapping them cuts startup
+/// from seconds per server to seconds per batch.
+pub async fn start_servers(requests: Vec<StartRequest>, xray_binary_path: &str, concurrency: usize) -> StartSummary {
+    let mut summary = StartSummary::default();
+    // Servers of the batch set to the same port would race for it; none of them starts
+    let ports = |request: &StartRequest| crate::ports::server_ports(request.local_port, &request.proxy_type, request.http_port);
+    let duplicates = crate::ports::duplicates(requests.iter().map(|request| (request.name.as_str(), ports(request))));
+    let (requests, conflicting): (Vec<StartRequest>, Vec<StartRequest>) = requests.into_iter()
+        .partition(|request| !ports(request).iter().any(|port| duplicates.iter().any(|(taken, _)| taken == port)));
+    for request in conflicting {
+        let shared: Vec<(u16, Vec<String>)> = duplicates.iter()
+            .filter(|(port, _)| ports(&request).contains(port))
+            .cloned()
+            .collect();
+        let message = format!("{}; give each server its own port", crate::ports::describe_duplicates(&shared).replace('\n', "; "));
+        crate::diagnostics::set_hint(&request.key, &message);
+        eprintln!("Not starting server {}: {}", request.name, message);
+        summary.failed.push((request.name, message));
+    }
+    let mut tasks = tokio::task::JoinSet::new();
+    for request in requests {
+        // Wait for a slot before spawning the next start
+        while tasks.len() >= concurrency.max(1) {
+            if let Some(result) = tasks.join_next().await {
+                record_start(&mut summary, result);
+            }
+        }
+        let xray_binary_path = xray_binary_path.to_string();
+        tasks.spawn(async move {
+            let result = start_server(
+                &request.key,
+                &request.uri,
+                request.local_port,
+                &request.proxy_type,
+                request.http_port,
+                &xray_binary_path,
+                &request.limits,
+            ).await;
+            (request.name, result)
+        });
+    }
+    while let Some(result) = tasks.join_next().await {
+        record_start(&mut summary, result);
+    }
+    summary
+}
+
+fn record_start(summary: &mut StartSummary, result: Result<(String, Result<(), String>), tokio::task::JoinError>) {
+    match result {
+        Ok((name, Ok(()))) => {
+            println!("Started server: {}", name);
+            summary.started.push(name);
+        }
+        Ok((name, Err(e))) => {
+            eprintln!("Failed to start server {}: {}", name, e);
+            summary.failed.push((name, e));
+        }
+        Err(e) => {
+            eprintln!("Server start task failed: {}", e);
+            summary.failed.push(("(unknown)".to_string(), e.to_string()));
+        }
+    }
+}
+
 /// Stop xray server for a specific server
 pub async fn stop_server(server_key: &str) -> Result<(), String> {
     if let Ok(mut processes) = XRAY_PROCESSES.lock() {
-        if let Some(mut runner) = processes.remove(server_key) {
-            runner.stop()
-                .await
+        if let Some(mut process) = processes.remove(server_key) {
+            kill_process(&mut process.child)
                 .map_err(|e| format!("Failed to stop xray: {}", e))?;
+            events::emit(Event::ServerStopped(process.info));
         }
     }
     Ok(())
@@ -55,13 +738,28 @@ pub async fn stop_server(server_key: &str) -> Result<(), String> {
 /// Stop all running xray servers
 pub async fn stop_all_servers() -> Result<(), String> {
     if let Ok(mut processes) = XRAY_PROCESSES.lock() {
-        for (_key, mut runner) in processes.drain() {
-            let _ = runner.stop().await; // Ignore errors during bulk shutdown
+        for (_key, mut process) in processes.drain() {
+            let _ = kill_process(&mut process.child); // Ignore errors during bulk shutdown
+            events::emit(Event::ServerStopped(process.info));
         }
     }
     Ok(())
 }
 
+/// Get details of a running server
+pub fn get_server_info(server_key: &str) -> Option<ServerInfo> {
+    XRAY_PROCESSES.lock().ok()?.get(server_key).map(|p| p.info.clone())
+}
+
+/// Get running server keys with the URI each was started from
+pub fn get_running_uris() -> HashMap<String, String> {
+    if let Ok(processes) = XRAY_PROCESSES.lock() {
+        processes.iter().map(|(key, p)| (key.clone(), p.uri.clone())).collect()
+    } else {
+        HashMap::new()
+    }
+}
+
 /// Get list of running server keys
 pub fn get_running_servers() -> Vec<String> {
     if let Ok(processes) = XRAY_PROCESSES.lock() {
@@ -70,3 +768,249 @@ pub fn get_running_servers() -> Vec<String> {
         Vec::new()
     }
 }
+
+// Backups tried for one failed server before giving up
+const FAILOVER_ATTEMPTS: usize = 5;
+
+// One failover at a time, so a crash and a failed check can't both replace a server
+static FAILOVER_LOCK: Mutex<()> = Mutex::new(());
+
+// Last failover, shown in the tray until the next one
+static LAST_FAILOVER: Mutex<Option<String>> = Mutex::new(None);
+
+/// Watch running servers and hand the port of one that keeps failing to a backup server.
+/// While failover is on, the periodic checks run here instead of in the health monitor.
+pub fn start_failover_watchdog() {
+    let receiver = events::subscribe();
+    std::thread::spawn(move || {
+        for event in receiver {
+            // A crash leaves the port dead right away, no need to wait for checks
+            if let Event::ServerCrashed(info) = event {
+                // A balanced group already routes around its failing members
+                if crate::balancer::is_group_key(&info.key) {
+                    continue;
+                }
+                if crate::config::Config::load().is_ok_and(|config| config.failover.enabled) {
+                    fail_over(&info);
+                }
+            }
+        }
+    });
+
+    std::thread::spawn(|| {
+        // Consecutive failed checks per running server
+        let mut strikes: HashMap<String, usize> = HashMap::new();
+        loop {
+            // Re-read each round so setting changes apply without restart
+            let interval = crate::config::Config::load().unwrap_or_default().test.interval_secs;
+            crate::idle::sleep(Duration::from_secs(interval.max(10)));
+            let failover = crate::config::Config::load().unwrap_or_default().failover;
+            if !failover.enabled {
+                strikes.clear();
+                continue;
+            }
+            
+            let running = get_running_servers();
+            strikes.retain(|key, _| running.contains(key));
+            for server_key in running {
+                if crate::balancer::is_group_key(&server_key) {
+                    continue;
+                }
+                // An earlier failover this round may have replaced it
+                let Some(info) = get_server_info(&server_key) else {
+                    continue;
+                };
+                if crate::health::check_server(&server_key).is_ok() {
+                    strikes.remove(&server_key);
+                    continue;
+                }
+                let count = strikes.entry(server_key.clone()).or_insert(0);
+                *count += 1;
+                if *count >= failover.failures.max(1) {
+                    strikes.remove(&server_key);
+                    fail_over(&info);
+                }
+            }
+            crate::request_menu_update();
+        }
+    });
+}
+
+/// What the last failover did, e.g. "⇄ Port 1080: A failed, switched to B"
+#[cfg(feature = "gui")]
+pub fn last_failover() -> Option<String> {
+    LAST_FAILOVER.lock().ok()?.clone()
+}
+
+// Stop a failed server and start the first backup that works on its port
+fn fail_over(failed: &ServerInfo) {
+    let Ok(_guard) = FAILOVER_LOCK.lock() else {
+        return;
+    };
+    let Some(_operation) = begin_operation() else {
+        return;
+    };
+    let config = crate::config::Config::load().unwrap_or_default();
+    if config.xray_binary_path.is_empty() {
+        return;
+    }
+    let system_proxy = crate::sysproxy::active_port() == Some(failed.local_port);
+    
+    // A crashed server is gone already; one failing checks still holds the port
+    let _ = crate::TOKIO_RUNTIME.block_on(stop_server(&failed.key));
+    
+    let mut backup = None;
+    for (key, name, uri) in backup_candidates(&failed.key, &config).into_iter().take(FAILOVER_ATTEMPTS) {
+        let limits = config.server_settings.get(&key)
+            .map(|settings| settings.limits.clone())
+            .unwrap_or_default();
+        let started = crate::TOKIO_RUNTIME.block_on(start_server(
+            &key,
+            &uri,
+            failed.local_port,
+            &failed.proxy_type,
+            failed.http_port,
+            &config.xray_binary_path,
+            &limits,
+        ));
+        // Starting only proves xray runs; the upstream has to answer too
+        match started.and_then(|_| crate::health::check_server(&key).map(|_| ())) {
+            Ok(()) => {
+                backup = Some(name);
+                break;
+            }
+            Err(e) => {
+                eprintln!("Failover: backup {} is not usable: {}", name, e);
+                let _ = crate::TOKIO_RUNTIME.block_on(stop_server(&key));
+            }
+        }
+    }
+    
+    let notice = match backup {
+        Some(name) => {
+            // The stop reverted the system proxy; the port works again
+            if system_proxy {
+                if let Err(e) = crate::sysproxy::enable(failed.local_port, &failed.proxy_type) {
+                    eprintln!("Failover: could not restore the system proxy: {}", e);
+                }
+            }
+            format!("⇄ Port {}: {} failed, switched to {}", failed.local_port, failed.name, name)
+        }
+        None => format!("⚠ Port {}: {} failed, no backup server works", failed.local_port, failed.name),
+    };
+    println!("Failover: {}", notice);
+    if let Ok(mut last) = LAST_FAILOVER.lock() {
+        *last = Some(notice);
+    }
+    crate::request_menu_update();
+}
+
+// Servers that can take over a port as (key, name, uri): those not running, enabled ones
+// first, each group in list order starting after the failed server
+fn backup_candidates(failed_key: &str, config: &crate::config::Config) -> Vec<(String, String, String)> {
+    let servers = crate::vpn::VPN_SERVERS.lock().ok()
+        .and_then(|servers| servers.clone())
+        .unwrap_or_default();
+    let running = get_running_servers();
+    let next = servers.iter()
+        .position(|server| server.get_server_key() == failed_key)
+        .map_or(0, |index| index + 1);
+    
+    let mut candidates: Vec<(bool, String, String, String)> = servers[next..].iter()
+        .chain(&servers[..next])
+        .filter(|server| !server.uri.is_empty())
+        .map(|server| {
+            let key = server.get_server_key();
+            let enabled = config.server_settings.get(&key).is_some_and(|settings| settings.enabled);
+            (enabled, key, server.display_name().to_string(), server.uri.clone())
+        })
+        .filter(|(_, key, _, _)| key != failed_key && !running.contains(key))
+        .collect();
+    candidates.sort_by_key(|(enabled, _, _, _)| !enabled); // Stable: keeps list order
+    candidates.into_iter().map(|(_, key, name, uri)| (key, name, uri)).collect()
+}
+
+// File in the temp directory a server's generated config goes to
+fn config_file_path(server_key: &str) -> PathBuf {
+    // Server keys contain ':' and other characters not allowed in file names
+    let file_name: String = server_key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect();
+    std::env::temp_dir().join("vpn-manager").join(format!("xray-{}.json", file_name))
+}
+
+// Write the generated xray config to the temp directory, one file per server
+pub(crate) fn write_config_file(server_key: &str, config_json: &str) -> Result<PathBuf, String> {
+    let path = config_file_path(server_key);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    std::fs::write(&path, config_json)
+        .map_err(|e| format!("Failed to write xray config: {}", e))?;
+    Ok(path)
+}
+
+// Forward each line of a child output stream to the server's log.
+// The stream closing means xray exited; with watch_exit set, report it if nobody stopped it.
+fn capture_output<R: Read + Send + 'static>(server_key: &str, stream: R, watch_exit: bool) {
+    let server_key = server_key.to_string();
+    std::thread::spawn(move || {
+        let reader = BufReader::new(stream);
+        for line in reader.lines().map_while(Result::ok) {
+            crate::xray_log::push(&server_key, &line);
+        }
+        if watch_exit {
+            handle_unexpected_exit(&server_key);
+        }
+    });
+}
+
+// Read a child output stream to the end on its own thread, so a chatty core never
+// blocks on a full pipe; the lines are there once the stream closes
+#[cfg(feature = "gui")]
+fn collect_lines<R: Read + Send + 'static>(stream: R) -> std::thread::JoinHandle<Vec<String>> {
+    std::thread::spawn(move || BufReader::new(stream).lines().map_while(Result::ok).collect())
+}
+
+/// Report processes that have exited without their output closing (e.g. a child
+/// process still holds the pipe); the output readers catch the usual case first
+pub fn poll_exited() {
+    let exited: Vec<String> = match XRAY_PROCESSES.lock() {
+        Ok(mut processes) => processes.iter_mut()
+            .filter_map(|(key, process)| matches!(process.child.try_wait(), Ok(Some(_))).then(|| key.clone()))
+            .collect(),
+        Err(_) => return,
+    };
+    for server_key in exited {
+        handle_unexpected_exit(&server_key);
+    }
+}
+
+// Stopped servers are removed from XRAY_PROCESSES before being killed,
+// so a process still registered here has exited on its own
+fn handle_unexpected_exit(server_key: &str) {
+    let crashed = if let Ok(mut processes) = XRAY_PROCESSES.lock() {
+        processes.remove(server_key).map(|mut process| {
+            let status = process.child.wait();
+            eprintln!("xray for {} exited unexpectedly: {:?}", process.info.name, status);
+            process.info
+        })
+    } else {
+        None
+    };
+    
+    if let Some(info) = crashed {
+        events::emit(Event::ServerCrashed(info));
+        crate::request_menu_update();
+    }
+}
+
+// Kill an xray process and reap it
+fn kill_process(child: &mut Child) -> std::io::Result<()> {
+    if child.try_wait()?.is_none() {
+        child.kill()?;
+    }
+    child.wait()?;
+    Ok(())
+}